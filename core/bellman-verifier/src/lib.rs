@@ -18,7 +18,7 @@ use ::std::vec::Vec;
 #[cfg(not(feature = "std"))]
 use crate::std::vec::Vec;
 #[cfg(feature = "std")]
-use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::{Serialize, Serializer, Deserialize, Deserializer, ser::Error as SerError, de::Error as DeError};
 #[cfg(feature = "std")]
 use substrate_primitives::bytes;
 use parity_codec::{Encode, Decode, Input};
@@ -26,6 +26,8 @@ use pairing::{
     Engine,
     CurveAffine,
     EncodedPoint,
+    Field,
+    PrimeField,
     io,
     RW,
 };
@@ -111,15 +113,20 @@ impl<E: Engine> Proof<E> {
 
 #[cfg_attr(feature = "std", derive(Debug))]
 #[derive(Clone, PartialEq)]
-pub struct PreparedVerifyingKey<E: Engine> {
-    /// Pairing result of alpha*beta
-    alpha_g1_beta_g2: E::Fqk,
-    /// -gamma in G2
-    neg_gamma_g2: <E::G2Affine as CurveAffine>::Prepared,
-    /// -delta in G2
-    neg_delta_g2: <E::G2Affine as CurveAffine>::Prepared,
-    /// Copy of IC from `VerifiyingKey`.
-    ic: Vec<E::G1Affine>
+pub enum PreparedVerifyingKey<E: Engine> {
+    /// No verifying key has been set, e.g. a missing or empty storage value.
+    /// Every proof is rejected against it.
+    Uninitialized,
+    Prepared {
+        /// Pairing result of alpha*beta
+        alpha_g1_beta_g2: E::Fqk,
+        /// -gamma in G2
+        neg_gamma_g2: <E::G2Affine as CurveAffine>::Prepared,
+        /// -delta in G2
+        neg_delta_g2: <E::G2Affine as CurveAffine>::Prepared,
+        /// Copy of IC from `VerifiyingKey`.
+        ic: Vec<E::G1Affine>
+    },
 }
 
 #[cfg(feature = "std")]
@@ -128,46 +135,55 @@ impl<E: Engine> Serialize for PreparedVerifyingKey<E> {
         where S: Serializer
     {
         let mut writer = vec![];
-        self.write(&mut &mut writer[..]).expect("Faild to serialize PreparedVerifyingKey.");
+        self.write(&mut writer).map_err(|_| S::Error::custom("Faild to serialize PreparedVerifyingKey."))?;
         bytes::serialize(&writer[..], serializer)
     }
 }
 
 #[cfg(feature = "std")]
 impl<'de, E: Engine> Deserialize<'de> for PreparedVerifyingKey<E> {
-    fn deserialize<D>(_deserializer: D) -> Result<Self, D::Error>
+    /// Empty bytes deserialize into `Uninitialized`, as they decode.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where D: Deserializer<'de>
     {
-        // Never called
-        unimplemented!();
+        let b = bytes::deserialize(deserializer)?;
+        if b.is_empty() {
+            return Ok(PreparedVerifyingKey::Uninitialized);
+        }
+
+        PreparedVerifyingKey::<E>::from_bytes(&b[..])
+            .map_err(|e| D::Error::custom(format!("Malformed PreparedVerifyingKey: {:?}", e)))
     }
 }
 
 impl<E: Engine> Encode for PreparedVerifyingKey<E> {
     fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+        // Writing into a growable `Vec` can't fail, unlike writing into a fixed-size slice.
         let mut writer = vec![];
-
-        #[cfg(feature = "std")]
-        self.write(&mut &mut writer).expect("Faild to write PreparedVerifyingKey");
-        #[cfg(not(feature = "std"))]
-        self.write(&mut &mut writer[..]).expect("Faild to write PreparedVerifyingKey");
+        self.write(&mut writer).expect("writing into a Vec never fails; qed");
 
         writer.using_encoded(f)
     }
 }
 
 impl<E: Engine> Decode for PreparedVerifyingKey<E> {
+    /// Empty bytes decode into `Uninitialized`.
+    /// A malformed key fails to decode instead of panicking.
+    /// Decoded keys were validated by `from_bytes` before they were stored,
+    /// so `alpha_g1_beta_g2` isn't checked again on every storage read.
     fn decode<I: Input>(input: &mut I) -> Option<Self> {
-        <Vec<u8> as Decode>::decode(input)
-            .map(|b| PreparedVerifyingKey::<E>::read(&mut &b[..])
-                .expect("Faild to read PreparedVerifyingKey"))
+        let b = <Vec<u8> as Decode>::decode(input)?;
+        if b.is_empty() {
+            return Some(PreparedVerifyingKey::Uninitialized);
+        }
+
+        PreparedVerifyingKey::<E>::read(&mut &b[..]).ok()
     }
 }
 
 impl<E: Engine> Default for PreparedVerifyingKey<E> {
     fn default() -> Self {
-        PreparedVerifyingKey::<E>::read(&mut &vec![0u8][..])
-            .expect("Faild to read PreparedVerifyingKey")
+        PreparedVerifyingKey::Uninitialized
     }
 }
 
@@ -179,43 +195,55 @@ impl<E: Engine> PreparedVerifyingKey<E> {
     {
         use byteorder::{ByteOrder, BigEndian};
 
-        self.alpha_g1_beta_g2.write(writer)?;
-        self.neg_gamma_g2.write(writer)?;
-        self.neg_delta_g2.write(writer)?;
+        let (alpha_g1_beta_g2, neg_gamma_g2, neg_delta_g2, ic) = match self {
+            PreparedVerifyingKey::Uninitialized => return Ok(()),
+            PreparedVerifyingKey::Prepared { alpha_g1_beta_g2, neg_gamma_g2, neg_delta_g2, ic } =>
+                (alpha_g1_beta_g2, neg_gamma_g2, neg_delta_g2, ic),
+        };
+
+        alpha_g1_beta_g2.write(writer)?;
+        neg_gamma_g2.write(writer)?;
+        neg_delta_g2.write(writer)?;
 
         let mut buf = [0u8; 4];
 
-        BigEndian::write_u32(&mut buf, self.ic.len() as u32);
+        BigEndian::write_u32(&mut buf, ic.len() as u32);
         writer.write(&buf)?;
 
-        for ic in &self.ic {
+        for ic in ic {
             writer.write(ic.into_uncompressed().as_ref())?;
         }
 
         Ok(())
     }
 
+    /// Read a prepared key, rejecting non-canonical encodings of its components.
+    /// It doesn't check that `alpha_g1_beta_g2` is a pairing result, which takes an exponentiation in `E::Fqk`,
+    /// so keys from untrusted sources must be read by `from_bytes` instead.
     pub fn read<R: io::Read> (
         reader: &mut R
-    ) -> io::Result<Self>
+    ) -> Result<Self, VerifyingKeyError>
     {
         use byteorder::{ByteOrder, BigEndian};
 
         let mut g1_repr = <E::G1Affine as CurveAffine>::Uncompressed::empty();
-        let alpha_g1_beta_g2 = E::Fqk::read(reader)?;
+        let alpha_g1_beta_g2 = E::Fqk::read(reader)
+            .map_err(VerifyingKeyError::InvalidAlphaG1BetaG2)?;
 
-        let neg_gamma_g2 = <E::G2Affine as CurveAffine>::Prepared::read(reader)?;
-        let neg_delta_g2 = <E::G2Affine as CurveAffine>::Prepared::read(reader)?;
+        let neg_gamma_g2 = <E::G2Affine as CurveAffine>::Prepared::read(reader)
+            .map_err(VerifyingKeyError::InvalidNegGammaG2)?;
+        let neg_delta_g2 = <E::G2Affine as CurveAffine>::Prepared::read(reader)
+            .map_err(VerifyingKeyError::InvalidNegDeltaG2)?;
 
         let mut buf = [0u8; 4];
-        reader.read(&mut buf)?;
+        reader.read(&mut buf).map_err(VerifyingKeyError::InvalidIc)?;
 
         let ic_len = BigEndian::read_u32(&buf) as usize;
 
         let mut ic = vec![];
 
         for _ in 0..ic_len {
-            reader.read(g1_repr.as_mut())?;
+            reader.read(g1_repr.as_mut()).map_err(VerifyingKeyError::InvalidIc)?;
             let g1 = g1_repr
                         .into_affine()
                         .map_err(|_| io::Error::InvalidData)
@@ -223,19 +251,76 @@ impl<E: Engine> PreparedVerifyingKey<E> {
                             Err(io::Error::PointInfinity)
                         } else {
                             Ok(e)
-                        })?;
+                        })
+                        .map_err(VerifyingKeyError::InvalidIc)?;
             ic.push(g1);
         }
 
-        Ok(PreparedVerifyingKey {
+        Ok(PreparedVerifyingKey::Prepared {
             alpha_g1_beta_g2: alpha_g1_beta_g2,
             neg_gamma_g2: neg_gamma_g2,
             neg_delta_g2: neg_delta_g2,
             ic: ic,
         })
     }
+
+    /// Read a key given either unprepared, in which case it is prepared here, or prepared.
+    /// The unprepared key is much smaller, so it is what should be shipped in calls and chain specs.
+    /// It is tried first, as it is cheap to rule out, and must span all of `bytes`.
+    ///
+    /// This is the validated constructor of keys from calls and chain specs:
+    /// a prepared key is also checked to have a pairing result as `alpha_g1_beta_g2`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, VerifyingKeyError> {
+        let mut reader = bytes;
+        if let Ok(vk) = VerifyingKey::<E>::read(&mut reader) {
+            if reader.is_empty() {
+                return Ok(vk.prepare());
+            }
+        }
+
+        let vk = Self::read(&mut &bytes[..])?;
+        vk.check_alpha_g1_beta_g2()?;
+
+        Ok(vk)
+    }
+
+    /// `alpha_g1_beta_g2` is a pairing result, so it must lie in the
+    /// subgroup of order r of the multiplicative group of `E::Fqk`.
+    fn check_alpha_g1_beta_g2(&self) -> Result<(), VerifyingKeyError> {
+        if let PreparedVerifyingKey::Prepared { alpha_g1_beta_g2, .. } = self {
+            if alpha_g1_beta_g2.is_zero() || alpha_g1_beta_g2.pow(E::Fr::char()) != E::Fqk::one() {
+                return Err(VerifyingKeyError::AlphaG1BetaG2NotInSubgroup);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The number of public inputs of the circuit, not counting the constant one.
+    /// `Uninitialized` takes no inputs.
+    pub fn num_inputs(&self) -> usize {
+        match self {
+            PreparedVerifyingKey::Uninitialized => 0,
+            PreparedVerifyingKey::Prepared { ic, .. } => ic.len().saturating_sub(1),
+        }
+    }
 }
 
+/// This is an error that could occur when reading a `PreparedVerifyingKey`.
+/// Each variant points to the component which was rejected.
+#[derive(Debug)]
+pub enum VerifyingKeyError {
+    /// `alpha_g1_beta_g2` could not be read or had a non-canonical encoding.
+    InvalidAlphaG1BetaG2(io::Error),
+    /// `alpha_g1_beta_g2` is not in the target group of the pairing.
+    AlphaG1BetaG2NotInSubgroup,
+    /// The prepared `-gamma` could not be read or had non-canonical coefficients.
+    InvalidNegGammaG2(io::Error),
+    /// The prepared `-delta` could not be read or had non-canonical coefficients.
+    InvalidNegDeltaG2(io::Error),
+    /// One of the IC elements could not be read or was not a valid G1 point.
+    InvalidIc(io::Error),
+}
 
 #[derive(Clone)]
 pub struct VerifyingKey<E: Engine> {
@@ -277,82 +362,89 @@ impl<E: Engine> PartialEq for VerifyingKey<E> {
     }
 }
 
-// impl<E: Engine> VerifyingKey<E> {
-//     pub fn write<W: io::Write>(
-//         &self,
-//         writer: &mut W
-//     ) -> io::Result<()>
-//     {
-//         use byteorder::BigEndian;
-
-//         writer.write(self.alpha_g1.into_uncompressed().as_ref())?;
-//         writer.write(self.beta_g1.into_uncompressed().as_ref())?;
-//         writer.write(self.beta_g2.into_uncompressed().as_ref())?;
-//         writer.write(self.gamma_g2.into_uncompressed().as_ref())?;
-//         writer.write(self.delta_g1.into_uncompressed().as_ref())?;
-//         writer.write(self.delta_g2.into_uncompressed().as_ref())?;
-//         BigEndian::write_u32(writer, self.ic.len() as u32)?;
-//         for ic in &self.ic {
-//             writer.write(ic.into_uncompressed().as_ref())?;
-//         }
-
-//         Ok(())
-//     }
-
-//     pub fn read<R: io::Read>(
-//         reader: &mut R
-//     ) -> io::Result<Self>
-//     {
-//         let mut g1_repr = <E::G1Affine as CurveAffine>::Uncompressed::empty();
-//         let mut g2_repr = <E::G2Affine as CurveAffine>::Uncompressed::empty();
-
-//         reader.read(g1_repr.as_mut())?;
-//         let alpha_g1 = g1_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-//         reader.read(g1_repr.as_mut())?;
-//         let beta_g1 = g1_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-//         reader.read(g2_repr.as_mut())?;
-//         let beta_g2 = g2_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-//         reader.read(g2_repr.as_mut())?;
-//         let gamma_g2 = g2_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-//         reader.read(g1_repr.as_mut())?;
-//         let delta_g1 = g1_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-//         reader.read(g2_repr.as_mut())?;
-//         let delta_g2 = g2_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-//         let ic_len = reader.read_u32::<BigEndian>()? as usize;
-
-//         let mut ic = vec![];
-
-//         for _ in 0..ic_len {
-//             reader.read(g1_repr.as_mut())?;
-//             let g1 = g1_repr
-//                      .into_affine()
-//                      .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
-//                      .and_then(|e| if e.is_zero() {
-//                          Err(io::Error::new(io::ErrorKind::InvalidData, "point at infinity"))
-//                      } else {
-//                          Ok(e)
-//                      })?;
-
-//             ic.push(g1);
-//         }
-
-//         Ok(VerifyingKey {
-//             alpha_g1: alpha_g1,
-//             beta_g1: beta_g1,
-//             beta_g2: beta_g2,
-//             gamma_g2: gamma_g2,
-//             delta_g1: delta_g1,
-//             delta_g2: delta_g2,
-//             ic: ic
-//         })
-//     }
-// }
+impl<E: Engine> VerifyingKey<E> {
+    /// Write the key with uncompressed points, in the same format as bellman.
+    /// It is much smaller than the prepared key, which holds the line coefficients of `-gamma` and `-delta`.
+    pub fn write<W: io::Write>(
+        &self,
+        writer: &mut W
+    ) -> io::Result<()>
+    {
+        use byteorder::{ByteOrder, BigEndian};
+
+        writer.write(self.alpha_g1.into_uncompressed().as_ref())?;
+        writer.write(self.beta_g1.into_uncompressed().as_ref())?;
+        writer.write(self.beta_g2.into_uncompressed().as_ref())?;
+        writer.write(self.gamma_g2.into_uncompressed().as_ref())?;
+        writer.write(self.delta_g1.into_uncompressed().as_ref())?;
+        writer.write(self.delta_g2.into_uncompressed().as_ref())?;
+
+        let mut buf = [0u8; 4];
+        BigEndian::write_u32(&mut buf, self.ic.len() as u32);
+        writer.write(&buf)?;
+
+        for ic in &self.ic {
+            writer.write(ic.into_uncompressed().as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R: io::Read>(
+        reader: &mut R
+    ) -> io::Result<Self>
+    {
+        use byteorder::{ByteOrder, BigEndian};
+
+        let alpha_g1 = read_non_zero::<E::G1Affine, _>(reader)?;
+        let beta_g1 = read_non_zero::<E::G1Affine, _>(reader)?;
+        let beta_g2 = read_non_zero::<E::G2Affine, _>(reader)?;
+        let gamma_g2 = read_non_zero::<E::G2Affine, _>(reader)?;
+        let delta_g1 = read_non_zero::<E::G1Affine, _>(reader)?;
+        let delta_g2 = read_non_zero::<E::G2Affine, _>(reader)?;
+
+        let mut buf = [0u8; 4];
+        reader.read(&mut buf)?;
+
+        let ic_len = BigEndian::read_u32(&buf) as usize;
+
+        let mut ic = vec![];
+
+        for _ in 0..ic_len {
+            ic.push(read_non_zero::<E::G1Affine, _>(reader)?);
+        }
+
+        Ok(VerifyingKey {
+            alpha_g1: alpha_g1,
+            beta_g1: beta_g1,
+            beta_g2: beta_g2,
+            gamma_g2: gamma_g2,
+            delta_g1: delta_g1,
+            delta_g2: delta_g2,
+            ic: ic
+        })
+    }
+
+    /// Compute the pairing of alpha and beta and the line coefficients of `-gamma` and `-delta`.
+    /// This is the expensive part of reading a prepared key, so it should be done once, e.g. when the key is set.
+    pub fn prepare(&self) -> PreparedVerifyingKey<E> {
+        prepare_verifying_key(self)
+    }
+}
+
+/// Read an uncompressed point, which must not be the point at infinity.
+fn read_non_zero<G: CurveAffine, R: io::Read>(reader: &mut R) -> io::Result<G> {
+    let mut repr = G::Uncompressed::empty();
+    reader.read(repr.as_mut())?;
+
+    repr.into_affine()
+        .map_err(|_| io::Error::InvalidData)
+        .and_then(|e| if e.is_zero() {
+            Err(io::Error::PointInfinity)
+        } else {
+            Ok(e)
+        })
+}
 
 /// This is an error that could occur during circuit synthesis contexts,
 /// such as CRS generation, proving or verification.
@@ -372,6 +464,8 @@ pub enum SynthesisError {
     IoError(io::Error),
     /// During verification, our verifying key was malformed.
     MalformedVerifyingKey,
+    /// During verification, no verifying key had been set.
+    UninitializedVerifyingKey,
     /// During CRS generation, we observed an unconstrained auxillary variable
     UnconstrainedVariable
 }
@@ -422,8 +516,12 @@ mod test_proof_write_read {
         assert!(proof == de_proof);
     }
 
-    #[test]
-    fn prepared_vk_read_write() {
+    // Byte offsets into a serialized `PreparedVerifyingKey<Bls12>`
+    const ALPHA_G1_BETA_G2_LEN: usize = 12 * 48;
+    const NEG_GAMMA_COEFFS_OFFSET: usize = ALPHA_G1_BETA_G2_LEN + 4;
+    const NEG_GAMMA_INFINITY_OFFSET: usize = NEG_GAMMA_COEFFS_OFFSET + 68 * 3 * 96;
+
+    fn read_test_vk() -> Vec<u8> {
         use std::path::Path;
         use std::fs::File;
         use std::io::{BufReader, Read};
@@ -435,6 +533,13 @@ mod test_proof_write_read {
         let mut buf_vk = vec![];
         vk_reader.read_to_end(&mut buf_vk).unwrap();
 
+        buf_vk
+    }
+
+    #[test]
+    fn prepared_vk_read_write() {
+        let buf_vk = read_test_vk();
+
         let prepared_vk_a = PreparedVerifyingKey::<Bls12>::read(&mut &buf_vk[..]).unwrap();
 
         let mut buf = vec![];
@@ -444,4 +549,194 @@ mod test_proof_write_read {
 
         assert!(prepared_vk_a == prepared_vk_b);
     }
+
+    #[test]
+    fn prepared_vk_default_is_uninitialized() {
+        let default_vk = PreparedVerifyingKey::<Bls12>::default();
+        assert!(default_vk == PreparedVerifyingKey::Uninitialized);
+
+        let encoded = default_vk.encode();
+        assert!(PreparedVerifyingKey::<Bls12>::decode(&mut &encoded[..]) == Some(PreparedVerifyingKey::Uninitialized));
+
+        // A corrupted key fails to decode rather than panicking.
+        let buf_vk = read_test_vk();
+        let corrupted = buf_vk[..ALPHA_G1_BETA_G2_LEN].to_vec().encode();
+        assert!(PreparedVerifyingKey::<Bls12>::decode(&mut &corrupted[..]).is_none());
+
+        let prepared_vk = PreparedVerifyingKey::<Bls12>::read(&mut &buf_vk[..]).unwrap();
+        let encoded = prepared_vk.encode();
+        assert!(PreparedVerifyingKey::<Bls12>::decode(&mut &encoded[..]) == Some(prepared_vk));
+    }
+
+    #[test]
+    fn prepared_vk_rejects_non_canonical_alpha_g1_beta_g2() {
+        let mut buf_vk = read_test_vk();
+        // Overwrite the first Fq limb with a value above the modulus.
+        for b in &mut buf_vk[..48] {
+            *b = 0xff;
+        }
+
+        match PreparedVerifyingKey::<Bls12>::read(&mut &buf_vk[..]) {
+            Err(VerifyingKeyError::InvalidAlphaG1BetaG2(io::Error::NotInField)) => {},
+            e => panic!("unexpected result: {:?}", e.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn prepared_vk_rejects_alpha_g1_beta_g2_outside_subgroup() {
+        let mut buf_vk = read_test_vk();
+        for b in &mut buf_vk[..ALPHA_G1_BETA_G2_LEN] {
+            *b = 0;
+        }
+        // alpha_g1_beta_g2 = 2 is a canonical element of Fq12 but not a pairing result.
+        buf_vk[47] = 2;
+
+        match PreparedVerifyingKey::<Bls12>::from_bytes(&buf_vk[..]) {
+            Err(VerifyingKeyError::AlphaG1BetaG2NotInSubgroup) => {},
+            e => panic!("unexpected result: {:?}", e.map(|_| ())),
+        }
+
+        // Decoding a stored key, which was validated when it was set, doesn't pay for the check.
+        let encoded = buf_vk.encode();
+        assert!(PreparedVerifyingKey::<Bls12>::decode(&mut &encoded[..]).is_some());
+
+        buf_vk[47] = 0;
+        match PreparedVerifyingKey::<Bls12>::from_bytes(&buf_vk[..]) {
+            Err(VerifyingKeyError::AlphaG1BetaG2NotInSubgroup) => {},
+            e => panic!("unexpected result: {:?}", e.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn prepared_vk_rejects_non_canonical_g2_coeffs() {
+        let mut buf_vk = read_test_vk();
+        for b in &mut buf_vk[NEG_GAMMA_COEFFS_OFFSET..NEG_GAMMA_COEFFS_OFFSET + 48] {
+            *b = 0xff;
+        }
+
+        match PreparedVerifyingKey::<Bls12>::read(&mut &buf_vk[..]) {
+            Err(VerifyingKeyError::InvalidNegGammaG2(io::Error::NotInField)) => {},
+            e => panic!("unexpected result: {:?}", e.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn prepared_vk_rejects_malformed_g2_prepared() {
+        let buf_vk = read_test_vk();
+
+        // The number of coefficients must match the pairing's loop length.
+        let mut invalid_len = buf_vk.clone();
+        invalid_len[ALPHA_G1_BETA_G2_LEN..NEG_GAMMA_COEFFS_OFFSET].copy_from_slice(&[0xff; 4]);
+        match PreparedVerifyingKey::<Bls12>::read(&mut &invalid_len[..]) {
+            Err(VerifyingKeyError::InvalidNegGammaG2(io::Error::InvalidData)) => {},
+            e => panic!("unexpected result: {:?}", e.map(|_| ())),
+        }
+
+        // The point at infinity is not allowed to carry coefficients.
+        let mut invalid_infinity = buf_vk.clone();
+        invalid_infinity[NEG_GAMMA_INFINITY_OFFSET] = 1;
+        match PreparedVerifyingKey::<Bls12>::read(&mut &invalid_infinity[..]) {
+            Err(VerifyingKeyError::InvalidNegGammaG2(io::Error::InvalidData)) => {},
+            e => panic!("unexpected result: {:?}", e.map(|_| ())),
+        }
+    }
+
+    fn random_vk() -> VerifyingKey<Bls12> {
+        use pairing::{CurveProjective, bls12_381::Fr};
+        use rand::{Rand, SeedableRng, XorShiftRng};
+
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let g1 = |rng: &mut XorShiftRng| G1Affine::one().mul(Fr::rand(rng)).into_affine();
+        let g2 = |rng: &mut XorShiftRng| G2Affine::one().mul(Fr::rand(rng)).into_affine();
+
+        VerifyingKey {
+            alpha_g1: g1(rng),
+            beta_g1: g1(rng),
+            beta_g2: g2(rng),
+            gamma_g2: g2(rng),
+            delta_g1: g1(rng),
+            delta_g2: g2(rng),
+            ic: (0..3).map(|_| g1(rng)).collect(),
+        }
+    }
+
+    #[test]
+    fn vk_read_write() {
+        let vk = random_vk();
+
+        let mut buf = vec![];
+        vk.write(&mut buf).unwrap();
+        assert_eq!(buf.len(), 3 * 96 + 3 * 192 + 4 + 3 * 96);
+
+        let vk_b = VerifyingKey::<Bls12>::read(&mut &buf[..]).unwrap();
+        assert!(vk == vk_b);
+
+        let mut prepared_buf = vec![];
+        vk.prepare().write(&mut prepared_buf).unwrap();
+        assert!(prepared_buf.len() > 10 * buf.len());
+
+        let prepared_vk = PreparedVerifyingKey::<Bls12>::read(&mut &prepared_buf[..]).unwrap();
+        assert!(prepared_vk == vk_b.prepare());
+        assert_eq!(prepared_vk.num_inputs(), 2);
+
+        assert!(PreparedVerifyingKey::<Bls12>::from_bytes(&buf[..]).unwrap() == prepared_vk);
+        assert!(PreparedVerifyingKey::<Bls12>::from_bytes(&prepared_buf[..]).unwrap() == prepared_vk);
+        buf.push(0);
+        assert!(PreparedVerifyingKey::<Bls12>::from_bytes(&buf[..]).is_err());
+    }
+
+    #[test]
+    fn vk_rejects_malformed_points() {
+        let vk = random_vk();
+        let mut buf = vec![];
+        vk.write(&mut buf).unwrap();
+
+        assert!(VerifyingKey::<Bls12>::read(&mut &buf[..buf.len() - 1]).is_err());
+
+        // The uncompressed encoding of the point at infinity
+        let mut infinity = buf.clone();
+        for b in &mut infinity[96..192] {
+            *b = 0;
+        }
+        infinity[96] = 0x40;
+        match VerifyingKey::<Bls12>::read(&mut &infinity[..]) {
+            Err(io::Error::PointInfinity) => {},
+            _ => panic!("the point at infinity must be rejected"),
+        }
+
+        let mut not_on_curve = buf.clone();
+        not_on_curve[191] ^= 1;
+        match VerifyingKey::<Bls12>::read(&mut &not_on_curve[..]) {
+            Err(io::Error::InvalidData) => {},
+            _ => panic!("a point not on the curve must be rejected"),
+        }
+    }
+
+    #[test]
+    fn verify_proofs_batch_rejects_invalid_proofs() {
+        use pairing::bls12_381::Fr;
+
+        let buf_vk = read_test_vk();
+        let pvk = PreparedVerifyingKey::<Bls12>::read(&mut &buf_vk[..]).unwrap();
+        let num_inputs = match pvk {
+            PreparedVerifyingKey::Prepared { ref ic, .. } => ic.len() - 1,
+            PreparedVerifyingKey::Uninitialized => unreachable!(),
+        };
+
+        let proof = Proof::<Bls12> { a: G1Affine::one(), b: G2Affine::one(), c: G1Affine::one() };
+        let inputs = vec![Fr::zero(); num_inputs];
+
+        assert!(verify_proofs_batch(&pvk, &[]).unwrap());
+        assert!(!verify_proofs_batch(&pvk, &[(&proof, &inputs[..])]).unwrap());
+        assert!(!verify_proofs_batch(&pvk, &[(&proof, &inputs[..]), (&proof, &inputs[..])]).unwrap());
+
+        match verify_proofs_batch(&pvk, &[(&proof, &inputs[1..])]) {
+            Err(SynthesisError::MalformedVerifyingKey) => {},
+            e => panic!("unexpected result: {:?}", e),
+        }
+        match verify_proofs_batch(&PreparedVerifyingKey::<Bls12>::default(), &[]) {
+            Err(SynthesisError::UninitializedVerifyingKey) => {},
+            e => panic!("unexpected result: {:?}", e),
+        }
+    }
 }
@@ -2,9 +2,13 @@ use pairing::{
     Engine,
     CurveProjective,
     CurveAffine,
-    PrimeField
+    PrimeField,
+    Field,
 };
+use blake2_rfc::blake2b::Blake2b;
 
+#[cfg(not(feature = "std"))]
+use crate::std::vec::Vec;
 use super::{
     Proof,
     VerifyingKey,
@@ -12,6 +16,9 @@ use super::{
     SynthesisError
 };
 
+/// Personalization of the transcript hash from which the batching scalars are derived.
+const BATCH_PERSONALIZATION: &'static [u8; 16] = b"Zerochain_Batch_";
+
 pub fn prepare_verifying_key<E: Engine>(
     vk: &VerifyingKey<E>
 ) -> PreparedVerifyingKey<E>
@@ -21,7 +28,7 @@ pub fn prepare_verifying_key<E: Engine>(
     let mut delta = vk.delta_g2;
     delta.negate();
 
-    PreparedVerifyingKey {
+    PreparedVerifyingKey::Prepared {
         alpha_g1_beta_g2: E::pairing(vk.alpha_g1, vk.beta_g2),
         neg_gamma_g2: gamma.prepare(),
         neg_delta_g2: delta.prepare(),
@@ -35,15 +42,13 @@ pub fn verify_proof<'a, E: Engine>(
     public_inputs: &[E::Fr]
 ) -> Result<bool, SynthesisError>
 {
-    if (public_inputs.len() + 1) != pvk.ic.len() {
+    let (alpha_g1_beta_g2, neg_gamma_g2, neg_delta_g2, ic) = prepared_parts(pvk)?;
+
+    if (public_inputs.len() + 1) != ic.len() {
         return Err(SynthesisError::MalformedVerifyingKey);
     }
 
-    let mut acc = pvk.ic[0].into_projective();
-
-    for (i, b) in public_inputs.iter().zip(pvk.ic.iter().skip(1)) {
-        acc.add_assign(&b.mul(i.into_repr()));
-    }
+    let acc = accumulate_inputs::<E>(ic, public_inputs);
 
     // The original verification equation is:
     // A * B = alpha * beta + inputs * gamma + C * delta
@@ -56,10 +61,140 @@ pub fn verify_proof<'a, E: Engine>(
     Ok(E::final_exponentiation(
         &E::miller_loop([
             (&proof.a.prepare(), &proof.b.prepare()),
-            (&acc.into_affine().prepare(), &pvk.neg_gamma_g2),
-            (&proof.c.prepare(), &pvk.neg_delta_g2)
+            (&acc.into_affine().prepare(), neg_gamma_g2),
+            (&proof.c.prepare(), neg_delta_g2)
         ].iter())
-    ).unwrap() == pvk.alpha_g1_beta_g2)
+    ).unwrap() == *alpha_g1_beta_g2)
+}
+
+/// Verify proofs against the same verifying key with a single final exponentiation.
+/// Each verification equation is raised to the corresponding scalar and the results are multiplied:
+///
+/// prod e(r_i * A_i, B_i) * e(sum r_i * inputs_i, -gamma) * e(sum r_i * C_i, -delta) == (alpha * beta)^(sum r_i)
+///
+/// The scalars must be unpredictable to the provers, otherwise invalid proofs can cancel each other out.
+pub fn verify_proofs_batch_with_scalars<'a, E: Engine>(
+    pvk: &'a PreparedVerifyingKey<E>,
+    batch: &[(&Proof<E>, &[E::Fr])],
+    scalars: &[E::Fr]
+) -> Result<bool, SynthesisError>
+{
+    let (alpha_g1_beta_g2, neg_gamma_g2, neg_delta_g2, ic) = prepared_parts(pvk)?;
+
+    if batch.len() != scalars.len() {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    if batch.iter().any(|(_, inputs)| inputs.len() + 1 != ic.len()) {
+        return Err(SynthesisError::MalformedVerifyingKey);
+    }
+
+    // sum r_i * inputs_i is accumulated over the scalars of each input,
+    // so that both sums are a single multi-exponentiation:
+    // sum r_i * (ic_0 + sum_j x_ij * ic_j) = (sum r_i) * ic_0 + sum_j (sum_i r_i * x_ij) * ic_j
+    let mut ic_scalars = vec![E::Fr::zero(); ic.len()];
+    let mut cs = Vec::with_capacity(batch.len());
+    let mut a_b = Vec::with_capacity(batch.len());
+
+    for ((proof, inputs), r) in batch.iter().zip(scalars.iter()) {
+        ic_scalars[0].add_assign(r);
+        for (s, input) in ic_scalars[1..].iter_mut().zip(inputs.iter()) {
+            let mut tmp = *input;
+            tmp.mul_assign(r);
+            s.add_assign(&tmp);
+        }
+
+        cs.push(proof.c);
+        a_b.push((proof.a.mul(r.into_repr()).into_affine().prepare(), proof.b.prepare()));
+    }
+
+    let sum_r = ic_scalars[0];
+    let reprs = |scalars: &[E::Fr]| -> Vec<_> { scalars.iter().map(|s| s.into_repr()).collect() };
+    let sum_acc = E::G1::multi_exp(ic, &reprs(&ic_scalars)).into_affine().prepare();
+    let sum_c = E::G1::multi_exp(&cs, &reprs(scalars)).into_affine().prepare();
+
+    let mut terms: Vec<_> = a_b.iter().map(|(a, b)| (a, b)).collect();
+    terms.push((&sum_acc, neg_gamma_g2));
+    terms.push((&sum_c, neg_delta_g2));
+
+    Ok(E::final_exponentiation(&E::miller_loop(terms.iter())).unwrap()
+        == alpha_g1_beta_g2.pow(sum_r.into_repr()))
+}
+
+/// Verify proofs against the same verifying key with a single final exponentiation.
+/// The scalars combining the verification equations are derived from the hash of all the proofs
+/// and public inputs, so a prover can't choose them to cancel out an invalid proof.
+/// Each scalar is a non-zero 128-bit integer, which keeps the scalar multiplications cheap.
+pub fn verify_proofs_batch<'a, E: Engine>(
+    pvk: &'a PreparedVerifyingKey<E>,
+    batch: &[(&Proof<E>, &[E::Fr])]
+) -> Result<bool, SynthesisError>
+{
+    prepared_parts(pvk)?;
+
+    if batch.is_empty() {
+        return Ok(true);
+    }
+
+    let mut h = Blake2b::with_params(32, &[], &[], BATCH_PERSONALIZATION);
+    for (proof, inputs) in batch {
+        h.update(proof.a.into_compressed().as_ref());
+        h.update(proof.b.into_compressed().as_ref());
+        h.update(proof.c.into_compressed().as_ref());
+        for input in inputs.iter() {
+            for limb in input.into_repr().as_ref() {
+                h.update(&limb.to_le_bytes());
+            }
+        }
+    }
+    let seed = h.finalize();
+
+    let scalars: Vec<_> = (0..batch.len())
+        .map(|i| batch_scalar::<E>(seed.as_bytes(), i as u32))
+        .collect();
+
+    verify_proofs_batch_with_scalars(pvk, batch, &scalars[..])
+}
+
+/// A non-zero 128-bit scalar for the `index`-th proof of a batch.
+fn batch_scalar<E: Engine>(seed: &[u8], index: u32) -> E::Fr {
+    let mut h = Blake2b::with_params(32, &[], &[], BATCH_PERSONALIZATION);
+    h.update(seed);
+    h.update(&index.to_le_bytes());
+    let bytes = h.finalize();
+
+    let limb = |b: &[u8]| b.iter().rev().fold(0u64, |acc, x| (acc << 8) | *x as u64);
+    let mut repr = <E::Fr as PrimeField>::Repr::default();
+    repr.as_mut()[0] = limb(&bytes.as_bytes()[0..8]) | 1;
+    repr.as_mut()[1] = limb(&bytes.as_bytes()[8..16]);
+
+    E::Fr::from_repr(repr).expect("a 128-bit integer is smaller than the modulus; qed")
+}
+
+/// `ic[0] + sum inputs[i] * ic[i + 1]`; the lengths are checked by the callers.
+fn accumulate_inputs<E: Engine>(ic: &[E::G1Affine], inputs: &[E::Fr]) -> E::G1 {
+    let scalars: Vec<_> = inputs.iter().map(|i| i.into_repr()).collect();
+
+    let mut acc = E::G1::multi_exp(&ic[1..], &scalars);
+    acc.add_assign_mixed(&ic[0]);
+    acc
+}
+
+/// Borrow the components of a prepared verifying key.
+/// Proofs can't be verified against an uninitialized key.
+fn prepared_parts<E: Engine>(
+    pvk: &PreparedVerifyingKey<E>
+) -> Result<(
+    &E::Fqk,
+    &<E::G2Affine as CurveAffine>::Prepared,
+    &<E::G2Affine as CurveAffine>::Prepared,
+    &Vec<E::G1Affine>
+), SynthesisError>
+{
+    match pvk {
+        PreparedVerifyingKey::Uninitialized => Err(SynthesisError::UninitializedVerifyingKey),
+        PreparedVerifyingKey::Prepared { alpha_g1_beta_g2, neg_gamma_g2, neg_delta_g2, ic } =>
+            Ok((alpha_g1_beta_g2, neg_gamma_g2, neg_delta_g2, ic)),
+    }
 }
 
 #[cfg(test)]
@@ -73,7 +208,7 @@ mod tests {
 
     #[test]
     fn test_verify() {
-        let pvk = PreparedVerifyingKey::<DummyEngine> {
+        let pvk = PreparedVerifyingKey::<DummyEngine>::Prepared {
             alpha_g1_beta_g2: Fr(Wrapping(18634)),
             neg_gamma_g2: Fr(Wrapping(11181)),
             neg_delta_g2: Fr(Wrapping(59032)),
@@ -89,5 +224,74 @@ mod tests {
         let pub_inp = [Fr(Wrapping(1))];
 
         assert!(verify_proof(&pvk, &proof, &pub_inp).unwrap());
+
+        match verify_proof(&PreparedVerifyingKey::<DummyEngine>::default(), &proof, &pub_inp) {
+            Err(SynthesisError::UninitializedVerifyingKey) => {},
+            _ => panic!("an uninitialized verifying key must be rejected"),
+        }
+    }
+
+    #[cfg(feature = "bn254")]
+    #[test]
+    fn test_verify_bn254() {
+        use pairing::bn254::{Bn254, Fr, G1Affine, G2Affine};
+        use rand::{Rand, SeedableRng, XorShiftRng};
+
+        let rng = &mut XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let g1 = G1Affine::one();
+        let g2 = G2Affine::one();
+
+        // Knowing the trapdoor, a valid proof can be made without running the prover.
+        let alpha = Fr::rand(rng);
+        let beta = Fr::rand(rng);
+        let gamma = Fr::rand(rng);
+        let delta = Fr::rand(rng);
+        let ic: Vec<Fr> = (0..3).map(|_| Fr::rand(rng)).collect();
+
+        let vk = VerifyingKey::<Bn254> {
+            alpha_g1: g1.mul(alpha).into_affine(),
+            beta_g1: g1.mul(beta).into_affine(),
+            beta_g2: g2.mul(beta).into_affine(),
+            gamma_g2: g2.mul(gamma).into_affine(),
+            delta_g1: g1.mul(delta).into_affine(),
+            delta_g2: g2.mul(delta).into_affine(),
+            ic: ic.iter().map(|s| g1.mul(*s).into_affine()).collect(),
+        };
+        let pvk = prepare_verifying_key(&vk);
+
+        // a = alpha * beta + (ic_0 + sum inputs_i * ic_i) * gamma + c * delta, with b = 1
+        let prove = |inputs: &[Fr], c: Fr| {
+            let mut acc = ic[0];
+            for (input, ic) in inputs.iter().zip(&ic[1..]) {
+                let mut tmp = *input;
+                tmp.mul_assign(ic);
+                acc.add_assign(&tmp);
+            }
+            acc.mul_assign(&gamma);
+            let mut a = alpha;
+            a.mul_assign(&beta);
+            a.add_assign(&acc);
+            let mut tmp = c;
+            tmp.mul_assign(&delta);
+            a.add_assign(&tmp);
+
+            Proof {
+                a: g1.mul(a).into_affine(),
+                b: g2,
+                c: g1.mul(c).into_affine(),
+            }
+        };
+
+        let inputs = [Fr::rand(rng), Fr::rand(rng)];
+        let proof = prove(&inputs, Fr::rand(rng));
+        let other_inputs = [Fr::rand(rng), Fr::rand(rng)];
+        let other_proof = prove(&other_inputs, Fr::rand(rng));
+
+        assert!(verify_proof(&pvk, &proof, &inputs).unwrap());
+        assert!(verify_proofs_batch(&pvk, &[(&proof, &inputs[..])]).unwrap());
+        assert!(!verify_proof(&pvk, &proof, &[inputs[1], inputs[0]]).unwrap());
+
+        assert!(verify_proofs_batch(&pvk, &[(&proof, &inputs[..]), (&other_proof, &other_inputs[..])]).unwrap());
+        assert!(!verify_proofs_batch(&pvk, &[(&proof, &other_inputs[..]), (&other_proof, &inputs[..])]).unwrap());
     }
 }
@@ -1,7 +1,7 @@
 #[cfg(feature = "std")]
-use ::std::u32;
+use ::std::{u32, ops::Range, vec::Vec};
 #[cfg(not(feature = "std"))]
-use crate::std::u32;
+use crate::std::{u32, ops::Range, vec::Vec};
 
 use jubjub::curve::{
         JubjubEngine,
@@ -13,7 +13,7 @@ use jubjub::curve::{
 use blake2_rfc::{
     blake2b::{Blake2b, Blake2bResult}
 };
-use pairing::io;
+use pairing::{io, PrimeField};
 
 /// The constant personalization for elgamal extending function
 pub const ELGAMAL_EXTEND_PERSONALIZATION: &'static [u8; 16] = b"zech_elgamal_ext";
@@ -46,16 +46,16 @@ impl<E: JubjubEngine> Ciphertext<E> {
     }
 
     pub fn encrypt(
-        amount: u32, // 32-bits restriction for the decryption.
+        amount: u64, // Decryption searches the amount, so only small amounts can be decrypted.
         randomness: &E::Fs,
         enc_key: &keys::EncryptionKey<E>,
         p_g: FixedGenerators,
         params: &E::Params
     ) -> Self
     {
-        let right = params.generator(p_g).mul(*randomness, params);
-        let v_point = params.generator(p_g).mul(amount as u64, params);
-        let r_point = enc_key.0.mul(*randomness, params);
+        let right = edwards::Point::<E, PrimeOrder>::mul_fixed(p_g, *randomness, params);
+        let v_point = edwards::Point::<E, PrimeOrder>::mul_fixed(p_g, amount, params);
+        let r_point = enc_key.0.mul_ct(*randomness, params);
         let left = v_point.add(&r_point, params);
 
         Ciphertext {
@@ -66,15 +66,15 @@ impl<E: JubjubEngine> Ciphertext<E> {
 
     // Encrypt with negative value
     pub fn neg_encrypt(
-        amount: u32,
+        amount: u64,
         randomness: &E::Fs,
         enc_key: &keys::EncryptionKey<E>,
         p_g: FixedGenerators,
         params: &E::Params
     ) -> Self {
-        let right = params.generator(p_g).mul(*randomness, params);
-        let v_point = params.generator(p_g).mul(amount as u64, params).negate();
-        let r_point = enc_key.0.mul(*randomness, params);
+        let right = edwards::Point::<E, PrimeOrder>::mul_fixed(p_g, *randomness, params);
+        let v_point = edwards::Point::<E, PrimeOrder>::mul_fixed(p_g, amount, params).negate();
+        let r_point = enc_key.0.mul_ct(*randomness, params);
         let left = v_point.add(&r_point, params);
 
         Ciphertext {
@@ -83,15 +83,34 @@ impl<E: JubjubEngine> Ciphertext<E> {
         }
     }
 
+    /// Refresh the randomness of the ciphertext encrypted by `enc_key`, without changing the amount:
+    /// ((m + r s)G + r' sG, rG + r'G), so that it can't be linked to the original one.
+    pub fn rerandomize(
+        &self,
+        randomness: &E::Fs,
+        enc_key: &keys::EncryptionKey<E>,
+        p_g: FixedGenerators,
+        params: &E::Params
+    ) -> Self
+    {
+        let left = self.left.add(&enc_key.0.mul_ct(*randomness, params), params);
+        let right = self.right.add(&edwards::Point::<E, PrimeOrder>::mul_fixed(p_g, *randomness, params), params);
+
+        Ciphertext {
+            left,
+            right,
+        }
+    }
+
     /// Decryption of the ciphetext for the amount
     pub fn decrypt(
         &self,
         decryption_key: &keys::DecryptionKey<E>,
         p_g: FixedGenerators,
         params: &E::Params
-    ) -> Option<u32>
+    ) -> Option<u64>
     {
-        let sr_point = self.right.mul(decryption_key.0, params);
+        let sr_point = self.right.mul_ct(decryption_key.0, params);
         let neg_sr_point = sr_point.negate();
         let v_point = self.left.add(&neg_sr_point, params);
 
@@ -109,6 +128,49 @@ impl<E: JubjubEngine> Ciphertext<E> {
         None
     }
 
+    /// Decryption of the ciphertext for amounts below `table.range()`
+    /// by the baby-step giant-step algorithm with the precomputed baby steps,
+    /// which takes at most `table.size()` giant steps.
+    /// Larger amounts are decrypted by `decrypt_with_hint` with a range known to contain them.
+    pub fn decrypt_with_table(
+        &self,
+        decryption_key: &keys::DecryptionKey<E>,
+        table: &DecryptionTable<E>,
+        params: &E::Params
+    ) -> Option<u64>
+    {
+        self.decrypt_with_hint(decryption_key, 0..table.range(), table, params)
+    }
+
+    /// Decryption of the ciphertext for an amount known to be in `range`,
+    /// which takes a giant step for each `table.size()` amounts of the range.
+    pub fn decrypt_with_hint(
+        &self,
+        decryption_key: &keys::DecryptionKey<E>,
+        range: Range<u64>,
+        table: &DecryptionTable<E>,
+        params: &E::Params
+    ) -> Option<u64>
+    {
+        let sr_point = self.right.mul_ct(decryption_key.0, params);
+        let v_point = self.left.add(&sr_point.negate(), params);
+
+        // Search (v - start)G from zero instead of vG from the start of the range.
+        let start_point = edwards::Point::<E, PrimeOrder>::mul_fixed(table.p_g, range.start, params);
+        let mut acc = v_point.add(&start_point.negate(), params);
+        let mut base = range.start;
+
+        while base < range.end {
+            if let Some(j) = table.find(&acc, params) {
+                return base.checked_add(j as u64).filter(|amount| *amount < range.end);
+            }
+            acc = acc.add(&table.giant_step, params);
+            base = base.checked_add(table.size() as u64)?;
+        }
+
+        None
+    }
+
     pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
         self.left.write(&mut writer)?;
         self.right.write(&mut writer)?;
@@ -180,6 +242,117 @@ impl<E: JubjubEngine> Ciphertext<E> {
     }
 }
 
+/// The baby steps of the baby-step giant-step decryption, i.e. jG for j in [0, size) indexed by a key of the points,
+/// with which `Ciphertext::decrypt_with_table` finds an amount below size^2 in at most `size` giant steps.
+/// Building the table takes `size` point additions, so it is built once and kept by `write` and `read`.
+pub struct DecryptionTable<E: JubjubEngine> {
+    p_g: FixedGenerators,
+    /// The keys of jG and j, sorted by the keys.
+    baby_steps: Vec<(u64, u32)>,
+    /// -size * G
+    giant_step: edwards::Point<E, PrimeOrder>,
+}
+
+impl<E: JubjubEngine> DecryptionTable<E> {
+    pub fn new(size: u32, p_g: FixedGenerators, params: &E::Params) -> Self {
+        assert!(size > 0, "The decryption table must have a baby step.");
+
+        let g = params.generator(p_g);
+        let mut acc = edwards::Point::<E, PrimeOrder>::zero();
+        let mut baby_steps = Vec::with_capacity(size as usize);
+        for j in 0..size {
+            baby_steps.push((point_key(&acc), j));
+            acc = acc.add(g, params);
+        }
+        baby_steps.sort_unstable();
+
+        Self::from_baby_steps(baby_steps, p_g, params)
+    }
+
+    fn from_baby_steps(baby_steps: Vec<(u64, u32)>, p_g: FixedGenerators, params: &E::Params) -> Self {
+        let giant_step = edwards::Point::<E, PrimeOrder>::mul_fixed(p_g, baby_steps.len() as u64, params).negate();
+
+        DecryptionTable {
+            p_g,
+            baby_steps,
+            giant_step,
+        }
+    }
+
+    /// The number of baby steps.
+    pub fn size(&self) -> u32 {
+        self.baby_steps.len() as u32
+    }
+
+    /// The amounts below which `Ciphertext::decrypt_with_table` decrypts.
+    pub fn range(&self) -> u64 {
+        self.size() as u64 * self.size() as u64
+    }
+
+    /// Find j such that jG is the point.
+    /// The keys can collide, so the points of the matching keys are compared.
+    fn find(&self, point: &edwards::Point<E, PrimeOrder>, params: &E::Params) -> Option<u32> {
+        let key = point_key(point);
+        let mut i = self.baby_steps.binary_search_by_key(&key, |&(k, _)| k).ok()?;
+        while i > 0 && self.baby_steps[i - 1].0 == key {
+            i -= 1;
+        }
+
+        self.baby_steps[i..].iter()
+            .take_while(|&&(k, _)| k == key)
+            .map(|&(_, j)| j)
+            .find(|&j| edwards::Point::<E, PrimeOrder>::mul_fixed(self.p_g, j as u64, params) == *point)
+    }
+
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write(&self.size().to_le_bytes())?;
+        for (key, j) in &self.baby_steps {
+            writer.write(&key.to_le_bytes())?;
+            writer.write(&j.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Read a table written by `write`. It must have been built for `p_g`.
+    pub fn read<R: io::Read>(reader: &mut R, p_g: FixedGenerators, params: &E::Params) -> io::Result<Self> {
+        // `io::Read::read` fills the whole buffer or fails with `UnexpectedEof`, like `read_exact`,
+        // so a truncated table is an error.
+        let mut buf = [0u8; 4];
+        reader.read(&mut buf)?;
+        let size = u32::from_le_bytes(buf);
+        if size == 0 {
+            return Err(io::Error::InvalidData);
+        }
+
+        let mut baby_steps = Vec::with_capacity(size as usize);
+        for _ in 0..size {
+            let mut key = [0u8; 8];
+            let mut j = [0u8; 4];
+            reader.read(&mut key)?;
+            reader.read(&mut j)?;
+            baby_steps.push((u64::from_le_bytes(key), u32::from_le_bytes(j)));
+        }
+
+        if baby_steps.windows(2).any(|w| w[0] > w[1]) || baby_steps.iter().any(|&(_, j)| j >= size) {
+            return Err(io::Error::InvalidData);
+        }
+
+        let table = Self::from_baby_steps(baby_steps, p_g, params);
+        // A table of another generator doesn't have the generator itself as a baby step.
+        if size > 1 && table.find(params.generator(p_g), params) != Some(1) {
+            return Err(io::Error::InvalidData);
+        }
+
+        Ok(table)
+    }
+}
+
+/// The lowest limb of the y-coordinate of the point.
+fn point_key<E: JubjubEngine>(point: &edwards::Point<E, PrimeOrder>) -> u64 {
+    point.into_xy().1.into_repr().as_ref()[0]
+}
+
 /// Extend the secret key to 64 bits for the scalar field generation.
 pub fn elgamal_extend(sk: &[u8]) -> Blake2bResult {
     let mut h = Blake2b::with_params(64, &[], &[], ELGAMAL_EXTEND_PERSONALIZATION);
@@ -192,7 +365,7 @@ mod tests {
     use super::*;
     use rand::{SeedableRng, XorShiftRng, Rand};
     use jubjub::curve::{JubjubBls12, fs::Fs};
-    use pairing::bls12_381::Bls12;
+    use pairing::{bls12_381::Bls12, Field};
     use keys::{ProofGenerationKey, EncryptionKey, DecryptionKey};
 
     #[test]
@@ -213,6 +386,107 @@ mod tests {
         assert_eq!(amount, decrypted_amount);
     }
 
+    #[test]
+    fn test_rerandomize() {
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::Diversifier; // 1
+        let rng = &mut XorShiftRng::from_seed([0xbc4f6d44, 0xd62f276c, 0xb963afd0, 0x5455863d]);
+        let amount = 5;
+
+        let sk_fs = Fs::rand(rng);
+        let r_fs = Fs::rand(rng);
+        let enc_key = EncryptionKey(params.generator(p_g).mul(sk_fs, params));
+
+        let ciphetext = Ciphertext::encrypt(amount, &r_fs, &enc_key, p_g, params);
+        let r_fs2 = Fs::rand(rng);
+        let rerandomized = ciphetext.rerandomize(&r_fs2, &enc_key, p_g, params);
+
+        assert!(rerandomized.left != ciphetext.left);
+        assert!(rerandomized.right != ciphetext.right);
+        assert_eq!(rerandomized.decrypt(&DecryptionKey(sk_fs), p_g, params), Some(amount));
+
+        let mut r_sum = r_fs;
+        r_sum.add_assign(&r_fs2);
+        assert!(rerandomized == Ciphertext::encrypt(amount, &r_sum, &enc_key, p_g, params));
+    }
+
+    #[test]
+    fn test_decrypt_with_table() {
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::Diversifier; // 1
+        let rng = &mut XorShiftRng::from_seed([0xbc4f6d44, 0xd62f276c, 0xb963afd0, 0x5455863d]);
+
+        let sk_fs = Fs::rand(rng);
+        let dec_key = DecryptionKey(sk_fs);
+        let enc_key = EncryptionKey(params.generator(p_g).mul(sk_fs, params));
+        let table = DecryptionTable::<Bls12>::new(256, p_g, params);
+        assert_eq!(table.range(), 65536);
+
+        for &amount in &[0, 1, 255, 256, 40000, 65535] {
+            let ciphertext = Ciphertext::encrypt(amount, &Fs::rand(rng), &enc_key, p_g, params);
+            assert_eq!(ciphertext.decrypt_with_table(&dec_key, &table, params), Some(amount));
+        }
+
+        // Amounts beyond the range aren't searched.
+        let ciphertext = Ciphertext::encrypt(65536, &Fs::rand(rng), &enc_key, p_g, params);
+        assert_eq!(ciphertext.decrypt_with_table(&dec_key, &table, params), None);
+
+        // Neither is a ciphertext under another key searched beyond the range.
+        let other_key = DecryptionKey(Fs::rand(rng));
+        let ciphertext = Ciphertext::encrypt(100, &Fs::rand(rng), &enc_key, p_g, params);
+        assert_eq!(ciphertext.decrypt_with_table(&other_key, &table, params), None);
+
+        // A hint finds amounts beyond the range of the table.
+        let ciphertext = Ciphertext::encrypt(3_000_123, &Fs::rand(rng), &enc_key, p_g, params);
+        assert_eq!(ciphertext.decrypt_with_hint(&dec_key, 3_000_000..3_001_000, &table, params), Some(3_000_123));
+        assert_eq!(ciphertext.decrypt_with_hint(&dec_key, 3_000_000..3_000_123, &table, params), None);
+        assert_eq!(ciphertext.decrypt_with_hint(&dec_key, 3_000_124..3_001_000, &table, params), None);
+    }
+
+    #[test]
+    fn test_decrypt_with_hint_above_u32() {
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::Diversifier; // 1
+        let rng = &mut XorShiftRng::from_seed([0xbc4f6d44, 0xd62f276c, 0xb963afd0, 0x5455863d]);
+
+        let sk_fs = Fs::rand(rng);
+        let dec_key = DecryptionKey(sk_fs);
+        let enc_key = EncryptionKey(params.generator(p_g).mul(sk_fs, params));
+        // The table of the wallets, whose range is 2^32.
+        let table = DecryptionTable::<Bls12>::new(1 << 16, p_g, params);
+
+        let ciphertext = Ciphertext::encrypt((1 << 32) - 1, &Fs::rand(rng), &enc_key, p_g, params);
+        assert_eq!(ciphertext.decrypt_with_table(&dec_key, &table, params), Some((1 << 32) - 1));
+
+        let ciphertext = Ciphertext::encrypt(1 << 32, &Fs::rand(rng), &enc_key, p_g, params);
+        assert_eq!(ciphertext.decrypt_with_table(&dec_key, &table, params), None);
+        assert_eq!(ciphertext.decrypt_with_hint(&dec_key, (1 << 32)..(1 << 33), &table, params), Some(1 << 32));
+
+        let amount = (1 << 60) + 65537;
+        let ciphertext = Ciphertext::encrypt(amount, &Fs::rand(rng), &enc_key, p_g, params);
+        assert_eq!(ciphertext.decrypt_with_hint(&dec_key, (1 << 60)..(1 << 60) + (1 << 20), &table, params), Some(amount));
+        assert_eq!(ciphertext.decrypt_with_hint(&dec_key, u64::max_value() - (1 << 20)..u64::max_value(), &table, params), None);
+    }
+
+    #[test]
+    fn test_decryption_table_read_write() {
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::Diversifier; // 1
+        let table = DecryptionTable::<Bls12>::new(100, p_g, params);
+
+        let mut buf = vec![];
+        table.write(&mut buf).unwrap();
+        assert_eq!(buf.len(), 4 + 100 * 12);
+
+        let read = DecryptionTable::<Bls12>::read(&mut &buf[..], p_g, params).unwrap();
+        assert!(read.baby_steps == table.baby_steps);
+        assert!(read.giant_step == table.giant_step);
+
+        assert!(DecryptionTable::<Bls12>::read(&mut &buf[..], FixedGenerators::ElGamal, params).is_err());
+        assert!(DecryptionTable::<Bls12>::read(&mut &buf[..buf.len() - 1], p_g, params).is_err());
+        assert!(DecryptionTable::<Bls12>::read(&mut &buf[..2], p_g, params).is_err());
+    }
+
     #[test]
     fn test_elgamal_enc_dec_ivk() {
         let params = &JubjubBls12::new();
@@ -0,0 +1,234 @@
+//! Proofs that two lifted-ElGamal ciphertexts with the same randomness encrypt the same amount
+//! under different encryption keys, without revealing the amount.
+//!
+//! For `right = rG`, `left_a = mG + r * enc_key_a` and `left_b = mG + r * enc_key_b`,
+//! `left_a - left_b = r * (enc_key_a - enc_key_b)`, so it is a Chaum-Pedersen proof that
+//! `right` and `left_a - left_b` have the same discrete logarithm to `G` and `enc_key_a - enc_key_b`.
+//! Against `amount * G` under the identity key, it proves a ciphertext encrypts a public amount.
+
+#[cfg(not(feature = "std"))]
+use crate::std::vec::Vec;
+
+use jubjub::curve::{
+        JubjubEngine,
+        JubjubParams,
+        edwards,
+        PrimeOrder,
+        FixedGenerators,
+};
+use jubjub::util::hash_to_scalar;
+use pairing::{Field, PrimeField, PrimeFieldRepr, io};
+use rand::{Rng, Rand};
+
+/// The constant personalization for the challenge of equality proofs
+pub const EQUALITY_PROOF_PERSONALIZATION: &'static [u8; 16] = b"zech_equality_pf";
+
+/// The length of an encoded equality proof.
+pub const EQUALITY_PROOF_SIZE: usize = 64;
+
+#[derive(Clone, PartialEq)]
+pub struct EqualityProof<E: JubjubEngine> {
+    pub challenge: E::Fs,
+    pub response: E::Fs,
+}
+
+impl<E: JubjubEngine> EqualityProof<E> {
+    /// Prove that `left_a` and `left_b` encrypt the same amount with `randomness`.
+    pub fn prove<R: Rng>(
+        randomness: &E::Fs,
+        left_a: &edwards::Point<E, PrimeOrder>,
+        enc_key_a: &keys::EncryptionKey<E>,
+        left_b: &edwards::Point<E, PrimeOrder>,
+        enc_key_b: &keys::EncryptionKey<E>,
+        p_g: FixedGenerators,
+        rng: &mut R,
+        params: &E::Params
+    ) -> Self
+    {
+        let right = params.generator(p_g).mul_ct(*randomness, params);
+        let base = enc_key_a.0.add(&enc_key_b.0.negate(), params);
+
+        let k = E::Fs::rand(rng);
+        let commitment_g = params.generator(p_g).mul_ct(k, params);
+        let commitment_base = base.mul_ct(k, params);
+
+        let challenge = Self::challenge(
+            &[left_a, &enc_key_a.0, left_b, &enc_key_b.0, &right, &commitment_g, &commitment_base]
+        );
+
+        // response = k + challenge * randomness
+        let mut response = challenge;
+        response.mul_assign(randomness);
+        response.add_assign(&k);
+
+        EqualityProof {
+            challenge,
+            response,
+        }
+    }
+
+    /// Verify that `left_a` and `left_b` with the common right component `right` encrypt the same amount.
+    pub fn verify(
+        &self,
+        left_a: &edwards::Point<E, PrimeOrder>,
+        enc_key_a: &keys::EncryptionKey<E>,
+        left_b: &edwards::Point<E, PrimeOrder>,
+        enc_key_b: &keys::EncryptionKey<E>,
+        right: &edwards::Point<E, PrimeOrder>,
+        p_g: FixedGenerators,
+        params: &E::Params
+    ) -> bool
+    {
+        let base = enc_key_a.0.add(&enc_key_b.0.negate(), params);
+        let diff = left_a.add(&left_b.negate(), params);
+
+        // response * G - challenge * right
+        let commitment_g = params.generator(p_g).mul(self.response, params)
+            .add(&right.mul(self.challenge, params).negate(), params);
+        // response * (enc_key_a - enc_key_b) - challenge * (left_a - left_b)
+        let commitment_base = base.mul(self.response, params)
+            .add(&diff.mul(self.challenge, params).negate(), params);
+
+        let challenge = Self::challenge(
+            &[left_a, &enc_key_a.0, left_b, &enc_key_b.0, right, &commitment_g, &commitment_base]
+        );
+
+        challenge == self.challenge
+    }
+
+    /// Prove that `left` encrypts the public `amount` with `randomness`, e.g. the fee of a transfer.
+    /// `amount * G` is the left of `amount` encrypted by the identity key, so it is an equality proof
+    /// of `left` against it.
+    pub fn prove_amount<R: Rng>(
+        randomness: &E::Fs,
+        left: &edwards::Point<E, PrimeOrder>,
+        enc_key: &keys::EncryptionKey<E>,
+        amount: u64,
+        p_g: FixedGenerators,
+        rng: &mut R,
+        params: &E::Params
+    ) -> Self
+    {
+        let amount_g = params.generator(p_g).mul(amount, params);
+
+        Self::prove(randomness, left, enc_key, &amount_g, &keys::EncryptionKey(edwards::Point::zero()), p_g, rng, params)
+    }
+
+    /// Verify that `left` with the right component `right` encrypts the public `amount`.
+    pub fn verify_amount(
+        &self,
+        left: &edwards::Point<E, PrimeOrder>,
+        enc_key: &keys::EncryptionKey<E>,
+        amount: u64,
+        right: &edwards::Point<E, PrimeOrder>,
+        p_g: FixedGenerators,
+        params: &E::Params
+    ) -> bool
+    {
+        let amount_g = params.generator(p_g).mul(amount, params);
+
+        self.verify(left, enc_key, &amount_g, &keys::EncryptionKey(edwards::Point::zero()), right, p_g, params)
+    }
+
+    pub fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.challenge.into_repr().write_le(writer)?;
+        self.response.into_repr().write_le(writer)?;
+
+        Ok(())
+    }
+
+    pub fn read<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let challenge = read_scalar::<E, R>(reader)?;
+        let response = read_scalar::<E, R>(reader)?;
+
+        Ok(EqualityProof {
+            challenge,
+            response,
+        })
+    }
+
+    fn challenge(points: &[&edwards::Point<E, PrimeOrder>]) -> E::Fs {
+        let mut buf = Vec::with_capacity(points.len() * 32);
+        for p in points {
+            let mut bytes = [0u8; 32];
+            p.write(&mut &mut bytes[..]).expect("a point is 32 bytes; qed");
+            buf.extend_from_slice(&bytes[..]);
+        }
+
+        hash_to_scalar::<E>(EQUALITY_PROOF_PERSONALIZATION, &buf[..], &[])
+    }
+}
+
+fn read_scalar<E: JubjubEngine, R: io::Read>(reader: &mut R) -> io::Result<E::Fs> {
+    let mut repr = <E::Fs as PrimeField>::Repr::default();
+    repr.read_le(reader)?;
+
+    E::Fs::from_repr(repr).map_err(|_| io::Error::NotInField)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, XorShiftRng, Rand};
+    use jubjub::curve::{JubjubBls12, fs::Fs};
+    use pairing::bls12_381::Bls12;
+    use keys::{ProofGenerationKey, EncryptionKey};
+    use crate::elgamal::Ciphertext;
+
+    #[test]
+    fn test_equality_proof() {
+        let params = &JubjubBls12::new();
+        let rng = &mut XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let p_g = FixedGenerators::Diversifier;
+
+        let enc_key_a = EncryptionKey::from_decryption_key(
+            &ProofGenerationKey::<Bls12>::from_seed(&[1u8; 32], params).into_decryption_key().unwrap(),
+            params
+        );
+        let enc_key_b = EncryptionKey::from_decryption_key(
+            &ProofGenerationKey::<Bls12>::from_seed(&[2u8; 32], params).into_decryption_key().unwrap(),
+            params
+        );
+
+        let r = Fs::rand(rng);
+        let c_a = Ciphertext::encrypt(10, &r, &enc_key_a, p_g, params);
+        let c_b = Ciphertext::encrypt(10, &r, &enc_key_b, p_g, params);
+        let c_other = Ciphertext::encrypt(11, &r, &enc_key_b, p_g, params);
+
+        let proof = EqualityProof::prove(&r, &c_a.left, &enc_key_a, &c_b.left, &enc_key_b, p_g, rng, params);
+        assert!(proof.verify(&c_a.left, &enc_key_a, &c_b.left, &enc_key_b, &c_a.right, p_g, params));
+
+        let mut buf = vec![];
+        proof.write(&mut buf).unwrap();
+        assert_eq!(buf.len(), EQUALITY_PROOF_SIZE);
+        assert!(EqualityProof::<Bls12>::read(&mut &buf[..]).unwrap() == proof);
+
+        // No proof holds for ciphertexts of different amounts.
+        assert!(!proof.verify(&c_a.left, &enc_key_a, &c_other.left, &enc_key_b, &c_a.right, p_g, params));
+        let forged = EqualityProof::prove(&r, &c_a.left, &enc_key_a, &c_other.left, &enc_key_b, p_g, rng, params);
+        assert!(!forged.verify(&c_a.left, &enc_key_a, &c_other.left, &enc_key_b, &c_a.right, p_g, params));
+    }
+
+    #[test]
+    fn test_amount_proof() {
+        let params = &JubjubBls12::new();
+        let rng = &mut XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let p_g = FixedGenerators::Diversifier;
+
+        let enc_key = EncryptionKey::from_decryption_key(
+            &ProofGenerationKey::<Bls12>::from_seed(&[1u8; 32], params).into_decryption_key().unwrap(),
+            params
+        );
+
+        let r = Fs::rand(rng);
+        let c = Ciphertext::encrypt(3, &r, &enc_key, p_g, params);
+
+        let proof = EqualityProof::prove_amount(&r, &c.left, &enc_key, 3, p_g, rng, params);
+        assert!(proof.verify_amount(&c.left, &enc_key, 3, &c.right, p_g, params));
+
+        // The proof doesn't hold for any other amount, nor can one be made for it.
+        assert!(!proof.verify_amount(&c.left, &enc_key, 2, &c.right, p_g, params));
+        let forged = EqualityProof::prove_amount(&r, &c.left, &enc_key, 2, p_g, rng, params);
+        assert!(!forged.verify_amount(&c.left, &enc_key, 2, &c.right, p_g, params));
+    }
+}
@@ -14,3 +14,4 @@ mod std {
 }
 
 pub mod elgamal;
+pub mod equality;
@@ -12,6 +12,7 @@ use super::{
     JubjubParams,
     Unknown,
     PrimeOrder,
+    FixedGenerators,
     montgomery
 };
 
@@ -591,4 +592,64 @@ impl<E: JubjubEngine, Subgroup> Point<E, Subgroup> {
 
         res
     }
+
+    /// Scalar multiplication by a secret scalar, such as a spending key or a decryption key.
+    /// Every bit of the representation takes a doubling and an addition whose result is selected
+    /// without branching on the bit, and the unified addition has no special cases,
+    /// so unlike `mul` the operations performed don't depend on the scalar.
+    #[must_use]
+    pub fn mul_ct<S: Into<<E::Fs as PrimeField>::Repr>>(
+        &self,
+        scalar: S,
+        params: &E::Params
+    ) -> Self
+    {
+        let mut res = Self::zero();
+
+        for b in BitIterator::new(scalar.into()) {
+            res = res.double(params);
+
+            let sum = res.add(self, params);
+            let candidates = [res, sum];
+            res = candidates[b as usize].clone();
+        }
+
+        res
+    }
+}
+
+impl<E: JubjubEngine> Point<E, PrimeOrder> {
+    /// Scalar multiplication of a fixed generator with its precomputed exp table,
+    /// which takes an addition per window of the scalar instead of a doubling and
+    /// an addition per bit. Every entry of a window table is read and selected
+    /// without branching on the scalar, so like `mul_ct` it is fit for secret scalars.
+    #[must_use]
+    pub fn mul_fixed<S: Into<<E::Fs as PrimeField>::Repr>>(
+        base: FixedGenerators,
+        scalar: S,
+        params: &E::Params
+    ) -> Self
+    {
+        let scalar = scalar.into();
+        let limbs = scalar.as_ref();
+        let window = <E::Params as JubjubParams<E>>::fixed_base_exp_window_size() as usize;
+        let mask = (1u64 << window) - 1;
+
+        let mut res = Self::zero();
+
+        for (i, table) in params.fixed_base_exp_table(base).iter().enumerate() {
+            let bit = i * window;
+            let chunk = limbs.get(bit / 64).map_or(0, |limb| (limb >> (bit % 64)) & mask) as usize;
+
+            let mut selected = table[0].clone();
+            for (j, p) in table.iter().enumerate() {
+                let candidates = [selected, p.clone()];
+                selected = candidates[(j == chunk) as usize].clone();
+            }
+
+            res = res.add(&selected, params);
+        }
+
+        res
+    }
 }
@@ -137,6 +137,13 @@ pub trait JubjubParams<E: JubjubEngine>: Sized {
     /// Returns the window size for exponentiation of Pedersen hash generators
     /// outside the circuit
     fn pedersen_hash_exp_window_size() -> u32;
+    /// Returns the exp table of a fixed generator for `edwards::Point::mul_fixed`:
+    /// for each window of the scalar, the multiples [0, 1, ..., 2^w - 1] of
+    /// the generator shifted to the window.
+    fn fixed_base_exp_table(&self, base: FixedGenerators) -> &[Vec<edwards::Point<E, PrimeOrder>>];
+    /// Returns the window size for exponentiation of fixed generators
+    /// outside the circuit
+    fn fixed_base_exp_window_size() -> u32;
 }
 
 impl JubjubEngine for Bls12 {
@@ -158,6 +165,7 @@ pub struct JubjubBls12 {
 
     fixed_base_generators: Vec<edwards::Point<Bls12, PrimeOrder>>,
     fixed_base_circuit_generators: Vec<Vec<Vec<(Fr, Fr)>>>,
+    fixed_base_exp: Vec<Vec<Vec<edwards::Point<Bls12, PrimeOrder>>>>,
 }
 
 impl JubjubParams<Bls12> for JubjubBls12 {
@@ -191,6 +199,13 @@ impl JubjubParams<Bls12> for JubjubBls12 {
     fn pedersen_hash_exp_window_size() -> u32 {
         8
     }
+    fn fixed_base_exp_table(&self, base: FixedGenerators) -> &[Vec<edwards::Point<Bls12, PrimeOrder>>]
+    {
+        &self.fixed_base_exp[base as usize][..]
+    }
+    fn fixed_base_exp_window_size() -> u32 {
+        4
+    }
 }
 
 impl JubjubBls12 {
@@ -215,6 +230,7 @@ impl JubjubBls12 {
             pedersen_circuit_generators: vec![],
             fixed_base_generators: vec![],
             fixed_base_circuit_generators: vec![],
+            fixed_base_exp: vec![],
         };
 
         fn find_group_hash<E: JubjubEngine>(
@@ -353,6 +369,43 @@ impl JubjubBls12 {
             tmp_params.fixed_base_generators = fixed_base_generators;
         }
 
+        // Create the exp table for the fixed generators. The windows cover all
+        // the 256 bits of a scalar representation, as `mul_fixed` takes any of them.
+        {
+            let mut fixed_base_exp = vec![];
+
+            for g in &tmp_params.fixed_base_generators {
+                let mut g = g.clone();
+
+                let window = JubjubBls12::fixed_base_exp_window_size();
+
+                let mut tables = vec![];
+
+                let mut num_bits = 0;
+                while num_bits <= fs::Fs::NUM_BITS {
+                    let mut table = Vec::with_capacity(1 << window);
+
+                    let mut base = edwards::Point::zero();
+
+                    for _ in 0..(1 << window) {
+                        table.push(base.clone());
+                        base = base.add(&g, &tmp_params);
+                    }
+
+                    tables.push(table);
+                    num_bits += window;
+
+                    for _ in 0..window {
+                        g = g.double(&tmp_params);
+                    }
+                }
+
+                fixed_base_exp.push(tables);
+            }
+
+            tmp_params.fixed_base_exp = fixed_base_exp;
+        }
+
         // Create the 2-bit window table lookups for each 4-bit
         // "chunk" in each segment of the Pedersen hash
         {
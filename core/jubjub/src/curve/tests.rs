@@ -32,6 +32,8 @@ pub fn test_suite<E: JubjubEngine>(params: &E::Params)
     test_addition_associativity::<E>(params);
     test_order::<E>(params);
     test_mul_associativity::<E>(params);
+    test_mul_ct::<E>(params);
+    test_mul_fixed::<E>(params);
     test_loworder::<E>(params);
     test_read_write::<E>(params);
 }
@@ -148,6 +150,57 @@ fn test_mul_associativity<E: JubjubEngine>(params: &E::Params) {
     }
 }
 
+fn test_mul_ct<E: JubjubEngine>(params: &E::Params) {
+    use self::edwards::Point;
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    for _ in 0..100 {
+        let base = Point::<E, _>::rand(rng, params).mul_by_cofactor(params);
+        let s = E::Fs::rand(rng);
+
+        assert!(base.mul_ct(s, params) == base.mul(s, params));
+    }
+
+    let base = Point::<E, _>::rand(rng, params).mul_by_cofactor(params);
+    let mut minus_one = E::Fs::one();
+    minus_one.negate();
+
+    assert!(base.mul_ct(E::Fs::zero(), params) == Point::zero());
+    assert!(base.mul_ct(E::Fs::one(), params) == base);
+    assert!(base.mul_ct(minus_one, params) == base.negate());
+    assert!(Point::<E, PrimeOrder>::zero().mul_ct(E::Fs::rand(rng), params) == Point::zero());
+}
+
+fn test_mul_fixed<E: JubjubEngine>(params: &E::Params) {
+    use self::edwards::Point;
+    use super::FixedGenerators;
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    for &p_g in &[FixedGenerators::ProofGenerationKey, FixedGenerators::Diversifier, FixedGenerators::ElGamal] {
+        let base = params.generator(p_g);
+
+        for _ in 0..20 {
+            let s = E::Fs::rand(rng);
+            assert!(Point::mul_fixed(p_g, s, params) == base.mul(s, params));
+        }
+
+        let mut minus_one = E::Fs::one();
+        minus_one.negate();
+
+        assert!(Point::mul_fixed(p_g, E::Fs::zero(), params) == Point::<E, PrimeOrder>::zero());
+        assert!(Point::mul_fixed(p_g, E::Fs::one(), params) == *base);
+        assert!(Point::mul_fixed(p_g, minus_one, params) == base.negate());
+        assert!(Point::mul_fixed(p_g, 1000u64, params) == base.mul(1000u64, params));
+
+        // Representations beyond the modulus.
+        let mut max = <E::Fs as PrimeField>::Repr::default();
+        for limb in max.as_mut() {
+            *limb = u64::max_value();
+        }
+        assert!(Point::mul_fixed(p_g, max, params) == base.mul(max, params));
+    }
+}
+
 fn test_order<E: JubjubEngine>(params: &E::Params) {
     use self::edwards::Point;
     let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
@@ -44,3 +44,45 @@ pub fn group_hash<E: JubjubEngine>(
         Err(_) => None
     }
 }
+
+/// The maximum length of a domain separation tag of `hash_to_curve`.
+pub const MAX_DST_LENGTH: usize = 255;
+
+/// Hashes a message to a point in the Jubjub curve by try-and-increment
+/// with a domain separation tag, as `hash_to_curve` of the IETF draft does for the suites without a map.
+/// The message is length-prefixed and the tag is suffixed with its length,
+/// so no two pairs of a tag and a message share a hash input, and the counter is kept out of both.
+/// The point is guaranteed to be prime order and not the identity.
+/// Returns `None` if no point is found in 256 attempts, which happens with probability about 2^-256.
+pub fn hash_to_curve<E: JubjubEngine>(
+    msg: &[u8],
+    dst: &[u8],
+    params: &E::Params
+) -> Option<edwards::Point<E, PrimeOrder>>
+{
+    assert!(dst.len() <= MAX_DST_LENGTH);
+    assert!(E::Fr::NUM_BITS == 255);
+
+    let msg_len = msg.len() as u32;
+    let msg_len = [msg_len as u8, (msg_len >> 8) as u8, (msg_len >> 16) as u8, (msg_len >> 24) as u8];
+
+    for counter in 0..=u8::max_value() {
+        let mut h = Blake2s::new(32);
+        h.update(&[counter]);
+        h.update(&msg_len);
+        h.update(msg);
+        h.update(dst);
+        h.update(&[dst.len() as u8]);
+        let h = h.finalize();
+
+        if let Ok(p) = edwards::Point::<E, _>::read(&mut h.as_ref(), params) {
+            let p = p.mul_by_cofactor(params);
+
+            if p != edwards::Point::zero() {
+                return Some(p);
+            }
+        }
+    }
+
+    None
+}
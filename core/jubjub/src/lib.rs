@@ -15,6 +15,7 @@ mod std {
 
 pub mod curve;
 pub mod group_hash;
+pub mod pedersen_hash;
 pub mod constants;
 pub mod redjubjub;
 pub mod util;
@@ -4,7 +4,7 @@
 use pairing::{Field, PrimeField, PrimeFieldRepr, io};
 use rand::{Rng, Rand};
 
-use crate::curve::{FixedGenerators, JubjubEngine, JubjubParams, Unknown, edwards::Point};
+use crate::curve::{FixedGenerators, JubjubEngine, PrimeOrder, Unknown, edwards::Point};
 use crate::util::{hash_to_scalar};
 
 pub fn read_scalar<E: JubjubEngine, R: io::Read>(mut reader: R) -> io::Result<E::Fs> {
@@ -86,7 +86,7 @@ impl<E: JubjubEngine> PrivateKey<E> {
         let r = h_star::<E>(&t[..], msg);
 
         // R = r . P_G
-        let r_g = params.generator(p_g).mul(r, params);
+        let r_g = Point::<E, PrimeOrder>::mul_fixed(p_g, r, params);
         let mut rbar = [0u8; 32];
         r_g.write(&mut &mut rbar[..])
             .expect("Jubjub points should serialize to 32 bytes");
@@ -105,12 +105,12 @@ impl<E: JubjubEngine> PrivateKey<E> {
 
 impl<E: JubjubEngine> PublicKey<E> {
     pub fn from_private(privkey: &PrivateKey<E>, p_g: FixedGenerators, params: &E::Params) -> Self {
-        let res = params.generator(p_g).mul(privkey.0, params).into();
+        let res = Point::<E, PrimeOrder>::mul_fixed(p_g, privkey.0, params).into();
         PublicKey(res)
     }
 
     pub fn randomize(&self, alpha: E::Fs, p_g: FixedGenerators, params: &E::Params) -> Self {
-        let res: Point<E, Unknown> = params.generator(p_g).mul(alpha, params).into();
+        let res: Point<E, Unknown> = Point::<E, PrimeOrder>::mul_fixed(p_g, alpha, params).into();
         let res = res.add(&self.0, params);
         PublicKey(res)
     }
@@ -149,7 +149,7 @@ impl<E: JubjubEngine> PublicKey<E> {
 
         // 0 = h_G(-S . P_G + R + c . vk)
         self.0.mul(c, params).add(&r, params).add(
-            &params.generator(p_g).mul(s, params).negate().into(),
+            &Point::<E, PrimeOrder>::mul_fixed(p_g, s, params).negate().into(),
             params
         ).mul_by_cofactor(params).eq(&Point::zero())
     }
@@ -195,7 +195,7 @@ pub fn batch_verify<'a, E: JubjubEngine, R: Rng>(
 
         acc = acc.add(&r, params);
         acc = acc.add(&entry.vk.0.mul(c, params), params);
-        acc = acc.add(&params.generator(p_g).mul(s, params).into(), params);
+        acc = acc.add(&Point::<E, PrimeOrder>::mul_fixed(p_g, s, params).into(), params);
     }
 
     acc = acc.mul_by_cofactor(params).into();
@@ -0,0 +1,427 @@
+//! Hierarchical deterministic derivation of the keys, following ZIP32
+//! (https://github.com/zcash/zips/blob/master/zip-0032.rst), so that one seed manages
+//! many accounts, each derived by its path, e.g. `m/32'/0'/1'`.
+//!
+//! Hardened children are only derived from an extended spending key. Non-hardened children are
+//! also derived from an extended proof generation key, which gives their encryption keys
+//! without exposing the spending keys.
+
+#[cfg(not(feature = "std"))]
+use crate::std::vec::Vec;
+use core::{fmt, str::FromStr};
+use pairing::{Field, io};
+use jubjub::curve::{JubjubEngine, ToUniform};
+use blake2_rfc::blake2b::Blake2b;
+use crate::{SpendingKey, ProofGenerationKey, EncryptionKey, prf_expand, prf_expand_vec};
+
+pub const MASTER_PERSONALIZATION: &'static [u8; 16] = b"Zerochain_Master";
+pub const EKFP_PERSONALIZATION: &'static [u8; 16] = b"ZerochainEFinger";
+
+/// The index of the first hardened child.
+const HARDENED_OFFSET: u32 = 1 << 31;
+
+/// A 32-byte chain code
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChainCode(pub [u8; 32]);
+
+impl ChainCode {
+    fn from_slice(slice: &[u8]) -> Self {
+        let mut c = [0u8; 32];
+        c.copy_from_slice(slice);
+        ChainCode(c)
+    }
+}
+
+/// The first 4 bytes of the fingerprint of the parent's proof generation key.
+/// It speeds up looking up the parent, but must not be assumed to identify it uniquely.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EncKeyTag(pub [u8; 4]);
+
+impl EncKeyTag {
+    pub fn master() -> Self {
+        EncKeyTag([0u8; 4])
+    }
+
+    fn from_proof_generation_key<E: JubjubEngine>(proof_gen_key: &ProofGenerationKey<E>) -> io::Result<Self> {
+        let mut h = Blake2b::with_params(32, &[], &[], EKFP_PERSONALIZATION);
+        h.update(&proof_gen_key.into_bytes()?);
+
+        let mut tag = [0u8; 4];
+        tag.copy_from_slice(&h.finalize().as_bytes()[..4]);
+        Ok(EncKeyTag(tag))
+    }
+}
+
+/// A child index for a derived key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChildIndex {
+    NonHardened(u32),
+    Hardened(u32),
+}
+
+impl ChildIndex {
+    pub fn from_index(i: u32) -> Self {
+        match i {
+            n if n >= HARDENED_OFFSET => ChildIndex::Hardened(n - HARDENED_OFFSET),
+            n => ChildIndex::NonHardened(n),
+        }
+    }
+
+    pub fn master() -> Self {
+        ChildIndex::from_index(0)
+    }
+
+    pub fn to_index(&self) -> u32 {
+        match *self {
+            ChildIndex::Hardened(i) => i + HARDENED_OFFSET,
+            ChildIndex::NonHardened(i) => i,
+        }
+    }
+}
+
+/// A derivation path from the master key like `m/32'/0'/1`, where `'` (or `h`) marks a hardened child.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DerivationPath(pub Vec<ChildIndex>);
+
+impl DerivationPath {
+    pub fn indices(&self) -> &[ChildIndex] {
+        &self.0[..]
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('/');
+        if parts.next() != Some("m") {
+            return Err("A derivation path must start with `m`.");
+        }
+
+        parts.map(|part| {
+            let (digits, hardened) = match part.chars().last() {
+                Some('\'') | Some('h') => (&part[..part.len() - 1], true),
+                _ => (part, false),
+            };
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return Err("A child index must be a decimal number.");
+            }
+
+            let i = digits.parse::<u32>()
+                .ok()
+                .filter(|i| *i < HARDENED_OFFSET)
+                .ok_or("A child index must be below 2^31.")?;
+
+            Ok(if hardened { ChildIndex::Hardened(i) } else { ChildIndex::NonHardened(i) })
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(DerivationPath)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "m")?;
+        for i in self.indices() {
+            match *i {
+                ChildIndex::Hardened(i) => write!(f, "/{}'", i)?,
+                ChildIndex::NonHardened(i) => write!(f, "/{}", i)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Extended spending key, from which both hardened and non-hardened children are derived.
+#[derive(Clone)]
+pub struct ExtendedSpendingKey<E: JubjubEngine> {
+    pub depth: u8,
+    pub parent_enckey_tag: EncKeyTag,
+    pub child_index: ChildIndex,
+    pub chain_code: ChainCode,
+    pub spending_key: SpendingKey<E>,
+}
+
+impl<E: JubjubEngine> ExtendedSpendingKey<E> {
+    /// Master key generation:
+    /// - Calculate I = BLAKE2b-512("MASTER_PERSONALIZATION", seed)
+    /// - Split I into two 32-bytes arrays, I_L and I_R.
+    /// - Use I_L as the seed of the master spending key and I_R as the master chain code.
+    pub fn master(seed: &[u8]) -> Self {
+        let mut h = Blake2b::with_params(64, &[], &[], MASTER_PERSONALIZATION);
+        h.update(seed);
+        let hashed = h.finalize();
+        let (left, right) = hashed.as_bytes().split_at(32);
+
+        ExtendedSpendingKey {
+            depth: 0,
+            parent_enckey_tag: EncKeyTag::master(),
+            child_index: ChildIndex::master(),
+            chain_code: ChainCode::from_slice(right),
+            spending_key: SpendingKey::from_seed(left),
+        }
+    }
+
+    /// Derive the child of the index, hardened or not.
+    pub fn derive_child(&self, i: ChildIndex, params: &E::Params) -> io::Result<Self> {
+        let proof_gen_key = ProofGenerationKey::from_spending_key(&self.spending_key, params);
+        let i_le = i.to_index().to_le_bytes();
+
+        let hashed = match i {
+            ChildIndex::Hardened(_) => prf_expand_vec(
+                &self.chain_code.0,
+                &[&[0x11], &self.spending_key.into_bytes()?, &i_le],
+            ),
+            ChildIndex::NonHardened(_) => prf_expand_vec(
+                &self.chain_code.0,
+                &[&[0x12], &proof_gen_key.into_bytes()?, &i_le],
+            ),
+        };
+        let (left, right) = hashed.as_bytes().split_at(32);
+
+        let mut fs = E::Fs::to_uniform(prf_expand(left, &[0x13]).as_bytes());
+        fs.add_assign(&self.spending_key.0);
+
+        Ok(ExtendedSpendingKey {
+            depth: self.depth.checked_add(1).ok_or(io::Error::InvalidData)?,
+            parent_enckey_tag: EncKeyTag::from_proof_generation_key(&proof_gen_key)?,
+            child_index: i,
+            chain_code: ChainCode::from_slice(right),
+            spending_key: SpendingKey(fs),
+        })
+    }
+
+    /// Derive the descendant at the path from this key.
+    pub fn derive_path(&self, path: &DerivationPath, params: &E::Params) -> io::Result<Self> {
+        path.indices().iter()
+            .try_fold(self.clone(), |xsk, i| xsk.derive_child(*i, params))
+    }
+
+    pub fn into_encryption_key(&self, params: &E::Params) -> io::Result<EncryptionKey<E>> {
+        EncryptionKey::from_spending_key(&self.spending_key, params)
+    }
+
+    pub fn read<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let (depth, parent_enckey_tag, child_index, chain_code) = read_header(&mut reader)?;
+        let spending_key = SpendingKey::read(reader)?;
+
+        Ok(ExtendedSpendingKey {
+            depth,
+            parent_enckey_tag,
+            child_index,
+            chain_code,
+            spending_key,
+        })
+    }
+
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        write_header(&mut writer, self.depth, &self.parent_enckey_tag, &self.child_index, &self.chain_code)?;
+        self.spending_key.write(writer)?;
+
+        Ok(())
+    }
+}
+
+/// Extended proof generation key, from which only non-hardened children are derived.
+#[derive(Clone)]
+pub struct ExtendedProofGenerationKey<E: JubjubEngine> {
+    pub depth: u8,
+    pub parent_enckey_tag: EncKeyTag,
+    pub child_index: ChildIndex,
+    pub chain_code: ChainCode,
+    pub proof_gen_key: ProofGenerationKey<E>,
+}
+
+impl<E: JubjubEngine> ExtendedProofGenerationKey<E> {
+    pub fn from_extended_spending_key(xsk: &ExtendedSpendingKey<E>, params: &E::Params) -> Self {
+        ExtendedProofGenerationKey {
+            depth: xsk.depth,
+            parent_enckey_tag: xsk.parent_enckey_tag,
+            child_index: xsk.child_index,
+            chain_code: xsk.chain_code,
+            proof_gen_key: ProofGenerationKey::from_spending_key(&xsk.spending_key, params),
+        }
+    }
+
+    /// Derive the non-hardened child of the index. Hardened children need the spending key.
+    pub fn derive_child(&self, i: ChildIndex, params: &E::Params) -> io::Result<Self> {
+        let hashed = match i {
+            ChildIndex::Hardened(_) => return Err(io::Error::InvalidData),
+            ChildIndex::NonHardened(_) => prf_expand_vec(
+                &self.chain_code.0,
+                &[&[0x12], &self.proof_gen_key.into_bytes()?, &i.to_index().to_le_bytes()],
+            ),
+        };
+        let (left, right) = hashed.as_bytes().split_at(32);
+
+        let fs = E::Fs::to_uniform(prf_expand(left, &[0x13]).as_bytes());
+        let proof_gen_key = ProofGenerationKey::from_spending_key(&SpendingKey(fs), params)
+            .add(&self.proof_gen_key, params);
+
+        Ok(ExtendedProofGenerationKey {
+            depth: self.depth.checked_add(1).ok_or(io::Error::InvalidData)?,
+            parent_enckey_tag: EncKeyTag::from_proof_generation_key(&self.proof_gen_key)?,
+            child_index: i,
+            chain_code: ChainCode::from_slice(right),
+            proof_gen_key,
+        })
+    }
+
+    /// Derive the descendant at the path from this key. The path must not contain hardened children.
+    pub fn derive_path(&self, path: &DerivationPath, params: &E::Params) -> io::Result<Self> {
+        path.indices().iter()
+            .try_fold(self.clone(), |xpgk, i| xpgk.derive_child(*i, params))
+    }
+
+    pub fn into_encryption_key(&self, params: &E::Params) -> io::Result<EncryptionKey<E>> {
+        self.proof_gen_key.into_encryption_key(params)
+    }
+
+    pub fn read<R: io::Read>(mut reader: R, params: &E::Params) -> io::Result<Self> {
+        let (depth, parent_enckey_tag, child_index, chain_code) = read_header(&mut reader)?;
+        let proof_gen_key = ProofGenerationKey::read(&mut reader, params)?;
+
+        Ok(ExtendedProofGenerationKey {
+            depth,
+            parent_enckey_tag,
+            child_index,
+            chain_code,
+            proof_gen_key,
+        })
+    }
+
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        write_header(&mut writer, self.depth, &self.parent_enckey_tag, &self.child_index, &self.chain_code)?;
+        self.proof_gen_key.write(writer)?;
+
+        Ok(())
+    }
+}
+
+/// The depth, the parent's tag, the child index and the chain code of an extended key,
+/// encoded in the same way as zface's keyfiles.
+fn read_header<R: io::Read>(reader: &mut R) -> io::Result<(u8, EncKeyTag, ChildIndex, ChainCode)> {
+    let mut depth = [0u8; 1];
+    reader.read(&mut depth)?;
+    let mut tag = [0u8; 4];
+    reader.read(&mut tag)?;
+    let mut i = [0u8; 4];
+    reader.read(&mut i)?;
+    let mut c = [0u8; 32];
+    reader.read(&mut c)?;
+
+    Ok((depth[0], EncKeyTag(tag), ChildIndex::from_index(u32::from_le_bytes(i)), ChainCode(c)))
+}
+
+fn write_header<W: io::Write>(
+    writer: &mut W,
+    depth: u8,
+    tag: &EncKeyTag,
+    i: &ChildIndex,
+    c: &ChainCode,
+) -> io::Result<()> {
+    writer.write(&[depth])?;
+    writer.write(&tag.0)?;
+    writer.write(&i.to_index().to_le_bytes())?;
+    writer.write(&c.0)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jubjub::curve::JubjubBls12;
+    use pairing::bls12_381::Bls12;
+
+    const SEED: &[u8] = b"zerochain hd derivation test seed";
+
+    fn pgk_bytes(xpgk: &ExtendedProofGenerationKey<Bls12>) -> [u8; 32] {
+        xpgk.proof_gen_key.into_bytes().unwrap()
+    }
+
+    #[test]
+    fn test_derive_nonhardened_child() {
+        let params = &JubjubBls12::new();
+        let xsk_m = ExtendedSpendingKey::<Bls12>::master(SEED);
+        let xpgk_m = ExtendedProofGenerationKey::from_extended_spending_key(&xsk_m, params);
+
+        let index = ChildIndex::NonHardened(3);
+        let xsk_child = xsk_m.derive_child(index, params).unwrap();
+        let xpgk_child = xpgk_m.derive_child(index, params).unwrap();
+
+        let expected = ExtendedProofGenerationKey::from_extended_spending_key(&xsk_child, params);
+        assert_eq!(pgk_bytes(&expected), pgk_bytes(&xpgk_child));
+        assert_eq!(expected.chain_code, xpgk_child.chain_code);
+        assert_eq!(expected.parent_enckey_tag, xpgk_child.parent_enckey_tag);
+        assert_eq!(xpgk_child.depth, 1);
+        assert!(
+            xsk_child.into_encryption_key(params).unwrap() == xpgk_child.into_encryption_key(params).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_derive_hardened_child() {
+        let params = &JubjubBls12::new();
+        let xsk_m = ExtendedSpendingKey::<Bls12>::master(SEED);
+        let xpgk_m = ExtendedProofGenerationKey::from_extended_spending_key(&xsk_m, params);
+
+        let hardened = xsk_m.derive_child(ChildIndex::Hardened(3), params).unwrap();
+        let nonhardened = xsk_m.derive_child(ChildIndex::NonHardened(3), params).unwrap();
+        assert!(xpgk_m.derive_child(ChildIndex::Hardened(3), params).is_err());
+        assert!(hardened.spending_key.into_bytes().unwrap() != nonhardened.spending_key.into_bytes().unwrap());
+        assert_eq!(hardened.child_index.to_index(), 3 + (1 << 31));
+    }
+
+    #[test]
+    fn test_derive_path() {
+        let params = &JubjubBls12::new();
+        let xsk_m = ExtendedSpendingKey::<Bls12>::master(SEED);
+        let path: DerivationPath = "m/32'/0'/1".parse().unwrap();
+
+        let derived = xsk_m.derive_path(&path, params).unwrap();
+        let expected = xsk_m.derive_child(ChildIndex::Hardened(32), params).unwrap()
+            .derive_child(ChildIndex::Hardened(0), params).unwrap()
+            .derive_child(ChildIndex::NonHardened(1), params).unwrap();
+        assert_eq!(derived.spending_key.into_bytes().unwrap(), expected.spending_key.into_bytes().unwrap());
+        assert_eq!(derived.depth, 3);
+
+        let xsk_m_read = xsk_m.derive_path(&"m".parse().unwrap(), params).unwrap();
+        assert_eq!(xsk_m_read.spending_key.into_bytes().unwrap(), xsk_m.spending_key.into_bytes().unwrap());
+    }
+
+    #[test]
+    fn test_parse_derivation_path() {
+        let path: DerivationPath = "m/32'/5h/7".parse().unwrap();
+        assert_eq!(path.indices(), &[ChildIndex::Hardened(32), ChildIndex::Hardened(5), ChildIndex::NonHardened(7)]);
+        assert_eq!(path.to_string(), "m/32'/5'/7");
+        assert_eq!("m".parse::<DerivationPath>(), Ok(DerivationPath::default()));
+
+        assert!("32'/0'".parse::<DerivationPath>().is_err());
+        assert!("m/".parse::<DerivationPath>().is_err());
+        assert!("m/x'".parse::<DerivationPath>().is_err());
+        assert!("m/+1".parse::<DerivationPath>().is_err());
+        assert!("m/2147483648".parse::<DerivationPath>().is_err());
+    }
+
+    #[test]
+    fn test_read_write() {
+        let params = &JubjubBls12::new();
+        let xsk = ExtendedSpendingKey::<Bls12>::master(SEED)
+            .derive_child(ChildIndex::Hardened(1), params).unwrap();
+
+        let mut bytes = vec![];
+        xsk.write(&mut bytes).unwrap();
+        let xsk_read = ExtendedSpendingKey::<Bls12>::read(&bytes[..]).unwrap();
+        assert_eq!(xsk_read.spending_key.into_bytes().unwrap(), xsk.spending_key.into_bytes().unwrap());
+        assert_eq!(xsk_read.child_index, xsk.child_index);
+        assert_eq!(xsk_read.chain_code, xsk.chain_code);
+
+        let xpgk = ExtendedProofGenerationKey::from_extended_spending_key(&xsk, params);
+        let mut bytes = vec![];
+        xpgk.write(&mut bytes).unwrap();
+        let xpgk_read = ExtendedProofGenerationKey::<Bls12>::read(&bytes[..], params).unwrap();
+        assert_eq!(pgk_bytes(&xpgk_read), pgk_bytes(&xpgk));
+        assert_eq!(xpgk_read.parent_enckey_tag, xpgk.parent_enckey_tag);
+    }
+}
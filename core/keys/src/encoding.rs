@@ -0,0 +1,169 @@
+//! A versioned and checksummed string encoding of decryption keys, shared by the wallets,
+//! so that a decryption key exported by one of them can be imported by the others.
+//!
+//! The encoding is the base58 of the version byte, the little-endian bytes of the key
+//! and the first four bytes of the Blake2b hash of both, like SS58 addresses.
+//! It is 37 bytes long, so it is never mistaken for an SS58 address of an encryption key.
+
+#[cfg(not(feature = "std"))]
+use crate::std::vec::Vec;
+use core::{fmt, str::FromStr};
+use crate::DecryptionKey;
+use jubjub::curve::JubjubEngine;
+use blake2_rfc::blake2b::Blake2b;
+
+pub const DECRYPTION_KEY_CHECKSUM_PERSONALIZATION: &'static [u8; 16] = b"zech_DecKeyCksum";
+
+/// The version of the encoding of decryption keys.
+pub const DECRYPTION_KEY_VERSION: u8 = 1;
+
+const CHECKSUM_LEN: usize = 4;
+const ENCODED_LEN: usize = 1 + 32 + CHECKSUM_LEN;
+
+const ALPHABET: &'static [u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+fn checksum(payload: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut h = Blake2b::with_params(64, &[], &[], DECRYPTION_KEY_CHECKSUM_PERSONALIZATION);
+    h.update(payload);
+
+    let mut res = [0u8; CHECKSUM_LEN];
+    res.copy_from_slice(&h.finalize().as_ref()[..CHECKSUM_LEN]);
+    res
+}
+
+/// Encode the bytes in base58, as the indices to the alphabet.
+fn to_base58(input: &[u8]) -> Vec<u8> {
+    let mut digits: Vec<u8> = Vec::with_capacity(input.len() * 138 / 100 + 1);
+
+    for &byte in input {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    // Each leading zero byte is encoded as the first character of the alphabet.
+    for _ in input.iter().take_while(|b| **b == 0) {
+        digits.push(0);
+    }
+
+    digits.reverse();
+    digits
+}
+
+fn from_base58(input: &str) -> Result<Vec<u8>, &'static str> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(input.len());
+
+    for c in input.bytes() {
+        let mut carry = ALPHABET.iter().position(|a| *a == c)
+            .ok_or("Invalid base58 character in the decryption key")? as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    for _ in input.bytes().take_while(|c| *c == ALPHABET[0]) {
+        bytes.push(0);
+    }
+
+    bytes.reverse();
+    Ok(bytes)
+}
+
+impl<E: JubjubEngine> fmt::Display for DecryptionKey<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut buf = [0u8; ENCODED_LEN];
+        buf[0] = DECRYPTION_KEY_VERSION;
+        self.write(&mut buf[1..33]).map_err(|_| fmt::Error)?;
+        let checksum = checksum(&buf[..33]);
+        buf[33..].copy_from_slice(&checksum);
+
+        for digit in to_base58(&buf[..]) {
+            write!(f, "{}", ALPHABET[digit as usize] as char)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<E: JubjubEngine> FromStr for DecryptionKey<E> {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let buf = from_base58(s)?;
+        if buf.len() != ENCODED_LEN {
+            return Err("Invalid length of the decryption key");
+        }
+        if buf[0] != DECRYPTION_KEY_VERSION {
+            return Err("Unknown version of the decryption key");
+        }
+        if checksum(&buf[..33])[..] != buf[33..] {
+            return Err("Invalid checksum of the decryption key");
+        }
+
+        DecryptionKey::read(&buf[1..33]).map_err(|_| "The decryption key is not in the field")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProofGenerationKey;
+    use jubjub::curve::JubjubBls12;
+    use pairing::bls12_381::Bls12;
+
+    #[test]
+    fn test_decryption_key_to_from_str() {
+        let params = &JubjubBls12::new();
+        let dec_key = ProofGenerationKey::<Bls12>::from_seed(b"Alice                           ", params)
+            .into_decryption_key()
+            .unwrap();
+
+        let encoded = dec_key.to_string();
+        let decoded: DecryptionKey<Bls12> = encoded.parse().unwrap();
+        assert!(dec_key.0 == decoded.0);
+
+        // A typo is caught by the checksum.
+        let mut typo = encoded.into_bytes();
+        let last = typo.len() - 1;
+        typo[last] = if typo[last] == b'2' { b'3' } else { b'2' };
+        let typo = String::from_utf8(typo).unwrap();
+        assert_eq!(typo.parse::<DecryptionKey<Bls12>>().err(), Some("Invalid checksum of the decryption key"));
+    }
+
+    #[test]
+    fn test_base58_round_trip() {
+        let input = [0u8, 0, 1, 2, 255, 0, 58];
+        let encoded = to_base58(&input);
+        let s: String = encoded.iter().map(|d| ALPHABET[*d as usize] as char).collect();
+        assert!(s.starts_with("11"));
+        assert_eq!(from_base58(&s).unwrap(), input.to_vec());
+
+        assert!(from_base58("0OIl").is_err());
+    }
+
+    #[test]
+    fn test_reject_other_versions_and_lengths() {
+        let mut buf = vec![2u8; 33];
+        let checksum = checksum(&buf);
+        buf.extend_from_slice(&checksum);
+        let s: String = to_base58(&buf).iter().map(|d| ALPHABET[*d as usize] as char).collect();
+        assert_eq!(s.parse::<DecryptionKey<Bls12>>().err(), Some("Unknown version of the decryption key"));
+
+        // An encryption key in SS58 is 35 bytes long.
+        let s: String = to_base58(&[42u8; 35]).iter().map(|d| ALPHABET[*d as usize] as char).collect();
+        assert_eq!(s.parse::<DecryptionKey<Bls12>>().err(), Some("Invalid length of the decryption key"));
+    }
+}
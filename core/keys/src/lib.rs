@@ -15,6 +15,9 @@ mod std {
     pub use crate::alloc::borrow;
 }
 
+pub mod derive;
+pub mod encoding;
+
 use pairing::{
     PrimeField,
     PrimeFieldRepr,
@@ -23,7 +26,6 @@ use pairing::{
 use jubjub::{
         curve::{
             JubjubEngine,
-            JubjubParams,
             edwards,
             PrimeOrder,
             FixedGenerators,
@@ -115,6 +117,21 @@ pub struct RandomizedSigVk<E: JubjubEngine>(
     pub edwards::Point<E, PrimeOrder>
 );
 
+/// A diversifier of the keys, by which one decryption key has many encryption keys,
+/// e.g. one for each counterparty, which can't be linked to each other without the decryption key.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Diversifier(pub [u8; 11]);
+
+impl Diversifier {
+    /// The diversifier of the `index`-th diversified encryption key.
+    pub fn from_index(index: u64) -> Self {
+        let mut d = [0u8; 11];
+        d[..8].copy_from_slice(&index.to_le_bytes());
+        Diversifier(d)
+    }
+}
+
 /// Decryption key for decrypting transferred ammounts and balances
 #[derive(Clone)]
 pub struct DecryptionKey<E: JubjubEngine>(pub E::Fs);
@@ -135,6 +152,28 @@ impl<E: JubjubEngine> DecryptionKey<E> {
         self.0.into_repr().write_le(&mut writer)?;
         Ok(())
     }
+
+    /// The decryption key of the encryption key diversified by `diversifier`.
+    /// Unlike Sapling, the diversified encryption key is this key times the fixed generator,
+    /// so ciphertexts and transfers are the same for diversified encryption keys as for others.
+    pub fn diversify(&self, diversifier: &Diversifier) -> io::Result<Self> {
+        let mut preimage = [0u8; 32];
+        self.write(&mut preimage[..])?;
+
+        let mut h = Blake2s::with_params(32, &[], &[], KEY_DIVERSIFICATION_PERSONALIZATION);
+        h.update(&preimage);
+        h.update(&diversifier.0);
+        let mut h = h.finalize().as_ref().to_vec();
+
+        // Drop the most significant five bits, so it can be interpreted as a scalar.
+        h[31] &= 0b0000_0111;
+        let mut e = <E::Fs as PrimeField>::Repr::default();
+        e.read_le(&mut &h[..])?;
+
+        let fs = E::Fs::from_repr(e).map_err(|_| io::Error::NotInField)?;
+
+        Ok(DecryptionKey(fs))
+    }
 }
 
 impl<E: JubjubEngine> ProofGenerationKey<E> {
@@ -157,9 +196,11 @@ impl<E: JubjubEngine> ProofGenerationKey<E> {
     ) -> Self
     {
         ProofGenerationKey (
-            params
-                .generator(FixedGenerators::Diversifier)
-                .mul(spending_key.0.into_repr(), params)
+            edwards::Point::<E, PrimeOrder>::mul_fixed(
+                FixedGenerators::Diversifier,
+                spending_key.0.into_repr(),
+                params
+            )
         )
     }
 
@@ -170,7 +211,7 @@ impl<E: JubjubEngine> ProofGenerationKey<E> {
         params: &E::Params
     ) -> RandomizedSigVk<E> {
         let point = self.0.add(
-            &params.generator(FixedGenerators::Diversifier).mul(alpha, params),
+            &edwards::Point::<E, PrimeOrder>::mul_fixed(FixedGenerators::Diversifier, alpha, params),
             params
         );
 
@@ -204,9 +245,11 @@ impl<E: JubjubEngine> ProofGenerationKey<E> {
         params: &E::Params
     ) -> io::Result<EncryptionKey<E>>
     {
-        let pk_d = params
-            .generator(FixedGenerators::Diversifier)
-            .mul(self.into_decryption_key()?.0, params);
+        let pk_d = edwards::Point::<E, PrimeOrder>::mul_fixed(
+            FixedGenerators::Diversifier,
+            self.into_decryption_key()?.0,
+            params
+        );
 
         Ok(EncryptionKey(pk_d))
     }
@@ -266,13 +309,25 @@ impl<E: JubjubEngine> EncryptionKey<E> {
         params: &E::Params,
     ) -> Self
     {
-        let pk_d = params
-            .generator(FixedGenerators::Diversifier)
-            .mul(decryption_key.0, params);
+        let pk_d = edwards::Point::<E, PrimeOrder>::mul_fixed(
+            FixedGenerators::Diversifier,
+            decryption_key.0,
+            params
+        );
 
         EncryptionKey(pk_d)
     }
 
+    /// The encryption key diversified by `diversifier` of the decryption key.
+    pub fn from_diversifier(
+        decryption_key: &DecryptionKey<E>,
+        diversifier: &Diversifier,
+        params: &E::Params,
+    ) -> io::Result<Self>
+    {
+        Ok(Self::from_decryption_key(&decryption_key.diversify(diversifier)?, params))
+    }
+
     pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
         self.0.write(&mut writer)?;
         Ok(())
@@ -317,4 +372,20 @@ mod tests {
         let addr2 = EncryptionKey::<Bls12>::read(&mut v.as_slice(), params).unwrap();
         assert!(addr1 == addr2);
     }
+
+    #[test]
+    fn test_diversified_encryption_keys() {
+        let params = &JubjubBls12::new();
+        let dec_key = ProofGenerationKey::<Bls12>::from_seed(b"Alice                           ", params)
+            .into_decryption_key()
+            .unwrap();
+        let enc_key = EncryptionKey::from_decryption_key(&dec_key, params);
+
+        let enc_key_1 = EncryptionKey::from_diversifier(&dec_key, &Diversifier::from_index(1), params).unwrap();
+        let enc_key_2 = EncryptionKey::from_diversifier(&dec_key, &Diversifier::from_index(2), params).unwrap();
+        assert!(enc_key_1 != enc_key && enc_key_2 != enc_key && enc_key_1 != enc_key_2);
+
+        let dec_key_1 = dec_key.diversify(&Diversifier::from_index(1)).unwrap();
+        assert!(EncryptionKey::from_decryption_key(&dec_key_1, params) == enc_key_1);
+    }
 }
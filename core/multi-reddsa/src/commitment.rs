@@ -1,3 +1,7 @@
+#[cfg(feature = "std")]
+use ::std::vec::Vec;
+#[cfg(not(feature = "std"))]
+use crate::std::vec::Vec;
 use merlin::Transcript;
 use jubjub::curve::{JubjubEngine, edwards::Point, PrimeOrder};
 use jubjub::redjubjub::h_star;
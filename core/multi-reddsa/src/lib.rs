@@ -1,5 +1,20 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(non_snake_case)]
 
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+mod std {
+    pub use ::core::*;
+    pub use crate::alloc::vec;
+}
+
+#[cfg(feature = "std")]
+use ::std::vec::Vec;
+#[cfg(not(feature = "std"))]
+use crate::std::vec::Vec;
 use pairing::{io, Field, PrimeField, PrimeFieldRepr};
 use jubjub::curve::{JubjubEngine, edwards::Point, PrimeOrder, FixedGenerators, JubjubParams};
 use jubjub::redjubjub::{Signature, h_star};
@@ -13,6 +28,9 @@ mod commitment;
 mod cosigners;
 mod mr_pubkey;
 
+pub use commitment::{Commitment, SignerKeys};
+pub use mr_pubkey::MRPubkey;
+
 #[allow(non_snake_case)]
 pub struct CommitmentStage<'m, E: JubjubEngine>{
     msg: &'m [u8],
@@ -42,7 +60,7 @@ impl<'m, E: JubjubEngine> CommitmentStage<'m, E> {
         rng.fill_bytes(&mut t[..]);
         let r_i = h_star::<E>(&t[..], msg);
 
-        let R_i = params.generator(p_g).mul(r_i, params);
+        let R_i = params.generator(p_g).mul_ct(r_i, params);
         let commitment = Commitment::from_R(&R_i)?;
 
         let cosigners = (0..signer_keys.len())
@@ -2,7 +2,7 @@ mod g1 {
     use rand::{Rand, SeedableRng, XorShiftRng};
 
     use pairing::bls12_381::*;
-    use pairing::CurveProjective;
+    use pairing::{CurveAffine, CurveProjective, PrimeField};
 
     #[bench]
     fn bench_g1_mul_assign(b: &mut ::test::Bencher) {
@@ -60,13 +60,43 @@ mod g1 {
             tmp
         });
     }
+
+    #[bench]
+    fn bench_g1_multi_exp(b: &mut ::test::Bencher) {
+        const SAMPLES: usize = 100;
+
+        let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        let bases: Vec<G1Affine> = (0..SAMPLES).map(|_| G1::rand(&mut rng).into()).collect();
+        let scalars: Vec<FrRepr> = (0..SAMPLES).map(|_| Fr::rand(&mut rng).into_repr()).collect();
+
+        b.iter(|| G1::multi_exp(&bases, &scalars));
+    }
+
+    #[bench]
+    fn bench_g1_naive_multi_exp(b: &mut ::test::Bencher) {
+        const SAMPLES: usize = 100;
+
+        let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        let bases: Vec<G1Affine> = (0..SAMPLES).map(|_| G1::rand(&mut rng).into()).collect();
+        let scalars: Vec<FrRepr> = (0..SAMPLES).map(|_| Fr::rand(&mut rng).into_repr()).collect();
+
+        b.iter(|| {
+            let mut acc = G1::zero();
+            for (base, scalar) in bases.iter().zip(scalars.iter()) {
+                acc.add_assign(&base.mul(*scalar));
+            }
+            acc
+        });
+    }
 }
 
 mod g2 {
     use rand::{Rand, SeedableRng, XorShiftRng};
 
     use pairing::bls12_381::*;
-    use pairing::CurveProjective;
+    use pairing::{CurveAffine, CurveProjective, PrimeField};
 
     #[bench]
     fn bench_g2_mul_assign(b: &mut ::test::Bencher) {
@@ -124,4 +154,34 @@ mod g2 {
             tmp
         });
     }
+
+    #[bench]
+    fn bench_g2_multi_exp(b: &mut ::test::Bencher) {
+        const SAMPLES: usize = 100;
+
+        let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        let bases: Vec<G2Affine> = (0..SAMPLES).map(|_| G2::rand(&mut rng).into()).collect();
+        let scalars: Vec<FrRepr> = (0..SAMPLES).map(|_| Fr::rand(&mut rng).into_repr()).collect();
+
+        b.iter(|| G2::multi_exp(&bases, &scalars));
+    }
+
+    #[bench]
+    fn bench_g2_naive_multi_exp(b: &mut ::test::Bencher) {
+        const SAMPLES: usize = 100;
+
+        let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        let bases: Vec<G2Affine> = (0..SAMPLES).map(|_| G2::rand(&mut rng).into()).collect();
+        let scalars: Vec<FrRepr> = (0..SAMPLES).map(|_| Fr::rand(&mut rng).into_repr()).collect();
+
+        b.iter(|| {
+            let mut acc = G2::zero();
+            for (base, scalar) in bases.iter().zip(scalars.iter()) {
+                acc.add_assign(&base.mul(*scalar));
+            }
+            acc
+        });
+    }
 }
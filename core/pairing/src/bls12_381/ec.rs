@@ -140,7 +140,19 @@ macro_rules! curve_impl {
             }
 
             fn is_in_correct_subgroup_assuming_on_curve(&self) -> bool {
-                self.mul($scalarfield::char()).is_zero()
+                // Not `mul`, which may rely on the point being in the subgroup.
+                self.mul_bits(BitIterator::new($scalarfield::char())).is_zero()
+            }
+        }
+
+        impl $projective {
+            fn mul_bits<S: AsRef<[u64]>>(&self, bits: BitIterator<S>) -> $projective {
+                let mut res = $projective::zero();
+                for i in bits {
+                    res.double();
+                    if i { res.add_assign(self) }
+                }
+                res
             }
         }
 
@@ -172,8 +184,7 @@ macro_rules! curve_impl {
             }
 
             fn mul<S: Into<<Self::Scalar as PrimeField>::Repr>>(&self, by: S) -> $projective {
-                let bits = BitIterator::new(by.into());
-                self.mul_bits(bits)
+                self.mul_repr(by.into())
             }
 
             fn negate(&mut self) {
@@ -532,24 +543,7 @@ macro_rules! curve_impl {
             }
 
             fn mul_assign<S: Into<<Self::Scalar as PrimeField>::Repr>>(&mut self, other: S) {
-                let mut res = Self::zero();
-
-                let mut found_one = false;
-
-                for i in BitIterator::new(other.into())
-                {
-                    if found_one {
-                        res.double();
-                    } else {
-                        found_one = i;
-                    }
-
-                    if i {
-                        res.add_assign(self);
-                    }
-                }
-
-                *self = res;
+                *self = self.mul_repr(other.into());
             }
 
             fn into_affine(&self) -> $affine {
@@ -889,9 +883,70 @@ pub mod g1 {
         fn perform_pairing(&self, other: &G2Affine) -> Fq12 {
             super::super::Bls12::pairing(*self, *other)
         }
+
+        /// (x, y) -> (BETA * x, y), which multiplies the points of the prime order subgroup
+        /// by `LAMBDA = BLS_X^2 - 1`.
+        fn endomorphism(&self) -> Self {
+            let mut res = *self;
+            res.x.mul_assign(&super::super::fq::BETA);
+            res
+        }
+
+        /// Multiplies by `scalar` with the GLV method: `scalar * P = k1 * P + k2 * endomorphism(P)`,
+        /// where k1 and k2 are about half as long as `scalar`, so that a joint double-and-add
+        /// takes half the doublings. The point must be in the prime order subgroup.
+        fn mul_repr(&self, scalar: FrRepr) -> G1 {
+            let phi = self.endomorphism();
+            let mut sum = G1::from(*self);
+            sum.add_assign_mixed(&phi);
+            let sum = sum.into_affine();
+
+            let (k1, k2) = glv_decompose(scalar);
+            let mut res = G1::zero();
+            for (b1, b2) in BitIterator::new(k1).zip(BitIterator::new(k2)) {
+                res.double();
+                match (b1, b2) {
+                    (true, false) => res.add_assign_mixed(self),
+                    (false, true) => res.add_assign_mixed(&phi),
+                    (true, true) => res.add_assign_mixed(&sum),
+                    (false, false) => {},
+                }
+            }
+            res
+        }
+    }
+
+    /// BLS_X^2, of which `LAMBDA = BLS_X^2 - 1` is a cube root of unity in Fr.
+    const BLS_X_SQUARED: FrRepr = FrRepr([0x0000000100000000, 0xac45a4010001a402, 0, 0]);
+
+    /// Splits `scalar` into `k1 + k2 * LAMBDA`, both of at most 129 bits:
+    /// with `scalar = q * BLS_X^2 + r`, `k2 = q` and `k1 = q + r`, as `BLS_X^2 = LAMBDA + 1`.
+    fn glv_decompose(scalar: FrRepr) -> (FrRepr, FrRepr) {
+        let mut q = FrRepr::from(0);
+        let mut r = FrRepr::from(0);
+
+        // Long division, where `r < 2 * BLS_X^2` never overflows.
+        for bit in BitIterator::new(scalar) {
+            q.mul2();
+            r.mul2();
+            if bit {
+                r.add_nocarry(&FrRepr::from(1));
+            }
+            if r >= BLS_X_SQUARED {
+                r.sub_noborrow(&BLS_X_SQUARED);
+                q.add_nocarry(&FrRepr::from(1));
+            }
+        }
+
+        r.add_nocarry(&q);
+        (r, q)
     }
 
     impl G1 {
+        fn mul_repr(&self, scalar: FrRepr) -> G1 {
+            self.into_affine().mul_repr(scalar)
+        }
+
         fn empirical_recommended_wnaf_for_scalar(scalar: FrRepr) -> usize {
             let num_bits = scalar.num_bits() as usize;
 
@@ -944,6 +999,28 @@ pub mod g1 {
         }
     }
 
+    #[test]
+    fn g1_glv_multiplication() {
+        use rand::{SeedableRng, XorShiftRng};
+
+        let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let lambda = FrRepr([0x00000000ffffffff, 0xac45a4010001a402, 0, 0]);
+
+        for _ in 0..10 {
+            let p = G1::rand(&mut rng).into_affine();
+            assert_eq!(p.endomorphism(), p.mul_bits(BitIterator::new(lambda)).into_affine());
+
+            // Scalars, and representations beyond the modulus, agree with a double-and-add.
+            let s = Fr::rand(&mut rng).into_repr();
+            let max = FrRepr([0xffffffffffffffff; 4]);
+            for scalar in &[s, Fr::char(), max, FrRepr::from(0), FrRepr::from(1)] {
+                assert_eq!(p.mul(*scalar), p.mul_bits(BitIterator::new(*scalar)));
+            }
+        }
+
+        assert!(G1Affine::zero().mul(Fr::rand(&mut rng)).is_zero());
+    }
+
     #[test]
     fn g1_generator() {
         use SqrtField;
@@ -1278,7 +1355,7 @@ pub mod g1 {
 }
 
 pub mod g2 {
-    use super::super::{Bls12, Fq, Fq12, Fq2, FqRepr, Fr, FrRepr};
+    use super::super::{Bls12, Fq, Fq12, Fq2, FqRepr, Fr, FrRepr, G2_PREPARED_COEFFS_LEN};
     use super::g1::G1Affine;
     use rand::{Rand, Rng};
     use std::fmt;
@@ -1590,9 +1667,17 @@ pub mod g2 {
         fn perform_pairing(&self, other: &G1Affine) -> Fq12 {
             super::super::Bls12::pairing(*other, *self)
         }
+
+        fn mul_repr(&self, scalar: FrRepr) -> G2 {
+            self.mul_bits(BitIterator::new(scalar))
+        }
     }
 
     impl G2 {
+        fn mul_repr(&self, scalar: FrRepr) -> G2 {
+            self.mul_bits(BitIterator::new(scalar))
+        }
+
         fn empirical_recommended_wnaf_for_scalar(scalar: FrRepr) -> usize {
             let num_bits = scalar.num_bits() as usize;
 
@@ -1657,6 +1742,12 @@ pub mod g2 {
 
             let coeffs_len = BigEndian::read_u32(&buf) as usize;
 
+            // Reject lengths that `G2Prepared::from_affine` can never produce
+            // before allocating anything for them.
+            if coeffs_len != 0 && coeffs_len != G2_PREPARED_COEFFS_LEN {
+                return Err(::io::Error::InvalidData);
+            }
+
             let mut coeffs = ::std::vec::Vec::with_capacity(coeffs_len);
 
             for _ in 0..coeffs_len {
@@ -1677,6 +1768,11 @@ pub mod g2 {
                 _ => return Err(::io::Error::NotOnCurve)
             }
 
+            // Only the point at infinity is prepared without coefficients.
+            if res != coeffs.is_empty() {
+                return Err(::io::Error::InvalidData);
+            }
+
             Ok(G2Prepared{
                 coeffs: coeffs,
                 infinity: res,
@@ -99,6 +99,18 @@ pub const G1_GENERATOR_Y: Fq = Fq(FqRepr([
     0xbbc3efc5008a26a,
 ]));
 
+// A primitive cube root of unity, with which (x, y) -> (BETA * x, y) is the endomorphism of G1
+// multiplying its points by BLS_X^2 - 1.
+// BETA = 4002409555221667392624310435006688643935503118305586438271171395842971157480381377015405980053539358417135540939436
+pub const BETA: Fq = Fq(FqRepr([
+    0xcd03c9e48671f071,
+    0x5dab22461fcda5d2,
+    0x587042afd3851b95,
+    0x8eb60ebe01bacb9e,
+    0x3f97d6e83d050d2,
+    0x18f0206554638741,
+]));
+
 // Generator of G2
 // x = 3059144344244213709971259814753781636986470325476647558659373206291635324768958432433509563104347017837885763365758*u + 352701069587466618187139116011060144890029952792775240219908644239793785735715026873347600343865175952761926303160
 // y = 927553665492332455747201965776037880757740193453592970025027978793976877002675564980949289727957565575433344219582*u + 1985150602287291935568054521177171638300868978215655730859378665066344726373823718423869104263333984641494340347905
@@ -24,6 +24,11 @@ use super::{BitIterator, CurveAffine, Engine, Field};
 const BLS_X: u64 = 0xd201000000010000;
 const BLS_X_IS_NEGATIVE: bool = true;
 
+// The number of line function coefficients of a prepared G2 point which is not
+// the point at infinity: one doubling step per bit of BLS_X >> 1 below the most
+// significant one, one addition step per set bit among them, and a final doubling step.
+pub(crate) const G2_PREPARED_COEFFS_LEN: usize = 68;
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Bls12;
 
@@ -358,6 +363,17 @@ impl G2Prepared {
     }
 }
 
+#[test]
+fn test_g2_prepared_coeffs_len() {
+    let prepared = G2Affine::one().prepare();
+    assert_eq!(prepared.coeffs.len(), G2_PREPARED_COEFFS_LEN);
+    assert!(!prepared.infinity);
+
+    let prepared = G2Affine::zero().prepare();
+    assert!(prepared.coeffs.is_empty());
+    assert!(prepared.infinity);
+}
+
 #[test]
 fn bls12_engine_tests() {
     ::tests::engine::engine_tests::<Bls12>();
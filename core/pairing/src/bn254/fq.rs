@@ -0,0 +1,1713 @@
+use super::fq2::Fq2;
+use std::cmp::Ordering;
+use {Field, PrimeField, PrimeFieldDecodingError, PrimeFieldRepr, SqrtField};
+
+// q = 21888242871839275222246405745257275088696311157297823662689037894645226208583
+const MODULUS: FqRepr = FqRepr([
+    0x3c208c16d87cfd47,
+    0x97816a916871ca8d,
+    0xb85045b68181585d,
+    0x30644e72e131a029,
+]);
+
+// The number of bits needed to represent the modulus.
+const MODULUS_BITS: u32 = 254;
+
+// The number of bits that must be shaved from the beginning of
+// the representation when randomly sampling.
+const REPR_SHAVE_BITS: u32 = 2;
+
+// R = 2**256 % q
+const R: FqRepr = FqRepr([
+    0xd35d438dc58f0d9d,
+    0xa78eb28f5c70b3d,
+    0x666ea36f7879462c,
+    0xe0a77c19a07df2f,
+]);
+
+// R2 = R^2 % q
+const R2: FqRepr = FqRepr([
+    0xf32cfc5b538afa89,
+    0xb5e71911d44501fb,
+    0x47ab1eff0a417ff6,
+    0x6d89f71cab8351f,
+]);
+
+// INV = -(q^{-1} mod 2^64) mod 2^64
+const INV: u64 = 0x87d20782e4866389;
+
+// GENERATOR = 3 (multiplicative generator of q-1 order, that is also quadratic nonresidue)
+const GENERATOR: FqRepr = FqRepr([
+    0x7a17caa950ad28d7,
+    0x1f6ac17ae15521b9,
+    0x334bea4e696bd284,
+    0x2a1f6744ce179d8e,
+]);
+
+// 2^s * t = MODULUS - 1 with t odd
+const S: u32 = 1;
+
+// 2^s root of unity computed by GENERATOR^t
+const ROOT_OF_UNITY: FqRepr = FqRepr([
+    0x68c3488912edefaa,
+    0x8d087f6872aabf4f,
+    0x51e1a24709081231,
+    0x2259d6b14729c0fa,
+]);
+
+// B coefficient of BN254 curve, 3.
+pub const B_COEFF: Fq = Fq(FqRepr([
+    0x7a17caa950ad28d7,
+    0x1f6ac17ae15521b9,
+    0x334bea4e696bd284,
+    0x2a1f6744ce179d8e,
+]));
+
+// B coefficient of the sextic twist of BN254, 3 / (u + 9).
+pub const B_COEFF_FQ2: Fq2 = Fq2 {
+    c0: Fq(FqRepr([
+        0x3bf938e377b802a8,
+        0x20b1b273633535d,
+        0x26b7edf049755260,
+        0x2514c6324384a86d,
+    ])),
+    c1: Fq(FqRepr([
+        0x38e7ecccd1dcff67,
+        0x65f0b37d93ce0d3e,
+        0xd749d0dd22ac00aa,
+        0x141b9ce4a688d4d,
+    ])),
+};
+
+// The generator of G1 is (1, 2), and the generator of G2 is the one used by
+// the precompiled contracts of Ethereum (EIP-197).
+
+// Generator of G1
+// x = 1
+// y = 2
+pub const G1_GENERATOR_X: Fq = Fq(FqRepr([
+    0xd35d438dc58f0d9d,
+    0xa78eb28f5c70b3d,
+    0x666ea36f7879462c,
+    0xe0a77c19a07df2f,
+]));
+pub const G1_GENERATOR_Y: Fq = Fq(FqRepr([
+    0xa6ba871b8b1e1b3a,
+    0x14f1d651eb8e167b,
+    0xccdd46def0f28c58,
+    0x1c14ef83340fbe5e,
+]));
+
+// Generator of G2
+// x = 11559732032986387107991004021392285783925812861821192530917403151452391805634*u + 10857046999023057135944570762232829481370756359578518086990519993285655852781
+// y = 4082367875863433681332203403145435568316851327593401208105741076214120093531*u + 8495653923123431417604973247489272438418190587263600148770280649306958101930
+pub const G2_GENERATOR_X_C0: Fq = Fq(FqRepr([
+    0x8e83b5d102bc2026,
+    0xdceb1935497b0172,
+    0xfbb8264797811adf,
+    0x19573841af96503b,
+]));
+pub const G2_GENERATOR_X_C1: Fq = Fq(FqRepr([
+    0xafb4737da84c6140,
+    0x6043dd5a5802d8c4,
+    0x9e950fc52a02f86,
+    0x14fef0833aea7b6b,
+]));
+pub const G2_GENERATOR_Y_C0: Fq = Fq(FqRepr([
+    0x619dfa9d886be9f6,
+    0xfe7fd297f59e9b78,
+    0xff9e1a62231b7dfe,
+    0x28fd7eebae9e4206,
+]));
+pub const G2_GENERATOR_Y_C1: Fq = Fq(FqRepr([
+    0x64095b56c71856ee,
+    0xdc57f922327d3cbb,
+    0x55f935be33351076,
+    0xda4a0e693fd6482,
+]));
+
+// Coefficients for the Frobenius automorphism.
+pub const FROBENIUS_COEFF_FQ2_C1: [Fq; 2] = [
+    // Fq(-1)**(((q^0) - 1) / 2)
+    Fq(FqRepr([
+        0xd35d438dc58f0d9d,
+        0xa78eb28f5c70b3d,
+        0x666ea36f7879462c,
+        0xe0a77c19a07df2f,
+    ])),
+    // Fq(-1)**(((q^1) - 1) / 2)
+    Fq(FqRepr([
+        0x68c3488912edefaa,
+        0x8d087f6872aabf4f,
+        0x51e1a24709081231,
+        0x2259d6b14729c0fa,
+    ])),
+];
+
+pub const FROBENIUS_COEFF_FQ6_C1: [Fq2; 6] = [
+    // Fq2(u + 9)**(((q^0) - 1) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xd35d438dc58f0d9d,
+            0xa78eb28f5c70b3d,
+            0x666ea36f7879462c,
+            0xe0a77c19a07df2f,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 9)**(((q^1) - 1) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xb5773b104563ab30,
+            0x347f91c8a9aa6454,
+            0x7a007127242e0991,
+            0x1956bcd8118214ec,
+        ])),
+        c1: Fq(FqRepr([
+            0x6e849f1ea0aa4757,
+            0xaa1c7b6d89f89141,
+            0xb6e713cdfae0ca3a,
+            0x26694fbb4e82ebc3,
+        ])),
+    },
+    // Fq2(u + 9)**(((q^2) - 1) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x3350c88e13e80b9c,
+            0x7dce557cdb5e56b9,
+            0x6001b4b8b615564a,
+            0x2682e617020217e0,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 9)**(((q^3) - 1) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xc9af22f716ad6bad,
+            0xb311782a4aa662b2,
+            0x19eeaf64e248c7f4,
+            0x20273e77e3439f82,
+        ])),
+        c1: Fq(FqRepr([
+            0xacc02860f7ce93ac,
+            0x3933d5817ba76b4c,
+            0x69e6188b446c8467,
+            0xa46036d4417cc55,
+        ])),
+    },
+    // Fq2(u + 9)**(((q^4) - 1) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x71930c11d782e155,
+            0xa6bb947cffbe3323,
+            0xaa303344d4741444,
+            0x2c3b3f0d26594943,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 9)**(((q^5) - 1) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xf91aba2654e8e3b1,
+            0x4771cb2fdc92ce12,
+            0xdcb16ae0fc8bdf35,
+            0x274aa195cd9d8be4,
+        ])),
+        c1: Fq(FqRepr([
+            0x5cfc50ae18811f8b,
+            0x4bb28433cb43988c,
+            0x4fd35f13c3b56219,
+            0x301949bd2fc8883a,
+        ])),
+    },
+];
+
+pub const FROBENIUS_COEFF_FQ6_C2: [Fq2; 6] = [
+    // Fq2(u + 9)**(((2q^0) - 2) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xd35d438dc58f0d9d,
+            0xa78eb28f5c70b3d,
+            0x666ea36f7879462c,
+            0xe0a77c19a07df2f,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 9)**(((2q^1) - 2) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x7361d77f843abe92,
+            0xa5bb2bd3273411fb,
+            0x9c941f314b3e2399,
+            0x15df9cddbb9fd3ec,
+        ])),
+        c1: Fq(FqRepr([
+            0x5dddfd154bd8c949,
+            0x62cb29a5a4445b60,
+            0x37bc870a0c7dd2b9,
+            0x24830a9d3171f0fd,
+        ])),
+    },
+    // Fq2(u + 9)**(((2q^2) - 2) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x71930c11d782e155,
+            0xa6bb947cffbe3323,
+            0xaa303344d4741444,
+            0x2c3b3f0d26594943,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 9)**(((2q^3) - 2) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x448a93a57b6762df,
+            0xbfd62df528fdeadf,
+            0xd858f5d00e9bd47a,
+            0x6b03d4d3476ec58,
+        ])),
+        c1: Fq(FqRepr([
+            0x2b19daf4bcc936d1,
+            0xa1a54e7a56f4299f,
+            0xb533eee05adeaef1,
+            0x170c812b84dda0b2,
+        ])),
+    },
+    // Fq2(u + 9)**(((2q^4) - 2) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x3350c88e13e80b9c,
+            0x7dce557cdb5e56b9,
+            0x6001b4b8b615564a,
+            0x2682e617020217e0,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 9)**(((2q^5) - 2) / 3)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x843420f1d8dadbd6,
+            0x31f010c9183fcdb2,
+            0x436330b527a76049,
+            0x13d47447f11adfe4,
+        ])),
+        c1: Fq(FqRepr([
+            0xef494023a857fa74,
+            0x2a925d02d5ab101a,
+            0x83b015829ba62f10,
+            0x2539111d0c13aea3,
+        ])),
+    },
+];
+
+// non_residue^((modulus^i-1)/6) for i=0,...,11
+pub const FROBENIUS_COEFF_FQ12_C1: [Fq2; 12] = [
+    // Fq2(u + 9)**(((q^0) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xd35d438dc58f0d9d,
+            0xa78eb28f5c70b3d,
+            0x666ea36f7879462c,
+            0xe0a77c19a07df2f,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 9)**(((q^1) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xaf9ba69633144907,
+            0xca6b1d7387afb78a,
+            0x11bded5ef08a2087,
+            0x2f34d751a1f3a7c,
+        ])),
+        c1: Fq(FqRepr([
+            0xa222ae234c492d72,
+            0xd00f02a4565de15b,
+            0xdc2ff3a253dfc926,
+            0x10a75716b3899551,
+        ])),
+    },
+    // Fq2(u + 9)**(((q^2) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xca8d800500fa1bf2,
+            0xf0c5d61468b39769,
+            0xe201271ad0d4418,
+            0x4290f65bad856e6,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 9)**(((q^3) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x365316184e46d97d,
+            0xaf7129ed4c96d9f,
+            0x659da72fca1009b5,
+            0x8116d8983a20d23,
+        ])),
+        c1: Fq(FqRepr([
+            0xb1df4af7c39c1939,
+            0x3d9f02878a73bf7f,
+            0x9b2220928caf0ae0,
+            0x26684515eff054a6,
+        ])),
+    },
+    // Fq2(u + 9)**(((q^4) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x3350c88e13e80b9c,
+            0x7dce557cdb5e56b9,
+            0x6001b4b8b615564a,
+            0x2682e617020217e0,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 9)**(((q^5) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x86b76f821b329076,
+            0x408bf52b4d19b614,
+            0x53dfb9d0d985e92d,
+            0x51e20146982d2a7,
+        ])),
+        c1: Fq(FqRepr([
+            0xfbc9cd47752ebc7,
+            0x6d8fffe33415de24,
+            0xbef22cf038cf41b9,
+            0x15c0edff3c66bf54,
+        ])),
+    },
+    // Fq2(u + 9)**(((q^6) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x68c3488912edefaa,
+            0x8d087f6872aabf4f,
+            0x51e1a24709081231,
+            0x2259d6b14729c0fa,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 9)**(((q^7) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x8c84e580a568b440,
+            0xcd164d1de0c21302,
+            0xa692585790f737d5,
+            0x2d7100fdc71265ad,
+        ])),
+        c1: Fq(FqRepr([
+            0x99fdddf38c33cfd5,
+            0xc77267ed1213e931,
+            0xdc2052142da18f36,
+            0x1fbcf75c2da80ad7,
+        ])),
+    },
+    // Fq2(u + 9)**(((q^8) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x71930c11d782e155,
+            0xa6bb947cffbe3323,
+            0xaa303344d4741444,
+            0x2c3b3f0d26594943,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 9)**(((q^9) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x5cd75fe8a3623ca,
+            0x8c8a57f293a85cee,
+            0x52b29e86b7714ea8,
+            0x2852e0e95d8f9306,
+        ])),
+        c1: Fq(FqRepr([
+            0x8a41411f14e0e40e,
+            0x59e26809ddfe0b0d,
+            0x1d2e2523f4d24d7d,
+            0x9fc095cf1414b83,
+        ])),
+    },
+    // Fq2(u + 9)**(((q^10) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0x8cfc388c494f1ab,
+            0x19b315148d1373d4,
+            0x584e90fdcb6c0213,
+            0x9e1685bdf2f8849,
+        ])),
+        c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+    },
+    // Fq2(u + 9)**(((q^11) - 1) / 6)
+    Fq2 {
+        c0: Fq(FqRepr([
+            0xb5691c94bd4a6cd1,
+            0x56f575661b581478,
+            0x64708be5a7fb6f30,
+            0x2b462e5e77aecd82,
+        ])),
+        c1: Fq(FqRepr([
+            0x2c63ef42612a1180,
+            0x29f16aae345bec69,
+            0xf95e18c648b216a4,
+            0x1aa36073a4cae0d4,
+        ])),
+    },
+];
+
+// Coefficients of the untwist-Frobenius-twist endomorphism of G2, which are used
+// to add the points q^1 * Q and -q^2 * Q in the final steps of the Miller loop.
+// Fq2(u + 9)**((q - 1) / 3)
+pub const TWIST_MUL_BY_Q_X: Fq2 = Fq2 {
+    c0: Fq(FqRepr([
+        0xb5773b104563ab30,
+        0x347f91c8a9aa6454,
+        0x7a007127242e0991,
+        0x1956bcd8118214ec,
+    ])),
+    c1: Fq(FqRepr([
+        0x6e849f1ea0aa4757,
+        0xaa1c7b6d89f89141,
+        0xb6e713cdfae0ca3a,
+        0x26694fbb4e82ebc3,
+    ])),
+};
+
+// Fq2(u + 9)**((q - 1) / 2)
+pub const TWIST_MUL_BY_Q_Y: Fq2 = Fq2 {
+    c0: Fq(FqRepr([
+        0xe4bbdd0c2936b629,
+        0xbb30f162e133bacb,
+        0x31a9d1b6f9645366,
+        0x253570bea500f8dd,
+    ])),
+    c1: Fq(FqRepr([
+        0xa1d77ce45ffe77c7,
+        0x7affd117826d1db,
+        0x6d16bd27bb7edc6b,
+        0x2c87200285defecc,
+    ])),
+};
+
+// Fq2(u + 9)**((q^2 - 1) / 3)
+pub const TWIST_MUL_BY_Q2_X: Fq2 = Fq2 {
+    c0: Fq(FqRepr([
+        0x3350c88e13e80b9c,
+        0x7dce557cdb5e56b9,
+        0x6001b4b8b615564a,
+        0x2682e617020217e0,
+    ])),
+    c1: Fq(FqRepr([0x0, 0x0, 0x0, 0x0])),
+};
+
+// 1 / 2
+pub const TWO_INV: Fq = Fq(FqRepr([
+    0x87bee7d24f060572,
+    0xd0fd2add2f1c6ae5,
+    0x8f5f7492fcfd4f44,
+    0x1f37631a3d9cbfac,
+]));
+
+// -((2**256) mod q) mod q
+pub const NEGATIVE_ONE: Fq = Fq(FqRepr([
+    0x68c3488912edefaa,
+    0x8d087f6872aabf4f,
+    0x51e1a24709081231,
+    0x2259d6b14729c0fa,
+]));
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct FqRepr(pub [u64; 4]);
+
+impl ::rand::Rand for FqRepr {
+    #[inline(always)]
+    fn rand<R: ::rand::Rng>(rng: &mut R) -> Self {
+        FqRepr(rng.gen())
+    }
+}
+
+impl ::std::fmt::Display for FqRepr {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        (write!(f, "0x"))?;
+        for i in self.0.iter().rev() {
+            (write!(f, "{:016x}", *i))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl AsRef<[u64]> for FqRepr {
+    #[inline(always)]
+    fn as_ref(&self) -> &[u64] {
+        &self.0
+    }
+}
+
+impl AsMut<[u64]> for FqRepr {
+    #[inline(always)]
+    fn as_mut(&mut self) -> &mut [u64] {
+        &mut self.0
+    }
+}
+
+impl From<u64> for FqRepr {
+    #[inline(always)]
+    fn from(val: u64) -> FqRepr {
+        let mut repr = Self::default();
+        repr.0[0] = val;
+        repr
+    }
+}
+
+impl Ord for FqRepr {
+    #[inline(always)]
+    fn cmp(&self, other: &FqRepr) -> Ordering {
+        for (a, b) in self.0.iter().rev().zip(other.0.iter().rev()) {
+            if a < b {
+                return Ordering::Less;
+            } else if a > b {
+                return Ordering::Greater;
+            }
+        }
+
+        Ordering::Equal
+    }
+}
+
+impl PartialOrd for FqRepr {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &FqRepr) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PrimeFieldRepr for FqRepr {
+    #[inline(always)]
+    fn is_odd(&self) -> bool {
+        self.0[0] & 1 == 1
+    }
+
+    #[inline(always)]
+    fn is_even(&self) -> bool {
+        !self.is_odd()
+    }
+
+    #[inline(always)]
+    fn is_zero(&self) -> bool {
+        self.0.iter().all(|&e| e == 0)
+    }
+
+    #[inline(always)]
+    fn shr(&mut self, mut n: u32) {
+        if n >= 64 * 4 {
+            *self = Self::from(0);
+            return;
+        }
+
+        while n >= 64 {
+            let mut t = 0;
+            for i in self.0.iter_mut().rev() {
+                ::std::mem::swap(&mut t, i);
+            }
+            n -= 64;
+        }
+
+        if n > 0 {
+            let mut t = 0;
+            for i in self.0.iter_mut().rev() {
+                let t2 = *i << (64 - n);
+                *i >>= n;
+                *i |= t;
+                t = t2;
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn div2(&mut self) {
+        let mut t = 0;
+        for i in self.0.iter_mut().rev() {
+            let t2 = *i << 63;
+            *i >>= 1;
+            *i |= t;
+            t = t2;
+        }
+    }
+
+    #[inline(always)]
+    fn mul2(&mut self) {
+        let mut last = 0;
+        for i in &mut self.0 {
+            let tmp = *i >> 63;
+            *i <<= 1;
+            *i |= last;
+            last = tmp;
+        }
+    }
+
+    #[inline(always)]
+    fn shl(&mut self, mut n: u32) {
+        if n >= 64 * 4 {
+            *self = Self::from(0);
+            return;
+        }
+
+        while n >= 64 {
+            let mut t = 0;
+            for i in &mut self.0 {
+                ::std::mem::swap(&mut t, i);
+            }
+            n -= 64;
+        }
+
+        if n > 0 {
+            let mut t = 0;
+            for i in &mut self.0 {
+                let t2 = *i >> (64 - n);
+                *i <<= n;
+                *i |= t;
+                t = t2;
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn num_bits(&self) -> u32 {
+        let mut ret = (4 as u32) * 64;
+        for i in self.0.iter().rev() {
+            let leading = i.leading_zeros();
+            ret -= leading;
+            if leading != 64 {
+                break;
+            }
+        }
+
+        ret
+    }
+
+    #[inline(always)]
+    fn add_nocarry(&mut self, other: &FqRepr) {
+        let mut carry = 0;
+
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a = ::adc(*a, *b, &mut carry);
+        }
+    }
+
+    #[inline(always)]
+    fn sub_noborrow(&mut self, other: &FqRepr) {
+        let mut borrow = 0;
+
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a = ::sbb(*a, *b, &mut borrow);
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Fq(pub FqRepr);
+
+/// `Fq` elements are ordered lexicographically.
+impl Ord for Fq {
+    #[inline(always)]
+    fn cmp(&self, other: &Fq) -> Ordering {
+        self.into_repr().cmp(&other.into_repr())
+    }
+}
+
+impl PartialOrd for Fq {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &Fq) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl ::std::fmt::Display for Fq {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "Fq({})", self.into_repr())
+    }
+}
+
+impl ::rand::Rand for Fq {
+    fn rand<R: ::rand::Rng>(rng: &mut R) -> Self {
+        loop {
+            let mut tmp = Fq(FqRepr::rand(rng));
+
+            // Mask away the unused bits at the beginning.
+            tmp.0.as_mut()[3] &= 0xffffffffffffffff >> REPR_SHAVE_BITS;
+
+            if tmp.is_valid() {
+                return tmp;
+            }
+        }
+    }
+}
+
+impl From<Fq> for FqRepr {
+    fn from(e: Fq) -> FqRepr {
+        e.into_repr()
+    }
+}
+
+impl PrimeField for Fq {
+    type Repr = FqRepr;
+
+    fn from_repr(r: FqRepr) -> Result<Fq, PrimeFieldDecodingError> {
+        let mut r = Fq(r);
+        if r.is_valid() {
+            r.mul_assign(&Fq(R2));
+
+            Ok(r)
+        } else {
+            Err(PrimeFieldDecodingError::NotInField(format!("{}", r.0)))
+        }
+    }
+
+    fn into_repr(&self) -> FqRepr {
+        let mut r = *self;
+        r.mont_reduce(
+            (self.0).0[0],
+            (self.0).0[1],
+            (self.0).0[2],
+            (self.0).0[3],
+            0,
+            0,
+            0,
+            0,
+        );
+        r.0
+    }
+
+    fn char() -> FqRepr {
+        MODULUS
+    }
+
+    const NUM_BITS: u32 = MODULUS_BITS;
+
+    const CAPACITY: u32 = Self::NUM_BITS - 1;
+
+    fn multiplicative_generator() -> Self {
+        Fq(GENERATOR)
+    }
+
+    const S: u32 = S;
+
+    fn root_of_unity() -> Self {
+        Fq(ROOT_OF_UNITY)
+    }
+}
+
+impl Field for Fq {
+    #[inline]
+    fn zero() -> Self {
+        Fq(FqRepr::from(0))
+    }
+
+    #[inline]
+    fn one() -> Self {
+        Fq(R)
+    }
+
+    #[inline]
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    #[inline]
+    fn add_assign(&mut self, other: &Fq) {
+        // This cannot exceed the backing capacity.
+        self.0.add_nocarry(&other.0);
+
+        // However, it may need to be reduced.
+        self.reduce();
+    }
+
+    #[inline]
+    fn double(&mut self) {
+        // This cannot exceed the backing capacity.
+        self.0.mul2();
+
+        // However, it may need to be reduced.
+        self.reduce();
+    }
+
+    #[inline]
+    fn sub_assign(&mut self, other: &Fq) {
+        // If `other` is larger than `self`, we'll need to add the modulus to self first.
+        if other.0 > self.0 {
+            self.0.add_nocarry(&MODULUS);
+        }
+
+        self.0.sub_noborrow(&other.0);
+    }
+
+    #[inline]
+    fn negate(&mut self) {
+        if !self.is_zero() {
+            let mut tmp = MODULUS;
+            tmp.sub_noborrow(&self.0);
+            self.0 = tmp;
+        }
+    }
+
+    fn inverse(&self) -> Option<Self> {
+        if self.is_zero() {
+            None
+        } else {
+            // Guajardo Kumar Paar Pelzl
+            // Efficient Software-Implementation of Finite Fields with Applications to Cryptography
+            // Algorithm 16 (BEA for Inversion in Fp)
+
+            let one = FqRepr::from(1);
+
+            let mut u = self.0;
+            let mut v = MODULUS;
+            let mut b = Fq(R2); // Avoids unnecessary reduction step.
+            let mut c = Self::zero();
+
+            while u != one && v != one {
+                while u.is_even() {
+                    u.div2();
+
+                    if b.0.is_even() {
+                        b.0.div2();
+                    } else {
+                        b.0.add_nocarry(&MODULUS);
+                        b.0.div2();
+                    }
+                }
+
+                while v.is_even() {
+                    v.div2();
+
+                    if c.0.is_even() {
+                        c.0.div2();
+                    } else {
+                        c.0.add_nocarry(&MODULUS);
+                        c.0.div2();
+                    }
+                }
+
+                if v < u {
+                    u.sub_noborrow(&v);
+                    b.sub_assign(&c);
+                } else {
+                    v.sub_noborrow(&u);
+                    c.sub_assign(&b);
+                }
+            }
+
+            if u == one {
+                Some(b)
+            } else {
+                Some(c)
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn frobenius_map(&mut self, _: usize) {
+        // This has no effect in a prime field.
+    }
+
+    #[inline]
+    fn mul_assign(&mut self, other: &Fq) {
+        let mut carry = 0;
+        let r0 = ::mac_with_carry(0, (self.0).0[0], (other.0).0[0], &mut carry);
+        let r1 = ::mac_with_carry(0, (self.0).0[0], (other.0).0[1], &mut carry);
+        let r2 = ::mac_with_carry(0, (self.0).0[0], (other.0).0[2], &mut carry);
+        let r3 = ::mac_with_carry(0, (self.0).0[0], (other.0).0[3], &mut carry);
+        let r4 = carry;
+        let mut carry = 0;
+        let r1 = ::mac_with_carry(r1, (self.0).0[1], (other.0).0[0], &mut carry);
+        let r2 = ::mac_with_carry(r2, (self.0).0[1], (other.0).0[1], &mut carry);
+        let r3 = ::mac_with_carry(r3, (self.0).0[1], (other.0).0[2], &mut carry);
+        let r4 = ::mac_with_carry(r4, (self.0).0[1], (other.0).0[3], &mut carry);
+        let r5 = carry;
+        let mut carry = 0;
+        let r2 = ::mac_with_carry(r2, (self.0).0[2], (other.0).0[0], &mut carry);
+        let r3 = ::mac_with_carry(r3, (self.0).0[2], (other.0).0[1], &mut carry);
+        let r4 = ::mac_with_carry(r4, (self.0).0[2], (other.0).0[2], &mut carry);
+        let r5 = ::mac_with_carry(r5, (self.0).0[2], (other.0).0[3], &mut carry);
+        let r6 = carry;
+        let mut carry = 0;
+        let r3 = ::mac_with_carry(r3, (self.0).0[3], (other.0).0[0], &mut carry);
+        let r4 = ::mac_with_carry(r4, (self.0).0[3], (other.0).0[1], &mut carry);
+        let r5 = ::mac_with_carry(r5, (self.0).0[3], (other.0).0[2], &mut carry);
+        let r6 = ::mac_with_carry(r6, (self.0).0[3], (other.0).0[3], &mut carry);
+        let r7 = carry;
+        self.mont_reduce(r0, r1, r2, r3, r4, r5, r6, r7);
+    }
+
+    #[inline]
+    fn square(&mut self) {
+        let mut carry = 0;
+        let r1 = ::mac_with_carry(0, (self.0).0[0], (self.0).0[1], &mut carry);
+        let r2 = ::mac_with_carry(0, (self.0).0[0], (self.0).0[2], &mut carry);
+        let r3 = ::mac_with_carry(0, (self.0).0[0], (self.0).0[3], &mut carry);
+        let r4 = carry;
+        let mut carry = 0;
+        let r3 = ::mac_with_carry(r3, (self.0).0[1], (self.0).0[2], &mut carry);
+        let r4 = ::mac_with_carry(r4, (self.0).0[1], (self.0).0[3], &mut carry);
+        let r5 = carry;
+        let mut carry = 0;
+        let r5 = ::mac_with_carry(r5, (self.0).0[2], (self.0).0[3], &mut carry);
+        let r6 = carry;
+
+        let r7 = r6 >> 63;
+        let r6 = (r6 << 1) | (r5 >> 63);
+        let r5 = (r5 << 1) | (r4 >> 63);
+        let r4 = (r4 << 1) | (r3 >> 63);
+        let r3 = (r3 << 1) | (r2 >> 63);
+        let r2 = (r2 << 1) | (r1 >> 63);
+        let r1 = r1 << 1;
+
+        let mut carry = 0;
+        let r0 = ::mac_with_carry(0, (self.0).0[0], (self.0).0[0], &mut carry);
+        let r1 = ::adc(r1, 0, &mut carry);
+        let r2 = ::mac_with_carry(r2, (self.0).0[1], (self.0).0[1], &mut carry);
+        let r3 = ::adc(r3, 0, &mut carry);
+        let r4 = ::mac_with_carry(r4, (self.0).0[2], (self.0).0[2], &mut carry);
+        let r5 = ::adc(r5, 0, &mut carry);
+        let r6 = ::mac_with_carry(r6, (self.0).0[3], (self.0).0[3], &mut carry);
+        let r7 = ::adc(r7, 0, &mut carry);
+        self.mont_reduce(r0, r1, r2, r3, r4, r5, r6, r7);
+    }
+}
+
+impl Fq {
+    /// Determines if the element is really in the field. This is only used
+    /// internally.
+    #[inline(always)]
+    fn is_valid(&self) -> bool {
+        self.0 < MODULUS
+    }
+
+    /// Subtracts the modulus from this element if this element is not in the
+    /// field. Only used internally.
+    #[inline(always)]
+    fn reduce(&mut self) {
+        if !self.is_valid() {
+            self.0.sub_noborrow(&MODULUS);
+        }
+    }
+
+    #[inline(always)]
+    fn mont_reduce(
+        &mut self,
+        r0: u64,
+        mut r1: u64,
+        mut r2: u64,
+        mut r3: u64,
+        mut r4: u64,
+        mut r5: u64,
+        mut r6: u64,
+        mut r7: u64,
+    ) {
+        // The Montgomery reduction here is based on Algorithm 14.32 in
+        // Handbook of Applied Cryptography
+        // <http://cacr.uwaterloo.ca/hac/about/chap14.pdf>.
+
+        let k = r0.wrapping_mul(INV);
+        let mut carry = 0;
+        ::mac_with_carry(r0, k, MODULUS.0[0], &mut carry);
+        r1 = ::mac_with_carry(r1, k, MODULUS.0[1], &mut carry);
+        r2 = ::mac_with_carry(r2, k, MODULUS.0[2], &mut carry);
+        r3 = ::mac_with_carry(r3, k, MODULUS.0[3], &mut carry);
+        r4 = ::adc(r4, 0, &mut carry);
+        let carry2 = carry;
+        let k = r1.wrapping_mul(INV);
+        let mut carry = 0;
+        ::mac_with_carry(r1, k, MODULUS.0[0], &mut carry);
+        r2 = ::mac_with_carry(r2, k, MODULUS.0[1], &mut carry);
+        r3 = ::mac_with_carry(r3, k, MODULUS.0[2], &mut carry);
+        r4 = ::mac_with_carry(r4, k, MODULUS.0[3], &mut carry);
+        r5 = ::adc(r5, carry2, &mut carry);
+        let carry2 = carry;
+        let k = r2.wrapping_mul(INV);
+        let mut carry = 0;
+        ::mac_with_carry(r2, k, MODULUS.0[0], &mut carry);
+        r3 = ::mac_with_carry(r3, k, MODULUS.0[1], &mut carry);
+        r4 = ::mac_with_carry(r4, k, MODULUS.0[2], &mut carry);
+        r5 = ::mac_with_carry(r5, k, MODULUS.0[3], &mut carry);
+        r6 = ::adc(r6, carry2, &mut carry);
+        let carry2 = carry;
+        let k = r3.wrapping_mul(INV);
+        let mut carry = 0;
+        ::mac_with_carry(r3, k, MODULUS.0[0], &mut carry);
+        r4 = ::mac_with_carry(r4, k, MODULUS.0[1], &mut carry);
+        r5 = ::mac_with_carry(r5, k, MODULUS.0[2], &mut carry);
+        r6 = ::mac_with_carry(r6, k, MODULUS.0[3], &mut carry);
+        r7 = ::adc(r7, carry2, &mut carry);
+        (self.0).0[0] = r4;
+        (self.0).0[1] = r5;
+        (self.0).0[2] = r6;
+        (self.0).0[3] = r7;
+        self.reduce();
+    }
+}
+
+
+impl SqrtField for Fq {
+    fn legendre(&self) -> ::LegendreSymbol {
+        use LegendreSymbol::*;
+
+        // s = self^((q - 1) // 2)
+        let s = self.pow([
+            0x9e10460b6c3e7ea3,
+            0xcbc0b548b438e546,
+            0xdc2822db40c0ac2e,
+            0x183227397098d014,
+        ]);
+        if s == Fq::zero() {
+            Zero
+        } else if s == Fq::one() {
+            QuadraticResidue
+        } else {
+            QuadraticNonResidue
+        }
+    }
+
+    fn sqrt(&self) -> Option<Self> {
+        // Shank's algorithm for q mod 4 = 3
+        // https://eprint.iacr.org/2012/685.pdf (page 9, algorithm 2)
+
+        // a1 = self^((q - 3) // 4)
+        let mut a1 = self.pow([
+            0x4f082305b61f3f51,
+            0x65e05aa45a1c72a3,
+            0x6e14116da0605617,
+            0xc19139cb84c680a,
+        ]);
+        let mut a0 = a1;
+        a0.square();
+        a0.mul_assign(self);
+
+        if a0 == NEGATIVE_ONE {
+            None
+        } else {
+            a1.mul_assign(self);
+            Some(a1)
+        }
+    }
+}
+
+#[test]
+fn test_b_coeff() {
+    assert_eq!(Fq::from_repr(FqRepr::from(3)).unwrap(), B_COEFF);
+
+    let mut b = Fq2 {
+        c0: Fq::from_repr(FqRepr::from(9)).unwrap(),
+        c1: Fq::one(),
+    };
+    b = b.inverse().unwrap();
+    b.mul_assign(&Fq2 {
+        c0: B_COEFF,
+        c1: Fq::zero(),
+    });
+    assert_eq!(b, B_COEFF_FQ2);
+}
+
+#[test]
+fn test_two_inv() {
+    let mut two = Fq::one();
+    two.double();
+    two.mul_assign(&TWO_INV);
+
+    assert_eq!(two, Fq::one());
+}
+
+#[test]
+fn test_frob_coeffs() {
+    let mut nqr = Fq::one();
+    nqr.negate();
+
+    assert_eq!(FROBENIUS_COEFF_FQ2_C1[0], Fq::one());
+    assert_eq!(
+        FROBENIUS_COEFF_FQ2_C1[1],
+        nqr.pow([
+            0x9e10460b6c3e7ea3,
+            0xcbc0b548b438e546,
+            0xdc2822db40c0ac2e,
+            0x183227397098d014
+        ])
+    );
+
+    let nqr = Fq2 {
+        c0: Fq::from_repr(FqRepr::from(9)).unwrap(),
+        c1: Fq::one(),
+    };
+
+    assert_eq!(FROBENIUS_COEFF_FQ6_C1[0], Fq2::one());
+    assert_eq!(
+        FROBENIUS_COEFF_FQ6_C1[1],
+        nqr.pow([
+            0x69602eb24829a9c2,
+            0xdd2b2385cd7b4384,
+            0xe81ac1e7808072c9,
+            0x10216f7ba065e00d
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ6_C1[2],
+        nqr.pow([
+            0x691c1d8b62747890,
+            0x8cab57b9adf8eb00,
+            0x18c55d8979dcee49,
+            0x56cd8a31d35b6b98,
+            0xb7a4a8c966ece684,
+            0xe5592c705cbd1cac,
+            0x1dde2529566d9b5e,
+            0x30c96e827699534
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ6_C1[3],
+        nqr.pow([
+            0x3de6332b975d69b2,
+            0x587b5b2bd890e101,
+            0x16677d4bec77bbeb,
+            0x3fdfdba3309dd645,
+            0xdfd4137cd943954b,
+            0xcfb035047f38c226,
+            0x1b5daf7ac73104c,
+            0x4cce8699d63e4f06,
+            0x40c0b41264a4b9f4,
+            0x7806da9ba1f6d7fb,
+            0x110a40708107d53a,
+            0x938e25ae57c88f
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ6_C1[4],
+        nqr.pow([
+            0xabb30419f6bee420,
+            0x6ce183e5f2f8d3b9,
+            0x9db42a441998ac99,
+            0xf74b04aa96e3852f,
+            0x64de4542a9807c06,
+            0x41f83258fd90abd1,
+            0x5ecb5383626aeca3,
+            0xb60804ce8f24ca82,
+            0xd4b3aadc1344e8bb,
+            0x436b70833cb2615b,
+            0x1a87eeb627861611,
+            0x4e155ea3e5090666,
+            0xacfcff9291a10112,
+            0x1cba0005b295d5bc,
+            0x319c8e7f94b31729,
+            0x1be477ceef2455
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ6_C1[5],
+        nqr.pow([
+            0x7501aa71de0e8ea2,
+            0x97516fd7ca83b8fe,
+            0x7da14ac0c03d4182,
+            0xaf5d35dc7f80498d,
+            0xb257f7f84fb899e0,
+            0x372cb1bd547dbe69,
+            0xb6696efbf52d5146,
+            0x3b6707d4a42574c,
+            0xeae6c62cf1670269,
+            0xfe70626cbbb760e9,
+            0xfa9d12d01fb42086,
+            0xc85218d5a7af23b7,
+            0xa70a73464ed35fb,
+            0x878713d44d9a2aca,
+            0xc81d8fc5cdfe15ee,
+            0xa3ebe919611e544d,
+            0xfe46bd734126775c,
+            0x6f8a7579371f67f,
+            0xa94a371ceb68884c,
+            0x545c441ba73d6
+        ])
+    );
+
+    assert_eq!(FROBENIUS_COEFF_FQ6_C2[0], Fq2::one());
+    assert_eq!(
+        FROBENIUS_COEFF_FQ6_C2[1],
+        nqr.pow([
+            0xd2c05d6490535384,
+            0xba56470b9af68708,
+            0xd03583cf0100e593,
+            0x2042def740cbc01b
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ6_C2[2],
+        nqr.pow([
+            0xd2383b16c4e8f120,
+            0x1956af735bf1d600,
+            0x318abb12f3b9dc93,
+            0xad9b1463a6b6d730,
+            0x6f495192cdd9cd08,
+            0xcab258e0b97a3959,
+            0x3bbc4a52acdb36bd,
+            0x6192dd04ed32a68
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ6_C2[3],
+        nqr.pow([
+            0x7bcc66572ebad364,
+            0xb0f6b657b121c202,
+            0x2ccefa97d8ef77d6,
+            0x7fbfb746613bac8a,
+            0xbfa826f9b2872a96,
+            0x9f606a08fe71844d,
+            0x36bb5ef58e62099,
+            0x999d0d33ac7c9e0c,
+            0x81816824c94973e8,
+            0xf00db53743edaff6,
+            0x221480e1020faa74,
+            0x1271c4b5caf911e
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ6_C2[4],
+        nqr.pow([
+            0x57660833ed7dc840,
+            0xd9c307cbe5f1a773,
+            0x3b68548833315932,
+            0xee9609552dc70a5f,
+            0xc9bc8a855300f80d,
+            0x83f064b1fb2157a2,
+            0xbd96a706c4d5d946,
+            0x6c10099d1e499504,
+            0xa96755b82689d177,
+            0x86d6e1067964c2b7,
+            0x350fdd6c4f0c2c22,
+            0x9c2abd47ca120ccc,
+            0x59f9ff2523420224,
+            0x3974000b652bab79,
+            0x63391cff29662e52,
+            0x37c8ef9dde48aa
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ6_C2[5],
+        nqr.pow([
+            0xea0354e3bc1d1d44,
+            0x2ea2dfaf950771fc,
+            0xfb429581807a8305,
+            0x5eba6bb8ff00931a,
+            0x64afeff09f7133c1,
+            0x6e59637aa8fb7cd3,
+            0x6cd2ddf7ea5aa28c,
+            0x76ce0fa9484ae99,
+            0xd5cd8c59e2ce04d2,
+            0xfce0c4d9776ec1d3,
+            0xf53a25a03f68410d,
+            0x90a431ab4f5e476f,
+            0x14e14e68c9da6bf7,
+            0xf0e27a89b345594,
+            0x903b1f8b9bfc2bdd,
+            0x47d7d232c23ca89b,
+            0xfc8d7ae6824ceeb9,
+            0xdf14eaf26e3ecff,
+            0x52946e39d6d11098,
+            0xa8b888374e7ad
+        ])
+    );
+
+    assert_eq!(FROBENIUS_COEFF_FQ12_C1[0], Fq2::one());
+    assert_eq!(
+        FROBENIUS_COEFF_FQ12_C1[1],
+        nqr.pow([
+            0x34b017592414d4e1,
+            0xee9591c2e6bda1c2,
+            0xf40d60f3c0403964,
+            0x810b7bdd032f006
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ12_C1[2],
+        nqr.pow([
+            0x348e0ec5b13a3c48,
+            0xc655abdcd6fc7580,
+            0xc62aec4bcee7724,
+            0x2b66c518e9adb5cc,
+            0x5bd25464b3767342,
+            0x72ac96382e5e8e56,
+            0xeef1294ab36cdaf,
+            0x1864b7413b4ca9a
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ12_C1[3],
+        nqr.pow([
+            0x9ef31995cbaeb4d9,
+            0xac3dad95ec487080,
+            0x8b33bea5f63bddf5,
+            0x9fefedd1984eeb22,
+            0x6fea09be6ca1caa5,
+            0x67d81a823f9c6113,
+            0xdaed7bd6398826,
+            0x2667434ceb1f2783,
+            0xa0605a0932525cfa,
+            0x3c036d4dd0fb6bfd,
+            0x888520384083ea9d,
+            0x49c712d72be447
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ12_C1[4],
+        nqr.pow([
+            0xd5d9820cfb5f7210,
+            0xb670c1f2f97c69dc,
+            0xceda15220ccc564c,
+            0x7ba582554b71c297,
+            0xb26f22a154c03e03,
+            0xa0fc192c7ec855e8,
+            0x2f65a9c1b1357651,
+            0xdb04026747926541,
+            0xea59d56e09a2745d,
+            0xa1b5b8419e5930ad,
+            0xd43f75b13c30b08,
+            0x270aaf51f2848333,
+            0x567e7fc948d08089,
+            0x8e5d0002d94aeade,
+            0x98ce473fca598b94,
+            0xdf23be777922a
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ12_C1[5],
+        nqr.pow([
+            0x3a80d538ef074751,
+            0x4ba8b7ebe541dc7f,
+            0xbed0a560601ea0c1,
+            0x57ae9aee3fc024c6,
+            0xd92bfbfc27dc4cf0,
+            0x1b9658deaa3edf34,
+            0x5b34b77dfa96a8a3,
+            0x81db383ea5212ba6,
+            0xf573631678b38134,
+            0x7f3831365ddbb074,
+            0xfd4e89680fda1043,
+            0xe4290c6ad3d791db,
+            0x538539a32769afd,
+            0x43c389ea26cd1565,
+            0xe40ec7e2e6ff0af7,
+            0x51f5f48cb08f2a26,
+            0xff235eb9a0933bae,
+            0x37c53abc9b8fb3f,
+            0x54a51b8e75b44426,
+            0x2a2e220dd39eb
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ12_C1[6],
+        nqr.pow([
+            0x7e26d70683d0a958,
+            0x1f9c1fbcfcc2cd05,
+            0x130f86d1897b9559,
+            0x4e63597377d880ec,
+            0xfa2d11b7bb74036d,
+            0xb8ac03166bceb3a1,
+            0xc56d5e66438791ed,
+            0x922147f815bcc460,
+            0x47aafd053f67d10e,
+            0x6aaba546e39fee61,
+            0xdc37833b929272e5,
+            0x5a588b29dbb15e87,
+            0xdc2763948e0aa62c,
+            0x1504af892cfccfda,
+            0x36659c7de4faa53a,
+            0x1d1fbbfd652e1750,
+            0x10d234dee9b5cb2b,
+            0x7d172098a5a992c,
+            0x7c652903e5cf8147,
+            0xa44fdfa0af640827,
+            0xb88d57f2250d5b08,
+            0x7da840104b4dd598,
+            0x5c5b8f2712c67cb4,
+            0x7f92d54e11a8
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ12_C1[7],
+        nqr.pow([
+            0x75e1bff130efc449,
+            0xfb4f505fb284ee15,
+            0x57b30efd96c492f5,
+            0xfdcb862e4e948b59,
+            0x3def467dae8887e2,
+            0xa47e3f76b755ca8c,
+            0xa63f6ea3debc563a,
+            0x115d1111a4fc4be2,
+            0xc3b2ece674d74549,
+            0xb2099a8141cb8830,
+            0x120dc0b8ac63867a,
+            0xae0245267985fe96,
+            0xed38ce9a40128f1d,
+            0xeba67d5d8ffa4939,
+            0xbff55f706b0de0f5,
+            0xb4f3f86e6f982aed,
+            0x62675f8a89bd61d,
+            0xa1098fb006a9726c,
+            0xe974fc0c7b0e5d9c,
+            0xf10af0bdc56fe9e,
+            0x628ca855d5d4ac87,
+            0x7bd59e7101d9d82d,
+            0xed98625bf5dc71aa,
+            0x7ab9b78fdc8558f4,
+            0x489b4c8564d6f8d2,
+            0xd055177a2fbfcd94,
+            0x59f68dd1e0cb392,
+            0x181d8471f268
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ12_C1[8],
+        nqr.pow([
+            0x742e7ca156ec6a20,
+            0x3fee59e5c3e8de2e,
+            0xfdef69cd295152ef,
+            0xe4ad8aece2ce3640,
+            0x5308778897ea5eb,
+            0xa4fc046ae1c2e50,
+            0xb16faf17473bba4a,
+            0xd106751c900aadfa,
+            0x115301a6c43ba345,
+            0x19f012d49d8a716c,
+            0x6b9d91b1c2a56cc5,
+            0xb77230690204b675,
+            0xf6d68e7229980805,
+            0xf4263d3b11784a87,
+            0x24bb64e5adeaa33d,
+            0x684c4ff325fa1c4d,
+            0x79a8c6430472e684,
+            0x823af8186da5609c,
+            0x2087966741a30941,
+            0x1876205eaf407912,
+            0xa614d3f14990435e,
+            0xd405328435bcc8df,
+            0x5afac38bad541421,
+            0x706fb9d17dec3d8,
+            0xecc747832c3f5f69,
+            0xe231b0ffd6651ed5,
+            0x45fa8e7ff2a80f15,
+            0xdce48166a2ee0170,
+            0x305fc72544895a12,
+            0x516ac4b20d800019,
+            0x826e9ab28689a4d3,
+            0x48efbc0eaac
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ12_C1[9],
+        nqr.pow([
+            0x64984dce4c07e3c1,
+            0x2e2096f441339496,
+            0xd50c9bd49d279670,
+            0xd52ead3ce3a93422,
+            0x426dad5fc6a6779a,
+            0x3f9dd6b6f19bc638,
+            0x6be503d3981b0db5,
+            0xb222e7512412d2c,
+            0x484bd275e77ff0bf,
+            0xb357542fb851205b,
+            0xd8c995246bf492ff,
+            0xc6b92fc3bf2887bc,
+            0xcd27cfd0d4499277,
+            0x967aa0012f40dcf9,
+            0x312baab0f5bc64e3,
+            0xe465b3c98a822e05,
+            0x3133d12c8828f7b8,
+            0x357a20a6a8a244ca,
+            0xd40b61719905e5b9,
+            0xcc4f1d5e2aed7a75,
+            0x7895032e16409563,
+            0x536db2a17eb54630,
+            0xdd66ae0d2d5ac57e,
+            0xe150b5a7f229f541,
+            0xd882dbabee789616,
+            0x1f380eb8775416ca,
+            0x73eca6c1c0abcd02,
+            0x8bd4f78c2fe1861e,
+            0xc53f421003b18ea2,
+            0xcae3f7b5d0591ecb,
+            0xbebe6ab21737113e,
+            0x838f0df2a5f7f26d,
+            0xbc2aa2593b06d88f,
+            0xcb02b95a74a8a0a,
+            0x74bd9a7b50725838,
+            0xdc98741fbf
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ12_C1[10],
+        nqr.pow([
+            0xed4127472fd6bc68,
+            0x72748872e11c4b47,
+            0x9c84e64776edc3f8,
+            0x8119b96d78b386,
+            0xfb0fbff1c5556968,
+            0x5009c51f998020be,
+            0xd6e688613527a368,
+            0xbe4f27942823152c,
+            0xd0f09d15c45fe09e,
+            0x7eb531158d2bbea5,
+            0x51bbe8e71be2cfd1,
+            0xbab37561b8c0c7c4,
+            0xd9173b5ab551b267,
+            0x5fafd9be4c78781,
+            0x61883bc8a78540ee,
+            0x7fe7aee3dcb694fb,
+            0xe4e85b12b4ac8a8,
+            0x9a0aa13a9ab47a86,
+            0xd5a3bd591ae12d4b,
+            0x5865cbfabbe53b4d,
+            0xf98188a9b0cd490f,
+            0x3985ef4af715da43,
+            0x573661cd006ced38,
+            0x95853a6aaa77d5c1,
+            0x165d538f0628b55e,
+            0x583e75f890f32cac,
+            0x5becf43a08a490b9,
+            0x63ed4071c1a8087a,
+            0x151d41c7701faa25,
+            0x1c661c8e4900b051,
+            0x581aa0f552590875,
+            0x31bf39ff43375aca,
+            0xe27c0f3d11310329,
+            0x4071459ef3a42c2,
+            0x59a2b029be2d6a1f,
+            0x30ef71f271cdbf61,
+            0xf3774b177f326e78,
+            0x976d79b23e8501c5,
+            0x9ed0e138633123c9,
+            0x29b304ecc1
+        ])
+    );
+    assert_eq!(
+        FROBENIUS_COEFF_FQ12_C1[11],
+        nqr.pow([
+            0xf4e8c249a335ddb9,
+            0x965085c9440aef70,
+            0xc16d84a741174aef,
+            0xbe1a366b81fe0680,
+            0x1c65508409269d2f,
+            0x185861e9cd07fb21,
+            0x26b682d951220b7a,
+            0x9f189f5a7b75876,
+            0xf7133ab3ecff7f0,
+            0xbf7d1ada5df0b2fd,
+            0x4b0df5207414a4b6,
+            0xbf6a6941b58966d3,
+            0x6a15cc7b6bb0483a,
+            0xc338843b8a236597,
+            0xc8d724986bc0856f,
+            0x1dcb8b084e928e52,
+            0x3645ba97c4af9161,
+            0x7d257d1abed180d3,
+            0xa66e85068416bdb,
+            0x8b745a2aeb2bd27e,
+            0xe34f87ec4949ec06,
+            0x6ba47fa06f902fd6,
+            0x225cd33864121ed2,
+            0xea5d91e41a3b068b,
+            0x35d2fbc8b7a05f5c,
+            0xe5b1e22f3dcbc837,
+            0xa9f7bdbee44d8301,
+            0xbb7a57512450e143,
+            0x2e2ca4188fd4eb5b,
+            0x9d512b5d1e158636,
+            0xdd18753b03f38ee8,
+            0xbbe44db3214b380e,
+            0x4534f7b060cca3d2,
+            0xcbb0309736f9df06,
+            0xfcb01aba828f0678,
+            0xe2e4d5dac5cc7917,
+            0x6631e85c4224e136,
+            0xb6c334bbd109d480,
+            0x2608e9c50edc2cdf,
+            0x959dba8288258d16,
+            0xd895fc73e207c8,
+            0x6b5ce08dc4a7bf13,
+            0xb02a4f252d6a301f,
+            0x7e1e7a192
+        ])
+    );
+
+    assert_eq!(TWIST_MUL_BY_Q_X, FROBENIUS_COEFF_FQ6_C1[1]);
+    assert_eq!(TWIST_MUL_BY_Q_Y, FROBENIUS_COEFF_FQ12_C1[1].pow([3]));
+    assert_eq!(TWIST_MUL_BY_Q2_X, FROBENIUS_COEFF_FQ6_C1[2]);
+}
+
+#[test]
+fn test_neg_one() {
+    let mut o = Fq::one();
+    o.negate();
+
+    assert_eq!(NEGATIVE_ONE, o);
+}
+
+#[test]
+fn test_fq_num_bits() {
+    assert_eq!(Fq::NUM_BITS, 254);
+    assert_eq!(Fq::CAPACITY, 253);
+}
+
+#[test]
+fn test_fq_root_of_unity() {
+    assert_eq!(Fq::S, 1);
+    assert_eq!(
+        Fq::multiplicative_generator(),
+        Fq::from_repr(FqRepr::from(3)).unwrap()
+    );
+    assert_eq!(
+        Fq::multiplicative_generator().pow([
+            0x9e10460b6c3e7ea3,
+            0xcbc0b548b438e546,
+            0xdc2822db40c0ac2e,
+            0x183227397098d014
+        ]),
+        Fq::root_of_unity()
+    );
+    assert_eq!(Fq::root_of_unity().pow([1 << Fq::S]), Fq::one());
+    assert!(Fq::multiplicative_generator().sqrt().is_none());
+}
+
+#[test]
+fn fq_field_tests() {
+    ::tests::field::random_field_tests::<Fq>();
+    ::tests::field::random_sqrt_tests::<Fq>();
+    ::tests::field::random_frobenius_tests::<Fq, _>(Fq::char(), 13);
+    ::tests::field::from_str_tests::<Fq>();
+}
+
+#[test]
+fn test_fq_ordering() {
+    // FqRepr's ordering is well-tested, but we still need to make sure the Fq
+    // elements aren't being compared in Montgomery form.
+    for i in 0..100 {
+        assert!(
+            Fq::from_repr(FqRepr::from(i + 1)).unwrap() > Fq::from_repr(FqRepr::from(i)).unwrap()
+        );
+    }
+}
+
+#[test]
+fn fq_repr_tests() {
+    ::tests::repr::random_repr_tests::<FqRepr>();
+}
+
+#[test]
+fn test_fq_legendre() {
+    use LegendreSymbol::*;
+
+    assert_eq!(QuadraticResidue, Fq::one().legendre());
+    assert_eq!(Zero, Fq::zero().legendre());
+    assert_eq!(
+        QuadraticNonResidue,
+        Fq::from_repr(FqRepr::from(3)).unwrap().legendre()
+    );
+    assert_eq!(
+        QuadraticResidue,
+        Fq::from_repr(FqRepr::from(4)).unwrap().legendre()
+    );
+}
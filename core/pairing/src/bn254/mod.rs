@@ -0,0 +1,387 @@
+mod ec;
+mod fq;
+mod fq12;
+mod fq2;
+mod fq6;
+mod fr;
+
+pub use self::ec::{
+    G1, G1Affine, G1Compressed, G1Prepared, G1Uncompressed, G2, G2Affine, G2Compressed, G2Prepared,
+    G2Uncompressed,
+};
+pub use self::fq::{Fq, FqRepr};
+pub use self::fq12::Fq12;
+pub use self::fq2::Fq2;
+pub use self::fq6::Fq6;
+pub use self::fr::{Fr, FrRepr};
+
+use self::fq::{B_COEFF_FQ2, TWIST_MUL_BY_Q2_X, TWIST_MUL_BY_Q_X, TWIST_MUL_BY_Q_Y, TWO_INV};
+use super::{CurveAffine, Engine, Field};
+
+// The BN parameter u for BN254 is 0x44e992b44a6909f1
+const BN_U: u64 = 0x44e992b44a6909f1;
+
+// The non-adjacent form of 6u + 2, the length of the optimal ate Miller loop,
+// starting from the least significant digit.
+const SIX_U_PLUS_2_NAF: [i8; 66] = [
+    0, 0, 0, 1, 0, 1, 0, -1, 0, 0, -1, 0, 0, 0, 1, 0, 0, -1, 0, -1, 0, 0, 0, 1, 0, -1, 0, 0, 0, 0,
+    -1, 0, 0, 1, 0, -1, 0, 0, 1, 0, 0, 0, 0, 0, -1, 0, 0, -1, 0, 1, 0, -1, 0, 0, 0, -1, 0, -1, 0,
+    0, 0, 1, 0, -1, 0, 1,
+];
+
+// The number of line function coefficients of a prepared G2 point which is not
+// the point at infinity: one doubling step per digit of SIX_U_PLUS_2_NAF below the
+// most significant one, one addition step per nonzero digit among them, and the
+// two addition steps with the Frobenius images of the point.
+pub(crate) const G2_PREPARED_COEFFS_LEN: usize = 88;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bn254;
+
+impl Engine for Bn254 {
+    type Fr = Fr;
+    type G1 = G1;
+    type G1Affine = G1Affine;
+    type G2 = G2;
+    type G2Affine = G2Affine;
+    type Fq = Fq;
+    type Fqe = Fq2;
+    type Fqk = Fq12;
+
+    fn miller_loop<'a, I>(i: I) -> Self::Fqk
+    where
+        I: IntoIterator<
+            Item = &'a (
+                &'a <Self::G1Affine as CurveAffine>::Prepared,
+                &'a <Self::G2Affine as CurveAffine>::Prepared,
+            ),
+        >,
+    {
+        let mut pairs = vec![];
+        for &(p, q) in i {
+            if !p.is_zero() && !q.is_zero() {
+                pairs.push((p, q.coeffs.iter()));
+            }
+        }
+
+        // Twisting isomorphism from E to E'
+        fn ell(f: &mut Fq12, coeffs: &(Fq2, Fq2, Fq2), p: &G1Affine) {
+            let mut c0 = coeffs.0;
+            let mut c1 = coeffs.1;
+
+            c0.c0.mul_assign(&p.y);
+            c0.c1.mul_assign(&p.y);
+
+            c1.c0.mul_assign(&p.x);
+            c1.c1.mul_assign(&p.x);
+
+            // Sparse multiplication in Fq12
+            f.mul_by_034(&c0, &c1, &coeffs.2);
+        }
+
+        let mut f = Fq12::one();
+
+        for i in (0..SIX_U_PLUS_2_NAF.len() - 1).rev() {
+            if i != SIX_U_PLUS_2_NAF.len() - 2 {
+                f.square();
+            }
+
+            for &mut (p, ref mut coeffs) in &mut pairs {
+                ell(&mut f, coeffs.next().unwrap(), &p.0);
+            }
+
+            if SIX_U_PLUS_2_NAF[i] != 0 {
+                for &mut (p, ref mut coeffs) in &mut pairs {
+                    ell(&mut f, coeffs.next().unwrap(), &p.0);
+                }
+            }
+        }
+
+        // Lines through q^1 * Q and -q^2 * Q
+        for &mut (p, ref mut coeffs) in &mut pairs {
+            ell(&mut f, coeffs.next().unwrap(), &p.0);
+        }
+
+        for &mut (p, ref mut coeffs) in &mut pairs {
+            ell(&mut f, coeffs.next().unwrap(), &p.0);
+        }
+
+        f
+    }
+
+    fn final_exponentiation(r: &Fq12) -> Option<Fq12> {
+        let mut f1 = *r;
+        f1.conjugate();
+
+        match r.inverse() {
+            Some(mut f2) => {
+                // Easy part: r^((q^6 - 1) * (q^2 + 1))
+                let mut r = f1;
+                r.mul_assign(&f2);
+                f2 = r;
+                r.frobenius_map(2);
+                r.mul_assign(&f2);
+
+                // Hard part, following Fuentes-Castaneda, Knapp and Rodriguez-Henriquez,
+                // "Faster hashing to G2", https://eprint.iacr.org/2011/297.pdf
+                fn exp_by_neg_u(f: &mut Fq12) {
+                    *f = f.pow(&[BN_U]);
+                    f.conjugate();
+                }
+
+                let mut y0 = r;
+                exp_by_neg_u(&mut y0);
+                let mut y1 = y0;
+                y1.square();
+                let mut y2 = y1;
+                y2.square();
+                let mut y3 = y2;
+                y3.mul_assign(&y1);
+                let mut y4 = y3;
+                exp_by_neg_u(&mut y4);
+                let mut y5 = y4;
+                y5.square();
+                let mut y6 = y5;
+                exp_by_neg_u(&mut y6);
+                y3.conjugate();
+                y6.conjugate();
+                let mut y7 = y6;
+                y7.mul_assign(&y4);
+                let mut y8 = y7;
+                y8.mul_assign(&y3);
+                let mut y9 = y8;
+                y9.mul_assign(&y1);
+                let mut y10 = y8;
+                y10.mul_assign(&y4);
+                let mut y11 = y10;
+                y11.mul_assign(&r);
+                let mut y12 = y9;
+                y12.frobenius_map(1);
+                let mut y13 = y12;
+                y13.mul_assign(&y11);
+                y8.frobenius_map(2);
+                let mut y14 = y8;
+                y14.mul_assign(&y13);
+                r.conjugate();
+                let mut y15 = r;
+                y15.mul_assign(&y9);
+                y15.frobenius_map(3);
+                let mut y16 = y15;
+                y16.mul_assign(&y14);
+
+                Some(y16)
+            }
+            None => None,
+        }
+    }
+}
+
+impl G2Prepared {
+    pub fn is_zero(&self) -> bool {
+        self.infinity
+    }
+
+    pub fn from_affine(q: G2Affine) -> Self {
+        if q.is_zero() {
+            return G2Prepared {
+                coeffs: vec![],
+                infinity: true,
+            };
+        }
+
+        // Unlike the curve arithmetic, the steps below keep `r` in homogeneous
+        // projective coordinates (x/z, y/z), where the line functions of the
+        // D-type twist come out cheapest.
+        fn doubling_step(r: &mut G2) -> (Fq2, Fq2, Fq2) {
+            // Doubling step with the line function of Section 4,
+            // https://eprint.iacr.org/2010/526.pdf
+            let mut a = r.x;
+            a.mul_assign(&r.y);
+            a.c0.mul_assign(&TWO_INV);
+            a.c1.mul_assign(&TWO_INV);
+
+            let mut b = r.y;
+            b.square();
+
+            let mut c = r.z;
+            c.square();
+
+            let mut e = c;
+            e.double();
+            e.add_assign(&c);
+            e.mul_assign(&B_COEFF_FQ2);
+
+            let mut f = e;
+            f.double();
+            f.add_assign(&e);
+
+            let mut g = b;
+            g.add_assign(&f);
+            g.c0.mul_assign(&TWO_INV);
+            g.c1.mul_assign(&TWO_INV);
+
+            let mut h = r.y;
+            h.add_assign(&r.z);
+            h.square();
+            h.sub_assign(&b);
+            h.sub_assign(&c);
+
+            let mut i = e;
+            i.sub_assign(&b);
+
+            let mut j = r.x;
+            j.square();
+
+            let mut e_square = e;
+            e_square.square();
+
+            r.x = b;
+            r.x.sub_assign(&f);
+            r.x.mul_assign(&a);
+
+            r.y = g;
+            r.y.square();
+            r.y.sub_assign(&e_square);
+            r.y.sub_assign(&e_square);
+            r.y.sub_assign(&e_square);
+
+            r.z = b;
+            r.z.mul_assign(&h);
+
+            h.negate();
+
+            let mut j3 = j;
+            j3.double();
+            j3.add_assign(&j);
+
+            (h, j3, i)
+        }
+
+        fn addition_step(r: &mut G2, q: &G2Affine) -> (Fq2, Fq2, Fq2) {
+            // Mixed addition step with the line function of Section 4,
+            // https://eprint.iacr.org/2010/526.pdf
+            let mut theta = q.y;
+            theta.mul_assign(&r.z);
+            theta.negate();
+            theta.add_assign(&r.y);
+
+            let mut lambda = q.x;
+            lambda.mul_assign(&r.z);
+            lambda.negate();
+            lambda.add_assign(&r.x);
+
+            let mut c = theta;
+            c.square();
+
+            let mut d = lambda;
+            d.square();
+
+            let mut e = lambda;
+            e.mul_assign(&d);
+
+            let mut f = r.z;
+            f.mul_assign(&c);
+
+            let mut g = r.x;
+            g.mul_assign(&d);
+
+            let mut h = e;
+            h.add_assign(&f);
+            h.sub_assign(&g);
+            h.sub_assign(&g);
+
+            r.x = lambda;
+            r.x.mul_assign(&h);
+
+            let mut t = r.y;
+            t.mul_assign(&e);
+
+            r.y = g;
+            r.y.sub_assign(&h);
+            r.y.mul_assign(&theta);
+            r.y.sub_assign(&t);
+
+            r.z.mul_assign(&e);
+
+            let mut j = theta;
+            j.mul_assign(&q.x);
+            let mut t = lambda;
+            t.mul_assign(&q.y);
+            j.sub_assign(&t);
+
+            theta.negate();
+
+            (lambda, theta, j)
+        }
+
+        let mut coeffs = vec![];
+        let mut r: G2 = q.into();
+
+        let mut neg_q = q;
+        neg_q.negate();
+
+        for i in (0..SIX_U_PLUS_2_NAF.len() - 1).rev() {
+            coeffs.push(doubling_step(&mut r));
+
+            match SIX_U_PLUS_2_NAF[i] {
+                1 => coeffs.push(addition_step(&mut r, &q)),
+                -1 => coeffs.push(addition_step(&mut r, &neg_q)),
+                _ => {}
+            }
+        }
+
+        // q1 = q^1 * Q, the Frobenius endomorphism through the twist
+        let mut q1 = q;
+        q1.x.c1.negate();
+        q1.x.mul_assign(&TWIST_MUL_BY_Q_X);
+        q1.y.c1.negate();
+        q1.y.mul_assign(&TWIST_MUL_BY_Q_Y);
+        coeffs.push(addition_step(&mut r, &q1));
+
+        // q2 = -q^2 * Q, where the y-coordinate is multiplied by (u + 9)^((q^2 - 1) / 2) = -1
+        let mut q2 = q;
+        q2.x.mul_assign(&TWIST_MUL_BY_Q2_X);
+        coeffs.push(addition_step(&mut r, &q2));
+
+        G2Prepared {
+            coeffs,
+            infinity: false,
+        }
+    }
+}
+
+#[test]
+fn test_g2_prepared_coeffs_len() {
+    let prepared = G2Affine::one().prepare();
+    assert_eq!(prepared.coeffs.len(), G2_PREPARED_COEFFS_LEN);
+    assert!(!prepared.infinity);
+
+    let prepared = G2Affine::zero().prepare();
+    assert!(prepared.coeffs.is_empty());
+    assert!(prepared.infinity);
+}
+
+#[test]
+fn test_pairing_of_generators() {
+    use PrimeField;
+
+    let e = Bn254::pairing(G1Affine::one(), G2Affine::one());
+    assert!(e != Fq12::one());
+    assert_eq!(e.pow(Fr::char()), Fq12::one());
+
+    // e(P, Q) * e(-P, Q) = 1
+    let mut neg_p = G1Affine::one();
+    neg_p.negate();
+    assert_eq!(
+        Bn254::final_exponentiation(&Bn254::miller_loop(&[
+            (&G1Affine::one().prepare(), &G2Affine::one().prepare()),
+            (&neg_p.prepare(), &G2Affine::one().prepare()),
+        ])).unwrap(),
+        Fq12::one()
+    );
+}
+
+#[test]
+fn bn254_engine_tests() {
+    ::tests::engine::engine_tests::<Bn254>();
+}
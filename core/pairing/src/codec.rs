@@ -0,0 +1,251 @@
+//! SCALE (the `parity-codec` feature) and serde (the `serde` feature) implementations of
+//! the scalars, the pairing results and the affine points, as fixed-size byte strings:
+//! scalars as big-endian integers, pairing results as written by `RW`, and points in
+//! the uncompressed encoding. Decoding fails on non-canonical scalars and on points which
+//! are not on the curve or not in the prime order subgroup, instead of panicking.
+
+use std::vec::Vec;
+use {CurveAffine, EncodedPoint, PrimeField, PrimeFieldRepr, RW};
+
+/// An element with an encoding of a fixed length.
+trait FixedBytes: Sized {
+    fn len() -> usize;
+
+    fn to_bytes(&self) -> Vec<u8>;
+
+    /// `bytes` is of `len()` bytes.
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+macro_rules! impl_scalar_bytes {
+    ($ty:ty) => {
+        impl FixedBytes for $ty {
+            fn len() -> usize {
+                <$ty as PrimeField>::Repr::default().as_ref().len() * 8
+            }
+
+            fn to_bytes(&self) -> Vec<u8> {
+                let mut res = Vec::with_capacity(Self::len());
+                self.into_repr()
+                    .write_be(&mut res)
+                    .expect("writing into a Vec never fails; qed");
+                res
+            }
+
+            fn from_bytes(bytes: &[u8]) -> Option<Self> {
+                let mut repr = <$ty as PrimeField>::Repr::default();
+                repr.read_be(&mut &bytes[..]).ok()?;
+                <$ty>::from_repr(repr).ok()
+            }
+        }
+    };
+}
+
+macro_rules! impl_pairing_result_bytes {
+    ($ty:ty, $len:expr) => {
+        impl FixedBytes for $ty {
+            fn len() -> usize {
+                $len
+            }
+
+            fn to_bytes(&self) -> Vec<u8> {
+                let mut res = Vec::with_capacity(Self::len());
+                self.write(&mut res)
+                    .expect("writing into a Vec never fails; qed");
+                res
+            }
+
+            fn from_bytes(bytes: &[u8]) -> Option<Self> {
+                <$ty as RW>::read(&mut &bytes[..]).ok()
+            }
+        }
+    };
+}
+
+macro_rules! impl_point_bytes {
+    ($ty:ty) => {
+        impl FixedBytes for $ty {
+            fn len() -> usize {
+                <<$ty as CurveAffine>::Uncompressed as EncodedPoint>::size()
+            }
+
+            fn to_bytes(&self) -> Vec<u8> {
+                self.into_uncompressed().as_ref().to_vec()
+            }
+
+            fn from_bytes(bytes: &[u8]) -> Option<Self> {
+                let mut encoded = <<$ty as CurveAffine>::Uncompressed as EncodedPoint>::empty();
+                encoded.as_mut().copy_from_slice(bytes);
+                encoded.into_affine().ok()
+            }
+        }
+    };
+}
+
+#[cfg(feature = "parity-codec")]
+macro_rules! impl_scale {
+    ($ty:ty) => {
+        impl ::parity_codec::Encode for $ty {
+            fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+                f(&self.to_bytes()[..])
+            }
+        }
+
+        impl ::parity_codec::Decode for $ty {
+            fn decode<I: ::parity_codec::Input>(input: &mut I) -> Option<Self> {
+                let mut bytes = vec![0u8; <$ty as FixedBytes>::len()];
+                if input.read(&mut bytes[..]) != bytes.len() {
+                    return None;
+                }
+                <$ty as FixedBytes>::from_bytes(&bytes[..])
+            }
+        }
+    };
+}
+
+#[cfg(feature = "serde")]
+macro_rules! impl_serde {
+    ($ty:ty, $name:expr) => {
+        impl ::serde::Serialize for $ty {
+            fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_bytes(&self.to_bytes()[..])
+            }
+        }
+
+        impl<'de> ::serde::Deserialize<'de> for $ty {
+            fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserializer.deserialize_bytes(BytesVisitor::<$ty>::new($name))
+            }
+        }
+    };
+}
+
+/// Deserializes a `FixedBytes` from bytes, or from a sequence of bytes
+/// for formats without a byte string type such as JSON.
+#[cfg(feature = "serde")]
+struct BytesVisitor<T> {
+    name: &'static str,
+    _marker: ::std::marker::PhantomData<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> BytesVisitor<T> {
+    fn new(name: &'static str) -> Self {
+        BytesVisitor {
+            name,
+            _marker: ::std::marker::PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: FixedBytes> ::serde::de::Visitor<'de> for BytesVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "{} bytes of a valid {}", T::len(), self.name)
+    }
+
+    fn visit_bytes<E: ::serde::de::Error>(self, v: &[u8]) -> Result<T, E> {
+        if v.len() != T::len() {
+            return Err(E::invalid_length(v.len(), &self));
+        }
+        T::from_bytes(v).ok_or_else(|| E::invalid_value(::serde::de::Unexpected::Bytes(v), &self))
+    }
+
+    fn visit_seq<A: ::serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<T, A::Error> {
+        let mut bytes = Vec::with_capacity(T::len());
+        while let Some(b) = seq.next_element::<u8>()? {
+            if bytes.len() == T::len() {
+                return Err(::serde::de::Error::invalid_length(bytes.len() + 1, &self));
+            }
+            bytes.push(b);
+        }
+        self.visit_bytes(&bytes[..])
+    }
+}
+
+macro_rules! impl_codecs {
+    ($($ty:ty => $name:expr),*) => {
+        $(
+            #[cfg(feature = "parity-codec")]
+            impl_scale!($ty);
+            #[cfg(feature = "serde")]
+            impl_serde!($ty, $name);
+        )*
+    };
+}
+
+mod bls12_381 {
+    use super::*;
+    use bls12_381::{Fq12, Fr, G1Affine, G2Affine};
+
+    impl_scalar_bytes!(Fr);
+    impl_pairing_result_bytes!(Fq12, 576);
+    impl_point_bytes!(G1Affine);
+    impl_point_bytes!(G2Affine);
+
+    impl_codecs!(
+        Fr => "scalar of BLS12-381",
+        Fq12 => "pairing result of BLS12-381",
+        G1Affine => "G1 point of BLS12-381",
+        G2Affine => "G2 point of BLS12-381"
+    );
+}
+
+#[cfg(feature = "bn254")]
+mod bn254 {
+    use super::*;
+    use bn254::{Fq12, Fr, G1Affine, G2Affine};
+
+    impl_scalar_bytes!(Fr);
+    impl_pairing_result_bytes!(Fq12, 384);
+    impl_point_bytes!(G1Affine);
+    impl_point_bytes!(G2Affine);
+
+    impl_codecs!(
+        Fr => "scalar of BN254",
+        Fq12 => "pairing result of BN254",
+        G1Affine => "G1 point of BN254",
+        G2Affine => "G2 point of BN254"
+    );
+}
+
+#[cfg(all(test, feature = "parity-codec"))]
+mod tests {
+    use bls12_381::{Bls12, Fr, G1Affine, G2Affine, G1, G2};
+    use parity_codec::{Decode, Encode};
+    use rand::{Rand, SeedableRng, XorShiftRng};
+    use std::fmt::Debug;
+    use {CurveAffine, CurveProjective, Engine, PrimeField, PrimeFieldRepr};
+
+    fn roundtrip<T: Encode + Decode + PartialEq + Debug>(t: T, len: usize) {
+        let encoded = t.encode();
+        assert_eq!(encoded.len(), len);
+        assert_eq!(T::decode(&mut &encoded[..]), Some(t));
+        assert_eq!(T::decode(&mut &encoded[..len - 1]), None);
+    }
+
+    #[test]
+    fn test_scale_roundtrip() {
+        let rng = &mut XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        roundtrip(Fr::rand(rng), 32);
+        roundtrip(Bls12::pairing(G1::rand(rng), G2::rand(rng)), 576);
+        roundtrip(G1::rand(rng).into_affine(), 96);
+        roundtrip(G2::rand(rng).into_affine(), 192);
+        roundtrip(G1Affine::zero(), 96);
+        roundtrip(G2Affine::zero(), 192);
+    }
+
+    #[test]
+    fn test_scale_rejects_invalid_encodings() {
+        let mut modulus = vec![];
+        Fr::char().write_be(&mut modulus).unwrap();
+        assert_eq!(Fr::decode(&mut &modulus[..]), None);
+
+        let mut not_on_curve = G1Affine::one().encode();
+        not_on_curve[95] ^= 1;
+        assert_eq!(G1Affine::decode(&mut &not_on_curve[..]), None);
+    }
+}
@@ -17,6 +17,10 @@
 
 extern crate byteorder;
 extern crate rand;
+#[cfg(feature = "parity-codec")]
+extern crate parity_codec;
+#[cfg(feature = "serde")]
+extern crate serde;
 
 #[cfg(not(feature = "std"))]
 #[macro_use]
@@ -40,10 +44,17 @@ pub mod io;
 pub mod tests;
 
 pub mod bls12_381;
+#[cfg(feature = "bn254")]
+pub mod bn254;
 
 mod wnaf;
 pub use self::wnaf::Wnaf;
 
+mod multiexp;
+
+#[cfg(any(feature = "parity-codec", feature = "serde"))]
+mod codec;
+
 /// An "engine" is a collection of types (fields, elliptic curve groups, etc.)
 /// with well-defined relationships. In particular, the G1/G2 curve groups are
 /// of prime order `r`, and are equipped with a bilinear pairing function.
@@ -187,6 +198,38 @@ pub trait CurveProjective:
     /// Performs scalar multiplication of this element.
     fn mul_assign<S: Into<<Self::Scalar as PrimeField>::Repr>>(&mut self, other: S);
 
+    /// Performs scalar multiplication of this element by a secret scalar.
+    /// Unlike `mul_assign`, the sequence of group operations and the memory accessed don't depend
+    /// on the bits of the scalar: a Montgomery ladder runs over every bit of the representation,
+    /// starting from `2^n + scalar` so that its points aren't the point at infinity, and subtracts
+    /// `2^n` times the element at the end. It is a few times slower than `mul_assign`.
+    fn mul_assign_ct<S: Into<<Self::Scalar as PrimeField>::Repr>>(&mut self, other: S) {
+        let scalar = other.into();
+
+        // ladder[1] - ladder[0] is always the element itself.
+        let mut ladder = [*self, *self];
+        ladder[1].double();
+        let mut offset = *self;
+
+        for limb in scalar.as_ref().iter().rev() {
+            for i in (0..64).rev() {
+                let bit = ((limb >> i) & 1) as usize;
+
+                let mut sum = ladder[0];
+                sum.add_assign(&ladder[1]);
+                let mut double = ladder[bit];
+                double.double();
+
+                ladder[bit] = double;
+                ladder[1 - bit] = sum;
+                offset.double();
+            }
+        }
+
+        ladder[0].sub_assign(&offset);
+        *self = ladder[0];
+    }
+
     /// Converts this element into its affine representation.
     fn into_affine(&self) -> Self::Affine;
 
@@ -197,6 +240,13 @@ pub trait CurveProjective:
     /// Recommends a wNAF window size given the number of scalars you intend to multiply
     /// a base by. Always returns a number between 2 and 22, inclusive.
     fn recommended_wnaf_for_num_scalars(num_scalars: usize) -> usize;
+
+    /// Computes `sum scalars[i] * bases[i]` with Pippenger's bucket method,
+    /// which is much faster than multiplying each base in turn for more than a few bases.
+    /// The extra elements of the longer slice are ignored.
+    fn multi_exp(bases: &[Self::Affine], scalars: &[<Self::Scalar as PrimeField>::Repr]) -> Self {
+        multiexp::multi_exp(bases, scalars)
+    }
 }
 
 /// Affine representation of an elliptic curve point guaranteed to be
@@ -621,10 +671,15 @@ fn test_bit_iterator() {
     assert!(a.next().is_none());
 }
 
-pub use self::arith_impl::*;
-
 #[cfg(feature = "u128-support")]
-mod arith_impl {
+pub use self::arith_u128::*;
+#[cfg(not(feature = "u128-support"))]
+pub use self::arith_u64::*;
+
+// Both backends are compiled under `cfg(test)` so that they can be
+// checked against each other regardless of the enabled feature.
+#[cfg(any(feature = "u128-support", test))]
+pub(crate) mod arith_u128 {
     /// Calculate a - b - borrow, returning the result and modifying
     /// the borrow value.
     #[inline(always)]
@@ -659,8 +714,8 @@ mod arith_impl {
     }
 }
 
-#[cfg(not(feature = "u128-support"))]
-mod arith_impl {
+#[cfg(any(not(feature = "u128-support"), test))]
+pub(crate) mod arith_u64 {
     #[inline(always)]
     fn split_u64(i: u64) -> (u64, u64) {
         (i >> 32, i & 0xFFFFFFFF)
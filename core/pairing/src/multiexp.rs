@@ -0,0 +1,101 @@
+use super::{CurveProjective, PrimeField, PrimeFieldRepr};
+use std::vec::Vec;
+
+/// Computes `sum scalars[i] * bases[i]` with Pippenger's bucket method.
+/// The extra elements of the longer slice are ignored.
+pub(crate) fn multi_exp<G: CurveProjective>(
+    bases: &[G::Affine],
+    scalars: &[<G::Scalar as PrimeField>::Repr],
+) -> G {
+    let n = bases.len().min(scalars.len());
+    if n == 0 {
+        return G::zero();
+    }
+
+    let c = window_size(n);
+    let num_bits = <G::Scalar as PrimeField>::NUM_BITS as usize;
+
+    // The sums of each window, from the least significant one.
+    let mut windows = Vec::with_capacity((num_bits + c - 1) / c);
+    let mut buckets = vec![G::zero(); (1 << c) - 1];
+
+    let mut skip = 0;
+    while skip < num_bits {
+        for bucket in buckets.iter_mut() {
+            *bucket = G::zero();
+        }
+
+        for (base, scalar) in bases.iter().zip(scalars.iter()) {
+            let index = window_bits(scalar, skip, c);
+            if index != 0 {
+                buckets[index - 1].add_assign_mixed(base);
+            }
+        }
+
+        // sum_{i} i * buckets[i - 1], by summing the running sums from the highest bucket.
+        let mut running_sum = G::zero();
+        let mut acc = G::zero();
+        for bucket in buckets.iter().rev() {
+            running_sum.add_assign(bucket);
+            acc.add_assign(&running_sum);
+        }
+        windows.push(acc);
+
+        skip += c;
+    }
+
+    let mut res = G::zero();
+    for window in windows.iter().rev() {
+        for _ in 0..c {
+            res.double();
+        }
+        res.add_assign(window);
+    }
+
+    res
+}
+
+/// Roughly ln(n) + 2, which balances the bucket accumulation against the doublings.
+fn window_size(n: usize) -> usize {
+    if n < 32 {
+        3
+    } else {
+        let log2 = (usize::max_value().count_ones() - n.leading_zeros()) as usize;
+        log2 * 69 / 100 + 2
+    }
+}
+
+/// The `c` bits of `scalar` from the `skip`-th bit.
+fn window_bits<R: PrimeFieldRepr>(scalar: &R, skip: usize, c: usize) -> usize {
+    let limbs = scalar.as_ref();
+    let mut res = 0;
+
+    for i in 0..c {
+        let bit = skip + i;
+        let limb = bit / 64;
+        if limb >= limbs.len() {
+            break;
+        }
+        if (limbs[limb] >> (bit % 64)) & 1 == 1 {
+            res |= 1 << i;
+        }
+    }
+
+    res
+}
+
+/// Computes the same sum by multiplying each base in turn, as a reference for the tests.
+#[cfg(test)]
+pub(crate) fn naive_multi_exp<G: CurveProjective>(
+    bases: &[G::Affine],
+    scalars: &[<G::Scalar as PrimeField>::Repr],
+) -> G {
+    use super::CurveAffine;
+
+    let mut acc = G::zero();
+    for (base, scalar) in bases.iter().zip(scalars.iter()) {
+        acc.add_assign(&base.mul(*scalar));
+    }
+
+    acc
+}
@@ -0,0 +1,414 @@
+use rand::{Rand, Rng, SeedableRng, XorShiftRng};
+use bls12_381::{Fq, FqRepr, Fr};
+use {arith_u128, arith_u64, Field, PrimeField};
+
+/// Common interface over the limb arithmetic backends so that the same
+/// checks can be run against each of them.
+trait Backend {
+    fn sbb(a: u64, b: u64, borrow: &mut u64) -> u64;
+    fn adc(a: u64, b: u64, carry: &mut u64) -> u64;
+    fn mac_with_carry(a: u64, b: u64, c: u64, carry: &mut u64) -> u64;
+}
+
+struct U128;
+struct U64;
+
+impl Backend for U128 {
+    fn sbb(a: u64, b: u64, borrow: &mut u64) -> u64 {
+        arith_u128::sbb(a, b, borrow)
+    }
+
+    fn adc(a: u64, b: u64, carry: &mut u64) -> u64 {
+        arith_u128::adc(a, b, carry)
+    }
+
+    fn mac_with_carry(a: u64, b: u64, c: u64, carry: &mut u64) -> u64 {
+        arith_u128::mac_with_carry(a, b, c, carry)
+    }
+}
+
+impl Backend for U64 {
+    fn sbb(a: u64, b: u64, borrow: &mut u64) -> u64 {
+        arith_u64::sbb(a, b, borrow)
+    }
+
+    fn adc(a: u64, b: u64, carry: &mut u64) -> u64 {
+        arith_u64::adc(a, b, carry)
+    }
+
+    fn mac_with_carry(a: u64, b: u64, c: u64, carry: &mut u64) -> u64 {
+        arith_u64::mac_with_carry(a, b, c, carry)
+    }
+}
+
+const EDGE_VALUES: [u64; 12] = [
+    0,
+    1,
+    2,
+    0xffffffff,
+    0x100000000,
+    0x100000001,
+    0x7fffffffffffffff,
+    0x8000000000000000,
+    0x8000000000000001,
+    0xfffffffeffffffff,
+    0xfffffffffffffffe,
+    0xffffffffffffffff,
+];
+
+const DIFFERENTIAL_ITERATIONS: usize = 1 << 21;
+
+fn reference_sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let tmp = (1u128 << 64) + u128::from(a) - u128::from(b) - u128::from(borrow);
+    (tmp as u64, if tmp >> 64 == 0 { 1 } else { 0 })
+}
+
+fn reference_adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let tmp = u128::from(a) + u128::from(b) + u128::from(carry);
+    (tmp as u64, (tmp >> 64) as u64)
+}
+
+fn reference_mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let tmp = u128::from(a) + u128::from(b) * u128::from(c) + u128::from(carry);
+    (tmp as u64, (tmp >> 64) as u64)
+}
+
+fn check_sbb<B: Backend>(a: u64, b: u64, borrow: u64) {
+    let mut out_borrow = borrow;
+    let res = B::sbb(a, b, &mut out_borrow);
+    assert_eq!(
+        (res, out_borrow),
+        reference_sbb(a, b, borrow),
+        "sbb({:#x}, {:#x}, {})", a, b, borrow
+    );
+}
+
+fn check_adc<B: Backend>(a: u64, b: u64, carry: u64) {
+    let mut out_carry = carry;
+    let res = B::adc(a, b, &mut out_carry);
+    assert_eq!(
+        (res, out_carry),
+        reference_adc(a, b, carry),
+        "adc({:#x}, {:#x}, {:#x})", a, b, carry
+    );
+}
+
+fn check_mac<B: Backend>(a: u64, b: u64, c: u64, carry: u64) {
+    let mut out_carry = carry;
+    let res = B::mac_with_carry(a, b, c, &mut out_carry);
+    assert_eq!(
+        (res, out_carry),
+        reference_mac(a, b, c, carry),
+        "mac_with_carry({:#x}, {:#x}, {:#x}, {:#x})", a, b, c, carry
+    );
+}
+
+fn edge_value_tests<B: Backend>() {
+    for &a in EDGE_VALUES.iter() {
+        for &b in EDGE_VALUES.iter() {
+            for borrow in 0..2 {
+                check_sbb::<B>(a, b, borrow);
+                check_adc::<B>(a, b, borrow);
+            }
+
+            for &c in EDGE_VALUES.iter() {
+                for &carry in EDGE_VALUES.iter() {
+                    check_mac::<B>(a, b, c, carry);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_arith_u128_edge_values() {
+    edge_value_tests::<U128>();
+}
+
+#[test]
+fn test_arith_u64_edge_values() {
+    edge_value_tests::<U64>();
+}
+
+#[test]
+fn test_sbb_overflow_boundaries() {
+    fn check<B: Backend>() {
+        // 0 - 1 wraps around and sets the borrow.
+        let mut borrow = 0;
+        assert_eq!(B::sbb(0, 1, &mut borrow), u64::max_value());
+        assert_eq!(borrow, 1);
+
+        // 0 - 0 - 1 behaves identically.
+        let mut borrow = 1;
+        assert_eq!(B::sbb(0, 0, &mut borrow), u64::max_value());
+        assert_eq!(borrow, 1);
+
+        // 0 - MAX - 1 wraps all the way to zero.
+        let mut borrow = 1;
+        assert_eq!(B::sbb(0, u64::max_value(), &mut borrow), 0);
+        assert_eq!(borrow, 1);
+
+        // a - a - 1 borrows, a - a does not.
+        let mut borrow = 1;
+        assert_eq!(B::sbb(1 << 63, 1 << 63, &mut borrow), u64::max_value());
+        assert_eq!(borrow, 1);
+        let mut borrow = 0;
+        assert_eq!(B::sbb(1 << 63, 1 << 63, &mut borrow), 0);
+        assert_eq!(borrow, 0);
+
+        // A borrow across the 32-bit halves must not leak out.
+        let mut borrow = 0;
+        assert_eq!(B::sbb(1 << 32, 1, &mut borrow), 0xffffffff);
+        assert_eq!(borrow, 0);
+    }
+
+    check::<U128>();
+    check::<U64>();
+}
+
+#[test]
+fn test_adc_overflow_boundaries() {
+    fn check<B: Backend>() {
+        let mut carry = 1;
+        assert_eq!(B::adc(u64::max_value(), 0, &mut carry), 0);
+        assert_eq!(carry, 1);
+
+        let mut carry = 1;
+        assert_eq!(B::adc(u64::max_value(), u64::max_value(), &mut carry), u64::max_value());
+        assert_eq!(carry, 1);
+
+        let mut carry = 0;
+        assert_eq!(B::adc(u64::max_value() - 1, 1, &mut carry), u64::max_value());
+        assert_eq!(carry, 0);
+
+        // The carry out of the low 32-bit half must propagate into the high half.
+        let mut carry = 0;
+        assert_eq!(B::adc(0xffffffff, 1, &mut carry), 1 << 32);
+        assert_eq!(carry, 0);
+
+        // Carries wider than one bit are accepted.
+        let mut carry = u64::max_value();
+        assert_eq!(B::adc(u64::max_value(), u64::max_value(), &mut carry), u64::max_value() - 2);
+        assert_eq!(carry, 2);
+    }
+
+    check::<U128>();
+    check::<U64>();
+}
+
+#[test]
+fn test_mac_with_carry_overflow_boundaries() {
+    fn check<B: Backend>() {
+        // MAX + MAX * MAX + MAX = 2^128 - 1 is the largest possible result.
+        let max = u64::max_value();
+        let mut carry = max;
+        assert_eq!(B::mac_with_carry(max, max, max, &mut carry), max);
+        assert_eq!(carry, max);
+
+        // MAX * MAX = 2^128 - 2^65 + 1
+        let mut carry = 0;
+        assert_eq!(B::mac_with_carry(0, max, max, &mut carry), 1);
+        assert_eq!(carry, max - 1);
+
+        // 2^63 * 2 = 2^64
+        let mut carry = 0;
+        assert_eq!(B::mac_with_carry(0, 1 << 63, 2, &mut carry), 0);
+        assert_eq!(carry, 1);
+
+        // Cross terms of the 32-bit halves: (2^32 + 1)^2 = 2^64 + 2^33 + 1
+        let mut carry = 0;
+        assert_eq!(B::mac_with_carry(0, 0x100000001, 0x100000001, &mut carry), (1 << 33) + 1);
+        assert_eq!(carry, 1);
+
+        // Multiplying by zero only propagates the addend and the carry.
+        let mut carry = max;
+        assert_eq!(B::mac_with_carry(1, max, 0, &mut carry), 0);
+        assert_eq!(carry, 1);
+    }
+
+    check::<U128>();
+    check::<U64>();
+}
+
+/// Draws a limb biased towards the boundaries of the 32-bit halves,
+/// where the split arithmetic is most likely to go wrong.
+fn biased_limb<R: Rng>(rng: &mut R) -> u64 {
+    match rng.gen_range(0, 4) {
+        0 => EDGE_VALUES[rng.gen_range(0, EDGE_VALUES.len())],
+        1 => (rng.gen::<u64>() & 0xffffffff00000000) | 0xffffffff,
+        2 => rng.gen::<u64>() | 0x8000000080000000,
+        _ => rng.gen(),
+    }
+}
+
+#[test]
+fn test_arith_backends_differential() {
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    for _ in 0..DIFFERENTIAL_ITERATIONS {
+        let a = biased_limb(&mut rng);
+        let b = biased_limb(&mut rng);
+        let c = biased_limb(&mut rng);
+        let bit = rng.gen::<u64>() & 1;
+        let carry = biased_limb(&mut rng);
+
+        let (mut b1, mut b2) = (bit, bit);
+        assert_eq!(
+            U128::sbb(a, b, &mut b1),
+            U64::sbb(a, b, &mut b2),
+            "sbb({:#x}, {:#x}, {})", a, b, bit
+        );
+        assert_eq!(b1, b2);
+
+        let (mut c1, mut c2) = (bit, bit);
+        assert_eq!(
+            U128::adc(a, b, &mut c1),
+            U64::adc(a, b, &mut c2),
+            "adc({:#x}, {:#x}, {})", a, b, bit
+        );
+        assert_eq!(c1, c2);
+
+        let (mut c1, mut c2) = (carry, carry);
+        assert_eq!(
+            U128::mac_with_carry(a, b, c, &mut c1),
+            U64::mac_with_carry(a, b, c, &mut c2),
+            "mac_with_carry({:#x}, {:#x}, {:#x}, {:#x})", a, b, c, carry
+        );
+        assert_eq!(c1, c2);
+    }
+}
+
+/// Montgomery multiplication over an arbitrary number of limbs, written
+/// in the same shape as the unrolled `mul_assign` and `mont_reduce` of
+/// the field implementations (Algorithm 14.32 in the Handbook of Applied
+/// Cryptography) but parameterized over the arithmetic backend.
+fn mont_mul<B: Backend>(a: &[u64], b: &[u64], modulus: &[u64], inv: u64) -> Vec<u64> {
+    let n = modulus.len();
+    let mut r = vec![0u64; 2 * n];
+
+    for i in 0..n {
+        let mut carry = 0;
+        for j in 0..n {
+            r[i + j] = B::mac_with_carry(r[i + j], a[i], b[j], &mut carry);
+        }
+        r[i + n] = carry;
+    }
+
+    let mut carry2 = 0;
+    for i in 0..n {
+        let k = r[i].wrapping_mul(inv);
+        let mut carry = 0;
+        B::mac_with_carry(r[i], k, modulus[0], &mut carry);
+        for j in 1..n {
+            r[i + j] = B::mac_with_carry(r[i + j], k, modulus[j], &mut carry);
+        }
+        r[i + n] = B::adc(r[i + n], carry2, &mut carry);
+        carry2 = carry;
+    }
+
+    let mut res = r[n..].to_vec();
+
+    // Final conditional subtraction of the modulus.
+    let mut geq = true;
+    for i in (0..n).rev() {
+        if res[i] != modulus[i] {
+            geq = res[i] > modulus[i];
+            break;
+        }
+    }
+    if geq {
+        let mut borrow = 0;
+        for i in 0..n {
+            res[i] = B::sbb(res[i], modulus[i], &mut borrow);
+        }
+    }
+
+    res
+}
+
+const FQ_INV: u64 = 0x89f3fffcfffcfffd;
+const FR_INV: u64 = 0xfffffffeffffffff;
+const MONTGOMERY_ITERATIONS: usize = 100000;
+
+#[test]
+fn test_fq_montgomery_differential() {
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+    let modulus = Fq::char().0;
+
+    let mut neg_one = Fq::one();
+    neg_one.negate();
+    let mut edges = vec![Fq::zero(), Fq::one(), neg_one];
+    // The largest representable Montgomery form, q - 1, and small values.
+    let mut q_minus_one = Fq::char();
+    q_minus_one.0[0] -= 1;
+    edges.push(Fq(q_minus_one));
+    edges.push(Fq(FqRepr([1, 0, 0, 0, 0, 0])));
+    edges.push(Fq(FqRepr([u64::max_value(), 0, 0, 0, 0, 0])));
+
+    let check = |a: &Fq, b: &Fq| {
+        let r1 = mont_mul::<U128>(&(a.0).0, &(b.0).0, &modulus, FQ_INV);
+        let r2 = mont_mul::<U64>(&(a.0).0, &(b.0).0, &modulus, FQ_INV);
+        assert_eq!(r1, r2, "montgomery backends diverged on {:?} * {:?}", a, b);
+
+        let mut c = *a;
+        c.mul_assign(b);
+        assert_eq!(&(c.0).0[..], &r1[..]);
+
+        let mut c = *a;
+        c.square();
+        assert_eq!(
+            &(c.0).0[..],
+            &mont_mul::<U64>(&(a.0).0, &(a.0).0, &modulus, FQ_INV)[..]
+        );
+    };
+
+    for a in &edges {
+        for b in &edges {
+            check(a, b);
+        }
+    }
+
+    for _ in 0..MONTGOMERY_ITERATIONS {
+        let a = Fq::rand(&mut rng);
+        let b = Fq::rand(&mut rng);
+        check(&a, &b);
+    }
+}
+
+#[test]
+fn test_fr_montgomery_differential() {
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+    let modulus = Fr::char().0;
+
+    let mut q_minus_one = modulus;
+    q_minus_one[0] -= 1;
+    let edges = [
+        [0, 0, 0, 0],
+        [1, 0, 0, 0],
+        [u64::max_value(), 0, 0, 0],
+        [0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff],
+        q_minus_one,
+    ];
+
+    for a in edges.iter() {
+        for b in edges.iter() {
+            assert_eq!(
+                mont_mul::<U128>(a, b, &modulus, FR_INV),
+                mont_mul::<U64>(a, b, &modulus, FR_INV)
+            );
+        }
+    }
+
+    for _ in 0..MONTGOMERY_ITERATIONS {
+        let a = Fr::rand(&mut rng).into_repr().0;
+        let b = Fr::rand(&mut rng).into_repr().0;
+        let r = mont_mul::<U128>(&a, &b, &modulus, FR_INV);
+
+        assert_eq!(r, mont_mul::<U64>(&a, &b, &modulus, FR_INV));
+
+        // The result of a reduction is always fully reduced.
+        let mut r_repr = [0u64; 4];
+        r_repr.copy_from_slice(&r);
+        assert!(Fr::from_repr(::bls12_381::FrRepr(r_repr)).is_ok());
+    }
+}
@@ -64,6 +64,7 @@ pub fn curve_tests<G: CurveProjective>()
     random_negation_tests::<G>();
     random_transformation_tests::<G>();
     random_wnaf_tests::<G>();
+    random_multi_exp_tests::<G>();
     random_encoding_tests::<G::Affine>();
 }
 
@@ -182,6 +183,35 @@ fn random_wnaf_tests<G: CurveProjective>()
     }
 }
 
+fn random_multi_exp_tests<G: CurveProjective>()
+    where G: ::std::fmt::Debug, G::Affine: ::std::fmt::Debug
+{
+    use multiexp::naive_multi_exp;
+    use PrimeField;
+
+    let mut rng = XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    // Both sides of the window size threshold.
+    for &n in [0, 1, 2, 31, 32, 100].iter() {
+        let bases: Vec<G::Affine> = (0..n).map(|_| G::rand(&mut rng).into_affine()).collect();
+        let scalars: Vec<_> = (0..n).map(|_| G::Scalar::rand(&mut rng).into_repr()).collect();
+
+        assert_eq!(G::multi_exp(&bases, &scalars), naive_multi_exp::<G>(&bases, &scalars));
+    }
+
+    // Zero scalars and the point at infinity.
+    {
+        let bases = vec![G::Affine::zero(), G::rand(&mut rng).into_affine(), G::rand(&mut rng).into_affine()];
+        let scalars = vec![
+            G::Scalar::rand(&mut rng).into_repr(),
+            G::Scalar::zero().into_repr(),
+            G::Scalar::one().into_repr(),
+        ];
+
+        assert_eq!(G::multi_exp(&bases, &scalars), bases[2].into_projective());
+    }
+}
+
 fn random_negation_tests<G: CurveProjective>() 
     where G: ::std::fmt::Debug, G::Affine: ::std::fmt::Debug
 {
@@ -273,6 +303,36 @@ fn random_multiplication_tests<G: CurveProjective>()
 
         assert_eq!(tmp1, tmp2);
         assert_eq!(tmp1, tmp3);
+
+        // Constant-time multiplication
+        let mut tmp4 = a_affine.into_projective();
+        tmp4.mul_assign_ct(s);
+        assert_eq!(tmp4, a);
+    }
+
+    // Edge cases of the constant-time multiplication
+    {
+        let r = G::rand(&mut rng);
+
+        let mut t = r;
+        t.mul_assign_ct(G::Scalar::zero());
+        assert!(t.is_zero());
+
+        let mut t = r;
+        t.mul_assign_ct(G::Scalar::one());
+        assert_eq!(t, r);
+
+        let mut minus_one = G::Scalar::one();
+        minus_one.negate();
+        let mut t = r;
+        t.mul_assign_ct(minus_one);
+        let mut neg_r = r;
+        neg_r.negate();
+        assert_eq!(t, neg_r);
+
+        let mut t = G::zero();
+        t.mul_assign_ct(G::Scalar::rand(&mut rng));
+        assert!(t.is_zero());
     }
 }
 
@@ -1,3 +1,4 @@
+pub mod arith;
 pub mod curve;
 pub mod engine;
 pub mod field;
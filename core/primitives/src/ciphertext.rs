@@ -2,19 +2,57 @@
 use ::std::vec::Vec;
 #[cfg(not(feature = "std"))]
 use crate::std::vec::Vec;
-use crate::{PARAMS, LeftCiphertext, RightCiphertext};
+use crate::{PARAMS, EncKey, LeftCiphertext, RightCiphertext};
 use zcrypto::elgamal;
+use zcrypto::equality::{EqualityProof, EQUALITY_PROOF_SIZE};
+use keys::EncryptionKey;
+use jubjub::curve::{edwards, PrimeOrder, FixedGenerators, JubjubParams, fs::Fs};
 use pairing::{
     bls12_381::Bls12,
+    Field,
     io
 };
 use parity_codec::{Encode, Decode};
+use rand::Rng;
 use core::convert::{TryInto, TryFrom};
 
 #[derive(Eq, PartialEq, Clone, Default, Encode, Decode)]
 #[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
 pub struct Ciphertext(Vec<u8>);
 
+/// Errors of building a ciphertext from its left and right halves.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum CiphertextError {
+    /// The left half is not a point in the prime order subgroup.
+    InvalidLeft,
+    /// The right half is not a point in the prime order subgroup.
+    InvalidRight,
+    /// The left half is the identity.
+    IdentityLeft,
+    /// The right half is the identity, so the ciphertext isn't randomized.
+    IdentityRight,
+    /// Failed to serialize the ciphertext.
+    Encoding,
+}
+
+impl CiphertextError {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            CiphertextError::InvalidLeft => "Invalid left ciphertext: not a point in the prime order subgroup.",
+            CiphertextError::InvalidRight => "Invalid right ciphertext: not a point in the prime order subgroup.",
+            CiphertextError::IdentityLeft => "Invalid left ciphertext: the identity point.",
+            CiphertextError::IdentityRight => "Invalid right ciphertext: the identity point.",
+            CiphertextError::Encoding => "Failed to encode ciphertext.",
+        }
+    }
+}
+
+impl From<CiphertextError> for &'static str {
+    fn from(e: CiphertextError) -> &'static str {
+        e.as_str()
+    }
+}
+
 impl TryFrom<elgamal::Ciphertext<Bls12>> for Ciphertext {
     type Error = io::Error;
 
@@ -78,13 +116,83 @@ impl Ciphertext {
         Ciphertext(slice.to_vec())
     }
 
-    pub fn from_left_right(left: LeftCiphertext, right: RightCiphertext) -> Result<Self, io::Error> {
-        elgamal::Ciphertext::new(
-            left.try_into()?,
-            right.try_into()?
-        )
-        .try_into()
-        .map_err(|_| io::Error::InvalidData)
+    /// Build a ciphertext from its halves, which must be points in the prime order subgroup other than the identity.
+    pub fn from_left_right(left: LeftCiphertext, right: RightCiphertext) -> Result<Self, CiphertextError> {
+        let left: edwards::Point<Bls12, PrimeOrder> = left.try_into()
+            .map_err(|_| CiphertextError::InvalidLeft)?;
+        let right: edwards::Point<Bls12, PrimeOrder> = right.try_into()
+            .map_err(|_| CiphertextError::InvalidRight)?;
+
+        if left == edwards::Point::zero() {
+            return Err(CiphertextError::IdentityLeft);
+        }
+        if right == edwards::Point::zero() {
+            return Err(CiphertextError::IdentityRight);
+        }
+
+        elgamal::Ciphertext::new(left, right)
+            .try_into()
+            .map_err(|_| CiphertextError::Encoding)
+    }
+
+    /// Encrypt a public amount under the key with the randomness one, as the genesis balances are.
+    /// Anyone can tell the amount, so it is only for amounts which are public anyway, e.g. of a faucet.
+    pub fn encrypt_public(amount: u64, enc_key: &EncKey) -> Result<Self, io::Error> {
+        let enc_key = EncryptionKey::<Bls12>::try_from(enc_key)?;
+
+        elgamal::Ciphertext::encrypt(amount, &Fs::one(), &enc_key, FixedGenerators::Diversifier, &*PARAMS)
+            .try_into()
+    }
+
+    /// Check the ciphertext encrypts `amount` under `enc_key::revealing_enc_key`,
+    /// i.e. its left is the amount in the exponent plus its right.
+    pub fn reveals(&self, amount: u64) -> Result<bool, io::Error> {
+        let ciphertext = elgamal::Ciphertext::<Bls12>::try_from(self)?;
+        let v_point = PARAMS.generator(FixedGenerators::Diversifier).mul(amount, &*PARAMS);
+
+        Ok(ciphertext.left == v_point.add(&ciphertext.right, &*PARAMS))
+    }
+
+    /// Prove the ciphertext encrypts the public `amount` under `enc_key` with `randomness`,
+    /// e.g. that the fee of a transfer is the fee the chain charges, without revealing the randomness.
+    pub fn prove_amount<R: Rng>(
+        &self,
+        amount: u64,
+        randomness: &Fs,
+        enc_key: &EncKey,
+        rng: &mut R
+    ) -> Result<Vec<u8>, io::Error> {
+        let ciphertext = elgamal::Ciphertext::<Bls12>::try_from(self)?;
+        let enc_key = EncryptionKey::<Bls12>::try_from(enc_key)?;
+
+        let mut proof = Vec::with_capacity(EQUALITY_PROOF_SIZE);
+        EqualityProof::prove_amount(randomness, &ciphertext.left, &enc_key, amount, FixedGenerators::Diversifier, rng, &*PARAMS)
+            .write(&mut proof)?;
+
+        Ok(proof)
+    }
+
+    /// Check `proof` proves the ciphertext encrypts the public `amount` under `enc_key`.
+    pub fn verify_amount(&self, amount: u64, enc_key: &EncKey, proof: &[u8]) -> Result<bool, io::Error> {
+        if proof.len() != EQUALITY_PROOF_SIZE {
+            return Err(io::Error::InvalidData);
+        }
+
+        let ciphertext = elgamal::Ciphertext::<Bls12>::try_from(self)?;
+        let enc_key = EncryptionKey::<Bls12>::try_from(enc_key)?;
+        let proof = EqualityProof::<Bls12>::read(&mut &proof[..])?;
+
+        Ok(proof.verify_amount(&ciphertext.left, &enc_key, amount, &ciphertext.right, FixedGenerators::Diversifier, &*PARAMS))
+    }
+
+    /// Refresh the randomness of the ciphertext encrypted by `enc_key` without changing the amount,
+    /// so that it can't be linked to the original one.
+    pub fn rerandomize(&self, randomness: &Fs, enc_key: &EncKey) -> Result<Self, io::Error> {
+        let enc_key = EncryptionKey::<Bls12>::try_from(enc_key)?;
+
+        elgamal::Ciphertext::<Bls12>::try_from(self)?
+            .rerandomize(randomness, &enc_key, FixedGenerators::Diversifier, &*PARAMS)
+            .try_into()
     }
 
     pub fn add(&self, other: &Self) -> Result<Self, io::Error> {
@@ -126,7 +234,7 @@ impl Ciphertext {
 mod tests {
     use super::*;
     use rand::{Rng, SeedableRng, XorShiftRng};
-    use jubjub::curve::{FixedGenerators, JubjubBls12};
+    use jubjub::curve::{FixedGenerators, JubjubBls12, Unknown};
     use parity_codec::{Encode, Decode};
     use keys::EncryptionKey;
 
@@ -140,7 +248,7 @@ mod tests {
         let seed: [u8; 32] = rng_seed.gen();
 
         let enc_key = EncryptionKey::from_seed(&seed[..], params).unwrap();
-        let amount: u32 = 5 as u32;
+        let amount: u64 = 5;
 
         elgamal::Ciphertext::encrypt(amount, &randomness, &enc_key, p_g, params)
     }
@@ -193,4 +301,75 @@ mod tests {
 
         assert!(ciphertext == ciphertext2);
     }
+
+    #[test]
+    fn test_from_left_right_rejects_invalid_halves() {
+        let ciphertext = gen_ciphertext();
+
+        let mut buf = [0u8; 64];
+        ciphertext.write(&mut &mut buf[..]).unwrap();
+        let left = LeftCiphertext::from_slice(&buf[..32]);
+        let right = RightCiphertext::from_slice(&buf[32..]);
+
+        let mut identity = [0u8; 32];
+        edwards::Point::<Bls12, PrimeOrder>::zero().write(&mut &mut identity[..]).unwrap();
+
+        // Not on the curve
+        let mut invalid = [0u8; 32];
+        invalid[31] = 0x7f;
+        invalid[0] = 0xff;
+        assert!(edwards::Point::<Bls12, Unknown>::read(&mut &invalid[..], &*PARAMS).is_err());
+
+        assert_eq!(
+            Ciphertext::from_left_right(LeftCiphertext::from_slice(&invalid[..]), right),
+            Err(CiphertextError::InvalidLeft)
+        );
+        assert_eq!(
+            Ciphertext::from_left_right(left, RightCiphertext::from_slice(&invalid[..])),
+            Err(CiphertextError::InvalidRight)
+        );
+        assert_eq!(
+            Ciphertext::from_left_right(LeftCiphertext::from_slice(&identity[..]), right),
+            Err(CiphertextError::IdentityLeft)
+        );
+        assert_eq!(
+            Ciphertext::from_left_right(left, RightCiphertext::from_slice(&identity[..])),
+            Err(CiphertextError::IdentityRight)
+        );
+    }
+
+    #[test]
+    fn test_reveals() {
+        let rng = &mut XorShiftRng::from_seed([0xbc4f6d47, 0xd62f276d, 0xb963afd3, 0x54558639]);
+        let enc_key = EncryptionKey::<Bls12>::try_from(crate::enc_key::revealing_enc_key()).unwrap();
+        let ciphertext = elgamal::Ciphertext::encrypt(7, &rng.gen(), &enc_key, FixedGenerators::Diversifier, &*PARAMS);
+        let ciphertext = Ciphertext::try_from(ciphertext).unwrap();
+
+        assert!(ciphertext.reveals(7).unwrap());
+        assert!(!ciphertext.reveals(8).unwrap());
+
+        // Amounts encrypted under other keys aren't revealed.
+        assert!(!Ciphertext::try_from(gen_ciphertext()).unwrap().reveals(5).unwrap());
+    }
+
+    #[test]
+    fn test_amount_proof() {
+        let rng = &mut XorShiftRng::from_seed([0xbc4f6d47, 0xd62f276d, 0xb963afd3, 0x54558639]);
+        let seed: [u8; 32] = rng.gen();
+        let randomness: Fs = rng.gen();
+        let enc_key = EncryptionKey::<Bls12>::from_seed(&seed[..], &*PARAMS).unwrap();
+        let ciphertext = elgamal::Ciphertext::encrypt(3, &randomness, &enc_key, FixedGenerators::Diversifier, &*PARAMS);
+        let ciphertext = Ciphertext::try_from(ciphertext).unwrap();
+        let enc_key = EncKey::try_from(enc_key).unwrap();
+
+        let proof = ciphertext.prove_amount(3, &randomness, &enc_key, rng).unwrap();
+        assert_eq!(proof.len(), EQUALITY_PROOF_SIZE);
+        assert!(ciphertext.verify_amount(3, &enc_key, &proof[..]).unwrap());
+
+        // An underpaid amount isn't proven, and a truncated proof is rejected.
+        assert!(!ciphertext.verify_amount(2, &enc_key, &proof[..]).unwrap());
+        assert!(!ciphertext.prove_amount(2, &randomness, &enc_key, rng)
+            .and_then(|p| ciphertext.verify_amount(2, &enc_key, &p[..])).unwrap());
+        assert!(ciphertext.verify_amount(3, &enc_key, &proof[..32]).is_err());
+    }
 }
@@ -4,7 +4,8 @@ use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use substrate_primitives::hexdisplay::AsBytesRef;
 #[cfg(feature = "std")]
 use substrate_primitives::bytes;
-use keys::EncryptionKey;
+use keys::{EncryptionKey, DecryptionKey, Diversifier};
+use jubjub::curve::{FixedGenerators, JubjubParams};
 use fixed_hash::construct_fixed_hash;
 use pairing::bls12_381::{Bls12, Fr};
 use pairing::io;
@@ -103,6 +104,19 @@ impl IntoXY<Bls12> for &EncKey {
     }
 }
 
+/// The encryption key whose decryption key is one, i.e. the generator encryption keys are derived from.
+/// Anyone can decrypt the amounts encrypted under it, so a transfer to it reveals its amount.
+pub fn revealing_enc_key() -> EncKey {
+    EncKey::try_from(EncryptionKey(PARAMS.generator(FixedGenerators::Diversifier).clone()))
+        .expect("The generator should be a valid encryption key.")
+}
+
+/// The account of the encryption key diversified by `diversifier` of the decryption key.
+/// The accounts of the diversified encryption keys of a decryption key are unlinkable on chain.
+pub fn diversified_enc_key(dec_key: &DecryptionKey<Bls12>, diversifier: &Diversifier) -> Result<EncKey, io::Error> {
+    EncKey::try_from(EncryptionKey::from_diversifier(dec_key, diversifier, &*PARAMS)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,4 +134,15 @@ mod tests {
         let addr2 = account_id.try_into().unwrap();
         assert!(addr1 == addr2);
     }
+
+    #[test]
+    fn test_diversified_enc_key() {
+        let dec_key = keys::ProofGenerationKey::<Bls12>::from_seed(b"Alice                           ", &*PARAMS)
+            .into_decryption_key()
+            .unwrap();
+        let diversified = diversified_enc_key(&dec_key, &Diversifier::from_index(1)).unwrap();
+
+        assert!(diversified != EncKey::try_from(EncryptionKey::from_decryption_key(&dec_key, &*PARAMS)).unwrap());
+        assert!(EncryptionKey::<Bls12>::try_from(diversified).is_ok());
+    }
 }
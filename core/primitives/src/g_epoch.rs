@@ -5,7 +5,7 @@ use substrate_primitives::bytes;
 use crate::{PARAMS, IntoXY};
 use fixed_hash::construct_fixed_hash;
 use jubjub::curve::{JubjubBls12, edwards, PrimeOrder, Unknown};
-use jubjub::group_hash::group_hash;
+use jubjub::group_hash::{group_hash, hash_to_curve};
 use pairing::{
     bls12_381::{Bls12, Fr},
     io
@@ -16,6 +16,7 @@ use core::convert::TryFrom;
 
 const SIZE: usize = 32;
 const GEPOCH_PERSONALIZATION: &[u8; 8] = b"zcgepoch";
+const GEPOCH_DST: &[u8] = b"Zerochain-GEpoch-Jubjub-BLAKE2s-TAI";
 
 construct_fixed_hash! {
     pub struct H256(SIZE);
@@ -23,6 +24,38 @@ construct_fixed_hash! {
 
 pub type GEpoch = H256;
 
+/// The hash-to-curve g_epochs are derived with.
+/// The version is stored on chain as a byte and switched at an epoch boundary,
+/// so the derivation can migrate without changing the g_epochs of past epochs.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum GEpochVersion {
+    /// `group_hash` with a counter appended to the epoch, as g_epochs were derived originally.
+    V1 = 0,
+    /// `hash_to_curve` with the domain separation tag `GEPOCH_DST`.
+    V2 = 1,
+}
+
+impl Default for GEpochVersion {
+    fn default() -> Self {
+        GEpochVersion::V1
+    }
+}
+
+impl GEpochVersion {
+    pub fn from_u8(version: u8) -> Option<Self> {
+        match version {
+            0 => Some(GEpochVersion::V1),
+            1 => Some(GEpochVersion::V2),
+            _ => None,
+        }
+    }
+
+    pub fn as_u8(&self) -> u8 {
+        *self as u8
+    }
+}
+
 #[cfg(feature = "std")]
 impl Serialize for GEpoch {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -100,11 +133,19 @@ impl GEpoch {
     }
 
     pub fn group_hash(curr_epoch: u32) -> Result<Self, io::Error> {
+        Self::group_hash_with_version(curr_epoch, GEpochVersion::V1)
+    }
+
+    pub fn group_hash_with_version(curr_epoch: u32, version: GEpochVersion) -> Result<Self, io::Error> {
         let mut epoch = [0u8; 4];
         LittleEndian::write_u32(&mut epoch, curr_epoch);
 
-        // Hash_to_curve(GEPOCH_PERSONALIZATION || current_epoch)
-        let g_epoch = find_group_hash(&epoch, GEPOCH_PERSONALIZATION, &PARAMS);
+        let g_epoch = match version {
+            // Hash_to_curve(GEPOCH_PERSONALIZATION || current_epoch)
+            GEpochVersion::V1 => find_group_hash(&epoch, GEPOCH_PERSONALIZATION, &PARAMS),
+            GEpochVersion::V2 => hash_to_curve(&epoch, GEPOCH_DST, &PARAMS)
+                .ok_or(io::Error::NotInField)?,
+        };
         GEpoch::try_from(g_epoch)
     }
 }
@@ -169,4 +210,18 @@ mod tests {
         println!("zero: {:?}", new_g_epoch);
         assert_eq!(new_g_epoch, zero_g_epch);
     }
+
+    #[test]
+    fn test_group_hash_versions() {
+        assert_eq!(GEpoch::group_hash_with_version(3, GEpochVersion::V1).unwrap(), GEpoch::group_hash(3).unwrap());
+
+        let g_epoch = GEpoch::group_hash_with_version(3, GEpochVersion::V2).unwrap();
+        assert_eq!(g_epoch, GEpoch::group_hash_with_version(3, GEpochVersion::V2).unwrap());
+        assert_ne!(g_epoch, GEpoch::group_hash(3).unwrap());
+        assert_ne!(g_epoch, GEpoch::group_hash_with_version(4, GEpochVersion::V2).unwrap());
+        assert!(edwards::Point::<Bls12, PrimeOrder>::try_from(g_epoch).is_ok());
+
+        assert_eq!(GEpochVersion::from_u8(GEpochVersion::V2.as_u8()), Some(GEpochVersion::V2));
+        assert_eq!(GEpochVersion::from_u8(2), None);
+    }
 }
@@ -0,0 +1,94 @@
+//! Checks the SCALE encodings of the on-chain types against the golden file `golden/encodings.txt`,
+//! so that a refactoring can't change an encoding, and with it the consensus, by accident.
+//! An intended change of an encoding bumps `ENCODING_VERSION` and updates the golden file with it.
+
+use crate::{
+    ENCODING_VERSION, Proof, Ciphertext, EncKey, Nonce, GEpoch, RedjubjubSignature,
+};
+use bellman_verifier::{VerifyingKey, PreparedVerifyingKey, prepare_verifying_key};
+use pairing::{CurveAffine, bls12_381::{Bls12, G1Affine, G2Affine}};
+use parity_codec::{Encode, Decode};
+use blake2_rfc::blake2b::blake2b;
+use std::fmt::Debug;
+
+const GOLDEN: &str = include_str!("../golden/encodings.txt");
+
+fn golden(name: &str) -> Vec<u8> {
+    let hex = GOLDEN.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some(n), Some(value)) if n == name => Some(value),
+                _ => None,
+            }
+        })
+        .next()
+        .unwrap_or_else(|| panic!("no golden encoding of {}", name));
+
+    (0..hex.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("the golden file is hex; qed"))
+        .collect()
+}
+
+fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|i| i as u8).collect()
+}
+
+fn check<T: Encode + Decode + PartialEq + Debug>(name: &str, value: T) {
+    let encoded = value.encode();
+    assert_eq!(
+        encoded, golden(name),
+        "The encoding of {} changed. Bump ENCODING_VERSION and update golden/encodings.txt if it is intended.", name
+    );
+    assert_eq!(T::decode(&mut &encoded[..]), Some(value));
+}
+
+#[test]
+fn test_golden_file_version() {
+    let version = GOLDEN.lines()
+        .find(|line| line.starts_with("version "))
+        .and_then(|line| line["version ".len()..].trim().parse::<u32>().ok());
+
+    assert_eq!(
+        version, Some(ENCODING_VERSION),
+        "golden/encodings.txt must be updated together with ENCODING_VERSION."
+    );
+}
+
+#[test]
+fn test_golden_encodings() {
+    check("proof", Proof::from_slice(&pattern(192)));
+    check("ciphertext", Ciphertext::from_slice(&pattern(64)));
+    check("enc_key", EncKey::from_slice(&pattern(32)));
+    check("nonce", Nonce::from_slice(&pattern(32)));
+    check("g_epoch", GEpoch::from_slice(&pattern(32)));
+
+    // The most significant byte of `s` is cleared so that it is canonical.
+    let mut sig = pattern(64);
+    sig[63] = 0;
+    check("redjubjub_signature", RedjubjubSignature::from_slice(&sig));
+}
+
+#[test]
+fn test_golden_verifying_key_encodings() {
+    let vk = VerifyingKey::<Bls12> {
+        alpha_g1: G1Affine::one(),
+        beta_g1: G1Affine::one(),
+        beta_g2: G2Affine::one(),
+        gamma_g2: G2Affine::one(),
+        delta_g1: G1Affine::one(),
+        delta_g2: G2Affine::one(),
+        ic: vec![G1Affine::one(), G1Affine::one()],
+    };
+    let pvk = prepare_verifying_key(&vk);
+
+    let encoded = pvk.encode();
+    assert_eq!(
+        blake2b(32, &[], &encoded[..]).as_bytes(), &golden("prepared_verifying_key")[..],
+        "The encoding of PreparedVerifyingKey changed. Bump ENCODING_VERSION and update golden/encodings.txt if it is intended."
+    );
+    assert!(PreparedVerifyingKey::<Bls12>::decode(&mut &encoded[..]) == Some(pvk));
+
+    check("uninitialized_verifying_key", PreparedVerifyingKey::<Bls12>::Uninitialized);
+}
@@ -26,21 +26,33 @@ pub mod nonce;
 pub mod g_epoch;
 pub mod right_ciphertext;
 pub mod left_ciphertext;
+pub mod public_input;
+pub mod memo;
+pub mod ss58;
+pub mod type_info;
+#[cfg(test)]
+mod golden;
 
 pub use self::enc_key::EncKey;
 pub use self::signature::RedjubjubSignature;
-pub use self::ciphertext::Ciphertext;
+pub use self::ciphertext::{Ciphertext, CiphertextError};
 pub use self::proof::Proof;
 pub use self::sig_vk::{SigVerificationKey, SigVk};
 pub use self::nonce::Nonce;
-pub use self::g_epoch::GEpoch;
+pub use self::g_epoch::{GEpoch, GEpochVersion};
 pub use self::right_ciphertext::RightCiphertext;
 pub use self::left_ciphertext::LeftCiphertext;
+pub use self::memo::Memo;
+pub use self::ss58::AddressFormat;
 
 use lazy_static::lazy_static;
 use jubjub::curve::{JubjubBls12, JubjubEngine};
 use pairing::io;
 
+/// The version of the SCALE encodings of the on-chain types, which are part of the consensus.
+/// Bump it, together with `golden/encodings.txt`, whenever one of the encodings changes.
+pub const ENCODING_VERSION: u32 = 1;
+
 lazy_static! {
     pub static ref PARAMS: JubjubBls12 = { JubjubBls12::new() };
 }
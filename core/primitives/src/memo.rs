@@ -0,0 +1,72 @@
+//! An encrypted memo attached to a confidential transfer, e.g. a payment reference.
+//! It is encrypted to the recipient's key by the sender, and the runtime only carries it in an event.
+#[cfg(feature = "std")]
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+#[cfg(feature = "std")]
+use substrate_primitives::bytes;
+#[cfg(feature = "std")]
+use substrate_primitives::hexdisplay::AsBytesRef;
+use fixed_hash::construct_fixed_hash;
+use parity_codec::{Encode, Decode, Input};
+
+/// The length of an encrypted memo.
+pub const MEMO_SIZE: usize = 64;
+
+construct_fixed_hash! {
+    pub struct H512(MEMO_SIZE);
+}
+
+pub type Memo = H512;
+
+#[cfg(feature = "std")]
+impl Serialize for Memo {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        bytes::serialize(&self.0, serializer)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for Memo {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        bytes::deserialize_check_len(deserializer, bytes::ExpectedLen::Exact(MEMO_SIZE))
+            .map(|x| Memo::from_slice(&x))
+    }
+}
+
+impl Encode for Memo {
+    fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+        self.0.using_encoded(f)
+    }
+}
+
+impl Decode for Memo {
+    fn decode<I: Input>(input: &mut I) -> Option<Self> {
+        <[u8; MEMO_SIZE] as Decode>::decode(input).map(H512)
+    }
+}
+
+#[cfg(feature = "std")]
+impl AsBytesRef for Memo {
+    fn as_bytes_ref(&self) -> &[u8] {
+        self.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        let memo = Memo::from_slice(&[7u8; MEMO_SIZE]);
+        let encoded = memo.encode();
+
+        assert_eq!(encoded.len(), MEMO_SIZE);
+        assert_eq!(Memo::decode(&mut &encoded[..]), Some(memo));
+        assert_eq!(Option::<Memo>::decode(&mut &Some(memo).encode()[..]), Some(Some(memo)));
+    }
+}
@@ -0,0 +1,238 @@
+//! Typed schemas of the public inputs of the circuits.
+//! A schema is a struct with a field per input of its circuit in the order the circuit inputizes them,
+//! from which both the size and the public input are derived.
+//! Adding an input to a schema changes its size and makes every verifier and prover building it
+//! fail to compile until they give the new input, instead of failing a length check at runtime.
+#[cfg(feature = "std")]
+use ::std::vec::Vec;
+#[cfg(not(feature = "std"))]
+use crate::std::vec::Vec;
+use crate::{IntoXY, EncKey, LeftCiphertext, RightCiphertext, Ciphertext, Nonce, GEpoch};
+use pairing::bls12_381::{Bls12, Fr};
+use pairing::PrimeField;
+use pairing::io;
+
+/// The number of accounts an anonymous transfer hides among, including the sender and the recipient.
+pub const ANONYMITY_SET_SIZE: usize = 12;
+
+/// Points of a public input, each of which is inputized as its two affine coordinates.
+pub trait InputPoints {
+    /// The number of field elements of the points.
+    const LEN: usize;
+
+    /// Push the coordinates of the points. The coordinates of encryption keys are given by `enc_key_xy`,
+    /// so that verifiers can cache them.
+    fn push_xy<K>(&self, inputs: &mut Vec<Fr>, enc_key_xy: &K) -> Result<(), io::Error>
+    where
+        K: Fn(&EncKey) -> Result<(Fr, Fr), io::Error>;
+}
+
+fn push_point<P: IntoXY<Bls12>>(point: &P, inputs: &mut Vec<Fr>) -> Result<(), io::Error> {
+    let (x, y) = point.into_xy()?;
+    inputs.push(x);
+    inputs.push(y);
+
+    Ok(())
+}
+
+impl InputPoints for EncKey {
+    const LEN: usize = 2;
+
+    fn push_xy<K>(&self, inputs: &mut Vec<Fr>, enc_key_xy: &K) -> Result<(), io::Error>
+    where
+        K: Fn(&EncKey) -> Result<(Fr, Fr), io::Error>,
+    {
+        let (x, y) = enc_key_xy(self)?;
+        inputs.push(x);
+        inputs.push(y);
+
+        Ok(())
+    }
+}
+
+macro_rules! impl_input_point {
+    ($($ty:ty),*) => {
+        $(
+            impl InputPoints for $ty {
+                const LEN: usize = 2;
+
+                fn push_xy<K>(&self, inputs: &mut Vec<Fr>, _enc_key_xy: &K) -> Result<(), io::Error>
+                where
+                    K: Fn(&EncKey) -> Result<(Fr, Fr), io::Error>,
+                {
+                    push_point(self, inputs)
+                }
+            }
+        )*
+    }
+}
+
+impl_input_point!(LeftCiphertext, RightCiphertext, Nonce, GEpoch);
+
+/// A public amount is inputized as a single field element.
+impl InputPoints for u64 {
+    const LEN: usize = 1;
+
+    fn push_xy<K>(&self, inputs: &mut Vec<Fr>, _enc_key_xy: &K) -> Result<(), io::Error>
+    where
+        K: Fn(&EncKey) -> Result<(Fr, Fr), io::Error>,
+    {
+        let repr = <Fr as PrimeField>::Repr::from(*self);
+        inputs.push(Fr::from_repr(repr).map_err(|_| io::Error::InvalidData)?);
+
+        Ok(())
+    }
+}
+
+/// A ciphertext is inputized as its left and then its right.
+impl InputPoints for Ciphertext {
+    const LEN: usize = 4;
+
+    fn push_xy<K>(&self, inputs: &mut Vec<Fr>, _enc_key_xy: &K) -> Result<(), io::Error>
+    where
+        K: Fn(&EncKey) -> Result<(Fr, Fr), io::Error>,
+    {
+        push_point(&self.left()?, inputs)?;
+        push_point(&self.right()?, inputs)
+    }
+}
+
+impl<T: InputPoints> InputPoints for [T; ANONYMITY_SET_SIZE] {
+    const LEN: usize = T::LEN * ANONYMITY_SET_SIZE;
+
+    fn push_xy<K>(&self, inputs: &mut Vec<Fr>, enc_key_xy: &K) -> Result<(), io::Error>
+    where
+        K: Fn(&EncKey) -> Result<(Fr, Fr), io::Error>,
+    {
+        for points in self.iter() {
+            points.push_xy(inputs, enc_key_xy)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Declare the schema of a circuit and the constant of its size.
+/// Every circuit ends with the inputs of the spend authority: rvk, g_epoch and the nonce,
+/// which are appended to the given fields. rvk is generic, as it is the account id of the runtime.
+macro_rules! decl_public_input {
+    (
+        $(#[$attr:meta])*
+        pub struct $name:ident, $size:ident {
+            $( $(#[$field_attr:meta])* pub $field:ident : $ty:ty, )*
+        }
+    ) => {
+        /// The number of public inputs of the circuit.
+        pub const $size: usize = 0 $( + <$ty as InputPoints>::LEN )* + 3 * 2;
+
+        $(#[$attr])*
+        pub struct $name<'a, R: 'a> {
+            $( $(#[$field_attr])* pub $field: &'a $ty, )*
+            /// The re-randomized spend authority key of the sender.
+            pub rvk: &'a R,
+            /// The generator of the epoch the proof is bound to.
+            pub g_epoch: &'a GEpoch,
+            /// The nonce of the sender in the epoch, which can be used only once.
+            pub nonce: &'a Nonce,
+        }
+
+        impl<'a, R: IntoXY<Bls12>> $name<'a, R> {
+            /// The public input, in which the coordinates of encryption keys are given by `enc_key_xy`.
+            pub fn to_vec_with<K>(&self, enc_key_xy: K) -> Result<Vec<Fr>, &'static str>
+            where
+                K: Fn(&EncKey) -> Result<(Fr, Fr), io::Error>,
+            {
+                let mut inputs = Vec::with_capacity($size);
+                $(
+                    self.$field.push_xy(&mut inputs, &enc_key_xy)
+                        .map_err(|_| concat!("Faild to get ", stringify!($field), " into xy."))?;
+                )*
+                push_point(self.rvk, &mut inputs).map_err(|_| "Faild to get rvk into xy.")?;
+                push_point(self.g_epoch, &mut inputs).map_err(|_| "Faild to get g_epoch into xy.")?;
+                push_point(self.nonce, &mut inputs).map_err(|_| "Faild to get nonce into xy.")?;
+
+                Ok(inputs)
+            }
+
+            /// The public input.
+            pub fn to_vec(&self) -> Result<Vec<Fr>, &'static str> {
+                self.to_vec_with(|enc_key: &EncKey| enc_key.into_xy())
+            }
+        }
+    }
+}
+
+decl_public_input! {
+    /// The public input of the confidential transfer circuit.
+    pub struct ConfidentialInput, CONFIDENTIAL_INPUT_SIZE {
+        pub address_sender: EncKey,
+        pub address_recipient: EncKey,
+        pub amount_sender: LeftCiphertext,
+        pub amount_recipient: LeftCiphertext,
+        pub randomness: RightCiphertext,
+        pub fee_sender: LeftCiphertext,
+        pub balance_sender: Ciphertext,
+    }
+}
+
+decl_public_input! {
+    /// The public input of the anonymous transfer circuit.
+    /// The balances of the anonymity set are inputized as all their lefts and then all their rights.
+    pub struct AnonymousInput, ANONYMOUS_INPUT_SIZE {
+        pub enc_keys: [EncKey; ANONYMITY_SET_SIZE],
+        pub left_ciphertexts: [LeftCiphertext; ANONYMITY_SET_SIZE],
+        pub enc_balances_left: [LeftCiphertext; ANONYMITY_SET_SIZE],
+        pub enc_balances_right: [RightCiphertext; ANONYMITY_SET_SIZE],
+        pub right_ciphertext: RightCiphertext,
+        /// The fee of the chain, which every anonymous transfer pays.
+        pub fee: u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SigVerificationKey;
+    use pairing::Field;
+
+    #[test]
+    fn test_input_sizes() {
+        // The numbers of public inputs of the verifying keys of the circuits.
+        assert_eq!(CONFIDENTIAL_INPUT_SIZE, 22);
+        assert_eq!(ANONYMOUS_INPUT_SIZE, 105);
+    }
+
+    #[test]
+    fn test_invalid_input_is_named() {
+        let g_epoch = GEpoch::try_new().unwrap();
+        let valid = EncKey::from_slice(g_epoch.as_bytes());
+        let invalid = EncKey::from_slice(&[0xff; 32]);
+        let left = LeftCiphertext::from_slice(g_epoch.as_bytes());
+        let right = RightCiphertext::from_slice(g_epoch.as_bytes());
+        let balance = Ciphertext::from_left_right(left, right).unwrap();
+        let rvk = SigVerificationKey::from_slice(g_epoch.as_bytes());
+        let nonce = Nonce::from_slice(g_epoch.as_bytes());
+
+        let input = ConfidentialInput {
+            address_sender: &valid,
+            address_recipient: &invalid,
+            amount_sender: &left,
+            amount_recipient: &left,
+            randomness: &right,
+            fee_sender: &left,
+            balance_sender: &balance,
+            rvk: &rvk,
+            g_epoch: &g_epoch,
+            nonce: &nonce,
+        };
+        assert_eq!(input.to_vec(), Err("Faild to get address_recipient into xy."));
+
+        let input = ConfidentialInput { address_recipient: &valid, ..input };
+        assert_eq!(input.to_vec().unwrap().len(), CONFIDENTIAL_INPUT_SIZE);
+
+        // The cached coordinates are used for encryption keys.
+        let inputs = input.to_vec_with(|_| Ok((Fr::one(), Fr::one()))).unwrap();
+        assert_eq!(&inputs[..4], &[Fr::one(); 4][..]);
+        assert_eq!(&inputs[4..], &input.to_vec().unwrap()[4..]);
+    }
+}
@@ -68,7 +68,7 @@ impl TryFrom<SigVerificationKey> for redjubjub::PublicKey<Bls12> {
     type Error = io::Error;
 
     fn try_from(sig_vk: SigVerificationKey) -> Result<Self, io::Error> {
-        redjubjub::PublicKey::read(&mut &sig_vk.0[..], &*PARAMS)
+        read_canonical(&sig_vk.0)
     }
 }
 
@@ -76,10 +76,36 @@ impl TryFrom<&SigVerificationKey> for redjubjub::PublicKey<Bls12> {
     type Error = io::Error;
 
     fn try_from(sig_vk: &SigVerificationKey) -> Result<Self, io::Error> {
-        redjubjub::PublicKey::read(&mut &sig_vk.0[..], &*PARAMS)
+        read_canonical(&sig_vk.0)
     }
 }
 
+impl TryFrom<&[u8]> for SigVerificationKey {
+    type Error = io::Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, io::Error> {
+        if bytes.len() != SIZE {
+            return Err(io::Error::InvalidData);
+        }
+
+        Ok(H256::from_slice(bytes))
+    }
+}
+
+// A point with x = 0 can be encoded with either sign bit, so a key is only accepted
+// in the encoding it is written back to, and a signer has exactly one account.
+fn read_canonical(bytes: &[u8; SIZE]) -> Result<redjubjub::PublicKey<Bls12>, io::Error> {
+    let vk = redjubjub::PublicKey::<Bls12>::read(&mut &bytes[..], &*PARAMS)?;
+
+    let mut written = [0u8; SIZE];
+    vk.write(&mut &mut written[..])?;
+    if &written != bytes {
+        return Err(io::Error::InvalidData);
+    }
+
+    Ok(vk)
+}
+
 impl IntoXY<Bls12> for SigVerificationKey {
     fn into_xy(&self) -> Result<(Fr, Fr), io::Error> {
         let point = redjubjub::PublicKey::<Bls12>::try_from(self)?
@@ -156,4 +182,47 @@ mod tests {
         let decoded_vk = SigVerificationKey::decode(&mut encoded_vk.as_slice()).unwrap();
         assert_eq!(vk_b, decoded_vk);
     }
+
+    #[test]
+    fn test_vk_decode_rejects_wrong_length() {
+        let encoded = [1u8; SIZE];
+        for len in 0..SIZE {
+            assert!(SigVerificationKey::decode(&mut &encoded[..len]).is_none());
+            assert!(SigVerificationKey::try_from(&encoded[..len]).is_err());
+        }
+        assert!(SigVerificationKey::try_from(&[1u8; SIZE + 1][..]).is_err());
+        assert!(SigVerificationKey::try_from(&encoded[..]).is_ok());
+    }
+
+    #[test]
+    fn test_vk_rejects_non_canonical_point() {
+        // y = 1 is the identity, whose x is 0, so setting the sign bit doesn't change the point.
+        let mut identity = [0u8; SIZE];
+        identity[0] = 1;
+        assert!(PublicKey::<Bls12>::try_from(H256::from_slice(&identity)).is_ok());
+
+        identity[SIZE - 1] |= 0x80;
+        assert!(PublicKey::<Bls12>::try_from(H256::from_slice(&identity)).is_err());
+    }
+
+    #[test]
+    fn fuzz_vk_decode() {
+        let rng = &mut XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        for _ in 0..10000 {
+            let len = rng.gen_range(0, 2 * SIZE);
+            let bytes: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+
+            match SigVerificationKey::decode(&mut bytes.as_slice()) {
+                Some(vk) => {
+                    assert!(len >= SIZE);
+                    // Whatever was decoded, converting it must fail cleanly or round trip.
+                    if let Ok(point) = PublicKey::<Bls12>::try_from(vk) {
+                        assert_eq!(SigVerificationKey::try_from(point).unwrap(), vk);
+                    }
+                },
+                None => assert!(len < SIZE),
+            }
+        }
+    }
 }
@@ -5,7 +5,7 @@ use substrate_primitives::hexdisplay::AsBytesRef;
 #[cfg(feature = "std")]
 use substrate_primitives::bytes;
 use jubjub::{
-    redjubjub,
+    redjubjub::{self, read_scalar},
     curve::FixedGenerators
 };
 use runtime_primitives::traits::{Verify, Lazy};
@@ -49,12 +49,42 @@ impl Encode for RedjubjubSignature {
     }
 }
 
+// Signatures are decoded from untrusted extrinsics, so the ones whose `s` is not canonical
+// are rejected here rather than when they are verified.
 impl Decode for RedjubjubSignature {
     fn decode<I: Input>(input: &mut I) -> Option<Self> {
-        <[u8; SIZE] as Decode>::decode(input).map(H512)
+        let bytes = <[u8; SIZE] as Decode>::decode(input)?;
+        if !has_canonical_s(&bytes) {
+            return None;
+        }
+
+        Some(H512(bytes))
     }
 }
 
+impl TryFrom<&[u8]> for RedjubjubSignature {
+    type Error = io::Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, io::Error> {
+        if bytes.len() != SIZE {
+            return Err(io::Error::InvalidData);
+        }
+
+        let mut sig = [0u8; SIZE];
+        sig.copy_from_slice(bytes);
+        if !has_canonical_s(&sig) {
+            return Err(io::Error::NotInField);
+        }
+
+        Ok(H512(sig))
+    }
+}
+
+/// The last 32 bytes of a signature are the scalar `s`, which must be less than the order of the subgroup.
+fn has_canonical_s(sig: &[u8; SIZE]) -> bool {
+    read_scalar::<Bls12, &[u8]>(&sig[32..]).is_ok()
+}
+
 #[cfg(feature = "std")]
 impl AsBytesRef for RedjubjubSignature {
     fn as_bytes_ref(&self) -> &[u8] {
@@ -96,6 +126,10 @@ impl TryFrom<RedjubjubSignature> for redjubjub::Signature {
     type Error = io::Error;
 
     fn try_from(sig: RedjubjubSignature) -> Result<Self, io::Error> {
+        if !has_canonical_s(&sig.0) {
+            return Err(io::Error::NotInField);
+        }
+
         redjubjub::Signature::read(&sig.0[..])
     }
 }
@@ -106,6 +140,8 @@ mod tests {
     use rand::{Rng, SeedableRng, XorShiftRng};
     use jubjub::curve::{FixedGenerators, JubjubBls12};
     use jubjub::redjubjub::PublicKey;
+    use jubjub::curve::fs::Fs;
+    use pairing::{PrimeField, PrimeFieldRepr};
     use core::convert::TryInto;
 
     #[test]
@@ -148,4 +184,57 @@ mod tests {
         let decoded_sig = RedjubjubSignature::decode(&mut encoded_sig.as_slice()).unwrap();
         assert_eq!(sig_b, decoded_sig);
     }
+
+    #[test]
+    fn test_sig_decode_rejects_short_input() {
+        let encoded = [1u8; SIZE];
+        for len in 0..SIZE {
+            assert!(RedjubjubSignature::decode(&mut &encoded[..len]).is_none());
+            assert!(RedjubjubSignature::try_from(&encoded[..len]).is_err());
+        }
+        assert!(RedjubjubSignature::try_from(&[1u8; SIZE + 1][..]).is_err());
+        assert!(RedjubjubSignature::decode(&mut &encoded[..]).is_some());
+    }
+
+    #[test]
+    fn test_sig_decode_rejects_non_canonical_s() {
+        let mut rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let p_g = FixedGenerators::Diversifier;
+        let params = &JubjubBls12::new();
+
+        let sk = redjubjub::PrivateKey::<Bls12>(rng.gen());
+        let sig = sk.sign(b"Foo bar", &mut rng, p_g, params);
+        let mut encoded = RedjubjubSignature::try_from(sig).unwrap().encode();
+
+        // s + r encodes the same scalar as s, but must not be accepted as another signature.
+        let mut s_repr = read_scalar::<Bls12, &[u8]>(&encoded[32..]).unwrap().into_repr();
+        s_repr.add_nocarry(&Fs::char());
+        s_repr.write_le(&mut &mut encoded[32..]).unwrap();
+
+        assert!(RedjubjubSignature::decode(&mut encoded.as_slice()).is_none());
+        assert!(RedjubjubSignature::try_from(&encoded[..]).is_err());
+        assert!(redjubjub::Signature::try_from(H512::from_slice(&encoded)).is_err());
+
+        // All ones is above the order as well.
+        assert!(RedjubjubSignature::decode(&mut &[0xffu8; SIZE][..]).is_none());
+    }
+
+    #[test]
+    fn fuzz_sig_decode() {
+        let rng = &mut XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        for _ in 0..10000 {
+            let len = rng.gen_range(0, 2 * SIZE);
+            let bytes: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+
+            match RedjubjubSignature::decode(&mut bytes.as_slice()) {
+                Some(sig) => {
+                    assert!(len >= SIZE);
+                    assert_eq!(&sig.0[..], &bytes[..SIZE]);
+                    assert!(redjubjub::Signature::try_from(sig).is_ok());
+                },
+                None => assert!(len < SIZE || !has_canonical_s(&H512::from_slice(&bytes[..SIZE]).0)),
+            }
+        }
+    }
 }
@@ -0,0 +1,65 @@
+//! The registry of SS58 address formats of zerochain networks.
+//! Each network encodes addresses with its own prefix byte, so that an address of
+//! one network is rejected by a wallet of another one instead of silently accepted.
+
+/// The SS58 format of a zerochain network, advertised as `ss58Format` in the chain properties.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressFormat {
+    Mainnet,
+    /// Also used by development chains. The prefix is the generic substrate one,
+    /// so addresses created before the registry keep their encoding.
+    Testnet,
+}
+
+impl AddressFormat {
+    pub const ALL: [AddressFormat; 2] = [AddressFormat::Mainnet, AddressFormat::Testnet];
+
+    /// The prefix byte of addresses of the network.
+    pub fn prefix(&self) -> u8 {
+        match *self {
+            AddressFormat::Mainnet => 37,
+            AddressFormat::Testnet => 42,
+        }
+    }
+
+    pub fn from_prefix(prefix: u8) -> Option<Self> {
+        Self::ALL.iter().find(|f| f.prefix() == prefix).cloned()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            AddressFormat::Mainnet => "mainnet",
+            AddressFormat::Testnet => "testnet",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|f| f.as_str() == name).cloned()
+    }
+}
+
+impl Default for AddressFormat {
+    fn default() -> Self {
+        AddressFormat::Testnet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefixes_are_distinct() {
+        assert_ne!(AddressFormat::Mainnet.prefix(), AddressFormat::Testnet.prefix());
+        for format in AddressFormat::ALL.iter() {
+            assert_eq!(AddressFormat::from_prefix(format.prefix()), Some(*format));
+            assert_eq!(AddressFormat::from_name(format.as_str()), Some(*format));
+        }
+    }
+
+    #[test]
+    fn test_unknown_format() {
+        assert_eq!(AddressFormat::from_prefix(0), None);
+        assert_eq!(AddressFormat::from_name("devnet"), None);
+    }
+}
@@ -0,0 +1,179 @@
+//! The definitions of the custom types of zerochain for the type registry of polkadot-js,
+//! from which `zface debug typegen` generates its `types.json`.
+//! Types implement `TypeInfo` next to their Rust definitions, with `impl_type_info!` for types
+//! encoded as another type and with `type_info!` around the definitions of structs,
+//! so that the definitions of polkadot-js follow the Rust ones instead of being maintained by hand.
+
+use crate::{
+    EncKey, RedjubjubSignature, Ciphertext, Proof, SigVerificationKey, Nonce, GEpoch,
+    RightCiphertext, LeftCiphertext, Memo,
+};
+
+/// The definition of a type in polkadot-js.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum TypeDef {
+    /// The type is encoded as another type, e.g. `H256` or `Vec<u8>`.
+    Alias(&'static str),
+    /// A struct of the named fields and their types, in the order they are encoded.
+    Struct(&'static [(&'static str, &'static str)]),
+}
+
+/// A type with a definition in polkadot-js.
+pub trait TypeInfo {
+    /// The name of the type in the metadata of the runtime.
+    fn type_name() -> &'static str;
+
+    fn type_def() -> TypeDef;
+}
+
+/// Implement `TypeInfo` for a type encoded as another type, e.g. `impl_type_info!(EncKey => "H256");`.
+#[macro_export]
+macro_rules! impl_type_info {
+    ($name:ident => $def:expr) => {
+        impl $crate::type_info::TypeInfo for $name {
+            fn type_name() -> &'static str {
+                stringify!($name)
+            }
+
+            fn type_def() -> $crate::type_info::TypeDef {
+                $crate::type_info::TypeDef::Alias($def)
+            }
+        }
+    };
+}
+
+/// Define a struct with public fields, implementing `TypeInfo` from its fields.
+#[macro_export]
+macro_rules! type_info {
+    (
+        $(#[$attr:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[$field_attr:meta])*
+                pub $field:ident : $ty:ty
+            ),* $(,)*
+        }
+    ) => {
+        $(#[$attr])*
+        pub struct $name {
+            $(
+                $(#[$field_attr])*
+                pub $field: $ty
+            ),*
+        }
+
+        impl $crate::type_info::TypeInfo for $name {
+            fn type_name() -> &'static str {
+                stringify!($name)
+            }
+
+            fn type_def() -> $crate::type_info::TypeDef {
+                $crate::type_info::TypeDef::Struct(&[$( (stringify!($field), stringify!($ty)) ),*])
+            }
+        }
+    };
+}
+
+impl_type_info!(EncKey => "H256");
+impl_type_info!(RedjubjubSignature => "H512");
+impl_type_info!(Ciphertext => "Vec<u8>");
+impl_type_info!(Proof => "Vec<u8>");
+impl_type_info!(SigVerificationKey => "H256");
+impl_type_info!(Nonce => "H256");
+impl_type_info!(GEpoch => "H256");
+impl_type_info!(RightCiphertext => "H256");
+impl_type_info!(LeftCiphertext => "H256");
+impl_type_info!(Memo => "H512");
+
+/// The definitions of types by their names, in the order they were registered.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct Registry {
+    types: Vec<(&'static str, TypeDef)>,
+}
+
+#[cfg(feature = "std")]
+impl Registry {
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    pub fn register<T: TypeInfo>(&mut self) -> &mut Self {
+        self.register_alias(T::type_name(), T::type_def())
+    }
+
+    /// Register a definition under a name without a Rust type of its own,
+    /// e.g. a type alias or an associated type of the runtime. A name is only registered once.
+    pub fn register_alias(&mut self, name: &'static str, def: TypeDef) -> &mut Self {
+        if !self.types.iter().any(|(n, _)| *n == name) {
+            self.types.push((name, def));
+        }
+        self
+    }
+
+    pub fn types(&self) -> &[(&'static str, TypeDef)] {
+        &self.types[..]
+    }
+}
+
+/// Register the types of this crate.
+#[cfg(feature = "std")]
+pub fn register_types(registry: &mut Registry) {
+    registry
+        .register::<EncKey>()
+        .register::<RedjubjubSignature>()
+        .register::<Ciphertext>()
+        .register::<Proof>()
+        .register::<SigVerificationKey>()
+        .register::<Nonce>()
+        .register::<GEpoch>()
+        .register::<RightCiphertext>()
+        .register::<LeftCiphertext>()
+        .register::<Memo>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type_info! {
+        #[derive(Clone)]
+        pub struct Transfer {
+            /// The recipient.
+            pub recipient: EncKey,
+            pub amounts: Vec<LeftCiphertext>,
+        }
+    }
+
+    #[test]
+    fn test_type_info() {
+        assert_eq!(EncKey::type_name(), "EncKey");
+        assert_eq!(EncKey::type_def(), TypeDef::Alias("H256"));
+
+        assert_eq!(Transfer::type_name(), "Transfer");
+        match Transfer::type_def() {
+            TypeDef::Struct(fields) => {
+                assert_eq!(fields[0], ("recipient", "EncKey"));
+                assert_eq!(fields[1].0, "amounts");
+                assert_eq!(fields[1].1.replace(' ', ""), "Vec<LeftCiphertext>");
+            },
+            def => panic!("unexpected definition {:?}", def),
+        }
+    }
+
+    #[test]
+    fn test_registry_keeps_the_first_definition() {
+        let mut registry = Registry::new();
+        register_types(&mut registry);
+        let len = registry.types().len();
+
+        registry
+            .register::<EncKey>()
+            .register_alias("EncKey", TypeDef::Alias("Vec<u8>"))
+            .register::<Transfer>();
+
+        assert_eq!(registry.types().len(), len + 1);
+        assert_eq!(registry.types()[0], ("EncKey", TypeDef::Alias("H256")));
+    }
+}
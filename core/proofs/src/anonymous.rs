@@ -1,6 +1,5 @@
 use bellman::{
         groth16::{
-            create_random_proof,
             verify_proof,
             Parameters,
             PreparedVerifyingKey,
@@ -28,13 +27,15 @@ use zprimitives::{
     Proof as zProof
 };
 use crate::{
-    circuit::AnonymousTransfer,
+    circuit::{AnonymousTransfer, HashedInputs, hashed_inputs::hash_public_inputs},
     elgamal::Ciphertext,
     EncryptionKey,
     ProofGenerationKey,
     SpendingKey,
     KeyContext,
     ProofBuilder,
+    Witness,
+    ConstraintCheck,
     constants::*,
 };
 use crate::crypto_components::{
@@ -49,7 +50,7 @@ use crate::crypto_components::{
     PublicInputBuilder,
 };
 use std::{
-    io::{self, Write, BufWriter},
+    io::{self, Write, BufWriter, BufReader},
     path::Path,
     fs::File,
     marker::PhantomData,
@@ -85,20 +86,20 @@ impl<E: JubjubEngine> ProofBuilder<E, Anonymous> for KeyContext<E, Anonymous> {
     }
 
     fn read_from_path<P: AsRef<Path>>(pk_path: P, vk_path: P) -> io::Result<Self>{
-        let pk_buf = Self::inner_read(pk_path)?;
         let vk_buf = Self::inner_read(vk_path)?;
 
-        let pk = Parameters::read(&pk_buf[..], true)?;
+        // Deserialize while reading, so the file is not buffered on top of the parameters.
+        let pk = Parameters::read(BufReader::new(File::open(pk_path)?), true)?;
         let vk = PreparedVerifyingKey::read(&vk_buf[..])?;
 
         Ok(KeyContext::new(pk, vk))
     }
 
-    fn gen_proof<R: Rng>(
+    fn build_witness<R: Rng>(
         &self,
-        amount: u32,
-        _fee: u32,
-        remaining_balance: u32,
+        amount: u64,
+        fee: u64,
+        remaining_balance: u64,
         s_index: usize,
         t_index: usize,
         spending_key: &SpendingKey<E>,
@@ -107,7 +108,7 @@ impl<E: JubjubEngine> ProofBuilder<E, Anonymous> for KeyContext<E, Anonymous> {
         g_epoch: edwards::Point<E, PrimeOrder>,
         rng: &mut R,
         params: &E::Params,
-    ) -> Result<Self::Submitter, SynthesisError> {
+    ) -> Result<Witness<E, Anonymous>, SynthesisError> {
         assert_eq!(enc_balances.len(), ANONIMITY_SIZE);
         let randomness = E::Fs::rand(rng);
         let alpha = E::Fs::rand(rng);
@@ -115,14 +116,6 @@ impl<E: JubjubEngine> ProofBuilder<E, Anonymous> for KeyContext<E, Anonymous> {
         let pgk = ProofGenerationKey::<E>::from_spending_key(&spending_key, params);
         let dec_key = pgk.into_decryption_key()?;
         let enc_key_sender = pgk.into_encryption_key(params)?;
-        let mut enc_keys_vec = enc_keys.get_decoys().to_vec();
-        if s_index < t_index {
-            enc_keys_vec.insert(s_index, enc_key_sender.clone());
-            enc_keys_vec.insert(t_index, enc_keys.get_recipient().clone());
-        } else {
-            enc_keys_vec.insert(t_index, enc_keys.get_recipient().clone());
-            enc_keys_vec.insert(s_index, enc_key_sender.clone());
-        }
 
         let rvk = PublicKey(pgk.0.clone().into())
             .randomize(
@@ -133,8 +126,50 @@ impl<E: JubjubEngine> ProofBuilder<E, Anonymous> for KeyContext<E, Anonymous> {
         let nonce = g_epoch.mul(dec_key.0, params);
 
         let multi_ciphertexts = MultiCiphertexts::<E, Anonymous>::encrypt(
-            amount, 0, &enc_key_sender, &enc_keys, &randomness, params
+            amount, fee, &enc_key_sender, &enc_keys, &randomness, params
         );
+
+        Ok(Witness {
+            amount,
+            fee,
+            remaining_balance,
+            s_index,
+            t_index,
+            spending_key: spending_key.clone(),
+            proof_generation_key: pgk,
+            dec_key,
+            enc_key_sender,
+            enc_keys,
+            enc_balances: enc_balances.to_vec(),
+            multi_ciphertexts,
+            randomness,
+            alpha,
+            rvk,
+            g_epoch,
+            nonce,
+        })
+    }
+
+    fn prove<R: Rng>(
+        &self,
+        witness: &Witness<E, Anonymous>,
+        rng: &mut R,
+        params: &E::Params,
+    ) -> Result<Self::Submitter, SynthesisError> {
+        let (s_index, t_index) = (witness.s_index, witness.t_index);
+        let enc_key_sender = &witness.enc_key_sender;
+        let enc_keys = &witness.enc_keys;
+        let multi_ciphertexts = &witness.multi_ciphertexts;
+
+        let mut enc_keys_vec = enc_keys.get_decoys().to_vec();
+        if s_index < t_index {
+            enc_keys_vec.insert(s_index, enc_key_sender.clone());
+            enc_keys_vec.insert(t_index, enc_keys.get_recipient().clone());
+        } else {
+            enc_keys_vec.insert(t_index, enc_keys.get_recipient().clone());
+            enc_keys_vec.insert(s_index, enc_key_sender.clone());
+        }
+
         let mut left_ciphertexts = multi_ciphertexts.get_decoys_left();
         if s_index < t_index {
             left_ciphertexts.insert(s_index, multi_ciphertexts.get_sender().left.clone());
@@ -146,42 +181,59 @@ impl<E: JubjubEngine> ProofBuilder<E, Anonymous> for KeyContext<E, Anonymous> {
 
         let instance = AnonymousTransfer {
             params,
-            amount: Some(amount),
-            remaining_balance: Some(remaining_balance),
+            amount: Some(witness.amount),
+            remaining_balance: Some(witness.remaining_balance),
+            fee: Some(witness.fee),
             s_index: Some(s_index),
             t_index: Some(t_index),
-            randomness: Some(&randomness),
-            alpha: Some(&alpha),
-            proof_generation_key: Some(&pgk),
-            dec_key: Some(&dec_key),
+            randomness: Some(&witness.randomness),
+            alpha: Some(&witness.alpha),
+            proof_generation_key: Some(&witness.proof_generation_key),
+            dec_key: Some(&witness.dec_key),
             enc_keys: Some(&enc_keys_vec[..]),
             left_ciphertexts: Some(&left_ciphertexts[..]),
             right_ciphertext: Some(multi_ciphertexts.get_right()),
-            enc_balances: Some(&enc_balances),
-            g_epoch: Some(&g_epoch),
+            enc_balances: Some(&witness.enc_balances[..]),
+            g_epoch: Some(&witness.g_epoch),
         };
 
+        // Catch an invalid witness before the expensive proving step.
+        ConstraintCheck::run(instance.clone())?;
+
         // Crate proof
-        let proof = create_random_proof(instance, &self.proving_key, rng)?;
+        let hashes_inputs = self.hashes_inputs();
+        let proof = if hashes_inputs {
+            self.proving_key.create_random_proof(HashedInputs::new(instance, params), rng)?
+        } else {
+            self.proving_key.create_random_proof(instance, rng)?
+        };
 
         ProofContext::new(
             proof,
-            rvk,
-            enc_key_sender,
-            enc_keys,
-            multi_ciphertexts,
-            enc_balances,
-            g_epoch,
-            nonce,
+            witness.rvk.clone(),
+            enc_key_sender.clone(),
+            enc_keys.clone(),
+            multi_ciphertexts.clone(),
+            &witness.enc_balances[..],
+            witness.g_epoch.clone(),
+            witness.nonce.clone(),
             s_index,
             t_index,
         )
-        .check_proof(&self.prepared_vk)?
-        .gen_xt(&spending_key, alpha)
+        .check_proof(witness.fee, &self.prepared_vk, if hashes_inputs { Some(params) } else { None })?
+        .gen_xt(&witness.spending_key, witness.alpha)
         .map_err(|e| SynthesisError::IoError(e))
     }
 }
 
+impl<E: JubjubEngine> KeyContext<E, Anonymous> {
+    /// Whether the keys are for the circuit committing to its public inputs by a single hash input,
+    /// set up by `anonymous_hashed_setup`.
+    pub fn hashes_inputs(&self) -> bool {
+        self.proving_key.vk().ic.len() == 2
+    }
+}
+
 impl<E: JubjubEngine> ProofContext<E, Unchecked, Anonymous> {
     fn new(
         proof: Proof<E>,
@@ -210,9 +262,13 @@ impl<E: JubjubEngine> ProofContext<E, Unchecked, Anonymous> {
         }
     }
 
+    /// Verify the proof. With `hash_params`, the proof is of the circuit committing to the inputs by their hash.
+    /// `fee` is the fee of the chain, which the verifier inputs.
     fn check_proof(
         self,
+        fee: u64,
         prepared_vk: &PreparedVerifyingKey<E>,
+        hash_params: Option<&E::Params>,
     ) -> Result<ProofContext<E, Checked, Anonymous>, SynthesisError> {
         let mut public_inputs = PublicInputBuilder::new(ANONIMOUS_INPUT_SIZE);
         let mut j = 0;
@@ -244,6 +300,7 @@ impl<E: JubjubEngine> ProofContext<E, Unchecked, Anonymous> {
             public_inputs.push(&rc);
         }
         public_inputs.push(self.right_randomness());
+        public_inputs.u64_push(fee);
         public_inputs.unknown_push(&self.rvk.0);
         public_inputs.push(&self.g_epoch);
         public_inputs.push(&self.nonce);
@@ -254,7 +311,12 @@ impl<E: JubjubEngine> ProofContext<E, Unchecked, Anonymous> {
             r.into_repr().write_le(&mut &mut buf).map_err(|_| "write error").unwrap();
         }
 
-        match verify_proof(prepared_vk, &self.proof, public_inputs.as_slice()) {
+        let verified = match hash_params {
+            Some(params) => verify_proof(prepared_vk, &self.proof, &[hash_public_inputs::<E>(public_inputs.as_slice(), params)]),
+            None => verify_proof(prepared_vk, &self.proof, public_inputs.as_slice()),
+        };
+
+        match verified {
             Ok(e) if !e => return Err(SynthesisError::Unsatisfiable),
             Err(e) => return Err(e),
             _ => { },
@@ -393,6 +455,7 @@ impl Submitter for AnonymousXt {
 
         let raw_payload = match calls {
             Calls::AnonymousTransfer => (Compact(index), self.call_transfer(), era, checkpoint),
+            Calls::AnonymousAssetTransfer(asset_id) => (Compact(index), self.call_asset_transfer(asset_id), era, checkpoint),
             _ => unreachable!(),
         };
 
@@ -417,8 +480,8 @@ impl Submitter for AnonymousXt {
 
 impl AnonymousXt {
     pub fn call_transfer(&self) -> Call {
-        let enc_keys = self.enc_keys.iter().map(|e| zEncKey::from_slice(e)).collect();
-        let left_ciphertexts = self.left_ciphertexts.iter().map(|e| zLeftCiphertext::from_slice(e)).collect();
+        let enc_keys: Vec<zEncKey> = self.enc_keys.iter().map(|e| zEncKey::from_slice(e)).collect();
+        let left_ciphertexts: Vec<zLeftCiphertext> = self.left_ciphertexts.iter().map(|e| zLeftCiphertext::from_slice(e)).collect();
         Call::AnonymousBalances(AnonymousBalancesCall::anonymous_transfer(
             zProof::from_slice(&self.proof[..]),
             enc_keys,
@@ -427,6 +490,19 @@ impl AnonymousXt {
             zNonce::from_slice(&self.nonce[..])
         ))
     }
+
+    pub fn call_asset_transfer(&self, asset_id: u32) -> Call {
+        let enc_keys: Vec<zEncKey> = self.enc_keys.iter().map(|e| zEncKey::from_slice(e)).collect();
+        let left_ciphertexts: Vec<zLeftCiphertext> = self.left_ciphertexts.iter().map(|e| zLeftCiphertext::from_slice(e)).collect();
+        Call::AnonymousBalances(AnonymousBalancesCall::anonymous_asset_transfer(
+            asset_id,
+            zProof::from_slice(&self.proof[..]),
+            enc_keys,
+            left_ciphertexts,
+            zRightCiphertext::from_slice(&self.right_ciphertext[..]),
+            zNonce::from_slice(&self.nonce[..])
+        ))
+    }
 }
 
 #[cfg(test)]
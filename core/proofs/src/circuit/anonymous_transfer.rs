@@ -1,7 +1,7 @@
 //! This module contains a circuit implementation for anonymous transfer.
 //! The statements are following:
 //! Amount check: \sum t_i * C_i = b_1 + \sum r * t_i * y_i, where b_1: transferred amount
-//! Amount check: \sum (s_i + t_i) * C_i = \sum (s_i + t_i) * r * y_i
+//! Amount check: \sum (s_i + t_i) * C_i + f * G = \sum (s_i + t_i) * r * y_i, where f: fee
 //! Amount check: (1 - s_i)(1 - t_i) * C = (1 - s_i)(1 - t_i) * r * y_i
 //! Randomness check: D = r * G
 //! Balance check: \sum s_i * (C_li - C_i) = b_2 * G + sk * (\sum (s_i * C_ri) - D) ,where b_2: remaining balance
@@ -14,6 +14,10 @@
 //! \sum t_i = 1
 //! b_1 \in [0, MAX]
 //! b_2 \in [0, MAX]
+//! f \in [0, MAX]
+//!
+//! The fee is a public input, which verifiers set to the fee of the chain,
+//! so every anonymous transfer pays the same fee and the sender's ciphertext isn't told apart by it.
 
 use bellman::{
     SynthesisError,
@@ -29,18 +33,21 @@ use scrypto::jubjub::{
 use scrypto::circuit::{
     boolean::self,
     ecc::{self, EdwardsPoint},
+    multipack,
 };
 use crate::{ProofGenerationKey, EncryptionKey, DecryptionKey, elgamal, constants::ANONIMITY_SIZE};
 use super::{
-    range_check::u32_into_bit_vec_le,
+    range_check::u64_into_bit_vec_le,
     anonimity_set::*,
     utils::*,
 };
 
+#[derive(Clone)]
 pub struct AnonymousTransfer<'a, E: JubjubEngine> {
     pub params: &'a E::Params,
-    pub amount: Option<u32>,
-    pub remaining_balance: Option<u32>,
+    pub amount: Option<u64>,
+    pub remaining_balance: Option<u64>,
+    pub fee: Option<u64>,
     pub s_index: Option<usize>,
     pub t_index: Option<usize>,
     pub randomness: Option<&'a E::Fs>,
@@ -69,8 +76,8 @@ impl<'a, E: JubjubEngine> Circuit<E> for AnonymousTransfer<'a, E> {
             params,
         )?;
 
-        // Ensure the amount is u32.
-        let amount_bits = u32_into_bit_vec_le(
+        // Ensure the amount is u64.
+        let amount_bits = u64_into_bit_vec_le(
             cs.namespace(|| "range proof of amount"),
             self.amount
         )?;
@@ -83,8 +90,8 @@ impl<'a, E: JubjubEngine> Circuit<E> for AnonymousTransfer<'a, E> {
             params
         )?;
 
-        // Ensure the remaining balance is u32.
-        let remaining_balance_bits = u32_into_bit_vec_le(
+        // Ensure the remaining balance is u64.
+        let remaining_balance_bits = u64_into_bit_vec_le(
             cs.namespace(|| "range proof of remaining_balance"),
             self.remaining_balance
         )?;
@@ -97,6 +104,20 @@ impl<'a, E: JubjubEngine> Circuit<E> for AnonymousTransfer<'a, E> {
             params
         )?;
 
+        // Ensure the fee is u64.
+        let fee_bits = u64_into_bit_vec_le(
+            cs.namespace(|| "range proof of fee"),
+            self.fee
+        )?;
+
+        // Multiply the fee to the base point same as FixedGenerators::ElGamal.
+        let fee_g = ecc::fixed_base_multiplication(
+            cs.namespace(|| format!("compute the fee in the exponent")),
+            FixedGenerators::NoteCommitmentRandomness,
+            &fee_bits,
+            params
+        )?;
+
         // dec_key in circuit
         let dec_key_bits = boolean::field_into_boolean_vec_le(
             cs.namespace(|| format!("dec_key")),
@@ -211,10 +232,17 @@ impl<'a, E: JubjubEngine> Circuit<E> for AnonymousTransfer<'a, E> {
                 params
             )?;
 
-            // Amount check: \sum (s_i + t_i) * C_i = \sum (s_i + t_i) * r * y_i
+            // Add fee * G: \sum (s_i + t_i) * C_i + f * G
+            let ciphertext_left_s_xor_t_plus_fee = ciphertext_left_s_xor_t.add(
+                cs.namespace(|| "add fee to left ciphertext based in (s_i xor t_i)"),
+                &fee_g,
+                params
+            )?;
+
+            // Amount check: \sum (s_i + t_i) * C_i + f * G = \sum (s_i + t_i) * r * y_i
             eq_edwards_points(
                 cs.namespace(|| "left ciphertext equals based in (s_i xor t_i)"),
-                &ciphertext_left_s_xor_t,
+                &ciphertext_left_s_xor_t_plus_fee,
                 &enc_keys_random_fold_s_xor_t
             )?;
 
@@ -317,6 +345,9 @@ impl<'a, E: JubjubEngine> Circuit<E> for AnonymousTransfer<'a, E> {
             right_ciphertext.inputize(cs.namespace(|| "inputize right amount ciphertext."))?;
         }
 
+        // Inputize the fee as a single field element
+        multipack::pack_into_inputs(cs.namespace(|| "inputize fee"), &fee_bits)?;
+
         // Inputize re-randomized signature verification key
         rvk_inputize(
             cs.namespace(|| "inputize rvk"),
@@ -340,20 +371,20 @@ impl<'a, E: JubjubEngine> Circuit<E> for AnonymousTransfer<'a, E> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use pairing::{bls12_381::{Bls12, Fr}, Field};
+    use pairing::{bls12_381::{Bls12, Fr}, Field, PrimeField};
     use rand::{SeedableRng, Rng, XorShiftRng, Rand};
     use crate::EncryptionKey;
     use crate::circuit::TestConstraintSystem;
     use crate::constants::*;
     use scrypto::jubjub::{JubjubBls12, fs::Fs};
 
-    fn test_based_amount(amount: u32) {
+    fn test_based_amount(amount: u64, fee: u64) {
         // constants
         let params = &JubjubBls12::new();
         let rng = &mut XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
         let p_g = FixedGenerators::NoteCommitmentRandomness;
         let current_balance_sender = 100;
-        let remaining_balance = 90;
+        let remaining_balance = 90 - fee;
 
         // randomness
         let seed_sender: [u8; 32] = rng.gen();
@@ -362,7 +393,7 @@ mod tests {
         let randomness_amount = Fs::rand(rng);
         let randomness_balanace_sender = Fs::rand(rng);
         let randomness_balanace_recipient = Fs::rand(rng);
-        let current_balance_recipient: u32 = rng.gen();
+        let current_balance_recipient: u64 = rng.gen();
         let s_index: usize = rng.gen_range(0, ANONIMITY_SIZE);
         let mut t_index: usize;
         loop {
@@ -375,7 +406,7 @@ mod tests {
         let rng = &mut XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
         let randomness_balances_iter = rng.gen_iter::<Fs>().take(DECOY_SIZE);
         let rng = &mut XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
-        let current_balance_iter = rng.gen_iter::<u32>().take(DECOY_SIZE);
+        let current_balance_iter = rng.gen_iter::<u64>().take(DECOY_SIZE);
         let rng = &mut XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
 
         // keys
@@ -390,7 +421,7 @@ mod tests {
         enc_keys.insert(t_index, enc_key_recipient.clone());
 
         // ciphertexts
-        let left_ciphertext_amount_sender = elgamal::Ciphertext::neg_encrypt(amount, &randomness_amount, &enc_key_sender, p_g, params).left;
+        let left_ciphertext_amount_sender = elgamal::Ciphertext::neg_encrypt(amount + fee, &randomness_amount, &enc_key_sender, p_g, params).left;
         let left_ciphertext_amount_recipient = elgamal::Ciphertext::encrypt(amount, &randomness_amount, &enc_key_recipient, p_g, params).left;
         let left_ciphertexts_amount_decoy = enc_keys_decoy.iter()
             .map(|e| elgamal::Ciphertext::encrypt(0, &randomness_amount, e, p_g, params).left)
@@ -428,6 +459,7 @@ mod tests {
             params,
             amount: Some(amount),
             remaining_balance: Some(remaining_balance),
+            fee: Some(fee),
             s_index: Some(s_index),
             t_index: Some(t_index),
             randomness: Some(&randomness_amount),
@@ -443,12 +475,11 @@ mod tests {
 
         instance.synthesize(&mut cs).unwrap();
         assert!(cs.is_satisfied());
-        println!("num: {:?}", cs.num_constraints());
-        println!("hash: {:?}", cs.hash());
-        println!("num_inputs: {:?}", cs.num_inputs());
-        // assert_eq!(cs.num_constraints(), 50634);
-        // assert_eq!(cs.hash(), "625c4b5d226c65b1087e2d04eb44c4a85952d8807c6218afb5fc170809a4ea37");
-        // assert_eq!(cs.num_inputs(), 105);
+        // println!("num: {:?}", cs.num_constraints());
+        // println!("hash: {:?}", cs.hash());
+        assert_eq!(cs.num_constraints(), 51165);
+        assert_eq!(cs.hash(), "8e00a191f638f3a09345edfa0ca99a525ed83825c45faf85aa8541abe39d8eb9");
+        assert_eq!(cs.num_inputs(), 106);
 
         let len = enc_keys.len();
         assert_eq!(cs.get_input(0, "ONE"), Fr::one());
@@ -470,21 +501,26 @@ mod tests {
         }
         assert_eq!(cs.get_input(len*8+1, &format!("inputize right amount ciphertext./x/input variable")), right_ciphertext_amount.into_xy().0);
         assert_eq!(cs.get_input(len*8+2, &format!("inputize right amount ciphertext./y/input variable")), right_ciphertext_amount.into_xy().1);
-        assert_eq!(cs.get_input(len*8+3, &format!("inputize rvk/rvk/x/input variable")), rvk_xy.0);
-        assert_eq!(cs.get_input(len*8+4, &format!("inputize rvk/rvk/y/input variable")), rvk_xy.1);
-        assert_eq!(cs.get_input(len*8+5, &format!("inputize g_epoch and nonce/inputize g_epoch/x/input variable")), g_epoch_xy.0);
-        assert_eq!(cs.get_input(len*8+6, &format!("inputize g_epoch and nonce/inputize g_epoch/y/input variable")), g_epoch_xy.1);
-        assert_eq!(cs.get_input(len*8+7, &format!("inputize g_epoch and nonce/inputize nonce/x/input variable")), nonce_xy.0);
-        assert_eq!(cs.get_input(len*8+8, &format!("inputize g_epoch and nonce/inputize nonce/y/input variable")), nonce_xy.1);
+        assert_eq!(cs.get_input(len*8+3, &format!("inputize fee/input 0")), Fr::from_str(&fee.to_string()).unwrap());
+        assert_eq!(cs.get_input(len*8+4, &format!("inputize rvk/rvk/x/input variable")), rvk_xy.0);
+        assert_eq!(cs.get_input(len*8+5, &format!("inputize rvk/rvk/y/input variable")), rvk_xy.1);
+        assert_eq!(cs.get_input(len*8+6, &format!("inputize g_epoch and nonce/inputize g_epoch/x/input variable")), g_epoch_xy.0);
+        assert_eq!(cs.get_input(len*8+7, &format!("inputize g_epoch and nonce/inputize g_epoch/y/input variable")), g_epoch_xy.1);
+        assert_eq!(cs.get_input(len*8+8, &format!("inputize g_epoch and nonce/inputize nonce/x/input variable")), nonce_xy.0);
+        assert_eq!(cs.get_input(len*8+9, &format!("inputize g_epoch and nonce/inputize nonce/y/input variable")), nonce_xy.1);
     }
 
     #[test]
     fn test_circuit_anonymous_transfer_valid() {
-        test_based_amount(10);
+        test_based_amount(10, 0);
     }
     #[should_panic]
     #[test]
     fn test_circuit_anonymous_transfer_invalid() {
-        test_based_amount(11);
+        test_based_amount(11, 0);
+    }
+    #[test]
+    fn test_circuit_anonymous_transfer_with_fee_valid() {
+        test_based_amount(10, 1);
     }
 }
@@ -24,19 +24,20 @@ use scrypto::circuit::{
 };
 use scrypto::jubjub::{edwards, PrimeOrder};
 use crate::elgamal::Ciphertext;
-use super::{range_check::u32_into_bit_vec_le, utils::*};
+use super::{range_check::u64_into_bit_vec_le, utils::*};
 
+#[derive(Clone)]
 pub struct ConfidentialTransfer<'a, E: JubjubEngine> {
     pub params: &'a E::Params,
-    pub amount: Option<u32>,
-    pub remaining_balance: Option<u32>,
+    pub amount: Option<u64>,
+    pub remaining_balance: Option<u64>,
     pub randomness: Option<&'a E::Fs>,
     pub alpha: Option<&'a E::Fs>,
     pub proof_generation_key: Option<&'a ProofGenerationKey<E>>,
     pub dec_key_sender: Option<&'a DecryptionKey<E>>,
     pub enc_key_recipient: Option<&'a EncryptionKey<E>>,
     pub encrypted_balance: Option<&'a Ciphertext<E>>,
-    pub fee: Option<u32>,
+    pub fee: Option<u64>,
     pub g_epoch: Option<&'a edwards::Point<E, PrimeOrder>>,
 }
 
@@ -66,20 +67,20 @@ impl<'a, E: JubjubEngine> Circuit<E> for ConfidentialTransfer<'a, E> {
     {
         let params = self.params;
 
-        // Ensure the amount is u32.
-        let amount_bits = u32_into_bit_vec_le(
+        // Ensure the amount is u64.
+        let amount_bits = u64_into_bit_vec_le(
             cs.namespace(|| "range proof of amount"),
             self.amount
         )?;
 
-        // Ensure the remaining balance is u32.
-        let remaining_balance_bits = u32_into_bit_vec_le(
+        // Ensure the remaining balance is u64.
+        let remaining_balance_bits = u64_into_bit_vec_le(
             cs.namespace(|| "range proof of remaining_balance"),
             self.remaining_balance
         )?;
 
-        // Ensure the fee is u32.
-        let fee_bits = u32_into_bit_vec_le(
+        // Ensure the fee is u64.
+        let fee_bits = u64_into_bit_vec_le(
             cs.namespace(|| "range proof of fee"),
             self.fee
         )?;
@@ -313,7 +314,7 @@ mod tests {
     use scrypto::jubjub::{JubjubBls12, fs::Fs};
     use crate::EncryptionKey;
 
-    fn test_based_amount(amount: u32) {
+    fn test_based_amount(current_balance: u64, amount: u64, remaining_balance: u64) {
         let params = &JubjubBls12::new();
         let rng = &mut XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
 
@@ -331,8 +332,6 @@ mod tests {
         let alpha: Fs = rng.gen();
 
         let fee = 1;
-        let current_balance = 27;
-        let remaining_balance = 16;
 
         let randomness_balance = Fs::rand(rng);
         let randomness_amount = Fs::rand(rng);
@@ -380,8 +379,8 @@ mod tests {
         // println!("num: {:?}", cs.num_constraints());
         // println!("hash: {:?}", cs.hash());
 
-        assert_eq!(cs.num_constraints(), 19974);
-        assert_eq!(cs.hash(), "d23c92fb60ee547d45118e160679929cfa186957280673af62f09fa12d401784");
+        assert_eq!(cs.num_constraints(), 20463);
+        assert_eq!(cs.hash(), "643c442093ed5be41c0db3b5d22cbf21ea8dcb75f8e9dbe46f04ca3a29f2fb76");
 
         assert_eq!(cs.num_inputs(), 23);
         assert_eq!(cs.get_input(0, "ONE"), Fr::one());
@@ -411,12 +410,19 @@ mod tests {
 
     #[test]
     fn test_circuit_transfer_valid() {
-        test_based_amount(10);
+        test_based_amount(27, 10, 16);
     }
 
     #[test]
     #[should_panic]
     fn test_circuit_transfer_invalid() {
-        test_based_amount(11);
+        test_based_amount(27, 11, 16);
+    }
+
+    #[test]
+    fn test_circuit_transfer_above_u32() {
+        let current_balance = 1 << 40;
+        let amount = (1 << 33) + 7;
+        test_based_amount(current_balance, amount, current_balance - amount - 1);
     }
 }
@@ -0,0 +1,265 @@
+//! A wrapper of a circuit which commits to its public inputs by a single hash public input.
+//! The inputs of the inner circuit are allocated as private variables instead,
+//! and the circuit recomputes the hash of them:
+//! h_0 = 0, h_{k+1} = PH(h_k || x_{2k} || x_{2k+1}) and h_n is the only public input,
+//! where PH is the x-coordinate of the Pedersen hash and each element is 255 bits in little endian.
+//! Verifiers only hash the inputs and prepare a single input instead of all of them,
+//! so that the verification doesn't grow linearly with the inputs of the inner circuit.
+
+use bellman::{
+    SynthesisError,
+    ConstraintSystem,
+    Circuit,
+    Variable,
+    LinearCombination,
+};
+use pairing::{Field, PrimeField, PrimeFieldRepr};
+use scrypto::jubjub::JubjubEngine;
+use scrypto::circuit::{
+    boolean::Boolean,
+    num::AllocatedNum,
+    pedersen_hash,
+};
+use scrypto::pedersen_hash::Personalization;
+
+/// The number of inputs absorbed by each Pedersen hash next to the previous hash,
+/// which is as many as fit in the bits of the generators.
+pub const INPUTS_PER_HASH: usize = 2;
+
+/// The personalization of the Pedersen hashes, which no merkle tree of sapling uses.
+pub const HASH_PERSONALIZATION: Personalization = Personalization::MerkleTree(62);
+
+pub struct HashedInputs<'a, E: JubjubEngine, C: Circuit<E>> {
+    pub params: &'a E::Params,
+    pub circuit: C,
+}
+
+impl<'a, E: JubjubEngine, C: Circuit<E>> HashedInputs<'a, E, C> {
+    pub fn new(circuit: C, params: &'a E::Params) -> Self {
+        HashedInputs {
+            params,
+            circuit,
+        }
+    }
+}
+
+impl<'a, E: JubjubEngine, C: Circuit<E>> Circuit<E> for HashedInputs<'a, E, C> {
+    fn synthesize<CS: ConstraintSystem<E>>(
+        self,
+        cs: &mut CS
+    ) -> Result<(), SynthesisError>
+    {
+        let inputs = {
+            let mut hashing_cs = InputHashingCS {
+                cs: &mut *cs,
+                inputs: vec![],
+            };
+            self.circuit.synthesize(&mut hashing_cs)?;
+
+            hashing_cs.inputs
+        };
+
+        let mut hash: Option<AllocatedNum<E>> = None;
+        for (i, chunk) in inputs.chunks(INPUTS_PER_HASH).enumerate() {
+            let mut cs = cs.namespace(|| format!("hash {}", i));
+
+            let mut bits = match hash {
+                Some(ref h) => h.into_bits_le_strict(cs.namespace(|| "previous hash into bits"))?,
+                None => vec![Boolean::constant(false); E::Fr::NUM_BITS as usize],
+            };
+
+            for (j, &(var, value)) in chunk.iter().enumerate() {
+                let num = AllocatedNum::alloc(
+                    cs.namespace(|| format!("input {}", j)),
+                    || value.ok_or(SynthesisError::AssignmentMissing)
+                )?;
+
+                cs.enforce(
+                    || format!("input {} equals the variable of the circuit", j),
+                    |lc| lc + var,
+                    |lc| lc + CS::one(),
+                    |lc| lc + num.get_variable()
+                );
+
+                bits.extend(num.into_bits_le_strict(cs.namespace(|| format!("input {} into bits", j)))?);
+            }
+
+            let point = pedersen_hash::pedersen_hash(
+                cs.namespace(|| "pedersen hash"),
+                HASH_PERSONALIZATION,
+                &bits,
+                self.params
+            )?;
+
+            hash = Some(point.get_x().clone());
+        }
+
+        hash.ok_or(SynthesisError::Unsatisfiable)?
+            .inputize(cs.namespace(|| "inputs hash"))
+    }
+}
+
+/// Compute the hash the wrapped circuit inputizes from the public inputs of the inner circuit.
+pub fn hash_public_inputs<E: JubjubEngine>(inputs: &[E::Fr], params: &E::Params) -> E::Fr {
+    let mut hash = E::Fr::zero();
+    for chunk in inputs.chunks(INPUTS_PER_HASH) {
+        let bits = Some(&hash).into_iter()
+            .chain(chunk)
+            .flat_map(|e| bits_le::<E>(e))
+            .collect::<Vec<bool>>();
+
+        hash = scrypto::pedersen_hash::pedersen_hash::<E, _>(HASH_PERSONALIZATION, bits, params)
+            .into_xy().0;
+    }
+
+    hash
+}
+
+fn bits_le<E: JubjubEngine>(e: &E::Fr) -> impl Iterator<Item=bool> {
+    let repr = e.into_repr();
+    (0..E::Fr::NUM_BITS as usize).map(move |i| (repr.as_ref()[i / 64] >> (i % 64)) & 1 == 1)
+}
+
+/// Allocates the inputs of the inner circuit as private variables and records them.
+struct InputHashingCS<'a, E: JubjubEngine, CS: ConstraintSystem<E> + 'a> {
+    cs: &'a mut CS,
+    inputs: Vec<(Variable, Option<E::Fr>)>,
+}
+
+impl<'a, E: JubjubEngine, CS: ConstraintSystem<E> + 'a> ConstraintSystem<E> for InputHashingCS<'a, E, CS> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(
+        &mut self,
+        annotation: A,
+        f: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        self.cs.alloc(annotation, f)
+    }
+
+    fn alloc_input<F, A, AR>(
+        &mut self,
+        annotation: A,
+        f: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        let mut value = None;
+        let var = self.cs.alloc(annotation, || {
+            let v = f()?;
+            value = Some(v);
+            Ok(v)
+        })?;
+        self.inputs.push((var, value));
+
+        Ok(var)
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(
+        &mut self,
+        annotation: A,
+        a: LA,
+        b: LB,
+        c: LC
+    )
+        where A: FnOnce() -> AR, AR: Into<String>,
+              LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>
+    {
+        self.cs.enforce(annotation, a, b, c)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+        where NR: Into<String>, N: FnOnce() -> NR
+    {
+        self.cs.get_root().push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self) {
+        self.cs.get_root().pop_namespace()
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pairing::bls12_381::{Bls12, Fr};
+    use scrypto::jubjub::JubjubBls12;
+    use rand::{SeedableRng, XorShiftRng, Rand};
+    use crate::circuit::TestConstraintSystem;
+
+    /// Inputizes its inputs and their sum.
+    struct Sum(Vec<Fr>);
+
+    impl Circuit<Bls12> for Sum {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            let mut sum = Fr::zero();
+            let mut lc = LinearCombination::zero();
+            for (i, x) in self.0.iter().enumerate() {
+                let var = cs.alloc_input(|| format!("input {}", i), || Ok(*x))?;
+                lc = lc + var;
+                sum.add_assign(x);
+            }
+            let sum_var = cs.alloc_input(|| "sum", || Ok(sum))?;
+            cs.enforce(|| "sum", |_| lc, |lc| lc + CS::one(), |lc| lc + sum_var);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_hashed_inputs() {
+        let params = &JubjubBls12::new();
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let inputs = (0..4).map(|_| Fr::rand(rng)).collect::<Vec<_>>();
+        let mut sum = Fr::zero();
+        for x in &inputs {
+            sum.add_assign(x);
+        }
+
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        HashedInputs::new(Sum(inputs.clone()), params).synthesize(&mut cs).unwrap();
+
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_inputs(), 2);
+
+        let mut expected = inputs.clone();
+        expected.push(sum);
+        let hash = hash_public_inputs::<Bls12>(&expected, params);
+        assert_eq!(cs.get_input(1, "inputs hash/input variable"), hash);
+
+        // Any other input gives another hash.
+        expected[0] = Fr::rand(rng);
+        assert!(hash_public_inputs::<Bls12>(&expected, params) != hash);
+    }
+
+    // The runtime recomputes the hash with the Pedersen hash of zjubjub.
+    #[test]
+    fn test_pedersen_hash_matches_zjubjub() {
+        use zjubjub::{curve::JubjubBls12 as zJubjubBls12, pedersen_hash as zpedersen_hash};
+        use zpairing::{bls12_381::Bls12 as zBls12, PrimeField as zPrimeField, PrimeFieldRepr as zPrimeFieldRepr};
+
+        let params = &JubjubBls12::new();
+        let zparams = &zJubjubBls12::new();
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let bits = (0..(3 * 255)).map(|_| bool::rand(rng)).collect::<Vec<bool>>();
+
+        let hash = scrypto::pedersen_hash::pedersen_hash::<Bls12, _>(HASH_PERSONALIZATION, bits.clone(), params)
+            .into_xy().0;
+        let zhash = zpedersen_hash::pedersen_hash::<zBls12, _>(zpedersen_hash::Personalization::MerkleTree(62), bits, zparams)
+            .into_xy().0;
+
+        let mut buf = vec![];
+        hash.into_repr().write_le(&mut buf).unwrap();
+        let mut zbuf = vec![];
+        zhash.into_repr().write_le(&mut zbuf).unwrap();
+        assert_eq!(buf, zbuf);
+    }
+}
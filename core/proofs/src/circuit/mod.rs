@@ -1,10 +1,13 @@
 pub mod confidential_transfer;
 pub mod anonymous_transfer;
 pub mod anonimity_set;
+pub mod hashed_inputs;
 mod range_check;
 mod utils;
 pub mod test;
 
 pub use self::confidential_transfer::ConfidentialTransfer;
 pub use self::anonymous_transfer::AnonymousTransfer;
+pub use self::hashed_inputs::HashedInputs;
 pub use self::test::TestConstraintSystem;
+pub use self::utils::rerandomize_ciphertext;
@@ -8,9 +8,9 @@ use scrypto::circuit::boolean::{Boolean, AllocatedBit};
 use scrypto::jubjub::JubjubEngine;
 use pairing::{PrimeField, Field, BitIterator, PrimeFieldRepr};
 
-pub fn u32_into_bit_vec_le<E, CS>(
+pub fn u64_into_bit_vec_le<E, CS>(
     mut cs: CS,
-    amount: Option<u32>
+    amount: Option<u64>
 ) -> Result<Vec<Boolean>, SynthesisError>
     where E: JubjubEngine, CS: ConstraintSystem<E>
 {
@@ -25,7 +25,7 @@ pub fn u32_into_bit_vec_le<E, CS>(
     )?;
 
     alloc_num.into_bits_le_strict(cs.namespace(
-        || "range check within u32"
+        || "range check within u64"
     ))
 }
 
@@ -100,7 +100,7 @@ impl<E: JubjubEngine> AllocRangedNum<E> {
         // We want to ensure that the bit representation of a is
         // less than or equal to r - 1.
         let mut a = self.value.map(|e| BitIterator::new(e.into_repr()));
-        let mut b = E::Fr::from_str(&std::u32::MAX.to_string())
+        let mut b = E::Fr::from_str(&std::u64::MAX.to_string())
             .map(|e| e.into_repr())
             .ok_or(SynthesisError::AssignmentMissing)?;
         b.sub_noborrow(&1.into());
@@ -157,7 +157,7 @@ impl<E: JubjubEngine> AllocRangedNum<E> {
                 let a_bit = AllocatedBit::alloc_conditionally(
                     cs.namespace(|| format!("bit {}", i)),
                     a_bit,
-                    &last_run.as_ref().expect("u32::MAX always starts with a one")
+                    &last_run.as_ref().expect("u64::MAX always starts with a one")
                 )?;
                 result.push(a_bit);
             }
@@ -165,7 +165,7 @@ impl<E: JubjubEngine> AllocRangedNum<E> {
             i += 1;
         }
 
-        // u32::MAX is prime, so we'll always end on
+        // u64::MAX - 1 is even, so we'll always end on
         // a run of zeros.
         assert_eq!(current_run.len(), 0);
 
@@ -229,20 +229,20 @@ mod tests {
         assert!(valid_range_check("234"));
         assert!(valid_range_check("2353649"));
 
-        let max_minus_one = std::u32::MAX - 1;
+        let max_minus_one = std::u64::MAX - 1;
         assert!(valid_range_check(&max_minus_one.to_string()));
     }
 
     #[test]
     fn test_range_check_invalid() {
-        let max = std::u32::MAX;
+        let max = std::u64::MAX;
         assert!(!valid_range_check(&max.to_string()));
     }
 
     #[should_panic]
     #[test]
     fn test_panic_overflow() {
-        let max = std::u32::MAX as u64;
+        let max = std::u64::MAX as u128;
         valid_range_check(&(max + 1).to_string());
     }
 
@@ -255,14 +255,14 @@ mod tests {
     #[should_panic]
     #[test]
     fn test_panic_neg_max() {
-        let max = std::u32::MAX;
+        let max = std::u64::MAX;
         should_panic_neg_range_check(&max.to_string());
     }
 
     #[should_panic]
     #[test]
     fn test_panic_neg_max_plus_one() {
-        let max = std::u32::MAX as u64;
+        let max = std::u64::MAX as u128;
         should_panic_neg_range_check(&(max + 1).to_string());
     }
 }
@@ -153,6 +153,49 @@ where
     Ok(())
 }
 
+/// Re-randomize the ciphertext (left, right) encrypted by `enc_key` with the randomness bits,
+/// i.e. (left + r'·enc_key, right + r'·G), which decrypts to the same amount as the original one.
+pub fn rerandomize_ciphertext<E, CS>(
+    mut cs: CS,
+    left: &EdwardsPoint<E>,
+    right: &EdwardsPoint<E>,
+    enc_key: &EdwardsPoint<E>,
+    randomness_bits: &[Boolean],
+    p_g: FixedGenerators,
+    params: &E::Params,
+) -> Result<(EdwardsPoint<E>, EdwardsPoint<E>), SynthesisError>
+where
+    E: JubjubEngine,
+    CS: ConstraintSystem<E>,
+{
+    let r_enc_key = enc_key.mul(
+        cs.namespace(|| "compute enc_key mul by randomness"),
+        randomness_bits,
+        params
+    )?;
+
+    let r_g = fixed_base_multiplication(
+        cs.namespace(|| "compute generator mul by randomness"),
+        p_g,
+        randomness_bits,
+        params
+    )?;
+
+    let left = left.add(
+        cs.namespace(|| "compute rerandomized left"),
+        &r_enc_key,
+        params
+    )?;
+
+    let right = right.add(
+        cs.namespace(|| "compute rerandomized right"),
+        &r_g,
+        params
+    )?;
+
+    Ok((left, right))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -215,4 +258,44 @@ mod tests {
         assert!(neg_p.get_x().get_value().unwrap() == expected_x);
         assert!(neg_p.get_y().get_value().unwrap() == expected_y);
     }
+
+    #[test]
+    fn test_rerandomize_ciphertext() {
+        use scrypto::jubjub::fs::Fs;
+        use rand::Rand;
+        use crate::{EncryptionKey, elgamal::Ciphertext};
+
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::Diversifier;
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+
+        let enc_key = EncryptionKey::<Bls12>(edwards::Point::rand(rng, params).mul_by_cofactor(params));
+        let ciphertext = Ciphertext::encrypt(10, &Fs::rand(rng), &enc_key, p_g, params);
+        let randomness = Fs::rand(rng);
+        let expected = ciphertext.rerandomize(&randomness, &enc_key, p_g, params);
+
+        let left = EdwardsPoint::witness(cs.namespace(|| "left"), Some(ciphertext.left.clone()), params).unwrap();
+        let right = EdwardsPoint::witness(cs.namespace(|| "right"), Some(ciphertext.right.clone()), params).unwrap();
+        let enc_key = EdwardsPoint::witness(cs.namespace(|| "enc_key"), Some(enc_key.0.clone()), params).unwrap();
+        let randomness_bits = field_into_boolean_vec_le(cs.namespace(|| "randomness"), Some(randomness)).unwrap();
+
+        let (left, right) = rerandomize_ciphertext(
+            cs.namespace(|| "rerandomize"),
+            &left,
+            &right,
+            &enc_key,
+            &randomness_bits,
+            p_g,
+            params
+        ).unwrap();
+
+        assert!(cs.is_satisfied());
+        let (expected_left_x, expected_left_y) = expected.left.into_xy();
+        let (expected_right_x, expected_right_y) = expected.right.into_xy();
+        assert!(left.get_x().get_value().unwrap() == expected_left_x);
+        assert!(left.get_y().get_value().unwrap() == expected_left_y);
+        assert!(right.get_x().get_value().unwrap() == expected_right_x);
+        assert!(right.get_y().get_value().unwrap() == expected_right_y);
+    }
 }
@@ -1,6 +1,5 @@
 use bellman::{
         groth16::{
-            create_random_proof,
             verify_proof,
             Parameters,
             PreparedVerifyingKey,
@@ -8,7 +7,7 @@ use bellman::{
         },
         SynthesisError,
 };
-use pairing::Field;
+use pairing::{Field, PrimeField, PrimeFieldRepr};
 use rand::{Rand, Rng};
 use scrypto::{
     jubjub::{
@@ -25,6 +24,9 @@ use zerochain_runtime::{
     EncryptedBalancesCall,
     EncryptedAssetsCall,
     AnonymousBalancesCall,
+    EncryptedStandingOrdersCall,
+    StandingOrderPayment,
+    AccountId,
     Call,
 };
 use zprimitives::{
@@ -33,7 +35,9 @@ use zprimitives::{
     LeftCiphertext as zLeftCiphertext,
     RightCiphertext as zRightCiphertext,
     Nonce as zNonce,
-    Proof as zProof
+    Proof as zProof,
+    Memo as zMemo,
+    SigVerificationKey,
 };
 use crate::{
     circuit::ConfidentialTransfer,
@@ -43,6 +47,8 @@ use crate::{
     SpendingKey,
     KeyContext,
     ProofBuilder,
+    Witness,
+    ConstraintCheck,
     constants::*,
 };
 use crate::crypto_components::{
@@ -56,7 +62,7 @@ use crate::crypto_components::{
     ProofContext, convert_to_checked,
 };
 use std::{
-    io::{self, Write, BufWriter},
+    io::{self, Write, BufWriter, BufReader},
     path::Path,
     fs::File,
     marker::PhantomData,
@@ -93,29 +99,29 @@ impl<E: JubjubEngine> ProofBuilder<E, Confidential> for KeyContext<E, Confidenti
     }
 
     fn read_from_path<P: AsRef<Path>>(pk_path: P, vk_path: P) -> io::Result<Self>{
-        let pk_buf = Self::inner_read(pk_path)?;
         let vk_buf = Self::inner_read(vk_path)?;
 
-        let pk = Parameters::read(&pk_buf[..], true)?;
+        // Deserialize while reading, so the file is not buffered on top of the parameters.
+        let pk = Parameters::read(BufReader::new(File::open(pk_path)?), true)?;
         let vk = PreparedVerifyingKey::read(&vk_buf[..])?;
 
         Ok(KeyContext::new(pk, vk))
     }
 
-    fn gen_proof<R: Rng>(
+    fn build_witness<R: Rng>(
         &self,
-        amount: u32,
-        fee: u32,
-        remaining_balance: u32,
-        _s_index: usize,
-        _t_index: usize,
+        amount: u64,
+        fee: u64,
+        remaining_balance: u64,
+        s_index: usize,
+        t_index: usize,
         spending_key: &SpendingKey<E>,
         enc_keys: MultiEncKeys<E, Confidential>,
         encrypted_balance: &[Ciphertext<E>],
         g_epoch: edwards::Point<E, PrimeOrder>,
         rng: &mut R,
         params: &E::Params,
-    ) -> Result<Self::Submitter, SynthesisError> {
+    ) -> Result<Witness<E, Confidential>, SynthesisError> {
         let randomness = E::Fs::rand(rng);
         let alpha = E::Fs::rand(rng);
 
@@ -131,22 +137,6 @@ impl<E: JubjubEngine> ProofBuilder<E, Confidential> for KeyContext<E, Confidenti
         );
         let nonce = g_epoch.mul(dec_key.0, params);
 
-        let instance = ConfidentialTransfer {
-            params,
-            amount: Some(amount),
-            remaining_balance: Some(remaining_balance),
-            randomness: Some(&randomness),
-            alpha: Some(&alpha),
-            proof_generation_key: Some(&pgk),
-            dec_key_sender: Some(&dec_key),
-            enc_key_recipient: Some(&enc_keys.get_recipient()),
-            encrypted_balance: Some(&encrypted_balance[0]),
-            fee: Some(fee),
-            g_epoch: Some(&g_epoch),
-        };
-
-        // Crate proof
-        let proof = create_random_proof(instance, &self.proving_key, rng)?;
         let multi_ciphertexts = MultiCiphertexts::<E, Confidential>::encrypt(
             amount,
             fee,
@@ -156,18 +146,65 @@ impl<E: JubjubEngine> ProofBuilder<E, Confidential> for KeyContext<E, Confidenti
             params
         );
 
-        ProofContext::new(
-            proof,
-            rvk,
+        Ok(Witness {
+            amount,
+            fee,
+            remaining_balance,
+            s_index,
+            t_index,
+            spending_key: spending_key.clone(),
+            proof_generation_key: pgk,
+            dec_key,
             enc_key_sender,
             enc_keys,
+            enc_balances: vec![encrypted_balance[0].clone()], // TODO
             multi_ciphertexts,
-            encrypted_balance[0].clone(), // TODO
+            randomness,
+            alpha,
+            rvk,
             g_epoch,
-            nonce
+            nonce,
+        })
+    }
+
+    fn prove<R: Rng>(
+        &self,
+        witness: &Witness<E, Confidential>,
+        rng: &mut R,
+        params: &E::Params,
+    ) -> Result<Self::Submitter, SynthesisError> {
+        let instance = ConfidentialTransfer {
+            params,
+            amount: Some(witness.amount),
+            remaining_balance: Some(witness.remaining_balance),
+            randomness: Some(&witness.randomness),
+            alpha: Some(&witness.alpha),
+            proof_generation_key: Some(&witness.proof_generation_key),
+            dec_key_sender: Some(&witness.dec_key),
+            enc_key_recipient: Some(&witness.enc_keys.get_recipient()),
+            encrypted_balance: Some(&witness.enc_balances[0]),
+            fee: Some(witness.fee),
+            g_epoch: Some(&witness.g_epoch),
+        };
+
+        // Catch an invalid witness before the expensive proving step.
+        ConstraintCheck::run(instance.clone())?;
+
+        // Crate proof
+        let proof = self.proving_key.create_random_proof(instance, rng)?;
+
+        ProofContext::new(
+            proof,
+            witness.rvk.clone(),
+            witness.enc_key_sender.clone(),
+            witness.enc_keys.clone(),
+            witness.multi_ciphertexts.clone(),
+            witness.enc_balances[0].clone(),
+            witness.g_epoch.clone(),
+            witness.nonce.clone()
         )
         .check_proof(&self.prepared_vk)?
-        .gen_xt(&spending_key, alpha)
+        .gen_xt(&witness.spending_key, witness.alpha, &witness.randomness, witness.fee, rng)
         .map_err(|e| SynthesisError::IoError(e))
     }
 }
@@ -279,7 +316,14 @@ impl<E: JubjubEngine> ProofContext<E, Unchecked, Confidential> {
 }
 
 impl<E: JubjubEngine> ProofContext<E, Checked, Confidential> {
-    fn gen_xt(&self, spending_key: &SpendingKey<E>, alpha: E::Fs) -> io::Result<ConfidentialXt> {
+    fn gen_xt<R: Rng>(
+        &self,
+        spending_key: &SpendingKey<E>,
+        alpha: E::Fs,
+        randomness: &E::Fs,
+        fee: u64,
+        rng: &mut R
+    ) -> io::Result<ConfidentialXt> {
         // Generate the re-randomized sign key
 		let mut rsk_bytes = [0u8; 32];
 		spending_key
@@ -336,6 +380,28 @@ impl<E: JubjubEngine> ProofContext<E, Checked, Confidential> {
 			.nonce
 			.write(&mut nonce[..])?;
 
+		// The fee is only bound to the proof in the ciphertext,
+		// so prove to the chain that it is the fee of its fee policy.
+		let fee_proof = {
+			use zjubjub::curve::fs::Fs as zFs;
+			use zpairing::{PrimeField as zPrimeField, PrimeFieldRepr as zPrimeFieldRepr};
+
+			let mut randomness_bytes = [0u8; 32];
+			randomness.into_repr().write_le(&mut randomness_bytes[..])?;
+			let mut randomness_repr = zFs::default().into_repr();
+			let randomness = randomness_repr.read_le(&mut &randomness_bytes[..]).ok()
+				.and_then(|_| zFs::from_repr(randomness_repr).ok())
+				.ok_or(io::Error::new(io::ErrorKind::InvalidData, "Invalid randomness."))?;
+
+			zCiphertext::from_left_right(
+					zLeftCiphertext::from_slice(&left_fee[..]),
+					zRightCiphertext::from_slice(&right_randomness[..])
+				)
+				.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.as_str()))?
+				.prove_amount(fee, &randomness, &zEncKey::from_slice(&enc_key_sender[..]), rng)
+				.map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Failed to prove the fee."))?
+		};
+
 		let tx = ConfidentialXt {
 			proof: proof_bytes,
 			rvk: rvk_bytes,
@@ -348,6 +414,7 @@ impl<E: JubjubEngine> ProofContext<E, Checked, Confidential> {
 			rsk: rsk_bytes,
 			enc_balance,
 			nonce,
+			fee_proof,
 		};
 
 		Ok(tx)
@@ -367,6 +434,8 @@ pub struct ConfidentialXt {
 	pub rvk: [u8; POINT_SIZE],
 	pub enc_balance: [u8; CIPHERTEXT_SIZE],
 	pub nonce: [u8; POINT_SIZE],
+	/// The proof that `left_fee` encrypts the fee, which the chain checks against its fee policy.
+	pub fee_proof: Vec<u8>,
 }
 
 impl Submitter for ConfidentialXt {
@@ -403,11 +472,17 @@ impl Submitter for ConfidentialXt {
             .expect("should be fetched the genesis block hash from zerochain node.");
 
         let raw_payload = match calls {
-            Calls::BalanceTransfer => (Compact(index), self.call_transfer(), era, checkpoint),
+            Calls::BalanceTransfer(memo) => (Compact(index), self.call_transfer(memo), era, checkpoint),
+            Calls::BalanceTransferWithReturnAddress(return_address) =>
+                (Compact(index), self.call_transfer_with_return_address(return_address), era, checkpoint),
             Calls::AssetIssue => (Compact(index), self.call_asset_issue(), era, checkpoint),
-            Calls::AssetTransfer(asset_id) => (Compact(index), self.call_asset_transfer(asset_id), era, checkpoint),
+            Calls::AssetMint(asset_id) => (Compact(index), self.call_asset_mint(asset_id), era, checkpoint),
+            Calls::AssetTransfer(asset_id, memo) => (Compact(index), self.call_asset_transfer(asset_id, memo), era, checkpoint),
             Calls::AssetBurn(asset_id) => (Compact(index), self.call_asset_burn(asset_id), era, checkpoint),
+            Calls::AssetDestroy(asset_id) => (Compact(index), self.call_asset_destroy(asset_id), era, checkpoint),
             Calls::AnonymousIssue => (Compact(index), self.call_anonymous_issue(), era, checkpoint),
+            Calls::StandingOrderSchedule(payments, interval) =>
+                (Compact(index), self.call_standing_order_schedule(payments, interval), era, checkpoint),
             _ => unreachable!(),
         };
 
@@ -431,7 +506,7 @@ impl Submitter for ConfidentialXt {
 }
 
 impl ConfidentialXt {
-    pub fn call_transfer(&self) -> Call {
+    pub fn call_transfer(&self, memo: Option<zMemo>) -> Call {
         Call::EncryptedBalances(EncryptedBalancesCall::confidential_transfer(
             zProof::from_slice(&self.proof[..]),
             zEncKey::from_slice(&self.enc_key_sender[..]),
@@ -440,7 +515,24 @@ impl ConfidentialXt {
             zLeftCiphertext::from_slice(&self.left_amount_recipient[..]),
             zLeftCiphertext::from_slice(&self.left_fee[..]),
             zRightCiphertext::from_slice(&self.right_randomness[..]),
-            zNonce::from_slice(&self.nonce[..])
+            zNonce::from_slice(&self.nonce[..]),
+            self.fee_proof.clone(),
+            memo
+        ))
+    }
+
+    pub fn call_transfer_with_return_address(&self, return_address: Vec<u8>) -> Call {
+        Call::EncryptedBalances(EncryptedBalancesCall::confidential_transfer_with_return_address(
+            zProof::from_slice(&self.proof[..]),
+            zEncKey::from_slice(&self.enc_key_sender[..]),
+            zEncKey::from_slice(&self.enc_key_recipient[..]),
+            zLeftCiphertext::from_slice(&self.left_amount_sender[..]),
+            zLeftCiphertext::from_slice(&self.left_amount_recipient[..]),
+            zLeftCiphertext::from_slice(&self.left_fee[..]),
+            zRightCiphertext::from_slice(&self.right_randomness[..]),
+            zNonce::from_slice(&self.nonce[..]),
+            self.fee_proof.clone(),
+            return_address
         ))
     }
 
@@ -456,7 +548,20 @@ impl ConfidentialXt {
         ))
     }
 
-    pub fn call_asset_transfer(&self, asset_id: u32) -> Call {
+    pub fn call_asset_mint(&self, asset_id: u32) -> Call {
+        Call::EncryptedAssets(EncryptedAssetsCall::mint(
+            asset_id,
+            zProof::from_slice(&self.proof[..]),
+            zEncKey::from_slice(&self.enc_key_recipient[..]),
+            zLeftCiphertext::from_slice(&self.left_amount_recipient[..]),
+            zLeftCiphertext::from_slice(&self.left_fee[..]),
+            zCiphertext::from_slice(&self.enc_balance[..]),
+            zRightCiphertext::from_slice(&self.right_randomness[..]),
+            zNonce::from_slice(&self.nonce[..])
+        ))
+    }
+
+    pub fn call_asset_transfer(&self, asset_id: u32, memo: Option<zMemo>) -> Call {
         Call::EncryptedAssets(EncryptedAssetsCall::confidential_transfer(
             asset_id,
             zProof::from_slice(&self.proof[..]),
@@ -466,11 +571,26 @@ impl ConfidentialXt {
             zLeftCiphertext::from_slice(&self.left_amount_recipient[..]),
             zLeftCiphertext::from_slice(&self.left_fee[..]),
             zRightCiphertext::from_slice(&self.right_randomness[..]),
-            zNonce::from_slice(&self.nonce[..])
+            zNonce::from_slice(&self.nonce[..]),
+            self.fee_proof.clone(),
+            memo
         ))
     }
 
     pub fn call_asset_burn(&self, asset_id: u32) -> Call {
+        Call::EncryptedAssets(EncryptedAssetsCall::burn(
+            asset_id,
+            zProof::from_slice(&self.proof[..]),
+            zEncKey::from_slice(&self.enc_key_sender[..]),
+            zLeftCiphertext::from_slice(&self.left_amount_sender[..]),
+            zLeftCiphertext::from_slice(&self.left_amount_recipient[..]),
+            zLeftCiphertext::from_slice(&self.left_fee[..]),
+            zRightCiphertext::from_slice(&self.right_randomness[..]),
+            zNonce::from_slice(&self.nonce[..])
+        ))
+    }
+
+    pub fn call_asset_destroy(&self, asset_id: u32) -> Call {
         Call::EncryptedAssets(EncryptedAssetsCall::destroy(
             zProof::from_slice(&self.proof[..]),
             zEncKey::from_slice(&self.enc_key_recipient[..]),
@@ -494,13 +614,42 @@ impl ConfidentialXt {
             zNonce::from_slice(&self.nonce[..])
         ))
     }
+
+    /// The transfer as a later payment of a standing order, whose proof is made with the same nonce
+    /// against the balance left by the payments before it.
+    pub fn standing_order_payment(&self) -> StandingOrderPayment<AccountId> {
+        StandingOrderPayment {
+            zkproof: zProof::from_slice(&self.proof[..]),
+            amount_payer: zLeftCiphertext::from_slice(&self.left_amount_sender[..]),
+            amount_recipient: zLeftCiphertext::from_slice(&self.left_amount_recipient[..]),
+            fee_payer: zLeftCiphertext::from_slice(&self.left_fee[..]),
+            randomness: zRightCiphertext::from_slice(&self.right_randomness[..]),
+            rvk: SigVerificationKey::from_slice(&self.rvk[..]),
+        }
+    }
+
+    pub fn call_standing_order_schedule(&self, payments: Vec<StandingOrderPayment<AccountId>>, interval: u64) -> Call {
+        Call::EncryptedStandingOrders(EncryptedStandingOrdersCall::schedule(
+            zProof::from_slice(&self.proof[..]),
+            zEncKey::from_slice(&self.enc_key_sender[..]),
+            zEncKey::from_slice(&self.enc_key_recipient[..]),
+            zLeftCiphertext::from_slice(&self.left_amount_sender[..]),
+            zLeftCiphertext::from_slice(&self.left_amount_recipient[..]),
+            zLeftCiphertext::from_slice(&self.left_fee[..]),
+            zRightCiphertext::from_slice(&self.right_randomness[..]),
+            zNonce::from_slice(&self.nonce[..]),
+            self.fee_proof.clone(),
+            payments,
+            interval
+        ))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use rand::{SeedableRng, XorShiftRng, Rng};
-    use crate::EncryptionKey;
+    use crate::{EncryptionKey, Diversifier};
     use scrypto::jubjub::JubjubBls12;
     use pairing::bls12_381::Bls12;
     use std::path::Path;
@@ -542,6 +691,74 @@ mod tests {
         assert!(proofs.is_ok());
     }
 
+    #[test]
+    fn test_prove_witness_in_next_epoch() {
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::NoteCommitmentRandomness;
+        let rng = &mut XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        let sender_seed: [u8; 32] = rng.gen();
+        let recipient_seed: [u8; 32] = rng.gen();
+
+        let spending_key = SpendingKey::<Bls12>::from_seed(&sender_seed);
+        let enc_key_recipient = EncryptionKey::<Bls12>::from_seed(&recipient_seed, params).unwrap();
+        let enc_key = EncryptionKey::from_seed(&sender_seed[..], params).unwrap();
+        let enc_balance = vec![Ciphertext::encrypt(100, &rng.gen(), &enc_key, p_g, params)];
+
+        let key_context: KeyContext<Bls12, Confidential> =
+            KeyContext::read_from_path("../../zface/params/test_conf_pk.dat", "../../zface/params/test_conf_vk.dat")
+                .unwrap();
+        let mut witness = key_context
+            .build_witness(
+                10, 1, 89, 0, 0, &spending_key,
+                MultiEncKeys::<Bls12, Confidential>::new(enc_key_recipient),
+                &enc_balance, edwards::Point::rand(rng, params).mul_by_cofactor(params),
+                rng, params
+            )
+            .unwrap();
+        let nonce = witness.nonce().clone();
+
+        // The epoch passed before the transfer was submitted.
+        witness.set_epoch(edwards::Point::rand(rng, params).mul_by_cofactor(params), params);
+        assert!(*witness.nonce() != nonce);
+        assert!(key_context.prove(&witness, rng, params).is_ok());
+    }
+
+    #[test]
+    fn test_prove_witness_from_diversified_address() {
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::NoteCommitmentRandomness;
+        let rng = &mut XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        let sender_seed: [u8; 32] = rng.gen();
+        let recipient_seed: [u8; 32] = rng.gen();
+
+        let spending_key = SpendingKey::<Bls12>::from_seed(&sender_seed);
+        let enc_key_recipient = EncryptionKey::<Bls12>::from_seed(&recipient_seed, params).unwrap();
+        let diversifier = Diversifier::from_index(1);
+        let dec_key = ProofGenerationKey::from_spending_key(&spending_key, params).into_decryption_key().unwrap();
+        let enc_key = EncryptionKey::from_diversifier(&dec_key, &diversifier, params).unwrap();
+        let enc_balance = vec![Ciphertext::encrypt(100, &rng.gen(), &enc_key, p_g, params)];
+
+        let key_context: KeyContext<Bls12, Confidential> =
+            KeyContext::read_from_path("../../zface/params/test_conf_pk.dat", "../../zface/params/test_conf_vk.dat")
+                .unwrap();
+        let mut witness = key_context
+            .build_witness(
+                10, 1, 89, 0, 0, &spending_key,
+                MultiEncKeys::<Bls12, Confidential>::new(enc_key_recipient),
+                &enc_balance, edwards::Point::rand(rng, params).mul_by_cofactor(params),
+                rng, params
+            )
+            .unwrap();
+
+        // The balance is encrypted under the diversified encryption key, not the default one.
+        assert!(key_context.prove(&witness, rng, params).is_err());
+        witness.set_diversifier(&diversifier, params).unwrap();
+        assert!(*witness.enc_key_sender() == enc_key);
+        assert!(key_context.prove(&witness, rng, params).is_ok());
+    }
+
     #[test]
     fn test_read_proving_key() {
         let pk_path = Path::new("../../zface/params/test_conf_pk.dat");
@@ -0,0 +1,151 @@
+//! Dry-run of a circuit with a concrete witness before proving.
+//! Synthesizing the circuit into a `TestConstraintSystem` takes a fraction of the proving time,
+//! so an out-of-range amount or an insufficient balance is reported before computing the proof
+//! together with the name of the first violated constraint.
+
+use bellman::{Circuit, SynthesisError};
+use pairing::Engine;
+use crate::circuit::TestConstraintSystem;
+use std::{error, fmt, io};
+
+/// A reason why a witness can't be proven by the circuit.
+#[derive(Debug)]
+pub enum ConstraintViolation {
+    /// The circuit failed to be synthesized, e.g. a part of the witness is missing.
+    Synthesis(SynthesisError),
+    /// The constraint at the given namespace path is not satisfied by the witness.
+    Unsatisfied(String),
+}
+
+impl fmt::Display for ConstraintViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConstraintViolation::Synthesis(ref e) => write!(f, "failed to synthesize the circuit: {}", e),
+            ConstraintViolation::Unsatisfied(ref path) => write!(f, "the constraint `{}` is not satisfied", path),
+        }
+    }
+}
+
+impl error::Error for ConstraintViolation {
+    fn description(&self) -> &str {
+        match *self {
+            ConstraintViolation::Synthesis(_) => "failed to synthesize the circuit",
+            ConstraintViolation::Unsatisfied(_) => "a constraint is not satisfied",
+        }
+    }
+}
+
+impl From<SynthesisError> for ConstraintViolation {
+    fn from(e: SynthesisError) -> Self {
+        ConstraintViolation::Synthesis(e)
+    }
+}
+
+impl From<ConstraintViolation> for SynthesisError {
+    fn from(e: ConstraintViolation) -> Self {
+        match e {
+            ConstraintViolation::Synthesis(e) => e,
+            // Keep the name of the violated constraint in the message.
+            e @ ConstraintViolation::Unsatisfied(_) =>
+                SynthesisError::IoError(io::Error::new(io::ErrorKind::InvalidInput, e.to_string())),
+        }
+    }
+}
+
+pub struct ConstraintCheck;
+
+impl ConstraintCheck {
+    /// Synthesize the circuit with the witness without proving it,
+    /// and return the first constraint the witness violates.
+    pub fn run<E: Engine, C: Circuit<E>>(witness: C) -> Result<(), ConstraintViolation> {
+        let mut cs = TestConstraintSystem::<E>::new();
+        witness.synthesize(&mut cs)?;
+
+        match cs.which_is_unsatisfied() {
+            Some(path) => Err(ConstraintViolation::Unsatisfied(path.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, Rng, XorShiftRng, Rand};
+    use pairing::bls12_381::Bls12;
+    use scrypto::jubjub::{JubjubBls12, FixedGenerators, edwards, fs::Fs};
+    use crate::{
+        circuit::ConfidentialTransfer,
+        elgamal::Ciphertext,
+        EncryptionKey, ProofGenerationKey,
+    };
+
+    fn check_transfer(amount: u64, remaining_balance: u64) -> Result<(), ConstraintViolation> {
+        let params = &JubjubBls12::new();
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        let seed_sender: [u8; 32] = rng.gen();
+        let seed_recipient: [u8; 32] = rng.gen();
+
+        let proof_gen_key = ProofGenerationKey::<Bls12>::from_seed(&seed_sender[..], params);
+        let dec_key = proof_gen_key.into_decryption_key().unwrap();
+        let enc_key_sender = EncryptionKey::from_decryption_key(&dec_key, params);
+        let enc_key_recipient = EncryptionKey::from_seed(&seed_recipient, params).unwrap();
+
+        let alpha: Fs = rng.gen();
+        let randomness = Fs::rand(rng);
+        let p_g = FixedGenerators::NoteCommitmentRandomness;
+        let encrypted_balance = Ciphertext::encrypt(27, &Fs::rand(rng), &enc_key_sender, p_g, params);
+        let g_epoch = edwards::Point::rand(rng, params).mul_by_cofactor(params);
+
+        ConstraintCheck::run(ConfidentialTransfer {
+            params,
+            amount: Some(amount),
+            remaining_balance: Some(remaining_balance),
+            randomness: Some(&randomness),
+            alpha: Some(&alpha),
+            proof_generation_key: Some(&proof_gen_key),
+            dec_key_sender: Some(&dec_key),
+            enc_key_recipient: Some(&enc_key_recipient),
+            encrypted_balance: Some(&encrypted_balance),
+            fee: Some(1),
+            g_epoch: Some(&g_epoch),
+        })
+    }
+
+    #[test]
+    fn test_satisfied_witness() {
+        assert!(check_transfer(10, 16).is_ok());
+    }
+
+    #[test]
+    fn test_unbalanced_witness() {
+        match check_transfer(11, 16) {
+            Err(ConstraintViolation::Unsatisfied(_)) => {},
+            e => panic!("unexpected result: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_missing_witness() {
+        let params = &JubjubBls12::new();
+        let instance = ConfidentialTransfer::<Bls12> {
+            params,
+            amount: None,
+            remaining_balance: None,
+            randomness: None,
+            alpha: None,
+            proof_generation_key: None,
+            dec_key_sender: None,
+            enc_key_recipient: None,
+            encrypted_balance: None,
+            fee: None,
+            g_epoch: None,
+        };
+
+        match ConstraintCheck::run(instance) {
+            Err(ConstraintViolation::Synthesis(_)) => {},
+            e => panic!("unexpected result: {:?}", e),
+        }
+    }
+}
@@ -17,12 +17,21 @@ use scrypto::{
     },
     redjubjub::PublicKey,
 };
+use pairing::{bls12_381::Bls12, PrimeField};
 use polkadot_rs::Api;
+use parity_codec::Encode;
+use primitives::blake2_256;
+use zerochain_runtime::{Call, UncheckedExtrinsic, AccountId, StandingOrderPayment};
+use zprimitives::Memo;
 use crate::{
     elgamal::Ciphertext,
     EncryptionKey,
+    DecryptionKey,
+    Diversifier,
+    ProofGenerationKey,
     SpendingKey,
     constants::DECOY_SIZE,
+    proving_key::{ProvingKey, StreamedParameters},
 };
 use std::{
     io::{self, BufReader, Read},
@@ -70,8 +79,8 @@ pub trait CiphertextTrait<E: JubjubEngine> {
     type PC: PrivacyConfing;
 
     fn encrypt(
-        amount: u32,
-        fee: u32,
+        amount: u64,
+        fee: u64,
         enc_key_sender: &EncryptionKey<E>,
         enc_keys: &MultiEncKeys<E, Self::PC>,
         randomness: &E::Fs,
@@ -83,8 +92,8 @@ impl<E: JubjubEngine> CiphertextTrait<E> for MultiCiphertexts<E, Confidential> {
     type PC = Confidential;
 
     fn encrypt(
-        amount: u32,
-        fee: u32,
+        amount: u64,
+        fee: u64,
         enc_key_sender: &EncryptionKey<E>,
         enc_keys: &MultiEncKeys<E, Self::PC>,
         randomness: &E::Fs,
@@ -169,8 +178,8 @@ impl<E: JubjubEngine> CiphertextTrait<E> for MultiCiphertexts<E, Anonymous> {
     type PC = Anonymous;
 
     fn encrypt(
-        amount: u32,
-        _fee: u32,
+        amount: u64,
+        fee: u64,
         enc_key_sender: &EncryptionKey<E>,
         enc_keys: &MultiEncKeys<E, Self::PC>,
         randomness: &E::Fs,
@@ -178,8 +187,9 @@ impl<E: JubjubEngine> CiphertextTrait<E> for MultiCiphertexts<E, Anonymous> {
     ) -> Self {
         let p_g = FixedGenerators::NoteCommitmentRandomness;
 
+        // The fee is only subtracted from the sender, and no one is credited with it.
         let cipher_sender = Ciphertext::neg_encrypt(
-            amount,
+            amount + fee,
             randomness,
             enc_key_sender,
             p_g,
@@ -257,18 +267,88 @@ impl<E: JubjubEngine> MultiEncKeys<E, Anonymous> {
 }
 
 pub enum Calls {
-    BalanceTransfer,
+    /// A balance transfer, optionally with a memo sealed to the recipient.
+    BalanceTransfer(Option<Memo>),
+    /// A balance transfer with the sender's address sealed to the recipient.
+    BalanceTransferWithReturnAddress(Vec<u8>),
     AssetIssue,
-    AssetTransfer(u32),
+    AssetMint(u32),
+    /// An asset transfer, optionally with a memo sealed to the recipient.
+    AssetTransfer(u32, Option<Memo>),
     AssetBurn(u32),
+    AssetDestroy(u32),
     AnonymousTransfer,
+    /// An anonymous transfer of an asset of the encrypted assets module.
+    AnonymousAssetTransfer(u32),
     AnonymousIssue,
+    /// A standing order with the payments after the first one and the interval in epochs,
+    /// whose first payment is the transfer.
+    StandingOrderSchedule(Vec<StandingOrderPayment<AccountId>>, u64),
 }
 
 pub trait Submitter {
     fn submit<R: Rng>(&self, calls: Calls, api: &Api, rng: &mut R);
 }
 
+/// Sign a call without a zk proof by the spending key re-randomized by `alpha`, and submit it.
+pub fn submit_call<R: Rng>(
+    spending_key: &SpendingKey<Bls12>,
+    alpha: <Bls12 as JubjubEngine>::Fs,
+    call: Call,
+    api: &Api,
+    rng: &mut R
+) -> io::Result<()> {
+    use zjubjub::{
+        curve::{fs::Fs as zFs, FixedGenerators as zFixedGenerators},
+        redjubjub,
+    };
+    use zpairing::{
+        bls12_381::Bls12 as zBls12,
+        PrimeField as zPrimeField,
+        PrimeFieldRepr as zPrimeFieldRepr
+    };
+    use parity_codec::Compact;
+    use runtime_primitives::generic::Era;
+    use zprimitives::{PARAMS as ZPARAMS, SigVerificationKey, RedjubjubSignature};
+    use std::convert::TryFrom;
+
+    let p_g = zFixedGenerators::Diversifier; // 1
+
+    let mut rsk_bytes = [0u8; 32];
+    spending_key.into_rsk(alpha).write(&mut rsk_bytes[..])?;
+
+    let mut rsk_repr = zFs::default().into_repr();
+    rsk_repr.read_le(&mut &rsk_bytes[..])
+        .expect("should be casted to Fs's repr type.");
+    let rsk = zFs::from_repr(rsk_repr)
+        .expect("should be casted to Fs type from repr type.");
+
+    let sig_sk = redjubjub::PrivateKey::<zBls12>(rsk);
+    let mut rvk_bytes = [0u8; 32];
+    redjubjub::PublicKey::from_private(&sig_sk, p_g, &*ZPARAMS)
+        .write(&mut rvk_bytes[..])
+        .expect("should be written to 32 bytes.");
+    let sig_vk = SigVerificationKey::from_slice(&rvk_bytes[..]);
+
+    let era = Era::Immortal;
+    let index = api.get_nonce(&sig_vk).expect("Nonce must be got.");
+    let checkpoint = api.get_genesis_blockhash()
+        .expect("should be fetched the genesis block hash from zerochain node.");
+
+    let raw_payload = (Compact(index), call, era, checkpoint);
+    let sig = raw_payload.using_encoded(|payload| {
+        sig_sk.sign(&blake2_256(payload)[..], rng, p_g, &*ZPARAMS)
+    });
+
+    let sig_repr = RedjubjubSignature::try_from(sig)
+        .expect("shoukd be casted from RedjubjubSignature.");
+    let uxt = UncheckedExtrinsic::new_signed(index, raw_payload.1, sig_vk.into(), sig_repr, era);
+    let _tx_hash = api.submit_extrinsic(&uxt)
+        .expect("Faild to submit a extrinsic to zerochain node.");
+
+    Ok(())
+}
+
 pub trait ProofBuilder<E: JubjubEngine, PC: PrivacyConfing>: Sized {
     type Submitter: Submitter;
 
@@ -278,11 +358,13 @@ pub trait ProofBuilder<E: JubjubEngine, PC: PrivacyConfing>: Sized {
 
     fn read_from_path<P: AsRef<Path>>(pk_path: P, vk_path: P) -> io::Result<Self>;
 
-    fn gen_proof<R: Rng>(
+    /// Build the witness of a transfer, which `prove` proves.
+    /// The witness can be proven again in another epoch after `Witness::set_epoch`.
+    fn build_witness<R: Rng>(
         &self,
-        amount: u32,
-        fee: u32,
-        remaining_balance: u32,
+        amount: u64,
+        fee: u64,
+        remaining_balance: u64,
         s_index: usize,
         t_index: usize,
         spending_key: &SpendingKey<E>,
@@ -291,11 +373,127 @@ pub trait ProofBuilder<E: JubjubEngine, PC: PrivacyConfing>: Sized {
         g_epoch: edwards::Point<E, PrimeOrder>,
         rng: &mut R,
         params: &E::Params,
+    ) -> Result<Witness<E, PC>, SynthesisError>;
+
+    /// Prove the witness and build the transaction with the proof.
+    fn prove<R: Rng>(
+        &self,
+        witness: &Witness<E, PC>,
+        rng: &mut R,
+        params: &E::Params,
     ) -> Result<Self::Submitter, SynthesisError>;
+
+    fn gen_proof<R: Rng>(
+        &self,
+        amount: u64,
+        fee: u64,
+        remaining_balance: u64,
+        s_index: usize,
+        t_index: usize,
+        spending_key: &SpendingKey<E>,
+        enc_keys: MultiEncKeys<E, PC>,
+        enc_balances: &[Ciphertext<E>],
+        g_epoch: edwards::Point<E, PrimeOrder>,
+        rng: &mut R,
+        params: &E::Params,
+    ) -> Result<Self::Submitter, SynthesisError> {
+        let witness = self.build_witness(
+            amount,
+            fee,
+            remaining_balance,
+            s_index,
+            t_index,
+            spending_key,
+            enc_keys,
+            enc_balances,
+            g_epoch,
+            rng,
+            params
+        )?;
+
+        self.prove(&witness, rng, params)
+    }
+}
+
+/// The witness of a transfer built by `ProofBuilder::build_witness`.
+/// Only `g_epoch` and the nonce depend on the epoch, so a transfer whose epoch passed
+/// is proven again after `set_epoch` without building the rest of the witness again.
+#[derive(Clone)]
+pub struct Witness<E: JubjubEngine, PC: PrivacyConfing> {
+    pub(crate) amount: u64,
+    pub(crate) fee: u64,
+    pub(crate) remaining_balance: u64,
+    pub(crate) s_index: usize,
+    pub(crate) t_index: usize,
+    pub(crate) spending_key: SpendingKey<E>,
+    pub(crate) proof_generation_key: ProofGenerationKey<E>,
+    pub(crate) dec_key: DecryptionKey<E>,
+    pub(crate) enc_key_sender: EncryptionKey<E>,
+    pub(crate) enc_keys: MultiEncKeys<E, PC>,
+    pub(crate) enc_balances: Vec<Ciphertext<E>>,
+    pub(crate) multi_ciphertexts: MultiCiphertexts<E, PC>,
+    pub(crate) randomness: E::Fs,
+    pub(crate) alpha: E::Fs,
+    pub(crate) rvk: PublicKey<E>,
+    pub(crate) g_epoch: edwards::Point<E, PrimeOrder>,
+    pub(crate) nonce: edwards::Point<E, PrimeOrder>,
+}
+
+impl<E: JubjubEngine, PC: PrivacyConfing> Witness<E, PC> {
+    pub fn g_epoch(&self) -> &edwards::Point<E, PrimeOrder> {
+        &self.g_epoch
+    }
+
+    pub fn nonce(&self) -> &edwards::Point<E, PrimeOrder> {
+        &self.nonce
+    }
+
+    pub fn enc_key_sender(&self) -> &EncryptionKey<E> {
+        &self.enc_key_sender
+    }
+
+    /// Move the witness to the epoch of `g_epoch`, deriving the nonce of the epoch.
+    pub fn set_epoch(&mut self, g_epoch: edwards::Point<E, PrimeOrder>, params: &E::Params) {
+        self.nonce = g_epoch.mul(self.dec_key.0, params);
+        self.g_epoch = g_epoch;
+    }
+}
+
+impl<E: JubjubEngine> Witness<E, Confidential> {
+    /// The encrypted balance left by the transfer, which the next transfer of the same epoch is proven against,
+    /// e.g. the later payments of a standing order.
+    pub fn remaining_enc_balance(&self, params: &E::Params) -> Ciphertext<E> {
+        self.enc_balances[0]
+            .sub(self.multi_ciphertexts.get_sender(), params)
+            .sub(self.multi_ciphertexts.get_fee(), params)
+    }
+}
+
+impl<E: JubjubEngine, PC: PrivacyConfing> Witness<E, PC>
+where
+    MultiCiphertexts<E, PC>: CiphertextTrait<E, PC = PC>,
+{
+    /// Send from the encryption key diversified by `diversifier` instead of the default one of the spending key.
+    /// The balances of the witness must be the ones of the diversified encryption key.
+    pub fn set_diversifier(&mut self, diversifier: &Diversifier, params: &E::Params) -> io::Result<()> {
+        self.dec_key = self.proof_generation_key.into_decryption_key()?.diversify(diversifier)?;
+        self.enc_key_sender = EncryptionKey::from_decryption_key(&self.dec_key, params);
+        self.multi_ciphertexts = MultiCiphertexts::<E, PC>::encrypt(
+            self.amount,
+            self.fee,
+            &self.enc_key_sender,
+            &self.enc_keys,
+            &self.randomness,
+            params
+        );
+        self.nonce = self.g_epoch.mul(self.dec_key.0, params);
+
+        Ok(())
+    }
 }
 
 pub struct KeyContext<E: JubjubEngine, PC: PrivacyConfing> {
-    pub proving_key: Parameters<E>,
+    pub proving_key: ProvingKey<E>,
     pub prepared_vk: PreparedVerifyingKey<E>,
     _marker: PhantomData<PC>,
 }
@@ -303,13 +501,26 @@ pub struct KeyContext<E: JubjubEngine, PC: PrivacyConfing> {
 impl<E: JubjubEngine, PC: PrivacyConfing> KeyContext<E, PC> {
     pub fn new(proving_key: Parameters<E>, prepared_vk: PreparedVerifyingKey<E>) -> Self {
         KeyContext {
-            proving_key,
+            proving_key: ProvingKey::InMemory(proving_key),
             prepared_vk,
             _marker: PhantomData,
         }
     }
 
-    pub fn pk(&self) -> &Parameters<E> {
+    /// Read the keys without loading the proving key into memory.
+    /// Each query of the proving key is read from `pk_path` while proving,
+    /// which trades proving time for memory.
+    pub fn read_streamed<P: AsRef<Path>>(pk_path: P, vk_path: P) -> io::Result<Self> {
+        let vk_buf = Self::inner_read(vk_path)?;
+
+        Ok(KeyContext {
+            proving_key: ProvingKey::Streamed(StreamedParameters::open(pk_path, true)?),
+            prepared_vk: PreparedVerifyingKey::read(&vk_buf[..])?,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn pk(&self) -> &ProvingKey<E> {
         &self.proving_key
     }
 
@@ -410,6 +621,12 @@ impl<E: JubjubEngine> PublicInputBuilder<E> {
         self.0.push(y);
     }
 
+    /// Push a public amount, which is inputized as a single field element.
+    pub fn u64_push(&mut self, input: u64) {
+        let repr = <E::Fr as PrimeField>::Repr::from(input);
+        self.0.push(E::Fr::from_repr(repr).expect("u64 is in the field."));
+    }
+
     pub fn as_slice(&self) -> &[E::Fr] {
         &self.0[..]
     }
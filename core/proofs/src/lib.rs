@@ -10,15 +10,19 @@ pub mod no_std_aliases;
 pub mod setup;
 pub mod crypto_components;
 pub mod constants;
+pub mod proving_key;
+pub mod constraint_check;
 
-pub use self::setup::{confidential_setup, anonymous_setup};
+pub use self::setup::{confidential_setup, anonymous_setup, anonymous_hashed_setup};
 pub use self::no_std_aliases::keys::{
     EncryptionKey, ProofGenerationKey,
-    SpendingKey, DecryptionKey,
+    SpendingKey, DecryptionKey, Diversifier,
     prf_expand_vec, prf_expand
 };
 pub use self::no_std_aliases::elgamal;
-pub use self::crypto_components::{MultiEncKeys, Confidential, KeyContext, ProofBuilder};
+pub use self::crypto_components::{MultiEncKeys, Confidential, KeyContext, ProofBuilder, Witness};
+pub use self::proving_key::{ProvingKey, StreamedParameters};
+pub use self::constraint_check::{ConstraintCheck, ConstraintViolation};
 
 lazy_static! {
     pub static ref PARAMS: JubjubBls12 = { JubjubBls12::new() };
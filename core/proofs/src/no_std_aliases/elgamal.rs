@@ -44,7 +44,7 @@ impl<E: JubjubEngine> Ciphertext<E> {
     }
 
     pub fn encrypt(
-        amount: u32, // 32-bits restriction for the decryption.
+        amount: u64, // Decryption searches the amount, so only small amounts can be decrypted.
         randomness: &E::Fs,
         enc_key: &EncryptionKey<E>,
         p_g: FixedGenerators,
@@ -52,7 +52,7 @@ impl<E: JubjubEngine> Ciphertext<E> {
     ) -> Self
     {
         let right = params.generator(p_g).mul(*randomness, params);
-        let v_point = params.generator(p_g).mul(amount as u64, params);
+        let v_point = params.generator(p_g).mul(amount, params);
         let r_point = enc_key.0.mul(*randomness, params);
         let left = v_point.add(&r_point, params);
 
@@ -64,14 +64,14 @@ impl<E: JubjubEngine> Ciphertext<E> {
 
     // Encrypt with negative value
     pub fn neg_encrypt(
-        amount: u32,
+        amount: u64,
         randomness: &E::Fs,
         enc_key: &EncryptionKey<E>,
         p_g: FixedGenerators,
         params: &E::Params
     ) -> Self {
         let right = params.generator(p_g).mul(*randomness, params);
-        let v_point = params.generator(p_g).mul(amount as u64, params).negate();
+        let v_point = params.generator(p_g).mul(amount, params).negate();
         let r_point = enc_key.0.mul(*randomness, params);
         let left = v_point.add(&r_point, params);
 
@@ -81,13 +81,32 @@ impl<E: JubjubEngine> Ciphertext<E> {
         }
     }
 
+    /// Refresh the randomness of the ciphertext encrypted by `enc_key`, without changing the amount:
+    /// ((m + r s)G + r' sG, rG + r'G), so that it can't be linked to the original one.
+    pub fn rerandomize(
+        &self,
+        randomness: &E::Fs,
+        enc_key: &EncryptionKey<E>,
+        p_g: FixedGenerators,
+        params: &E::Params
+    ) -> Self
+    {
+        let left = self.left.add(&enc_key.0.mul(*randomness, params), params);
+        let right = self.right.add(&params.generator(p_g).mul(*randomness, params), params);
+
+        Ciphertext {
+            left,
+            right,
+        }
+    }
+
     /// Decryption of the ciphetext for the amount
     pub fn decrypt(
         &self,
         decryption_key: &DecryptionKey<E>,
         p_g: FixedGenerators,
         params: &E::Params
-    ) -> Option<u32>
+    ) -> Option<u64>
     {
         let sr_point = self.right.mul(decryption_key.0, params);
         let neg_sr_point = sr_point.negate();
@@ -100,6 +100,20 @@ pub struct RandomizedSigVk<E: JubjubEngine>(
     pub edwards::Point<E, PrimeOrder>
 );
 
+/// A diversifier of the keys, by which one decryption key has many encryption keys,
+/// e.g. one for each counterparty, which can't be linked to each other without the decryption key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Diversifier(pub [u8; 11]);
+
+impl Diversifier {
+    /// The diversifier of the `index`-th diversified encryption key.
+    pub fn from_index(index: u64) -> Self {
+        let mut d = [0u8; 11];
+        d[..8].copy_from_slice(&index.to_le_bytes());
+        Diversifier(d)
+    }
+}
+
 /// Decryption key for decrypting transferred ammounts and balances
 #[derive(Clone)]
 pub struct DecryptionKey<E: JubjubEngine>(pub E::Fs);
@@ -120,6 +134,29 @@ impl<E: JubjubEngine> DecryptionKey<E> {
         self.0.into_repr().write_le(&mut writer)?;
         Ok(())
     }
+
+    /// The decryption key of the encryption key diversified by `diversifier`.
+    /// Unlike Sapling, the diversified encryption key is this key times the fixed generator,
+    /// so ciphertexts and transfers are the same for diversified encryption keys as for others.
+    pub fn diversify(&self, diversifier: &Diversifier) -> io::Result<Self> {
+        let mut preimage = [0u8; 32];
+        self.write(&mut preimage[..])?;
+
+        let mut h = Blake2s::with_params(32, &[], &[], KEY_DIVERSIFICATION_PERSONALIZATION);
+        h.update(&preimage);
+        h.update(&diversifier.0);
+        let mut h = h.finalize().as_ref().to_vec();
+
+        // Drop the most significant five bits, so it can be interpreted as a scalar.
+        h[31] &= 0b0000_0111;
+        let mut e = <E::Fs as PrimeField>::Repr::default();
+        e.read_le(&mut &h[..])?;
+
+        let fs = E::Fs::from_repr(e)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Not in field."))?;
+
+        Ok(DecryptionKey(fs))
+    }
 }
 
 impl<E: JubjubEngine> ProofGenerationKey<E> {
@@ -261,6 +298,16 @@ impl<E: JubjubEngine> EncryptionKey<E> {
         EncryptionKey(pk_d)
     }
 
+    /// The encryption key diversified by `diversifier` of the decryption key.
+    pub fn from_diversifier(
+        decryption_key: &DecryptionKey<E>,
+        diversifier: &Diversifier,
+        params: &E::Params,
+    ) -> io::Result<Self>
+    {
+        Ok(Self::from_decryption_key(&decryption_key.diversify(diversifier)?, params))
+    }
+
     pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
         self.0.write(&mut writer)?;
         Ok(())
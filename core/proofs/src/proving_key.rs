@@ -0,0 +1,646 @@
+//! Groth16 proving parameters which don't have to be held in memory as a whole.
+//! The parameters of the anonymous circuit are hundreds of MB, so reading the file into a buffer
+//! and deserializing it on top of that doesn't fit on small machines.
+//! `StreamedParameters` only indexes the file, and proves with its own prover:
+//! the multi-exponentiations of the queries (h, l, a, b_g1 and b_g2) run one after another,
+//! each reading its section from the file in chunks of `CHUNK_LEN` points,
+//! so only a chunk of the parameters is in memory at once.
+
+use bellman::{
+    groth16::{
+        create_random_proof,
+        Parameters,
+        Proof,
+        VerifyingKey,
+    },
+    domain::{EvaluationDomain, Scalar},
+    multicore::Worker,
+    Circuit,
+    ConstraintSystem,
+    Index,
+    LinearCombination,
+    SynthesisError,
+    Variable,
+};
+use pairing::{CurveAffine, CurveProjective, EncodedPoint, Field, PrimeField, PrimeFieldRepr};
+use scrypto::jubjub::JubjubEngine;
+use byteorder::{BigEndian, ReadBytesExt};
+use rand::Rng;
+use std::{
+    io::{self, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    fs::File,
+};
+
+/// The number of points of a query read from the parameters file at once.
+const CHUNK_LEN: usize = 1 << 14;
+
+/// Proving parameters of a circuit.
+pub enum ProvingKey<E: JubjubEngine> {
+    /// The whole parameters are deserialized in memory.
+    InMemory(Parameters<E>),
+    /// Each query is read from the parameters file while proving.
+    Streamed(StreamedParameters<E>),
+}
+
+impl<E: JubjubEngine> ProvingKey<E> {
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        match self {
+            ProvingKey::InMemory(params) => params.write(writer),
+            ProvingKey::Streamed(params) => {
+                io::copy(&mut File::open(&params.path)?, &mut writer)?;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn vk(&self) -> &VerifyingKey<E> {
+        match self {
+            ProvingKey::InMemory(params) => &params.vk,
+            ProvingKey::Streamed(params) => params.vk(),
+        }
+    }
+
+    pub fn create_random_proof<C: Circuit<E>, R: Rng>(
+        &self,
+        circuit: C,
+        rng: &mut R
+    ) -> Result<Proof<E>, SynthesisError> {
+        match self {
+            ProvingKey::InMemory(params) => create_random_proof(circuit, params, rng),
+            ProvingKey::Streamed(params) => params.create_random_proof(circuit, rng),
+        }
+    }
+}
+
+/// The position of a query in the parameters file.
+#[derive(Clone, Copy, Debug)]
+struct Section {
+    offset: u64,
+    len: usize,
+}
+
+/// An index of the queries in a parameters file written by `Parameters::write`.
+pub struct StreamedParameters<E: JubjubEngine> {
+    path: PathBuf,
+    vk: VerifyingKey<E>,
+    h: Section,
+    l: Section,
+    a: Section,
+    b_g1: Section,
+    b_g2: Section,
+    checked: bool,
+    chunk_len: usize,
+}
+
+impl<E: JubjubEngine> StreamedParameters<E> {
+    /// Index the parameters file. Only the verifying key is kept in memory.
+    /// If `checked` is true, the points are checked to be in the subgroup each time they are read.
+    pub fn open<P: AsRef<Path>>(path: P, checked: bool) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(&path)?);
+        let vk = VerifyingKey::<E>::read(&mut reader)?;
+
+        let g1_size = <E::G1Affine as CurveAffine>::Uncompressed::size();
+        let g2_size = <E::G2Affine as CurveAffine>::Uncompressed::size();
+
+        let h = Self::skip_section(&mut reader, g1_size)?;
+        let l = Self::skip_section(&mut reader, g1_size)?;
+        let a = Self::skip_section(&mut reader, g1_size)?;
+        let b_g1 = Self::skip_section(&mut reader, g1_size)?;
+        let b_g2 = Self::skip_section(&mut reader, g2_size)?;
+
+        Ok(StreamedParameters {
+            path: path.as_ref().to_path_buf(),
+            vk,
+            h,
+            l,
+            a,
+            b_g1,
+            b_g2,
+            checked,
+            chunk_len: CHUNK_LEN,
+        })
+    }
+
+    pub fn vk(&self) -> &VerifyingKey<E> {
+        &self.vk
+    }
+
+    fn skip_section<R: Read + Seek>(reader: &mut R, point_size: usize) -> io::Result<Section> {
+        let len = reader.read_u32::<BigEndian>()? as usize;
+        let offset = reader.seek(SeekFrom::Current(0))?;
+        reader.seek(SeekFrom::Current((len * point_size) as i64))?;
+
+        Ok(Section { offset, len })
+    }
+
+    /// Read the next `len` points of a section.
+    fn read_chunk<G: CurveAffine, R: Read>(&self, reader: &mut R, len: usize, chunk: &mut Vec<G>) -> io::Result<()> {
+        chunk.clear();
+        let mut repr = G::Uncompressed::empty();
+        for _ in 0..len {
+            reader.read_exact(repr.as_mut())?;
+
+            let point = if self.checked {
+                repr.into_affine()
+            } else {
+                repr.into_affine_unchecked()
+            }
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+            if point.is_zero() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "point at infinity"));
+            }
+            chunk.push(point);
+        }
+
+        Ok(())
+    }
+
+    /// The multi-exponentiation of the points of a section by the exponents, in the order of the section.
+    /// The section is read in chunks, and a chunk is dropped before the next one is read.
+    fn multiexp<G: CurveAffine>(
+        &self,
+        worker: &Worker,
+        section: Section,
+        exps: &[<G::Scalar as PrimeField>::Repr],
+    ) -> Result<G::Projective, SynthesisError> {
+        if exps.len() > section.len {
+            return Err(SynthesisError::IoError(io::Error::new(io::ErrorKind::UnexpectedEof, "too few points in the query")));
+        }
+
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        reader.seek(SeekFrom::Start(section.offset))?;
+
+        let mut acc = G::Projective::zero();
+        let mut chunk = Vec::with_capacity(self.chunk_len);
+        for exps in exps.chunks(self.chunk_len) {
+            self.read_chunk::<G, _>(&mut reader, exps.len(), &mut chunk)?;
+            acc.add_assign(&parallel_multiexp(worker, &chunk[..], exps));
+        }
+
+        Ok(acc)
+    }
+
+    pub fn create_random_proof<C: Circuit<E>, R: Rng>(
+        &self,
+        circuit: C,
+        rng: &mut R
+    ) -> Result<Proof<E>, SynthesisError> {
+        let r = rng.gen();
+        let s = rng.gen();
+
+        self.create_proof(circuit, r, s)
+    }
+
+    /// The same proof as `bellman::groth16::create_proof` with `Parameters`.
+    pub fn create_proof<C: Circuit<E>>(
+        &self,
+        circuit: C,
+        r: E::Fr,
+        s: E::Fr
+    ) -> Result<Proof<E>, SynthesisError> {
+        let mut prover = ProvingAssignment::<E>::default();
+
+        prover.alloc_input(|| "", || Ok(E::Fr::one()))?;
+
+        circuit.synthesize(&mut prover)?;
+
+        for i in 0..prover.input_assignment.len() {
+            prover.enforce(|| "",
+                |lc| lc + Variable::new_unchecked(Index::Input(i)),
+                |lc| lc,
+                |lc| lc,
+            );
+        }
+
+        let vk = &self.vk;
+        if vk.delta_g1.is_zero() || vk.delta_g2.is_zero() {
+            // If this element is zero, someone is trying to perform a
+            // subversion-CRS attack.
+            return Err(SynthesisError::UnexpectedIdentity);
+        }
+
+        let worker = Worker::new();
+
+        let h = {
+            let mut a = EvaluationDomain::from_coeffs(prover.a)?;
+            let mut b = EvaluationDomain::from_coeffs(prover.b)?;
+            let mut c = EvaluationDomain::from_coeffs(prover.c)?;
+            a.ifft(&worker);
+            a.coset_fft(&worker);
+            b.ifft(&worker);
+            b.coset_fft(&worker);
+            c.ifft(&worker);
+            c.coset_fft(&worker);
+
+            a.mul_assign(&worker, &b);
+            drop(b);
+            a.sub_assign(&worker, &c);
+            drop(c);
+            a.divide_by_z_on_coset(&worker);
+            a.icoset_fft(&worker);
+            let mut a = a.into_coeffs();
+            let a_len = a.len() - 1;
+            a.truncate(a_len);
+            let a = a.into_iter().map(|s| s.0.into_repr()).collect::<Vec<_>>();
+
+            self.multiexp::<E::G1Affine>(&worker, self.h, &a[..])?
+        };
+
+        let input_assignment = prover.input_assignment.iter().map(|s| s.into_repr()).collect::<Vec<_>>();
+        let aux_assignment = prover.aux_assignment.iter().map(|s| s.into_repr()).collect::<Vec<_>>();
+
+        let l = self.multiexp::<E::G1Affine>(&worker, self.l, &aux_assignment[..])?;
+
+        // The queries only have the points of the variables in their density, inputs first.
+        // The inputs have full density in the A query.
+        let dense = |assignment: &[<E::Fr as PrimeField>::Repr], density: &[bool]| -> Vec<<E::Fr as PrimeField>::Repr> {
+            assignment.iter().zip(density).filter(|(_, d)| **d).map(|(e, _)| *e).collect()
+        };
+        let a_exps = [&input_assignment[..], &dense(&aux_assignment, &prover.a_aux_density)[..]].concat();
+        let b_exps = [
+            &dense(&input_assignment, &prover.b_input_density)[..],
+            &dense(&aux_assignment, &prover.b_aux_density)[..],
+        ].concat();
+
+        let a_answer = self.multiexp::<E::G1Affine>(&worker, self.a, &a_exps[..])?;
+        let mut b1_answer = self.multiexp::<E::G1Affine>(&worker, self.b_g1, &b_exps[..])?;
+        let b2_answer = self.multiexp::<E::G2Affine>(&worker, self.b_g2, &b_exps[..])?;
+
+        let mut g_a = vk.delta_g1.mul(r);
+        g_a.add_assign_mixed(&vk.alpha_g1);
+        let mut g_b = vk.delta_g2.mul(s);
+        g_b.add_assign_mixed(&vk.beta_g2);
+        let mut g_c;
+        {
+            let mut rs = r;
+            rs.mul_assign(&s);
+
+            g_c = vk.delta_g1.mul(rs);
+            g_c.add_assign(&vk.alpha_g1.mul(s));
+            g_c.add_assign(&vk.beta_g1.mul(r));
+        }
+
+        let mut a_answer = a_answer;
+        g_a.add_assign(&a_answer);
+        a_answer.mul_assign(s);
+        g_c.add_assign(&a_answer);
+
+        g_b.add_assign(&b2_answer);
+        b1_answer.mul_assign(r);
+        g_c.add_assign(&b1_answer);
+        g_c.add_assign(&h);
+        g_c.add_assign(&l);
+
+        Ok(Proof {
+            a: g_a.into_affine(),
+            b: g_b.into_affine(),
+            c: g_c.into_affine(),
+        })
+    }
+}
+
+/// The evaluations of the A, B and C polynomials of a circuit and the assignments of its variables,
+/// as bellman's prover collects them.
+struct ProvingAssignment<E: JubjubEngine> {
+    // Whether each variable is in the queries
+    a_aux_density: Vec<bool>,
+    b_input_density: Vec<bool>,
+    b_aux_density: Vec<bool>,
+
+    // Evaluations of A, B, C polynomials
+    a: Vec<Scalar<E>>,
+    b: Vec<Scalar<E>>,
+    c: Vec<Scalar<E>>,
+
+    // Assignments of variables
+    input_assignment: Vec<E::Fr>,
+    aux_assignment: Vec<E::Fr>,
+}
+
+impl<E: JubjubEngine> Default for ProvingAssignment<E> {
+    fn default() -> Self {
+        ProvingAssignment {
+            a_aux_density: vec![],
+            b_input_density: vec![],
+            b_aux_density: vec![],
+            a: vec![],
+            b: vec![],
+            c: vec![],
+            input_assignment: vec![],
+            aux_assignment: vec![],
+        }
+    }
+}
+
+fn eval<E: JubjubEngine>(
+    lc: &LinearCombination<E>,
+    mut input_density: Option<&mut Vec<bool>>,
+    mut aux_density: Option<&mut Vec<bool>>,
+    input_assignment: &[E::Fr],
+    aux_assignment: &[E::Fr]
+) -> E::Fr {
+    let mut acc = E::Fr::zero();
+
+    for &(var, ref coeff) in lc.as_ref() {
+        let mut tmp = match var.get_unchecked() {
+            Index::Input(i) => {
+                if let Some(ref mut v) = input_density {
+                    v[i] = true;
+                }
+                input_assignment[i]
+            },
+            Index::Aux(i) => {
+                if let Some(ref mut v) = aux_density {
+                    v[i] = true;
+                }
+                aux_assignment[i]
+            },
+        };
+
+        tmp.mul_assign(coeff);
+        acc.add_assign(&tmp);
+    }
+
+    acc
+}
+
+impl<E: JubjubEngine> ConstraintSystem<E> for ProvingAssignment<E> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.aux_assignment.push(f()?);
+        self.a_aux_density.push(false);
+        self.b_aux_density.push(false);
+
+        Ok(Variable::new_unchecked(Index::Aux(self.aux_assignment.len() - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.input_assignment.push(f()?);
+        self.b_input_density.push(false);
+
+        Ok(Variable::new_unchecked(Index::Input(self.input_assignment.len() - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+        LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+        LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+    {
+        let a = a(LinearCombination::zero());
+        let b = b(LinearCombination::zero());
+        let c = c(LinearCombination::zero());
+
+        self.a.push(Scalar(eval(&a, None, Some(&mut self.a_aux_density), &self.input_assignment, &self.aux_assignment)));
+        self.b.push(Scalar(eval(
+            &b,
+            Some(&mut self.b_input_density),
+            Some(&mut self.b_aux_density),
+            &self.input_assignment,
+            &self.aux_assignment
+        )));
+        self.c.push(Scalar(eval(&c, None, None, &self.input_assignment, &self.aux_assignment)));
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {}
+
+    fn pop_namespace(&mut self) {}
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+/// The multi-exponentiation of a chunk, split among the cpus.
+fn parallel_multiexp<G: CurveAffine>(
+    worker: &Worker,
+    bases: &[G],
+    exps: &[<G::Scalar as PrimeField>::Repr],
+) -> G::Projective {
+    worker.scope(bases.len(), |scope, chunk_size| {
+        let partials = bases.chunks(chunk_size)
+            .zip(exps.chunks(chunk_size))
+            .map(|(bases, exps)| scope.spawn(move || multiexp(bases, exps)))
+            .collect::<Vec<_>>();
+
+        partials.into_iter().fold(G::Projective::zero(), |mut acc, partial| {
+            acc.add_assign(&partial.join());
+            acc
+        })
+    })
+}
+
+/// The multi-exponentiation by the bucket method, with windows of `c` bits of the exponents.
+fn multiexp<G: CurveAffine>(bases: &[G], exps: &[<G::Scalar as PrimeField>::Repr]) -> G::Projective {
+    let c = if bases.len() < 32 {
+        3
+    } else {
+        (bases.len() as f64).ln().ceil() as u32
+    };
+    let num_bits = <G::Scalar as PrimeField>::NUM_BITS;
+    let num_windows = (num_bits + c - 1) / c;
+
+    let mut acc = G::Projective::zero();
+    for window in (0..num_windows).rev() {
+        for _ in 0..c {
+            acc.double();
+        }
+
+        // buckets[i] is the sum of the bases whose exponents are i + 1 in this window.
+        let mut buckets = vec![G::Projective::zero(); (1 << c) - 1];
+        for (base, exp) in bases.iter().zip(exps) {
+            let mut exp = *exp;
+            exp.shr(window * c);
+            let digit = (exp.as_ref()[0] % (1 << c)) as usize;
+            if digit != 0 {
+                buckets[digit - 1].add_assign_mixed(base);
+            }
+        }
+
+        let mut running_sum = G::Projective::zero();
+        for bucket in buckets.into_iter().rev() {
+            running_sum.add_assign(&bucket);
+            acc.add_assign(&running_sum);
+        }
+    }
+
+    acc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellman::groth16::{create_proof, generate_random_parameters, prepare_verifying_key, verify_proof};
+    use pairing::bls12_381::{Bls12, Fr};
+    use rand::{Rand, SeedableRng, XorShiftRng};
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+        env,
+        fs,
+    };
+
+    /// Counts the bytes allocated by each thread, to measure the peak memory of proving.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATED: Cell<isize> = Cell::new(0);
+        static PEAK: Cell<isize> = Cell::new(0);
+    }
+
+    fn track(size: isize) {
+        let _ = ALLOCATED.try_with(|allocated| {
+            allocated.set(allocated.get() + size);
+            let _ = PEAK.try_with(|peak| peak.set(peak.get().max(allocated.get())));
+        });
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                track(layout.size() as isize);
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            track(-(layout.size() as isize));
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAllocator = CountingAllocator;
+
+    /// The peak of the bytes allocated by this thread while running `f`.
+    fn peak_memory<T, F: FnOnce() -> T>(f: F) -> (T, usize) {
+        let start = ALLOCATED.with(|allocated| allocated.get());
+        PEAK.with(|peak| peak.set(start));
+        let res = f();
+        (res, (PEAK.with(|peak| peak.get()) - start) as usize)
+    }
+
+    /// Knows x such that x^(2^n) is the input,
+    /// with a copy of x which is only in the C polynomial to have variables out of the B query.
+    #[derive(Clone)]
+    struct RepeatedSquaring {
+        x: Option<Fr>,
+        n: usize,
+    }
+
+    impl Circuit<Bls12> for RepeatedSquaring {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            let mut value = self.x;
+            let mut var = cs.alloc(|| "x", || value.ok_or(SynthesisError::AssignmentMissing))?;
+            let copy = cs.alloc(|| "copy of x", || value.ok_or(SynthesisError::AssignmentMissing))?;
+            cs.enforce(|| "copy", |lc| lc + var, |lc| lc + CS::one(), |lc| lc + copy);
+
+            for i in 0..self.n {
+                let square = value.map(|mut v| { v.square(); v });
+                let square_var = if i == self.n - 1 {
+                    cs.alloc_input(|| "y", || square.ok_or(SynthesisError::AssignmentMissing))?
+                } else {
+                    cs.alloc(|| "square", || square.ok_or(SynthesisError::AssignmentMissing))?
+                };
+                cs.enforce(|| "squaring", |lc| lc + var, |lc| lc + var, |lc| lc + square_var);
+
+                value = square;
+                var = square_var;
+            }
+
+            Ok(())
+        }
+    }
+
+    fn write_params(name: &str, params: &Parameters<Bls12>) -> PathBuf {
+        let path = env::temp_dir().join(name);
+        params.write(File::create(&path).unwrap()).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_streamed_proof_matches_in_memory() {
+        let rng = &mut XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let n = 100;
+        let params = generate_random_parameters::<Bls12, _, _>(RepeatedSquaring { x: None, n }, rng).unwrap();
+        let path = write_params("streamed_params_match.dat", &params);
+
+        let x = Fr::rand(rng);
+        let mut y = x;
+        for _ in 0..n {
+            y.square();
+        }
+        let circuit = RepeatedSquaring { x: Some(x), n };
+
+        let mut streamed = StreamedParameters::<Bls12>::open(&path, true).unwrap();
+        assert!(streamed.vk() == &params.vk);
+        // Read the queries in a few chunks, the last one of which isn't full.
+        streamed.chunk_len = 7;
+
+        let (r, s) = (Fr::rand(rng), Fr::rand(rng));
+        let proof = streamed.create_proof(circuit.clone(), r, s).unwrap();
+        assert!(proof == create_proof(circuit.clone(), &params, r, s).unwrap());
+        assert!(verify_proof(&prepare_verifying_key(&params.vk), &proof, &[y]).unwrap());
+
+        let proof = ProvingKey::Streamed(streamed).create_random_proof(circuit, rng).unwrap();
+        assert!(verify_proof(&prepare_verifying_key(&params.vk), &proof, &[y]).unwrap());
+
+        let mut buf = vec![];
+        ProvingKey::Streamed(StreamedParameters::<Bls12>::open(&path, false).unwrap())
+            .write(&mut buf)
+            .unwrap();
+        assert_eq!(buf.len() as u64, fs::metadata(&path).unwrap().len());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_streamed_proof_peak_memory() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let n = 256;
+        let params = generate_random_parameters::<Bls12, _, _>(RepeatedSquaring { x: None, n }, rng).unwrap();
+        let path = write_params("streamed_params_peak.dat", &params);
+        let params_size = fs::metadata(&path).unwrap().len() as usize;
+        drop(params);
+
+        let circuit = RepeatedSquaring { x: Some(Fr::rand(rng)), n };
+        let (r, s) = (Fr::rand(rng), Fr::rand(rng));
+
+        let (in_memory_proof, in_memory_peak) = peak_memory(|| {
+            let params = Parameters::<Bls12>::read(BufReader::new(File::open(&path).unwrap()), true).unwrap();
+            create_proof(circuit.clone(), &params, r, s).unwrap()
+        });
+
+        let mut streamed = StreamedParameters::<Bls12>::open(&path, true).unwrap();
+        streamed.chunk_len = 16;
+        let (streamed_proof, streamed_peak) = peak_memory(|| streamed.create_proof(circuit, r, s).unwrap());
+
+        assert!(streamed_proof == in_memory_proof);
+        // The parameters are in memory as a whole only without streaming,
+        // while the streamed prover never holds as many bytes as the parameters file.
+        assert!(in_memory_peak > params_size);
+        assert!(streamed_peak < params_size);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -4,7 +4,7 @@ use bellman::groth16::{
     prepare_verifying_key,
 };
 use rand::Rng;
-use crate::circuit::{ConfidentialTransfer, AnonymousTransfer};
+use crate::circuit::{ConfidentialTransfer, AnonymousTransfer, HashedInputs};
 use crate::PARAMS;
 use crate::crypto_components::{KeyContext, Confidential, Anonymous};
 
@@ -43,6 +43,7 @@ pub fn anonymous_setup<R: Rng>(rng: &mut R) -> KeyContext<Bls12, Anonymous> {
             params: &PARAMS,
             amount: None,
             remaining_balance: None,
+            fee: None,
             s_index: None,
             t_index: None,
             randomness: None,
@@ -67,6 +68,36 @@ pub fn anonymous_setup<R: Rng>(rng: &mut R) -> KeyContext<Bls12, Anonymous> {
     KeyContext::new(proving_key, prepared_vk)
 }
 
+/// Setup of the anonymous transfer circuit committing to its public inputs by a single hash input.
+/// Its verifying key is registered by `update_hashed_anonymous_vk` of zk-system.
+pub fn anonymous_hashed_setup<R: Rng>(rng: &mut R) -> KeyContext<Bls12, Anonymous> {
+    let proving_key = {
+        let c = AnonymousTransfer::<Bls12> {
+            params: &PARAMS,
+            amount: None,
+            remaining_balance: None,
+            fee: None,
+            s_index: None,
+            t_index: None,
+            randomness: None,
+            alpha: None,
+            proof_generation_key: None,
+            dec_key: None,
+            enc_keys: None,
+            left_ciphertexts: None,
+            right_ciphertext: None,
+            enc_balances: None,
+            g_epoch: None,
+        };
+
+        generate_random_parameters(HashedInputs::new(c, &PARAMS), rng).unwrap()
+    };
+
+    let prepared_vk = prepare_verifying_key(&proving_key.vk);
+
+    KeyContext::new(proving_key, prepared_vk)
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,324 @@
+//! The conformance vectors of the key derivations, redjubjub signatures and elgamal encryption,
+//! in `vectors/keys.json` of this crate.
+//!
+//! The vectors are the reference for third-party wallets: a wallet conforms if it derives
+//! the same keys and ciphertexts from the same seeds and randomness, and accepts exactly the valid signatures.
+//! They were made with an implementation independent of this repository, and the tests of this module check
+//! both in-repo key stacks against them: `keys` with `jubjub` and `zcrypto`, which the runtime uses,
+//! and the std alias of it in `zerochain-proofs` on `sapling-crypto`, which the prover and the wallet use.
+//!
+//! The two stacks agree on all the vectors. Their known differences are in naming only:
+//! the generator of the keys, signatures and ciphertexts is `FixedGenerators::Diversifier` in `jubjub`
+//! and `FixedGenerators::NoteCommitmentRandomness` in `sapling-crypto`, which are the same point.
+//! Note that `SpendingKey::from_seed` of both hashes the seed with the personalization of `prf_expand`
+//! but without its domain separator, so it is not `prf_expand(seed, &[0x00])`.
+//!
+//! A key vector is derived from its seed as:
+//! - `spending_key`: BLAKE2b-512 of the seed, personalized by `zech_ExpandSeed_`, reduced modulo the order of Jubjub.
+//! - `proof_generation_key`: the spending key times the generator.
+//! - `decryption_key`: BLAKE2s-256 of the proof generation key, personalized by `zech_bdk`,
+//!   with the most significant five bits dropped.
+//! - `encryption_key`: the decryption key times the generator.
+//!
+//! Scalars are 32 bytes little endian, and points are compressed as in Sapling.
+//! A signature vector is signed by the spending key plus `alpha`, so it verifies under
+//! the proof generation key plus `alpha` times the generator, as the randomized keys of transactions do.
+//! An elgamal ciphertext is the left point `amount·G + randomness·encryption_key` followed by the right point `randomness·G`.
+
+use std::{fs, path::{Path, PathBuf}};
+use serde_derive::Deserialize;
+
+/// The path of the conformance vectors.
+pub fn vectors_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("vectors/keys.json")
+}
+
+/// The conformance vectors. All the bytes are hex-encoded.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Vectors {
+    /// The generator of the keys, signatures and ciphertexts.
+    pub generator: String,
+    pub keys: Vec<KeyVector>,
+    pub signatures: Vec<SignatureVector>,
+    pub elgamal: Vec<ElgamalVector>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct KeyVector {
+    pub seed: String,
+    pub spending_key: String,
+    pub proof_generation_key: String,
+    pub decryption_key: String,
+    pub encryption_key: String,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct SignatureVector {
+    /// The seed of the signing key.
+    pub seed: String,
+    /// The randomizer of the signing key.
+    pub alpha: String,
+    pub message: String,
+    pub signature: String,
+    /// Whether the signature verifies.
+    pub valid: bool,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ElgamalVector {
+    /// The seed of the encryption key.
+    pub seed: String,
+    pub amount: u64,
+    pub randomness: String,
+    pub ciphertext: String,
+}
+
+/// Read the conformance vectors.
+pub fn vectors() -> Vectors {
+    let json = fs::read(vectors_path()).expect("the conformance vectors should exist.");
+    serde_json::from_slice(&json).expect("the conformance vectors should be well-formed.")
+}
+
+fn from_hex(s: &str) -> Vec<u8> {
+    hex::decode(s).expect("the conformance vectors should be hex-encoded.")
+}
+
+/// The derivations with `keys`, `jubjub` and `zcrypto`.
+mod runtime_stack {
+    use pairing::{PrimeField, PrimeFieldRepr, bls12_381::Bls12};
+    use jubjub::{
+        curve::{JubjubBls12, JubjubParams, FixedGenerators, edwards, Unknown, fs::{Fs, FsRepr}},
+        redjubjub::{PublicKey, Signature},
+    };
+    use keys::{SpendingKey, ProofGenerationKey, EncryptionKey};
+    use zcrypto::elgamal;
+    use super::{KeyVector, from_hex};
+
+    const P_G: FixedGenerators = FixedGenerators::Diversifier;
+
+    pub fn fs(s: &str) -> Fs {
+        let mut repr = FsRepr::default();
+        repr.read_le(&mut &from_hex(s)[..]).unwrap();
+        Fs::from_repr(repr).expect("the scalars of the vectors should be in the field.")
+    }
+
+    pub fn generator(params: &JubjubBls12) -> String {
+        let mut buf = vec![];
+        params.generator(P_G).write(&mut buf).unwrap();
+        hex::encode(buf)
+    }
+
+    pub fn derive(seed: &str, params: &JubjubBls12) -> KeyVector {
+        let sk = SpendingKey::<Bls12>::from_seed(&from_hex(seed));
+        let pgk = ProofGenerationKey::from_spending_key(&sk, params);
+        let dk = pgk.into_decryption_key().unwrap();
+        let ek = EncryptionKey::from_decryption_key(&dk, params);
+
+        let (mut sk_buf, mut pgk_buf, mut dk_buf, mut ek_buf) = (vec![], vec![], vec![], vec![]);
+        sk.write(&mut sk_buf).unwrap();
+        pgk.write(&mut pgk_buf).unwrap();
+        dk.write(&mut dk_buf).unwrap();
+        ek.write(&mut ek_buf).unwrap();
+
+        KeyVector {
+            seed: seed.to_string(),
+            spending_key: hex::encode(sk_buf),
+            proof_generation_key: hex::encode(pgk_buf),
+            decryption_key: hex::encode(dk_buf),
+            encryption_key: hex::encode(ek_buf),
+        }
+    }
+
+    pub fn verify(seed: &str, alpha: &str, msg: &str, sig: &str, params: &JubjubBls12) -> bool {
+        let pgk = ProofGenerationKey::<Bls12>::from_seed(&from_hex(seed), params);
+        let rvk = PublicKey::<Bls12>(pgk.into_rvk(fs(alpha), params).0.into());
+        let sig = Signature::read(&from_hex(sig)[..]).unwrap();
+
+        rvk.verify(&from_hex(msg), &sig, P_G, params)
+    }
+
+    /// Sign with the randomized signing key and verify under the randomized verification key.
+    pub fn sign_and_verify(seed: &str, alpha: &str, msg: &str, params: &JubjubBls12) -> String {
+        let rng = &mut rand::thread_rng();
+        let sk = SpendingKey::<Bls12>::from_seed(&from_hex(seed));
+        let sig = sk.into_rsk(fs(alpha)).sign(&from_hex(msg), rng, P_G, params);
+        assert!(verify(seed, alpha, msg, &hex::encode(&[&sig.rbar[..], &sig.sbar[..]].concat()), params));
+
+        hex::encode(&[&sig.rbar[..], &sig.sbar[..]].concat())
+    }
+
+    pub fn encrypt(seed: &str, amount: u64, randomness: &str, params: &JubjubBls12) -> String {
+        let ek = EncryptionKey::<Bls12>::from_seed(&from_hex(seed), params).unwrap();
+        let ciphertext = elgamal::Ciphertext::encrypt(amount, &fs(randomness), &ek, P_G, params);
+
+        let mut buf = vec![];
+        ciphertext.write(&mut buf).unwrap();
+        hex::encode(buf)
+    }
+
+    pub fn decrypt(seed: &str, ciphertext: &str, params: &JubjubBls12) -> Option<u64> {
+        let dk = ProofGenerationKey::<Bls12>::from_seed(&from_hex(seed), params).into_decryption_key().unwrap();
+        let bytes = from_hex(ciphertext);
+        let left = edwards::Point::<Bls12, Unknown>::read(&mut &bytes[..32], params).unwrap()
+            .as_prime_order(params).unwrap();
+        let right = edwards::Point::<Bls12, Unknown>::read(&mut &bytes[32..], params).unwrap()
+            .as_prime_order(params).unwrap();
+
+        elgamal::Ciphertext::new(left, right).decrypt(&dk, P_G, params)
+    }
+}
+
+/// The derivations with the std alias of `keys` in `zerochain-proofs`, on `sapling-crypto`.
+mod prover_stack {
+    use test_pairing::{PrimeField, PrimeFieldRepr, bls12_381::Bls12};
+    use scrypto::{
+        jubjub::{JubjubBls12, JubjubParams, FixedGenerators, fs::{Fs, FsRepr}},
+        redjubjub::{PublicKey, Signature},
+    };
+    use test_proofs::{SpendingKey, ProofGenerationKey, EncryptionKey, DecryptionKey, elgamal};
+    use super::{KeyVector, from_hex};
+
+    const P_G: FixedGenerators = FixedGenerators::NoteCommitmentRandomness;
+
+    pub fn fs(s: &str) -> Fs {
+        let mut repr = FsRepr::default();
+        repr.read_le(&mut &from_hex(s)[..]).unwrap();
+        Fs::from_repr(repr).expect("the scalars of the vectors should be in the field.")
+    }
+
+    pub fn generator(params: &JubjubBls12) -> String {
+        let mut buf = vec![];
+        params.generator(P_G).write(&mut buf).unwrap();
+        hex::encode(buf)
+    }
+
+    pub fn derive(seed: &str, params: &JubjubBls12) -> KeyVector {
+        let sk = SpendingKey::<Bls12>::from_seed(&from_hex(seed));
+        let pgk = ProofGenerationKey::from_spending_key(&sk, params);
+        let dk = pgk.into_decryption_key().unwrap();
+        let ek = EncryptionKey::from_decryption_key(&dk, params);
+
+        let (mut sk_buf, mut pgk_buf, mut dk_buf, mut ek_buf) = (vec![], vec![], vec![], vec![]);
+        sk.write(&mut sk_buf).unwrap();
+        pgk.write(&mut pgk_buf).unwrap();
+        dk.write(&mut dk_buf).unwrap();
+        ek.write(&mut ek_buf).unwrap();
+
+        KeyVector {
+            seed: seed.to_string(),
+            spending_key: hex::encode(sk_buf),
+            proof_generation_key: hex::encode(pgk_buf),
+            decryption_key: hex::encode(dk_buf),
+            encryption_key: hex::encode(ek_buf),
+        }
+    }
+
+    pub fn verify(seed: &str, alpha: &str, msg: &str, sig: &str, params: &JubjubBls12) -> bool {
+        let pgk = ProofGenerationKey::<Bls12>::from_seed(&from_hex(seed), params);
+        let rvk = PublicKey::<Bls12>(pgk.into_rvk(fs(alpha), params).0.into());
+        let sig = Signature::read(&from_hex(sig)[..]).unwrap();
+
+        rvk.verify(&from_hex(msg), &sig, P_G, params)
+    }
+
+    /// Sign with the randomized signing key and verify under the randomized verification key.
+    pub fn sign_and_verify(seed: &str, alpha: &str, msg: &str, params: &JubjubBls12) -> String {
+        let rng = &mut rand::thread_rng();
+        let sk = SpendingKey::<Bls12>::from_seed(&from_hex(seed));
+        let sig = sk.into_rsk(fs(alpha)).sign(&from_hex(msg), rng, P_G, params);
+        assert!(verify(seed, alpha, msg, &hex::encode(&[&sig.rbar[..], &sig.sbar[..]].concat()), params));
+
+        hex::encode(&[&sig.rbar[..], &sig.sbar[..]].concat())
+    }
+
+    pub fn encrypt(seed: &str, amount: u64, randomness: &str, params: &JubjubBls12) -> String {
+        let ek = EncryptionKey::<Bls12>::from_seed(&from_hex(seed), params).unwrap();
+        let ciphertext = elgamal::Ciphertext::encrypt(amount, &fs(randomness), &ek, P_G, params);
+
+        let mut buf = vec![];
+        ciphertext.write(&mut buf).unwrap();
+        hex::encode(buf)
+    }
+
+    pub fn decrypt(seed: &str, ciphertext: &str, params: &JubjubBls12) -> Option<u64> {
+        let dk: DecryptionKey<Bls12> = ProofGenerationKey::<Bls12>::from_seed(&from_hex(seed), params)
+            .into_decryption_key().unwrap();
+        let ciphertext = elgamal::Ciphertext::read(&mut &from_hex(ciphertext)[..], params).unwrap();
+
+        ciphertext.decrypt(&dk, P_G, params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jubjub::curve::JubjubBls12;
+    use test_proofs::PARAMS;
+    use crate::{ALICE_SEED, BOB_SEED, BOB_ADDRESS};
+
+    /// The amounts up to this are decrypted, as the decryption is by brute force.
+    const MAX_DECRYPTED_AMOUNT: u64 = 1_000;
+
+    #[test]
+    fn test_vectors_cover_the_fixtures() {
+        let vectors = vectors();
+        let seeds: Vec<_> = vectors.keys.iter().map(|k| from_hex(&k.seed)).collect();
+
+        assert!(seeds.contains(&ALICE_SEED.to_vec()));
+        assert!(seeds.contains(&BOB_SEED.to_vec()));
+        assert!(vectors.keys.iter().any(|k| k.encryption_key == hex::encode(&BOB_ADDRESS[..])));
+        assert!(vectors.signatures.iter().any(|s| !s.valid));
+    }
+
+    #[test]
+    fn test_generator() {
+        let vectors = vectors();
+
+        assert_eq!(runtime_stack::generator(&JubjubBls12::new()), vectors.generator);
+        assert_eq!(prover_stack::generator(&PARAMS), vectors.generator);
+    }
+
+    #[test]
+    fn test_key_derivations() {
+        let params = &JubjubBls12::new();
+
+        for v in vectors().keys {
+            assert_eq!(runtime_stack::derive(&v.seed, params), v);
+            assert_eq!(prover_stack::derive(&v.seed, &PARAMS), v);
+        }
+    }
+
+    #[test]
+    fn test_signatures() {
+        let params = &JubjubBls12::new();
+
+        for v in vectors().signatures {
+            assert_eq!(runtime_stack::verify(&v.seed, &v.alpha, &v.message, &v.signature, params), v.valid);
+            assert_eq!(prover_stack::verify(&v.seed, &v.alpha, &v.message, &v.signature, &PARAMS), v.valid);
+
+            if v.valid {
+                // The signatures are randomized, so each stack must accept the other's.
+                let sig = runtime_stack::sign_and_verify(&v.seed, &v.alpha, &v.message, params);
+                assert!(prover_stack::verify(&v.seed, &v.alpha, &v.message, &sig, &PARAMS));
+
+                let sig = prover_stack::sign_and_verify(&v.seed, &v.alpha, &v.message, &PARAMS);
+                assert!(runtime_stack::verify(&v.seed, &v.alpha, &v.message, &sig, params));
+            }
+        }
+    }
+
+    #[test]
+    fn test_elgamal() {
+        let params = &JubjubBls12::new();
+
+        for v in vectors().elgamal {
+            assert_eq!(runtime_stack::encrypt(&v.seed, v.amount, &v.randomness, params), v.ciphertext);
+            assert_eq!(prover_stack::encrypt(&v.seed, v.amount, &v.randomness, &PARAMS), v.ciphertext);
+
+            if v.amount <= MAX_DECRYPTED_AMOUNT {
+                assert_eq!(runtime_stack::decrypt(&v.seed, &v.ciphertext, params), Some(v.amount));
+                assert_eq!(prover_stack::decrypt(&v.seed, &v.ciphertext, &PARAMS), Some(v.amount));
+            }
+        }
+    }
+}
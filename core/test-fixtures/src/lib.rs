@@ -0,0 +1,237 @@
+//! Canonical fixtures for the tests of the runtime modules:
+//! the keys of the test accounts, their genesis balances, the test params of both circuits
+//! and valid proofs made with them.
+//!
+//! The params are resolved from the manifest directory of this crate,
+//! so tests don't depend on the directory cargo runs them in.
+//! The conformance vectors of the key derivations are in `conformance`.
+
+pub mod conformance;
+
+use std::{
+    path::{Path, PathBuf},
+    fs,
+    convert::TryFrom,
+};
+use rand::{Rng, SeedableRng, XorShiftRng};
+use hex_literal::{hex, hex_impl};
+use pairing::{Field, bls12_381::Bls12};
+use jubjub::curve::{JubjubBls12, FixedGenerators, fs::Fs};
+use keys::{EncryptionKey, ProofGenerationKey, DecryptionKey};
+use zcrypto::elgamal;
+use zprimitives::{EncKey, Ciphertext, GEpoch, PARAMS as ZPARAMS, enc_key::revealing_enc_key};
+use bellman_verifier::PreparedVerifyingKey;
+use test_pairing::bls12_381::Bls12 as tBls12;
+use scrypto::jubjub::{edwards as tedwards, PrimeOrder as tPrimeOrder};
+use test_proofs::{
+    EncryptionKey as tEncryptionKey, SpendingKey as tSpendingKey, elgamal as telgamal,
+    PARAMS, MultiEncKeys, KeyContext, ProofBuilder, Confidential,
+    crypto_components::Anonymous,
+    confidential::ConfidentialXt,
+    anonymous::AnonymousXt,
+};
+
+pub const ALICE_SEED: &[u8; 32] = b"Alice                           ";
+pub const BOB_SEED: &[u8; 32] = b"Bob                             ";
+
+/// The encryption key of `BOB_SEED`.
+pub const BOB_ADDRESS: [u8; 32] = hex!("45e66da531088b55dcb3b273ca825454d79d2d1d5c4fa2ba4a12c1fa1ccd6389");
+
+/// The balance of Alice in the genesis of the test externalities.
+pub const ALICE_BALANCE: u64 = 100;
+
+/// The number of decoys of the anonymity set, next to Alice and Bob.
+pub const DECOYS: usize = 10;
+
+pub const CONF_PK: &str = "test_conf_pk.dat";
+pub const CONF_VK: &str = "test_conf_vk.dat";
+pub const ANONY_PK: &str = "test_anony_pk.dat";
+pub const ANONY_VK: &str = "test_anony_vk.dat";
+
+/// The path of a file of the test params.
+pub fn params_path(file: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../zface/params").join(file)
+}
+
+pub fn vk_bytes(file: &str) -> Vec<u8> {
+    fs::read(params_path(file)).expect("the test params should exist.")
+}
+
+pub fn read_vk(file: &str) -> PreparedVerifyingKey<Bls12> {
+    PreparedVerifyingKey::<Bls12>::read(&mut &vk_bytes(file)[..])
+        .expect("the test params should be a prepared verifying key.")
+}
+
+pub fn conf_vk() -> PreparedVerifyingKey<Bls12> {
+    read_vk(CONF_VK)
+}
+
+pub fn anony_vk() -> PreparedVerifyingKey<Bls12> {
+    read_vk(ANONY_VK)
+}
+
+pub fn enc_key(seed: &[u8; 32]) -> EncryptionKey<Bls12> {
+    EncryptionKey::<Bls12>::from_seed(&seed[..], &*ZPARAMS)
+        .expect("should be generated encryption key from seed.")
+}
+
+pub fn dec_key(seed: &[u8; 32]) -> DecryptionKey<Bls12> {
+    ProofGenerationKey::<Bls12>::from_seed(&seed[..], &*ZPARAMS)
+        .into_decryption_key()
+        .expect("should be generated decryption key from seed.")
+}
+
+pub fn address(seed: &[u8; 32]) -> EncKey {
+    EncKey::try_from(enc_key(seed)).unwrap()
+}
+
+/// The genesis balance of an account. The genesis balances are not encrypted with randomness.
+pub fn genesis_balance(enc_key: &EncryptionKey<Bls12>, amount: u64) -> (EncKey, Ciphertext) {
+    let ciphertext = elgamal::Ciphertext::encrypt(amount, &Fs::one(), enc_key, FixedGenerators::Diversifier, &*ZPARAMS);
+
+    (EncKey::try_from(enc_key.clone()).unwrap(), Ciphertext::try_from(ciphertext).unwrap())
+}
+
+/// The genesis balance of Alice and her last rollover.
+pub fn alice_genesis() -> ((EncKey, Ciphertext), (EncKey, u64)) {
+    let enc_key = enc_key(ALICE_SEED);
+
+    (genesis_balance(&enc_key, ALICE_BALANCE), (address(ALICE_SEED), 0))
+}
+
+/// The accounts of anonymous transfers: Alice, Bob and the decoys, in this order.
+pub fn anonymity_set() -> Vec<EncryptionKey<Bls12>> {
+    let params = &JubjubBls12::new();
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let mut acc = vec![enc_key(ALICE_SEED), enc_key(BOB_SEED)];
+    for _ in 0..DECOYS {
+        let seed: [u8; 32] = rng.gen();
+        acc.push(EncryptionKey::<Bls12>::from_seed(&seed, params)
+            .expect("should be generated encryption key from seed."));
+    }
+
+    acc
+}
+
+/// The genesis balances of the anonymity set, where only Alice has a balance.
+pub fn anonymity_set_genesis(alice_amount: u64) -> Vec<(EncKey, Ciphertext)> {
+    anonymity_set().iter().enumerate()
+        .map(|(i, e)| genesis_balance(e, if i == 0 { alice_amount } else { 0 }))
+        .collect()
+}
+
+/// The g_epoch of an epoch in the prover's types.
+pub fn g_epoch(epoch: u32) -> tedwards::Point<tBls12, tPrimeOrder> {
+    let g_epoch = GEpoch::group_hash(epoch).unwrap();
+    tedwards::Point::read(g_epoch.as_bytes(), &*PARAMS).unwrap()
+        .as_prime_order(&*PARAMS).unwrap()
+}
+
+fn into_prover_key(enc_key: &EncryptionKey<Bls12>) -> tEncryptionKey<tBls12> {
+    let mut buf = vec![];
+    enc_key.write(&mut buf).unwrap();
+    tEncryptionKey::<tBls12>::read(&mut &buf[..], &*PARAMS).unwrap()
+}
+
+fn into_prover_ciphertext(ciphertext: &Ciphertext) -> telgamal::Ciphertext<tBls12> {
+    telgamal::Ciphertext::read(&mut ciphertext.as_bytes(), &*PARAMS).unwrap()
+}
+
+/// A valid proof of a confidential transfer of `amount` from Alice's genesis balance of `balance` to Bob.
+pub fn confidential_transfer<R: Rng>(
+    amount: u64,
+    fee: u64,
+    balance: u64,
+    epoch: u32,
+    rng: &mut R,
+) -> ConfidentialXt {
+    confidential_transfer_to(&enc_key(BOB_SEED), amount, fee, balance, epoch, rng)
+}
+
+/// A valid proof of burning `amount` from Alice's genesis balance of `balance`,
+/// i.e. of a confidential transfer to `revealing_enc_key`.
+pub fn burn<R: Rng>(
+    amount: u64,
+    fee: u64,
+    balance: u64,
+    epoch: u32,
+    rng: &mut R,
+) -> ConfidentialXt {
+    let recipient = EncryptionKey::<Bls12>::try_from(revealing_enc_key()).unwrap();
+    confidential_transfer_to(&recipient, amount, fee, balance, epoch, rng)
+}
+
+fn confidential_transfer_to<R: Rng>(
+    recipient: &EncryptionKey<Bls12>,
+    amount: u64,
+    fee: u64,
+    balance: u64,
+    epoch: u32,
+    rng: &mut R,
+) -> ConfidentialXt {
+    let (_, enc_balance) = genesis_balance(&enc_key(ALICE_SEED), balance);
+
+    KeyContext::read_from_path(params_path(CONF_PK), params_path(CONF_VK))
+        .expect("the test params should exist.")
+        .gen_proof(
+            amount,
+            fee,
+            balance - amount - fee, 0, 0,
+            &tSpendingKey::<tBls12>::from_seed(&ALICE_SEED[..]),
+            MultiEncKeys::<tBls12, Confidential>::new(into_prover_key(recipient)),
+            &[into_prover_ciphertext(&enc_balance)],
+            g_epoch(epoch),
+            rng,
+            &*PARAMS
+        )
+        .expect("should be proved with the test params.")
+}
+
+/// A valid proof of an anonymous transfer of `amount` from Alice to Bob among the anonymity set,
+/// whose current balances are `enc_balances`.
+pub fn anonymous_transfer<R: Rng>(
+    amount: u64,
+    remaining_balance: u64,
+    enc_balances: &[Ciphertext],
+    epoch: u32,
+    rng: &mut R,
+) -> AnonymousXt {
+    let decoys = anonymity_set().iter().skip(2).map(into_prover_key).collect();
+    let enc_balances = enc_balances.iter().map(into_prover_ciphertext).collect::<Vec<_>>();
+
+    KeyContext::<tBls12, Anonymous>::read_from_path(params_path(ANONY_PK), params_path(ANONY_VK))
+        .expect("the test params should exist.")
+        .gen_proof(
+            amount,
+            0,
+            remaining_balance,
+            0,
+            1,
+            &tSpendingKey::<tBls12>::from_seed(&ALICE_SEED[..]),
+            MultiEncKeys::<tBls12, Anonymous>::new(into_prover_key(&enc_key(BOB_SEED)), decoys),
+            &enc_balances,
+            g_epoch(epoch),
+            rng,
+            &*PARAMS
+        )
+        .expect("should be proved with the test params.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixtures() {
+        assert_eq!(address(BOB_SEED), EncKey::from_slice(&BOB_ADDRESS[..]));
+
+        let ((_, balance), _) = alice_genesis();
+        let balance = elgamal::Ciphertext::<Bls12>::try_from(balance).unwrap();
+        assert_eq!(balance.decrypt(&dec_key(ALICE_SEED), FixedGenerators::Diversifier, &*ZPARAMS), Some(ALICE_BALANCE));
+
+        assert_eq!(conf_vk().num_inputs(), 22);
+        assert_eq!(anony_vk().num_inputs(), 104);
+        assert_eq!(anonymity_set_genesis(ALICE_BALANCE).len(), DECOYS + 2);
+    }
+}
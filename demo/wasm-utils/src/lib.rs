@@ -1,23 +1,23 @@
 // extern crate cfg_if;
-// #[macro_use]
-// extern crate serde_derive;
+#[macro_use]
+extern crate serde_derive;
 
 // mod utils;
 // use cfg_if::cfg_if;
-// use wasm_bindgen::prelude::*;
+use wasm_bindgen::prelude::*;
 
 // use rand::{ChaChaRng, SeedableRng, Rng, Rand};
 // use keys;
+use zpairing::bls12_381::Bls12 as zBls12;
 // use zpairing::{
-//     bls12_381::Bls12 as zBls12,
 //     Field as zField, PrimeField as zPrimeField, PrimeFieldRepr as zPrimeFieldRepr,
 // };
 // use pairing::{
 //     bls12_381::Bls12, Field,
 // };
+use zjubjub::curve::{JubjubBls12 as zJubjubBls12, FixedGenerators as zFixedGenerators};
 // use zjubjub::{
-//     curve::{JubjubBls12 as zJubjubBls12,
-//         FixedGenerators as zFixedGenerators,
+//     curve::{
 //         JubjubParams as zJubjubParams,
 //         edwards::Point as zPoint,
 //         fs::Fs as zFs
@@ -36,7 +36,7 @@
 //     elgamal::Ciphertext,
 // };
 // use bellman::groth16::{Parameters, PreparedVerifyingKey};
-// use zcrypto::elgamal::Ciphertext as zCiphertext;
+use zcrypto::elgamal::Ciphertext as zCiphertext;
 
 // pub mod transaction;
 // use transaction::Transaction;
@@ -246,22 +246,195 @@
 //     JsValue::from_serde(&calls).expect("fails to write json")
 // }
 
-// #[wasm_bindgen(catch)]
-// pub fn decrypt_ca(mut ciphertext: &[u8], mut sk: &[u8]) -> Result<u32, JsValue> {
-//     let params = &zJubjubBls12::new();
-//     let p_g = zFixedGenerators::Diversifier;
-
-//     let ciphertext = zCiphertext::<zBls12>::read(&mut ciphertext, params).unwrap();
-//     let mut sk_repr = zFs::default().into_repr();
-//     sk_repr.read_le(&mut sk).unwrap();
-
-//     match ciphertext.decrypt(zFs::from_repr(sk_repr).unwrap(), p_g, params) {
-//         Some(v) => Ok(v),
-//         None => {
-//             Err(JsValue::from_str("fails to decrypt"))
-//         }
-//     }
-// }
+const CIPHERTEXT_SIZE: usize = 64;
+
+#[wasm_bindgen]
+pub fn decrypt_ca(mut ciphertext: &[u8], dk: &[u8]) -> Result<u64, JsValue> {
+    let params = &zJubjubBls12::new();
+    let p_g = zFixedGenerators::Diversifier;
+
+    let ciphertext = zCiphertext::<zBls12>::read(&mut ciphertext, params)
+        .map_err(|_| JsValue::from_str("fails to read ciphertext"))?;
+    let dk = read_dk(dk).map_err(JsValue::from_str)?;
+
+    ciphertext.decrypt(&dk, p_g, params)
+        .ok_or(JsValue::from_str("fails to decrypt"))
+}
+
+/// Decrypt the sum of pending transfers given as concatenated 64-byte ciphertexts.
+#[wasm_bindgen]
+pub fn decrypt_pending(ciphertexts_concat: &[u8], dk: &[u8]) -> Result<u64, JsValue> {
+    let params = &zJubjubBls12::new();
+    let p_g = zFixedGenerators::Diversifier;
+
+    let pending = sum_ciphertexts(ciphertexts_concat, params).map_err(JsValue::from_str)?;
+    let dk = read_dk(dk).map_err(JsValue::from_str)?;
+
+    pending.decrypt(&dk, p_g, params)
+        .ok_or(JsValue::from_str("fails to decrypt"))
+}
+
+/// Decrypted balance of an account, split in the same way as zface's `BalanceQuery`.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct BalanceView {
+    /// Balance which can be spent in the current epoch.
+    pub spendable: u64,
+    /// Transfers received in the current epoch, spendable after the next rollover.
+    pub pending: u64,
+    /// Sum of spendable and pending.
+    pub total: u64,
+    /// Sum of the encrypted balance and the pending transfers.
+    pub encrypted_total: Vec<u8>,
+}
+
+/// Decrypt an encrypted balance and its pending transfers.
+/// An empty `enc_balance` or `enc_pending` is treated as an encryption of zero.
+#[wasm_bindgen]
+pub fn aggregate_balance(enc_balance: &[u8], enc_pending: &[u8], dk: &[u8]) -> Result<JsValue, JsValue> {
+    let balance_view = balance_view(enc_balance, enc_pending, dk)
+        .map_err(JsValue::from_str)?;
+
+    JsValue::from_serde(&balance_view)
+        .map_err(|_| JsValue::from_str("fails to write json"))
+}
+
+fn balance_view(enc_balance: &[u8], enc_pending: &[u8], dk: &[u8]) -> Result<BalanceView, &'static str> {
+    let params = &zJubjubBls12::new();
+    let p_g = zFixedGenerators::Diversifier;
+
+    let balance = sum_ciphertexts(enc_balance, params)?;
+    let pending = sum_ciphertexts(enc_pending, params)?;
+    let dk = read_dk(dk)?;
+
+    let spendable = balance.decrypt(&dk, p_g, params).ok_or("fails to decrypt balance")?;
+    let pending_amount = pending.decrypt(&dk, p_g, params).ok_or("fails to decrypt pending transfer")?;
+    let total = spendable.checked_add(pending_amount).ok_or("overflow in total balance")?;
+
+    let mut encrypted_total = vec![0u8; CIPHERTEXT_SIZE];
+    balance.add(&pending, params)
+        .write(&mut encrypted_total[..])
+        .map_err(|_| "fails to write ciphertext")?;
+
+    Ok(BalanceView {
+        spendable,
+        pending: pending_amount,
+        total,
+        encrypted_total,
+    })
+}
+
+fn sum_ciphertexts(ciphertexts_concat: &[u8], params: &zJubjubBls12) -> Result<zCiphertext<zBls12>, &'static str> {
+    if ciphertexts_concat.len() % CIPHERTEXT_SIZE != 0 {
+        return Err("ciphertexts must be a multiple of 64 bytes");
+    }
+
+    ciphertexts_concat.chunks(CIPHERTEXT_SIZE)
+        .try_fold(zCiphertext::zero(), |acc, mut c| {
+            zCiphertext::<zBls12>::read(&mut c, params)
+                .map(|c| acc.add(&c, params))
+                .map_err(|_| "fails to read ciphertext")
+        })
+}
+
+fn read_dk(dk: &[u8]) -> Result<keys::DecryptionKey<zBls12>, &'static str> {
+    keys::DecryptionKey::<zBls12>::read(dk)
+        .map_err(|_| "fails to read decryption key")
+}
+
+/// Import a decryption key exported by zface, as the bytes taken by the functions above.
+#[wasm_bindgen]
+pub fn import_dk(encoded: &str) -> Result<Vec<u8>, JsValue> {
+    let dk: keys::DecryptionKey<zBls12> = encoded.trim().parse()
+        .map_err(JsValue::from_str)?;
+
+    let mut buf = vec![];
+    dk.write(&mut buf)
+        .map_err(|_| JsValue::from_str("fails to write decryption key"))?;
+    Ok(buf)
+}
+
+/// Export the bytes of a decryption key in the format zface imports.
+#[wasm_bindgen]
+pub fn export_dk(dk: &[u8]) -> Result<String, JsValue> {
+    let dk = read_dk(dk).map_err(JsValue::from_str)?;
+    Ok(dk.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zpairing::Field;
+
+    fn alice_dk(params: &zJubjubBls12) -> keys::DecryptionKey<zBls12> {
+        keys::ProofGenerationKey::<zBls12>::from_seed(b"Alice                           ", params)
+            .into_decryption_key()
+            .unwrap()
+    }
+
+    fn encrypt(amount: u64, dk: &keys::DecryptionKey<zBls12>, params: &zJubjubBls12) -> Vec<u8> {
+        let p_g = zFixedGenerators::Diversifier;
+        let enc_key = keys::EncryptionKey::from_decryption_key(dk, params);
+        let randomness = zjubjub::curve::fs::Fs::one();
+        let ciphertext = zCiphertext::encrypt(amount, &randomness, &enc_key, p_g, params);
+
+        let mut buf = vec![0u8; CIPHERTEXT_SIZE];
+        ciphertext.write(&mut buf[..]).unwrap();
+        buf
+    }
+
+    fn dk_bytes(dk: &keys::DecryptionKey<zBls12>) -> Vec<u8> {
+        let mut buf = vec![];
+        dk.write(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_balance_view() {
+        let params = &zJubjubBls12::new();
+        let dk = alice_dk(params);
+
+        let enc_balance = encrypt(100, &dk, params);
+        let mut enc_pending = encrypt(7, &dk, params);
+        enc_pending.extend(encrypt(3, &dk, params));
+
+        let view = balance_view(&enc_balance, &enc_pending, &dk_bytes(&dk)).unwrap();
+        assert_eq!(view.spendable, 100);
+        assert_eq!(view.pending, 10);
+        assert_eq!(view.total, 110);
+
+        let total = zCiphertext::<zBls12>::read(&mut &view.encrypted_total[..], params).unwrap();
+        assert_eq!(total.decrypt(&dk, zFixedGenerators::Diversifier, params), Some(110));
+    }
+
+    #[test]
+    fn test_balance_view_without_pending() {
+        let params = &zJubjubBls12::new();
+        let dk = alice_dk(params);
+
+        let view = balance_view(&encrypt(5, &dk, params), &[], &dk_bytes(&dk)).unwrap();
+        assert_eq!(view.spendable, 5);
+        assert_eq!(view.pending, 0);
+        assert_eq!(view.total, 5);
+    }
+
+    #[test]
+    fn test_rejects_truncated_ciphertexts() {
+        let params = &zJubjubBls12::new();
+        let dk = alice_dk(params);
+
+        let enc_pending = encrypt(7, &dk, params);
+        assert!(balance_view(&[], &enc_pending[..63], &dk_bytes(&dk)).is_err());
+    }
+
+    #[test]
+    fn test_export_import_dk() {
+        let params = &zJubjubBls12::new();
+        let dk = dk_bytes(&alice_dk(params));
+
+        let encoded = export_dk(&dk).unwrap();
+        assert_eq!(import_dk(&encoded).unwrap(), dk);
+    }
+}
 
 // #[cfg(test)]
 // mod tests {
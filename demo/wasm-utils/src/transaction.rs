@@ -29,8 +29,8 @@ pub struct Transaction{
 
 impl Transaction {
     pub fn gen_tx<R: Rng>(
-        value: u32,
-        remaining_balance: u32,
+        value: u64,
+        remaining_balance: u64,
         alpha: fs::Fs,
         proving_key: &Parameters<Bls12>,
 		prepared_vk: &PreparedVerifyingKey<Bls12>,
@@ -38,7 +38,7 @@ impl Transaction {
 		ok_sender: &fs::Fs,
         ciphertext_balance: proofs::elgamal::Ciphertext<Bls12>,
 		rng: &mut R,
-		fee: u32,
+		fee: u64,
     ) -> Result<Self, io::Error>
 	{
 		// The pramaters from std environment
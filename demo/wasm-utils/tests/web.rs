@@ -82,7 +82,7 @@ fn test_decrypt() {
     let rng = &mut XorShiftRng::from_seed([0xbc4f6d47, 0xd62f276d, 0xb963afd3, 0x54558639]);
     let r_fs = zFs::rand(rng);
 
-    let value: u32 = 6 as u32;
+    let value: u64 = 6 as u64;
     let ciphetext = zCiphertext::encrypt(value, r_fs, &address.0, p_g, params);
 
     let mut v = vec![];
@@ -1,18 +1,86 @@
 //! A module for dealing with anonymous transfer
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use support::{decl_module, decl_storage, decl_event, StorageMap, dispatch::Result, ensure};
+use support::{decl_module, decl_storage, decl_event, StorageValue, StorageMap, dispatch::Result, ensure};
+use support::traits::{Currency, ReservableCurrency};
 use rstd::{
     prelude::*,
     result,
+    convert::TryFrom,
+    borrow::Borrow,
 };
 use runtime_primitives::traits::Zero;
-use zprimitives::{EncKey, Proof, Nonce, RightCiphertext, LeftCiphertext, Ciphertext};
-use system::ensure_signed;
-
-pub trait Trait: system::Trait + zk_system::Trait {
+use zprimitives::{EncKey, Proof, Nonce, RightCiphertext, LeftCiphertext, Ciphertext, CiphertextError};
+use system::{ensure_signed, ensure_root};
+use keys::EncryptionKey;
+use pairing::bls12_381::Bls12;
+use zk_system::VerificationError;
+use zk_system::pool::{Pool, PoolMap};
+use zk_auditor::AuditCiphertext;
+use encrypted_balances::ConfidentialFee;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: system::Trait + zk_system::Trait + zk_stats::Trait + zk_auditor::Trait + encrypted_assets::Trait {
     // The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// The currency in which the deposit of joining the anonymity set is reserved.
+    type Currency: ReservableCurrency<Self::AccountId>;
+}
+
+/// The number of accounts in an anonymous transfer: the sender, the recipient and the decoys.
+/// The anonymous transfer circuit is built for this ring size, and the anonymity set can't shrink below it.
+pub const RING_SIZE: usize = 12;
+
+/// The maximum number of accounts in the anonymity set, which bounds the cost of checking the membership.
+pub const MAX_ENC_KEY_SET_SIZE: usize = 1024;
+
+/// Errors of rolling over and updating encrypted balances.
+/// The codes are stable across runtime upgrades, so front-ends can match on them.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Error {
+    /// A ciphertext made of the given left and right halves is invalid.
+    MalformedCiphertext(CiphertextError),
+    /// Failed to add the matured pending transfers to the balance.
+    BalanceMutation,
+    /// Failed to add the amount to the pending transfer.
+    PendingTransferMutation,
+    /// The numbers of accounts and amounts of an anonymous transfer are not the ring size.
+    InvalidRingSize,
+}
+
+impl Error {
+    pub fn code(&self) -> u8 {
+        match *self {
+            Error::MalformedCiphertext(_) => 1,
+            Error::BalanceMutation => 2,
+            Error::PendingTransferMutation => 3,
+            Error::InvalidRingSize => 4,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Error::MalformedCiphertext(e) => e.as_str(),
+            Error::BalanceMutation => "Faild to mutate encrypted balance.",
+            Error::PendingTransferMutation => "Faild to mutate pending transfer.",
+            Error::InvalidRingSize => "The number of accounts must be the ring size.",
+        }
+    }
+}
+
+impl From<CiphertextError> for Error {
+    fn from(e: CiphertextError) -> Self {
+        Error::MalformedCiphertext(e)
+    }
+}
+
+impl From<Error> for &'static str {
+    fn from(e: Error) -> &'static str {
+        e.as_str()
+    }
 }
 
 decl_module! {
@@ -28,45 +96,58 @@ decl_module! {
             right_ciphertext: RightCiphertext,
             nonce: Nonce
         ) -> Result {
-            ensure!(enc_keys.len() == left_ciphertexts.len(), "length should be equal");
+            Self::check_ring_size(&enc_keys[..], &left_ciphertexts[..])?;
             let rvk = ensure_signed(origin)?;
 
-            // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
-            // No problem if errors occur after this function because
-            // it just rollover user's own `pending trasfer` to `encrypted balances`.
-            for e in &enc_keys {
-                Self::rollover(e)?;
-            }
-
-            // Veridate the provided nonce isn't included in the nonce pool.
-            assert!(!<zk_system::Module<T>>::nonce_pool().contains(&nonce));
+            Self::verify_and_transfer(
+                &rvk,
+                &zkproof,
+                &enc_keys[..],
+                &left_ciphertexts[..],
+                &right_ciphertext,
+                &nonce,
+                &[],
+                None
+            )?;
 
-            let mut acc = vec![];
-            for c in &enc_keys {
-                let tmp = Self::encrypted_balance(c).map_or(Ciphertext::zero(), |e| e);
-                acc.push(tmp);
-            }
+            Self::deposit_event(
+                RawEvent::AnonymousTransfer(
+                    zkproof,
+                    enc_keys,
+                    left_ciphertexts,
+                    right_ciphertext,
+                    rvk
+                )
+            );
 
-            // Verify the zk proof
-            if !<zk_system::Module<T>>::verify_anonymous_proof(
-                    &zkproof,
-                    &enc_keys[..],
-                    &left_ciphertexts[..],
-                    &right_ciphertext,
-                    &acc[..],
-                    &rvk,
-                    &nonce
-                )? {
-                    Self::deposit_event(RawEvent::InvalidZkProof());
-                    return Err("Invalid zkproof");
-            }
+            Ok(())
+        }
 
-            // Add a nonce into the nonce pool
-            <zk_system::Module<T>>::nonce_pool().push(nonce);
+        /// An anonymous transfer whose anonymity set includes accounts with auditors.
+        /// `audits` are the amounts encrypted to the auditors of those accounts, in the order of `enc_keys`,
+        /// so that the auditor of a decoy sees a zero amount.
+        pub fn anonymous_transfer_audited(
+            origin,
+            zkproof: Proof,
+            enc_keys: Vec<EncKey>,
+            left_ciphertexts: Vec<LeftCiphertext>,
+            right_ciphertext: RightCiphertext,
+            nonce: Nonce,
+            audits: Vec<AuditCiphertext>
+        ) -> Result {
+            Self::check_ring_size(&enc_keys[..], &left_ciphertexts[..])?;
+            let rvk = ensure_signed(origin)?;
 
-            for (e, c) in enc_keys.iter().zip(left_ciphertexts.iter()) {
-                Self::add_pending_transfer(e, c, &right_ciphertext)?;
-            }
+            Self::verify_and_transfer(
+                &rvk,
+                &zkproof,
+                &enc_keys[..],
+                &left_ciphertexts[..],
+                &right_ciphertext,
+                &nonce,
+                &audits[..],
+                None
+            )?;
 
             Self::deposit_event(
                 RawEvent::AnonymousTransfer(
@@ -77,10 +158,94 @@ decl_module! {
                     rvk
                 )
             );
+            Self::deposit_event(RawEvent::TransferAudited(audits, right_ciphertext));
+
+            Ok(())
+        }
+
+        /// An anonymous transfer of an asset issued by `encrypted_assets`, which moves the balances
+        /// of the asset held there instead of the balances of this module.
+        /// The accounts are taken from the same anonymity set.
+        pub fn anonymous_asset_transfer(
+            origin,
+            asset_id: T::AssetId,
+            zkproof: Proof,
+            enc_keys: Vec<EncKey>,
+            left_ciphertexts: Vec<LeftCiphertext>,
+            right_ciphertext: RightCiphertext,
+            nonce: Nonce
+        ) -> Result {
+            Self::check_ring_size(&enc_keys[..], &left_ciphertexts[..])?;
+            let rvk = ensure_signed(origin)?;
+
+            Self::verify_and_transfer(
+                &rvk,
+                &zkproof,
+                &enc_keys[..],
+                &left_ciphertexts[..],
+                &right_ciphertext,
+                &nonce,
+                &[],
+                Some(asset_id)
+            )?;
+
+            Self::deposit_event(
+                RawEvent::AnonymousAssetTransfer(
+                    asset_id,
+                    zkproof,
+                    enc_keys,
+                    left_ciphertexts,
+                    right_ciphertext,
+                    rvk
+                )
+            );
 
             Ok(())
         }
 
+        /// Join the anonymity set, so that the account can send and receive anonymous transfers
+        /// and be picked as a decoy. `EncKeyDeposit` is reserved from the signer until the account leaves,
+        /// so that filling the set costs a deposit for each of its accounts.
+        pub fn register_enc_key(origin, enc_key: EncKey) -> Result {
+            let who = ensure_signed(origin)?;
+            EncryptionKey::<Bls12>::try_from(enc_key).map_err(|_| "Invalid encryption key.")?;
+
+            let mut enc_key_set = Self::enc_key_set();
+            ensure!(!enc_key_set.contains(&enc_key), "The account is already in the anonymity set.");
+            ensure!(enc_key_set.len() < MAX_ENC_KEY_SET_SIZE, "The anonymity set is full.");
+
+            let deposit = Self::enc_key_deposit();
+            T::Currency::reserve(&who, deposit)
+                .map_err(|_| "Not enough free balance for the deposit of the anonymity set.")?;
+
+            enc_key_set.push(enc_key);
+            <EncKeySet<T>>::put(enc_key_set);
+            <EncKeyOwner<T>>::insert(enc_key, (who, deposit));
+
+            Self::deposit_event(RawEvent::EncKeyRegistered(enc_key));
+
+            Ok(())
+        }
+
+        /// Leave the anonymity set, returning the deposit to the account which registered the key.
+        pub fn deregister_enc_key(origin, enc_key: EncKey) -> Result {
+            let who = ensure_signed(origin)?;
+            match Self::enc_key_owner(enc_key) {
+                Some((owner, _)) => ensure!(owner == who, "Only the account which registered the key can deregister it."),
+                None => return Err("The key wasn't registered by an account."),
+            }
+
+            Self::remove_enc_key(enc_key)
+        }
+
+        /// Remove an account from the anonymity set, e.g. one of the genesis, returning the deposit if any.
+        /// Only callable by root.
+        pub fn force_deregister_enc_key(origin, enc_key: EncKey) -> Result {
+            ensure_root(origin)?;
+
+            Self::remove_enc_key(enc_key)
+        }
+
         /// Issue a new class of encrypted fungible assets. There are, and will only ever be, `total`
 		/// such assets and they'll all belong to the `issuer` initially. It will have an
 		/// identifier `AssetId` instance: this will be specified in the `Issued` event.
@@ -96,18 +261,22 @@ decl_module! {
         ) {
             let rvk = ensure_signed(origin)?;
 
+            // Reject malformed ciphertexts before any storage mutation.
+            let total_ciphertext = Ciphertext::from_left_right(total, randomness)?;
+            Ciphertext::from_left_right(fee, randomness)?;
+
             // Initialize a nonce pool
             let current_epoch = <zk_system::Module<T>>::get_current_epoch();
             <zk_system::Module<T>>::init_nonce_pool(current_epoch);
 
-            // Veridate the provided nonce isn't included in the nonce pool.
-            ensure!(!<zk_system::Module<T>>::nonce_pool().contains(&nonce), "Provided nonce is already included in the nonce pool.");
+            // Reserve the provided nonce, which fails if it's included in the nonce pool.
+            let ticket = <zk_system::Module<T>>::reserve_nonce(nonce)?;
 
             // Verify a zk proof
             // 1. Spend authority verification
             // 2. Range check of issued amount
             // 3. Encryption integrity
-            if !<zk_system::Module<T>>::verify_confidential_proof(
+            if let Err(e) = <zk_system::Module<T>>::verify_confidential_proof(
                 &zkproof,
                 &issuer,
                 &issuer,
@@ -118,16 +287,17 @@ decl_module! {
                 &fee,
                 &randomness,
                 &nonce
-            )? {
-                Self::deposit_event(RawEvent::InvalidZkProof());
-                return Err("Invalid zkproof");
+            ) {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                if e == VerificationError::InvalidProof {
+                    Self::deposit_event(RawEvent::InvalidZkProof());
+                }
+                return Err(e.into());
             }
 
             // Add a nonce into the nonce pool
-            <zk_system::Module<T>>::nonce_pool().push(nonce);
+            <zk_system::Module<T>>::commit_nonce(ticket);
 
-            let total_ciphertext = Ciphertext::from_left_right(total, randomness)
-                .map_err(|_| "Faild to create ciphertext from left and right.")?;
             <EncryptedBalance<T>>::insert(issuer.clone(), total_ciphertext.clone());
 
             Self::deposit_event(RawEvent::Issued(issuer, total_ciphertext));
@@ -137,65 +307,257 @@ decl_module! {
 
 decl_storage! {
     trait Store for Module<T: Trait> as AnonymousBalances {
-        /// An encrypted balance for each account
-        pub EncryptedBalance get(encrypted_balance) config() : map EncKey => Option<Ciphertext>;
-        /// A pending transfer
-        pub PendingTransfer get(pending_transfer) : map EncKey => Option<Ciphertext>;
+        /// The epochs of the pending transfer buckets of each account in ascending order.
+        pub PendingEpochs get(pending_epochs) : map EncKey => Vec<T::BlockNumber>;
         /// A last epoch for rollover
         pub LastRollOver get(last_rollover) config() : map EncKey => Option<T::BlockNumber>;
         // TODO: Change to BTreeSet once parity-codec is updated to parity-scale-codec
         pub EncKeySet get(enc_key_set) config() : Vec<EncKey>;
+        /// The deposit reserved from the account joining the anonymity set.
+        pub EncKeyDeposit get(enc_key_deposit) config() : BalanceOf<T>;
+        /// The account which registered the key in the anonymity set, and the deposit reserved from it.
+        /// The accounts of the genesis have none.
+        pub EncKeyOwner get(enc_key_owner) : map EncKey => Option<(T::AccountId, BalanceOf<T>)>;
     }
+    add_extra_genesis {
+        config(encrypted_balance): Vec<(EncKey, Ciphertext)>;
+
+        build(|_: &mut runtime_primitives::StorageOverlay, children: &mut runtime_primitives::ChildrenStorageOverlay, config: &GenesisConfig<T>| {
+            <EncryptedBalance<T>>::build_genesis(children, &config.encrypted_balance);
+        });
+    }
+}
+
+/// An encrypted balance for each account, in the child trie of the pool.
+pub struct EncryptedBalance<T>(rstd::marker::PhantomData<T>);
+
+impl<T: Trait> PoolMap for EncryptedBalance<T> {
+    type Key = EncKey;
+    type Value = Ciphertext;
+    const POOL: Pool = Pool::AnonymousBalances;
+    const PREFIX: &'static [u8] = b"EncryptedBalance";
+}
+
+/// Pending transfers bucketed by the epoch in which they were received, in the child trie of the pool.
+/// They are rolled over into the balance at the first rollover after the epoch.
+pub struct PendingTransfer<T>(rstd::marker::PhantomData<T>);
+
+impl<T: Trait> PoolMap for PendingTransfer<T> {
+    type Key = (EncKey, T::BlockNumber);
+    type Value = Ciphertext;
+    const POOL: Pool = Pool::AnonymousBalances;
+    const PREFIX: &'static [u8] = b"PendingTransfer";
 }
 
 decl_event! (
     /// An event in this module.
-    pub enum Event<T> where <T as system::Trait>::AccountId {
+    pub enum Event<T> where <T as system::Trait>::AccountId, <T as encrypted_assets::Trait>::AssetId {
         AnonymousTransfer(Proof, Vec<EncKey>, Vec<LeftCiphertext>, RightCiphertext, AccountId),
+        /// An anonymous transfer of an asset of `encrypted_assets`.
+        AnonymousAssetTransfer(AssetId, Proof, Vec<EncKey>, Vec<LeftCiphertext>, RightCiphertext, AccountId),
         Issued(EncKey, Ciphertext),
         InvalidZkProof(),
+        /// A transfer was rejected since its nonce is already used in the current epoch.
+        NonceReplayed(Nonce),
+        /// The amounts of an anonymous transfer encrypted to the auditors, with the right component of the transfer.
+        TransferAudited(Vec<AuditCiphertext>, RightCiphertext),
+        /// The account joined the anonymity set.
+        EncKeyRegistered(EncKey),
+        /// The account left or was removed from the anonymity set.
+        EncKeyDeregistered(EncKey),
     }
 );
 
 impl<T: Trait> Module<T> {
     // PUBLIC MUTABLES
 
+    /// Verify a zk proof of an anonymous transfer and apply it to the balances.
+    /// `audits` must cover every account in `enc_keys` which has an auditor.
+    /// The balances of `asset_id` in `encrypted_assets` are used if it's given, otherwise the ones of this module.
+    fn verify_and_transfer(
+        rvk: &T::AccountId,
+        zkproof: &Proof,
+        enc_keys: &[EncKey],
+        left_ciphertexts: &[LeftCiphertext],
+        right_ciphertext: &RightCiphertext,
+        nonce: &Nonce,
+        audits: &[AuditCiphertext],
+        asset_id: Option<T::AssetId>
+    ) -> Result {
+        let parties = enc_keys.iter().cloned().zip(left_ciphertexts.iter().cloned()).collect::<Vec<_>>();
+        <zk_auditor::Module<T>>::check_audits(&parties[..], right_ciphertext, audits)?;
+
+        let ticket = match <zk_system::Module<T>>::reserve_nonce(*nonce) {
+            Ok(ticket) => ticket,
+            Err(e) => {
+                Self::deposit_event(RawEvent::NonceReplayed(*nonce));
+                return Err(e.into());
+            }
+        };
+
+        if let Err(e) = Self::check_anonymous_proof(
+                rvk,
+                zkproof,
+                enc_keys,
+                left_ciphertexts,
+                right_ciphertext,
+                nonce,
+                asset_id
+            ) {
+            <zk_system::Module<T>>::abort_nonce(ticket);
+            match e {
+                VerificationError::NonceUsed => Self::deposit_event(RawEvent::NonceReplayed(*nonce)),
+                VerificationError::InvalidProof => Self::deposit_event(RawEvent::InvalidZkProof()),
+                _ => {}
+            }
+            return Err(e.into());
+        }
+
+        let fee = <T as encrypted_balances::Trait>::ConfidentialFee::anonymous_fee();
+
+        // Every pending transfer is staged before anything is written, so a failure can't leave
+        // the nonce used with only some of the accounts credited.
+        let staged = match Self::stage_pending_transfers(enc_keys, left_ciphertexts, right_ciphertext, asset_id) {
+            Ok(staged) => staged,
+            Err(e) => {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                return Err(e.into());
+            }
+        };
+
+        // Add a nonce into the nonce pool
+        <zk_system::Module<T>>::commit_nonce(ticket);
+
+        Self::commit_pending_transfers(staged, asset_id);
+
+        <zk_stats::Module<T>>::note_anonymous_transfer();
+        <zk_stats::Module<T>>::note_fee_charged(fee);
+
+        Ok(())
+    }
+
+    /// Check an anonymous transfer against the current state of the chain: the ring size, the ciphertexts,
+    /// the anonymity set, the nonce pool, and the proof against the g_epoch, the verifying key
+    /// and the balances of the accounts. The accounts are rolled over first, as the transfer would do.
+    /// This is exposed as a dry-run through the runtime API, whose storage mutations are discarded.
+    pub fn check_anonymous_proof(
+        rvk: &T::AccountId,
+        zkproof: &Proof,
+        enc_keys: &[EncKey],
+        left_ciphertexts: &[LeftCiphertext],
+        right_ciphertext: &RightCiphertext,
+        nonce: &Nonce,
+        asset_id: Option<T::AssetId>
+    ) -> result::Result<(), VerificationError> {
+        Self::check_ring_size(enc_keys, left_ciphertexts)
+            .map_err(|e| VerificationError::MalformedInput(e.as_str()))?;
+
+        // Reject malformed ciphertexts before any storage mutation.
+        for c in left_ciphertexts {
+            Ciphertext::from_left_right(*c, *right_ciphertext)
+                .map_err(|e| VerificationError::MalformedInput(e.as_str()))?;
+        }
+
+        let enc_key_set = Self::enc_key_set();
+        if !enc_keys.iter().all(|e| enc_key_set.contains(e)) {
+            return Err(VerificationError::MalformedInput("Every account must be in the anonymity set."));
+        }
+
+        // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
+        // No problem if errors occur after this function because
+        // it just rollover user's own `pending trasfer` to `encrypted balances`.
+        for e in enc_keys {
+            match asset_id {
+                Some(id) => <encrypted_assets::Module<T>>::rollover(e, id).map_err(|e| e.as_str()),
+                None => Self::rollover(e).map_err(|e| e.as_str()),
+            }?;
+        }
+
+        // Validate the provided nonce isn't included in the nonce pool.
+        if <zk_system::Module<T>>::is_nonce_used(nonce) {
+            return Err(VerificationError::NonceUsed);
+        }
+
+        let mut acc = vec![];
+        for c in enc_keys {
+            let tmp = match asset_id {
+                Some(id) => <encrypted_assets::Module<T>>::encrypted_balance((id, *c)),
+                None => Self::encrypted_balance(c),
+            };
+            acc.push(tmp.map_or(Ciphertext::zero(), |e| e));
+        }
+
+        // Every anonymous transfer pays the same fee, which is a public input of the proof,
+        // so the sender's ciphertext can't be told apart from the others' by its fee.
+        let fee = <T as encrypted_balances::Trait>::ConfidentialFee::anonymous_fee();
+
+        <zk_system::Module<T>>::verify_anonymous_proof(
+            zkproof,
+            enc_keys,
+            left_ciphertexts,
+            right_ciphertext,
+            &acc[..],
+            fee,
+            rvk,
+            nonce
+        )
+    }
+
+    /// The circuit takes exactly `RING_SIZE` accounts and amounts.
+    /// This is checked before any rollover, so a transfer of another size doesn't mutate the storage.
+    pub fn check_ring_size(enc_keys: &[EncKey], left_ciphertexts: &[LeftCiphertext]) -> result::Result<(), Error> {
+        if enc_keys.len() != RING_SIZE || left_ciphertexts.len() != RING_SIZE {
+            return Err(Error::InvalidRingSize);
+        }
+
+        Ok(())
+    }
+
     /// Rolling over allows us to send transactions asynchronously and protect from front-running attacks.
     /// We rollover an account in an epoch when the first message from this account is received;
     /// so, one message rolls over only one account.
     /// To achieve this, we define a separate (internal) method for rolling over,
     /// and the first thing every other method does is to call this method.
     /// More details in Section 3.1: https://crypto.stanford.edu/~buenz/papers/zether.pdf
-    pub fn rollover(addr: &EncKey) -> result::Result<(), &'static str> {
+    pub fn rollover(addr: &EncKey) -> result::Result<(), Error> {
         let current_epoch = <zk_system::Module<T>>::get_current_epoch();
 
         let last_rollover = Self::last_rollover(addr)
             .map_or(T::BlockNumber::zero(), |e| e);
 
-        // Get balance with the type
-        let enc_pending_transfer = Self::pending_transfer(addr)
-            .map_or(Ciphertext::zero(), |e| e);
+        // Buckets received in past epochs have matured; the bucket of the current epoch stays pending.
+        let pending_epochs = Self::pending_epochs(addr);
+        let matured = pending_epochs.iter().take_while(|e| **e < current_epoch).count();
 
-        // Checks if the last roll over was in an older epoch.
+        // Checks if the last roll over was in an older epoch or some pending transfers have matured.
         // If so, some storage changes are happend here.
-        if last_rollover < current_epoch {
-            // transfer balance from pending_transfer to actual balance
-            <EncryptedBalance<T>>::mutate(addr, |balance| {
-                let new_balance = match balance.clone() {
-                    Some(b) => b.add(&enc_pending_transfer),
-                    None => Ok(enc_pending_transfer),
-                };
-
-                match new_balance {
-                    Ok(nb) => *balance = Some(nb),
-                    Err(_) => return Err("Faild to mutate encrypted balance."),
+        if last_rollover < current_epoch || matured > 0 {
+            let mut enc_pending_transfer = Ciphertext::zero();
+            for epoch in &pending_epochs[..matured] {
+                if let Some(p) = Self::pending_transfer((*addr, *epoch)) {
+                    enc_pending_transfer = enc_pending_transfer.add(&p)
+                        .map_err(|_| Error::BalanceMutation)?;
                 }
+            }
 
-                Ok(())
-            })?;
+            // transfer balance from the matured pending transfers to actual balance
+            let new_balance = match Self::encrypted_balance(addr) {
+                Some(b) => b.add(&enc_pending_transfer),
+                None => Ok(enc_pending_transfer),
+            }
+            .map_err(|_| Error::BalanceMutation)?;
+            <EncryptedBalance<T>>::insert(addr, new_balance);
+
+            // Prune the matured buckets.
+            for epoch in &pending_epochs[..matured] {
+                <PendingTransfer<T>>::remove((*addr, *epoch));
+            }
+            if matured == pending_epochs.len() {
+                <PendingEpochs<T>>::remove(addr);
+            } else {
+                <PendingEpochs<T>>::insert(addr, pending_epochs[matured..].to_vec());
+            }
 
-            // Reset pending_transfer.
-            <PendingTransfer<T>>::remove(addr);
             // Set last rollover to current epoch.
             <LastRollOver<T>>::insert(addr, current_epoch);
         }
@@ -205,30 +567,128 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
-     /// Adding transferred amount to pending transfer.
+    /// Get the accounts which rolled over within the last `k_epochs` epochs, as decoy candidates.
+    /// A decoy which hasn't been active for a long time is unlikely to be a real sender or recipient,
+    /// so an observer can rule it out of the anonymity set; sampling decoys only from recently
+    /// active accounts keeps them indistinguishable from the real parties by their activity.
+    pub fn active_enc_keys(k_epochs: T::BlockNumber) -> Vec<EncKey> {
+        let current_epoch = <zk_system::Module<T>>::get_current_epoch();
+        let oldest = if current_epoch > k_epochs {
+            current_epoch - k_epochs
+        } else {
+            T::BlockNumber::zero()
+        };
+
+        Self::enc_key_set()
+            .into_iter()
+            .filter(|e| Self::last_rollover(e).map_or(false, |epoch| epoch >= oldest))
+            .collect()
+    }
+
+    /// Adding transferred amount to the pending transfer bucket of the current epoch.
     pub fn add_pending_transfer(
         address: &EncKey,
         amount: &LeftCiphertext,
         randomness: &RightCiphertext
-    ) -> result::Result<(), &'static str> {
-        let enc_amount = Ciphertext::from_left_right(*amount, *randomness)
-            .map_err(|_| "Faild to create amount ciphertext.")?;
-
-        <PendingTransfer<T>>::mutate(address, |pending_transfer| {
-            let new_pending_transfer = match pending_transfer.clone() {
-                Some(p) => p.add(&enc_amount),
-                None => Ok(enc_amount),
+    ) -> result::Result<(), Error> {
+        let staged = Self::stage_pending_transfers(&[*address], &[*amount], randomness, None)?;
+        Self::commit_pending_transfers(staged, None);
+
+        Ok(())
+    }
+
+    // Remove the key from the anonymity set and return the deposit of its owner.
+    fn remove_enc_key(enc_key: EncKey) -> Result {
+        let mut enc_key_set = Self::enc_key_set();
+        let index = enc_key_set.iter().position(|e| *e == enc_key)
+            .ok_or("The account is not in the anonymity set.")?;
+        ensure!(enc_key_set.len() > RING_SIZE, "The anonymity set would be smaller than an anonymous transfer.");
+
+        enc_key_set.remove(index);
+        <EncKeySet<T>>::put(enc_key_set);
+        if let Some((owner, deposit)) = <EncKeyOwner<T>>::take(enc_key) {
+            T::Currency::unreserve(&owner, deposit);
+        }
+
+        Self::deposit_event(RawEvent::EncKeyDeregistered(enc_key));
+
+        Ok(())
+    }
+
+    // Stage adding the amounts of a transfer to the pending transfers of the current epoch to the accounts,
+    // on top of the amount already staged for an account which appears more than once.
+    // The pending transfers of `asset_id` in `encrypted_assets` are used if it's given.
+    fn stage_pending_transfers(
+        enc_keys: &[EncKey],
+        left_ciphertexts: &[LeftCiphertext],
+        right_ciphertext: &RightCiphertext,
+        asset_id: Option<T::AssetId>
+    ) -> result::Result<Vec<(EncKey, Ciphertext)>, Error> {
+        let current_epoch = <zk_system::Module<T>>::get_current_epoch();
+        let mut staged: Vec<(EncKey, Ciphertext)> = vec![];
+
+        for (address, amount) in enc_keys.iter().zip(left_ciphertexts.iter()) {
+            let enc_amount = Ciphertext::from_left_right(*amount, *right_ciphertext)?;
+            let index = staged.iter().position(|(a, _)| a == address);
+            let pending_transfer = match (index, asset_id) {
+                (Some(i), _) => Some(staged[i].1.clone()),
+                (None, Some(id)) => <encrypted_assets::Module<T>>::pending_transfer((id, *address, current_epoch)),
+                (None, None) => Self::pending_transfer((*address, current_epoch)),
             };
 
-            match new_pending_transfer {
-                Ok(np) => *pending_transfer = Some(np),
-                Err(_) => return Err("Faild to mutate pending transfer.")
+            let new_pending_transfer = match pending_transfer {
+                Some(p) => p.add(&enc_amount).map_err(|_| Error::PendingTransferMutation)?,
+                None => enc_amount,
+            };
+            match index {
+                Some(i) => staged[i].1 = new_pending_transfer,
+                None => staged.push((*address, new_pending_transfer)),
             }
+        }
 
-            Ok(())
-        })?;
+        Ok(staged)
+    }
 
-        Ok(())
+    // Write the staged pending transfers. Nothing here can fail.
+    fn commit_pending_transfers(staged: Vec<(EncKey, Ciphertext)>, asset_id: Option<T::AssetId>) {
+        let current_epoch = <zk_system::Module<T>>::get_current_epoch();
+
+        for (address, pending_transfer) in staged {
+            match asset_id {
+                Some(id) => <encrypted_assets::Module<T>>::set_pending_transfer(&address, id, pending_transfer),
+                None => {
+                    <PendingTransfer<T>>::insert((address, current_epoch), pending_transfer);
+                    <PendingEpochs<T>>::mutate(address, |epochs| {
+                        if epochs.last() != Some(&current_epoch) {
+                            epochs.push(current_epoch);
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    // PUBLIC IMMUTABLES
+
+    /// The encrypted balance of the account.
+    pub fn encrypted_balance<K: Borrow<EncKey>>(addr: K) -> Option<Ciphertext> {
+        <EncryptedBalance<T>>::get(addr)
+    }
+
+    /// The pending transfers to the account received in the epoch.
+    pub fn pending_transfer<K: Borrow<(EncKey, T::BlockNumber)>>(key: K) -> Option<Ciphertext> {
+        <PendingTransfer<T>>::get(key)
+    }
+
+    /// The sum of all the pending transfer buckets of the account, matured or not.
+    pub fn total_pending_transfer(address: &EncKey) -> Option<Ciphertext> {
+        Self::pending_epochs(address)
+            .iter()
+            .filter_map(|epoch| Self::pending_transfer((*address, *epoch)))
+            .fold(None, |acc, p| match acc {
+                Some(a) => a.add(&p).ok(),
+                None => Some(p),
+            })
     }
 }
 
@@ -241,7 +701,7 @@ extern crate lazy_static;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rand::{SeedableRng, XorShiftRng, Rng};
+    use rand::{SeedableRng, XorShiftRng};
     use runtime_io::with_externalities;
     use support::{impl_outer_origin, assert_ok};
     use primitives::{H256, Blake2Hasher};
@@ -249,36 +709,28 @@ mod tests {
         BuildStorage, traits::{BlakeTwo256, IdentityLookup},
         testing::{Digest, DigestItem, Header}
     };
-    use zprimitives::{Ciphertext, SigVerificationKey, PARAMS as ZPARAMS};
+    use zprimitives::{Ciphertext, SigVerificationKey};
     use keys::EncryptionKey;
-    use jubjub::{curve::{JubjubBls12, FixedGenerators, fs}};
-    use pairing::{Field, bls12_381::Bls12};
-    use zcrypto::elgamal;
+    use jubjub::curve::JubjubBls12;
+    use pairing::bls12_381::Bls12;
     use hex_literal::{hex, hex_impl};
-    use bellman_verifier::PreparedVerifyingKey;
     use test_proofs::{EncryptionKey as tEncryptionKey, SpendingKey as tSpendingKey,
             elgamal as telgamal, PARAMS, MultiEncKeys, KeyContext, ProofBuilder,
             crypto_components::Anonymous,
         };
     use test_pairing::{bls12_381::Bls12 as tBls12, Field as tField};
     use scrypto::jubjub::edwards as tedwards;
-    use std::{
-        path::Path,
-        fs::File,
-        io::{BufReader, Read},
-        convert::TryFrom,
+    use test_fixtures::{
+        ALICE_SEED, BOB_SEED, BOB_ADDRESS, ALICE_BALANCE, ANONY_PK, ANONY_VK,
+        params_path, conf_vk, anony_vk, enc_key, alice_genesis, anonymity_set, anonymity_set_genesis,
     };
-
-    const ALICE_BALANCE: u32 = 100;
+    use std::convert::TryFrom;
 
     lazy_static! {
-        pub static ref ANONY_BALANCES: Vec<(EncKey, Ciphertext)> = { init_anonymous_balances(ALICE_BALANCE) };
-        pub static ref ENC_KEYS: Vec<EncryptionKey<Bls12>> = { init_typed_enc_keys() };
+        pub static ref ANONY_BALANCES: Vec<(EncKey, Ciphertext)> = { anonymity_set_genesis(ALICE_BALANCE) };
+        pub static ref ENC_KEYS: Vec<EncryptionKey<Bls12>> = { anonymity_set() };
     }
 
-    const PK_PATH: &str = "../../zface/params/test_anony_pk.dat";
-    const VK_PATH: &str = "../../zface/params/test_anony_vk.dat";
-
     impl_outer_origin! {
         pub enum Origin for Test {}
     }
@@ -306,53 +758,50 @@ mod tests {
 
     impl Trait for Test {
         type Event = ();
+        type Currency = balances::Module<Test>;
     }
-    impl zk_system::Trait for Test { }
-    type AnonymousBalances = Module<Test>;
 
-    fn alice_epoch_init() -> (EncKey, u64) {
-        let (_, enc_key) = get_alice_seed_ek();
+    impl balances::Trait for Test {
+        type Balance = u64;
+        type OnFreeBalanceZero = ();
+        type OnNewAccount = ();
+        type Event = ();
+        type TransactionPayment = ();
+        type DustRemoval = ();
+        type TransferPayment = ();
+    }
 
-        (EncKey::try_from(enc_key).unwrap(), 0)
+    impl zk_system::Trait for Test {
+        type Event = ();
+        type Log = DigestItem;
     }
 
-    fn get_alice_seed_ek() -> (Vec<u8>, EncryptionKey<Bls12>) {
-        let params = &JubjubBls12::new();
-        let alice_seed = b"Alice                           ".to_vec();
+    impl zk_stats::Trait for Test { }
 
-        (alice_seed.clone(), EncryptionKey::<Bls12>::from_seed(&alice_seed[..], params)
-            .expect("should be generated encryption key from seed."))
+    impl zk_auditor::Trait for Test {
+        type Event = ();
     }
 
-    fn get_alice_enc_key() -> EncryptionKey<Bls12> {
-        let params = &JubjubBls12::new();
-        let alice_seed = b"Alice                           ".to_vec();
-        let enc_key = EncryptionKey::<Bls12>::from_seed(&&alice_seed, params)
-            .expect("should be generated encryption key from seed.");
-        enc_key
+    impl encrypted_balances::Trait for Test {
+        type Event = ();
+        type ConfidentialFee = encrypted_balances::FlatFee<Test>;
     }
 
-    fn get_bob_enc_key() -> EncryptionKey<Bls12> {
-        let bob_addr: [u8; 32] = hex!("45e66da531088b55dcb3b273ca825454d79d2d1d5c4fa2ba4a12c1fa1ccd6389");
-        let enc_key_recipient = EncryptionKey::<Bls12>::read(&mut &bob_addr[..], &ZPARAMS).unwrap();
-        enc_key_recipient
+    impl encrypted_assets::Trait for Test {
+        type Event = ();
+        type AssetId = u64;
     }
 
-    fn init_typed_enc_keys() -> Vec<EncryptionKey<Bls12>> {
-        let params = &JubjubBls12::new();
-        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+    type AnonymousBalances = Module<Test>;
+    type Balances = balances::Module<Test>;
+    type EncryptedAssets = encrypted_assets::Module<Test>;
 
-        let mut acc = vec![];
-        for _ in 0..10 {
-            let random_seed: [u8; 32] = rng.gen();
-            let enc_key = EncryptionKey::<Bls12>::from_seed(&random_seed, params)
-                .expect("should be generated encryption key from seed.");
-            acc.push(enc_key);
-        }
-        acc.insert(0, get_alice_enc_key());
-        acc.insert(1, get_bob_enc_key());
+    const ASSET_ID: u64 = 0;
+    const ENC_KEY_DEPOSIT: u64 = 40;
 
-        acc
+    // The account paying the deposits of the anonymity set.
+    fn depositor() -> SigVerificationKey {
+        SigVerificationKey::from_slice(&[7u8; 32][..])
     }
 
     fn init_anonymous_enc_keys() -> Vec<EncKey> {
@@ -367,64 +816,47 @@ mod tests {
         }).collect()
     }
 
-    fn init_anonymous_balances(alice_value: u32) -> Vec<(EncKey, Ciphertext)> {
-        let params = &JubjubBls12::new();
-        let p_g = FixedGenerators::Diversifier; // 1 same as NoteCommitmentRandomness;
-
-        let mut acc = vec![];
-        for (i, e) in ENC_KEYS.iter().enumerate() {
-            if i == 0 {
-                let ciphertext = elgamal::Ciphertext::encrypt(alice_value, &fs::Fs::one(), &e, p_g, params);
-                acc.push((EncKey::try_from(e.clone()).unwrap(), Ciphertext::try_from(ciphertext).unwrap()))
-            } else {
-                let ciphertext = elgamal::Ciphertext::encrypt(0, &fs::Fs::one(), &e, p_g, params);
-                // let ciphertext = Ciphertext::zero();
-                acc.push((EncKey::try_from(e.clone()).unwrap(), Ciphertext::try_from(ciphertext).unwrap()))
-            }
-        }
-        acc
-    }
-
-    pub fn get_conf_vk() -> PreparedVerifyingKey<Bls12> {
-        let vk_path = Path::new("../../zface/params/test_conf_vk.dat");
-        let vk_file = File::open(&vk_path).unwrap();
-        let mut vk_reader = BufReader::new(vk_file);
-
-        let mut buf_vk = vec![];
-        vk_reader.read_to_end(&mut buf_vk).unwrap();
-
-        PreparedVerifyingKey::<Bls12>::read(&mut &buf_vk[..]).unwrap()
-    }
-
-    pub fn get_anony_vk() -> PreparedVerifyingKey<Bls12> {
-        let vk_path = Path::new("../../zface/params/test_anony_vk.dat");
-        let vk_file = File::open(&vk_path).unwrap();
-        let mut vk_reader = BufReader::new(vk_file);
-
-        let mut buf_vk = vec![];
-        vk_reader.read_to_end(&mut buf_vk).unwrap();
-
-        PreparedVerifyingKey::<Bls12>::read(&mut &buf_vk[..]).unwrap()
-    }
-
     fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let (_, (alice, last_rollover)) = alice_genesis();
+
         let (mut t, mut c) = system::GenesisConfig::<Test>::default().build_storage().unwrap();
         let _ = zk_system::GenesisConfig::<Test>{
             last_epoch: 1,
             epoch_length: 1,
-            confidential_vk: get_conf_vk(),
-            anonymous_vk: get_anony_vk(),
-            nonce_pool: vec![],
+            max_anonymity_set_size: 12,
+            nonce_retention_epochs: 0,
+            confidential_vk: conf_vk(),
+            anonymous_vk: anony_vk(),
+            confidential_pk_hash: [0u8; 32],
+            anonymous_pk_hash: [0u8; 32],
         }.assimilate_storage(&mut t, &mut c);
 
         let _ = GenesisConfig::<Test>{
             encrypted_balance: ANONY_BALANCES.to_vec(),
-			last_rollover: vec![alice_epoch_init()],
+			last_rollover: vec![(alice, last_rollover)],
 			enc_key_set: init_anonymous_enc_keys(),
+            enc_key_deposit: ENC_KEY_DEPOSIT,
+            _genesis_phantom_data: Default::default()
+        }.assimilate_storage(&mut t, &mut c);
+
+        let _ = balances::GenesisConfig::<Test>{
+            balances: vec![(depositor(), 100)],
+            vesting: vec![],
+            existential_deposit: 0,
+            transfer_fee: 0,
+            creation_fee: 0,
+            transaction_base_fee: 0,
+            transaction_byte_fee: 0,
+        }.assimilate_storage(&mut t, &mut c);
+
+        // The same balances of an asset, for the anonymous asset transfers.
+        let _ = encrypted_assets::GenesisConfig::<Test>{
+            encrypted_balance: ANONY_BALANCES.iter().map(|(e, c)| ((ASSET_ID, *e), c.clone())).collect(),
+            last_rollover: vec![((ASSET_ID, alice), last_rollover)],
             _genesis_phantom_data: Default::default()
         }.assimilate_storage(&mut t, &mut c);
 
-        t.into()
+        zk_system::pool::test_externalities(t, c)
     }
 
     fn no_std_e(enc_key: &EncryptionKey<Bls12>) -> tEncryptionKey<tBls12> {
@@ -434,14 +866,213 @@ mod tests {
         key
     }
 
+    #[test]
+    fn test_epoch_lifecycle() {
+        use zprimitives::GEpoch;
+
+        with_externalities(&mut new_test_ext(), || {
+            let alice_seed = ALICE_SEED.to_vec();
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let spending_key = tSpendingKey::<tBls12>::from_seed(&alice_seed);
+            let bob_addr = BOB_ADDRESS;
+            let enc_key_recipient = tEncryptionKey::<tBls12>::read(&mut &bob_addr[..], &PARAMS).unwrap();
+            let key_context = KeyContext::<tBls12, Anonymous>::read_from_path(params_path(ANONY_PK), params_path(ANONY_VK)).unwrap();
+            let bob = EncKey::from_slice(&bob_addr[..]);
+
+            // Prove a transfer of 10 from alice to bob against the on-chain balances in the given epoch.
+            let mut prove = |remaining_balance: u64, epoch: u32| {
+                let enc_balances: Vec<_> = init_anonymous_enc_keys().iter().map(|e| {
+                    let balance = AnonymousBalances::encrypted_balance(e).unwrap();
+                    telgamal::Ciphertext::read(&mut balance.as_bytes(), &*PARAMS).unwrap()
+                }).collect();
+                let g_epoch = GEpoch::group_hash(epoch).unwrap();
+                let g_epoch = tedwards::Point::read(g_epoch.as_bytes(), &*PARAMS).unwrap().as_prime_order(&*PARAMS).unwrap();
+                let decoys = ENC_KEYS.iter().skip(2).map(|e| no_std_e(e)).collect();
+
+                let tx = key_context.gen_proof(
+                    10,
+                    0,
+                    remaining_balance,
+                    0,
+                    1,
+                    &spending_key,
+                    MultiEncKeys::<tBls12, Anonymous>::new(enc_key_recipient.clone(), decoys),
+                    &enc_balances,
+                    g_epoch,
+                    rng,
+                    &*PARAMS
+                ).unwrap();
+
+                let args = (
+                    Proof::from_slice(&tx.proof[..]),
+                    tx.enc_keys.iter().map(|e| EncKey::from_slice(e)).collect::<Vec<_>>(),
+                    tx.left_ciphertexts.iter().map(|e| LeftCiphertext::from_slice(e)).collect::<Vec<_>>(),
+                    RightCiphertext::from_slice(&tx.right_ciphertext[..]),
+                    Nonce::from_slice(&tx.nonce[..])
+                );
+
+                (SigVerificationKey::from_slice(&tx.rvk[..]), args)
+            };
+
+            type Args = (Proof, Vec<EncKey>, Vec<LeftCiphertext>, RightCiphertext, Nonce);
+            fn submit((rvk, args): &(SigVerificationKey, Args)) -> Result {
+                AnonymousBalances::anonymous_transfer(
+                    Origin::signed(*rvk),
+                    args.0.clone(), args.1.clone(), args.2.clone(), args.3, args.4
+                )
+            }
+
+            // Epoch 0: every account of the anonymity set gets a pending transfer.
+            let tx_0 = prove(ALICE_BALANCE - 10, 0);
+            assert_ok!(submit(&tx_0));
+            assert!(AnonymousBalances::total_pending_transfer(&bob).is_some());
+
+            // Replaying the transfer in the same epoch fails the extrinsic without a panic.
+            let pending = AnonymousBalances::total_pending_transfer(&bob);
+            assert_eq!(submit(&tx_0), Err("Provided nonce is already included in the nonce pool."));
+            assert_eq!(AnonymousBalances::total_pending_transfer(&bob), pending);
+
+            // Epoch 1: the nonce pool and g_epoch are reset and the pending transfers are rolled over
+            // by the first call touching the accounts, even if the call fails.
+            system::Module::<Test>::set_block_number(1);
+            assert_eq!(submit(&tx_0), Err("Invalid zkproof"));
+            assert_eq!(<zk_system::Module<Test>>::last_epoch(), 1);
+            assert_eq!(<zk_system::Module<Test>>::g_epoch(), GEpoch::group_hash(1).unwrap());
+            assert!(!<zk_system::Module<Test>>::is_nonce_used(&(tx_0.1).4));
+            assert!(!<zk_system::Module<Test>>::is_nonce_reserved(&(tx_0.1).4));
+            assert_eq!(AnonymousBalances::total_pending_transfer(&bob), None);
+            assert_eq!(AnonymousBalances::last_rollover(bob), Some(1));
+
+            // A fresh proof against the new g_epoch and balances succeeds.
+            let tx_1 = prove(ALICE_BALANCE - 20, 1);
+            assert_ne!((tx_1.1).4, (tx_0.1).4);
+            assert_ok!(submit(&tx_1));
+            assert!(AnonymousBalances::total_pending_transfer(&bob).is_some());
+        })
+    }
+
+    #[test]
+    fn test_active_enc_keys() {
+        with_externalities(&mut new_test_ext(), || {
+            let alice = EncKey::try_from(enc_key(ALICE_SEED)).unwrap();
+            let bob = EncKey::try_from(enc_key(BOB_SEED)).unwrap();
+
+            system::Module::<Test>::set_block_number(5);
+            assert_eq!(AnonymousBalances::active_enc_keys(5), vec![alice]);
+            assert!(AnonymousBalances::active_enc_keys(4).is_empty());
+
+            assert_ok!(AnonymousBalances::rollover(&bob));
+            assert_eq!(AnonymousBalances::active_enc_keys(0), vec![bob]);
+            assert_eq!(AnonymousBalances::active_enc_keys(5), vec![alice, bob]);
+        })
+    }
+
+    #[test]
+    fn test_anonymous_transfer_rejects_invalid_ring_size() {
+        with_externalities(&mut new_test_ext(), || {
+            let enc_keys = init_anonymous_enc_keys();
+            let transfer = |enc_keys: Vec<EncKey>, left_ciphertexts: Vec<LeftCiphertext>| {
+                AnonymousBalances::anonymous_transfer(
+                    Origin::signed(SigVerificationKey::default()),
+                    Proof::from_slice(&[0u8; 192][..]),
+                    enc_keys,
+                    left_ciphertexts,
+                    RightCiphertext::default(),
+                    Nonce::default()
+                )
+            };
+
+            assert_eq!(
+                transfer(enc_keys[..3].to_vec(), vec![LeftCiphertext::default(); 3]),
+                Err(Error::InvalidRingSize.as_str())
+            );
+            assert_eq!(
+                transfer(enc_keys.clone(), vec![LeftCiphertext::default(); RING_SIZE - 1]),
+                Err(Error::InvalidRingSize.as_str())
+            );
+            // Nothing is rolled over.
+            assert!(enc_keys.iter().skip(1).all(|e| AnonymousBalances::last_rollover(e).is_none()));
+        })
+    }
+
+    #[test]
+    fn test_pending_transfers_are_staged() {
+        with_externalities(&mut new_test_ext(), || {
+            let alice = EncKey::try_from(enc_key(ALICE_SEED)).unwrap();
+            let bob = EncKey::try_from(enc_key(BOB_SEED)).unwrap();
+            let balance = AnonymousBalances::encrypted_balance(alice).unwrap();
+            let (left, right) = (balance.left().unwrap(), balance.right().unwrap());
+
+            // A malformed ciphertext fails the staging, and nothing is written.
+            let mut identity = [0u8; 32];
+            identity[0] = 1;
+            let res = AnonymousBalances::stage_pending_transfers(&[alice, bob], &[left, left], &RightCiphertext::from_slice(&identity), None);
+            assert_eq!(res, Err(Error::MalformedCiphertext(CiphertextError::IdentityRight)));
+            assert_eq!(AnonymousBalances::total_pending_transfer(&alice), None);
+            assert_eq!(AnonymousBalances::total_pending_transfer(&bob), None);
+
+            // An account appearing twice is credited with both amounts.
+            let staged = AnonymousBalances::stage_pending_transfers(&[alice, bob, alice], &[left, left, left], &right, None).unwrap();
+            assert_eq!(staged.len(), 2);
+            assert_eq!(staged[0], (alice, balance.add(&balance).unwrap()));
+            assert_eq!(AnonymousBalances::total_pending_transfer(&alice), None);
+
+            AnonymousBalances::commit_pending_transfers(staged, None);
+            assert_eq!(AnonymousBalances::total_pending_transfer(&alice), Some(balance.add(&balance).unwrap()));
+            assert_eq!(AnonymousBalances::total_pending_transfer(&bob), Some(balance));
+        })
+    }
+
+    #[test]
+    fn test_register_and_deregister_enc_key() {
+        with_externalities(&mut new_test_ext(), || {
+            let params = &JubjubBls12::new();
+            let charlie = EncryptionKey::<Bls12>::from_seed(b"Charlie                         ", params).unwrap();
+            let charlie = EncKey::try_from(charlie).unwrap();
+            let alice = EncKey::try_from(enc_key(ALICE_SEED)).unwrap();
+            let other = SigVerificationKey::from_slice(&[8u8; 32][..]);
+
+            // The set can't be smaller than an anonymous transfer.
+            assert_eq!(
+                AnonymousBalances::force_deregister_enc_key(system::RawOrigin::Root.into(), alice),
+                Err("The anonymity set would be smaller than an anonymous transfer.")
+            );
+
+            // Joining reserves the deposit, which an account without funds can't pay.
+            assert!(AnonymousBalances::register_enc_key(Origin::signed(depositor()), EncKey::repeat_byte(0xff)).is_err());
+            assert!(AnonymousBalances::register_enc_key(Origin::signed(other), charlie).is_err());
+            assert_ok!(AnonymousBalances::register_enc_key(Origin::signed(depositor()), charlie));
+            assert!(AnonymousBalances::register_enc_key(Origin::signed(depositor()), charlie).is_err());
+            assert_eq!(AnonymousBalances::enc_key_set().len(), RING_SIZE + 1);
+            assert_eq!(Balances::free_balance(&depositor()), 100 - ENC_KEY_DEPOSIT);
+            assert_eq!(Balances::reserved_balance(&depositor()), ENC_KEY_DEPOSIT);
+            assert_eq!(AnonymousBalances::enc_key_owner(charlie), Some((depositor(), ENC_KEY_DEPOSIT)));
+
+            // Only the owner leaves, and gets the deposit back.
+            assert!(AnonymousBalances::deregister_enc_key(Origin::signed(other), charlie).is_err());
+            assert!(AnonymousBalances::deregister_enc_key(Origin::signed(depositor()), alice).is_err());
+            assert_ok!(AnonymousBalances::deregister_enc_key(Origin::signed(depositor()), charlie));
+            assert!(!AnonymousBalances::enc_key_set().contains(&charlie));
+            assert_eq!(AnonymousBalances::enc_key_owner(charlie), None);
+            assert_eq!(Balances::free_balance(&depositor()), 100);
+            assert_eq!(Balances::reserved_balance(&depositor()), 0);
+
+            // Root removes any account, returning the deposit of a registered one.
+            assert_ok!(AnonymousBalances::register_enc_key(Origin::signed(depositor()), charlie));
+            assert!(AnonymousBalances::force_deregister_enc_key(Origin::signed(depositor()), charlie).is_err());
+            assert_ok!(AnonymousBalances::force_deregister_enc_key(system::RawOrigin::Root.into(), charlie));
+            assert_eq!(Balances::reserved_balance(&depositor()), 0);
+        })
+    }
+
     #[test]
     fn test_call_from_zface() {
         with_externalities(&mut new_test_ext(), || {
-            let alice_seed = b"Alice                           ".to_vec();
+            let alice_seed = ALICE_SEED.to_vec();
             let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
 
             let spending_key = tSpendingKey::<tBls12>::from_seed(&alice_seed);
-            let bob_addr: [u8; 32] = hex!("45e66da531088b55dcb3b273ca825454d79d2d1d5c4fa2ba4a12c1fa1ccd6389");
+            let bob_addr = BOB_ADDRESS;
             let enc_key_recipient = tEncryptionKey::<tBls12>::read(&mut &bob_addr[..], &PARAMS).unwrap();
 
             let remaining_balance = 90;
@@ -464,7 +1095,7 @@ mod tests {
             assert!(no_std_e(&ENC_KEYS[t_index]) ==  enc_key_recipient);
             assert_eq!(enc_balances.clone().len(), 12);
 
-            let tx = KeyContext::<tBls12, Anonymous>::read_from_path(PK_PATH, VK_PATH)
+            let tx = KeyContext::<tBls12, Anonymous>::read_from_path(params_path(ANONY_PK), params_path(ANONY_VK))
                 .unwrap()
                 .gen_proof(
                     amount,
@@ -480,17 +1111,128 @@ mod tests {
                     &*PARAMS
                 ).unwrap();
 
-            let enc_keys = tx.enc_keys.iter().map(|e| EncKey::from_slice(e)).collect();
-            let left_ciphertexts = tx.left_ciphertexts.iter().map(|e| LeftCiphertext::from_slice(e)).collect();
+            let enc_keys: Vec<EncKey> = tx.enc_keys.iter().map(|e| EncKey::from_slice(e)).collect();
+            let left_ciphertexts: Vec<LeftCiphertext> = tx.left_ciphertexts.iter().map(|e| LeftCiphertext::from_slice(e)).collect();
 
-            assert_ok!(AnonymousBalances::anonymous_transfer(
-                Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..])),
+            let args = (
                 Proof::from_slice(&tx.proof[..]),
                 enc_keys,
                 left_ciphertexts,
                 RightCiphertext::from_slice(&tx.right_ciphertext[..]),
                 Nonce::from_slice(&tx.nonce[..])
+            );
+
+            assert_ok!(AnonymousBalances::anonymous_transfer(
+                Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..])),
+                args.0, args.1, args.2, args.3, args.4
             ));
         })
     }
+
+    #[test]
+    #[ignore]
+    fn bench_verify_anonymous_proof() {
+        use zk_system::{benchmarking, weights};
+
+        with_externalities(&mut new_test_ext(), || {
+            let alice_seed = ALICE_SEED.to_vec();
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+            let spending_key = tSpendingKey::<tBls12>::from_seed(&alice_seed);
+            let bob_addr = BOB_ADDRESS;
+            let enc_key_recipient = tEncryptionKey::<tBls12>::read(&mut &bob_addr[..], &PARAMS).unwrap();
+
+            // G_epoch of block height one.
+            let g_epoch_vec: [u8; 32] = hex!("0953f47325251a2f479c25527df6d977925bebafde84423b20ae6c903411665a");
+            let g_epoch = tedwards::Point::read(&g_epoch_vec[..], &*PARAMS).unwrap().as_prime_order(&*PARAMS).unwrap();
+            let decoys = ENC_KEYS.iter().skip(2).map(|e| no_std_e(e)).collect();
+
+            let tx = KeyContext::<tBls12, Anonymous>::read_from_path(params_path(ANONY_PK), params_path(ANONY_VK))
+                .unwrap()
+                .gen_proof(
+                    10, 0, 90, 0, 1,
+                    &spending_key,
+                    MultiEncKeys::<tBls12, Anonymous>::new(enc_key_recipient, decoys),
+                    &get_enc_balances(),
+                    g_epoch,
+                    rng,
+                    &*PARAMS
+                ).unwrap();
+
+            let enc_keys: Vec<EncKey> = tx.enc_keys.iter().map(|e| EncKey::from_slice(e)).collect();
+            let left_ciphertexts: Vec<LeftCiphertext> = tx.left_ciphertexts.iter().map(|e| LeftCiphertext::from_slice(e)).collect();
+            let enc_balances: Vec<Ciphertext> = enc_keys.iter()
+                .map(|e| AnonymousBalances::encrypted_balance(e).map_or(Ciphertext::zero(), |c| c))
+                .collect();
+
+            let measurement = benchmarking::measure("verify_anonymous_proof", 20, || {
+                assert_eq!(<zk_system::Module<Test>>::verify_anonymous_proof(
+                    &Proof::from_slice(&tx.proof[..]),
+                    &enc_keys[..],
+                    &left_ciphertexts[..],
+                    &RightCiphertext::from_slice(&tx.right_ciphertext[..]),
+                    &enc_balances[..],
+                    0,
+                    &SigVerificationKey::from_slice(&tx.rvk[..]),
+                    &Nonce::from_slice(&tx.nonce[..])
+                ), Ok(()));
+            });
+
+            measurement.report(weights::ANONYMOUS_PROOF);
+            assert!(measurement.weight() <= weights::ANONYMOUS_PROOF);
+        })
+    }
+
+    #[test]
+    fn test_anonymous_asset_transfer() {
+        with_externalities(&mut new_test_ext(), || {
+            let alice_seed = ALICE_SEED.to_vec();
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+            let spending_key = tSpendingKey::<tBls12>::from_seed(&alice_seed);
+            let bob_addr = BOB_ADDRESS;
+            let enc_key_recipient = tEncryptionKey::<tBls12>::read(&mut &bob_addr[..], &PARAMS).unwrap();
+            let bob = EncKey::from_slice(&bob_addr[..]);
+
+            // G_epoch of block height one.
+            let g_epoch_vec: [u8; 32] = hex!("0953f47325251a2f479c25527df6d977925bebafde84423b20ae6c903411665a");
+            let g_epoch = tedwards::Point::read(&g_epoch_vec[..], &*PARAMS).unwrap().as_prime_order(&*PARAMS).unwrap();
+            let decoys = ENC_KEYS.iter().skip(2).map(|e| no_std_e(e)).collect();
+
+            let tx = KeyContext::<tBls12, Anonymous>::read_from_path(params_path(ANONY_PK), params_path(ANONY_VK))
+                .unwrap()
+                .gen_proof(
+                    10,
+                    0,
+                    ALICE_BALANCE - 10,
+                    0,
+                    1,
+                    &spending_key,
+                    MultiEncKeys::<tBls12, Anonymous>::new(enc_key_recipient, decoys),
+                    &get_enc_balances(),
+                    g_epoch,
+                    rng,
+                    &*PARAMS
+                ).unwrap();
+
+            let args = (
+                ASSET_ID,
+                Proof::from_slice(&tx.proof[..]),
+                tx.enc_keys.iter().map(|e| EncKey::from_slice(e)).collect::<Vec<_>>(),
+                tx.left_ciphertexts.iter().map(|e| LeftCiphertext::from_slice(e)).collect::<Vec<_>>(),
+                RightCiphertext::from_slice(&tx.right_ciphertext[..]),
+                Nonce::from_slice(&tx.nonce[..])
+            );
+            let rvk = SigVerificationKey::from_slice(&tx.rvk[..]);
+
+            assert_ok!(AnonymousBalances::anonymous_asset_transfer(
+                Origin::signed(rvk),
+                args.0, args.1, args.2, args.3, args.4, args.5
+            ));
+
+            // Only the balances of the asset are moved.
+            assert!(EncryptedAssets::total_pending_transfer(&bob, ASSET_ID).is_some());
+            assert_eq!(AnonymousBalances::total_pending_transfer(&bob), None);
+        })
+    }
 }
@@ -0,0 +1,715 @@
+//! A module for distributing encrypted balances to many recipients at once.
+//! A funder deposits an encrypted pot from its balance together with the claims,
+//! each of which is an amount encrypted under the recipient's key and under the funder's key with the same randomness.
+//! Only the Merkle root of the claims is stored. Recipients claim with a Merkle proof and a zk proof
+//! of the knowledge of their decryption key, and the amount is credited to their pending transfer,
+//! so the funder doesn't need to send N transfers.
+//!
+//! Each claim comes with the zk proof of a confidential transfer of its amount from the funder to the recipient,
+//! made against the pot left by the claims before it. The proofs show that both parts of a claim encrypt
+//! the same amount and that the claims don't add up to more than the pot. The part under the funder's key
+//! is subtracted from the pot when it's claimed, so that the funder can reclaim what is left after the airdrop expires.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use support::{decl_module, decl_storage, decl_event, StorageMap, StorageValue, ensure, dispatch::Result};
+use rstd::prelude::*;
+use parity_codec::{Encode, Decode};
+use runtime_primitives::traits::{Zero, Hash};
+use system::ensure_signed;
+use zk_system::{VerificationError, ConfidentialProof};
+use zprimitives::{EncKey, Proof, Nonce, Ciphertext, LeftCiphertext, RightCiphertext};
+
+/// Domain separator of the leaves of the claims tree.
+const LEAF_CONTEXT: &[u8] = b"Zerochain-AirdropClaim";
+
+pub type AirdropId = u64;
+
+/// The maximum number of claims of an airdrop, whose proofs are all verified when it's created.
+pub const MAX_CLAIMS: usize = 16;
+
+pub trait Trait: system::Trait + encrypted_balances::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+/// A claim of an airdrop, which is a leaf of the claims tree.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Claim {
+    /// The recipient of the claim.
+    pub recipient: EncKey,
+    /// The amount encrypted under the recipient's key.
+    pub amount: LeftCiphertext,
+    /// The same amount encrypted under the funder's key.
+    pub funder_amount: LeftCiphertext,
+    /// The randomness shared by both encryptions.
+    pub randomness: RightCiphertext,
+}
+
+/// The proof that a claim is covered by the pot.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ClaimProof<AccountId> {
+    /// The zk proof of a confidential transfer of the claim from the funder to the recipient,
+    /// made against the pot left by the claims before it.
+    pub zkproof: Proof,
+    /// The fee of the transfer, encrypted under the funder's key. It is subtracted from the pot left for the next claims.
+    pub fee: LeftCiphertext,
+    /// The re-randomized spend authority key the proof is made with.
+    pub rvk: AccountId,
+}
+
+/// An airdrop which can be claimed until it expires.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Airdrop<Hash, BlockNumber> {
+    /// The account which deposited the pot.
+    pub funder: EncKey,
+    /// The Merkle root of the claims.
+    pub root: Hash,
+    /// The unclaimed amount, encrypted under the funder's key.
+    pub pot: Ciphertext,
+    /// The block number from which the airdrop can't be claimed and the pot can be reclaimed.
+    pub expiry: BlockNumber,
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
+        /// Deposit `total` from the funder's encrypted balance into a new airdrop of the `claims`.
+        /// The zk proof is a confidential transfer from the funder to itself, which proves that
+        /// the funder's balance covers `total` and the fee. Each of `claim_proofs` is made with the same nonce
+        /// and proves the claim of the same index against the pot left by the claims before it.
+        pub fn create(
+            origin,
+            zkproof: Proof,
+            funder: EncKey,
+            total: LeftCiphertext,
+            fee: LeftCiphertext,
+            randomness: RightCiphertext,
+            nonce: Nonce,
+            claims: Vec<Claim>,
+            claim_proofs: Vec<ClaimProof<T::AccountId>>,
+            duration: T::BlockNumber
+        ) -> Result {
+            let rvk = ensure_signed(origin)?;
+
+            ensure!(!duration.is_zero(), "The airdrop must last at least one block.");
+            ensure!(!claims.is_empty(), "No claims are provided.");
+            ensure!(claims.len() <= MAX_CLAIMS, "Too many claims.");
+            ensure!(claims.len() == claim_proofs.len(), "Mismatch the number of claims and proofs.");
+
+            // Reject malformed ciphertexts before any storage mutation.
+            let pot = Ciphertext::from_left_right(total, randomness)?;
+            let enc_fee = Ciphertext::from_left_right(fee, randomness)?;
+
+            // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
+            // No problem if errors occur after this function because
+            // it just rollover user's own `pending trasfer` to `encrypted balances`.
+            <encrypted_balances::Module<T>>::rollover(&funder)?;
+
+            let mut proofs = Vec::with_capacity(1 + claims.len());
+            proofs.push(ConfidentialProof {
+                zkproof,
+                address_sender: funder,
+                address_recipient: funder,
+                amount_sender: total,
+                amount_recipient: total,
+                balance_sender: <encrypted_balances::Module<T>>::encrypted_balance(funder).map_or(Ciphertext::zero(), |e| e),
+                rvk: rvk.clone(),
+                fee_sender: fee,
+                randomness,
+                nonce,
+            });
+
+            // The pot left after each claim, which the proof of the next claim is made against.
+            let mut left = pot.clone();
+            for (claim, claim_proof) in claims.iter().zip(claim_proofs) {
+                Ciphertext::from_left_right(claim.amount, claim.randomness)?;
+                let funder_amount = Ciphertext::from_left_right(claim.funder_amount, claim.randomness)?;
+                let claim_fee = Ciphertext::from_left_right(claim_proof.fee, claim.randomness)?;
+
+                proofs.push(ConfidentialProof {
+                    zkproof: claim_proof.zkproof,
+                    address_sender: funder,
+                    address_recipient: claim.recipient,
+                    amount_sender: claim.funder_amount,
+                    amount_recipient: claim.amount,
+                    balance_sender: left.clone(),
+                    rvk: claim_proof.rvk,
+                    fee_sender: claim_proof.fee,
+                    randomness: claim.randomness,
+                    nonce,
+                });
+
+                left = left.sub(&funder_amount)
+                    .and_then(|l| l.sub(&claim_fee))
+                    .map_err(|_| "Faild to subtract the claim from the pot.")?;
+            }
+
+            // Reserve the provided nonce, which fails if it's included in the nonce pool.
+            let ticket = <zk_system::Module<T>>::reserve_nonce(nonce)?;
+
+            // Verify the zk proofs
+            if let Err(e) = <zk_system::Module<T>>::verify_confidential_proofs_batch(&proofs[..]) {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                if e == VerificationError::InvalidProof {
+                    Self::deposit_event(RawEvent::InvalidZkProof());
+                }
+                return Err(e.into());
+            }
+
+            // Subtracting the deposit and fee from the funder's encrypted balances.
+            // This function causes a storage mutation.
+            if let Err(e) = <encrypted_balances::Module<T>>::sub_enc_balance(&funder, &total, &fee, &randomness) {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                return Err(e.into());
+            }
+
+            // Add a nonce into the nonce pool
+            <zk_system::Module<T>>::commit_nonce(ticket);
+
+            <zk_stats::Module<T>>::note_confidential_transfer(&[funder], &enc_fee);
+
+            let leaves = claims.iter().map(Self::claim_leaf).collect::<Vec<_>>();
+            let root = Self::merkle_root(&leaves);
+            let id = Self::next_airdrop_id();
+            let expiry = <system::Module<T>>::block_number() + duration;
+            <Airdrops<T>>::insert(id, Airdrop { funder, root, pot, expiry });
+            <NextAirdropId<T>>::put(id + 1);
+
+            Self::deposit_event(RawEvent::Created(id, funder, root, expiry));
+
+            Ok(())
+        }
+
+        /// Claim the `index`-th leaf of an airdrop and credit its amount to the recipient's pending transfer.
+        /// The zk proof is a confidential transfer from the recipient to itself, as in `EncryptedAssets::mint`,
+        /// which proves the knowledge of the recipient's decryption key.
+        pub fn claim(
+            origin,
+            airdrop_id: AirdropId,
+            index: u32,
+            claim: Claim,
+            merkle_proof: Vec<T::Hash>,
+            zkproof: Proof,
+            amount: LeftCiphertext,
+            fee: LeftCiphertext,
+            balance: Ciphertext,
+            randomness: RightCiphertext,
+            nonce: Nonce
+        ) -> Result {
+            let rvk = ensure_signed(origin)?;
+
+            let airdrop = Self::airdrop(airdrop_id).ok_or("The airdrop doesn't exist.")?;
+            ensure!(<system::Module<T>>::block_number() < airdrop.expiry, "The airdrop has expired.");
+
+            // Claims are tracked by their leaf rather than their index, because the last leaf of
+            // a level with an odd number of nodes is also reachable from the index next to it.
+            let leaf = Self::claim_leaf(&claim);
+            ensure!(!Self::claimed((airdrop_id, leaf)), "The claim has already been claimed.");
+            ensure!(Self::verify_merkle_proof(&leaf, index, &merkle_proof, &airdrop.root), "Invalid merkle proof.");
+
+            // Reject malformed ciphertexts and compute the remaining pot before any storage mutation.
+            Ciphertext::from_left_right(claim.amount, claim.randomness)?;
+            let funder_amount = Ciphertext::from_left_right(claim.funder_amount, claim.randomness)?;
+            let pot = airdrop.pot.sub(&funder_amount)
+                .map_err(|_| "Faild to subtract the claim from the pot.")?;
+            Ciphertext::from_left_right(amount, randomness)?;
+            Ciphertext::from_left_right(fee, randomness)?;
+
+            // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
+            // No problem if errors occur after this function because
+            // it just rollover user's own `pending trasfer` to `encrypted balances`.
+            <encrypted_balances::Module<T>>::rollover(&claim.recipient)?;
+
+            // Reserve the provided nonce, which fails if it's included in the nonce pool.
+            let ticket = <zk_system::Module<T>>::reserve_nonce(nonce)?;
+
+            // Verify the zk proof
+            if let Err(e) = <zk_system::Module<T>>::verify_confidential_proof(
+                &zkproof,
+                &claim.recipient,
+                &claim.recipient,
+                &amount,
+                &amount,
+                &balance,
+                &rvk,
+                &fee,
+                &randomness,
+                &nonce
+            ) {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                if e == VerificationError::InvalidProof {
+                    Self::deposit_event(RawEvent::InvalidZkProof());
+                }
+                return Err(e.into());
+            }
+
+            // Adding the claimed amount to the recipient's pending transfer.
+            // This function causes a storage mutation.
+            if let Err(e) = <encrypted_balances::Module<T>>::add_pending_transfer(&claim.recipient, &claim.amount, &claim.randomness) {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                return Err(e.into());
+            }
+
+            // Add a nonce into the nonce pool
+            <zk_system::Module<T>>::commit_nonce(ticket);
+
+            <Airdrops<T>>::insert(airdrop_id, Airdrop { pot, ..airdrop });
+            <Claimed<T>>::insert((airdrop_id, leaf), true);
+
+            Self::deposit_event(RawEvent::Claimed(airdrop_id, leaf, claim.recipient));
+
+            Ok(())
+        }
+
+        /// Credit the unclaimed pot of an expired airdrop back to the funder's pending transfer.
+        /// Anyone can call it, since the pot only goes back to the funder.
+        pub fn reclaim(origin, airdrop_id: AirdropId) -> Result {
+            ensure_signed(origin)?;
+
+            let airdrop = Self::airdrop(airdrop_id).ok_or("The airdrop doesn't exist.")?;
+            ensure!(<system::Module<T>>::block_number() >= airdrop.expiry, "The airdrop hasn't expired yet.");
+
+            let left = airdrop.pot.left().map_err(|_| "Invalid ciphertext of the pot.")?;
+            let right = airdrop.pot.right().map_err(|_| "Invalid ciphertext of the pot.")?;
+
+            <encrypted_balances::Module<T>>::rollover(&airdrop.funder)?;
+            <encrypted_balances::Module<T>>::add_pending_transfer(&airdrop.funder, &left, &right)?;
+            <Airdrops<T>>::remove(airdrop_id);
+
+            Self::deposit_event(RawEvent::Reclaimed(airdrop_id, airdrop.funder, airdrop.pot));
+
+            Ok(())
+        }
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as EncryptedAirdrop {
+        /// Airdrops which haven't been reclaimed
+        pub Airdrops get(airdrop) : map AirdropId => Option<Airdrop<T::Hash, T::BlockNumber>>;
+
+        /// The next airdrop identifier up for grabs.
+        pub NextAirdropId get(next_airdrop_id) : AirdropId;
+
+        /// Whether a leaf of an airdrop has been claimed
+        pub Claimed get(claimed) : map (AirdropId, T::Hash) => bool;
+    }
+}
+
+decl_event! (
+    pub enum Event<T> where <T as system::Trait>::Hash, <T as system::Trait>::BlockNumber {
+        /// An airdrop was created by the funder with the Merkle root of the claims and the expiry.
+        Created(AirdropId, EncKey, Hash, BlockNumber),
+        /// A leaf of an airdrop was claimed by the recipient.
+        Claimed(AirdropId, Hash, EncKey),
+        /// The unclaimed pot of an expired airdrop was credited back to the funder.
+        Reclaimed(AirdropId, EncKey, Ciphertext),
+        InvalidZkProof(),
+    }
+);
+
+impl<T: Trait> Module<T> {
+    /// The leaf of a claim in the claims tree.
+    pub fn claim_leaf(claim: &Claim) -> T::Hash {
+        let mut buf = LEAF_CONTEXT.to_vec();
+        claim.encode_to(&mut buf);
+        T::Hashing::hash(&buf[..])
+    }
+
+    /// The Merkle root of the leaves. The last node of a level with an odd number of nodes is paired with itself.
+    pub fn merkle_root(leaves: &[T::Hash]) -> T::Hash {
+        if leaves.is_empty() {
+            return T::Hash::default();
+        }
+
+        let mut level = leaves.to_vec();
+        while level.len() > 1 {
+            level = level.chunks(2)
+                .map(|pair| Self::hash_pair(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+                .collect();
+        }
+
+        level[0]
+    }
+
+    /// The sibling nodes from the `index`-th leaf up to the root.
+    pub fn merkle_proof(leaves: &[T::Hash], index: usize) -> Option<Vec<T::Hash>> {
+        if index >= leaves.len() {
+            return None;
+        }
+
+        let mut proof = vec![];
+        let mut level = leaves.to_vec();
+        let mut index = index;
+        while level.len() > 1 {
+            let sibling = index ^ 1;
+            proof.push(*level.get(sibling).unwrap_or(&level[index]));
+
+            level = level.chunks(2)
+                .map(|pair| Self::hash_pair(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+                .collect();
+            index /= 2;
+        }
+
+        Some(proof)
+    }
+
+    /// Check that `leaf` is the `index`-th leaf of the tree with the `root`.
+    pub fn verify_merkle_proof(leaf: &T::Hash, index: u32, proof: &[T::Hash], root: &T::Hash) -> bool {
+        if proof.len() > 32 || (index as u64) >> proof.len() != 0 {
+            return false;
+        }
+
+        let node = proof.iter().enumerate().fold(*leaf, |node, (i, sibling)| {
+            if (index >> i) & 1 == 0 {
+                Self::hash_pair(&node, sibling)
+            } else {
+                Self::hash_pair(sibling, &node)
+            }
+        });
+
+        &node == root
+    }
+
+    fn hash_pair(left: &T::Hash, right: &T::Hash) -> T::Hash {
+        T::Hashing::hash_of(&(left, right))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use support::{impl_outer_origin, assert_ok};
+    use primitives::{H256, Blake2Hasher};
+    use runtime_primitives::{
+        BuildStorage, traits::{BlakeTwo256, IdentityLookup},
+        testing::{Digest, DigestItem, Header}
+    };
+    use zprimitives::SigVerificationKey;
+    use keys::{ProofGenerationKey, EncryptionKey};
+    use jubjub::curve::{JubjubBls12, FixedGenerators, fs::Fs};
+    use pairing::{Field, bls12_381::Bls12};
+    use zcrypto::elgamal;
+    use rand::{SeedableRng, XorShiftRng};
+    use test_pairing::bls12_381::Bls12 as tBls12;
+    use test_proofs::{EncryptionKey as tEncryptionKey, SpendingKey as tSpendingKey,
+        elgamal as telgamal, PARAMS, MultiEncKeys, KeyContext, ProofBuilder, Confidential,
+        confidential::ConfidentialXt,
+    };
+    use test_fixtures::{ALICE_SEED, BOB_SEED, CONF_PK, CONF_VK, params_path, conf_vk, anony_vk, alice_genesis, g_epoch};
+    use std::convert::TryFrom;
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = SigVerificationKey;
+        type SigVerificationKey = u64;
+        type Lookup = IdentityLookup<SigVerificationKey>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl encrypted_balances::Trait for Test {
+        type Event = ();
+        type ConfidentialFee = encrypted_balances::FlatFee<Test>;
+    }
+
+    impl zk_system::Trait for Test {
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl zk_stats::Trait for Test { }
+
+    impl zk_auditor::Trait for Test {
+        type Event = ();
+    }
+
+    impl Trait for Test {
+        type Event = ();
+    }
+
+    type EncryptedBalances = encrypted_balances::Module<Test>;
+    type EncryptedAirdrop = Module<Test>;
+
+    fn enc_key(seed: &[u8]) -> EncryptionKey<Bls12> {
+        EncryptionKey::<Bls12>::from_seed(seed, &JubjubBls12::new()).unwrap()
+    }
+
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let ((alice, balance), _) = alice_genesis();
+
+        let (mut t, mut c) = system::GenesisConfig::<Test>::default().build_storage().unwrap();
+        let _ = zk_system::GenesisConfig::<Test>{
+            last_epoch: 1,
+            epoch_length: 1,
+            max_anonymity_set_size: 12,
+            nonce_retention_epochs: 0,
+            confidential_vk: conf_vk(),
+            anonymous_vk: anony_vk(),
+            confidential_pk_hash: [0u8; 32],
+            anonymous_pk_hash: [0u8; 32],
+        }.assimilate_storage(&mut t, &mut c);
+        let _ = encrypted_balances::GenesisConfig::<Test>{
+            encrypted_balance: vec![(alice, balance)],
+            last_rollover: vec![(alice, 0)],
+            transaction_base_fee: 1,
+            anonymous_fee: 0,
+            _genesis_phantom_data: Default::default()
+        }.assimilate_storage(&mut t, &mut c);
+
+        zk_system::pool::test_externalities(t, c)
+    }
+
+    fn decrypt(seed: &[u8], ciphertext: &Ciphertext) -> u64 {
+        let params = &JubjubBls12::new();
+        let dec_key = ProofGenerationKey::<Bls12>::from_seed(seed, params).into_decryption_key().unwrap();
+        elgamal::Ciphertext::<Bls12>::try_from(ciphertext).unwrap()
+            .decrypt(&dec_key, FixedGenerators::Diversifier, params)
+            .unwrap()
+    }
+
+    // Prove a confidential transfer of `amount` with the fee from the seed's account to the recipient's in the first epoch.
+    fn prove_transfer(
+        seed: &[u8],
+        recipient_seed: &[u8],
+        amount: u64,
+        fee: u64,
+        remaining_balance: u64,
+        balance: &Ciphertext,
+        rng: &mut XorShiftRng
+    ) -> ConfidentialXt {
+        let spending_key = tSpendingKey::<tBls12>::from_seed(seed);
+        let enc_key = tEncryptionKey::<tBls12>::from_seed(recipient_seed, &PARAMS).unwrap();
+        let enc_balance = vec![telgamal::Ciphertext::read(&mut balance.as_bytes(), &*PARAMS).unwrap()];
+
+        KeyContext::read_from_path(params_path(CONF_PK), params_path(CONF_VK))
+            .unwrap()
+            .gen_proof(
+                amount,
+                fee,
+                remaining_balance, 0, 0,
+                &spending_key,
+                MultiEncKeys::<tBls12, Confidential>::new(enc_key),
+                &enc_balance,
+                g_epoch(0),
+                rng,
+                &*PARAMS
+            ).unwrap()
+    }
+
+    // A claim of `amount` from alice's airdrop, proven against the pot left by the claims before it.
+    fn new_claim(
+        recipient_seed: &[u8],
+        amount: u64,
+        remaining_pot: u64,
+        pot: &Ciphertext,
+        rng: &mut XorShiftRng
+    ) -> (Claim, ClaimProof<SigVerificationKey>) {
+        let tx = prove_transfer(ALICE_SEED, recipient_seed, amount, 0, remaining_pot, pot, rng);
+        let claim = Claim {
+            recipient: EncKey::from_slice(&tx.enc_key_recipient[..]),
+            amount: LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+            funder_amount: LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+            randomness: RightCiphertext::from_slice(&tx.right_randomness[..]),
+        };
+        let claim_proof = ClaimProof {
+            zkproof: Proof::from_slice(&tx.proof[..]),
+            fee: LeftCiphertext::from_slice(&tx.left_fee[..]),
+            rvk: SigVerificationKey::from_slice(&tx.rvk[..]),
+        };
+
+        (claim, claim_proof)
+    }
+
+    // The pot left for the claims after `claim`.
+    fn pot_after(pot: &Ciphertext, claim: &Claim, claim_proof: &ClaimProof<SigVerificationKey>) -> Ciphertext {
+        pot.sub(&Ciphertext::from_left_right(claim.funder_amount, claim.randomness).unwrap())
+            .and_then(|p| p.sub(&Ciphertext::from_left_right(claim_proof.fee, claim.randomness).unwrap()))
+            .unwrap()
+    }
+
+    type CreateArgs = (Proof, EncKey, LeftCiphertext, LeftCiphertext, RightCiphertext, Nonce, Vec<Claim>, Vec<ClaimProof<SigVerificationKey>>, u64);
+
+    // Alice deposits 10 with the fee 1 from her genesis balance of 100.
+    // The claims are made by `claims` from the pot.
+    fn create_args<F>(claims: F) -> (SigVerificationKey, CreateArgs)
+        where F: FnOnce(&Ciphertext, &mut XorShiftRng) -> Vec<(Claim, ClaimProof<SigVerificationKey>)>
+    {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let (alice, _) = alice_genesis().0;
+        let balance = EncryptedBalances::encrypted_balance(alice).unwrap();
+        let tx = prove_transfer(ALICE_SEED, ALICE_SEED, 10, 1, 89, &balance, rng);
+        let total = LeftCiphertext::from_slice(&tx.left_amount_sender[..]);
+        let randomness = RightCiphertext::from_slice(&tx.right_randomness[..]);
+        let (claims, claim_proofs): (Vec<_>, Vec<_>) = claims(&Ciphertext::from_left_right(total, randomness).unwrap(), rng)
+            .into_iter()
+            .unzip();
+
+        let args = (
+            Proof::from_slice(&tx.proof[..]),
+            EncKey::from_slice(&tx.enc_key_sender[..]),
+            total,
+            LeftCiphertext::from_slice(&tx.left_fee[..]),
+            randomness,
+            Nonce::from_slice(&tx.nonce[..]),
+            claims,
+            claim_proofs,
+            10u64
+        );
+
+        (SigVerificationKey::from_slice(&tx.rvk[..]), args)
+    }
+
+    fn create((rvk, args): (SigVerificationKey, CreateArgs)) -> Result {
+        EncryptedAirdrop::create(
+            Origin::signed(rvk),
+            args.0, args.1, args.2, args.3, args.4, args.5, args.6, args.7, args.8
+        )
+    }
+
+    fn claim_args(airdrop_id: AirdropId, index: u32, claim: &Claim, merkle_proof: &[H256], tx: &ConfidentialXt, balance: &Ciphertext) -> (
+        SigVerificationKey,
+        (AirdropId, u32, Claim, Vec<H256>, Proof, LeftCiphertext, LeftCiphertext, Ciphertext, RightCiphertext, Nonce)
+    ) {
+        let args = (
+            airdrop_id,
+            index,
+            claim.clone(),
+            merkle_proof.to_vec(),
+            Proof::from_slice(&tx.proof[..]),
+            LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+            LeftCiphertext::from_slice(&tx.left_fee[..]),
+            balance.clone(),
+            RightCiphertext::from_slice(&tx.right_randomness[..]),
+            Nonce::from_slice(&tx.nonce[..])
+        );
+
+        (SigVerificationKey::from_slice(&tx.rvk[..]), args)
+    }
+
+    #[test]
+    fn test_airdrop() {
+        with_externalities(&mut new_test_ext(), || {
+            let (alice, _) = alice_genesis().0;
+
+            // Alice airdrops 4 to herself and 6 to bob from a pot of 10.
+            let args = create_args(|pot, rng| {
+                let (alice_claim, alice_proof) = new_claim(ALICE_SEED, 4, 6, pot, rng);
+                let pot = pot_after(pot, &alice_claim, &alice_proof);
+                let (bob_claim, bob_proof) = new_claim(BOB_SEED, 6, 0, &pot, rng);
+                vec![(alice_claim, alice_proof), (bob_claim, bob_proof)]
+            });
+            let claims = (args.1).6.clone();
+            let leaves: Vec<H256> = claims.iter().map(EncryptedAirdrop::claim_leaf).collect();
+
+            assert_ok!(create(args));
+            assert_eq!(decrypt(ALICE_SEED, &EncryptedBalances::encrypted_balance(alice).unwrap()), 89);
+            assert_eq!(EncryptedAirdrop::airdrop(0).unwrap().root, EncryptedAirdrop::merkle_root(&leaves));
+            assert_eq!(decrypt(ALICE_SEED, &EncryptedAirdrop::airdrop(0).unwrap().pot), 10);
+            assert_eq!(EncryptedAirdrop::next_airdrop_id(), 1);
+
+            // Bob proves the knowledge of his key with a zero transfer to himself.
+            let bob = claims[1].recipient;
+            let dummy_balance = Ciphertext::try_from(elgamal::Ciphertext::encrypt(
+                0, &Fs::one(), &enc_key(BOB_SEED), FixedGenerators::Diversifier, &JubjubBls12::new()
+            )).unwrap();
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let tx = prove_transfer(BOB_SEED, BOB_SEED, 0, 0, 0, &dummy_balance, rng);
+            let proof = EncryptedAirdrop::merkle_proof(&leaves, 1).unwrap();
+            type Args = (AirdropId, u32, Claim, Vec<H256>, Proof, LeftCiphertext, LeftCiphertext, Ciphertext, RightCiphertext, Nonce);
+            fn submit((rvk, args): &(SigVerificationKey, Args)) -> Result {
+                EncryptedAirdrop::claim(
+                    Origin::signed(*rvk),
+                    args.0, args.1, args.2.clone(), args.3.clone(), args.4.clone(),
+                    args.5, args.6, args.7.clone(), args.8, args.9
+                )
+            }
+
+            assert_eq!(
+                submit(&claim_args(0, 0, &claims[1], &proof, &tx, &dummy_balance)),
+                Err("Invalid merkle proof.")
+            );
+
+            let bob_claim = claim_args(0, 1, &claims[1], &proof, &tx, &dummy_balance);
+            assert_ok!(submit(&bob_claim));
+            assert_eq!(decrypt(BOB_SEED, &EncryptedBalances::total_pending_transfer(&bob).unwrap()), 6);
+            assert_eq!(decrypt(ALICE_SEED, &EncryptedAirdrop::airdrop(0).unwrap().pot), 4);
+            assert!(EncryptedAirdrop::claimed((0, leaves[1])));
+
+            assert_eq!(submit(&bob_claim), Err("The claim has already been claimed."));
+
+            // Alice doesn't claim her leaf, so the rest of the pot goes back to her after the expiry.
+            assert_eq!(
+                EncryptedAirdrop::reclaim(Origin::signed(bob_claim.0), 0),
+                Err("The airdrop hasn't expired yet.")
+            );
+
+            <system::Module<Test>>::set_block_number(10);
+            assert_ok!(EncryptedAirdrop::reclaim(Origin::signed(bob_claim.0), 0));
+            assert_eq!(EncryptedAirdrop::airdrop(0), None);
+            assert_eq!(decrypt(ALICE_SEED, &EncryptedBalances::total_pending_transfer(&alice).unwrap()), 4);
+        })
+    }
+
+    #[test]
+    fn test_create_rejects_claims_above_the_pot() {
+        with_externalities(&mut new_test_ext(), || {
+            let (alice, _) = alice_genesis().0;
+
+            // Both claims of 6 are covered by the pot of 10 alone, but not together.
+            let args = create_args(|pot, rng| vec![
+                new_claim(ALICE_SEED, 6, 4, pot, rng),
+                new_claim(BOB_SEED, 6, 4, pot, rng),
+            ]);
+            assert_eq!(create(args), Err("Invalid zkproof"));
+
+            let args = create_args(|pot, rng| vec![new_claim(BOB_SEED, 6, 4, pot, rng)]);
+            let mut mismatched = args.clone();
+            (mismatched.1).7.clear();
+            assert_eq!(create(mismatched), Err("Mismatch the number of claims and proofs."));
+
+            // Nothing is debited or recorded by the failed calls.
+            let nonce = (args.1).5;
+            assert!(!<zk_system::Module<Test>>::is_nonce_used(&nonce));
+            assert!(!<zk_system::Module<Test>>::is_nonce_reserved(&nonce));
+            assert_eq!(decrypt(ALICE_SEED, &EncryptedBalances::encrypted_balance(alice).unwrap()), 100);
+
+            assert_ok!(create(args));
+            assert!(<zk_system::Module<Test>>::is_nonce_used(&nonce));
+        })
+    }
+
+    #[test]
+    fn test_merkle_proof() {
+        let leaves: Vec<H256> = (0u8..5).map(|i| H256::repeat_byte(i)).collect();
+        let root = EncryptedAirdrop::merkle_root(&leaves);
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let proof = EncryptedAirdrop::merkle_proof(&leaves, i).unwrap();
+            assert!(EncryptedAirdrop::verify_merkle_proof(leaf, i as u32, &proof, &root));
+            assert!(!EncryptedAirdrop::verify_merkle_proof(leaf, (i as u32) ^ 1, &proof, &root));
+            assert!(!EncryptedAirdrop::verify_merkle_proof(&H256::repeat_byte(9), i as u32, &proof, &root));
+        }
+
+        assert_eq!(EncryptedAirdrop::merkle_proof(&leaves, 5), None);
+        assert_eq!(EncryptedAirdrop::merkle_root(&leaves[..1]), leaves[0]);
+    }
+}
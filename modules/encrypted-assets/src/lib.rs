@@ -6,13 +6,101 @@
 use support::{decl_module, decl_storage, decl_event, StorageMap, Parameter, StorageValue, ensure};
 use rstd::prelude::*;
 use rstd::result;
-use runtime_primitives::traits::{SimpleArithmetic, Zero, One};
+use rstd::convert::TryFrom;
+use rstd::borrow::Borrow;
+use runtime_primitives::traits::{SimpleArithmetic, Zero, One, Hash};
+use parity_codec::{Encode, Decode};
 use system::ensure_signed;
+use zk_system::VerificationError;
+use zk_system::pool::{Pool, PoolMap};
+use encrypted_balances::ConfidentialFee;
+use jubjub::{redjubjub, curve::FixedGenerators};
+use pairing::bls12_381::Bls12;
 use zprimitives::{
     EncKey, Proof,
-    Nonce, Ciphertext, LeftCiphertext, RightCiphertext,
+    Nonce, Ciphertext, CiphertextError, LeftCiphertext, RightCiphertext,
+    RedjubjubSignature, Memo, PARAMS,
 };
 
+/// Domain separator of the metadata message signed by the issuer.
+const METADATA_CONTEXT: &[u8] = b"Zerochain-AssetMetadata";
+
+/// Domain separator of the commitment to the issuer of `issue_with_hidden_issuer`.
+const ISSUER_COMMITMENT_CONTEXT: &[u8] = b"Zerochain-AssetIssuerCommitment";
+
+/// The maximum length of the encrypted issuer hint of `issue_with_hidden_issuer`.
+const MAX_ISSUER_HINT_LENGTH: usize = 256;
+
+/// The maximum length of the name of an asset.
+pub const MAX_NAME_LENGTH: usize = 64;
+
+/// The maximum length of the symbol of an asset.
+pub const MAX_SYMBOL_LENGTH: usize = 16;
+
+/// The maximum number of decimals of an asset. Amounts are `u64`, whose maximum has twenty digits.
+pub const MAX_DECIMALS: u8 = 19;
+
+/// Errors of rolling over and updating encrypted balances.
+/// The codes are stable across runtime upgrades, so front-ends can match on them.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Error {
+    /// A ciphertext made of the given left and right halves is invalid.
+    MalformedCiphertext(CiphertextError),
+    /// Failed to add the matured pending transfers to the balance.
+    BalanceMutation,
+    /// Failed to add the amount to the pending transfer.
+    PendingTransferMutation,
+    /// Failed to add the fee to the amount.
+    FeeAddition,
+}
+
+impl Error {
+    pub fn code(&self) -> u8 {
+        match *self {
+            Error::MalformedCiphertext(_) => 1,
+            Error::BalanceMutation => 2,
+            Error::PendingTransferMutation => 3,
+            Error::FeeAddition => 4,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Error::MalformedCiphertext(e) => e.as_str(),
+            Error::BalanceMutation => "Faild to mutate encrypted balance.",
+            Error::PendingTransferMutation => "Faild to mutate pending transfer.",
+            Error::FeeAddition => "Failed to add fee to amount",
+        }
+    }
+}
+
+impl From<CiphertextError> for Error {
+    fn from(e: CiphertextError) -> Self {
+        Error::MalformedCiphertext(e)
+    }
+}
+
+impl From<Error> for &'static str {
+    fn from(e: Error) -> &'static str {
+        e.as_str()
+    }
+}
+
+/// Human readable information about an asset for wallets.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Metadata {
+    /// The name of the asset.
+    pub name: Vec<u8>,
+    /// The ticker symbol of the asset.
+    pub symbol: Vec<u8>,
+    /// The number of decimals an amount is displayed with.
+    pub decimals: u8,
+    /// The issuer who set the metadata.
+    pub issuer: EncKey,
+}
+
 /// The module configuration trait.
 pub trait Trait: system::Trait + encrypted_balances::Trait + zk_system::Trait {
     /// The overarching event type.
@@ -41,48 +129,155 @@ decl_module! {
         ) {
             let rvk = ensure_signed(origin)?;
 
+            let (id, total_ciphertext) = Self::issue_asset(
+                &rvk, &zkproof, &issuer, &total, &fee, &balance, &randomness, &nonce
+            )?;
+            <Issuer<T>>::insert(id, issuer);
+
+            Self::deposit_event(RawEvent::Issued(id, issuer, total_ciphertext));
+        }
+
+        /// Increase the supply of an asset by `amount`, credited to the issuer's encrypted balance.
+        /// Only the issuer recorded by `issue` can mint. The zk proof is a confidential transfer
+        /// from the issuer to itself, as in `issue`, which proves the knowledge of the issuer's
+        /// decryption key and that `amount` is a well-formed encryption of an in-range value under the issuer's key.
+        fn mint(
+            origin,
+            asset_id: T::AssetId,
+            zkproof: Proof,
+            issuer: EncKey,
+            amount: LeftCiphertext,
+            fee: LeftCiphertext,
+            balance: Ciphertext,
+            randomness: RightCiphertext,
+            nonce: Nonce
+        ) {
+            let rvk = ensure_signed(origin)?;
+
+            ensure!(Self::issuer(asset_id) == Some(issuer), "Only the issuer can mint the asset.");
+
+            // Reject malformed ciphertexts and compute the new supply and balance before any storage mutation.
+            let amount_ciphertext = Ciphertext::from_left_right(amount, randomness)?;
+            Ciphertext::from_left_right(fee, randomness)?;
+            let new_total_supply = <TotalSupply<T>>::get(asset_id).add(&amount_ciphertext)
+                .map_err(|_| "Faild to add amount to total supply.")?;
+            let new_balance = match Self::encrypted_balance((asset_id, issuer)) {
+                Some(b) => b.add(&amount_ciphertext).map_err(|_| "Faild to add amount to issuer's balance.")?,
+                None => amount_ciphertext.clone(),
+            };
+
             // Initialize a nonce pool
             let current_epoch = <zk_system::Module<T>>::get_current_epoch();
             <zk_system::Module<T>>::init_nonce_pool(current_epoch);
 
-            // Veridate the provided nonce isn't included in the nonce pool.
-            ensure!(!<zk_system::Module<T>>::nonce_pool().contains(&nonce), "Provided nonce is already included in the nonce pool.");
+            // Reserve the provided nonce, which fails if it's included in the nonce pool.
+            let ticket = <zk_system::Module<T>>::reserve_nonce(nonce)?;
 
             // Verify a zk proof
             // 1. Spend authority verification
-            // 2. Range check of issued amount
+            // 2. Range check of minted amount
             // 3. Encryption integrity
-            if !<zk_system::Module<T>>::verify_confidential_proof(
+            if let Err(e) = <zk_system::Module<T>>::verify_confidential_proof(
                 &zkproof,
                 &issuer,
                 &issuer,
-                &total,
-                &total,
+                &amount,
+                &amount,
                 &balance,
                 &rvk,
                 &fee,
                 &randomness,
                 &nonce
-            )? {
-                Self::deposit_event(RawEvent::InvalidZkProof());
-                return Err("Invalid zkproof");
+            ) {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                if e == VerificationError::InvalidProof {
+                    Self::deposit_event(RawEvent::InvalidZkProof());
+                }
+                return Err(e.into());
             }
 
             // Add a nonce into the nonce pool
-            <zk_system::Module<T>>::nonce_pool().push(nonce);
+            <zk_system::Module<T>>::commit_nonce(ticket);
 
-            let id = Self::next_asset_id();
-            <NextAssetId<T>>::mutate(|id| *id += One::one());
+            if !<EncryptedBalance<T>>::exists((asset_id, issuer)) {
+                <HolderCount<T>>::mutate(asset_id, |count| *count += 1);
+            }
+            <EncryptedBalance<T>>::insert((asset_id, issuer), new_balance);
+            <TotalSupply<T>>::insert(asset_id, new_total_supply);
 
-            let total_ciphertext = Ciphertext::from_left_right(total, randomness)
-                .map_err(|_| "Faild to create ciphertext from left and right.")?;
-            <EncryptedBalance<T>>::insert((id, issuer.clone()), total_ciphertext.clone());
-            <TotalSupply<T>>::insert(id, total_ciphertext.clone());
+            Self::deposit_event(RawEvent::Minted(asset_id, issuer, amount_ciphertext));
+        }
 
-            Self::deposit_event(RawEvent::Issued(id, issuer, total_ciphertext));
+        /// Set the name, the symbol and the decimals of an asset, replacing the existing ones.
+        /// Only the issuer recorded by `issue` can set them;
+        /// `signature` is made with the issuer's decryption key over `metadata_message`.
+        fn set_metadata(
+            origin,
+            asset_id: T::AssetId,
+            name: Vec<u8>,
+            symbol: Vec<u8>,
+            decimals: u8,
+            signature: RedjubjubSignature
+        ) {
+            ensure_signed(origin)?;
+
+            ensure!(name.len() <= MAX_NAME_LENGTH, "Asset name is too long.");
+            ensure!(symbol.len() <= MAX_SYMBOL_LENGTH, "Asset symbol is too long.");
+            ensure!(decimals <= MAX_DECIMALS, "Too many decimals.");
+
+            let issuer = Self::issuer(asset_id).ok_or("The asset has no recorded issuer.")?;
+            let msg = Self::metadata_message(&asset_id, &name, &symbol, decimals);
+            ensure!(Self::verify_issuer_signature(&issuer, &msg[..], &signature)?, "Invalid signature of the issuer.");
+
+            <AssetMetadata<T>>::insert(asset_id, Metadata {
+                name: name.clone(),
+                symbol: symbol.clone(),
+                decimals,
+                issuer,
+            });
+            <MetadataNonce<T>>::mutate(asset_id, |n| *n += 1);
+
+            Self::deposit_event(RawEvent::MetadataSet(asset_id, name, symbol, decimals));
+        }
+
+        /// Issue a new class of encrypted fungible assets like `issue`, but to `asset_key`, a one-time key
+        /// derived from a fresh seed for this asset, so that neither the call nor the storage names the issuer's account.
+        /// The `IssuedWithHiddenIssuer` event carries `issuer_commitment`, expected to be
+        /// `issuer_commitment(issuer, asset_key, blinding)`, and `issuer_hint`, the issuer key and the blinding
+        /// encrypted to the holders, so that they can recover and check the issuer.
+        /// `asset_key` can't hold a native encrypted balance, and it's recorded as the issuer which can mint the asset.
+        fn issue_with_hidden_issuer(
+            origin,
+            zkproof: Proof,
+            asset_key: EncKey,
+            total: LeftCiphertext,
+            fee: LeftCiphertext,
+            balance: Ciphertext,
+            randomness: RightCiphertext,
+            nonce: Nonce,
+            issuer_commitment: T::Hash,
+            issuer_hint: Vec<u8>
+        ) {
+            let rvk = ensure_signed(origin)?;
+
+            ensure!(issuer_hint.len() <= MAX_ISSUER_HINT_LENGTH, "Issuer hint is too long.");
+            // A key which already holds a native balance would link the asset to its account.
+            ensure!(
+                <encrypted_balances::Module<T>>::encrypted_balance(asset_key).is_none(),
+                "The asset key must be a one-time key without an encrypted balance."
+            );
+
+            let (id, total_ciphertext) = Self::issue_asset(
+                &rvk, &zkproof, &asset_key, &total, &fee, &balance, &randomness, &nonce
+            )?;
+            <Issuer<T>>::insert(id, asset_key);
+
+            Self::deposit_event(RawEvent::IssuedWithHiddenIssuer(id, issuer_commitment, issuer_hint, total_ciphertext));
         }
 
         /// Move some encrypted assets from one holder to another.
+        /// `fee_proof` proves that `fee_sender` is the fee of the asset's fee policy.
+        /// A memo encrypted to the recipient is carried in the `Memo` event following the transfer, as in encrypted-balances.
         fn confidential_transfer(
             origin,
             asset_id: T::AssetId,
@@ -93,10 +288,19 @@ decl_module! {
             amount_recipient: LeftCiphertext,
             fee_sender: LeftCiphertext,
             randomness: RightCiphertext,
-            nonce: Nonce
+            nonce: Nonce,
+            fee_proof: Vec<u8>,
+            memo: Option<Memo>
         ) {
             let rvk = ensure_signed(origin)?;
 
+            // Reject malformed ciphertexts and a fee other than the policy's before any storage mutation.
+            Ciphertext::from_left_right(amount_sender, randomness)?;
+            Ciphertext::from_left_right(amount_recipient, randomness)?;
+            let enc_fee = Ciphertext::from_left_right(fee_sender, randomness)?;
+            let fee = <T as encrypted_balances::Trait>::ConfidentialFee::asset_fee(&asset_id);
+            <encrypted_balances::Module<T>>::check_fee(&address_sender, &fee_sender, &randomness, fee, &fee_proof[..])?;
+
             // Rollover and get sender's balance.
             // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
             // No problem if errors occur after this function because
@@ -109,11 +313,11 @@ decl_module! {
             // it just rollover user's own `pending trasfer` to `encrypted balances`.
             Self::rollover(&address_recipient, asset_id)?;
 
-            // Veridate the provided nonce isn't included in the nonce pool.
-            ensure!(!<zk_system::Module<T>>::nonce_pool().contains(&nonce), "Provided nonce is already included in the nonce pool.");
+            // Reserve the provided nonce, which fails if it's included in the nonce pool.
+            let ticket = <zk_system::Module<T>>::reserve_nonce(nonce)?;
 
             // Verify the zk proof
-            if !<zk_system::Module<T>>::verify_confidential_proof(
+            if let Err(e) = <zk_system::Module<T>>::verify_confidential_proof(
                 &zkproof,
                 &address_sender,
                 &address_recipient,
@@ -124,13 +328,16 @@ decl_module! {
                 &fee_sender,
                 &randomness,
                 &nonce
-            )? {
-                Self::deposit_event(RawEvent::InvalidZkProof());
-                return Err("Invalid zkproof");
+            ) {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                if e == VerificationError::InvalidProof {
+                    Self::deposit_event(RawEvent::InvalidZkProof());
+                }
+                return Err(e.into());
             }
 
             // Add a nonce into the nonce pool
-            <zk_system::Module<T>>::nonce_pool().push(nonce);
+            <zk_system::Module<T>>::commit_nonce(ticket);
 
             // Subtracting transferred amount and fee from the sender's encrypted balances.
             // This function causes a storage mutation.
@@ -140,8 +347,7 @@ decl_module! {
                 &amount_sender,
                 &fee_sender,
                 &randomness
-            )
-            .map_err(|_| "Faild to subtract amount from sender's balance.")?;
+            )?;
 
             // Adding transferred amount to the recipient's pending transfer.
             // This function causes a storage mutation.
@@ -150,8 +356,10 @@ decl_module! {
                 asset_id,
                 &amount_recipient,
                 &randomness
-            )
-            .map_err(|_| "Faild to add amount to recipient's pending_transfer.")?;
+            )?;
+
+            <zk_stats::Module<T>>::note_confidential_transfer(&[address_sender, address_recipient], &enc_fee);
+            <zk_stats::Module<T>>::note_fee_charged(fee);
 
             Self::deposit_event(
                 RawEvent::ConfidentialAssetTransferred(
@@ -161,9 +369,80 @@ decl_module! {
                     rvk
                 )
             );
+            if let Some(memo) = memo {
+                Self::deposit_event(RawEvent::Memo(asset_id, address_recipient, memo));
+            }
+        }
+
+        /// Burn `amount_holder` of the holder's encrypted assets and reduce the total supply by the same amount.
+        /// The zk proof is a confidential transfer from the holder to the issuer, which proves that
+        /// `amount_holder` and `amount_issuer` encrypt the same amount under the holder's and the issuer's keys,
+        /// and that it doesn't exceed the holder's balance. `TotalSupply` is encrypted under the issuer's key,
+        /// so `amount_issuer` is subtracted from it, while nothing is added to the issuer's balance.
+        fn burn(
+            origin,
+            asset_id: T::AssetId,
+            zkproof: Proof,
+            holder: EncKey,
+            amount_holder: LeftCiphertext,
+            amount_issuer: LeftCiphertext,
+            fee: LeftCiphertext,
+            randomness: RightCiphertext,
+            nonce: Nonce
+        ) {
+            let rvk = ensure_signed(origin)?;
+
+            let issuer = Self::issuer(asset_id).ok_or("The asset has no recorded issuer.")?;
+
+            // Reject malformed ciphertexts and compute the new supply before any storage mutation.
+            Ciphertext::from_left_right(amount_holder, randomness)?;
+            Ciphertext::from_left_right(fee, randomness)?;
+            let burned = Ciphertext::from_left_right(amount_issuer, randomness)?;
+            let new_total_supply = <TotalSupply<T>>::get(asset_id).sub(&burned)
+                .map_err(|_| "Faild to subtract amount from total supply.")?;
+
+            // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
+            // No problem if errors occur after this function because
+            // it just rollover user's own `pending trasfer` to `encrypted balances`.
+            Self::rollover(&holder, asset_id)?;
+
+            // Reserve the provided nonce, which fails if it's included in the nonce pool.
+            let ticket = <zk_system::Module<T>>::reserve_nonce(nonce)?;
+
+            // Verify the zk proof
+            // 1. Spend authority verification
+            // 2. Range check of the remaining balance
+            // 3. Encryption integrity of the burned amount under both keys
+            if let Err(e) = <zk_system::Module<T>>::verify_confidential_proof(
+                &zkproof,
+                &holder,
+                &issuer,
+                &amount_holder,
+                &amount_issuer,
+                &Self::encrypted_balance((asset_id, holder)).map_or(Ciphertext::zero(), |e| e),
+                &rvk,
+                &fee,
+                &randomness,
+                &nonce
+            ) {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                if e == VerificationError::InvalidProof {
+                    Self::deposit_event(RawEvent::InvalidZkProof());
+                }
+                return Err(e.into());
+            }
+
+            // Add a nonce into the nonce pool
+            <zk_system::Module<T>>::commit_nonce(ticket);
+
+            Self::sub_enc_balance(&holder, asset_id, &amount_holder, &fee, &randomness)?;
+            <TotalSupply<T>>::insert(asset_id, new_total_supply);
+
+            Self::deposit_event(RawEvent::Burned(asset_id, holder, burned));
         }
 
         /// Destroy any encrypted assets of `id` owned by `owner`.
+        /// The total supply isn't reduced, since the destroyed amount is unknown; use `burn` to keep it accurate.
         fn destroy(
             origin,
             zkproof: Proof,
@@ -181,12 +460,12 @@ decl_module! {
             let current_epoch = <zk_system::Module<T>>::get_current_epoch();
             <zk_system::Module<T>>::init_nonce_pool(current_epoch);
 
-            // Veridate the provided nonce isn't included in the nonce pool.
-            ensure!(!<zk_system::Module<T>>::nonce_pool().contains(&nonce), "Provided nonce is already included in the nonce pool.");
+            // Reserve the provided nonce, which fails if it's included in the nonce pool.
+            let ticket = <zk_system::Module<T>>::reserve_nonce(nonce)?;
 
             // Verify the zk proof
             // 1. Spend authority verification
-            if !<zk_system::Module<T>>::verify_confidential_proof(
+            if let Err(e) = <zk_system::Module<T>>::verify_confidential_proof(
                 &zkproof,
                 &owner,
                 &owner,
@@ -197,22 +476,37 @@ decl_module! {
                 &dummy_fee,
                 &randomness,
                 &nonce
-            )? {
-                Self::deposit_event(RawEvent::InvalidZkProof());
-                return Err("Invalid zkproof");
+            ) {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                if e == VerificationError::InvalidProof {
+                    Self::deposit_event(RawEvent::InvalidZkProof());
+                }
+                return Err(e.into());
             }
 
             // Add a nonce into the nonce pool
-            <zk_system::Module<T>>::nonce_pool().push(nonce);
+            <zk_system::Module<T>>::commit_nonce(ticket);
 
+            if <EncryptedBalance<T>>::exists((id, owner)) {
+                <HolderCount<T>>::mutate(id, |count| *count = count.saturating_sub(1));
+            }
             let balance = <EncryptedBalance<T>>::take((id, owner.clone()))
                 .map_or(Default::default(), |e| e);
 
-            let pending_transfer = <PendingTransfer<T>>::take((id, owner.clone()))
+            let pending_transfer = Self::take_pending_transfers(&owner, id)
                 .map_or(Default::default(), |e| e);
 
             Self::deposit_event(RawEvent::Destroyed(id, owner, balance, pending_transfer));
         }
+
+        /// Roll over the matured pending transfers of an asset of any account into its balance without a transfer.
+        /// Anyone can call it, as in `encrypted_balances::force_rollover`.
+        fn force_rollover(origin, asset_id: T::AssetId, addr: EncKey) {
+            ensure_signed(origin)?;
+
+            Self::rollover(&addr, asset_id)?;
+            Self::deposit_event(RawEvent::RolledOver(asset_id, addr));
+        }
     }
 }
 
@@ -220,28 +514,39 @@ decl_event!(
     pub enum Event<T>
     where
         <T as Trait>::AssetId,
-        <T as system::Trait>::AccountId
+        <T as system::Trait>::AccountId,
+        <T as system::Trait>::Hash
     {
         /// Some encrypted assets were issued.
         Issued(AssetId, EncKey, Ciphertext),
+        /// Some encrypted assets were issued to a one-time key by an issuer hidden behind a commitment,
+        /// along with the encrypted hint to recover the issuer.
+        IssuedWithHiddenIssuer(AssetId, Hash, Vec<u8>, Ciphertext),
+        /// Some encrypted assets were minted to the issuer.
+        Minted(AssetId, EncKey, Ciphertext),
         /// Some encrypted assets were transferred.
         ConfidentialAssetTransferred(
             AssetId, Proof, EncKey, EncKey, LeftCiphertext,
             LeftCiphertext, LeftCiphertext, RightCiphertext, Ciphertext, AccountId
         ),
+        /// The memo encrypted to the recipient, following a transfer of the asset to the recipient.
+        Memo(AssetId, EncKey, Memo),
+        /// Some encrypted assets were burned by the holder, with the amount encrypted under the issuer's key.
+        Burned(AssetId, EncKey, Ciphertext),
         /// Some encrypted assets were destroyed.
         Destroyed(AssetId, EncKey, Ciphertext, Ciphertext),
+        /// The name, the symbol and the decimals of an asset were set by the issuer.
+        MetadataSet(AssetId, Vec<u8>, Vec<u8>, u8),
         InvalidZkProof(),
+        /// The account was rolled over by `force_rollover`.
+        RolledOver(AssetId, EncKey),
     }
 );
 
 decl_storage! {
     trait Store for Module<T: Trait> as EncryptedAssets {
-        /// An encrypted balance for each account
-        pub EncryptedBalance get(encrypted_balance) config() : map (T::AssetId, EncKey) => Option<Ciphertext>;
-
-        /// A pending transfer
-        pub PendingTransfer get(pending_transfer) : map (T::AssetId, EncKey) => Option<Ciphertext>;
+        /// The epochs of the pending transfer buckets of each account in ascending order.
+        pub PendingEpochs get(pending_epochs) : map (T::AssetId, EncKey) => Vec<T::BlockNumber>;
 
         /// A last epoch for rollover
         pub LastRollOver get(last_rollover) config() : map (T::AssetId, EncKey) => Option<T::BlockNumber>;
@@ -249,12 +554,92 @@ decl_storage! {
         /// The next asset identifier up for grabs.
         pub NextAssetId get(next_asset_id): T::AssetId;
 
-        /// The total unit supply of an asset.
+        /// The total unit supply of an asset, encrypted under the issuer's key.
         pub TotalSupply: map T::AssetId => Ciphertext;
+
+        /// The issuer of an asset, who is allowed to mint it.
+        pub Issuer get(issuer): map T::AssetId => Option<EncKey>;
+
+        /// The name, the symbol and the decimals of an asset.
+        pub AssetMetadata get(asset_metadata): map T::AssetId => Option<Metadata>;
+
+        /// The number of times the metadata of an asset was set, so that a signature can't be replayed.
+        pub MetadataNonce get(metadata_nonce): map T::AssetId => u64;
+
+        /// The number of pending transfer buckets of an asset which are not rolled over yet.
+        pub TotalPendingCount get(total_pending_count): map T::AssetId => u64;
+
+        /// The number of accounts which have an encrypted balance of an asset.
+        pub HolderCount get(holder_count) build(|config: &GenesisConfig<T>| {
+            let mut counts: Vec<(T::AssetId, u64)> = vec![];
+            for ((asset_id, _), _) in &config.encrypted_balance {
+                match counts.iter_mut().find(|(id, _)| id == asset_id) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((*asset_id, 1)),
+                }
+            }
+            counts
+        }): map T::AssetId => u64;
     }
+    add_extra_genesis {
+        config(encrypted_balance): Vec<((T::AssetId, EncKey), Ciphertext)>;
+
+        build(|_: &mut runtime_primitives::StorageOverlay, children: &mut runtime_primitives::ChildrenStorageOverlay, config: &GenesisConfig<T>| {
+            <EncryptedBalance<T>>::build_genesis(children, &config.encrypted_balance);
+        });
+    }
+}
+
+/// An encrypted balance of an asset for each account, in the child trie of the pool.
+pub struct EncryptedBalance<T>(rstd::marker::PhantomData<T>);
+
+impl<T: Trait> PoolMap for EncryptedBalance<T> {
+    type Key = (T::AssetId, EncKey);
+    type Value = Ciphertext;
+    const POOL: Pool = Pool::EncryptedAssets;
+    const PREFIX: &'static [u8] = b"EncryptedBalance";
+}
+
+/// Pending transfers bucketed by the epoch in which they were received, in the child trie of the pool.
+/// They are rolled over into the balance at the first rollover after the epoch.
+pub struct PendingTransfer<T>(rstd::marker::PhantomData<T>);
+
+impl<T: Trait> PoolMap for PendingTransfer<T> {
+    type Key = (T::AssetId, EncKey, T::BlockNumber);
+    type Value = Ciphertext;
+    const POOL: Pool = Pool::EncryptedAssets;
+    const PREFIX: &'static [u8] = b"PendingTransfer";
 }
 
 impl<T: Trait> Module<T> {
+    // PUBLIC IMMUTABLES
+
+    /// The encrypted balance of the asset of the account.
+    pub fn encrypted_balance<K: Borrow<(T::AssetId, EncKey)>>(key: K) -> Option<Ciphertext> {
+        <EncryptedBalance<T>>::get(key)
+    }
+
+    /// The pending transfers of the asset to the account received in the epoch.
+    pub fn pending_transfer<K: Borrow<(T::AssetId, EncKey, T::BlockNumber)>>(key: K) -> Option<Ciphertext> {
+        <PendingTransfer<T>>::get(key)
+    }
+
+    /// The commitment to the issuer's encryption key emitted by `issue_with_hidden_issuer`,
+    /// bound to the one-time key of the asset so that it can't be claimed for another asset.
+    /// Holders recompute it from the issuer key and the blinding in the issuer hint.
+    pub fn issuer_commitment(issuer: &EncKey, asset_key: &EncKey, blinding: &[u8; 32]) -> T::Hash {
+        let mut buf = ISSUER_COMMITMENT_CONTEXT.to_vec();
+        (issuer, asset_key, blinding).encode_to(&mut buf);
+        T::Hashing::hash(&buf[..])
+    }
+
+    /// The message the issuer signs to set the metadata of an asset.
+    pub fn metadata_message(asset_id: &T::AssetId, name: &[u8], symbol: &[u8], decimals: u8) -> Vec<u8> {
+        let mut msg = METADATA_CONTEXT.to_vec();
+        (asset_id, name, symbol, decimals, Self::metadata_nonce(asset_id)).encode_to(&mut msg);
+        msg
+    }
+
     // PUBLIC MUTABLES
 
     /// Rolling over allows us to send transactions asynchronously and protect from front-running attacks.
@@ -263,37 +648,51 @@ impl<T: Trait> Module<T> {
     /// To achieve this, we define a separate (internal) method for rolling over,
     /// and the first thing every other method does is to call this method.
     /// More details in Section 3.1: https://crypto.stanford.edu/~buenz/papers/zether.pdf
-    pub fn rollover(addr: &EncKey, asset_id: T::AssetId) -> Result<(), &'static str> {
+    pub fn rollover(addr: &EncKey, asset_id: T::AssetId) -> result::Result<(), Error> {
         let current_epoch = <zk_system::Module<T>>::get_current_epoch();
         let addr_id = (asset_id, *addr);
 
         let last_rollover = Self::last_rollover(addr_id)
             .map_or(T::BlockNumber::zero(), |e| e);
 
-        // Get current pending transfer
-        let enc_pending_transfer = Self::pending_transfer(addr_id)
-            .map_or(Ciphertext::zero(), |e| e);
+        // Buckets received in past epochs have matured; the bucket of the current epoch stays pending.
+        let pending_epochs = Self::pending_epochs(addr_id);
+        let matured = pending_epochs.iter().take_while(|e| **e < current_epoch).count();
 
-        // Checks if the last roll over was in an older epoch.
+        // Checks if the last roll over was in an older epoch or some pending transfers have matured.
         // If so, some storage changes are happend here.
-        if last_rollover < current_epoch {
-            // transfer balance from pending_transfer to actual balance
-            <EncryptedBalance<T>>::mutate(addr_id, |balance| {
-                let new_balance = match balance.clone() {
-                    Some(b) => b.add(&enc_pending_transfer),
-                    None => Ok(enc_pending_transfer),
-                };
-
-                match new_balance {
-                    Ok(nb) => *balance = Some(nb),
-                    Err(_) => return Err("Faild to mutate encrypted balance."),
+        if last_rollover < current_epoch || matured > 0 {
+            let mut enc_pending_transfer = Ciphertext::zero();
+            for epoch in &pending_epochs[..matured] {
+                if let Some(p) = Self::pending_transfer((asset_id, *addr, *epoch)) {
+                    enc_pending_transfer = enc_pending_transfer.add(&p)
+                        .map_err(|_| Error::BalanceMutation)?;
                 }
+            }
 
-                Ok(())
-            })?;
+            // transfer balance from the matured pending transfers to actual balance
+            let current_balance = Self::encrypted_balance(addr_id);
+            let new_balance = match current_balance {
+                Some(b) => b.add(&enc_pending_transfer),
+                None => Ok(enc_pending_transfer),
+            }
+            .map_err(|_| Error::BalanceMutation)?;
+            if current_balance.is_none() {
+                <HolderCount<T>>::mutate(asset_id, |count| *count += 1);
+            }
+            <EncryptedBalance<T>>::insert(addr_id, new_balance);
+
+            // Prune the matured buckets.
+            for epoch in &pending_epochs[..matured] {
+                <PendingTransfer<T>>::remove((asset_id, *addr, *epoch));
+            }
+            <TotalPendingCount<T>>::mutate(asset_id, |count| *count = count.saturating_sub(matured as u64));
+            if matured == pending_epochs.len() {
+                <PendingEpochs<T>>::remove(addr_id);
+            } else {
+                <PendingEpochs<T>>::insert(addr_id, pending_epochs[matured..].to_vec());
+            }
 
-            // Reset pending_transfer.
-            <PendingTransfer<T>>::remove(addr_id);
             // Set last rollover to current epoch.
             <LastRollOver<T>>::insert(addr_id, current_epoch);
         }
@@ -303,6 +702,64 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
+    /// Verify the zk proof of issuing and create the new asset class owned by the issuer.
+    fn issue_asset(
+        rvk: &T::AccountId,
+        zkproof: &Proof,
+        issuer: &EncKey,
+        total: &LeftCiphertext,
+        fee: &LeftCiphertext,
+        balance: &Ciphertext,
+        randomness: &RightCiphertext,
+        nonce: &Nonce
+    ) -> result::Result<(T::AssetId, Ciphertext), &'static str> {
+        // Reject malformed ciphertexts before any storage mutation.
+        let total_ciphertext = Ciphertext::from_left_right(*total, *randomness)?;
+        Ciphertext::from_left_right(*fee, *randomness)?;
+
+        // Initialize a nonce pool
+        let current_epoch = <zk_system::Module<T>>::get_current_epoch();
+        <zk_system::Module<T>>::init_nonce_pool(current_epoch);
+
+        // Reserve the provided nonce, which fails if it's included in the nonce pool.
+        let ticket = <zk_system::Module<T>>::reserve_nonce(*nonce)?;
+
+        // Verify a zk proof
+        // 1. Spend authority verification
+        // 2. Range check of issued amount
+        // 3. Encryption integrity
+        if let Err(e) = <zk_system::Module<T>>::verify_confidential_proof(
+            zkproof,
+            issuer,
+            issuer,
+            total,
+            total,
+            balance,
+            rvk,
+            fee,
+            randomness,
+            nonce
+        ) {
+            <zk_system::Module<T>>::abort_nonce(ticket);
+            if e == VerificationError::InvalidProof {
+                Self::deposit_event(RawEvent::InvalidZkProof());
+            }
+            return Err(e.into());
+        }
+
+        // Add a nonce into the nonce pool
+        <zk_system::Module<T>>::commit_nonce(ticket);
+
+        let id = Self::next_asset_id();
+        <NextAssetId<T>>::mutate(|id| *id += One::one());
+
+        <EncryptedBalance<T>>::insert((id, *issuer), total_ciphertext.clone());
+        <HolderCount<T>>::insert(id, 1);
+        <TotalSupply<T>>::insert(id, total_ciphertext.clone());
+
+        Ok((id, total_ciphertext))
+    }
+
     // Subtracting transferred amount and fee from encrypted balances.
     pub fn sub_enc_balance(
         address: &EncKey,
@@ -310,13 +767,11 @@ impl<T: Trait> Module<T> {
         amount: &LeftCiphertext,
         fee: &LeftCiphertext,
         randomness: &RightCiphertext
-    ) -> result::Result<(), &'static str> {
-        let enc_amount = Ciphertext::from_left_right(*amount, *randomness)
-            .map_err(|_| "Faild to create amount ciphertext.")?;
-        let enc_fee = Ciphertext::from_left_right(*fee, *randomness)
-            .map_err(|_| "Faild to create fee ciphertext.")?;
+    ) -> result::Result<(), Error> {
+        let enc_amount = Ciphertext::from_left_right(*amount, *randomness)?;
+        let enc_fee = Ciphertext::from_left_right(*fee, *randomness)?;
         let amount_plus_fee = enc_amount.add(&enc_fee)
-            .map_err(|_| "Failed to add fee to amount")?;
+            .map_err(|_| Error::FeeAddition)?;
 
         <EncryptedBalance<T>>::mutate((asset_id, *address), |balance| {
             let new_balance = balance.clone()
@@ -330,33 +785,78 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
-    /// Adding transferred amount to pending transfer.
+    /// Adding transferred amount to the pending transfer bucket of the current epoch.
     pub fn add_pending_transfer(
         address: &EncKey,
         asset_id: T::AssetId,
         amount: &LeftCiphertext,
         randomness: &RightCiphertext
-    ) -> result::Result<(), &'static str> {
-        let enc_amount = Ciphertext::from_left_right(*amount, *randomness)
-            .map_err(|_| "Faild to create amount ciphertext.")?;
-
-        <PendingTransfer<T>>::mutate((asset_id, *address), |pending_transfer| {
-            let new_pending_transfer = match pending_transfer.clone() {
-                Some(p) => p.add(&enc_amount),
-                None => Ok(enc_amount),
-            };
+    ) -> result::Result<(), Error> {
+        let enc_amount = Ciphertext::from_left_right(*amount, *randomness)?;
+        let current_epoch = <zk_system::Module<T>>::get_current_epoch();
+
+        let new_pending_transfer = match Self::pending_transfer((asset_id, *address, current_epoch)) {
+            Some(p) => p.add(&enc_amount).map_err(|_| Error::PendingTransferMutation)?,
+            None => enc_amount,
+        };
+        Self::set_pending_transfer(address, asset_id, new_pending_transfer);
 
-            match new_pending_transfer {
-                Ok(np) => *pending_transfer = Some(np),
-                Err(_) => return Err("Faild to mutate pending transfer.")
+        Ok(())
+    }
+
+    /// Replace the pending transfer of the current epoch to the account with one computed by the caller,
+    /// e.g. staged with the other mutations of a transfer. Nothing here can fail.
+    pub fn set_pending_transfer(address: &EncKey, asset_id: T::AssetId, pending_transfer: Ciphertext) {
+        let current_epoch = <zk_system::Module<T>>::get_current_epoch();
+
+        <PendingTransfer<T>>::insert((asset_id, *address, current_epoch), pending_transfer);
+        <PendingEpochs<T>>::mutate((asset_id, *address), |epochs| {
+            if epochs.last() != Some(&current_epoch) {
+                epochs.push(current_epoch);
+                <TotalPendingCount<T>>::mutate(asset_id, |count| *count += 1);
             }
+        });
+    }
 
-            Ok(())
-        })?;
+    /// The sum of all the pending transfer buckets of the account, matured or not.
+    pub fn total_pending_transfer(address: &EncKey, asset_id: T::AssetId) -> Option<Ciphertext> {
+        Self::pending_epochs((asset_id, *address))
+            .iter()
+            .filter_map(|epoch| Self::pending_transfer((asset_id, *address, *epoch)))
+            .fold(None, |acc, p| match acc {
+                Some(a) => a.add(&p).ok(),
+                None => Some(p),
+            })
+    }
 
-        Ok(())
+    /// Remove all the pending transfer buckets of the account, returning their sum.
+    fn take_pending_transfers(address: &EncKey, asset_id: T::AssetId) -> Option<Ciphertext> {
+        let epochs = <PendingEpochs<T>>::take((asset_id, *address));
+        <TotalPendingCount<T>>::mutate(asset_id, |count| *count = count.saturating_sub(epochs.len() as u64));
+
+        epochs
+            .iter()
+            .filter_map(|epoch| <PendingTransfer<T>>::take((asset_id, *address, *epoch)))
+            .fold(None, |acc, p| match acc {
+                Some(a) => a.add(&p).ok(),
+                None => Some(p),
+            })
     }
 
+    // An EncKey is the decryption key times the generator,
+    // so the issuer signs with the decryption key as a RedDSA private key.
+    fn verify_issuer_signature(
+        issuer: &EncKey,
+        msg: &[u8],
+        signature: &RedjubjubSignature
+    ) -> result::Result<bool, &'static str> {
+        let issuer = redjubjub::PublicKey::<Bls12>::read(&mut issuer.as_ref(), &*PARAMS)
+            .map_err(|_| "Invalid encryption key.")?;
+        let sig = redjubjub::Signature::try_from(*signature)
+            .map_err(|_| "Invalid signature format.")?;
+
+        Ok(issuer.verify(msg, &sig, FixedGenerators::Diversifier, &*PARAMS))
+    }
 }
 
 #[cfg(feature = "std")]
@@ -371,9 +871,8 @@ mod tests {
         testing::{Digest, DigestItem, Header}
     };
     use zprimitives::{Ciphertext, SigVerificationKey};
-    use keys::{ProofGenerationKey, EncryptionKey};
-    use jubjub::{curve::{JubjubBls12, FixedGenerators, fs}};
-    use pairing::{Field, bls12_381::Bls12};
+    use jubjub::{curve::{JubjubBls12, JubjubParams, FixedGenerators, fs}};
+    use pairing::{PrimeField, bls12_381::Bls12};
     use hex_literal::{hex, hex_impl};
     use rand::{SeedableRng, XorShiftRng};
     use test_pairing::{bls12_381::Bls12 as tBls12, Field as tField};
@@ -381,17 +880,8 @@ mod tests {
         elgamal as telgamal, PARAMS, MultiEncKeys, KeyContext, ProofBuilder, Confidential,
     };
     use scrypto::jubjub::{FixedGenerators as tFixedGenerators, fs::Fs as tFs, edwards as tedwards, PrimeOrder};
-    use zcrypto::elgamal;
-    use bellman_verifier::PreparedVerifyingKey;
-    use std::{
-        path::Path,
-        fs::File,
-        io::{BufReader, Read},
-        convert::TryFrom,
-    };
-
-    const PK_PATH: &str = "../../zface/params/test_conf_pk.dat";
-    const VK_PATH: &str = "../../zface/params/test_conf_vk.dat";
+    use test_fixtures::{ALICE_SEED, CONF_PK, CONF_VK, params_path, conf_vk, anony_vk, alice_genesis};
+    use std::convert::TryFrom;
 
     impl_outer_origin! {
         pub enum Origin for Test {}
@@ -420,92 +910,46 @@ mod tests {
 
     impl encrypted_balances::Trait for Test {
         type Event = ();
+        type ConfidentialFee = encrypted_balances::FlatFee<Test>;
     }
 
-    impl zk_system::Trait for Test { }
-
-    impl Trait for Test {
+    impl zk_system::Trait for Test {
         type Event = ();
-        type AssetId = u64;
-    }
-
-    type EncryptedAssets = Module<Test>;
-
-    fn alice_balance_init() -> (EncKey, Ciphertext) {
-        let (alice_seed, enc_key) = get_alice_seed_ek();
-        let alice_amount = 100 as u32;
-        let params = &JubjubBls12::new();
-        let p_g = FixedGenerators::Diversifier; // 1 same as NoteCommitmentRandomness;
-
-        // The default balance is not encrypted with randomness.
-        let enc_alice_bal = elgamal::Ciphertext::encrypt(
-            alice_amount,
-            &fs::Fs::one(),
-            &enc_key,
-            p_g,
-            params
-        );
-
-        let decryption_key = ProofGenerationKey::<Bls12>::from_seed(&alice_seed[..], params).into_decryption_key().unwrap();
-
-        let dec_alice_bal = enc_alice_bal.decrypt(&decryption_key, p_g, params).unwrap();
-        assert_eq!(dec_alice_bal, alice_amount);
-
-        (EncKey::try_from(enc_key).unwrap(), Ciphertext::try_from(enc_alice_bal).unwrap())
-    }
-
-    fn alice_epoch_init() -> (EncKey, u64) {
-        let (_, enc_key) = get_alice_seed_ek();
-
-        (EncKey::try_from(enc_key).unwrap(), 0)
+        type Log = DigestItem;
     }
 
-    fn get_alice_seed_ek() -> (Vec<u8>, EncryptionKey<Bls12>) {
-        let params = &JubjubBls12::new();
-        let alice_seed = b"Alice                           ".to_vec();
+    impl zk_stats::Trait for Test { }
 
-        (alice_seed.clone(), EncryptionKey::<Bls12>::from_seed(&alice_seed[..], params)
-            .expect("should be generated encryption key from seed."))
+    impl zk_auditor::Trait for Test {
+        type Event = ();
     }
 
-    pub fn get_conf_vk() -> PreparedVerifyingKey<Bls12> {
-        let vk_path = Path::new("../../zface/params/test_conf_vk.dat");
-        let vk_file = File::open(&vk_path).unwrap();
-        let mut vk_reader = BufReader::new(vk_file);
-
-        let mut buf_vk = vec![];
-        vk_reader.read_to_end(&mut buf_vk).unwrap();
-
-        PreparedVerifyingKey::<Bls12>::read(&mut &buf_vk[..]).unwrap()
+    impl Trait for Test {
+        type Event = ();
+        type AssetId = u64;
     }
 
-    pub fn get_anony_vk() -> PreparedVerifyingKey<Bls12> {
-        let vk_path = Path::new("../../zface/params/test_anony_vk.dat");
-        let vk_file = File::open(&vk_path).unwrap();
-        let mut vk_reader = BufReader::new(vk_file);
-
-        let mut buf_vk = vec![];
-        vk_reader.read_to_end(&mut buf_vk).unwrap();
-
-        PreparedVerifyingKey::<Bls12>::read(&mut &buf_vk[..]).unwrap()
-    }
+    type EncryptedAssets = Module<Test>;
 
     fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
-        let balance_init = alice_balance_init();
-        let epoch_init = alice_epoch_init();
+        let (balance_init, epoch_init) = alice_genesis();
 
         let (mut t, mut c) = system::GenesisConfig::<Test>::default().build_storage().unwrap();
         let _ = zk_system::GenesisConfig::<Test>{
             last_epoch: 1,
             epoch_length: 1,
-            confidential_vk: get_conf_vk(),
-            anonymous_vk: get_anony_vk(),
-            nonce_pool: vec![],
+            max_anonymity_set_size: 12,
+            nonce_retention_epochs: 0,
+            confidential_vk: conf_vk(),
+            anonymous_vk: anony_vk(),
+            confidential_pk_hash: [0u8; 32],
+            anonymous_pk_hash: [0u8; 32],
         }.assimilate_storage(&mut t, &mut c);
         let _ = encrypted_balances::GenesisConfig::<Test>{
             encrypted_balance: vec![balance_init.clone()],
 			last_rollover: vec![epoch_init],
             transaction_base_fee: 1,
+            anonymous_fee: 0,
             _genesis_phantom_data: Default::default()
         }.assimilate_storage(&mut t, &mut c);
         let _ = GenesisConfig::<Test>{
@@ -514,7 +958,7 @@ mod tests {
             _genesis_phantom_data: Default::default()
         }.assimilate_storage(&mut t, &mut c);
 
-        t.into()
+        zk_system::pool::test_externalities(t, c)
     }
 
     fn get_g_epoch() -> tedwards::Point<tBls12, PrimeOrder> {
@@ -526,7 +970,7 @@ mod tests {
     #[test]
     fn test_issue_from_zface() {
         with_externalities(&mut new_test_ext(), || {
-            let seed = b"Alice                           ".to_vec();
+            let seed = ALICE_SEED.to_vec();
             let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
             let p_g = tFixedGenerators::NoteCommitmentRandomness;
 
@@ -542,7 +986,7 @@ mod tests {
                 &*PARAMS
             )];
 
-            let tx = KeyContext::read_from_path(PK_PATH, VK_PATH)
+            let tx = KeyContext::read_from_path(params_path(CONF_PK), params_path(CONF_VK))
                 .unwrap()
                 .gen_proof(
                     amount,
@@ -558,8 +1002,132 @@ mod tests {
 
             // System::set_block_number(10);
 
+            let args = (
+                Proof::from_slice(&tx.proof[..]),
+                EncKey::from_slice(&tx.enc_key_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_fee[..]),
+                Ciphertext::from_slice(&tx.enc_balance[..]),
+                RightCiphertext::from_slice(&tx.right_randomness[..]),
+                Nonce::from_slice(&tx.nonce[..])
+            );
+
             assert_ok!(EncryptedAssets::issue(
                 Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..])),
+                args.0, args.1, args.2, args.3, args.4, args.5, args.6
+            ));
+            assert_eq!(EncryptedAssets::issuer(0), Some(args.1));
+            assert_eq!(EncryptedAssets::holder_count(0), 1);
+        })
+    }
+
+    #[test]
+    fn test_issue_with_hidden_issuer() {
+        with_externalities(&mut new_test_ext(), || {
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let p_g = tFixedGenerators::NoteCommitmentRandomness;
+            let (issuer, _) = alice_genesis().0;
+
+            // Issue 100 to the key of `seed`, with the hint of the given length.
+            let mut issue = |seed: &[u8], hint_len: usize| {
+                let spending_key = tSpendingKey::<tBls12>::from_seed(seed);
+                let enc_key = tEncryptionKey::from_seed(seed, &*PARAMS).unwrap();
+
+                let amount = 100;
+                let enc_balance = vec![telgamal::Ciphertext::encrypt(
+                    amount,
+                    &tFs::one(),
+                    &enc_key,
+                    p_g,
+                    &*PARAMS
+                )];
+
+                let tx = KeyContext::read_from_path(params_path(CONF_PK), params_path(CONF_VK))
+                    .unwrap()
+                    .gen_proof(
+                        amount,
+                        0,
+                        0, 0, 0,
+                        &spending_key,
+                        MultiEncKeys::<tBls12, Confidential>::new(enc_key),
+                        &enc_balance,
+                        get_g_epoch(),
+                        rng,
+                        &*PARAMS
+                    ).unwrap();
+
+                let asset_key = EncKey::from_slice(&tx.enc_key_recipient[..]);
+                let res = EncryptedAssets::issue_with_hidden_issuer(
+                    Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..])),
+                    Proof::from_slice(&tx.proof[..]),
+                    asset_key,
+                    LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                    LeftCiphertext::from_slice(&tx.left_fee[..]),
+                    Ciphertext::from_slice(&tx.enc_balance[..]),
+                    RightCiphertext::from_slice(&tx.right_randomness[..]),
+                    Nonce::from_slice(&tx.nonce[..]),
+                    EncryptedAssets::issuer_commitment(&issuer, &asset_key, &[7u8; 32]),
+                    vec![1u8; hint_len]
+                );
+                (asset_key, res)
+            };
+
+            assert_eq!(issue(&[42u8; 32], MAX_ISSUER_HINT_LENGTH + 1).1, Err("Issuer hint is too long."));
+            // Alice's own key holds a native balance, which would reveal her as the issuer.
+            assert_eq!(
+                issue(&ALICE_SEED[..], 64).1,
+                Err("The asset key must be a one-time key without an encrypted balance.")
+            );
+            assert_eq!(EncryptedAssets::next_asset_id(), 0);
+
+            let (asset_key, res) = issue(&[42u8; 32], 64);
+            assert_ok!(res);
+            assert_eq!(EncryptedAssets::next_asset_id(), 1);
+            assert_eq!(EncryptedAssets::issuer(0), Some(asset_key));
+
+            // The commitment binds both the issuer and the asset key.
+            let commitment = EncryptedAssets::issuer_commitment(&issuer, &asset_key, &[7u8; 32]);
+            assert_ne!(commitment, EncryptedAssets::issuer_commitment(&issuer, &asset_key, &[8u8; 32]));
+            assert_ne!(commitment, EncryptedAssets::issuer_commitment(&issuer, &issuer, &[7u8; 32]));
+        })
+    }
+
+    #[test]
+    fn test_mint_from_zface() {
+        with_externalities(&mut new_test_ext(), || {
+            let seed = ALICE_SEED.to_vec();
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let p_g = tFixedGenerators::NoteCommitmentRandomness;
+
+            let spending_key = tSpendingKey::<tBls12>::from_seed(&seed);
+            let enc_key = tEncryptionKey::from_seed(&seed[..], &*PARAMS).unwrap();
+
+            let amount = 100;
+            let enc_balance = vec![telgamal::Ciphertext::encrypt(
+                amount,
+                &tFs::one(),
+                &enc_key,
+                p_g,
+                &*PARAMS
+            )];
+
+            let tx = KeyContext::read_from_path(params_path(CONF_PK), params_path(CONF_VK))
+                .unwrap()
+                .gen_proof(
+                    amount,
+                    0,
+                    0, 0, 0,
+                    &spending_key,
+                    MultiEncKeys::<tBls12, Confidential>::new(enc_key),
+                    &enc_balance,
+                    get_g_epoch(),
+                    rng,
+                    &*PARAMS
+                ).unwrap();
+
+            let (issuer, genesis_balance) = alice_genesis().0;
+            let args = (
+                0,
                 Proof::from_slice(&tx.proof[..]),
                 EncKey::from_slice(&tx.enc_key_recipient[..]),
                 LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
@@ -567,14 +1135,31 @@ mod tests {
                 Ciphertext::from_slice(&tx.enc_balance[..]),
                 RightCiphertext::from_slice(&tx.right_randomness[..]),
                 Nonce::from_slice(&tx.nonce[..])
+            );
+            let origin = || Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..]));
+
+            // The genesis asset has no issuer.
+            assert_eq!(EncryptedAssets::mint(
+                origin(), args.0, args.1.clone(), args.2, args.3, args.4, args.5.clone(), args.6, args.7
+            ), Err("Only the issuer can mint the asset."));
+
+            <Issuer<Test>>::insert(0, issuer);
+            <TotalSupply<Test>>::insert(0, genesis_balance.clone());
+            assert_ok!(EncryptedAssets::mint(
+                origin(), args.0, args.1, args.2, args.3, args.4, args.5, args.6, args.7
             ));
+
+            let minted = Ciphertext::from_left_right(args.3, args.6).unwrap();
+            let expected = genesis_balance.add(&minted).unwrap();
+            assert_eq!(EncryptedAssets::encrypted_balance((0, issuer)), Some(expected.clone()));
+            assert_eq!(<TotalSupply<Test>>::get(0), expected);
         })
     }
 
     #[test]
     fn test_confidential_transfer_from_zface() {
         with_externalities(&mut new_test_ext(), || {
-            let alice_seed = b"Alice                           ".to_vec();
+            let alice_seed = ALICE_SEED.to_vec();
             let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
             let bob_addr: [u8; 32] = hex!("45e66da531088b55dcb3b273ca825454d79d2d1d5c4fa2ba4a12c1fa1ccd6389");
             let recipient_account_id = tEncryptionKey::<tBls12>::read(&mut &bob_addr[..], &PARAMS).unwrap();
@@ -598,7 +1183,7 @@ mod tests {
                 &*PARAMS
             )];
 
-            let tx = KeyContext::read_from_path(PK_PATH, VK_PATH)
+            let tx = KeyContext::read_from_path(params_path(CONF_PK), params_path(CONF_VK))
                 .unwrap()
                 .gen_proof(
                     amount,
@@ -612,25 +1197,255 @@ mod tests {
                     &*PARAMS
                 ).unwrap();
 
+            let args = (
+                0u64,
+                Proof::from_slice(&tx.proof[..]),
+                EncKey::from_slice(&tx.enc_key_sender[..]),
+                EncKey::from_slice(&tx.enc_key_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_fee[..]),
+                RightCiphertext::from_slice(&tx.right_randomness[..]),
+                Nonce::from_slice(&tx.nonce[..]),
+                tx.fee_proof.clone(),
+                Some(Memo::from_slice(&[1u8; zprimitives::memo::MEMO_SIZE]))
+            );
+
             assert_ok!(EncryptedAssets::confidential_transfer(
                 Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..])),
-                0,
+                args.0, args.1, args.2, args.3, args.4, args.5, args.6, args.7, args.8, args.9, args.10
+            ));
+        })
+    }
+
+    #[test]
+    fn test_epoch_lifecycle() {
+        use zprimitives::GEpoch;
+
+        with_externalities(&mut new_test_ext(), || {
+            let alice_seed = ALICE_SEED.to_vec();
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let bob_addr: [u8; 32] = hex!("45e66da531088b55dcb3b273ca825454d79d2d1d5c4fa2ba4a12c1fa1ccd6389");
+            let recipient_account_id = tEncryptionKey::<tBls12>::read(&mut &bob_addr[..], &PARAMS).unwrap();
+            let spending_key = tSpendingKey::<tBls12>::from_seed(&alice_seed);
+            let key_context = KeyContext::read_from_path(params_path(CONF_PK), params_path(CONF_VK)).unwrap();
+            let (alice, _) = alice_genesis().1;
+            let bob = EncKey::from_slice(&bob_addr[..]);
+
+            // Prove a transfer of 8 with the fee 1 against alice's on-chain balance in the given epoch.
+            let mut prove = |remaining_balance: u64, epoch: u32| {
+                let balance = EncryptedAssets::encrypted_balance((0, alice)).unwrap();
+                let enc_balance = vec![telgamal::Ciphertext::read(&mut balance.as_bytes(), &*PARAMS).unwrap()];
+                let g_epoch = GEpoch::group_hash(epoch).unwrap();
+                let g_epoch = tedwards::Point::read(g_epoch.as_bytes(), &*PARAMS).unwrap().as_prime_order(&*PARAMS).unwrap();
+
+                let tx = key_context.gen_proof(
+                    8,
+                    1,
+                    remaining_balance, 0, 0,
+                    &spending_key,
+                    MultiEncKeys::<tBls12, Confidential>::new(recipient_account_id.clone()),
+                    &enc_balance,
+                    g_epoch,
+                    rng,
+                    &*PARAMS
+                ).unwrap();
+
+                let args = (
+                    0u64,
+                    Proof::from_slice(&tx.proof[..]),
+                    EncKey::from_slice(&tx.enc_key_sender[..]),
+                    EncKey::from_slice(&tx.enc_key_recipient[..]),
+                    LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+                    LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                    LeftCiphertext::from_slice(&tx.left_fee[..]),
+                    RightCiphertext::from_slice(&tx.right_randomness[..]),
+                    Nonce::from_slice(&tx.nonce[..]),
+                    tx.fee_proof.clone(),
+                    None
+                );
+
+                (SigVerificationKey::from_slice(&tx.rvk[..]), args)
+            };
+
+            type Args = (u64, Proof, EncKey, EncKey, LeftCiphertext, LeftCiphertext, LeftCiphertext, RightCiphertext, Nonce, Vec<u8>, Option<Memo>);
+            fn submit((rvk, args): &(SigVerificationKey, Args)) -> support::dispatch::Result {
+                EncryptedAssets::confidential_transfer(
+                    Origin::signed(*rvk),
+                    args.0, args.1.clone(), args.2, args.3, args.4, args.5, args.6, args.7, args.8, args.9.clone(), args.10
+                )
+            }
+
+            // Epoch 0: the transfer is applied once, and replaying it in the same epoch is rejected by the nonce pool.
+            let tx_0 = prove(91, 0);
+            assert_ok!(submit(&tx_0));
+            assert_eq!(submit(&tx_0), Err("Provided nonce is already included in the nonce pool."));
+            assert!(EncryptedAssets::total_pending_transfer(&bob, 0).is_some());
+            assert_eq!(EncryptedAssets::total_pending_count(0), 1);
+            assert_eq!(EncryptedAssets::holder_count(0), 1);
+
+            // Rolling over in the same epoch leaves the transfer pending.
+            assert_ok!(EncryptedAssets::force_rollover(Origin::signed(SigVerificationKey::from_slice(&[1u8; 32])), 0, bob));
+            assert!(EncryptedAssets::total_pending_transfer(&bob, 0).is_some());
+
+            // Epoch 1: the nonce pool and g_epoch are reset and bob's pending transfer is rolled over
+            // by the first call touching his account, even if the call fails.
+            <system::Module<Test>>::set_block_number(1);
+            assert_eq!(submit(&tx_0), Err("Invalid zkproof"));
+            assert_eq!(<zk_system::Module<Test>>::last_epoch(), 1);
+            assert_eq!(<zk_system::Module<Test>>::g_epoch(), GEpoch::group_hash(1).unwrap());
+            assert!(!<zk_system::Module<Test>>::is_nonce_used(&(tx_0.1).8));
+            // The nonce reserved by the rejected transfer is released for a valid one.
+            assert!(!<zk_system::Module<Test>>::is_nonce_reserved(&(tx_0.1).8));
+            assert_eq!(EncryptedAssets::total_pending_transfer(&bob, 0), None);
+            assert_eq!(EncryptedAssets::last_rollover((0, bob)), Some(1));
+            assert_eq!(EncryptedAssets::total_pending_count(0), 0);
+            assert_eq!(EncryptedAssets::holder_count(0), 2);
+
+            // A fresh proof against the new g_epoch and balance succeeds.
+            let tx_1 = prove(82, 1);
+            assert_ne!((tx_1.1).8, (tx_0.1).8);
+            assert_ok!(submit(&tx_1));
+            assert!(EncryptedAssets::total_pending_transfer(&bob, 0).is_some());
+        })
+    }
+
+    #[test]
+    fn test_burn_from_zface() {
+        with_externalities(&mut new_test_ext(), || {
+            let alice_seed = ALICE_SEED.to_vec();
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let spending_key = tSpendingKey::<tBls12>::from_seed(&alice_seed);
+            let enc_key = tEncryptionKey::<tBls12>::from_seed(&alice_seed[..], &PARAMS).unwrap();
+
+            let enc_alice_bal = vec![telgamal::Ciphertext::encrypt(
+                100,
+                &tFs::one(),
+                &enc_key,
+                tFixedGenerators::NoteCommitmentRandomness,
+                &*PARAMS
+            )];
+
+            // Alice burns 8 with the fee 1 by proving a transfer to the issuer, herself here.
+            let tx = KeyContext::read_from_path(params_path(CONF_PK), params_path(CONF_VK))
+                .unwrap()
+                .gen_proof(
+                    8,
+                    1,
+                    91, 0, 0,
+                    &spending_key,
+                    MultiEncKeys::<tBls12, Confidential>::new(enc_key),
+                    &enc_alice_bal,
+                    get_g_epoch(),
+                    rng,
+                    &*PARAMS
+                ).unwrap();
+
+            let args = (
+                0u64,
                 Proof::from_slice(&tx.proof[..]),
                 EncKey::from_slice(&tx.enc_key_sender[..]),
-                EncKey::from_slice(&tx.enc_key_recipient[..]),
                 LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
                 LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
                 LeftCiphertext::from_slice(&tx.left_fee[..]),
                 RightCiphertext::from_slice(&tx.right_randomness[..]),
                 Nonce::from_slice(&tx.nonce[..])
+            );
+            let origin = || Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..]));
+
+            assert_eq!(EncryptedAssets::burn(
+                origin(), args.0, args.1.clone(), args.2, args.3, args.4, args.5, args.6, args.7
+            ), Err("The asset has no recorded issuer."));
+
+            let (issuer, genesis_balance) = alice_genesis().0;
+            <Issuer<Test>>::insert(0, issuer);
+            <TotalSupply<Test>>::insert(0, genesis_balance.clone());
+            assert_ok!(EncryptedAssets::burn(
+                origin(), args.0, args.1, args.2, args.3, args.4, args.5, args.6, args.7
             ));
+
+            let burned = Ciphertext::from_left_right(args.4, args.6).unwrap();
+            let fee = Ciphertext::from_left_right(args.5, args.6).unwrap();
+            let sent = Ciphertext::from_left_right(args.3, args.6).unwrap();
+            assert_eq!(<TotalSupply<Test>>::get(0), genesis_balance.sub(&burned).unwrap());
+            assert_eq!(
+                EncryptedAssets::encrypted_balance((0, issuer)),
+                Some(genesis_balance.sub(&sent.add(&fee).unwrap()).unwrap())
+            );
+            assert_eq!(EncryptedAssets::total_pending_transfer(&issuer, 0), None);
+        })
+    }
+
+    #[test]
+    fn test_set_metadata() {
+        with_externalities(&mut new_test_ext(), || {
+            let params = &JubjubBls12::new();
+            let p_g = FixedGenerators::Diversifier;
+            let issuer_secret = fs::Fs::from_str("42").unwrap();
+
+            let mut buf = [0u8; 32];
+            params.generator(p_g).mul(issuer_secret, params).write(&mut buf[..]).unwrap();
+            let issuer = EncKey::from_slice(&buf[..]);
+
+            let sign = |secret: fs::Fs, msg: &[u8]| {
+                let rng = &mut rand::thread_rng();
+                let sig = redjubjub::PrivateKey::<Bls12>(secret).sign(msg, rng, p_g, params);
+                RedjubjubSignature::try_from(sig).unwrap()
+            };
+            let origin = || Origin::signed(SigVerificationKey::from_slice(ALICE_SEED));
+            let name = b"Zerochain Dollar".to_vec();
+            let symbol = b"ZUSD".to_vec();
+
+            // The asset has no issuer yet.
+            let msg = EncryptedAssets::metadata_message(&0, &name, &symbol, 2);
+            assert_eq!(
+                EncryptedAssets::set_metadata(origin(), 0, name.clone(), symbol.clone(), 2, sign(issuer_secret, &msg)),
+                Err("The asset has no recorded issuer.")
+            );
+
+            <Issuer<Test>>::insert(0, issuer);
+
+            // Only the issuer can set the metadata.
+            let other_secret = fs::Fs::from_str("43").unwrap();
+            assert_eq!(
+                EncryptedAssets::set_metadata(origin(), 0, name.clone(), symbol.clone(), 2, sign(other_secret, &msg)),
+                Err("Invalid signature of the issuer.")
+            );
+
+            let signature = sign(issuer_secret, &msg);
+            assert_ok!(EncryptedAssets::set_metadata(origin(), 0, name.clone(), symbol.clone(), 2, signature));
+            assert_eq!(EncryptedAssets::asset_metadata(0), Some(Metadata {
+                name: name.clone(),
+                symbol: symbol.clone(),
+                decimals: 2,
+                issuer,
+            }));
+
+            // The signature can't be replayed.
+            assert_eq!(
+                EncryptedAssets::set_metadata(origin(), 0, name.clone(), symbol.clone(), 2, signature),
+                Err("Invalid signature of the issuer.")
+            );
+
+            let long_name = vec![b'a'; MAX_NAME_LENGTH + 1];
+            let msg = EncryptedAssets::metadata_message(&0, &long_name, &symbol, 2);
+            assert_eq!(
+                EncryptedAssets::set_metadata(origin(), 0, long_name, symbol.clone(), 2, sign(issuer_secret, &msg)),
+                Err("Asset name is too long.")
+            );
+
+            let msg = EncryptedAssets::metadata_message(&0, &name, &symbol, MAX_DECIMALS + 1);
+            assert_eq!(
+                EncryptedAssets::set_metadata(origin(), 0, name, symbol, MAX_DECIMALS + 1, sign(issuer_secret, &msg)),
+                Err("Too many decimals.")
+            );
         })
     }
 
     #[test]
     fn test_destroy_from_zface() {
         with_externalities(&mut new_test_ext(), || {
-            let alice_seed = b"Alice                           ".to_vec();
+            let alice_seed = ALICE_SEED.to_vec();
             let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
 
             let spending_key = tSpendingKey::<tBls12>::from_seed(&alice_seed);
@@ -645,7 +1460,7 @@ mod tests {
                 &*PARAMS
             )];
 
-            let tx = KeyContext::read_from_path(PK_PATH, VK_PATH)
+            let tx = KeyContext::read_from_path(params_path(CONF_PK), params_path(CONF_VK))
                 .unwrap()
                 .gen_proof(
                     0,
@@ -659,16 +1474,20 @@ mod tests {
                     &*PARAMS
                 ).unwrap();
 
-            assert_ok!(EncryptedAssets::destroy(
-                Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..])),
+            let args = (
                 Proof::from_slice(&tx.proof[..]),
                 EncKey::from_slice(&tx.enc_key_recipient[..]),
-                0,
+                0u64,
                 LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
                 LeftCiphertext::from_slice(&tx.left_fee[..]),
                 Ciphertext::from_slice(&tx.enc_balance[..]),
                 RightCiphertext::from_slice(&tx.right_randomness[..]),
                 Nonce::from_slice(&tx.nonce[..])
+            );
+
+            assert_ok!(EncryptedAssets::destroy(
+                Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..])),
+                args.0, args.1, args.2, args.3, args.4, args.5, args.6, args.7
             ));
 
         })
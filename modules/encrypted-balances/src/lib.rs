@@ -1,27 +1,191 @@
 //! A module for dealing with confidential transfer
+//!
+//! The zk proof of a transfer takes the signer `rvk` as a public input, and the extrinsic signature by `rvk`
+//! covers all the other arguments, so a proof can't be replayed with altered arguments or by another signer.
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use support::{decl_module, decl_storage, decl_event, StorageMap, dispatch::Result};
+use support::{decl_module, decl_storage, decl_event, StorageMap, ensure, dispatch::Result};
 use rstd::{
     prelude::*,
     result,
+    borrow::Borrow,
 };
-use runtime_primitives::traits::Zero;
-use zprimitives::{EncKey, Proof, Nonce, RightCiphertext, LeftCiphertext, Ciphertext};
+use runtime_primitives::traits::{Zero, Verify, Hash};
+use parity_codec::{Encode, Decode};
+use zprimitives::{
+    EncKey, Proof, Nonce, RightCiphertext, LeftCiphertext, Ciphertext, CiphertextError, Memo,
+    RedjubjubSignature, SigVerificationKey,
+};
+use zprimitives::memo::MEMO_SIZE;
+use zprimitives::enc_key::revealing_enc_key;
+#[cfg(feature = "std")]
+use zprimitives::type_info::Registry;
 use system::{IsDeadAccount, ensure_signed};
+use zk_system::{VerificationError, ConfidentialProof};
+use zk_system::pool::{Pool, PoolMap};
+use zk_auditor::AuditCiphertext;
 
-pub trait Trait: system::Trait + zk_system::Trait {
+pub trait Trait: system::Trait + zk_system::Trait + zk_stats::Trait + zk_auditor::Trait {
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The fee policy of confidential, asset and anonymous transfers. `FlatFee` charges `TransactionBaseFee`.
+	type ConfidentialFee: ConfidentialFee;
+}
+
+pub type FeeAmount = u64;
+
+/// Register the types of the events of this module, which include the ones of `zprimitives`.
+#[cfg(feature = "std")]
+pub fn register_types(registry: &mut Registry) {
+    zprimitives::type_info::register_types(registry);
+    registry
+        .register::<AuditCiphertext>();
+}
+
+/// A fee policy of shielded transfers.
+/// The fee of a confidential transfer is encrypted and only bound to the zk proof, so the chain can't read it;
+/// wallets encrypt the amount given by the policy and prove it in the fee proof, which `check_fee` verifies.
+pub trait ConfidentialFee {
+    /// The fee of a confidential transfer of the native balance whose arguments are `payload_len` bytes
+    /// longer than a transfer to a single recipient, e.g. with more recipients or a return address.
+    fn confidential_fee(payload_len: usize) -> FeeAmount;
+
+    /// The fee of a confidential transfer of an encrypted asset.
+    fn asset_fee<AssetId: Encode>(asset_id: &AssetId) -> FeeAmount;
+
+    /// The fee of an anonymous transfer. It is a public input of the anonymous transfer circuit,
+    /// so it must be the same for every anonymous transfer, and can't depend on the transfer.
+    fn anonymous_fee() -> FeeAmount;
+}
+
+/// The default fee policy: `TransactionBaseFee` for every confidential transfer and `AnonymousFee` for anonymous transfers.
+pub struct FlatFee<T>(rstd::marker::PhantomData<T>);
+
+impl<T: Trait> ConfidentialFee for FlatFee<T> {
+    fn confidential_fee(_payload_len: usize) -> FeeAmount {
+        <Module<T>>::transaction_base_fee()
+    }
+
+    fn asset_fee<AssetId: Encode>(_asset_id: &AssetId) -> FeeAmount {
+        <Module<T>>::transaction_base_fee()
+    }
+
+    fn anonymous_fee() -> FeeAmount {
+        <Module<T>>::anonymous_fee()
+    }
+}
+
+/// The length of a return address: an ephemeral key, the sender's address encrypted under the key
+/// agreed with the recipient, and an authentication tag.
+pub const RETURN_ADDRESS_LENGTH: usize = 96;
+
+/// The maximum number of transfers of `batch_confidential_transfer`.
+pub const MAX_BATCH_TRANSFERS: usize = 16;
+
+/// The context of `batch_transfer_message`, so that the signature of a transfer in a batch
+/// can't be reused as the signature of any other message of the `rvk`.
+pub const BATCH_TRANSFER_CONTEXT: &[u8] = b"EncryptedBalances::batch_confidential_transfer";
+
+/// Errors of rolling over and updating encrypted balances.
+/// The codes are stable across runtime upgrades, so front-ends can match on them.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Error {
+    /// A ciphertext made of the given left and right halves is invalid.
+    MalformedCiphertext(CiphertextError),
+    /// Failed to add the matured pending transfers to the balance.
+    BalanceMutation,
+    /// Failed to add the amount to the pending transfer.
+    PendingTransferMutation,
+    /// Failed to add the fee to the amount.
+    FeeAddition,
+}
+
+impl Error {
+    pub fn code(&self) -> u8 {
+        match *self {
+            Error::MalformedCiphertext(_) => 1,
+            Error::BalanceMutation => 2,
+            Error::PendingTransferMutation => 3,
+            Error::FeeAddition => 4,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Error::MalformedCiphertext(e) => e.as_str(),
+            Error::BalanceMutation => "Faild to mutate encrypted balance.",
+            Error::PendingTransferMutation => "Faild to mutate pending transfer.",
+            Error::FeeAddition => "Failed to add fee to amount",
+        }
+    }
+}
+
+impl From<CiphertextError> for Error {
+    fn from(e: CiphertextError) -> Self {
+        Error::MalformedCiphertext(e)
+    }
+}
+
+impl From<Error> for &'static str {
+    fn from(e: Error) -> &'static str {
+        e.as_str()
+    }
+}
+
+/// An account as it would be after its rollover in the current epoch, returned by `balance_snapshot`.
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct BalanceSnapshot<BlockNumber> {
+    /// The balance with the matured pending transfers added, which proofs in the current epoch are made against.
+    pub balance: Option<Ciphertext>,
+    /// The pending transfers received in the current epoch, which mature in a later epoch.
+    pub pending_transfer: Option<Ciphertext>,
+    /// The epoch of the last rollover.
+    pub last_rollover: BlockNumber,
 }
 
-type FeeAmount = u32;
+/// A confidential transfer of `batch_confidential_transfer`.
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct TransferParams<AccountId> {
+    pub zkproof: Proof,
+    pub address_sender: EncKey,
+    pub address_recipient: EncKey,
+    pub amount_sender: LeftCiphertext,
+    pub amount_recipient: LeftCiphertext,
+    pub fee_sender: LeftCiphertext,
+    pub randomness: RightCiphertext,
+    pub nonce: Nonce,
+    /// The proof that `fee_sender` encrypts the fee of the fee policy.
+    pub fee_proof: Vec<u8>,
+    /// The re-randomized spend authority key the proof is made with.
+    pub rvk: AccountId,
+    /// The signature by `rvk` over `batch_transfer_message` of the transfer.
+    /// It is omitted if `rvk` signed the batch itself.
+    pub signature: Option<RedjubjubSignature>,
+}
+
+/// The balance mutations of a transfer, computed before any of them is written.
+/// A transfer stages all of its mutations and commits them only once every step has succeeded,
+/// so a failure can't leave the sender debited without the recipients credited.
+#[derive(Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+struct Changeset {
+    /// The new balances of the debited accounts.
+    balances: Vec<(EncKey, Ciphertext)>,
+    /// The new pending transfers of the current epoch of the credited accounts.
+    pending_transfers: Vec<(EncKey, Ciphertext)>,
+}
 
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         // Initializing events
 		fn deposit_event<T>() = default;
 
+        /// A confidential transfer, optionally with a memo encrypted to the recipient, e.g. a payment reference.
+        /// The memo is stored nowhere and only carried in the `Memo` event following the transfer.
 		pub fn confidential_transfer(
             origin,
             zkproof: Proof,
@@ -31,52 +195,133 @@ decl_module! {
             amount_recipient: LeftCiphertext,
             fee_sender: LeftCiphertext,
             randomness: RightCiphertext,
-            nonce: Nonce
+            nonce: Nonce,
+            fee_proof: Vec<u8>,
+            memo: Option<Memo>
         ) -> Result {
 			let rvk = ensure_signed(origin)?;
 
-            // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
-            // No problem if errors occur after this function because
-            // it just rollover user's own `pending trasfer` to `encrypted balances`.
-            Self::rollover(&address_sender)?;
+            Self::verify_and_transfer(
+                &rvk,
+                &zkproof,
+                &address_sender,
+                &address_recipient,
+                &amount_sender,
+                &amount_recipient,
+                &fee_sender,
+                &randomness,
+                &nonce,
+                &fee_proof[..],
+                &[],
+                memo.map_or(0, |_| MEMO_SIZE)
+            )?;
 
-            // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
-            // No problem if errors occur after this function because
-            // it just rollover user's own `pending trasfer` to `encrypted balances`.
-            Self::rollover(&address_recipient)?;
+            Self::deposit_event(
+                RawEvent::ConfidentialTransfer(
+                    zkproof,
+                    address_sender,
+                    address_recipient,
+                    amount_sender,
+                    amount_recipient,
+                    fee_sender,
+                    randomness,
+                    Self::encrypted_balance(address_sender).map_or(Ciphertext::zero(), |e| e),
+                    rvk,
+                    <zk_system::Module<T>>::get_current_epoch()
+                )
+            );
+            if let Some(memo) = memo {
+                Self::deposit_event(RawEvent::Memo(address_recipient, memo));
+            }
 
-            // Veridate the provided nonce isn't included in the nonce pool.
-            assert!(!<zk_system::Module<T>>::nonce_pool().contains(&nonce));
+            Ok(())
+		}
 
-            // Verify the zk proof
-            if !<zk_system::Module<T>>::verify_confidential_proof(
-                    &zkproof,
-                    &address_sender,
-                    &address_recipient,
-                    &amount_sender,
-                    &amount_recipient,
-                    &Self::encrypted_balance(address_sender).map_or(Ciphertext::zero(), |e| e),
-                    &rvk,
-                    &fee_sender,
-                    &randomness,
-                    &nonce
-                )? {
-                    Self::deposit_event(RawEvent::InvalidZkProof());
-                    return Err("Invalid zkproof");
-            }
+        /// A confidential transfer involving accounts with auditors.
+        /// `audits` are the amounts encrypted to the auditors of the sender and then the recipient,
+        /// for those of them which have an auditor.
+        pub fn confidential_transfer_audited(
+            origin,
+            zkproof: Proof,
+            address_sender: EncKey,
+            address_recipient: EncKey,
+            amount_sender: LeftCiphertext,
+            amount_recipient: LeftCiphertext,
+            fee_sender: LeftCiphertext,
+            randomness: RightCiphertext,
+            nonce: Nonce,
+            fee_proof: Vec<u8>,
+            audits: Vec<AuditCiphertext>
+        ) -> Result {
+            let rvk = ensure_signed(origin)?;
+
+            Self::verify_and_transfer(
+                &rvk,
+                &zkproof,
+                &address_sender,
+                &address_recipient,
+                &amount_sender,
+                &amount_recipient,
+                &fee_sender,
+                &randomness,
+                &nonce,
+                &fee_proof[..],
+                &audits[..],
+                0
+            )?;
 
-            // Add a nonce into the nonce pool
-            <zk_system::Module<T>>::nonce_pool().push(nonce);
+            Self::deposit_event(
+                RawEvent::ConfidentialTransfer(
+                    zkproof,
+                    address_sender,
+                    address_recipient,
+                    amount_sender,
+                    amount_recipient,
+                    fee_sender,
+                    randomness,
+                    Self::encrypted_balance(address_sender).map_or(Ciphertext::zero(), |e| e),
+                    rvk,
+                    <zk_system::Module<T>>::get_current_epoch()
+                )
+            );
+            Self::deposit_event(RawEvent::TransferAudited(address_sender, audits, randomness));
 
-            // Subtracting transferred amount and fee from the sender's encrypted balances.
-            // This function causes a storage mutation.
-            Self::sub_enc_balance(&address_sender, &amount_sender, &fee_sender, &randomness)
-                .map_err(|_| "Faild to subtract amount from sender's balance.")?;
+            Ok(())
+        }
 
-            // Adding transferred amount to the recipient's pending transfer.
-            // This function causes a storage mutation.
-            Self::add_pending_transfer(&address_recipient, &amount_recipient, &randomness)
-                .map_err(|_| "Faild to add amount to recipient's pending_transfer.")?;
+        /// A confidential transfer with the sender's address sealed to the recipient,
+        /// so the recipient can learn who paid them and reply. The runtime can't open it and only checks its length.
+        pub fn confidential_transfer_with_return_address(
+            origin,
+            zkproof: Proof,
+            address_sender: EncKey,
+            address_recipient: EncKey,
+            amount_sender: LeftCiphertext,
+            amount_recipient: LeftCiphertext,
+            fee_sender: LeftCiphertext,
+            randomness: RightCiphertext,
+            nonce: Nonce,
+            fee_proof: Vec<u8>,
+            return_address: Vec<u8>
+        ) -> Result {
+            let rvk = ensure_signed(origin)?;
+
+            ensure!(return_address.len() == RETURN_ADDRESS_LENGTH, "Invalid return address length.");
+
+            Self::verify_and_transfer(
+                &rvk,
+                &zkproof,
+                &address_sender,
+                &address_recipient,
+                &amount_sender,
+                &amount_recipient,
+                &fee_sender,
+                &randomness,
+                &nonce,
+                &fee_proof[..],
+                &[],
+                RETURN_ADDRESS_LENGTH
+            )?;
 
             Self::deposit_event(
                 RawEvent::ConfidentialTransfer(
@@ -88,36 +333,304 @@ decl_module! {
                     fee_sender,
                     randomness,
                     Self::encrypted_balance(address_sender).map_or(Ciphertext::zero(), |e| e),
-                    rvk
+                    rvk,
+                    <zk_system::Module<T>>::get_current_epoch()
                 )
             );
+            Self::deposit_event(RawEvent::ReturnAddress(address_recipient, return_address));
 
             Ok(())
-		}
+        }
+
+        /// Make multiple confidential transfers in one extrinsic, e.g. for the payouts of an exchange,
+        /// applying all of them or none. Each transfer is authorized by its own `rvk`: the signer of the batch
+        /// or a signature over `batch_transfer_message`. The proofs are verified together, and each account is
+        /// rolled over once however many transfers it takes part in.
+        /// A sender can make only one transfer in a batch, as its nonce is the same for the whole epoch.
+        pub fn batch_confidential_transfer(
+            origin,
+            transfers: Vec<TransferParams<T::AccountId>>
+        ) -> Result {
+            let signer = ensure_signed(origin)?;
+
+            ensure!(!transfers.is_empty(), "No transfers are provided.");
+            ensure!(transfers.len() <= MAX_BATCH_TRANSFERS, "Too many transfers.");
+
+            // Check everything which doesn't depend on the balances before any storage mutation.
+            let mut accounts = vec![];
+            for (i, transfer) in transfers.iter().enumerate() {
+                if transfer.rvk != signer {
+                    let signature = transfer.signature.ok_or("A transfer of another rvk than the signer must be signed.")?;
+                    ensure!(
+                        Self::verify_transfer_signature(&transfer.rvk, &Self::batch_transfer_message(transfer), &signature)?,
+                        "Invalid signature of a transfer."
+                    );
+                }
+
+                ensure!(
+                    transfers[..i].iter().all(|t| t.nonce != transfer.nonce),
+                    "A sender can make only one transfer in a batch."
+                );
+
+                Ciphertext::from_left_right(transfer.amount_sender, transfer.randomness)?;
+                Ciphertext::from_left_right(transfer.amount_recipient, transfer.randomness)?;
+                Self::check_fee(
+                    &transfer.address_sender,
+                    &transfer.fee_sender,
+                    &transfer.randomness,
+                    T::ConfidentialFee::confidential_fee(0),
+                    &transfer.fee_proof[..]
+                )?;
+
+                // Batched transfers can't carry audit ciphertexts,
+                // so they fail if any of the accounts has an auditor.
+                <zk_auditor::Module<T>>::check_audits(
+                    &[(transfer.address_sender, transfer.amount_sender), (transfer.address_recipient, transfer.amount_recipient)],
+                    &transfer.randomness,
+                    &[]
+                )?;
+
+                for address in &[transfer.address_sender, transfer.address_recipient] {
+                    if !accounts.contains(address) {
+                        accounts.push(*address);
+                    }
+                }
+            }
+
+            // Rolling over only moves each account's own pending transfer into its balance,
+            // so it is fine even if the verification fails afterwards.
+            for address in &accounts {
+                Self::rollover(address)?;
+            }
+
+            // Reserving the nonces up front also rejects a batch using a nonce twice,
+            // before any of its nonces is recorded.
+            let mut tickets = Vec::with_capacity(transfers.len());
+            for transfer in &transfers {
+                match <zk_system::Module<T>>::reserve_nonce(transfer.nonce) {
+                    Ok(ticket) => tickets.push(ticket),
+                    Err(e) => {
+                        <zk_system::Module<T>>::abort_nonces(tickets);
+                        Self::deposit_event(RawEvent::NonceReplayed(transfer.nonce));
+                        return Err(e.into());
+                    }
+                }
+            }
+
+            // The senders are distinct and credits only go to pending transfers,
+            // so every proof is made against the balance of its sender before the batch.
+            let proofs = transfers.iter().map(|t| ConfidentialProof {
+                zkproof: t.zkproof,
+                address_sender: t.address_sender,
+                address_recipient: t.address_recipient,
+                amount_sender: t.amount_sender,
+                amount_recipient: t.amount_recipient,
+                balance_sender: Self::encrypted_balance(t.address_sender).map_or(Ciphertext::zero(), |e| e),
+                rvk: t.rvk.clone(),
+                fee_sender: t.fee_sender,
+                randomness: t.randomness,
+                nonce: t.nonce,
+            }).collect::<Vec<_>>();
+            if let Err(e) = <zk_system::Module<T>>::verify_confidential_proofs_batch(&proofs[..]) {
+                <zk_system::Module<T>>::abort_nonces(tickets);
+                if e == VerificationError::InvalidProof {
+                    Self::deposit_event(RawEvent::InvalidZkProof());
+                }
+                return Err(e.into());
+            }
+
+            let mut changeset = Changeset::default();
+            let staged = transfers.iter().try_for_each(|t| -> Result {
+                Self::stage_sub_enc_balance(&mut changeset, &t.address_sender, &t.amount_sender, &t.fee_sender, &t.randomness)?;
+                let enc_amount = Ciphertext::from_left_right(t.amount_recipient, t.randomness)?;
+                Self::stage_pending_transfer(&mut changeset, &t.address_recipient, &enc_amount)?;
+                Ok(())
+            });
+            if let Err(e) = staged {
+                <zk_system::Module<T>>::abort_nonces(tickets);
+                return Err(e);
+            }
+
+            for ticket in tickets {
+                <zk_system::Module<T>>::commit_nonce(ticket);
+            }
+
+            Self::commit(changeset);
+
+            let current_epoch = <zk_system::Module<T>>::get_current_epoch();
+            for t in transfers {
+                let enc_fee = Ciphertext::from_left_right(t.fee_sender, t.randomness)?;
+                <zk_stats::Module<T>>::note_confidential_transfer(&[t.address_sender, t.address_recipient], &enc_fee);
+                <zk_stats::Module<T>>::note_fee_charged(T::ConfidentialFee::confidential_fee(0));
+
+                Self::deposit_event(
+                    RawEvent::ConfidentialTransfer(
+                        t.zkproof,
+                        t.address_sender,
+                        t.address_recipient,
+                        t.amount_sender,
+                        t.amount_recipient,
+                        t.fee_sender,
+                        t.randomness,
+                        Self::encrypted_balance(t.address_sender).map_or(Ciphertext::zero(), |e| e),
+                        t.rvk,
+                        current_epoch
+                    )
+                );
+            }
+
+            Ok(())
+        }
+
+        /// Burn a public amount from the sender's balance, e.g. for bridges, buybacks and transparency commitments.
+        /// The proof is of a confidential transfer to `revealing_enc_key`, whose decryption key is public,
+        /// so the runtime checks the transferred amount is `amount_plain` and the circuit checks the sender's
+        /// balance covers it and the fee. The amount is removed from the balance without being credited to anyone.
+        pub fn reveal_and_burn(
+            origin,
+            zkproof: Proof,
+            address_sender: EncKey,
+            amount_plain: u64,
+            amount_sender: LeftCiphertext,
+            amount_burned: LeftCiphertext,
+            fee_sender: LeftCiphertext,
+            randomness: RightCiphertext,
+            nonce: Nonce,
+            fee_proof: Vec<u8>
+        ) -> Result {
+            let rvk = ensure_signed(origin)?;
+
+            Self::verify_and_burn(
+                &rvk,
+                &zkproof,
+                &address_sender,
+                amount_plain,
+                &amount_sender,
+                &amount_burned,
+                &fee_sender,
+                &randomness,
+                &nonce,
+                &fee_proof[..]
+            )?;
+
+            <TotalBurned<T>>::mutate(|total| *total = total.saturating_add(amount_plain));
+
+            Self::deposit_event(RawEvent::Burned(address_sender, amount_plain, rvk));
+
+            Ok(())
+        }
+
+        /// Roll over the matured pending transfers of any account into its balance without a transfer,
+        /// so that received amounts show up in the balance before the recipient transacts.
+        /// Anyone can call it: it only does what the next transfer of the account would do first,
+        /// and wallets prove against the balance with the matured pending transfers added.
+        pub fn force_rollover(origin, addr: EncKey) -> Result {
+            ensure_signed(origin)?;
+
+            Self::rollover(&addr)?;
+
+            Ok(())
+        }
+
+        /// Remove what is left of a dead account, i.e. its last rollover epoch and its rollover count,
+        /// so that rolling over unknown accounts doesn't grow the storage unboundedly.
+        /// Anyone can call it: a dead account has nothing to prove against,
+        /// and it is rolled over again by the first transfer to it.
+        pub fn reap_account(origin, addr: EncKey) -> Result {
+            ensure_signed(origin)?;
+
+            ensure!(Self::is_dead(&addr), "The account has a balance or a pending transfer.");
+            ensure!(<LastRollOver<T>>::exists(addr), "The account has nothing to reap.");
+
+            <LastRollOver<T>>::remove(addr);
+            <RolloverCount<T>>::remove(addr);
+            Self::deposit_event(RawEvent::AccountReaped(addr));
+
+            Ok(())
+        }
 	}
 }
 
 decl_storage! {
     trait Store for Module<T: Trait> as EncryptedBalances {
-        /// An encrypted balance for each account
-        pub EncryptedBalance get(encrypted_balance) config() : map EncKey => Option<Ciphertext>;
-
-        /// A pending transfer
-        pub PendingTransfer get(pending_transfer) : map EncKey => Option<Ciphertext>;
+        /// The epochs of the pending transfer buckets of each account in ascending order.
+        pub PendingEpochs get(pending_epochs) : map EncKey => Vec<T::BlockNumber>;
 
         /// A last epoch for rollover
         pub LastRollOver get(last_rollover) config() : map EncKey => Option<T::BlockNumber>;
 
+        /// The number of rollovers of each account, each of which emitted `RolledOver`.
+        pub RolloverCount get(rollover_count) : map EncKey => u64;
+
         /// A fee to be paid for making a transaction; the base.
         pub TransactionBaseFee get(transaction_base_fee) config(): FeeAmount;
+
+        /// The fee of every anonymous transfer, which senders of anonymous transfers prove to pay.
+        pub AnonymousFee get(anonymous_fee) config(): FeeAmount;
+
+        /// The number of pending transfer buckets of all the accounts which are not rolled over yet.
+        pub TotalPendingCount get(total_pending_count): u64;
+
+        /// The sum of the amounts burned by `reveal_and_burn`.
+        pub TotalBurned get(total_burned): u64;
+
+        /// The number of accounts which have an encrypted balance.
+        pub ActiveAccountCount get(active_account_count) build(|config: &GenesisConfig<T>| {
+            config.encrypted_balance.len() as u64
+        }): u64;
     }
+    add_extra_genesis {
+        config(encrypted_balance): Vec<(EncKey, Ciphertext)>;
+
+        build(|_: &mut runtime_primitives::StorageOverlay, children: &mut runtime_primitives::ChildrenStorageOverlay, config: &GenesisConfig<T>| {
+            <EncryptedBalance<T>>::build_genesis(children, &config.encrypted_balance);
+        });
+    }
+}
+
+/// An encrypted balance for each account, in the child trie of the pool.
+pub struct EncryptedBalance<T>(rstd::marker::PhantomData<T>);
+
+impl<T: Trait> PoolMap for EncryptedBalance<T> {
+    type Key = EncKey;
+    type Value = Ciphertext;
+    const POOL: Pool = Pool::EncryptedBalances;
+    const PREFIX: &'static [u8] = b"EncryptedBalance";
+}
+
+/// Pending transfers bucketed by the epoch in which they were received, in the child trie of the pool.
+/// A bucket is rolled over into the balance at the first rollover after its epoch,
+/// so transfers received in the current epoch never change a balance proven against in this epoch.
+pub struct PendingTransfer<T>(rstd::marker::PhantomData<T>);
+
+impl<T: Trait> PoolMap for PendingTransfer<T> {
+    type Key = (EncKey, T::BlockNumber);
+    type Value = Ciphertext;
+    const POOL: Pool = Pool::EncryptedBalances;
+    const PREFIX: &'static [u8] = b"PendingTransfer";
 }
 
 decl_event! (
     /// An event in this module.
-	pub enum Event<T> where <T as system::Trait>::AccountId {
-		ConfidentialTransfer(Proof, EncKey, EncKey, LeftCiphertext, LeftCiphertext, LeftCiphertext, RightCiphertext, Ciphertext, AccountId),
+	pub enum Event<T> where <T as system::Trait>::AccountId, <T as system::Trait>::BlockNumber {
+        /// A confidential transfer, followed by the epoch it was made in.
+        /// The recipient's address is in the clear, so wallets filter the transfers to their accounts by it before decrypting anything.
+		ConfidentialTransfer(Proof, EncKey, EncKey, LeftCiphertext, LeftCiphertext, LeftCiphertext, RightCiphertext, Ciphertext, AccountId, BlockNumber),
         InvalidZkProof(),
+        /// A transfer was rejected since its nonce is already used in the current epoch.
+        NonceReplayed(Nonce),
+        /// The amounts of a transfer from the account encrypted to the auditors, with the right component of the transfer.
+        TransferAudited(EncKey, Vec<AuditCiphertext>, RightCiphertext),
+        /// The account was rolled over in the epoch, and whether some matured pending transfers were applied to its balance.
+        RolledOver(EncKey, BlockNumber, bool),
+        /// The sender's address sealed to the recipient, following the transfer to the recipient.
+        ReturnAddress(EncKey, Vec<u8>),
+        /// The memo encrypted to the recipient, following the transfer to the recipient.
+        Memo(EncKey, Memo),
+        /// The last rollover epoch of a dead account was removed by `reap_account`.
+        AccountReaped(EncKey),
+        /// The amount was burned from the account's balance by `reveal_and_burn`.
+        Burned(EncKey, u64, AccountId),
 	}
 );
 
@@ -130,38 +643,43 @@ impl<T: Trait> Module<T> {
     /// To achieve this, we define a separate (internal) method for rolling over,
     /// and the first thing every other method does is to call this method.
     /// More details in Section 3.1: https://crypto.stanford.edu/~buenz/papers/zether.pdf
-    pub fn rollover(addr: &EncKey) -> result::Result<(), &'static str> {
+    pub fn rollover(addr: &EncKey) -> result::Result<(), Error> {
         let current_epoch = <zk_system::Module<T>>::get_current_epoch();
 
         let last_rollover = Self::last_rollover(addr)
             .map_or(T::BlockNumber::zero(), |e| e);
 
-        // Get balance with the type
-        let enc_pending_transfer = Self::pending_transfer(addr)
-            .map_or(Ciphertext::zero(), |e| e);
+        // Buckets received in past epochs have matured; the bucket of the current epoch stays pending.
+        let pending_epochs = Self::pending_epochs(addr);
+        let (new_balance, matured) = Self::matured_balance(addr, &pending_epochs, current_epoch)?;
 
-        // Checks if the last roll over was in an older epoch.
+        // Checks if the last roll over was in an older epoch or some pending transfers have matured.
         // If so, some storage changes are happend here.
-        if last_rollover < current_epoch {
-            // transfer balance from pending_transfer to actual balance
-            <EncryptedBalance<T>>::mutate(addr, |balance| {
-                let new_balance = match balance.clone() {
-                    Some(b) => b.add(&enc_pending_transfer),
-                    None => Ok(enc_pending_transfer),
-                };
-
-                match new_balance {
-                    Ok(nb) => *balance = Some(nb),
-                    Err(_) => return Err("Faild to mutate encrypted balance."),
+        if last_rollover < current_epoch || matured > 0 {
+            // transfer balance from the matured pending transfers to actual balance.
+            if let Some(new_balance) = new_balance {
+                if !<EncryptedBalance<T>>::exists(addr) {
+                    <ActiveAccountCount<T>>::mutate(|count| *count += 1);
                 }
+                <EncryptedBalance<T>>::insert(addr, new_balance);
+            }
 
-                Ok(())
-            })?;
+            // Prune the matured buckets.
+            for epoch in &pending_epochs[..matured] {
+                <PendingTransfer<T>>::remove((*addr, *epoch));
+            }
+            <TotalPendingCount<T>>::mutate(|count| *count = count.saturating_sub(matured as u64));
+            if matured == pending_epochs.len() {
+                <PendingEpochs<T>>::remove(addr);
+            } else {
+                <PendingEpochs<T>>::insert(addr, pending_epochs[matured..].to_vec());
+            }
 
-            // Reset pending_transfer.
-            <PendingTransfer<T>>::remove(addr);
             // Set last rollover to current epoch.
             <LastRollOver<T>>::insert(addr, current_epoch);
+
+            <RolloverCount<T>>::mutate(addr, |count| *count += 1);
+            Self::deposit_event(RawEvent::RolledOver(*addr, current_epoch, matured > 0));
         }
         // Initialize a nonce pool
         <zk_system::Module<T>>::init_nonce_pool(current_epoch);
@@ -169,204 +687,586 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
-    // Subtracting transferred amount and fee from encrypted balances.
-    pub fn sub_enc_balance(
-        address: &EncKey,
-        amount: &LeftCiphertext,
-        fee: &LeftCiphertext,
-        randomness: &RightCiphertext
-    ) -> result::Result<(), &'static str> {
-        let enc_amount = Ciphertext::from_left_right(*amount, *randomness)
-            .map_err(|_| "Faild to create amount ciphertext.")?;
-        let enc_fee = Ciphertext::from_left_right(*fee, *randomness)
-            .map_err(|_| "Faild to create fee ciphertext.")?;
-        let amount_plus_fee = enc_amount.add(&enc_fee)
-            .map_err(|_| "Failed to add fee to amount")?;
-
-        <EncryptedBalance<T>>::mutate(address, |balance| {
-            let new_balance = balance.clone()
-                .and_then(
-                    |b| b.sub(&amount_plus_fee).ok()
-            );
+    /// Verify a zk proof of a confidential transfer and apply it to the balances.
+    /// Used by `confidential_transfer` and by other modules which move encrypted balances on behalf of an account.
+    /// `audits` must cover the sender and the recipient if they have auditors.
+    /// `payload_len` is passed to the fee policy, as in `ConfidentialFee::confidential_fee`,
+    /// and `fee_proof` must prove `fee_sender` encrypts the fee it gives.
+    pub fn verify_and_transfer(
+        rvk: &T::AccountId,
+        zkproof: &Proof,
+        address_sender: &EncKey,
+        address_recipient: &EncKey,
+        amount_sender: &LeftCiphertext,
+        amount_recipient: &LeftCiphertext,
+        fee_sender: &LeftCiphertext,
+        randomness: &RightCiphertext,
+        nonce: &Nonce,
+        fee_proof: &[u8],
+        audits: &[AuditCiphertext],
+        payload_len: usize
+    ) -> Result {
+        // Reject malformed ciphertexts and a fee other than the policy's before any storage mutation.
+        Ciphertext::from_left_right(*amount_sender, *randomness)?;
+        Ciphertext::from_left_right(*amount_recipient, *randomness)?;
+        let enc_fee = Ciphertext::from_left_right(*fee_sender, *randomness)?;
+        let fee = T::ConfidentialFee::confidential_fee(payload_len);
+        Self::check_fee(address_sender, fee_sender, randomness, fee, fee_proof)?;
+
+        <zk_auditor::Module<T>>::check_audits(
+            &[(*address_sender, *amount_sender), (*address_recipient, *amount_recipient)],
+            randomness,
+            audits
+        )?;
+
+        let ticket = match <zk_system::Module<T>>::reserve_nonce(*nonce) {
+            Ok(ticket) => ticket,
+            Err(e) => {
+                Self::deposit_event(RawEvent::NonceReplayed(*nonce));
+                return Err(e.into());
+            }
+        };
 
-            *balance = new_balance
-        });
+        if let Err(e) = Self::check_confidential_proof(
+                rvk,
+                zkproof,
+                address_sender,
+                address_recipient,
+                amount_sender,
+                amount_recipient,
+                fee_sender,
+                randomness,
+                nonce
+            ) {
+            <zk_system::Module<T>>::abort_nonce(ticket);
+            match e {
+                VerificationError::NonceUsed => Self::deposit_event(RawEvent::NonceReplayed(*nonce)),
+                VerificationError::InvalidProof => Self::deposit_event(RawEvent::InvalidZkProof()),
+                _ => {}
+            }
+            return Err(e.into());
+        }
 
-        Ok(())
-    }
+        // Subtracting transferred amount and fee from the sender's encrypted balances
+        // and adding transferred amount to the recipient's pending transfer,
+        // staged so that neither is written if the other fails.
+        let changeset = match Self::stage_transfer(
+            address_sender,
+            amount_sender,
+            fee_sender,
+            &[(*address_recipient, *amount_recipient)],
+            randomness
+        ) {
+            Ok(changeset) => changeset,
+            Err(e) => {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                return Err(e.into());
+            }
+        };
 
-    /// Adding transferred amount to pending transfer.
-    pub fn add_pending_transfer(
-        address: &EncKey,
-        amount: &LeftCiphertext,
-        randomness: &RightCiphertext
-    ) -> result::Result<(), &'static str> {
-        let enc_amount = Ciphertext::from_left_right(*amount, *randomness)
-            .map_err(|_| "Faild to create amount ciphertext.")?;
-
-        <PendingTransfer<T>>::mutate(address, |pending_transfer| {
-            let new_pending_transfer = match pending_transfer.clone() {
-                Some(p) => p.add(&enc_amount),
-                None => Ok(enc_amount),
-            };
+        // Add a nonce into the nonce pool
+        <zk_system::Module<T>>::commit_nonce(ticket);
 
-            match new_pending_transfer {
-                Ok(np) => *pending_transfer = Some(np),
-                Err(_) => return Err("Faild to mutate pending transfer.")
-            }
+        Self::commit(changeset);
 
-            Ok(())
-        })?;
+        <zk_stats::Module<T>>::note_confidential_transfer(&[*address_sender, *address_recipient], &enc_fee);
+        <zk_stats::Module<T>>::note_fee_charged(fee);
 
         Ok(())
     }
-}
 
-impl<T: Trait> IsDeadAccount<T::AccountId> for Module<T>
-{
-    fn is_dead_account(_who: &T::AccountId) -> bool {
-        unimplemented!();
-    }
-}
+    /// Check a confidential transfer against the current state of the chain: the ciphertexts,
+    /// the nonce pool, and the proof against the g_epoch, the verifying key and the sender's balance.
+    /// The sender and the recipient are rolled over first, as the transfer would do.
+    /// This is exposed as a dry-run through the runtime API, whose storage mutations are discarded,
+    /// so wallets can catch a proof of a past epoch before paying the fee of the transfer.
+    pub fn check_confidential_proof(
+        rvk: &T::AccountId,
+        zkproof: &Proof,
+        address_sender: &EncKey,
+        address_recipient: &EncKey,
+        amount_sender: &LeftCiphertext,
+        amount_recipient: &LeftCiphertext,
+        fee_sender: &LeftCiphertext,
+        randomness: &RightCiphertext,
+        nonce: &Nonce
+    ) -> result::Result<(), VerificationError> {
+        for c in &[amount_sender, amount_recipient, fee_sender] {
+            Ciphertext::from_left_right(**c, *randomness)
+                .map_err(|e| VerificationError::MalformedInput(e.as_str()))?;
+        }
 
-#[cfg(feature = "std")]
-#[cfg(test)]
-pub mod tests {
-    use super::*;
-    use runtime_io::with_externalities;
-    use support::{impl_outer_origin, assert_ok};
-    use primitives::{H256, Blake2Hasher};
-    use runtime_primitives::{
-        BuildStorage, traits::{BlakeTwo256, IdentityLookup},
-        testing::{Digest, DigestItem, Header}
-    };
-    use zprimitives::{Ciphertext, SigVerificationKey};
-    use keys::{ProofGenerationKey, EncryptionKey};
-    use jubjub::{curve::{JubjubBls12, FixedGenerators, fs}};
-    use pairing::{Field, bls12_381::Bls12};
-    use zcrypto::elgamal;
-    use hex_literal::{hex, hex_impl};
-    use bellman_verifier::PreparedVerifyingKey;
-    use std::{
-        path::Path,
-        fs::File,
-        io::{BufReader, Read},
-        convert::TryFrom,
-    };
+        // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
+        // No problem if errors occur after this function because
+        // it just rollover user's own `pending trasfer` to `encrypted balances`.
+        Self::rollover(address_sender).map_err(|e| VerificationError::MalformedInput(e.as_str()))?;
+        Self::rollover(address_recipient).map_err(|e| VerificationError::MalformedInput(e.as_str()))?;
 
-    const PK_PATH: &str = "../../zface/params/test_conf_pk.dat";
-    const VK_PATH: &str = "../../zface/params/test_conf_vk.dat";
+        // Validate the provided nonce isn't included in the nonce pool.
+        if <zk_system::Module<T>>::is_nonce_used(nonce) {
+            return Err(VerificationError::NonceUsed);
+        }
 
-    impl_outer_origin! {
-        pub enum Origin for Test {}
+        <zk_system::Module<T>>::verify_confidential_proof(
+            zkproof,
+            address_sender,
+            address_recipient,
+            amount_sender,
+            amount_recipient,
+            &Self::encrypted_balance(*address_sender).map_or(Ciphertext::zero(), |e| e),
+            rvk,
+            fee_sender,
+            randomness,
+            nonce
+        )
     }
 
-    // For testing the module, we construct most of a mock runtime. This means
-	// first constructing a configuration type (`Test`) which `impl`s each of the
-	// configuration traits of modules we want to use.
-    #[derive(Clone, Eq, PartialEq)]
-    pub struct Test;
-
-    impl system::Trait for Test {
-        type Origin = Origin;
-        type Index = u64;
-        type BlockNumber = u64;
-        type Hash = H256;
-        type Hashing = BlakeTwo256;
-        type Digest = Digest;
-        type AccountId = SigVerificationKey;
-        type SigVerificationKey = u64;
-        type Lookup = IdentityLookup<SigVerificationKey>;
-        type Header = Header;
-        type Event = ();
-        type Log = DigestItem;
-    }
+    /// Verify a zk proof of burning the public `amount_plain` and subtract it and the fee from the sender's balance.
+    /// The proof is of a confidential transfer to `revealing_enc_key`, which is not credited.
+    /// Used by `reveal_and_burn` and by other modules which move burned amounts out of the shielded pool.
+    pub fn verify_and_burn(
+        rvk: &T::AccountId,
+        zkproof: &Proof,
+        address_sender: &EncKey,
+        amount_plain: u64,
+        amount_sender: &LeftCiphertext,
+        amount_burned: &LeftCiphertext,
+        fee_sender: &LeftCiphertext,
+        randomness: &RightCiphertext,
+        nonce: &Nonce,
+        fee_proof: &[u8]
+    ) -> Result {
+        // Reject malformed ciphertexts, a mismatched amount and a fee other than the policy's
+        // before any storage mutation.
+        Ciphertext::from_left_right(*amount_sender, *randomness)?;
+        let enc_fee = Ciphertext::from_left_right(*fee_sender, *randomness)?;
+        let fee = T::ConfidentialFee::confidential_fee(0);
+        Self::check_fee(address_sender, fee_sender, randomness, fee, fee_proof)?;
+        let burned = Ciphertext::from_left_right(*amount_burned, *randomness)?;
+        ensure!(
+            burned.reveals(amount_plain).map_err(|_| "Invalid ciphertext of the burned amount.")?,
+            "The burned amount doesn't match amount_plain."
+        );
 
-    impl Trait for Test {
-        type Event = ();
-    }
+        // This function causes a storage mutation, but it's needed before `verify_proof` function is called.
+        // No problem if errors occur after this function because
+        // it just rollover user's own `pending trasfer` to `encrypted balances`.
+        Self::rollover(address_sender)?;
+
+        // Reserve the provided nonce, which fails if it's included in the nonce pool.
+        let ticket = match <zk_system::Module<T>>::reserve_nonce(*nonce) {
+            Ok(ticket) => ticket,
+            Err(e) => {
+                Self::deposit_event(RawEvent::NonceReplayed(*nonce));
+                return Err(e.into());
+            }
+        };
 
-    impl zk_system::Trait for Test { }
+        // Verify the zk proof
+        // 1. Spend authority verification
+        // 2. Range check of the remaining balance
+        // 3. Encryption integrity of the burned amount under both keys
+        if let Err(e) = <zk_system::Module<T>>::verify_confidential_proof(
+            zkproof,
+            address_sender,
+            &revealing_enc_key(),
+            amount_sender,
+            amount_burned,
+            &Self::encrypted_balance(address_sender).map_or(Ciphertext::zero(), |e| e),
+            rvk,
+            fee_sender,
+            randomness,
+            nonce
+        ) {
+            <zk_system::Module<T>>::abort_nonce(ticket);
+            if e == VerificationError::InvalidProof {
+                Self::deposit_event(RawEvent::InvalidZkProof());
+            }
+            return Err(e.into());
+        }
 
-    type EncryptedBalances = Module<Test>;
+        let changeset = match Self::stage_transfer(address_sender, amount_sender, fee_sender, &[], randomness) {
+            Ok(changeset) => changeset,
+            Err(e) => {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                return Err(e.into());
+            }
+        };
 
-    fn alice_balance_init() -> (EncKey, Ciphertext) {
-        let (alice_seed, enc_key) = get_alice_seed_ek();
-        let alice_amount = 100 as u32;
-        let params = &JubjubBls12::new();
-        let p_g = FixedGenerators::Diversifier; // 1 same as NoteCommitmentRandomness;
-
-        // The default balance is not encrypted with randomness.
-        let enc_alice_bal = elgamal::Ciphertext::encrypt(
-            alice_amount,
-            &fs::Fs::one(),
-            &enc_key,
-            p_g,
-            params
-        );
+        // Add a nonce into the nonce pool
+        <zk_system::Module<T>>::commit_nonce(ticket);
 
-        let decryption_key = ProofGenerationKey::<Bls12>::from_seed(&alice_seed[..], params).into_decryption_key().unwrap();
+        Self::commit(changeset);
 
-        let dec_alice_bal = enc_alice_bal.decrypt(&decryption_key, p_g, params).unwrap();
-        assert_eq!(dec_alice_bal, alice_amount);
+        <zk_stats::Module<T>>::note_confidential_transfer(&[*address_sender], &enc_fee);
+        <zk_stats::Module<T>>::note_fee_charged(fee);
 
-        (EncKey::try_from(enc_key).unwrap(), Ciphertext::try_from(enc_alice_bal).unwrap())
+        Ok(())
     }
 
-    fn alice_epoch_init() -> (EncKey, u64) {
-        let (_, enc_key) = get_alice_seed_ek();
+    /// Check `fee_proof` proves `fee_sender` encrypts `fee` under the sender's key with `randomness`,
+    /// so that a transfer pays the fee of the fee policy, which the chain can't read from the ciphertext.
+    pub fn check_fee(
+        address_sender: &EncKey,
+        fee_sender: &LeftCiphertext,
+        randomness: &RightCiphertext,
+        fee: FeeAmount,
+        fee_proof: &[u8]
+    ) -> Result {
+        let enc_fee = Ciphertext::from_left_right(*fee_sender, *randomness)?;
+
+        ensure!(
+            enc_fee.verify_amount(fee, address_sender, fee_proof).map_err(|_| "Invalid fee proof.")?,
+            "The fee doesn't match the fee policy."
+        );
 
-        (EncKey::try_from(enc_key).unwrap(), 0)
+        Ok(())
     }
 
-    fn get_alice_seed_ek() -> (Vec<u8>, EncryptionKey<Bls12>) {
-        let params = &JubjubBls12::new();
-        let alice_seed = b"Alice                           ".to_vec();
+    // Subtracting transferred amount and fee from encrypted balances.
+    pub fn sub_enc_balance(
+        address: &EncKey,
+        amount: &LeftCiphertext,
+        fee: &LeftCiphertext,
+        randomness: &RightCiphertext
+    ) -> result::Result<(), Error> {
+        let mut changeset = Changeset::default();
+        Self::stage_sub_enc_balance(&mut changeset, address, amount, fee, randomness)?;
+        Self::commit(changeset);
 
-        (alice_seed.clone(), EncryptionKey::<Bls12>::from_seed(&alice_seed[..], params)
-            .expect("should be generated encryption key from seed."))
+        Ok(())
     }
 
-    pub fn get_conf_vk() -> PreparedVerifyingKey<Bls12> {
-        let vk_path = Path::new("../../zface/params/test_conf_vk.dat");
-        let vk_file = File::open(&vk_path).unwrap();
-        let mut vk_reader = BufReader::new(vk_file);
-
-        let mut buf_vk = vec![];
-        vk_reader.read_to_end(&mut buf_vk).unwrap();
-
-        PreparedVerifyingKey::<Bls12>::read(&mut &buf_vk[..]).unwrap()
-    }
+    /// Adding transferred amount to the pending transfer bucket of the current epoch.
+    /// Callers roll over the recipient first, so an account only has the bucket of the current epoch
+    /// and the buckets which mature at its next rollover.
+    pub fn add_pending_transfer(
+        address: &EncKey,
+        amount: &LeftCiphertext,
+        randomness: &RightCiphertext
+    ) -> result::Result<(), Error> {
+        let enc_amount = Ciphertext::from_left_right(*amount, *randomness)?;
+
+        let mut changeset = Changeset::default();
+        Self::stage_pending_transfer(&mut changeset, address, &enc_amount)?;
+        Self::commit(changeset);
+
+        Ok(())
+    }
+
+    /// Subtract the amount from the sender's balance and add the amounts to the pending transfers of the recipients,
+    /// e.g. to move the payments of a standing order into its escrow. The caller verifies the proofs of the amounts
+    /// and rolls over the accounts first, and none of the mutations is written if one of them fails.
+    pub fn move_balance(
+        address_sender: &EncKey,
+        amount_sender: &Ciphertext,
+        recipients: &[(EncKey, Ciphertext)]
+    ) -> result::Result<(), Error> {
+        let mut changeset = Changeset::default();
+        Self::stage_sub(&mut changeset, address_sender, amount_sender)?;
+        for (address, amount) in recipients {
+            Self::stage_pending_transfer(&mut changeset, address, amount)?;
+        }
+        Self::commit(changeset);
+
+        Ok(())
+    }
+
+    // The balance with the pending transfers received before the current epoch added,
+    // and the number of their epochs at the front of `pending_epochs`.
+    // An account without a balance gets one only once a transfer to it matures,
+    // so rolling over an unknown account doesn't make it alive.
+    fn matured_balance(
+        addr: &EncKey,
+        pending_epochs: &[T::BlockNumber],
+        current_epoch: T::BlockNumber
+    ) -> result::Result<(Option<Ciphertext>, usize), Error> {
+        let matured = pending_epochs.iter().take_while(|e| **e < current_epoch).count();
+
+        let mut enc_pending_transfer = Ciphertext::zero();
+        for epoch in &pending_epochs[..matured] {
+            if let Some(p) = Self::pending_transfer((*addr, *epoch)) {
+                enc_pending_transfer = enc_pending_transfer.add(&p)
+                    .map_err(|_| Error::BalanceMutation)?;
+            }
+        }
+
+        let balance = match Self::encrypted_balance(addr) {
+            Some(b) => Some(b.add(&enc_pending_transfer).map_err(|_| Error::BalanceMutation)?),
+            None if matured > 0 => Some(enc_pending_transfer),
+            None => None,
+        };
+
+        Ok((balance, matured))
+    }
+
+    // Stage all the balance mutations of a transfer from the sender to the recipients.
+    fn stage_transfer(
+        address_sender: &EncKey,
+        amount_sender: &LeftCiphertext,
+        fee_sender: &LeftCiphertext,
+        recipients: &[(EncKey, LeftCiphertext)],
+        randomness: &RightCiphertext
+    ) -> result::Result<Changeset, Error> {
+        let mut changeset = Changeset::default();
+        Self::stage_sub_enc_balance(&mut changeset, address_sender, amount_sender, fee_sender, randomness)?;
+        for (address, amount) in recipients {
+            let enc_amount = Ciphertext::from_left_right(*amount, *randomness)?;
+            Self::stage_pending_transfer(&mut changeset, address, &enc_amount)?;
+        }
+
+        Ok(changeset)
+    }
+
+    // Stage subtracting transferred amount and fee from the balance of the sender.
+    fn stage_sub_enc_balance(
+        changeset: &mut Changeset,
+        address: &EncKey,
+        amount: &LeftCiphertext,
+        fee: &LeftCiphertext,
+        randomness: &RightCiphertext
+    ) -> result::Result<(), Error> {
+        let enc_amount = Ciphertext::from_left_right(*amount, *randomness)?;
+        let enc_fee = Ciphertext::from_left_right(*fee, *randomness)?;
+        let amount_plus_fee = enc_amount.add(&enc_fee)
+            .map_err(|_| Error::FeeAddition)?;
+
+        Self::stage_sub(changeset, address, &amount_plus_fee)
+    }
+
+    // Stage subtracting the amount from the balance, on top of the balance already staged for the account.
+    // An account without a balance keeps having none.
+    fn stage_sub(changeset: &mut Changeset, address: &EncKey, amount: &Ciphertext) -> result::Result<(), Error> {
+        let staged = changeset.balances.iter().position(|(a, _)| a == address);
+        let balance = match staged {
+            Some(i) => changeset.balances[i].1.clone(),
+            None => match Self::encrypted_balance(address) {
+                Some(b) => b,
+                None => return Ok(()),
+            },
+        };
+
+        let new_balance = balance.sub(amount)
+            .map_err(|_| Error::BalanceMutation)?;
+        match staged {
+            Some(i) => changeset.balances[i].1 = new_balance,
+            None => changeset.balances.push((*address, new_balance)),
+        }
+
+        Ok(())
+    }
+
+    // Stage adding the amount to the pending transfer of the current epoch,
+    // on top of the pending transfer already staged for the account.
+    fn stage_pending_transfer(changeset: &mut Changeset, address: &EncKey, amount: &Ciphertext) -> result::Result<(), Error> {
+        let current_epoch = <zk_system::Module<T>>::get_current_epoch();
+        let staged = changeset.pending_transfers.iter().position(|(a, _)| a == address);
+        let pending_transfer = match staged {
+            Some(i) => Some(changeset.pending_transfers[i].1.clone()),
+            None => Self::pending_transfer((*address, current_epoch)),
+        };
+
+        let new_pending_transfer = match pending_transfer {
+            Some(p) => p.add(amount).map_err(|_| Error::PendingTransferMutation)?,
+            None => amount.clone(),
+        };
+        match staged {
+            Some(i) => changeset.pending_transfers[i].1 = new_pending_transfer,
+            None => changeset.pending_transfers.push((*address, new_pending_transfer)),
+        }
+
+        Ok(())
+    }
+
+    // Write the staged mutations. Nothing here can fail.
+    fn commit(changeset: Changeset) {
+        let current_epoch = <zk_system::Module<T>>::get_current_epoch();
+
+        for (address, balance) in changeset.balances {
+            <EncryptedBalance<T>>::insert(address, balance);
+        }
+
+        for (address, pending_transfer) in changeset.pending_transfers {
+            <PendingTransfer<T>>::insert((address, current_epoch), pending_transfer);
+            <PendingEpochs<T>>::mutate(address, |epochs| {
+                if epochs.last() != Some(&current_epoch) {
+                    epochs.push(current_epoch);
+                    <TotalPendingCount<T>>::mutate(|count| *count += 1);
+                }
+            });
+        }
+    }
+
+    // PUBLIC IMMUTABLES
+
+    /// The encrypted balance of the account.
+    pub fn encrypted_balance<K: Borrow<EncKey>>(addr: K) -> Option<Ciphertext> {
+        <EncryptedBalance<T>>::get(addr)
+    }
+
+    /// The pending transfers to the account received in the epoch.
+    pub fn pending_transfer<K: Borrow<(EncKey, T::BlockNumber)>>(key: K) -> Option<Ciphertext> {
+        <PendingTransfer<T>>::get(key)
+    }
+
+    /// The message signed by the `rvk` of a transfer of `batch_confidential_transfer` which didn't sign the batch.
+    pub fn batch_transfer_message(transfer: &TransferParams<T::AccountId>) -> Vec<u8> {
+        let mut buf = BATCH_TRANSFER_CONTEXT.to_vec();
+        (&transfer.zkproof, &transfer.address_sender, &transfer.address_recipient, &transfer.amount_sender,
+            &transfer.amount_recipient, &transfer.fee_sender, &transfer.randomness, &transfer.nonce, &transfer.rvk)
+            .encode_to(&mut buf);
+        T::Hashing::hash(&buf[..]).encode()
+    }
+
+    /// The account as it would be after its rollover in the current epoch, without mutating storage.
+    /// Wallets prove against its balance, and it saves them reading the balance, the pending transfers
+    /// and the last rollover separately and replicating the rollover.
+    pub fn balance_snapshot(addr: &EncKey) -> result::Result<BalanceSnapshot<T::BlockNumber>, Error> {
+        let current_epoch = <zk_system::Module<T>>::get_current_epoch();
+        let last_rollover = Self::last_rollover(addr)
+            .map_or(T::BlockNumber::zero(), |e| e);
 
-    pub fn get_anony_vk() -> PreparedVerifyingKey<Bls12> {
-        let vk_path = Path::new("../../zface/params/test_anony_vk.dat");
-        let vk_file = File::open(&vk_path).unwrap();
-        let mut vk_reader = BufReader::new(vk_file);
+        let pending_epochs = Self::pending_epochs(addr);
+        let (balance, matured) = Self::matured_balance(addr, &pending_epochs, current_epoch)?;
+        let pending_transfer = pending_epochs[matured..]
+            .iter()
+            .filter_map(|epoch| Self::pending_transfer((*addr, *epoch)))
+            .fold(Ok(None), |acc: result::Result<Option<Ciphertext>, Error>, p| match acc? {
+                Some(a) => a.add(&p).map(Some).map_err(|_| Error::PendingTransferMutation),
+                None => Ok(Some(p)),
+            })?;
+
+        Ok(BalanceSnapshot {
+            balance,
+            pending_transfer,
+            last_rollover: if last_rollover < current_epoch || matured > 0 { current_epoch } else { last_rollover },
+        })
+    }
+
+    /// An account is dead if it has neither a balance nor a pending transfer.
+    /// Its `LastRollOver` is all that may be left of it, which `reap_account` removes.
+    pub fn is_dead(address: &EncKey) -> bool {
+        Self::encrypted_balance(address).is_none() && Self::pending_epochs(address).is_empty()
+    }
+
+    /// The sum of all the pending transfer buckets of the account, matured or not.
+    pub fn total_pending_transfer(address: &EncKey) -> Option<Ciphertext> {
+        Self::pending_epochs(address)
+            .iter()
+            .filter_map(|epoch| Self::pending_transfer((*address, *epoch)))
+            .fold(None, |acc, p| match acc {
+                Some(a) => a.add(&p).ok(),
+                None => Some(p),
+            })
+    }
+
+    // Account ids are the bytes of the re-randomized spend authority keys,
+    // which sign as they sign extrinsics.
+    fn verify_transfer_signature(
+        rvk: &T::AccountId,
+        msg: &[u8],
+        signature: &RedjubjubSignature
+    ) -> result::Result<bool, &'static str> {
+        let rvk = SigVerificationKey::decode(&mut &rvk.encode()[..])
+            .ok_or("Invalid rvk of a transfer.")?;
+
+        Ok(signature.verify(msg, &rvk))
+    }
+}
+
+/// Account ids are re-randomized spend authority keys, which nothing in this module is keyed by:
+/// balances are keyed by encryption keys, which can't be told from an rvk.
+/// So no account id is ever reported dead, rather than letting an index of a live account be reclaimed.
+/// The liveness of encryption keys is `is_dead`, which `reap_account` checks.
+impl<T: Trait> IsDeadAccount<T::AccountId> for Module<T>
+{
+    fn is_dead_account(_who: &T::AccountId) -> bool {
+        false
+    }
+}
 
-        let mut buf_vk = vec![];
-        vk_reader.read_to_end(&mut buf_vk).unwrap();
+#[cfg(feature = "std")]
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use support::{impl_outer_origin, assert_ok};
+    use primitives::{H256, Blake2Hasher};
+    use runtime_primitives::{
+        BuildStorage, traits::{BlakeTwo256, IdentityLookup},
+        testing::{Digest, DigestItem, Header}
+    };
+    use zprimitives::SigVerificationKey;
+    use hex_literal::{hex, hex_impl};
+    use test_fixtures::{
+        ALICE_SEED, CONF_PK, CONF_VK, params_path, conf_vk, anony_vk, alice_genesis, dec_key, burn, confidential_transfer,
+    };
 
-        PreparedVerifyingKey::<Bls12>::read(&mut &buf_vk[..]).unwrap()
+    impl_outer_origin! {
+        pub enum Origin for Test {}
     }
 
+    // For testing the module, we construct most of a mock runtime. This means
+	// first constructing a configuration type (`Test`) which `impl`s each of the
+	// configuration traits of modules we want to use.
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = SigVerificationKey;
+        type SigVerificationKey = u64;
+        type Lookup = IdentityLookup<SigVerificationKey>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl Trait for Test {
+        type Event = ();
+        type ConfidentialFee = FlatFee<Test>;
+    }
+
+    impl zk_system::Trait for Test {
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl zk_stats::Trait for Test { }
+
+    impl zk_auditor::Trait for Test {
+        type Event = ();
+    }
+
+    type EncryptedBalances = Module<Test>;
+
     fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
         let (mut t, mut c) = system::GenesisConfig::<Test>::default().build_storage().unwrap();
         let _ = zk_system::GenesisConfig::<Test>{
             last_epoch: 1,
             epoch_length: 1,
-            confidential_vk: get_conf_vk(),
-            anonymous_vk: get_anony_vk(),
-            nonce_pool: vec![],
+            max_anonymity_set_size: 12,
+            nonce_retention_epochs: 0,
+            confidential_vk: conf_vk(),
+            anonymous_vk: anony_vk(),
+            confidential_pk_hash: [0u8; 32],
+            anonymous_pk_hash: [0u8; 32],
         }.assimilate_storage(&mut t, &mut c);
 
         let _ = GenesisConfig::<Test>{
-            encrypted_balance: vec![alice_balance_init()],
-			last_rollover: vec![alice_epoch_init()],
+            encrypted_balance: vec![alice_genesis().0],
+			last_rollover: vec![alice_genesis().1],
             transaction_base_fee: 1,
+            anonymous_fee: 0,
             _genesis_phantom_data: Default::default()
         }.assimilate_storage(&mut t, &mut c);
 
-        t.into()
+        zk_system::pool::test_externalities(t, c)
     }
 
     #[test]
@@ -379,7 +1279,7 @@ pub mod tests {
         use scrypto::jubjub::{FixedGenerators as tFixedGenerators, fs::Fs as tFs, edwards as tedwards};
 
         with_externalities(&mut new_test_ext(), || {
-            let alice_seed = b"Alice                           ".to_vec();
+            let alice_seed = ALICE_SEED.to_vec();
             let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
             let bob_addr: [u8; 32] = hex!("45e66da531088b55dcb3b273ca825454d79d2d1d5c4fa2ba4a12c1fa1ccd6389");
             let recipient_account_id = tEncryptionKey::<tBls12>::read(&mut &bob_addr[..], &PARAMS).unwrap();
@@ -407,7 +1307,7 @@ pub mod tests {
             let g_epoch_vec: [u8; 32] = hex!("0953f47325251a2f479c25527df6d977925bebafde84423b20ae6c903411665a");
             let g_epoch = tedwards::Point::read(&g_epoch_vec[..], &*PARAMS).unwrap().as_prime_order(&*PARAMS).unwrap();
 
-            let tx = KeyContext::read_from_path(PK_PATH, VK_PATH)
+            let tx = KeyContext::read_from_path(params_path(CONF_PK), params_path(CONF_VK))
                 .unwrap()
                 .gen_proof(
                     amount,
@@ -421,17 +1321,421 @@ pub mod tests {
                     &*PARAMS
                 ).unwrap();
 
+            let args = (
+                Proof::from_slice(&tx.proof[..]),
+                EncKey::from_slice(&tx.enc_key_sender[..]),
+                EncKey::from_slice(&tx.enc_key_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_fee[..]),
+                RightCiphertext::from_slice(&tx.right_randomness[..]),
+                Nonce::from_slice(&tx.nonce[..]),
+                tx.fee_proof.clone(),
+                Some(Memo::from_slice(&[1u8; MEMO_SIZE]))
+            );
+
             assert_ok!(EncryptedBalances::confidential_transfer(
+                Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..])),
+                args.0, args.1, args.2, args.3, args.4, args.5, args.6, args.7, args.8, args.9
+            ));
+
+            // The flat policy charges the base fee, which the proof was made with.
+            assert_eq!(<zk_stats::Module<Test>>::total_fee_charged(), 1);
+        })
+    }
+
+    #[test]
+    fn test_underpaying_transfer_is_rejected() {
+        use rand::{SeedableRng, XorShiftRng};
+
+        with_externalities(&mut new_test_ext(), || {
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            // The proof is valid, but pays no fee while the flat policy charges one.
+            let tx = confidential_transfer(10, 0, 100, 1, rng);
+            let alice = EncKey::from_slice(&tx.enc_key_sender[..]);
+            let nonce = Nonce::from_slice(&tx.nonce[..]);
+            let submit = |fee_proof: Vec<u8>| EncryptedBalances::confidential_transfer(
                 Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..])),
                 Proof::from_slice(&tx.proof[..]),
+                alice,
+                EncKey::from_slice(&tx.enc_key_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_fee[..]),
+                RightCiphertext::from_slice(&tx.right_randomness[..]),
+                nonce,
+                fee_proof,
+                None
+            );
+
+            let balance = EncryptedBalances::encrypted_balance(alice);
+            assert_eq!(submit(tx.fee_proof.clone()), Err("The fee doesn't match the fee policy."));
+            // Nor does a proof of the fee of another transfer do.
+            let paid = confidential_transfer(10, 1, 100, 1, rng);
+            assert_eq!(submit(paid.fee_proof.clone()), Err("The fee doesn't match the fee policy."));
+            assert_eq!(submit(vec![]), Err("Invalid fee proof."));
+
+            assert_eq!(EncryptedBalances::encrypted_balance(alice), balance);
+            assert!(!<zk_system::Module<Test>>::is_nonce_used(&nonce));
+            assert_eq!(<zk_stats::Module<Test>>::total_fee_charged(), 0);
+        })
+    }
+
+    #[test]
+    fn test_proof_is_bound_to_its_signer() {
+        use rand::{SeedableRng, XorShiftRng};
+
+        with_externalities(&mut new_test_ext(), || {
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let tx = confidential_transfer(10, 1, 100, 1, rng);
+            // The same transfer proven again is signed by another rvk, re-randomized by another alpha.
+            let other = confidential_transfer(10, 1, 100, 1, rng);
+            assert_ne!(tx.rvk, other.rvk);
+
+            let nonce = Nonce::from_slice(&tx.nonce[..]);
+            let submit = |rvk: &[u8]| EncryptedBalances::confidential_transfer(
+                Origin::signed(SigVerificationKey::from_slice(rvk)),
+                Proof::from_slice(&tx.proof[..]),
                 EncKey::from_slice(&tx.enc_key_sender[..]),
                 EncKey::from_slice(&tx.enc_key_recipient[..]),
                 LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
                 LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
                 LeftCiphertext::from_slice(&tx.left_fee[..]),
                 RightCiphertext::from_slice(&tx.right_randomness[..]),
-                Nonce::from_slice(&tx.nonce[..])
+                nonce,
+                tx.fee_proof.clone(),
+                None
+            );
+
+            assert_eq!(submit(&other.rvk[..]), Err(VerificationError::InvalidProof.into()));
+            assert!(!<zk_system::Module<Test>>::is_nonce_used(&nonce));
+            assert_ok!(submit(&tx.rvk[..]));
+        })
+    }
+
+    #[test]
+    fn test_reveal_and_burn() {
+        use rand::{SeedableRng, XorShiftRng};
+        use jubjub::curve::{JubjubBls12, FixedGenerators};
+        use pairing::bls12_381::Bls12;
+        use zcrypto::elgamal;
+        use std::convert::TryFrom;
+
+        with_externalities(&mut new_test_ext(), || {
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let tx = burn(8, 1, 100, 1, rng);
+
+            let args = |amount_plain: u64| (
+                Proof::from_slice(&tx.proof[..]),
+                EncKey::from_slice(&tx.enc_key_sender[..]),
+                amount_plain,
+                LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_fee[..]),
+                RightCiphertext::from_slice(&tx.right_randomness[..]),
+                Nonce::from_slice(&tx.nonce[..]),
+                tx.fee_proof.clone()
+            );
+            let submit = |args: (Proof, EncKey, u64, LeftCiphertext, LeftCiphertext, LeftCiphertext, RightCiphertext, Nonce, Vec<u8>)| {
+                EncryptedBalances::reveal_and_burn(
+                    Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..])),
+                    args.0, args.1, args.2, args.3, args.4, args.5, args.6, args.7, args.8
+                )
+            };
+
+            // The revealed amount must be the amount of the proof.
+            assert_eq!(submit(args(9)), Err("The burned amount doesn't match amount_plain."));
+            assert_ok!(submit(args(8)));
+            assert_eq!(EncryptedBalances::total_burned(), 8);
+
+            // The amount and the fee are subtracted, and no one is credited.
+            let alice = EncKey::from_slice(&tx.enc_key_sender[..]);
+            let balance = elgamal::Ciphertext::<Bls12>::try_from(EncryptedBalances::encrypted_balance(alice).unwrap()).unwrap();
+            assert_eq!(balance.decrypt(&dec_key(ALICE_SEED), FixedGenerators::Diversifier, &JubjubBls12::new()), Some(91));
+            assert!(EncryptedBalances::pending_epochs(revealing_enc_key()).is_empty());
+        })
+    }
+
+    #[test]
+    fn test_verify_confidential_proofs_batch() {
+        use rand::{SeedableRng, XorShiftRng};
+        use test_pairing::{bls12_381::Bls12 as tBls12, Field as tField};
+        use test_proofs::{EncryptionKey as tEncryptionKey, SpendingKey as tSpendingKey,
+            elgamal as telgamal, PARAMS, MultiEncKeys, KeyContext, ProofBuilder, Confidential,
+        };
+        use scrypto::jubjub::{FixedGenerators as tFixedGenerators, fs::Fs as tFs, edwards as tedwards};
+        use zk_system::ConfidentialProof;
+
+        with_externalities(&mut new_test_ext(), || {
+            let alice_seed = ALICE_SEED.to_vec();
+            let bob_addr: [u8; 32] = hex!("45e66da531088b55dcb3b273ca825454d79d2d1d5c4fa2ba4a12c1fa1ccd6389");
+            let recipient_account_id = tEncryptionKey::<tBls12>::read(&mut &bob_addr[..], &PARAMS).unwrap();
+            let spending_key = tSpendingKey::<tBls12>::from_seed(&alice_seed);
+
+            let enc_key = tEncryptionKey::<tBls12>::from_seed(&alice_seed[..], &PARAMS).unwrap();
+            let enc_alice_bal = vec![telgamal::Ciphertext::encrypt(
+                100,
+                &tFs::one(),
+                &enc_key,
+                tFixedGenerators::NoteCommitmentRandomness,
+                &*PARAMS
+            )];
+
+            let g_epoch_vec: [u8; 32] = hex!("0953f47325251a2f479c25527df6d977925bebafde84423b20ae6c903411665a");
+            let g_epoch = tedwards::Point::read(&g_epoch_vec[..], &*PARAMS).unwrap().as_prime_order(&*PARAMS).unwrap();
+            let key_context = KeyContext::read_from_path(params_path(CONF_PK), params_path(CONF_VK)).unwrap();
+
+            let transfers: Vec<_> = [(8, 91), (20, 79)].iter().enumerate().map(|(i, (amount, remaining_balance))| {
+                let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, i as u32 + 1]);
+                let tx = key_context.gen_proof(
+                    *amount,
+                    1,
+                    *remaining_balance, 0, 0,
+                    &spending_key,
+                    MultiEncKeys::<tBls12, Confidential>::new(recipient_account_id.clone()),
+                    &enc_alice_bal,
+                    g_epoch.clone(),
+                    rng,
+                    &*PARAMS
+                ).unwrap();
+
+                let address_sender = EncKey::from_slice(&tx.enc_key_sender[..]);
+                ConfidentialProof {
+                    zkproof: Proof::from_slice(&tx.proof[..]),
+                    address_sender,
+                    address_recipient: EncKey::from_slice(&tx.enc_key_recipient[..]),
+                    amount_sender: LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+                    amount_recipient: LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                    balance_sender: EncryptedBalances::encrypted_balance(address_sender).unwrap(),
+                    rvk: SigVerificationKey::from_slice(&tx.rvk[..]),
+                    fee_sender: LeftCiphertext::from_slice(&tx.left_fee[..]),
+                    randomness: RightCiphertext::from_slice(&tx.right_randomness[..]),
+                    nonce: Nonce::from_slice(&tx.nonce[..]),
+                }
+            }).collect();
+
+            assert_eq!(<zk_system::Module<Test>>::verify_confidential_proofs_batch(&transfers[..]), Ok(()));
+
+            // A single tampered transfer fails the whole batch.
+            let mut tampered = transfers.clone();
+            tampered[1].amount_recipient = transfers[0].amount_recipient;
+            assert_eq!(
+                <zk_system::Module<Test>>::verify_confidential_proofs_batch(&tampered[..]),
+                Err(VerificationError::InvalidProof)
+            );
+        })
+    }
+
+    #[test]
+    fn test_check_confidential_proof() {
+        use rand::{SeedableRng, XorShiftRng};
+
+        with_externalities(&mut new_test_ext(), || {
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let tx = confidential_transfer(10, 1, 100, 1, rng);
+
+            let args = (
+                Proof::from_slice(&tx.proof[..]),
+                EncKey::from_slice(&tx.enc_key_sender[..]),
+                EncKey::from_slice(&tx.enc_key_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_fee[..]),
+                RightCiphertext::from_slice(&tx.right_randomness[..]),
+                Nonce::from_slice(&tx.nonce[..]),
+                tx.fee_proof.clone(),
+                None
+            );
+            let rvk = SigVerificationKey::from_slice(&tx.rvk[..]);
+            let check = |amount_recipient: &LeftCiphertext| EncryptedBalances::check_confidential_proof(
+                &rvk, &args.0, &args.1, &args.2, &args.3, amount_recipient, &args.5, &args.6, &args.7
+            );
+
+            assert_eq!(check(&args.4), Ok(()));
+            assert_eq!(check(&args.3), Err(VerificationError::InvalidProof));
+
+            // The dry-run doesn't insert the nonce, so the transfer still goes through.
+            let a = args.clone();
+            assert_ok!(EncryptedBalances::confidential_transfer(
+                Origin::signed(rvk),
+                a.0, a.1, a.2, a.3, a.4, a.5, a.6, a.7, a.8, a.9
             ));
+            assert_eq!(check(&args.4), Err(VerificationError::NonceUsed));
+        })
+    }
+
+    #[test]
+    fn test_batch_confidential_transfer() {
+        use rand::{SeedableRng, XorShiftRng};
+        use test_pairing::{bls12_381::Bls12 as tBls12, Field as tField};
+        use test_proofs::{EncryptionKey as tEncryptionKey, SpendingKey as tSpendingKey,
+            elgamal as telgamal, PARAMS, MultiEncKeys, KeyContext, ProofBuilder, Confidential,
+        };
+        use scrypto::jubjub::{FixedGenerators as tFixedGenerators, fs::Fs as tFs, edwards as tedwards};
+        use jubjub::curve::{JubjubBls12, FixedGenerators, fs::Fs};
+        use jubjub::redjubjub;
+        use pairing::{PrimeField, PrimeFieldRepr, bls12_381::Bls12};
+        use zcrypto::elgamal;
+        use std::convert::TryFrom;
+
+        with_externalities(&mut new_test_ext(), || {
+            let alice_seed = ALICE_SEED.to_vec();
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let bob_addr: [u8; 32] = hex!("45e66da531088b55dcb3b273ca825454d79d2d1d5c4fa2ba4a12c1fa1ccd6389");
+            let recipient_account_id = tEncryptionKey::<tBls12>::read(&mut &bob_addr[..], &PARAMS).unwrap();
+            let spending_key = tSpendingKey::<tBls12>::from_seed(&alice_seed);
+
+            let enc_key = tEncryptionKey::<tBls12>::from_seed(&alice_seed[..], &PARAMS).unwrap();
+            let enc_alice_bal = vec![telgamal::Ciphertext::encrypt(
+                100,
+                &tFs::one(),
+                &enc_key,
+                tFixedGenerators::NoteCommitmentRandomness,
+                &*PARAMS
+            )];
+
+            let g_epoch_vec: [u8; 32] = hex!("0953f47325251a2f479c25527df6d977925bebafde84423b20ae6c903411665a");
+            let g_epoch = tedwards::Point::read(&g_epoch_vec[..], &*PARAMS).unwrap().as_prime_order(&*PARAMS).unwrap();
+
+            let tx = KeyContext::read_from_path(params_path(CONF_PK), params_path(CONF_VK))
+                .unwrap()
+                .gen_proof(
+                    8, 1, 91, 0, 0,
+                    &spending_key,
+                    MultiEncKeys::<tBls12, Confidential>::new(recipient_account_id),
+                    &enc_alice_bal,
+                    g_epoch,
+                    rng,
+                    &*PARAMS
+                ).unwrap();
+
+            let rvk = SigVerificationKey::from_slice(&tx.rvk[..]);
+            let transfer = TransferParams {
+                zkproof: Proof::from_slice(&tx.proof[..]),
+                address_sender: EncKey::from_slice(&tx.enc_key_sender[..]),
+                address_recipient: EncKey::from_slice(&tx.enc_key_recipient[..]),
+                amount_sender: LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+                amount_recipient: LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                fee_sender: LeftCiphertext::from_slice(&tx.left_fee[..]),
+                randomness: RightCiphertext::from_slice(&tx.right_randomness[..]),
+                nonce: Nonce::from_slice(&tx.nonce[..]),
+                fee_proof: tx.fee_proof.clone(),
+                rvk,
+                signature: None,
+            };
+            let relayer = SigVerificationKey::from_slice(b"Relayer                         ");
+            let submit = |signer: SigVerificationKey, transfers: Vec<TransferParams<SigVerificationKey>>| {
+                EncryptedBalances::batch_confidential_transfer(Origin::signed(signer), transfers)
+            };
+
+            // A sender has a single nonce in an epoch.
+            assert_eq!(
+                submit(rvk, vec![transfer.clone(), transfer.clone()]),
+                Err("A sender can make only one transfer in a batch.")
+            );
+
+            // A transfer of another rvk than the signer of the batch must be signed by its rvk.
+            assert_eq!(
+                submit(relayer, vec![transfer.clone()]),
+                Err("A transfer of another rvk than the signer must be signed.")
+            );
+
+            let mut rsk_repr = Fs::default().into_repr();
+            rsk_repr.read_le(&mut &tx.rsk[..]).unwrap();
+            let rsk = redjubjub::PrivateKey::<Bls12>(Fs::from_repr(rsk_repr).unwrap());
+            let sign = |msg: &[u8]| {
+                let sig = rsk.sign(msg, &mut rand::thread_rng(), FixedGenerators::Diversifier, &JubjubBls12::new());
+                RedjubjubSignature::try_from(sig).unwrap()
+            };
+
+            let forged = TransferParams { signature: Some(sign(b"another message")), ..transfer.clone() };
+            assert_eq!(submit(relayer, vec![forged]), Err("Invalid signature of a transfer."));
+
+            // A rejected batch leaves its nonces neither used nor reserved.
+            let tampered = TransferParams { fee_sender: transfer.amount_sender, ..transfer.clone() };
+            assert!(submit(rvk, vec![tampered]).is_err());
+            assert!(!<zk_system::Module<Test>>::is_nonce_used(&transfer.nonce));
+            assert!(!<zk_system::Module<Test>>::is_nonce_reserved(&transfer.nonce));
+
+            let signed = TransferParams {
+                signature: Some(sign(&EncryptedBalances::batch_transfer_message(&transfer)[..])),
+                ..transfer.clone()
+            };
+            assert_ok!(submit(relayer, vec![signed]));
+
+            let balance = elgamal::Ciphertext::<Bls12>::try_from(EncryptedBalances::encrypted_balance(transfer.address_sender).unwrap()).unwrap();
+            assert_eq!(balance.decrypt(&dec_key(ALICE_SEED), FixedGenerators::Diversifier, &JubjubBls12::new()), Some(91));
+            assert!(EncryptedBalances::total_pending_transfer(&transfer.address_recipient).is_some());
+            assert_eq!(<zk_stats::Module<Test>>::total_fee_charged(), 1);
+
+            // The nonce is used by the batch.
+            assert!(<zk_system::Module<Test>>::is_nonce_used(&transfer.nonce));
+            assert!(!<zk_system::Module<Test>>::is_nonce_reserved(&transfer.nonce));
+            assert!(submit(rvk, vec![transfer]).is_err());
+        })
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_verify_confidential_proof() {
+        use rand::{SeedableRng, XorShiftRng};
+        use test_pairing::{bls12_381::Bls12 as tBls12, Field as tField};
+        use test_proofs::{EncryptionKey as tEncryptionKey, SpendingKey as tSpendingKey,
+            elgamal as telgamal, PARAMS, MultiEncKeys, KeyContext, ProofBuilder, Confidential,
+        };
+        use scrypto::jubjub::{FixedGenerators as tFixedGenerators, fs::Fs as tFs, edwards as tedwards};
+        use zk_system::{benchmarking, weights};
+
+        with_externalities(&mut new_test_ext(), || {
+            let alice_seed = ALICE_SEED.to_vec();
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let bob_addr: [u8; 32] = hex!("45e66da531088b55dcb3b273ca825454d79d2d1d5c4fa2ba4a12c1fa1ccd6389");
+            let recipient_account_id = tEncryptionKey::<tBls12>::read(&mut &bob_addr[..], &PARAMS).unwrap();
+            let spending_key = tSpendingKey::<tBls12>::from_seed(&alice_seed);
+
+            let enc_key = tEncryptionKey::<tBls12>::from_seed(&alice_seed[..], &PARAMS).unwrap();
+            let enc_alice_bal = vec![telgamal::Ciphertext::encrypt(
+                100,
+                &tFs::one(),
+                &enc_key,
+                tFixedGenerators::NoteCommitmentRandomness,
+                &*PARAMS
+            )];
+
+            let g_epoch_vec: [u8; 32] = hex!("0953f47325251a2f479c25527df6d977925bebafde84423b20ae6c903411665a");
+            let g_epoch = tedwards::Point::read(&g_epoch_vec[..], &*PARAMS).unwrap().as_prime_order(&*PARAMS).unwrap();
+
+            let tx = KeyContext::read_from_path(params_path(CONF_PK), params_path(CONF_VK))
+                .unwrap()
+                .gen_proof(
+                    8, 1, 91, 0, 0,
+                    &spending_key,
+                    MultiEncKeys::<tBls12, Confidential>::new(recipient_account_id),
+                    &enc_alice_bal,
+                    g_epoch,
+                    rng,
+                    &*PARAMS
+                ).unwrap();
+
+            let address_sender = EncKey::from_slice(&tx.enc_key_sender[..]);
+            let balance_sender = EncryptedBalances::encrypted_balance(address_sender).unwrap();
+            let measurement = benchmarking::measure("verify_confidential_proof", 20, || {
+                assert_eq!(<zk_system::Module<Test>>::verify_confidential_proof(
+                    &Proof::from_slice(&tx.proof[..]),
+                    &address_sender,
+                    &EncKey::from_slice(&tx.enc_key_recipient[..]),
+                    &LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+                    &LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                    &balance_sender,
+                    &SigVerificationKey::from_slice(&tx.rvk[..]),
+                    &LeftCiphertext::from_slice(&tx.left_fee[..]),
+                    &RightCiphertext::from_slice(&tx.right_randomness[..]),
+                    &Nonce::from_slice(&tx.nonce[..])
+                ), Ok(()));
+            });
+
+            measurement.report(weights::CONFIDENTIAL_PROOF);
+            assert!(measurement.weight() <= weights::CONFIDENTIAL_PROOF);
         })
     }
 
@@ -449,8 +1753,7 @@ pub mod tests {
             let rvk: [u8; 32] = hex!("fa8e6fbf6d2116ef083670d6859da118c662b97c4fabe6eacf7c6dc0b2953346");
             let nonce: [u8; 32] = hex!("c3427a3e3e9f19ff730d45c7c7daa1ee3c96b10a86085d11647fe27d923d654e");
 
-            assert_ok!(EncryptedBalances::confidential_transfer(
-                Origin::signed(SigVerificationKey::from_slice(&rvk[..])),
+            let args = (
                 Proof::from_slice(&proof[..]),
                 EncKey::from_slice(&pkd_addr_alice),
                 EncKey::from_slice(&pkd_addr_bob),
@@ -458,8 +1761,349 @@ pub mod tests {
                 LeftCiphertext::from_slice(&enc10_by_bob[..]),
                 LeftCiphertext::from_slice(&enc1_by_alice[..]),
                 RightCiphertext::from_slice(&randomness[..]),
-                Nonce::from_slice(&nonce[..])
+                Nonce::from_slice(&nonce[..]),
+                vec![],
+                None
+            );
+
+            assert_ok!(EncryptedBalances::confidential_transfer(
+                Origin::signed(SigVerificationKey::from_slice(&rvk[..])),
+                args.0, args.1, args.2, args.3, args.4, args.5, args.6, args.7, args.8, args.9
             ));
         })
     }
+
+    #[test]
+    fn test_return_address_length_is_checked() {
+        with_externalities(&mut new_test_ext(), || {
+            let args = (
+                Proof::from_slice(&[0u8; 192][..]),
+                EncKey::default(),
+                EncKey::default(),
+                LeftCiphertext::default(),
+                LeftCiphertext::default(),
+                LeftCiphertext::default(),
+                RightCiphertext::default(),
+                Nonce::default(),
+                vec![],
+                vec![0u8; RETURN_ADDRESS_LENGTH + 1]
+            );
+
+            assert_eq!(
+                EncryptedBalances::confidential_transfer_with_return_address(
+                    Origin::signed(SigVerificationKey::default()),
+                    args.0, args.1, args.2, args.3, args.4, args.5, args.6, args.7, args.8, args.9
+                ),
+                Err("Invalid return address length.")
+            );
+        })
+    }
+
+    #[test]
+    fn test_epoch_lifecycle() {
+        use rand::{SeedableRng, XorShiftRng};
+        use test_pairing::bls12_381::Bls12 as tBls12;
+        use test_proofs::{EncryptionKey as tEncryptionKey, SpendingKey as tSpendingKey,
+            elgamal as telgamal, PARAMS, MultiEncKeys, KeyContext, ProofBuilder, Confidential,
+        };
+        use scrypto::jubjub::edwards as tedwards;
+        use zprimitives::GEpoch;
+
+        with_externalities(&mut new_test_ext(), || {
+            let alice_seed = ALICE_SEED.to_vec();
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let bob_addr: [u8; 32] = hex!("45e66da531088b55dcb3b273ca825454d79d2d1d5c4fa2ba4a12c1fa1ccd6389");
+            let recipient_account_id = tEncryptionKey::<tBls12>::read(&mut &bob_addr[..], &PARAMS).unwrap();
+            let spending_key = tSpendingKey::<tBls12>::from_seed(&alice_seed);
+            let key_context = KeyContext::read_from_path(params_path(CONF_PK), params_path(CONF_VK)).unwrap();
+            let (alice, _) = alice_genesis().1;
+            let bob = EncKey::from_slice(&bob_addr[..]);
+
+            // Prove a transfer of 8 with the fee 1 against alice's on-chain balance in the given epoch.
+            let mut prove = |remaining_balance: u64, epoch: u32| {
+                let balance = EncryptedBalances::encrypted_balance(alice).unwrap();
+                let enc_balance = vec![telgamal::Ciphertext::read(&mut balance.as_bytes(), &*PARAMS).unwrap()];
+                let g_epoch = GEpoch::group_hash(epoch).unwrap();
+                let g_epoch = tedwards::Point::read(g_epoch.as_bytes(), &*PARAMS).unwrap().as_prime_order(&*PARAMS).unwrap();
+
+                let tx = key_context.gen_proof(
+                    8,
+                    1,
+                    remaining_balance, 0, 0,
+                    &spending_key,
+                    MultiEncKeys::<tBls12, Confidential>::new(recipient_account_id.clone()),
+                    &enc_balance,
+                    g_epoch,
+                    rng,
+                    &*PARAMS
+                ).unwrap();
+
+                let args = (
+                    Proof::from_slice(&tx.proof[..]),
+                    EncKey::from_slice(&tx.enc_key_sender[..]),
+                    EncKey::from_slice(&tx.enc_key_recipient[..]),
+                    LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+                    LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                    LeftCiphertext::from_slice(&tx.left_fee[..]),
+                    RightCiphertext::from_slice(&tx.right_randomness[..]),
+                    Nonce::from_slice(&tx.nonce[..]),
+                    tx.fee_proof.clone(),
+                    None
+                );
+
+                (SigVerificationKey::from_slice(&tx.rvk[..]), args)
+            };
+            type Args = (Proof, EncKey, EncKey, LeftCiphertext, LeftCiphertext, LeftCiphertext, RightCiphertext, Nonce, Vec<u8>, Option<Memo>);
+            fn submit((rvk, args): &(SigVerificationKey, Args)) -> Result {
+                EncryptedBalances::confidential_transfer(
+                    Origin::signed(*rvk),
+                    args.0.clone(), args.1, args.2, args.3, args.4, args.5, args.6, args.7, args.8.clone(), args.9
+                )
+            }
+
+            // Epoch 0: the transfer is applied to alice's balance and bob's pending transfer.
+            let tx_0 = prove(91, 0);
+            assert_ok!(submit(&tx_0));
+            assert!(<zk_system::Module<Test>>::is_nonce_used(&(tx_0.1).7));
+            assert!(EncryptedBalances::total_pending_transfer(&bob).is_some());
+
+            // Replaying the transfer in the same epoch fails the extrinsic without a panic.
+            let balance = EncryptedBalances::encrypted_balance(alice);
+            assert_eq!(submit(&tx_0), Err("Provided nonce is already included in the nonce pool."));
+            assert_eq!(EncryptedBalances::encrypted_balance(alice), balance);
+
+            // Epoch 1: the nonce pool and g_epoch are reset and bob's pending transfer is rolled over
+            // by the first call touching his account, even if the call fails.
+            <system::Module<Test>>::set_block_number(1);
+            assert_eq!(submit(&tx_0), Err("Invalid zkproof"));
+            assert_eq!(<zk_system::Module<Test>>::last_epoch(), 1);
+            assert_eq!(<zk_system::Module<Test>>::g_epoch(), GEpoch::group_hash(1).unwrap());
+            assert!(!<zk_system::Module<Test>>::is_nonce_used(&(tx_0.1).7));
+            assert_eq!(EncryptedBalances::total_pending_transfer(&bob), None);
+            assert_eq!(EncryptedBalances::last_rollover(bob), Some(1));
+
+            // A fresh proof against the new g_epoch and balance succeeds.
+            let tx_1 = prove(82, 1);
+            assert_ne!((tx_1.1).7, (tx_0.1).7);
+            assert_ok!(submit(&tx_1));
+            assert!(EncryptedBalances::total_pending_transfer(&bob).is_some());
+        })
+    }
+
+    #[test]
+    fn test_pending_transfers_mature_by_epoch() {
+        with_externalities(&mut new_test_ext(), || {
+            let (alice, balance) = alice_genesis().0;
+            let (left, right) = (balance.left().unwrap(), balance.right().unwrap());
+
+            <system::Module<Test>>::set_block_number(10);
+            assert_ok!(EncryptedBalances::rollover(&alice));
+            assert_ok!(EncryptedBalances::add_pending_transfer(&alice, &left, &right));
+            assert_eq!(EncryptedBalances::pending_epochs(alice), vec![10]);
+            assert_eq!(EncryptedBalances::total_pending_count(), 1);
+
+            // A transfer received in the same epoch is not rolled over...
+            assert_ok!(EncryptedBalances::rollover(&alice));
+            assert_eq!(EncryptedBalances::encrypted_balance(alice), Some(balance));
+            assert_eq!(EncryptedBalances::rollover_count(alice), 1);
+
+            // ...but matures in the next epoch, even if new transfers arrive in it.
+            <system::Module<Test>>::set_block_number(11);
+            assert_ok!(EncryptedBalances::rollover(&alice));
+            assert_ok!(EncryptedBalances::add_pending_transfer(&alice, &left, &right));
+            assert_eq!(EncryptedBalances::encrypted_balance(alice), Some(balance.add(&balance).unwrap()));
+            assert_eq!(EncryptedBalances::pending_transfer((alice, 10)), None);
+            assert_eq!(EncryptedBalances::pending_epochs(alice), vec![11]);
+            assert_eq!(EncryptedBalances::total_pending_transfer(&alice), Some(balance));
+            assert_eq!(EncryptedBalances::total_pending_count(), 1);
+            assert_eq!(EncryptedBalances::active_account_count(), 1);
+            assert_eq!(EncryptedBalances::rollover_count(alice), 2);
+
+            // A new account becomes active at the rollover of its first transfer.
+            let bob = EncKey::from_slice(&[7u8; 32]);
+            assert_ok!(EncryptedBalances::add_pending_transfer(&bob, &left, &right));
+            assert_eq!(EncryptedBalances::total_pending_count(), 2);
+
+            <system::Module<Test>>::set_block_number(12);
+            assert_ok!(EncryptedBalances::rollover(&bob));
+            assert_eq!(EncryptedBalances::total_pending_count(), 1);
+            assert_eq!(EncryptedBalances::active_account_count(), 2);
+
+            // Anyone can roll over an account whose pending transfers matured.
+            <system::Module<Test>>::set_block_number(13);
+            assert_ok!(EncryptedBalances::force_rollover(Origin::signed(SigVerificationKey::from_slice(&[1u8; 32])), alice));
+            assert_eq!(EncryptedBalances::encrypted_balance(alice), Some(balance.add(&balance).unwrap().add(&balance).unwrap()));
+            assert_eq!(EncryptedBalances::total_pending_transfer(&alice), None);
+            assert_eq!(EncryptedBalances::total_pending_count(), 0);
+            assert_eq!(EncryptedBalances::rollover_count(alice), 3);
+        })
+    }
+
+    #[test]
+    fn test_balance_snapshot() {
+        with_externalities(&mut new_test_ext(), || {
+            let (alice, balance) = alice_genesis().0;
+            let (left, right) = (balance.left().unwrap(), balance.right().unwrap());
+
+            <system::Module<Test>>::set_block_number(10);
+            assert_ok!(EncryptedBalances::rollover(&alice));
+            assert_ok!(EncryptedBalances::add_pending_transfer(&alice, &left, &right));
+            assert_eq!(EncryptedBalances::balance_snapshot(&alice), Ok(BalanceSnapshot {
+                balance: Some(balance.clone()),
+                pending_transfer: Some(balance.clone()),
+                last_rollover: 10,
+            }));
+
+            // The snapshot of the next epoch is the account after its rollover, which it doesn't write.
+            <system::Module<Test>>::set_block_number(11);
+            let snapshot = EncryptedBalances::balance_snapshot(&alice).unwrap();
+            assert_eq!(snapshot.balance, Some(balance.add(&balance).unwrap()));
+            assert_eq!(snapshot.pending_transfer, None);
+            assert_eq!(snapshot.last_rollover, 11);
+            assert_eq!(EncryptedBalances::last_rollover(alice), Some(10));
+
+            assert_ok!(EncryptedBalances::rollover(&alice));
+            assert_eq!(EncryptedBalances::encrypted_balance(alice), snapshot.balance);
+            assert_eq!(EncryptedBalances::last_rollover(alice), Some(snapshot.last_rollover));
+
+            // Unknown accounts have nothing.
+            let bob = EncKey::from_slice(&[7u8; 32]);
+            assert_eq!(EncryptedBalances::balance_snapshot(&bob).map(|s| (s.balance, s.pending_transfer)), Ok((None, None)));
+        })
+    }
+
+    #[test]
+    fn test_reap_dead_account() {
+        with_externalities(&mut new_test_ext(), || {
+            let (alice, balance) = alice_genesis().0;
+            let (left, right) = (balance.left().unwrap(), balance.right().unwrap());
+            let origin = || Origin::signed(SigVerificationKey::from_slice(&[1u8; 32]));
+            let bob = EncKey::from_slice(&[7u8; 32]);
+
+            // Rolling over an unknown account leaves nothing but its last rollover.
+            <system::Module<Test>>::set_block_number(10);
+            assert_ok!(EncryptedBalances::force_rollover(origin(), bob));
+            assert_eq!(EncryptedBalances::encrypted_balance(bob), None);
+            assert_eq!(EncryptedBalances::active_account_count(), 1);
+            assert!(EncryptedBalances::last_rollover(bob).is_some());
+            assert!(EncryptedBalances::is_dead(&bob));
+
+            assert_eq!(EncryptedBalances::rollover_count(bob), 1);
+            assert_ok!(EncryptedBalances::reap_account(origin(), bob));
+            assert_eq!(EncryptedBalances::last_rollover(bob), None);
+            assert_eq!(EncryptedBalances::rollover_count(bob), 0);
+            assert!(EncryptedBalances::reap_account(origin(), bob).is_err());
+
+            // Accounts with a pending transfer or a balance are alive.
+            assert_ok!(EncryptedBalances::add_pending_transfer(&bob, &left, &right));
+            assert!(!EncryptedBalances::is_dead(&bob));
+            assert!(EncryptedBalances::reap_account(origin(), bob).is_err());
+            assert!(!EncryptedBalances::is_dead(&alice));
+            assert!(EncryptedBalances::reap_account(origin(), alice).is_err());
+        })
+    }
+
+    #[test]
+    fn test_rvk_is_never_dead() {
+        use rand::{SeedableRng, XorShiftRng};
+
+        with_externalities(&mut new_test_ext(), || {
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let tx = confidential_transfer(10, 1, 100, 1, rng);
+            let rvk = SigVerificationKey::from_slice(&tx.rvk[..]);
+
+            // Neither the rvk of a transfer nor an rvk with the bytes of a dead encryption key is dead,
+            // since nothing in this module tells whether an rvk is used.
+            assert!(!EncryptedBalances::is_dead_account(&rvk));
+            let bob = EncKey::from_slice(&[7u8; 32]);
+            assert!(EncryptedBalances::is_dead(&bob));
+            assert!(!EncryptedBalances::is_dead_account(&SigVerificationKey::from_slice(bob.as_bytes())));
+
+            let args = (
+                Proof::from_slice(&tx.proof[..]),
+                EncKey::from_slice(&tx.enc_key_sender[..]),
+                EncKey::from_slice(&tx.enc_key_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_fee[..]),
+                RightCiphertext::from_slice(&tx.right_randomness[..]),
+                Nonce::from_slice(&tx.nonce[..]),
+                tx.fee_proof.clone(),
+                None
+            );
+            assert_ok!(EncryptedBalances::confidential_transfer(
+                Origin::signed(rvk),
+                args.0, args.1, args.2, args.3, args.4, args.5, args.6, args.7, args.8, args.9
+            ));
+            assert!(!EncryptedBalances::is_dead_account(&rvk));
+        })
+    }
+
+    #[test]
+    fn test_add_pending_transfer_rejects_identity_randomness() {
+        with_externalities(&mut new_test_ext(), || {
+            let (alice, balance) = alice_genesis().0;
+            let mut identity = [0u8; 32];
+            identity[0] = 1;
+
+            let res = EncryptedBalances::add_pending_transfer(&alice, &balance.left().unwrap(), &RightCiphertext::from_slice(&identity));
+            assert_eq!(res, Err(Error::MalformedCiphertext(CiphertextError::IdentityRight)));
+            assert_eq!(res.unwrap_err().code(), 1);
+            assert_eq!(EncryptedBalances::pending_epochs(alice), vec![]);
+        })
+    }
+
+    #[test]
+    fn test_transfer_mutations_are_staged() {
+        with_externalities(&mut new_test_ext(), || {
+            let (alice, balance) = alice_genesis().0;
+            let (left, right) = (balance.left().unwrap(), balance.right().unwrap());
+            let bob = EncKey::from_slice(&[7u8; 32]);
+            let current_epoch = <zk_system::Module<Test>>::get_current_epoch();
+
+            // A recipient whose pending transfer can't be added to fails the transfer
+            // after the sender's mutation is staged, and nothing is written.
+            <PendingTransfer<Test>>::insert((bob, current_epoch), Ciphertext::from_slice(&[0xff; 64]));
+            let res = EncryptedBalances::stage_transfer(&alice, &left, &left, &[(bob, left)], &right);
+            assert_eq!(res.unwrap_err(), Error::PendingTransferMutation);
+            assert_eq!(EncryptedBalances::encrypted_balance(alice), Some(balance.clone()));
+
+            // Otherwise all the mutations are written at once, adding up the amounts to the same recipient.
+            <PendingTransfer<Test>>::remove((bob, current_epoch));
+            let changeset = EncryptedBalances::stage_transfer(&alice, &left, &left, &[(bob, left), (bob, left)], &right).unwrap();
+            assert_eq!(EncryptedBalances::pending_transfer((bob, current_epoch)), None);
+            EncryptedBalances::commit(changeset);
+
+            let double = balance.add(&balance).unwrap();
+            assert_eq!(EncryptedBalances::encrypted_balance(alice), Some(balance.sub(&double).unwrap()));
+            assert_eq!(EncryptedBalances::pending_transfer((bob, current_epoch)), Some(double));
+            assert_eq!(EncryptedBalances::pending_epochs(bob), vec![current_epoch]);
+        })
+    }
+
+    #[test]
+    fn test_rollover_across_epoch_length_change() {
+        with_externalities(&mut new_test_ext(), || {
+            let (alice, _) = alice_genesis().1;
+
+            <system::Module<Test>>::set_block_number(10);
+            assert_ok!(EncryptedBalances::rollover(&alice));
+            assert_eq!(EncryptedBalances::last_rollover(alice), Some(10));
+
+            // The change closes epoch 10 and starts epoch 11 at the same height,
+            // so alice is rolled over once in the new epoch...
+            assert!(<zk_system::Module<Test>>::migrate_epoch_length(5).is_ok());
+            assert_ok!(EncryptedBalances::rollover(&alice));
+            assert_eq!(EncryptedBalances::last_rollover(alice), Some(11));
+
+            // ...and not again until the epoch ends under the new length.
+            <system::Module<Test>>::set_block_number(14);
+            assert_ok!(EncryptedBalances::rollover(&alice));
+            assert_eq!(EncryptedBalances::last_rollover(alice), Some(11));
+
+            <system::Module<Test>>::set_block_number(15);
+            assert_ok!(EncryptedBalances::rollover(&alice));
+            assert_eq!(EncryptedBalances::last_rollover(alice), Some(12));
+        })
+    }
 }
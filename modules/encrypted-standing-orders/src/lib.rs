@@ -0,0 +1,774 @@
+//! A module for recurring encrypted payments, i.e. standing orders.
+//! A payer schedules an order of payments to the recipient, the first of which is made now
+//! and the others every `interval` epochs until the last one is made or the payer cancels the order.
+//!
+//! Each payment comes with the zk proof of a confidential transfer from the payer to the recipient,
+//! made against the payer's balance left by the payments before it. The proofs show that the amounts
+//! under both keys are the same and that the balance covers all the payments, which are moved out of it
+//! when the order is scheduled: the later ones into the escrow of the order, which each payment is then
+//! debited from. What is left in the escrow is credited back to the payer if the order ends early.
+//! Accounts with auditors can't have standing orders, since the payments carry no audit ciphertexts.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use support::{decl_module, decl_storage, decl_event, StorageMap, StorageValue, ensure, dispatch::Result};
+use rstd::{prelude::*, result};
+use parity_codec::{Encode, Decode};
+use runtime_primitives::traits::{Zero, One};
+use system::ensure_signed;
+use encrypted_balances::{ConfidentialFee, Error};
+use zk_system::{VerificationError, ConfidentialProof};
+use zprimitives::{EncKey, Proof, Nonce, Ciphertext, LeftCiphertext, RightCiphertext};
+
+/// The maximum number of payments executed at the start of an epoch.
+/// Payments scheduled for a full epoch are executed in the first epoch after it with room.
+pub const MAX_PAYMENTS_PER_EPOCH: usize = 64;
+
+/// The maximum number of active standing orders, which bounds the search for an epoch with room.
+pub const MAX_ACTIVE_ORDERS: u64 = 4096;
+
+/// The maximum number of payments of an order after the first one, whose proofs are all verified when it's scheduled.
+pub const MAX_LATER_PAYMENTS: usize = 16;
+
+pub type OrderId = u64;
+
+pub trait Trait: system::Trait + encrypted_balances::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+/// A payment of a standing order after the first one, with the proof that the payer's balance covers it.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Payment<AccountId> {
+    /// The zk proof of a confidential transfer of the payment from the payer to the recipient,
+    /// made against the payer's balance left by the payments before it.
+    pub zkproof: Proof,
+    /// The amount encrypted under the payer's key.
+    pub amount_payer: LeftCiphertext,
+    /// The same amount encrypted under the recipient's key.
+    pub amount_recipient: LeftCiphertext,
+    /// The fee of the transfer encrypted under the payer's key, which is paid when the order is scheduled.
+    pub fee_payer: LeftCiphertext,
+    /// The randomness shared by the encryptions.
+    pub randomness: RightCiphertext,
+    /// The re-randomized spend authority key the proof is made with.
+    pub rvk: AccountId,
+}
+
+/// A payment of a standing order which hasn't been made yet.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ScheduledPayment {
+    /// The amount encrypted under the payer's key.
+    pub amount_payer: LeftCiphertext,
+    /// The same amount encrypted under the recipient's key.
+    pub amount_recipient: LeftCiphertext,
+    /// The randomness shared by both encryptions.
+    pub randomness: RightCiphertext,
+}
+
+/// A recurring payment from the payer to the recipient.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct StandingOrder<BlockNumber> {
+    pub payer: EncKey,
+    pub recipient: EncKey,
+    /// The payments which haven't been made yet, in the order they are made.
+    pub payments: Vec<ScheduledPayment>,
+    /// The amounts of `payments` under the payer's key, which were moved out of the payer's balance.
+    pub escrow: Ciphertext,
+    /// The number of epochs between two payments.
+    pub interval: BlockNumber,
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
+        fn on_initialize(n: T::BlockNumber) {
+            let epoch = <zk_system::Module<T>>::epoch_at(n);
+            let mut due_epoch = match Self::last_processed_epoch() {
+                Some(last) if last >= epoch => return,
+                Some(last) => last + One::one(),
+                // Nothing can be due before the first processed epoch.
+                None => epoch,
+            };
+
+            while due_epoch <= epoch {
+                for id in <Due<T>>::take(due_epoch) {
+                    Self::execute(id, epoch);
+                }
+                due_epoch += One::one();
+            }
+            <LastProcessedEpoch<T>>::put(epoch);
+        }
+
+        /// Schedule a standing order. The zk proof is of a confidential transfer from the payer to the recipient,
+        /// which is made now as the first payment. Each of `payments` is made with the same nonce, against the payer's
+        /// balance left by the payments before it, and they are made every `interval` epochs from `interval` epochs later.
+        /// `fee_proof` proves the fee of the first payment is the fee of the order, which is charged once.
+        pub fn schedule(
+            origin,
+            zkproof: Proof,
+            payer: EncKey,
+            recipient: EncKey,
+            amount_payer: LeftCiphertext,
+            amount_recipient: LeftCiphertext,
+            fee_payer: LeftCiphertext,
+            randomness: RightCiphertext,
+            nonce: Nonce,
+            fee_proof: Vec<u8>,
+            payments: Vec<Payment<T::AccountId>>,
+            interval: T::BlockNumber
+        ) -> Result {
+            let rvk = ensure_signed(origin)?;
+
+            ensure!(!interval.is_zero(), "The interval must be at least one epoch.");
+            ensure!(!payments.is_empty(), "No payments are provided after the first one.");
+            ensure!(payments.len() <= MAX_LATER_PAYMENTS, "Too many payments.");
+            ensure!(Self::active_order_count() < MAX_ACTIVE_ORDERS, "Too many standing orders.");
+
+            // Reject malformed ciphertexts and accounts with auditors before any storage mutation.
+            let first_payer = Ciphertext::from_left_right(amount_payer, randomness)?;
+            let first_recipient = Ciphertext::from_left_right(amount_recipient, randomness)?;
+            let first_fee = Ciphertext::from_left_right(fee_payer, randomness)?;
+            let fee = <T as encrypted_balances::Trait>::ConfidentialFee::confidential_fee(0);
+            <encrypted_balances::Module<T>>::check_fee(&payer, &fee_payer, &randomness, fee, &fee_proof[..])?;
+            <zk_auditor::Module<T>>::check_audits(&[(payer, amount_payer), (recipient, amount_recipient)], &randomness, &[])?;
+            for payment in &payments {
+                <zk_auditor::Module<T>>::check_audits(
+                    &[(payer, payment.amount_payer), (recipient, payment.amount_recipient)],
+                    &payment.randomness,
+                    &[]
+                )?;
+            }
+
+            // The balance the proofs are against is the one after the rollover.
+            <encrypted_balances::Module<T>>::rollover(&payer)?;
+            <encrypted_balances::Module<T>>::rollover(&recipient)?;
+
+            let balance = <encrypted_balances::Module<T>>::encrypted_balance(payer).map_or(Ciphertext::zero(), |e| e);
+            let mut proofs = Vec::with_capacity(1 + payments.len());
+            proofs.push(ConfidentialProof {
+                zkproof,
+                address_sender: payer,
+                address_recipient: recipient,
+                amount_sender: amount_payer,
+                amount_recipient,
+                balance_sender: balance.clone(),
+                rvk,
+                fee_sender: fee_payer,
+                randomness,
+                nonce,
+            });
+
+            // The balance left after each payment, which the proof of the next payment is made against,
+            // the fees of all the payments, and the escrow of the later payments.
+            let mut left = balance.sub(&first_payer)
+                .and_then(|l| l.sub(&first_fee))
+                .map_err(|_| "Faild to subtract the payment from the balance.")?;
+            let mut fees = first_fee;
+            let mut escrow = Ciphertext::zero();
+            let mut scheduled = Vec::with_capacity(payments.len());
+            for payment in payments {
+                Ciphertext::from_left_right(payment.amount_recipient, payment.randomness)?;
+                let enc_amount = Ciphertext::from_left_right(payment.amount_payer, payment.randomness)?;
+                let enc_fee = Ciphertext::from_left_right(payment.fee_payer, payment.randomness)?;
+
+                proofs.push(ConfidentialProof {
+                    zkproof: payment.zkproof,
+                    address_sender: payer,
+                    address_recipient: recipient,
+                    amount_sender: payment.amount_payer,
+                    amount_recipient: payment.amount_recipient,
+                    balance_sender: left.clone(),
+                    rvk: payment.rvk,
+                    fee_sender: payment.fee_payer,
+                    randomness: payment.randomness,
+                    nonce,
+                });
+
+                left = left.sub(&enc_amount)
+                    .and_then(|l| l.sub(&enc_fee))
+                    .map_err(|_| "Faild to subtract the payment from the balance.")?;
+                fees = fees.add(&enc_fee)
+                    .map_err(|_| "Faild to add the fees of the payments.")?;
+                escrow = escrow.add(&enc_amount)
+                    .map_err(|_| "Faild to add the payment to the escrow.")?;
+                scheduled.push(ScheduledPayment {
+                    amount_payer: payment.amount_payer,
+                    amount_recipient: payment.amount_recipient,
+                    randomness: payment.randomness,
+                });
+            }
+            let debit = first_payer.add(&fees)
+                .and_then(|d| d.add(&escrow))
+                .map_err(|_| "Faild to add the payments to the debit.")?;
+
+            // Reserve the provided nonce, which fails if it's included in the nonce pool.
+            let ticket = <zk_system::Module<T>>::reserve_nonce(nonce)?;
+
+            // Verify the zk proofs
+            if let Err(e) = <zk_system::Module<T>>::verify_confidential_proofs_batch(&proofs[..]) {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                if e == VerificationError::InvalidProof {
+                    Self::deposit_event(RawEvent::InvalidZkProof());
+                }
+                return Err(e.into());
+            }
+
+            // Subtracting all the payments and fees from the payer's encrypted balance
+            // and adding the first payment to the recipient's pending transfer.
+            // This function causes a storage mutation.
+            if let Err(e) = <encrypted_balances::Module<T>>::move_balance(&payer, &debit, &[(recipient, first_recipient)]) {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                return Err(e.into());
+            }
+
+            // Add a nonce into the nonce pool
+            <zk_system::Module<T>>::commit_nonce(ticket);
+
+            <zk_stats::Module<T>>::note_confidential_transfer(&[payer, recipient], &fees);
+            <zk_stats::Module<T>>::note_fee_charged(fee);
+
+            let id = Self::next_order_id();
+            let payment_count = 1 + scheduled.len() as u32;
+            <Orders<T>>::insert(id, StandingOrder {
+                payer,
+                recipient,
+                payments: scheduled,
+                escrow,
+                interval,
+            });
+            <NextOrderId<T>>::put(id + 1);
+            <ActiveOrderCount<T>>::mutate(|count| *count += 1);
+            Self::insert_due(id, <zk_system::Module<T>>::get_current_epoch() + interval);
+
+            Self::deposit_event(RawEvent::Scheduled(id, payer, recipient, interval, payment_count));
+
+            Ok(())
+        }
+
+        /// Cancel a standing order and credit its escrow back to the payer. The zk proof is a confidential
+        /// transfer from the payer to itself, as in `EncryptedAirdrop::claim`, which proves the knowledge
+        /// of the payer's decryption key.
+        pub fn cancel(
+            origin,
+            order_id: OrderId,
+            zkproof: Proof,
+            amount: LeftCiphertext,
+            fee: LeftCiphertext,
+            balance: Ciphertext,
+            randomness: RightCiphertext,
+            nonce: Nonce
+        ) -> Result {
+            let rvk = ensure_signed(origin)?;
+
+            let order = Self::order(order_id).ok_or("The standing order doesn't exist.")?;
+            Ciphertext::from_left_right(amount, randomness)?;
+            Ciphertext::from_left_right(fee, randomness)?;
+
+            // The balance the proof is against is the one after the rollover.
+            <encrypted_balances::Module<T>>::rollover(&order.payer)?;
+
+            // Reserve the provided nonce, which fails if it's included in the nonce pool.
+            let ticket = <zk_system::Module<T>>::reserve_nonce(nonce)?;
+
+            if let Err(e) = <zk_system::Module<T>>::verify_confidential_proof(
+                &zkproof,
+                &order.payer,
+                &order.payer,
+                &amount,
+                &amount,
+                &balance,
+                &rvk,
+                &fee,
+                &randomness,
+                &nonce
+            ) {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                if e == VerificationError::InvalidProof {
+                    Self::deposit_event(RawEvent::InvalidZkProof());
+                }
+                return Err(e.into());
+            }
+
+            // This function causes a storage mutation.
+            if let Err(e) = Self::refund(&order) {
+                <zk_system::Module<T>>::abort_nonce(ticket);
+                return Err(e);
+            }
+
+            // Add a nonce into the nonce pool
+            <zk_system::Module<T>>::commit_nonce(ticket);
+
+            // The due entry of the order is skipped once the order is gone.
+            Self::remove_order(order_id);
+            Self::deposit_event(RawEvent::Cancelled(order_id, order.escrow));
+
+            Ok(())
+        }
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as EncryptedStandingOrders {
+        /// Standing orders which haven't been completed, failed or been cancelled.
+        pub Orders get(order) : map OrderId => Option<StandingOrder<T::BlockNumber>>;
+
+        /// The next order identifier up for grabs.
+        pub NextOrderId get(next_order_id) : OrderId;
+
+        /// The number of standing orders which haven't been completed, failed or been cancelled.
+        pub ActiveOrderCount get(active_order_count) : u64;
+
+        /// The orders whose next payment is due in each epoch.
+        pub Due get(due) : map T::BlockNumber => Vec<OrderId>;
+
+        /// The last epoch whose due payments were executed.
+        pub LastProcessedEpoch get(last_processed_epoch) : Option<T::BlockNumber>;
+    }
+}
+
+decl_event! (
+    pub enum Event<T> where <T as system::Trait>::BlockNumber {
+        /// A standing order from the payer to the recipient was scheduled with the interval and the number of payments.
+        Scheduled(OrderId, EncKey, EncKey, BlockNumber, u32),
+        /// A payment of the standing order was made in the epoch.
+        Paid(OrderId, BlockNumber),
+        /// A payment of the standing order failed with the code of `encrypted_balances::Error`,
+        /// or 0 if an account has an auditor, and the order was removed with its escrow credited back to the payer.
+        Failed(OrderId, u8),
+        /// The last payment of the standing order was made.
+        Completed(OrderId),
+        /// The standing order was cancelled by the payer, and the escrow was credited back to the payer.
+        Cancelled(OrderId, Ciphertext),
+        InvalidZkProof(),
+    }
+);
+
+impl<T: Trait> Module<T> {
+    // Make the next payment of the order and schedule the one after it.
+    fn execute(id: OrderId, epoch: T::BlockNumber) {
+        let mut order = match Self::order(id) {
+            Some(order) => order,
+            None => return,
+        };
+        if order.payments.is_empty() {
+            Self::remove_order(id);
+            return;
+        }
+        let payment = order.payments.remove(0);
+
+        let escrow = match Self::pay(&order, &payment) {
+            Ok(escrow) => escrow,
+            Err(code) => {
+                // The escrow was valid when the order was scheduled, so crediting it can't fail
+                // for a malformed ciphertext, and there is nothing else to do with it if it does.
+                let _ = Self::refund(&order);
+                Self::remove_order(id);
+                Self::deposit_event(RawEvent::Failed(id, code));
+                return;
+            }
+        };
+        Self::deposit_event(RawEvent::Paid(id, epoch));
+
+        if order.payments.is_empty() {
+            Self::remove_order(id);
+            Self::deposit_event(RawEvent::Completed(id));
+        } else {
+            let next_payment = epoch + order.interval;
+            order.escrow = escrow;
+            <Orders<T>>::insert(id, order);
+            Self::insert_due(id, next_payment);
+        }
+    }
+
+    // Credit the payment to the recipient and return the escrow left after it,
+    // or the code of the error, which is 0 if an account has an auditor.
+    fn pay(order: &StandingOrder<T::BlockNumber>, payment: &ScheduledPayment) -> result::Result<Ciphertext, u8> {
+        <zk_auditor::Module<T>>::check_audits(
+            &[(order.payer, payment.amount_payer), (order.recipient, payment.amount_recipient)],
+            &payment.randomness,
+            &[]
+        ).map_err(|_| 0)?;
+
+        let amount_payer = Ciphertext::from_left_right(payment.amount_payer, payment.randomness)
+            .map_err(|e| Error::from(e).code())?;
+        let escrow = order.escrow.sub(&amount_payer)
+            .map_err(|_| Error::BalanceMutation.code())?;
+
+        <encrypted_balances::Module<T>>::rollover(&order.recipient)
+            .and_then(|_| <encrypted_balances::Module<T>>::add_pending_transfer(
+                &order.recipient,
+                &payment.amount_recipient,
+                &payment.randomness
+            ))
+            .map_err(|e| e.code())?;
+
+        Ok(escrow)
+    }
+
+    // Credit the escrow of the order back to the payer.
+    fn refund(order: &StandingOrder<T::BlockNumber>) -> Result {
+        let left = order.escrow.left().map_err(|_| "Invalid ciphertext of the escrow.")?;
+        let right = order.escrow.right().map_err(|_| "Invalid ciphertext of the escrow.")?;
+
+        <encrypted_balances::Module<T>>::rollover(&order.payer)?;
+        <encrypted_balances::Module<T>>::add_pending_transfer(&order.payer, &left, &right)?;
+
+        Ok(())
+    }
+
+    // Schedule the order in the first epoch from `epoch` with room for a payment.
+    fn insert_due(id: OrderId, epoch: T::BlockNumber) {
+        let mut epoch = epoch;
+        while Self::due(epoch).len() >= MAX_PAYMENTS_PER_EPOCH {
+            epoch += One::one();
+        }
+        <Due<T>>::mutate(epoch, |ids| ids.push(id));
+    }
+
+    fn remove_order(id: OrderId) {
+        <Orders<T>>::remove(id);
+        <ActiveOrderCount<T>>::mutate(|count| *count = count.saturating_sub(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use support::{impl_outer_origin, assert_ok};
+    use primitives::{H256, Blake2Hasher};
+    use runtime_primitives::{
+        BuildStorage, traits::{BlakeTwo256, IdentityLookup, OnInitialize},
+        testing::{Digest, DigestItem, Header}
+    };
+    use zprimitives::SigVerificationKey;
+    use keys::{ProofGenerationKey, EncryptionKey};
+    use jubjub::curve::{JubjubBls12, FixedGenerators, fs::Fs};
+    use pairing::{Field, bls12_381::Bls12};
+    use zcrypto::elgamal;
+    use rand::{SeedableRng, XorShiftRng};
+    use test_pairing::bls12_381::Bls12 as tBls12;
+    use test_proofs::{EncryptionKey as tEncryptionKey, SpendingKey as tSpendingKey,
+        elgamal as telgamal, PARAMS, MultiEncKeys, KeyContext, ProofBuilder, Confidential,
+        confidential::ConfidentialXt,
+    };
+    use test_fixtures::{ALICE_SEED, BOB_SEED, CONF_PK, CONF_VK, params_path, conf_vk, anony_vk, alice_genesis, address, g_epoch};
+    use std::convert::TryFrom;
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = SigVerificationKey;
+        type SigVerificationKey = u64;
+        type Lookup = IdentityLookup<SigVerificationKey>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl encrypted_balances::Trait for Test {
+        type Event = ();
+        type ConfidentialFee = encrypted_balances::FlatFee<Test>;
+    }
+
+    impl zk_system::Trait for Test {
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl zk_stats::Trait for Test { }
+
+    impl zk_auditor::Trait for Test {
+        type Event = ();
+    }
+
+    impl Trait for Test {
+        type Event = ();
+    }
+
+    type EncryptedBalances = encrypted_balances::Module<Test>;
+    type StandingOrders = Module<Test>;
+    type ZkSystem = zk_system::Module<Test>;
+
+    fn dec_key(seed: &[u8]) -> keys::DecryptionKey<Bls12> {
+        ProofGenerationKey::<Bls12>::from_seed(seed, &JubjubBls12::new()).into_decryption_key().unwrap()
+    }
+
+    fn enc_key(seed: &[u8]) -> EncryptionKey<Bls12> {
+        EncryptionKey::<Bls12>::from_seed(seed, &JubjubBls12::new()).unwrap()
+    }
+
+    fn decrypt(balance: Ciphertext, seed: &[u8]) -> Option<u64> {
+        elgamal::Ciphertext::<Bls12>::try_from(balance).unwrap()
+            .decrypt(&dec_key(seed), FixedGenerators::Diversifier, &JubjubBls12::new())
+    }
+
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let ((alice, balance), _) = alice_genesis();
+
+        let (mut t, mut c) = system::GenesisConfig::<Test>::default().build_storage().unwrap();
+        let _ = zk_system::GenesisConfig::<Test>{
+            last_epoch: 1,
+            epoch_length: 1,
+            max_anonymity_set_size: 12,
+            nonce_retention_epochs: 0,
+            confidential_vk: conf_vk(),
+            anonymous_vk: anony_vk(),
+            confidential_pk_hash: [0u8; 32],
+            anonymous_pk_hash: [0u8; 32],
+        }.assimilate_storage(&mut t, &mut c);
+        let _ = encrypted_balances::GenesisConfig::<Test>{
+            encrypted_balance: vec![(alice, balance)],
+            last_rollover: vec![(alice, 0)],
+            transaction_base_fee: 1,
+            anonymous_fee: 0,
+            _genesis_phantom_data: Default::default()
+        }.assimilate_storage(&mut t, &mut c);
+
+        zk_system::pool::test_externalities(t, c)
+    }
+
+    // Prove a confidential transfer of `amount` with the fee from Alice's account to the recipient's in the epoch.
+    fn prove_transfer(
+        recipient_seed: &[u8],
+        amount: u64,
+        fee: u64,
+        remaining_balance: u64,
+        balance: &Ciphertext,
+        epoch: u32,
+        rng: &mut XorShiftRng
+    ) -> ConfidentialXt {
+        let spending_key = tSpendingKey::<tBls12>::from_seed(ALICE_SEED);
+        let enc_key = tEncryptionKey::<tBls12>::from_seed(recipient_seed, &PARAMS).unwrap();
+        let enc_balance = vec![telgamal::Ciphertext::read(&mut balance.as_bytes(), &*PARAMS).unwrap()];
+
+        KeyContext::read_from_path(params_path(CONF_PK), params_path(CONF_VK))
+            .unwrap()
+            .gen_proof(
+                amount,
+                fee,
+                remaining_balance, 0, 0,
+                &spending_key,
+                MultiEncKeys::<tBls12, Confidential>::new(enc_key),
+                &enc_balance,
+                g_epoch(epoch),
+                rng,
+                &*PARAMS
+            ).unwrap()
+    }
+
+    // A later payment of `amount` to Bob, proven against the balance left by the payments before it.
+    fn new_payment(amount: u64, remaining_balance: u64, balance: &Ciphertext, rng: &mut XorShiftRng) -> Payment<SigVerificationKey> {
+        let tx = prove_transfer(BOB_SEED, amount, 0, remaining_balance, balance, 0, rng);
+
+        Payment {
+            zkproof: Proof::from_slice(&tx.proof[..]),
+            amount_payer: LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+            amount_recipient: LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+            fee_payer: LeftCiphertext::from_slice(&tx.left_fee[..]),
+            randomness: RightCiphertext::from_slice(&tx.right_randomness[..]),
+            rvk: SigVerificationKey::from_slice(&tx.rvk[..]),
+        }
+    }
+
+    // The balance left for the payments after `payment`.
+    fn balance_after(balance: &Ciphertext, payment: &Payment<SigVerificationKey>) -> Ciphertext {
+        balance.sub(&Ciphertext::from_left_right(payment.amount_payer, payment.randomness).unwrap())
+            .and_then(|b| b.sub(&Ciphertext::from_left_right(payment.fee_payer, payment.randomness).unwrap()))
+            .unwrap()
+    }
+
+    type ScheduleArgs = (Proof, EncKey, EncKey, LeftCiphertext, LeftCiphertext, LeftCiphertext, RightCiphertext, Nonce, Vec<u8>, Vec<Payment<SigVerificationKey>>, u64);
+
+    // Alice's order to Bob every 2 epochs, whose first payment of `first` is made with the fee 1 from her genesis balance of 100.
+    // The later payments are made by `payments` from the balance left by the first one.
+    fn schedule_args<F>(first: u64, payments: F) -> (SigVerificationKey, ScheduleArgs)
+        where F: FnOnce(&Ciphertext, &mut XorShiftRng) -> Vec<Payment<SigVerificationKey>>
+    {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let (alice, _) = alice_genesis().0;
+        let balance = EncryptedBalances::encrypted_balance(alice).unwrap();
+        let tx = prove_transfer(BOB_SEED, first, 1, 99 - first, &balance, 0, rng);
+        let amount_payer = LeftCiphertext::from_slice(&tx.left_amount_sender[..]);
+        let fee_payer = LeftCiphertext::from_slice(&tx.left_fee[..]);
+        let randomness = RightCiphertext::from_slice(&tx.right_randomness[..]);
+        let left = balance.sub(&Ciphertext::from_left_right(amount_payer, randomness).unwrap())
+            .and_then(|b| b.sub(&Ciphertext::from_left_right(fee_payer, randomness).unwrap()))
+            .unwrap();
+
+        let args = (
+            Proof::from_slice(&tx.proof[..]),
+            EncKey::from_slice(&tx.enc_key_sender[..]),
+            EncKey::from_slice(&tx.enc_key_recipient[..]),
+            amount_payer,
+            LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+            fee_payer,
+            randomness,
+            Nonce::from_slice(&tx.nonce[..]),
+            tx.fee_proof.clone(),
+            payments(&left, rng),
+            2u64
+        );
+
+        (SigVerificationKey::from_slice(&tx.rvk[..]), args)
+    }
+
+    fn schedule((rvk, args): (SigVerificationKey, ScheduleArgs)) -> Result {
+        StandingOrders::schedule(
+            Origin::signed(rvk),
+            args.0, args.1, args.2, args.3, args.4, args.5, args.6, args.7, args.8, args.9, args.10
+        )
+    }
+
+    // Alice pays 10 to Bob now and twice more every 2 epochs.
+    fn schedule_three_payments() -> Result {
+        schedule(schedule_args(10, |balance, rng| {
+            let second = new_payment(10, 79, balance, rng);
+            let balance = balance_after(balance, &second);
+            let third = new_payment(10, 69, &balance, rng);
+            vec![second, third]
+        }))
+    }
+
+    #[test]
+    fn test_payments_until_completed() {
+        with_externalities(&mut new_test_ext(), || {
+            let (alice, bob) = (address(ALICE_SEED), address(BOB_SEED));
+
+            assert_ok!(schedule_three_payments());
+            assert_eq!(decrypt(EncryptedBalances::encrypted_balance(alice).unwrap(), ALICE_SEED), Some(69));
+            assert_eq!(decrypt(StandingOrders::order(0).unwrap().escrow, ALICE_SEED), Some(20));
+            assert_eq!(StandingOrders::active_order_count(), 1);
+
+            for n in 1..=10 {
+                <system::Module<Test>>::set_block_number(n);
+                StandingOrders::on_initialize(n);
+            }
+
+            // Paid in the epochs 2 and 4 from the escrow, which is used up.
+            assert_eq!(StandingOrders::order(0), None);
+            assert_eq!(StandingOrders::active_order_count(), 0);
+            assert_ok!(EncryptedBalances::rollover(&alice));
+            assert_ok!(EncryptedBalances::rollover(&bob));
+            assert_eq!(decrypt(EncryptedBalances::encrypted_balance(alice).unwrap(), ALICE_SEED), Some(69));
+            assert_eq!(decrypt(EncryptedBalances::encrypted_balance(bob).unwrap(), BOB_SEED), Some(30));
+        })
+    }
+
+    #[test]
+    fn test_schedule_rejects_payments_above_the_balance() {
+        with_externalities(&mut new_test_ext(), || {
+            let alice = address(ALICE_SEED);
+
+            // Both later payments of 40 are covered by the balance of 59 left by the first one alone, but not together.
+            let args = schedule_args(40, |balance, rng| vec![
+                new_payment(40, 19, balance, rng),
+                new_payment(40, 19, balance, rng),
+            ]);
+            let nonce = (args.1).7;
+            assert_eq!(schedule(args), Err("Invalid zkproof"));
+
+            // Nothing is debited or recorded by the failed call.
+            assert!(!ZkSystem::is_nonce_used(&nonce));
+            assert!(!ZkSystem::is_nonce_reserved(&nonce));
+            assert_eq!(decrypt(EncryptedBalances::encrypted_balance(alice).unwrap(), ALICE_SEED), Some(100));
+            assert_eq!(StandingOrders::next_order_id(), 0);
+
+            assert_ok!(schedule(schedule_args(40, |balance, rng| vec![new_payment(40, 19, balance, rng)])));
+            assert!(ZkSystem::is_nonce_used(&nonce));
+        })
+    }
+
+    #[test]
+    fn test_cancel_refunds_the_escrow() {
+        with_externalities(&mut new_test_ext(), || {
+            let alice = address(ALICE_SEED);
+            assert_ok!(schedule_three_payments());
+
+            // The second payment is made in the epoch 2.
+            <system::Module<Test>>::set_block_number(2);
+            ZkSystem::on_initialize(2);
+            StandingOrders::on_initialize(2);
+            assert_eq!(decrypt(StandingOrders::order(0).unwrap().escrow, ALICE_SEED), Some(10));
+
+            // Alice proves the knowledge of her key with a zero transfer to herself.
+            let dummy_balance = Ciphertext::try_from(elgamal::Ciphertext::encrypt(
+                0, &Fs::one(), &enc_key(ALICE_SEED), FixedGenerators::Diversifier, &JubjubBls12::new()
+            )).unwrap();
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let tx = prove_transfer(ALICE_SEED, 0, 0, 0, &dummy_balance, 2, rng);
+            let cancel = || StandingOrders::cancel(
+                Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..])),
+                0,
+                Proof::from_slice(&tx.proof[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+                LeftCiphertext::from_slice(&tx.left_fee[..]),
+                dummy_balance.clone(),
+                RightCiphertext::from_slice(&tx.right_randomness[..]),
+                Nonce::from_slice(&tx.nonce[..])
+            );
+
+            assert_ok!(cancel());
+            assert_eq!(StandingOrders::order(0), None);
+            assert_eq!(StandingOrders::active_order_count(), 0);
+            assert!(ZkSystem::is_nonce_used(&Nonce::from_slice(&tx.nonce[..])));
+            assert_eq!(decrypt(EncryptedBalances::total_pending_transfer(&alice).unwrap(), ALICE_SEED), Some(10));
+
+            assert_eq!(cancel(), Err("The standing order doesn't exist."));
+        })
+    }
+
+    #[test]
+    fn test_full_epoch_defers_payments() {
+        with_externalities(&mut new_test_ext(), || {
+            for id in 0..MAX_PAYMENTS_PER_EPOCH as u64 {
+                StandingOrders::insert_due(id, 3);
+            }
+            StandingOrders::insert_due(100, 3);
+
+            assert_eq!(StandingOrders::due(3).len(), MAX_PAYMENTS_PER_EPOCH);
+            assert_eq!(StandingOrders::due(4), vec![100]);
+        })
+    }
+
+    #[test]
+    fn test_schedule_checks_interval_and_payments() {
+        with_externalities(&mut new_test_ext(), || {
+            let origin = || Origin::signed(SigVerificationKey::from_slice(&[1u8; 32]));
+            let (alice, bob) = (address(ALICE_SEED), address(BOB_SEED));
+            let payment = Payment {
+                zkproof: Proof::default(),
+                amount_payer: LeftCiphertext::default(),
+                amount_recipient: LeftCiphertext::default(),
+                fee_payer: LeftCiphertext::default(),
+                randomness: RightCiphertext::default(),
+                rvk: SigVerificationKey::from_slice(&[1u8; 32]),
+            };
+            let schedule = |payments: usize, interval: u64| {
+                StandingOrders::schedule(origin(), Proof::default(), alice, bob, LeftCiphertext::default(),
+                    LeftCiphertext::default(), LeftCiphertext::default(), RightCiphertext::default(), Nonce::default(),
+                    vec![], vec![payment.clone(); payments], interval)
+            };
+
+            assert_eq!(schedule(1, 0), Err("The interval must be at least one epoch."));
+            assert_eq!(schedule(0, 2), Err("No payments are provided after the first one."));
+            assert_eq!(schedule(MAX_LATER_PAYMENTS + 1, 2), Err("Too many payments."));
+            assert_eq!(StandingOrders::next_order_id(), 0);
+        })
+    }
+}
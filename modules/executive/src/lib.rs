@@ -49,6 +49,18 @@ mod internal {
 	}
 }
 
+/// A call with a weight, i.e. a bound of its execution time, charged against the weight limit of the block.
+pub trait Weighable {
+	/// The maximum sum of the weights of the extrinsics of a block.
+	const MAX_BLOCK_WEIGHT: u32;
+
+	/// The weight of dispatching the call.
+	fn weight(&self) -> u32;
+}
+
+/// The call of an extrinsic of the block.
+type CallOf<Block, Context> = <<<Block as BlockT>::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call;
+
 /// Something that can be used to execute a block.
 pub trait ExecuteBlock<Block: BlockT> {
 	/// Actually execute all transitioning for `block`.
@@ -67,7 +79,7 @@ impl<
 > ExecuteBlock<Block> for Executive<System, Block, Context, AllModules> where
 	Block::Extrinsic: Checkable<Context> + Codec,
 	<Block::Extrinsic as Checkable<Context>>::Checked: Applyable<Index=System::Index, AccountId=System::AccountId>,
-	<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call: Dispatchable,
+	<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call: Dispatchable + Weighable,
 	<<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call as Dispatchable>::Origin: From<Option<System::AccountId>>
 {
 	fn execute_block(block: Block) {
@@ -83,7 +95,7 @@ impl<
 > Executive<System, Block, Context, AllModules> where
 	Block::Extrinsic: Checkable<Context> + Codec,
 	<Block::Extrinsic as Checkable<Context>>::Checked: Applyable<Index=System::Index, AccountId=System::AccountId>,
-	<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call: Dispatchable,
+	<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call: Dispatchable + Weighable,
 	<<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call as Dispatchable>::Origin: From<Option<System::AccountId>>
 {
 	/// Start the execution of a particular block.
@@ -193,8 +205,20 @@ impl<
 			if index != &expected_index { return Err(
 				if index < &expected_index { internal::ApplyError::Stale } else { internal::ApplyError::Future }
 			) }
+		}
+		let signed = xt.index().is_some();
 
-			// increment nonce in storage
+		// decode parameters
+		let (f, s) = xt.deconstruct();
+
+		// Check the weight of the block if that extrinsic is applied.
+		let weight = f.weight();
+		if <system::Module<System>>::all_extrinsics_weight().saturating_add(weight) > <CallOf<Block, Context> as Weighable>::MAX_BLOCK_WEIGHT {
+			return Err(internal::ApplyError::FullBlock);
+		}
+
+		// increment nonce in storage
+		if let (true, Some(sender)) = (signed, s.as_ref()) {
 			<system::Module<System>>::inc_account_nonce(sender);
 		}
 
@@ -204,8 +228,8 @@ impl<
 			<system::Module<System>>::note_extrinsic(encoded);
 		}
 
-		// decode parameters and dispatch
-		let (f, s) = xt.deconstruct();
+		// dispatch
+		<system::Module<System>>::note_extrinsic_weight(weight);
 		let r = f.dispatch(s.into());
 		<system::Module<System>>::note_applied_extrinsic(&r, encoded_len as u32);
 
@@ -260,34 +284,42 @@ impl<
 			Err(_) => return TransactionValidity::Invalid(UNKNOWN_ERROR),
 		};
 
-		if let (Some(sender), Some(index)) = (xt.sender(), xt.index()) {
-			// check index
-			let mut expected_index = <system::Module<System>>::account_nonce(sender);
-			if index < &expected_index {
-				return TransactionValidity::Invalid(ApplyError::Stale as i8)
-			}
-			if *index > expected_index + As::sa(256) {
-				return TransactionValidity::Unknown(ApplyError::Future as i8)
-			}
-
-			let mut deps = Vec::new();
-			while expected_index < *index {
-				deps.push((sender, expected_index).encode());
-				expected_index = expected_index + One::one();
-			}
-
-			TransactionValidity::Valid {
-				priority: encoded_len as TransactionPriority,
-				requires: deps,
-				provides: vec![(sender, *index).encode()],
-				longevity: TransactionLongevity::max_value(),
-			}
-		} else {
-			return TransactionValidity::Invalid(if xt.sender().is_none() {
+		let (sender, index) = match (xt.sender(), xt.index()) {
+			(Some(sender), Some(index)) => (sender.clone(), index.clone()),
+			_ => return TransactionValidity::Invalid(if xt.sender().is_none() {
 				MISSING_SENDER
 			} else {
 				INVALID_INDEX
-			})
+			}),
+		};
+
+		// An extrinsic heavier than a whole block could never be included,
+		// and would keep failing the blocks it is tried in.
+		let (f, _) = xt.deconstruct();
+		if f.weight() > <CallOf<Block, Context> as Weighable>::MAX_BLOCK_WEIGHT {
+			return TransactionValidity::Invalid(ApplyError::FullBlock as i8)
+		}
+
+		// check index
+		let mut expected_index = <system::Module<System>>::account_nonce(&sender);
+		if index < expected_index {
+			return TransactionValidity::Invalid(ApplyError::Stale as i8)
+		}
+		if index > expected_index + As::sa(256) {
+			return TransactionValidity::Unknown(ApplyError::Future as i8)
+		}
+
+		let mut deps = Vec::new();
+		while expected_index < index {
+			deps.push((&sender, expected_index).encode());
+			expected_index = expected_index + One::one();
+		}
+
+		TransactionValidity::Valid {
+			priority: encoded_len as TransactionPriority,
+			requires: deps,
+			provides: vec![(&sender, index).encode()],
+			longevity: TransactionLongevity::max_value(),
 		}
 	}
 
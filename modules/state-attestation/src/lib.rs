@@ -0,0 +1,363 @@
+//! A module where validators periodically co-sign a commitment to the chain state.
+//! The attesters aggregate their keys with multi-RedDSA and submit a single signature
+//! over the hash and the state root of a past block, so light wallets can check
+//! read proofs of shielded balances against a root backed by the whole attester set
+//! rather than a single node's word.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use support::{decl_module, decl_storage, decl_event, StorageValue, StorageMap, ensure, dispatch::Result};
+use rstd::{
+    prelude::*,
+    result,
+    convert::TryFrom,
+};
+use parity_codec::{Encode, Decode};
+use runtime_primitives::traits::Zero;
+use system::{ensure_signed, ensure_root};
+use jubjub::{redjubjub, curve::FixedGenerators};
+use pairing::bls12_381::Bls12;
+use multi_reddsa::SignerKeys;
+use zprimitives::{SigVerificationKey, RedjubjubSignature, PARAMS};
+
+/// Domain separator of the signed attestation message.
+const ATTESTATION_CONTEXT: &[u8] = b"Zerochain-StateAttestation";
+
+pub trait Trait: system::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+/// A commitment to the state at a block, co-signed by all attesters.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Attestation<BlockNumber, Hash> {
+    /// The attested block number.
+    pub number: BlockNumber,
+    /// The hash of the attested block.
+    pub block_hash: Hash,
+    /// The state root in the header of the attested block.
+    pub state_root: Hash,
+    /// The aggregated multi-RedDSA signature of the attesters.
+    pub signature: RedjubjubSignature,
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
+        /// Submit an attestation of the state at the block `number`.
+        /// Anyone can relay it, since the signature must be made by all attesters.
+        pub fn attest(
+            origin,
+            number: T::BlockNumber,
+            block_hash: T::Hash,
+            state_root: T::Hash,
+            signature: RedjubjubSignature
+        ) -> Result {
+            ensure_signed(origin)?;
+
+            let period = Self::attestation_period();
+            ensure!(!period.is_zero() && (number % period).is_zero(), "The block is not at an attestation period.");
+            ensure!(number < <system::Module<T>>::block_number(), "Cannot attest to a future block.");
+            ensure!(Self::attestation(number).is_none(), "The block has already been attested.");
+            ensure!(<system::Module<T>>::block_hash(number) == block_hash, "Mismatch the hash of the attested block.");
+
+            let msg = Self::attestation_message(&number, &block_hash, &state_root);
+            ensure!(Self::verify_aggregated_signature(&msg[..], &signature)?, "Invalid signature of attesters.");
+
+            <Attestations<T>>::insert(number, Attestation {
+                number,
+                block_hash,
+                state_root,
+                signature,
+            });
+            if Self::latest_attested().map_or(true, |latest| latest < number) {
+                <LatestAttested<T>>::put(number);
+            }
+
+            Self::deposit_event(RawEvent::Attested(number, block_hash, state_root));
+
+            Ok(())
+        }
+
+        /// Replace the set of attesters. Only callable by root.
+        pub fn set_attesters(origin, attesters: Vec<SigVerificationKey>) -> Result {
+            ensure_root(origin)?;
+            ensure!(attesters.len() > 1, "At least two attesters are required.");
+
+            let count = attesters.len() as u32;
+            <Attesters<T>>::put(attesters);
+            Self::deposit_event(RawEvent::AttestersChanged(count));
+
+            Ok(())
+        }
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as StateAttestation {
+        /// Signature verification keys of the validators co-signing attestations.
+        pub Attesters get(attesters) config() : Vec<SigVerificationKey>;
+
+        /// Attestations are only accepted for blocks whose number is a multiple of this period.
+        pub AttestationPeriod get(attestation_period) config() : T::BlockNumber;
+
+        /// Attestations by the attested block number.
+        pub Attestations get(attestation) : map T::BlockNumber => Option<Attestation<T::BlockNumber, T::Hash>>;
+
+        /// The block number of the most recent attestation.
+        pub LatestAttested get(latest_attested) : Option<T::BlockNumber>;
+    }
+}
+
+decl_event! (
+    pub enum Event<T>
+    where
+        <T as system::Trait>::BlockNumber,
+        <T as system::Trait>::Hash
+    {
+        /// The state at the block was attested, with its block hash and state root.
+        Attested(BlockNumber, Hash, Hash),
+        /// The attester set was replaced by the given number of attesters.
+        AttestersChanged(u32),
+    }
+);
+
+impl<T: Trait> Module<T> {
+    /// The message the attesters sign for a block.
+    pub fn attestation_message(number: &T::BlockNumber, block_hash: &T::Hash, state_root: &T::Hash) -> Vec<u8> {
+        let mut msg = ATTESTATION_CONTEXT.to_vec();
+        (number, block_hash, state_root).encode_to(&mut msg);
+        msg
+    }
+
+    /// Get the most recent attestation.
+    pub fn latest_attestation() -> Option<Attestation<T::BlockNumber, T::Hash>> {
+        Self::latest_attested().and_then(|number| Self::attestation(number))
+    }
+
+    fn verify_aggregated_signature(msg: &[u8], signature: &RedjubjubSignature) -> result::Result<bool, &'static str> {
+        let attesters = Self::attesters();
+        ensure!(attesters.len() > 1, "At least two attesters are required.");
+
+        let pub_keys = attesters.iter()
+            .map(|vk| redjubjub::PublicKey::<Bls12>::try_from(vk).ok()
+                .and_then(|pk| pk.0.as_prime_order(&*PARAMS))
+                .ok_or("Invalid verification key of an attester."))
+            .collect::<result::Result<Vec<_>, _>>()?;
+
+        let signer_keys = SignerKeys::new(pub_keys, &*PARAMS)
+            .map_err(|_| "Failed to aggregate verification keys of attesters.")?;
+        let sig = redjubjub::Signature::try_from(*signature)
+            .map_err(|_| "Invalid signature format.")?;
+
+        Ok(signer_keys.get_mr_pub_key().verify(msg, &sig, FixedGenerators::Diversifier, &*PARAMS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use support::{impl_outer_origin, assert_ok};
+    use primitives::{H256, Blake2Hasher};
+    use runtime_primitives::{
+        BuildStorage, traits::{BlakeTwo256, IdentityLookup},
+        testing::{Digest, DigestItem, Header}
+    };
+    use jubjub::curve::{JubjubBls12, JubjubParams, PrimeOrder, edwards::Point, fs::Fs};
+    use multi_reddsa::CommitmentStage;
+    use pairing::PrimeField;
+    use rstd::convert::TryInto;
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = SigVerificationKey;
+        type SigVerificationKey = u64;
+        type Lookup = IdentityLookup<SigVerificationKey>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl Trait for Test {
+        type Event = ();
+    }
+
+    type System = system::Module<Test>;
+    type StateAttestation = Module<Test>;
+
+    const PERIOD: u64 = 4;
+
+    fn secrets() -> Vec<Fs> {
+        vec![
+            Fs::from_str("11").unwrap(),
+            Fs::from_str("12").unwrap(),
+            Fs::from_str("13").unwrap(),
+        ]
+    }
+
+    fn pub_keys(secrets: &[Fs]) -> Vec<Point<Bls12, PrimeOrder>> {
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::Diversifier;
+        secrets.iter().map(|s| params.generator(p_g).mul(*s, params)).collect()
+    }
+
+    fn attesters(secrets: &[Fs]) -> Vec<SigVerificationKey> {
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::Diversifier;
+        secrets.iter()
+            .map(|s| redjubjub::PublicKey::from_private(&redjubjub::PrivateKey::<Bls12>(*s), p_g, params))
+            .map(|vk| SigVerificationKey::try_from(vk).unwrap())
+            .collect()
+    }
+
+    // Run the multi-RedDSA protocol among all signers.
+    fn co_sign(msg: &[u8], secrets: &[Fs]) -> RedjubjubSignature {
+        let rng = &mut rand::thread_rng();
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::Diversifier;
+        let signer_keys = SignerKeys::new(pub_keys(secrets), params).unwrap();
+
+        let (cosigners, comms): (Vec<_>, Vec<_>) = secrets.iter().enumerate()
+            .map(|(i, x_i)| CommitmentStage::new(msg, *x_i, signer_keys.clone(), i, p_g, params, rng).unwrap())
+            .unzip();
+        let (cosigners, reveals): (Vec<_>, Vec<_>) = cosigners.into_iter().map(|c| c.commit(comms.clone()).unwrap()).unzip();
+        let (cosigners, shares): (Vec<_>, Vec<_>) = cosigners.into_iter().map(|c| c.reveal(reveals.clone(), params).unwrap()).unzip();
+        let sig: redjubjub::Signature = cosigners.into_iter().next().unwrap()
+            .share(shares, p_g, params)
+            .try_into()
+            .unwrap();
+
+        RedjubjubSignature::try_from(sig).unwrap()
+    }
+
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let (mut t, mut c) = system::GenesisConfig::<Test>::default().build_storage().unwrap();
+        let _ = GenesisConfig::<Test>{
+            attesters: attesters(&secrets()),
+            attestation_period: PERIOD,
+        }.assimilate_storage(&mut t, &mut c);
+
+        t.into()
+    }
+
+    fn origin() -> Origin {
+        Origin::signed(SigVerificationKey::from_slice(b"Alice                           "))
+    }
+
+    #[test]
+    fn test_attest() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(10);
+            let block_hash = System::block_hash(8);
+            let state_root = H256::repeat_byte(42);
+
+            let msg = StateAttestation::attestation_message(&8, &block_hash, &state_root);
+            let signature = co_sign(&msg[..], &secrets());
+
+            assert_ok!(StateAttestation::attest(origin(), 8, block_hash, state_root, signature));
+            assert_eq!(StateAttestation::latest_attested(), Some(8));
+            assert_eq!(StateAttestation::latest_attestation(), Some(Attestation {
+                number: 8,
+                block_hash,
+                state_root,
+                signature,
+            }));
+
+            // The same block cannot be attested twice.
+            assert!(StateAttestation::attest(origin(), 8, block_hash, state_root, signature).is_err());
+        })
+    }
+
+    #[test]
+    fn test_attest_rejects_partial_signers() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(10);
+            let block_hash = System::block_hash(8);
+            let state_root = H256::repeat_byte(42);
+
+            let msg = StateAttestation::attestation_message(&8, &block_hash, &state_root);
+            let signature = co_sign(&msg[..], &secrets()[..2]);
+
+            assert_eq!(
+                StateAttestation::attest(origin(), 8, block_hash, state_root, signature),
+                Err("Invalid signature of attesters.")
+            );
+            assert_eq!(StateAttestation::latest_attested(), None);
+        })
+    }
+
+    #[test]
+    fn test_attest_rejects_tampered_state_root() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(10);
+            let block_hash = System::block_hash(8);
+
+            let msg = StateAttestation::attestation_message(&8, &block_hash, &H256::repeat_byte(42));
+            let signature = co_sign(&msg[..], &secrets());
+
+            assert!(StateAttestation::attest(origin(), 8, block_hash, H256::repeat_byte(43), signature).is_err());
+        })
+    }
+
+    #[test]
+    fn test_attest_rejects_invalid_blocks() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(10);
+            let state_root = H256::repeat_byte(42);
+
+            // Not at an attestation period
+            let block_hash = System::block_hash(7);
+            let msg = StateAttestation::attestation_message(&7, &block_hash, &state_root);
+            let signature = co_sign(&msg[..], &secrets());
+            assert!(StateAttestation::attest(origin(), 7, block_hash, state_root, signature).is_err());
+
+            // A future block
+            let block_hash = System::block_hash(12);
+            let msg = StateAttestation::attestation_message(&12, &block_hash, &state_root);
+            let signature = co_sign(&msg[..], &secrets());
+            assert!(StateAttestation::attest(origin(), 12, block_hash, state_root, signature).is_err());
+
+            // A block hash which is not on chain
+            let block_hash = H256::repeat_byte(1);
+            let msg = StateAttestation::attestation_message(&8, &block_hash, &state_root);
+            let signature = co_sign(&msg[..], &secrets());
+            assert!(StateAttestation::attest(origin(), 8, block_hash, state_root, signature).is_err());
+        })
+    }
+
+    #[test]
+    fn test_set_attesters() {
+        with_externalities(&mut new_test_ext(), || {
+            let new_secrets = vec![Fs::from_str("21").unwrap(), Fs::from_str("22").unwrap()];
+
+            assert!(StateAttestation::set_attesters(origin(), attesters(&new_secrets)).is_err());
+            assert!(StateAttestation::set_attesters(system::RawOrigin::Root.into(), attesters(&new_secrets[..1])).is_err());
+            assert_ok!(StateAttestation::set_attesters(system::RawOrigin::Root.into(), attesters(&new_secrets)));
+
+            System::set_block_number(10);
+            let block_hash = System::block_hash(4);
+            let state_root = H256::repeat_byte(42);
+            let msg = StateAttestation::attestation_message(&4, &block_hash, &state_root);
+
+            let old_signature = co_sign(&msg[..], &secrets());
+            assert!(StateAttestation::attest(origin(), 4, block_hash, state_root, old_signature).is_err());
+
+            let new_signature = co_sign(&msg[..], &new_secrets);
+            assert_ok!(StateAttestation::attest(origin(), 4, block_hash, state_root, new_signature));
+        })
+    }
+}
@@ -310,6 +310,8 @@ decl_storage! {
 		ExtrinsicCount: Option<u32>;
 		/// Total length in bytes for all extrinsics put together, for the current block.
 		AllExtrinsicsLen: Option<u32>;
+		/// Total weight of all extrinsics put together, for the current block.
+		AllExtrinsicsWeight: Option<u32>;
 		/// Map of block numbers to block hashes.
 		pub BlockHash get(block_hash) build(|_| vec![(T::BlockNumber::zero(), hash69())]): map T::BlockNumber => T::Hash;
 		/// Extrinsics data for the current block (maps extrinsic's index to its data).
@@ -399,6 +401,16 @@ impl<T: Trait> Module<T> {
 		<AllExtrinsicsLen<T>>::get().unwrap_or_default()
 	}
 
+	/// Gets a total weight of all executed extrinsics.
+	pub fn all_extrinsics_weight() -> u32 {
+		<AllExtrinsicsWeight<T>>::get().unwrap_or_default()
+	}
+
+	/// Add the weight of an extrinsic about to be dispatched to the total weight of the block.
+	pub fn note_extrinsic_weight(weight: u32) {
+		<AllExtrinsicsWeight<T>>::put(weight.saturating_add(Self::all_extrinsics_weight()));
+	}
+
 	/// Start the execution of a particular block.
 	pub fn initialize(number: &T::BlockNumber, parent_hash: &T::Hash, txs_root: &T::Hash) {
 		// populate environment.
@@ -416,6 +428,7 @@ impl<T: Trait> Module<T> {
 		<RandomSeed<T>>::kill();
 		<ExtrinsicCount<T>>::kill();
 		<AllExtrinsicsLen<T>>::kill();
+		<AllExtrinsicsWeight<T>>::kill();
 
 		let number = <Number<T>>::take();
 		let parent_hash = <ParentHash<T>>::take();
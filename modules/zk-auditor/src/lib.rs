@@ -0,0 +1,377 @@
+//! A module for auditors with viewing access to the transfers of accounts which registered them,
+//! for regulated deployments where an auditor must see the amounts of some accounts without
+//! being able to decrypt the whole chain.
+//!
+//! Once an account registers an auditor's EncKey, every confidential and anonymous transfer of the
+//! native balances involving the account has to carry the transferred amount encrypted to the auditor,
+//! with the randomness of the transfer, and a proof that it encrypts the same amount as the account's
+//! ciphertext of the transfer. The auditor decrypts it with its own decryption key.
+//!
+//! The account registers the auditor with a signature by its decryption key, and only the auditor
+//! can release the account, otherwise an account could drop its auditor right before a transfer.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use support::{decl_module, decl_storage, decl_event, StorageMap, ensure, dispatch::Result};
+use rstd::{
+    prelude::*,
+    result,
+    convert::TryFrom,
+};
+use parity_codec::{Encode, Decode};
+use system::ensure_signed;
+use jubjub::{redjubjub, curve::{FixedGenerators, edwards, PrimeOrder}};
+use pairing::bls12_381::Bls12;
+use keys::EncryptionKey;
+use zcrypto::equality::{EqualityProof, EQUALITY_PROOF_SIZE};
+use zprimitives::{EncKey, LeftCiphertext, RightCiphertext, RedjubjubSignature, PARAMS};
+
+/// Domain separator of the messages signed by accounts and auditors.
+const AUDITOR_CONTEXT: &[u8] = b"Zerochain-Auditor";
+
+pub trait Trait: system::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+zprimitives::type_info! {
+    /// The amount of a transfer encrypted to the auditor of one of its parties.
+    #[derive(Encode, Decode, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(Debug))]
+    pub struct AuditCiphertext {
+        /// The party of the transfer whose auditor can decrypt `amount`.
+        pub account: EncKey,
+        /// The left component of the amount encrypted by the auditor's key.
+        /// The right component is the randomness of the transfer.
+        pub amount: LeftCiphertext,
+        /// A proof that `amount` encrypts the same amount as the party's ciphertext of the transfer.
+        pub proof: Vec<u8>,
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
+        /// Register an auditor of the account. The account signs the registration with its decryption key,
+        /// so anyone can submit it.
+        pub fn register_auditor(
+            origin,
+            account: EncKey,
+            auditor: EncKey,
+            signature: RedjubjubSignature
+        ) -> Result {
+            let who = ensure_signed(origin)?;
+
+            ensure!(Self::auditor(account).is_none(), "The account already has an auditor.");
+            ensure!(account != auditor, "The account can't audit itself.");
+            EncryptionKey::<Bls12>::try_from(auditor).map_err(|_| "Invalid encryption key of the auditor.")?;
+
+            let msg = Self::auditor_message(b"register", &account, &auditor);
+            ensure!(Self::verify_signature(&account, &msg[..], &signature)?, "Invalid signature of the account.");
+
+            <Auditor<T>>::insert(account, auditor);
+            <AuditorNonce<T>>::mutate(account, |nonce| *nonce += 1);
+
+            Self::deposit_event(RawEvent::AuditorRegistered(account, auditor, who));
+
+            Ok(())
+        }
+
+        /// Release the account from its auditor. The auditor signs the release with its decryption key.
+        pub fn release_auditor(
+            origin,
+            account: EncKey,
+            signature: RedjubjubSignature
+        ) -> Result {
+            let who = ensure_signed(origin)?;
+
+            let auditor = Self::auditor(account).ok_or("The account has no auditor.")?;
+
+            let msg = Self::auditor_message(b"release", &account, &auditor);
+            ensure!(Self::verify_signature(&auditor, &msg[..], &signature)?, "Invalid signature of the auditor.");
+
+            <Auditor<T>>::remove(account);
+            <AuditorNonce<T>>::mutate(account, |nonce| *nonce += 1);
+
+            Self::deposit_event(RawEvent::AuditorReleased(account, auditor, who));
+
+            Ok(())
+        }
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as ZkAuditor {
+        /// The auditor of each account.
+        pub Auditor get(auditor): map EncKey => Option<EncKey>;
+
+        /// The number of times the auditor of an account was registered or released, so that a signature can't be replayed.
+        pub AuditorNonce get(auditor_nonce): map EncKey => u64;
+    }
+}
+
+decl_event! (
+    pub enum Event<T> where <T as system::Trait>::AccountId {
+        /// An account registered an auditor. (account, auditor, submitter)
+        AuditorRegistered(EncKey, EncKey, AccountId),
+        /// An auditor released an account. (account, auditor, submitter)
+        AuditorReleased(EncKey, EncKey, AccountId),
+    }
+);
+
+impl<T: Trait> Module<T> {
+    /// The message signed to register (`action` is `register`) or release (`release`) an auditor.
+    pub fn auditor_message(action: &[u8], account: &EncKey, auditor: &EncKey) -> Vec<u8> {
+        let mut msg = AUDITOR_CONTEXT.to_vec();
+        (action, account, auditor, Self::auditor_nonce(*account)).encode_to(&mut msg);
+        msg
+    }
+
+    /// Check the audit ciphertexts of a transfer.
+    /// `parties` are the encryption keys involved in the transfer with the left components of their ciphertexts,
+    /// all of which have `randomness` as the right component.
+    /// Each party with an auditor must have an audit ciphertext, given in the order of the parties,
+    /// and there must be no other audit ciphertexts.
+    pub fn check_audits(
+        parties: &[(EncKey, LeftCiphertext)],
+        randomness: &RightCiphertext,
+        audits: &[AuditCiphertext]
+    ) -> result::Result<(), &'static str> {
+        let mut audits = audits.iter();
+
+        for (account, amount) in parties {
+            let auditor = match Self::auditor(*account) {
+                Some(a) => a,
+                None => continue,
+            };
+
+            let audit = audits.next()
+                .ok_or("Missing the audit ciphertext of an account with an auditor.")?;
+            ensure!(audit.account == *account, "Mismatch the account of the audit ciphertext.");
+            ensure!(
+                Self::verify_audit(account, amount, &auditor, audit, randomness)?,
+                "Invalid proof of the audit ciphertext."
+            );
+        }
+
+        ensure!(audits.next().is_none(), "Unexpected audit ciphertexts.");
+
+        Ok(())
+    }
+
+    fn verify_audit(
+        account: &EncKey,
+        amount: &LeftCiphertext,
+        auditor: &EncKey,
+        audit: &AuditCiphertext,
+        randomness: &RightCiphertext
+    ) -> result::Result<bool, &'static str> {
+        ensure!(audit.proof.len() == EQUALITY_PROOF_SIZE, "Invalid length of the proof of the audit ciphertext.");
+
+        let proof = EqualityProof::<Bls12>::read(&mut &audit.proof[..])
+            .map_err(|_| "Faild to read the proof of the audit ciphertext.")?;
+        let account_key = EncryptionKey::<Bls12>::try_from(account)
+            .map_err(|_| "Invalid encryption key.")?;
+        let auditor_key = EncryptionKey::<Bls12>::try_from(auditor)
+            .map_err(|_| "Invalid encryption key of the auditor.")?;
+        let amount = edwards::Point::<Bls12, PrimeOrder>::try_from(amount)
+            .map_err(|_| "Invalid left ciphertext.")?;
+        let audit_amount = edwards::Point::<Bls12, PrimeOrder>::try_from(&audit.amount)
+            .map_err(|_| "Invalid left ciphertext of the audit.")?;
+        let randomness = edwards::Point::<Bls12, PrimeOrder>::try_from(randomness)
+            .map_err(|_| "Invalid right ciphertext.")?;
+
+        Ok(proof.verify(
+            &amount,
+            &account_key,
+            &audit_amount,
+            &auditor_key,
+            &randomness,
+            FixedGenerators::Diversifier,
+            &*PARAMS
+        ))
+    }
+
+    // An EncKey is the decryption key times the generator,
+    // so the account and the auditor sign with their decryption keys as RedDSA private keys.
+    fn verify_signature(
+        enc_key: &EncKey,
+        msg: &[u8],
+        signature: &RedjubjubSignature
+    ) -> result::Result<bool, &'static str> {
+        let signer = redjubjub::PublicKey::<Bls12>::read(&mut enc_key.as_ref(), &*PARAMS)
+            .map_err(|_| "Invalid encryption key.")?;
+        let sig = redjubjub::Signature::try_from(*signature)
+            .map_err(|_| "Invalid signature format.")?;
+
+        Ok(signer.verify(msg, &sig, FixedGenerators::Diversifier, &*PARAMS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use support::{impl_outer_origin, assert_ok};
+    use primitives::{H256, Blake2Hasher};
+    use runtime_primitives::{
+        BuildStorage, traits::{BlakeTwo256, IdentityLookup},
+        testing::{Digest, DigestItem, Header}
+    };
+    use jubjub::curve::{JubjubBls12, JubjubParams, fs::Fs};
+    use pairing::PrimeField;
+    use zcrypto::elgamal::Ciphertext;
+    use zprimitives::SigVerificationKey;
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = SigVerificationKey;
+        type SigVerificationKey = u64;
+        type Lookup = IdentityLookup<SigVerificationKey>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl Trait for Test {
+        type Event = ();
+    }
+
+    type ZkAuditor = Module<Test>;
+
+    const ACCOUNT_SECRET: &str = "42";
+    const AUDITOR_SECRET: &str = "43";
+
+    fn enc_key(secret: &str) -> EncKey {
+        let params = &JubjubBls12::new();
+        let point = params.generator(FixedGenerators::Diversifier).mul(Fs::from_str(secret).unwrap(), params);
+
+        let mut buf = [0u8; 32];
+        point.write(&mut buf[..]).unwrap();
+        EncKey::from_slice(&buf[..])
+    }
+
+    fn sign(secret: &str, msg: &[u8]) -> RedjubjubSignature {
+        let rng = &mut rand::thread_rng();
+        let params = &JubjubBls12::new();
+        let sig = redjubjub::PrivateKey::<Bls12>(Fs::from_str(secret).unwrap())
+            .sign(msg, rng, FixedGenerators::Diversifier, params);
+
+        RedjubjubSignature::try_from(sig).unwrap()
+    }
+
+    fn relayer() -> Origin {
+        Origin::signed(SigVerificationKey::from_slice(b"Relayer                         "))
+    }
+
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let (t, _) = system::GenesisConfig::<Test>::default().build_storage().unwrap();
+        t.into()
+    }
+
+    fn register() -> Result {
+        let (account, auditor) = (enc_key(ACCOUNT_SECRET), enc_key(AUDITOR_SECRET));
+        let signature = sign(ACCOUNT_SECRET, &ZkAuditor::auditor_message(b"register", &account, &auditor)[..]);
+
+        ZkAuditor::register_auditor(relayer(), account, auditor, signature)
+    }
+
+    // Encrypt the amount to the account and to the auditor with the same randomness.
+    fn audited_transfer(amount: u64, audited_amount: u64) -> (LeftCiphertext, RightCiphertext, AuditCiphertext) {
+        let rng = &mut rand::thread_rng();
+        let params = &JubjubBls12::new();
+        let p_g = FixedGenerators::Diversifier;
+        let account_key = EncryptionKey::<Bls12>::try_from(enc_key(ACCOUNT_SECRET)).unwrap();
+        let auditor_key = EncryptionKey::<Bls12>::try_from(enc_key(AUDITOR_SECRET)).unwrap();
+
+        let r = Fs::from_str("1234").unwrap();
+        let c = Ciphertext::encrypt(amount, &r, &account_key, p_g, params);
+        let c_audit = Ciphertext::encrypt(audited_amount, &r, &auditor_key, p_g, params);
+        let proof = EqualityProof::prove(&r, &c.left, &account_key, &c_audit.left, &auditor_key, p_g, rng, params);
+
+        let mut proof_bytes = vec![];
+        proof.write(&mut proof_bytes).unwrap();
+        let audit = AuditCiphertext {
+            account: enc_key(ACCOUNT_SECRET),
+            amount: LeftCiphertext::try_from(c_audit.clone()).unwrap(),
+            proof: proof_bytes,
+        };
+
+        (LeftCiphertext::try_from(c.clone()).unwrap(), RightCiphertext::try_from(c).unwrap(), audit)
+    }
+
+    #[test]
+    fn test_register_and_release_auditor() {
+        with_externalities(&mut new_test_ext(), || {
+            let (account, auditor) = (enc_key(ACCOUNT_SECRET), enc_key(AUDITOR_SECRET));
+
+            assert_ok!(register());
+            assert_eq!(ZkAuditor::auditor(account), Some(auditor));
+            assert_eq!(register(), Err("The account already has an auditor."));
+
+            // The account can't release itself.
+            let signature = sign(ACCOUNT_SECRET, &ZkAuditor::auditor_message(b"release", &account, &auditor)[..]);
+            assert_eq!(
+                ZkAuditor::release_auditor(relayer(), account, signature),
+                Err("Invalid signature of the auditor.")
+            );
+
+            let signature = sign(AUDITOR_SECRET, &ZkAuditor::auditor_message(b"release", &account, &auditor)[..]);
+            assert_ok!(ZkAuditor::release_auditor(relayer(), account, signature));
+            assert_eq!(ZkAuditor::auditor(account), None);
+
+            // The signature of the release can't be replayed.
+            assert_eq!(
+                ZkAuditor::release_auditor(relayer(), account, signature),
+                Err("The account has no auditor.")
+            );
+            assert_ok!(register());
+            assert_eq!(
+                ZkAuditor::release_auditor(relayer(), account, signature),
+                Err("Invalid signature of the auditor.")
+            );
+        })
+    }
+
+    #[test]
+    fn test_check_audits() {
+        with_externalities(&mut new_test_ext(), || {
+            let account = enc_key(ACCOUNT_SECRET);
+            let other = EncKey::repeat_byte(7);
+            let (amount, randomness, audit) = audited_transfer(10, 10);
+            let parties = [(other, amount), (account, amount)];
+
+            // Nothing is required before the auditor is registered.
+            assert_ok!(ZkAuditor::check_audits(&parties, &randomness, &[]));
+            assert_eq!(
+                ZkAuditor::check_audits(&parties, &randomness, &[audit.clone()]),
+                Err("Unexpected audit ciphertexts.")
+            );
+
+            assert_ok!(register());
+            assert_eq!(
+                ZkAuditor::check_audits(&parties, &randomness, &[]),
+                Err("Missing the audit ciphertext of an account with an auditor.")
+            );
+            assert_ok!(ZkAuditor::check_audits(&parties, &randomness, &[audit]));
+
+            let (amount, randomness, audit) = audited_transfer(10, 11);
+            assert_eq!(
+                ZkAuditor::check_audits(&[(account, amount)], &randomness, &[audit]),
+                Err("Invalid proof of the audit ciphertext.")
+            );
+        })
+    }
+}
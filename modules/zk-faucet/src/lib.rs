@@ -0,0 +1,227 @@
+//! A faucet of encrypted balances for testnets.
+//! Any account can claim a fixed amount once per `ClaimInterval` epochs, and so can any signer,
+//! so that testers can onboard without operators seeding their balances by hand.
+//!
+//! The amount is public, so it is encrypted under the recipient's key with the randomness one
+//! and credited to the recipient's pending transfer, which matures at its rollover in a later epoch.
+//! Claims are only accepted if the module is built with the `testnet` feature.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use support::{decl_module, decl_storage, decl_event, StorageMap, StorageValue, ensure, dispatch::Result};
+use runtime_primitives::traits::Zero;
+use system::{ensure_signed, ensure_root};
+use zprimitives::{EncKey, Ciphertext};
+
+pub trait Trait: system::Trait + encrypted_balances::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
+        /// Credit `FaucetAmount` to the recipient's pending transfer.
+        /// Both the recipient and the signer can only claim once per `ClaimInterval` epochs.
+        pub fn claim(origin, recipient: EncKey) -> Result {
+            let rvk = ensure_signed(origin)?;
+
+            ensure!(cfg!(feature = "testnet"), "The faucet is only available on testnets.");
+
+            let amount = Self::faucet_amount();
+            ensure!(amount > 0, "The faucet is closed.");
+
+            let current_epoch = <zk_system::Module<T>>::get_current_epoch();
+            ensure!(Self::can_claim(Self::last_claim(recipient), current_epoch), "The account claimed too recently.");
+            ensure!(Self::can_claim(Self::last_claim_by(&rvk), current_epoch), "The signer claimed too recently.");
+
+            let enc_amount = Ciphertext::encrypt_public(amount, &recipient)
+                .map_err(|_| "Invalid encryption key of the recipient.")?;
+            let left = enc_amount.left().map_err(|_| "Invalid ciphertext of the amount.")?;
+            let right = enc_amount.right().map_err(|_| "Invalid ciphertext of the amount.")?;
+
+            // Callers of `add_pending_transfer` roll over the recipient first.
+            <encrypted_balances::Module<T>>::rollover(&recipient)?;
+            <encrypted_balances::Module<T>>::add_pending_transfer(&recipient, &left, &right)?;
+
+            <LastClaim<T>>::insert(recipient, current_epoch);
+            <LastClaimBy<T>>::insert(&rvk, current_epoch);
+
+            Self::deposit_event(RawEvent::Claimed(recipient, amount, rvk));
+
+            Ok(())
+        }
+
+        /// Change the amount and the interval of claims. An amount of zero closes the faucet. Only callable by root.
+        pub fn set_faucet(origin, amount: u64, interval: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+
+            <FaucetAmount<T>>::put(amount);
+            <ClaimInterval<T>>::put(interval);
+            Self::deposit_event(RawEvent::FaucetChanged(amount, interval));
+
+            Ok(())
+        }
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as ZkFaucet {
+        /// The amount credited by a claim.
+        pub FaucetAmount get(faucet_amount) config() : u64;
+
+        /// The number of epochs between two claims of the same account or signer.
+        pub ClaimInterval get(claim_interval) config() : T::BlockNumber;
+
+        /// The epoch of the last claim of each account.
+        pub LastClaim get(last_claim) : map EncKey => Option<T::BlockNumber>;
+
+        /// The epoch of the last claim signed by each signer.
+        pub LastClaimBy get(last_claim_by) : map T::AccountId => Option<T::BlockNumber>;
+    }
+}
+
+decl_event! (
+    pub enum Event<T> where <T as system::Trait>::AccountId, <T as system::Trait>::BlockNumber {
+        /// The amount was credited to the account, claimed by the signer.
+        Claimed(EncKey, u64, AccountId),
+        /// The amount and the interval of claims were changed.
+        FaucetChanged(u64, BlockNumber),
+    }
+);
+
+impl<T: Trait> Module<T> {
+    fn can_claim(last_claim: Option<T::BlockNumber>, current_epoch: T::BlockNumber) -> bool {
+        match last_claim {
+            Some(last) => last + Self::claim_interval() <= current_epoch || Self::claim_interval().is_zero(),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use support::{impl_outer_origin, assert_ok};
+    use primitives::{H256, Blake2Hasher};
+    use runtime_primitives::{
+        BuildStorage, traits::{BlakeTwo256, IdentityLookup},
+        testing::{Digest, DigestItem, Header}
+    };
+    use zprimitives::SigVerificationKey;
+    use keys::{ProofGenerationKey, EncryptionKey};
+    use jubjub::curve::{JubjubBls12, FixedGenerators};
+    use pairing::bls12_381::Bls12;
+    use zcrypto::elgamal;
+    use std::convert::TryFrom;
+
+    const ALICE_SEED: &[u8; 32] = b"Alice                           ";
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = SigVerificationKey;
+        type SigVerificationKey = u64;
+        type Lookup = IdentityLookup<SigVerificationKey>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl encrypted_balances::Trait for Test {
+        type Event = ();
+        type ConfidentialFee = encrypted_balances::FlatFee<Test>;
+    }
+
+    impl zk_system::Trait for Test {
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl zk_stats::Trait for Test { }
+
+    impl zk_auditor::Trait for Test {
+        type Event = ();
+    }
+
+    impl Trait for Test {
+        type Event = ();
+    }
+
+    type EncryptedBalances = encrypted_balances::Module<Test>;
+    type ZkFaucet = Module<Test>;
+
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let (mut t, mut c) = system::GenesisConfig::<Test>::default().build_storage().unwrap();
+        let _ = zk_system::GenesisConfig::<Test>{
+            last_epoch: 1,
+            epoch_length: 1,
+            max_anonymity_set_size: 12,
+            nonce_retention_epochs: 0,
+            confidential_vk: Default::default(),
+            anonymous_vk: Default::default(),
+            confidential_pk_hash: [0u8; 32],
+            anonymous_pk_hash: [0u8; 32],
+        }.assimilate_storage(&mut t, &mut c);
+        let _ = GenesisConfig::<Test>{
+            faucet_amount: 100,
+            claim_interval: 2,
+        }.assimilate_storage(&mut t, &mut c);
+
+        t.into()
+    }
+
+    fn signer(i: u8) -> Origin {
+        Origin::signed(SigVerificationKey::from_slice(&[i; 32]))
+    }
+
+    #[test]
+    fn test_claim() {
+        with_externalities(&mut new_test_ext(), || {
+            let params = &JubjubBls12::new();
+            let dec_key = ProofGenerationKey::<Bls12>::from_seed(ALICE_SEED, params).into_decryption_key().unwrap();
+            let alice = EncKey::try_from(EncryptionKey::from_decryption_key(&dec_key, params)).unwrap();
+
+            <system::Module<Test>>::set_block_number(10);
+            assert_ok!(ZkFaucet::claim(signer(1), alice));
+            assert_eq!(ZkFaucet::last_claim(alice), Some(10));
+
+            // Neither the account nor the signer can claim again until the interval passes.
+            assert!(ZkFaucet::claim(signer(2), alice).is_err());
+            assert!(ZkFaucet::claim(signer(1), EncKey::try_from(EncryptionKey::<Bls12>::from_seed(&[7u8; 32], params).unwrap()).unwrap()).is_err());
+
+            <system::Module<Test>>::set_block_number(12);
+            assert_ok!(ZkFaucet::claim(signer(2), alice));
+
+            // Both claims mature into the balance, which decrypts to their sum.
+            <system::Module<Test>>::set_block_number(13);
+            assert_ok!(EncryptedBalances::rollover(&alice));
+            let balance = elgamal::Ciphertext::<Bls12>::try_from(EncryptedBalances::encrypted_balance(alice).unwrap()).unwrap();
+            assert_eq!(balance.decrypt(&dec_key, FixedGenerators::Diversifier, params), Some(200));
+        })
+    }
+
+    #[test]
+    fn test_closed_faucet() {
+        with_externalities(&mut new_test_ext(), || {
+            let alice = EncKey::try_from(EncryptionKey::<Bls12>::from_seed(ALICE_SEED, &JubjubBls12::new()).unwrap()).unwrap();
+
+            assert!(ZkFaucet::set_faucet(signer(1), 0, 2).is_err());
+            assert_ok!(ZkFaucet::set_faucet(system::RawOrigin::Root.into(), 0, 2));
+            assert!(ZkFaucet::claim(signer(1), alice).is_err());
+            assert_eq!(EncryptedBalances::pending_epochs(alice), vec![]);
+        })
+    }
+}
@@ -0,0 +1,726 @@
+//! A module for recovering an encrypted balance whose seed is lost.
+//! The owner of an EncKey registers guardians who, after a delay and a threshold of approvals,
+//! re-bind the account's balance to a new EncKey.
+//!
+//! The guardians never learn the lost decryption key. The owner splits it among them off-chain with
+//! Shamir's secret sharing, and registers the commitments to the coefficients of the sharing polynomial,
+//! i.e. the coefficients times the generator, the constant one of which is the EncKey. So the chain knows
+//! the public key of the share of each guardian, which the guardian checks its share against.
+//!
+//! The rescue procedure is that a guardian initiates a recovery, which moves the balance out of the account
+//! into an escrow, and each guardian approves it with the right component of the escrow times its share and
+//! a proof that it is made with the share. Once as many guardians approved as there are commitments
+//! and the delay has passed, the chain combines their approvals into the decryption of the escrow
+//! and credits the amount to the new key. Fewer guardians learn nothing about the key or the amount.
+//!
+//! The escrow is decrypted on chain, so the recovered amount becomes public.
+//! The owner cancels a recovery it didn't ask for with a signature by its decryption key,
+//! which credits the escrow back to the account. Transfers to the account which haven't matured
+//! when a recovery is initiated stay with the lost key.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use support::{decl_module, decl_storage, decl_event, StorageMap, ensure, dispatch::Result};
+use rstd::{
+    prelude::*,
+    result,
+    convert::TryFrom,
+};
+use parity_codec::{Encode, Decode};
+use system::ensure_signed;
+use jubjub::{redjubjub, curve::{JubjubParams, FixedGenerators, edwards, PrimeOrder, fs::{Fs, FsRepr}}};
+use pairing::{Field, PrimeField, bls12_381::Bls12};
+use keys::EncryptionKey;
+use zcrypto::equality::{EqualityProof, EQUALITY_PROOF_SIZE};
+use zprimitives::{
+    EncKey, Ciphertext, LeftCiphertext, RightCiphertext,
+    RedjubjubSignature, PARAMS,
+};
+
+/// Domain separator of the messages signed by the owner.
+const RECOVERY_CONTEXT: &[u8] = b"Zerochain-Recovery";
+
+/// The maximum number of guardians of an account.
+pub const MAX_GUARDIANS: usize = 16;
+
+pub trait Trait: system::Trait + encrypted_balances::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+/// Guardians registered by the owner of an EncKey.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct RecoveryConfig<AccountId, BlockNumber> {
+    /// Signature verification keys of the guardians.
+    /// The share of the i-th guardian is the sharing polynomial at `i + 1`.
+    pub guardians: Vec<AccountId>,
+    /// Commitments to the coefficients of the sharing polynomial, from the constant one, which is the EncKey.
+    pub commitments: Vec<EncKey>,
+    /// The number of blocks a recovery must wait after it was initiated.
+    pub delay: BlockNumber,
+}
+
+impl<AccountId, BlockNumber> RecoveryConfig<AccountId, BlockNumber> {
+    /// The number of guardian approvals required to complete a recovery, i.e. the number of coefficients.
+    pub fn threshold(&self) -> usize {
+        self.commitments.len()
+    }
+}
+
+/// A recovery in progress.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ActiveRecovery<BlockNumber> {
+    /// The EncKey the balance is re-bound to.
+    pub new_key: EncKey,
+    /// The block number at which the recovery was initiated.
+    pub initiated_at: BlockNumber,
+    /// The balance moved out of the lost account.
+    pub escrow: Ciphertext,
+    /// The indices of the guardians who approved the recovery,
+    /// with the right component of the escrow times their shares.
+    pub approvals: Vec<(u32, LeftCiphertext)>,
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
+        /// Register guardians of `enc_key`, replacing the existing ones.
+        /// `signature` is made with the decryption key of `enc_key` over `registration_message`.
+        pub fn register_guardians(
+            origin,
+            enc_key: EncKey,
+            guardians: Vec<T::AccountId>,
+            commitments: Vec<EncKey>,
+            delay: T::BlockNumber,
+            signature: RedjubjubSignature
+        ) -> Result {
+            ensure_signed(origin)?;
+
+            ensure!(!guardians.is_empty(), "At least one guardian is required.");
+            ensure!(guardians.len() <= MAX_GUARDIANS, "Too many guardians.");
+            ensure!(
+                !commitments.is_empty() && commitments.len() <= guardians.len(),
+                "Invalid threshold of approvals."
+            );
+            ensure!(commitments[0] == enc_key, "The constant commitment must be the encryption key.");
+            for commitment in &commitments {
+                EncryptionKey::<Bls12>::try_from(commitment).map_err(|_| "Invalid commitment of the sharing polynomial.")?;
+            }
+            ensure!(
+                guardians.iter().enumerate().all(|(i, g)| !guardians[..i].contains(g)),
+                "Duplicated guardians."
+            );
+            ensure!(Self::active_recovery(enc_key).is_none(), "A recovery is in progress.");
+
+            let config = RecoveryConfig { guardians, commitments, delay };
+            let msg = Self::registration_message(&enc_key, &config);
+            ensure!(Self::verify_owner_signature(&enc_key, &msg[..], &signature)?, "Invalid signature of the owner.");
+
+            let (count, threshold) = (config.guardians.len() as u32, config.threshold() as u32);
+            <RecoveryConfigs<T>>::insert(enc_key, config);
+            <OwnerNonce<T>>::mutate(enc_key, |n| *n += 1);
+
+            Self::deposit_event(RawEvent::GuardiansRegistered(enc_key, count, threshold));
+
+            Ok(())
+        }
+
+        /// Start re-binding the balance of `lost_key` to `new_key`, moving the balance into the escrow of the recovery.
+        /// Only callable by a guardian.
+        pub fn initiate_recovery(origin, lost_key: EncKey, new_key: EncKey) -> Result {
+            let guardian = ensure_signed(origin)?;
+
+            let config = Self::recovery_config(lost_key).ok_or("No guardians are registered.")?;
+            ensure!(config.guardians.contains(&guardian), "Not a guardian of the account.");
+            ensure!(Self::active_recovery(lost_key).is_none(), "A recovery is in progress.");
+            ensure!(lost_key != new_key, "The new key must differ from the lost key.");
+            EncryptionKey::<Bls12>::try_from(new_key).map_err(|_| "Invalid encryption key of the new key.")?;
+
+            // The matured pending transfers are recovered along with the balance.
+            <encrypted_balances::Module<T>>::rollover(&lost_key)?;
+
+            let escrow = <encrypted_balances::Module<T>>::encrypted_balance(lost_key)
+                .ok_or("The account has no balance to recover.")?;
+            // The approvals are points other than the identity, so the right of the escrow must be one.
+            let left = escrow.left().map_err(|_| "Invalid ciphertext of the balance.")?;
+            let right = escrow.right().map_err(|_| "Invalid ciphertext of the balance.")?;
+            Ciphertext::from_left_right(left, right).map_err(|_| "The account has no balance to recover.")?;
+
+            <encrypted_balances::Module<T>>::move_balance(&lost_key, &escrow, &[])?;
+
+            <ActiveRecoveries<T>>::insert(lost_key, ActiveRecovery {
+                new_key,
+                initiated_at: <system::Module<T>>::block_number(),
+                escrow,
+                approvals: vec![],
+            });
+
+            Self::deposit_event(RawEvent::RecoveryInitiated(lost_key, new_key, guardian));
+
+            Ok(())
+        }
+
+        /// Approve the recovery of `lost_key` in progress with `decryption_share`, the right of the escrow
+        /// times the guardian's share, and `proof`, an equality proof that it and the public key of the share
+        /// have the same discrete logarithm, made by `EqualityProof::prove_amount` of the amount zero
+        /// with the share as the randomness and the right of the escrow as the encryption key.
+        /// Only callable by a guardian.
+        pub fn approve_recovery(
+            origin,
+            lost_key: EncKey,
+            decryption_share: LeftCiphertext,
+            proof: Vec<u8>
+        ) -> Result {
+            let guardian = ensure_signed(origin)?;
+
+            let config = Self::recovery_config(lost_key).ok_or("No guardians are registered.")?;
+            let index = config.guardians.iter().position(|g| *g == guardian)
+                .ok_or("Not a guardian of the account.")? as u32;
+
+            let mut recovery = Self::active_recovery(lost_key).ok_or("No recovery is in progress.")?;
+            ensure!(!recovery.approvals.iter().any(|(i, _)| *i == index), "Already approved.");
+            ensure!(recovery.approvals.len() < config.threshold(), "The recovery already has enough approvals.");
+            ensure!(
+                Self::verify_decryption_share(&config.commitments, index, &recovery.escrow, &decryption_share, &proof)?,
+                "Invalid proof of the decryption share."
+            );
+
+            recovery.approvals.push((index, decryption_share));
+            <ActiveRecoveries<T>>::insert(lost_key, recovery);
+
+            Self::deposit_event(RawEvent::RecoveryApproved(lost_key, guardian));
+
+            Ok(())
+        }
+
+        /// Cancel the recovery of `enc_key` in progress and credit its escrow back to the account.
+        /// `signature` is made with the decryption key of `enc_key` over `cancellation_message`.
+        pub fn cancel_recovery(origin, enc_key: EncKey, signature: RedjubjubSignature) -> Result {
+            ensure_signed(origin)?;
+
+            let recovery = Self::active_recovery(enc_key).ok_or("No recovery is in progress.")?;
+            let msg = Self::cancellation_message(&enc_key);
+            ensure!(Self::verify_owner_signature(&enc_key, &msg[..], &signature)?, "Invalid signature of the owner.");
+
+            let left = recovery.escrow.left().map_err(|_| "Invalid ciphertext of the escrow.")?;
+            let right = recovery.escrow.right().map_err(|_| "Invalid ciphertext of the escrow.")?;
+
+            <encrypted_balances::Module<T>>::rollover(&enc_key)?;
+            <encrypted_balances::Module<T>>::add_pending_transfer(&enc_key, &left, &right)?;
+
+            <ActiveRecoveries<T>>::remove(enc_key);
+            <OwnerNonce<T>>::mutate(enc_key, |n| *n += 1);
+
+            Self::deposit_event(RawEvent::RecoveryCancelled(enc_key));
+
+            Ok(())
+        }
+
+        /// Complete the recovery of `lost_key` by decrypting its escrow with the approvals
+        /// and crediting the amount to the new key.
+        /// The guardians are released, since their shares are of the lost key.
+        pub fn complete_recovery(origin, lost_key: EncKey) -> Result {
+            ensure_signed(origin)?;
+
+            let config = Self::recovery_config(lost_key).ok_or("No guardians are registered.")?;
+            let recovery = Self::active_recovery(lost_key).ok_or("No recovery is in progress.")?;
+            ensure!(recovery.approvals.len() >= config.threshold(), "Not enough guardian approvals.");
+            ensure!(
+                <system::Module<T>>::block_number() >= recovery.initiated_at + config.delay,
+                "The recovery delay has not passed yet."
+            );
+
+            let new_key = EncryptionKey::<Bls12>::try_from(recovery.new_key)
+                .map_err(|_| "Invalid encryption key of the new key.")?;
+            let amount = Self::decrypt_escrow(&recovery)?;
+
+            // The amount is public anyway, so it is encrypted with the randomness one, as the genesis balances are.
+            let left = LeftCiphertext::try_from(amount.add(&new_key.0, &*PARAMS))
+                .map_err(|_| "Faild to encrypt the recovered amount.")?;
+            let right = RightCiphertext::try_from(PARAMS.generator(FixedGenerators::Diversifier).clone())
+                .map_err(|_| "Faild to encrypt the recovered amount.")?;
+
+            <encrypted_balances::Module<T>>::rollover(&recovery.new_key)?;
+            <encrypted_balances::Module<T>>::add_pending_transfer(&recovery.new_key, &left, &right)?;
+
+            <ActiveRecoveries<T>>::remove(lost_key);
+            <RecoveryConfigs<T>>::remove(lost_key);
+            <RecoveredTo<T>>::insert(lost_key, recovery.new_key);
+
+            Self::deposit_event(RawEvent::Recovered(lost_key, recovery.new_key));
+
+            Ok(())
+        }
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as ZkRecovery {
+        /// Guardians of each account
+        pub RecoveryConfigs get(recovery_config) : map EncKey => Option<RecoveryConfig<T::AccountId, T::BlockNumber>>;
+
+        /// A recovery in progress for each account
+        pub ActiveRecoveries get(active_recovery) : map EncKey => Option<ActiveRecovery<T::BlockNumber>>;
+
+        /// The number of messages the owner has signed, to prevent replaying them.
+        pub OwnerNonce get(owner_nonce) : map EncKey => u32;
+
+        /// The key each recovered account was re-bound to
+        pub RecoveredTo get(recovered_to) : map EncKey => Option<EncKey>;
+    }
+}
+
+decl_event! (
+    pub enum Event<T> where <T as system::Trait>::AccountId {
+        /// Guardians were registered for the account, with their number and the threshold.
+        GuardiansRegistered(EncKey, u32, u32),
+        /// A guardian started re-binding the lost account to the new key.
+        RecoveryInitiated(EncKey, EncKey, AccountId),
+        /// A guardian approved the recovery of the account.
+        RecoveryApproved(EncKey, AccountId),
+        /// The owner cancelled the recovery of the account.
+        RecoveryCancelled(EncKey),
+        /// The balance of the lost account was re-bound to the new key.
+        Recovered(EncKey, EncKey),
+    }
+);
+
+impl<T: Trait> Module<T> {
+    /// The message the owner signs to register guardians.
+    pub fn registration_message(
+        enc_key: &EncKey,
+        config: &RecoveryConfig<T::AccountId, T::BlockNumber>
+    ) -> Vec<u8> {
+        let mut msg = RECOVERY_CONTEXT.to_vec();
+        msg.extend_from_slice(b"::register");
+        (enc_key, config, Self::owner_nonce(enc_key)).encode_to(&mut msg);
+        msg
+    }
+
+    /// The message the owner signs to cancel a recovery.
+    pub fn cancellation_message(enc_key: &EncKey) -> Vec<u8> {
+        let mut msg = RECOVERY_CONTEXT.to_vec();
+        msg.extend_from_slice(b"::cancel");
+        (enc_key, Self::owner_nonce(enc_key)).encode_to(&mut msg);
+        msg
+    }
+
+    /// The public key of the share of the `index`-th guardian, i.e. the sharing polynomial at `index + 1`
+    /// times the generator, evaluated on the commitments.
+    pub fn share_key(
+        commitments: &[EncKey],
+        index: u32
+    ) -> result::Result<edwards::Point<Bls12, PrimeOrder>, &'static str> {
+        let x = Self::share_point(index);
+
+        // Horner's method from the highest coefficient
+        let mut key = edwards::Point::zero();
+        for commitment in commitments.iter().rev() {
+            let commitment = EncryptionKey::<Bls12>::try_from(commitment)
+                .map_err(|_| "Invalid commitment of the sharing polynomial.")?;
+            key = key.mul(x, &*PARAMS).add(&commitment.0, &*PARAMS);
+        }
+
+        Ok(key)
+    }
+
+    // The decryption share of a guardian is the right of the escrow times the share, as the share key is
+    // the generator times the share. So it is the left of the amount zero encrypted by the right as the key
+    // with the share as the randomness, whose right is the share key.
+    fn verify_decryption_share(
+        commitments: &[EncKey],
+        index: u32,
+        escrow: &Ciphertext,
+        decryption_share: &LeftCiphertext,
+        proof: &[u8]
+    ) -> result::Result<bool, &'static str> {
+        ensure!(proof.len() == EQUALITY_PROOF_SIZE, "Invalid length of the proof of the decryption share.");
+
+        let proof = EqualityProof::<Bls12>::read(&mut &proof[..])
+            .map_err(|_| "Faild to read the proof of the decryption share.")?;
+        let right = escrow.right()
+            .and_then(edwards::Point::<Bls12, PrimeOrder>::try_from)
+            .map_err(|_| "Invalid ciphertext of the escrow.")?;
+        let decryption_share = edwards::Point::<Bls12, PrimeOrder>::try_from(decryption_share)
+            .map_err(|_| "Invalid decryption share.")?;
+        let share_key = Self::share_key(commitments, index)?;
+
+        Ok(proof.verify_amount(
+            &decryption_share,
+            &EncryptionKey(right),
+            0,
+            &share_key,
+            FixedGenerators::Diversifier,
+            &*PARAMS
+        ))
+    }
+
+    // The amount of the escrow times the generator, i.e. its left minus its right times the lost decryption key,
+    // which is the decryption shares combined by their Lagrange coefficients at zero.
+    fn decrypt_escrow(
+        recovery: &ActiveRecovery<T::BlockNumber>
+    ) -> result::Result<edwards::Point<Bls12, PrimeOrder>, &'static str> {
+        let left = recovery.escrow.left()
+            .and_then(edwards::Point::<Bls12, PrimeOrder>::try_from)
+            .map_err(|_| "Invalid ciphertext of the escrow.")?;
+
+        let mut decryption = edwards::Point::zero();
+        for (index, decryption_share) in &recovery.approvals {
+            let decryption_share = edwards::Point::<Bls12, PrimeOrder>::try_from(decryption_share)
+                .map_err(|_| "Invalid decryption share.")?;
+            let coefficient = Self::lagrange_coefficient(&recovery.approvals, *index);
+            decryption = decryption.add(&decryption_share.mul(coefficient, &*PARAMS), &*PARAMS);
+        }
+
+        Ok(left.add(&decryption.negate(), &*PARAMS))
+    }
+
+    // The Lagrange coefficient at zero of the share of the `index`-th guardian among the approvals.
+    fn lagrange_coefficient(approvals: &[(u32, LeftCiphertext)], index: u32) -> Fs {
+        let x_i = Self::share_point(index);
+
+        let mut numerator = Fs::one();
+        let mut denominator = Fs::one();
+        for (j, _) in approvals.iter().filter(|(j, _)| *j != index) {
+            let x_j = Self::share_point(*j);
+            numerator.mul_assign(&x_j);
+
+            let mut diff = x_j;
+            diff.sub_assign(&x_i);
+            denominator.mul_assign(&diff);
+        }
+
+        numerator.mul_assign(&denominator.inverse().expect("a guardian approves only once; qed"));
+        numerator
+    }
+
+    // The point the sharing polynomial is evaluated at for the share of the `index`-th guardian.
+    fn share_point(index: u32) -> Fs {
+        Fs::from_repr(FsRepr::from(index as u64 + 1)).expect("a u32 is less than the modulus; qed")
+    }
+
+    // An EncKey is the decryption key times the generator,
+    // so the owner signs with the decryption key as a RedDSA private key.
+    fn verify_owner_signature(
+        enc_key: &EncKey,
+        msg: &[u8],
+        signature: &RedjubjubSignature
+    ) -> result::Result<bool, &'static str> {
+        let owner = redjubjub::PublicKey::<Bls12>::read(&mut enc_key.as_ref(), &*PARAMS)
+            .map_err(|_| "Invalid encryption key.")?;
+        let sig = redjubjub::Signature::try_from(*signature)
+            .map_err(|_| "Invalid signature format.")?;
+
+        Ok(owner.verify(msg, &sig, FixedGenerators::Diversifier, &*PARAMS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use support::{impl_outer_origin, assert_ok};
+    use primitives::{H256, Blake2Hasher};
+    use runtime_primitives::{
+        BuildStorage, traits::{BlakeTwo256, IdentityLookup},
+        testing::{Digest, DigestItem, Header}
+    };
+    use jubjub::curve::JubjubBls12;
+    use keys::DecryptionKey;
+    use zcrypto::elgamal;
+    use rand::{SeedableRng, XorShiftRng};
+    use zprimitives::SigVerificationKey;
+    use test_fixtures::{conf_vk, anony_vk, genesis_balance};
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = SigVerificationKey;
+        type SigVerificationKey = u64;
+        type Lookup = IdentityLookup<SigVerificationKey>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl encrypted_balances::Trait for Test {
+        type Event = ();
+        type ConfidentialFee = encrypted_balances::FlatFee<Test>;
+    }
+
+    impl zk_system::Trait for Test {
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl zk_stats::Trait for Test { }
+
+    impl zk_auditor::Trait for Test {
+        type Event = ();
+    }
+
+    impl Trait for Test {
+        type Event = ();
+    }
+
+    type System = system::Module<Test>;
+    type EncryptedBalances = encrypted_balances::Module<Test>;
+    type ZkRecovery = Module<Test>;
+
+    const DELAY: u64 = 5;
+
+    // The owner's decryption key is the constant coefficient of the sharing polynomial `42 + 7x`,
+    // so the shares of Alice, Bob and Charlie are 49, 56 and 63, any two of which recover the key.
+    const OWNER_SECRET: &str = "42";
+    const COEFFICIENT: &str = "7";
+    const SHARES: [&str; 3] = ["49", "56", "63"];
+    const NEW_SECRET: &str = "43";
+
+    fn enc_key(secret: &str) -> EncKey {
+        let params = &JubjubBls12::new();
+        let point = params.generator(FixedGenerators::Diversifier).mul(Fs::from_str(secret).unwrap(), params);
+
+        let mut buf = [0u8; 32];
+        point.write(&mut buf[..]).unwrap();
+        EncKey::from_slice(&buf[..])
+    }
+
+    fn owner_key() -> EncKey {
+        enc_key(OWNER_SECRET)
+    }
+
+    fn new_key() -> EncKey {
+        enc_key(NEW_SECRET)
+    }
+
+    fn commitments() -> Vec<EncKey> {
+        vec![owner_key(), enc_key(COEFFICIENT)]
+    }
+
+    fn owner_sign(msg: &[u8]) -> RedjubjubSignature {
+        let rng = &mut rand::thread_rng();
+        let params = &JubjubBls12::new();
+        let sig = redjubjub::PrivateKey::<Bls12>(Fs::from_str(OWNER_SECRET).unwrap())
+            .sign(msg, rng, FixedGenerators::Diversifier, params);
+
+        RedjubjubSignature::try_from(sig).unwrap()
+    }
+
+    fn guardian(name: &[u8; 32]) -> SigVerificationKey {
+        SigVerificationKey::from_slice(&name[..])
+    }
+
+    fn guardians() -> Vec<SigVerificationKey> {
+        vec![
+            guardian(b"Alice                           "),
+            guardian(b"Bob                             "),
+            guardian(b"Charlie                         "),
+        ]
+    }
+
+    fn decrypt(balance: Ciphertext, secret: &str) -> Option<u64> {
+        elgamal::Ciphertext::<Bls12>::try_from(balance).unwrap()
+            .decrypt(&DecryptionKey(Fs::from_str(secret).unwrap()), FixedGenerators::Diversifier, &JubjubBls12::new())
+    }
+
+    // The balance of the account after its rollover in the current epoch
+    fn balance(enc_key: EncKey, secret: &str) -> Option<u64> {
+        EncryptedBalances::rollover(&enc_key).unwrap();
+        EncryptedBalances::encrypted_balance(enc_key).and_then(|b| decrypt(b, secret))
+    }
+
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let owner = EncryptionKey::<Bls12>::try_from(owner_key()).unwrap();
+
+        let (mut t, mut c) = system::GenesisConfig::<Test>::default().build_storage().unwrap();
+        let _ = zk_system::GenesisConfig::<Test>{
+            last_epoch: 1,
+            epoch_length: 1,
+            max_anonymity_set_size: 12,
+            nonce_retention_epochs: 0,
+            confidential_vk: conf_vk(),
+            anonymous_vk: anony_vk(),
+            confidential_pk_hash: [0u8; 32],
+            anonymous_pk_hash: [0u8; 32],
+        }.assimilate_storage(&mut t, &mut c);
+        let _ = encrypted_balances::GenesisConfig::<Test>{
+            encrypted_balance: vec![genesis_balance(&owner, 100)],
+            last_rollover: vec![(owner_key(), 0)],
+            transaction_base_fee: 1,
+            anonymous_fee: 0,
+            _genesis_phantom_data: Default::default()
+        }.assimilate_storage(&mut t, &mut c);
+
+        zk_system::pool::test_externalities(t, c)
+    }
+
+    fn register(commitments: Vec<EncKey>) -> Result {
+        let config = RecoveryConfig { guardians: guardians(), commitments, delay: DELAY };
+        let signature = owner_sign(&ZkRecovery::registration_message(&owner_key(), &config)[..]);
+
+        ZkRecovery::register_guardians(
+            Origin::signed(guardian(b"Relayer                         ")),
+            owner_key(),
+            config.guardians,
+            config.commitments,
+            config.delay,
+            signature
+        )
+    }
+
+    // The decryption share of the escrow by the share of the guardian with the proof.
+    fn approve(index: usize, share: &str) -> Result {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let params = &JubjubBls12::new();
+        let share = Fs::from_str(share).unwrap();
+
+        let escrow = ZkRecovery::active_recovery(owner_key()).unwrap().escrow;
+        let right = elgamal::Ciphertext::<Bls12>::try_from(escrow).unwrap().right;
+        let decryption_share = right.mul(share, params);
+        let proof = EqualityProof::prove_amount(
+            &share, &decryption_share, &EncryptionKey(right), 0, FixedGenerators::Diversifier, rng, params
+        );
+
+        let mut proof_bytes = vec![];
+        proof.write(&mut proof_bytes).unwrap();
+
+        ZkRecovery::approve_recovery(
+            Origin::signed(guardians()[index]),
+            owner_key(),
+            LeftCiphertext::try_from(decryption_share).unwrap(),
+            proof_bytes
+        )
+    }
+
+    #[test]
+    fn test_register_guardians() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(register(commitments()));
+            assert_eq!(ZkRecovery::recovery_config(owner_key()), Some(RecoveryConfig {
+                guardians: guardians(),
+                commitments: commitments(),
+                delay: DELAY,
+            }));
+            assert_eq!(ZkRecovery::owner_nonce(owner_key()), 1);
+        })
+    }
+
+    #[test]
+    fn test_register_guardians_rejects_invalid_signature() {
+        with_externalities(&mut new_test_ext(), || {
+            let config = RecoveryConfig { guardians: guardians(), commitments: commitments(), delay: DELAY };
+            // Signed over other commitments
+            let signature = owner_sign(&ZkRecovery::registration_message(
+                &owner_key(),
+                &RecoveryConfig { commitments: vec![owner_key()], ..config.clone() }
+            )[..]);
+
+            assert_eq!(
+                ZkRecovery::register_guardians(Origin::signed(guardians()[0]), owner_key(), config.guardians, config.commitments, DELAY, signature),
+                Err("Invalid signature of the owner.")
+            );
+        })
+    }
+
+    #[test]
+    fn test_register_guardians_rejects_invalid_commitments() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_eq!(register(vec![]), Err("Invalid threshold of approvals."));
+            assert_eq!(register(vec![owner_key(); 4]), Err("Invalid threshold of approvals."));
+            assert_eq!(
+                register(vec![enc_key(COEFFICIENT), owner_key()]),
+                Err("The constant commitment must be the encryption key.")
+            );
+            assert_eq!(ZkRecovery::recovery_config(owner_key()), None);
+        })
+    }
+
+    #[test]
+    fn test_share_key() {
+        let params = &JubjubBls12::new();
+
+        for (index, share) in SHARES.iter().enumerate() {
+            let key = params.generator(FixedGenerators::Diversifier).mul(Fs::from_str(share).unwrap(), params);
+            assert!(ZkRecovery::share_key(&commitments(), index as u32).unwrap() == key);
+        }
+    }
+
+    #[test]
+    fn test_recovery() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            assert_ok!(register(commitments()));
+
+            assert_eq!(
+                ZkRecovery::initiate_recovery(Origin::signed(guardian(b"Mallory                         ")), owner_key(), new_key()),
+                Err("Not a guardian of the account.")
+            );
+            assert_ok!(ZkRecovery::initiate_recovery(Origin::signed(guardians()[0]), owner_key(), new_key()));
+            assert_eq!(
+                ZkRecovery::initiate_recovery(Origin::signed(guardians()[1]), owner_key(), new_key()),
+                Err("A recovery is in progress.")
+            );
+            // The balance is in the escrow.
+            assert_eq!(balance(owner_key(), OWNER_SECRET), Some(0));
+
+            // Charlie approves with the share of Bob.
+            assert_eq!(approve(2, SHARES[1]), Err("Invalid proof of the decryption share."));
+            assert_ok!(approve(2, SHARES[2]));
+            assert_eq!(approve(2, SHARES[2]), Err("Already approved."));
+
+            let complete = || ZkRecovery::complete_recovery(Origin::signed(guardians()[0]), owner_key());
+
+            assert_eq!(complete(), Err("Not enough guardian approvals."));
+            assert_ok!(approve(0, SHARES[0]));
+            assert_eq!(approve(1, SHARES[1]), Err("The recovery already has enough approvals."));
+            assert_eq!(complete(), Err("The recovery delay has not passed yet."));
+
+            System::set_block_number(1 + DELAY);
+            assert_ok!(complete());
+            assert_eq!(ZkRecovery::active_recovery(owner_key()), None);
+            assert_eq!(ZkRecovery::recovery_config(owner_key()), None);
+            assert_eq!(ZkRecovery::recovered_to(owner_key()), Some(new_key()));
+
+            // The amount matures in the next epoch.
+            System::set_block_number(2 + DELAY);
+            assert_eq!(balance(new_key(), NEW_SECRET), Some(100));
+        })
+    }
+
+    #[test]
+    fn test_cancel_recovery() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            assert_ok!(register(commitments()));
+            assert_ok!(ZkRecovery::initiate_recovery(Origin::signed(guardians()[0]), owner_key(), new_key()));
+
+            let signature = owner_sign(&ZkRecovery::cancellation_message(&owner_key())[..]);
+            assert_ok!(ZkRecovery::cancel_recovery(Origin::signed(guardians()[2]), owner_key(), signature));
+            assert_eq!(ZkRecovery::active_recovery(owner_key()), None);
+
+            // The escrow is credited back in the next epoch.
+            System::set_block_number(2);
+            assert_eq!(balance(owner_key(), OWNER_SECRET), Some(100));
+
+            // The signature cannot be replayed against the next recovery.
+            assert_ok!(ZkRecovery::initiate_recovery(Origin::signed(guardians()[0]), owner_key(), new_key()));
+            assert_eq!(
+                ZkRecovery::cancel_recovery(Origin::signed(guardians()[2]), owner_key(), signature),
+                Err("Invalid signature of the owner.")
+            );
+        })
+    }
+}
@@ -0,0 +1,307 @@
+//! The boundary between a transparent balances module and the encrypted balances,
+//! so that shielded amounts can be used by the rest of the ecosystem, e.g. staking and governance deposits.
+//!
+//! `shield` withdraws a public amount from the signer's transparent balance and credits it,
+//! encrypted with the randomness one, to the pending transfer of an encrypted balance.
+//! `unshield` verifies a proof of burning a public amount from an encrypted balance,
+//! as `reveal_and_burn` does, and deposits the amount to a transparent balance.
+//!
+//! Unshielding is capped by `ShieldedPool`, the amount shielded and not unshielded yet,
+//! so the genesis encrypted balances, which no transparent funds back, can't be minted into transparent balances.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use support::{decl_module, decl_storage, decl_event, StorageValue, ensure, dispatch::Result};
+use support::traits::{Currency, WithdrawReason, ExistenceRequirement};
+use runtime_primitives::traits::As;
+use rstd::prelude::*;
+use system::ensure_signed;
+use zprimitives::{EncKey, Proof, Nonce, LeftCiphertext, RightCiphertext, Ciphertext};
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: system::Trait + encrypted_balances::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// The transparent balances, one unit of which is one unit of the encrypted balances.
+    type Currency: Currency<Self::AccountId>;
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
+        /// Move `amount` from the signer's transparent balance to the pending transfer of `recipient`.
+        /// The amount is public, so it is encrypted with the randomness one, and matures at the rollover
+        /// of the recipient in a later epoch.
+        pub fn shield(origin, recipient: EncKey, amount: u64) -> Result {
+            let who = ensure_signed(origin)?;
+
+            ensure!(amount > 0, "The amount must not be zero.");
+
+            let enc_amount = Ciphertext::encrypt_public(amount, &recipient)
+                .map_err(|_| "Invalid encryption key of the recipient.")?;
+            let left = enc_amount.left().map_err(|_| "Invalid ciphertext of the amount.")?;
+            let right = enc_amount.right().map_err(|_| "Invalid ciphertext of the amount.")?;
+            let new_pool = Self::shielded_pool().checked_add(amount)
+                .ok_or("The shielded pool overflowed.")?;
+
+            // Withdraw first, so that nothing is credited if the signer can't pay.
+            let _ = T::Currency::withdraw(
+                &who,
+                <BalanceOf<T> as As<u64>>::sa(amount),
+                WithdrawReason::Transfer,
+                ExistenceRequirement::AllowDeath
+            )?;
+
+            // Callers of `add_pending_transfer` roll over the recipient first.
+            <encrypted_balances::Module<T>>::rollover(&recipient)?;
+            <encrypted_balances::Module<T>>::add_pending_transfer(&recipient, &left, &right)?;
+
+            <ShieldedPool<T>>::put(new_pool);
+            Self::deposit_event(RawEvent::Shielded(who, recipient, amount));
+
+            Ok(())
+        }
+
+        /// Burn `amount_plain` from the encrypted balance of `address_sender` and deposit it to `dest`.
+        /// The proof is of a confidential transfer to `revealing_enc_key`, as of `reveal_and_burn`.
+        pub fn unshield(
+            origin,
+            zkproof: Proof,
+            address_sender: EncKey,
+            amount_plain: u64,
+            amount_sender: LeftCiphertext,
+            amount_burned: LeftCiphertext,
+            fee_sender: LeftCiphertext,
+            randomness: RightCiphertext,
+            nonce: Nonce,
+            fee_proof: Vec<u8>,
+            dest: T::AccountId
+        ) -> Result {
+            let rvk = ensure_signed(origin)?;
+
+            let new_pool = Self::shielded_pool().checked_sub(amount_plain)
+                .ok_or("The amount exceeds the shielded pool.")?;
+
+            <encrypted_balances::Module<T>>::verify_and_burn(
+                &rvk,
+                &zkproof,
+                &address_sender,
+                amount_plain,
+                &amount_sender,
+                &amount_burned,
+                &fee_sender,
+                &randomness,
+                &nonce,
+                &fee_proof[..]
+            )?;
+
+            let _ = T::Currency::deposit_creating(&dest, <BalanceOf<T> as As<u64>>::sa(amount_plain));
+
+            <ShieldedPool<T>>::put(new_pool);
+            Self::deposit_event(RawEvent::Unshielded(address_sender, dest, amount_plain));
+
+            Ok(())
+        }
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as ZkShield {
+        /// The amount shielded and not unshielded yet, which caps the amounts unshielded.
+        pub ShieldedPool get(shielded_pool) : u64;
+    }
+}
+
+decl_event! (
+    pub enum Event<T> where <T as system::Trait>::AccountId {
+        /// The amount was moved from the transparent balance of the account to the encrypted balance.
+        Shielded(AccountId, EncKey, u64),
+        /// The amount was burned from the encrypted balance and deposited to the transparent balance of the account.
+        Unshielded(EncKey, AccountId, u64),
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use support::{impl_outer_origin, assert_ok};
+    use primitives::{H256, Blake2Hasher};
+    use runtime_primitives::{
+        BuildStorage, traits::{BlakeTwo256, IdentityLookup},
+        testing::{Digest, DigestItem, Header}
+    };
+    use zprimitives::SigVerificationKey;
+    use jubjub::curve::{JubjubBls12, FixedGenerators};
+    use pairing::bls12_381::Bls12;
+    use zcrypto::elgamal;
+    use rand::{SeedableRng, XorShiftRng};
+    use std::convert::TryFrom;
+    use test_fixtures::{ALICE_SEED, conf_vk, anony_vk, alice_genesis, address, dec_key, burn};
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = SigVerificationKey;
+        type SigVerificationKey = u64;
+        type Lookup = IdentityLookup<SigVerificationKey>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl balances::Trait for Test {
+        type Balance = u64;
+        type OnFreeBalanceZero = ();
+        type OnNewAccount = ();
+        type Event = ();
+        type TransactionPayment = ();
+        type DustRemoval = ();
+        type TransferPayment = ();
+    }
+
+    impl encrypted_balances::Trait for Test {
+        type Event = ();
+        type ConfidentialFee = encrypted_balances::FlatFee<Test>;
+    }
+
+    impl zk_system::Trait for Test {
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl zk_stats::Trait for Test { }
+
+    impl zk_auditor::Trait for Test {
+        type Event = ();
+    }
+
+    impl Trait for Test {
+        type Event = ();
+        type Currency = balances::Module<Test>;
+    }
+
+    type Balances = balances::Module<Test>;
+    type EncryptedBalances = encrypted_balances::Module<Test>;
+    type ZkShield = Module<Test>;
+
+    fn transparent_account() -> SigVerificationKey {
+        SigVerificationKey::from_slice(&[1u8; 32])
+    }
+
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let (mut t, mut c) = system::GenesisConfig::<Test>::default().build_storage().unwrap();
+        let _ = balances::GenesisConfig::<Test>{
+            balances: vec![(transparent_account(), 100)],
+            vesting: vec![],
+            existential_deposit: 0,
+            transfer_fee: 0,
+            creation_fee: 0,
+            transaction_base_fee: 0,
+            transaction_byte_fee: 0,
+        }.assimilate_storage(&mut t, &mut c);
+        let _ = zk_system::GenesisConfig::<Test>{
+            last_epoch: 1,
+            epoch_length: 1,
+            max_anonymity_set_size: 12,
+            nonce_retention_epochs: 0,
+            confidential_vk: conf_vk(),
+            anonymous_vk: anony_vk(),
+            confidential_pk_hash: [0u8; 32],
+            anonymous_pk_hash: [0u8; 32],
+        }.assimilate_storage(&mut t, &mut c);
+        let _ = encrypted_balances::GenesisConfig::<Test>{
+            encrypted_balance: vec![alice_genesis().0],
+            last_rollover: vec![alice_genesis().1],
+            transaction_base_fee: 1,
+            anonymous_fee: 0,
+            _genesis_phantom_data: Default::default()
+        }.assimilate_storage(&mut t, &mut c);
+
+        zk_system::pool::test_externalities(t, c)
+    }
+
+    fn decrypt(ciphertext: Ciphertext) -> Option<u64> {
+        elgamal::Ciphertext::<Bls12>::try_from(ciphertext).unwrap()
+            .decrypt(&dec_key(ALICE_SEED), FixedGenerators::Diversifier, &JubjubBls12::new())
+    }
+
+    #[test]
+    fn test_shield_and_unshield() {
+        with_externalities(&mut new_test_ext(), || {
+            let alice = address(ALICE_SEED);
+            let signer = Origin::signed(transparent_account());
+
+            assert!(ZkShield::shield(signer.clone(), alice, 101).is_err());
+            assert_ok!(ZkShield::shield(signer, alice, 30));
+            assert_eq!(Balances::free_balance(transparent_account()), 70);
+            assert_eq!(ZkShield::shielded_pool(), 30);
+            assert_eq!(EncryptedBalances::total_pending_transfer(&alice).and_then(decrypt), Some(30));
+
+            // Burn 8 with the fee of 1 from the genesis balance of 100, and deposit it to a new account.
+            let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+            let tx = burn(8, 1, 100, 1, rng);
+            let dest = SigVerificationKey::from_slice(&[2u8; 32]);
+            let args = (
+                Proof::from_slice(&tx.proof[..]),
+                EncKey::from_slice(&tx.enc_key_sender[..]),
+                8u64,
+                LeftCiphertext::from_slice(&tx.left_amount_sender[..]),
+                LeftCiphertext::from_slice(&tx.left_amount_recipient[..]),
+                LeftCiphertext::from_slice(&tx.left_fee[..]),
+                RightCiphertext::from_slice(&tx.right_randomness[..]),
+                Nonce::from_slice(&tx.nonce[..]),
+                tx.fee_proof.clone(),
+                dest
+            );
+
+            assert_ok!(ZkShield::unshield(
+                Origin::signed(SigVerificationKey::from_slice(&tx.rvk[..])),
+                args.0, args.1, args.2, args.3, args.4, args.5, args.6, args.7, args.8, args.9
+            ));
+            assert_eq!(Balances::free_balance(dest), 8);
+            assert_eq!(ZkShield::shielded_pool(), 22);
+            assert_eq!(EncryptedBalances::encrypted_balance(alice).and_then(decrypt), Some(91));
+        })
+    }
+
+    #[test]
+    fn test_unshield_is_capped_by_pool() {
+        with_externalities(&mut new_test_ext(), || {
+            let args = (
+                Proof::from_slice(&[0u8; 192][..]),
+                address(ALICE_SEED),
+                8u64,
+                LeftCiphertext::default(),
+                LeftCiphertext::default(),
+                LeftCiphertext::default(),
+                RightCiphertext::default(),
+                Nonce::default(),
+                vec![],
+                transparent_account()
+            );
+
+            // The genesis encrypted balances aren't in the pool.
+            assert_eq!(
+                ZkShield::unshield(
+                    Origin::signed(transparent_account()),
+                    args.0, args.1, args.2, args.3, args.4, args.5, args.6, args.7, args.8, args.9
+                ),
+                Err("The amount exceeds the shielded pool.")
+            );
+        })
+    }
+}
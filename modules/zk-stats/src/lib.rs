@@ -0,0 +1,192 @@
+//! A module for privacy-preserving statistics of shielded transfers.
+//! It only aggregates what is already public in the transfer calls (the number of transfers,
+//! the encryption keys involved and the fee ciphertexts), so explorers can chart the activity
+//! from storage without decrypting anything or crawling events.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use support::{decl_module, decl_storage, StorageValue, StorageMap};
+use runtime_primitives::traits::As;
+use zprimitives::{EncKey, Ciphertext};
+
+pub trait Trait: system::Trait + zk_system::Trait { }
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin { }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as ZkStats {
+        /// The number of shielded transfers in each epoch.
+        pub TransferCount get(transfer_count) : map T::BlockNumber => u64;
+
+        /// The number of distinct encryption keys which sent or received a confidential transfer in each epoch.
+        pub ActiveEncKeys get(active_enc_keys) : map T::BlockNumber => u64;
+
+        /// The number of distinct encryption keys which have ever sent or received a confidential transfer.
+        pub TotalEncKeys get(total_enc_keys) : u64;
+
+        /// The last epoch in which an encryption key sent or received a confidential transfer.
+        pub LastActiveEpoch get(last_active_epoch) : map EncKey => Option<T::BlockNumber>;
+
+        /// The homomorphic sum of all fee ciphertexts paid by confidential transfers.
+        /// Each fee is encrypted under its sender's key, so the sum can't be decrypted by anyone alone.
+        pub TotalFee get(total_fee) : Option<Ciphertext>;
+
+        /// The sum of the fees charged by the fee policy of `encrypted_balances`, in plain amounts.
+        pub TotalFeeCharged get(total_fee_charged) : u64;
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Record a confidential transfer between known encryption keys.
+    pub fn note_confidential_transfer(enc_keys: &[EncKey], fee: &Ciphertext) {
+        let current_epoch = <zk_system::Module<T>>::get_current_epoch();
+        Self::note_transfer(current_epoch);
+
+        for enc_key in enc_keys {
+            Self::note_active(enc_key, current_epoch);
+        }
+
+        let total_fee = match Self::total_fee() {
+            Some(total) => total.add(fee).ok(),
+            None => Some(*fee),
+        };
+        if let Some(total_fee) = total_fee {
+            <TotalFee<T>>::put(total_fee);
+        }
+    }
+
+    /// Record the fee the fee policy charged for a transfer.
+    pub fn note_fee_charged(fee: u64) {
+        <TotalFeeCharged<T>>::mutate(|total| *total = total.saturating_add(fee));
+    }
+
+    /// Record an anonymous transfer. The sender and the recipient are hidden among decoys,
+    /// so none of the encryption keys are counted as active.
+    pub fn note_anonymous_transfer() {
+        Self::note_transfer(<zk_system::Module<T>>::get_current_epoch());
+    }
+
+    fn note_transfer(epoch: T::BlockNumber) {
+        <TransferCount<T>>::mutate(epoch, |count| *count += 1);
+    }
+
+    fn note_active(enc_key: &EncKey, epoch: T::BlockNumber) {
+        match Self::last_active_epoch(enc_key) {
+            Some(last) if last == epoch => return,
+            Some(_) => {},
+            None => <TotalEncKeys<T>>::mutate(|count| *count += 1),
+        }
+
+        <ActiveEncKeys<T>>::mutate(epoch, |count| *count += 1);
+        <LastActiveEpoch<T>>::insert(enc_key, epoch);
+    }
+
+    /// The number of shielded transfers in the current epoch.
+    pub fn current_transfer_count() -> u64 {
+        Self::transfer_count(<zk_system::Module<T>>::get_current_epoch())
+    }
+
+    /// The average number of shielded transfers per epoch over the last `epochs` epochs including the current one.
+    pub fn average_transfer_count(epochs: u64) -> u64 {
+        if epochs == 0 {
+            return 0;
+        }
+
+        let current_epoch = <zk_system::Module<T>>::get_current_epoch().as_();
+        let first = current_epoch.saturating_sub(epochs - 1);
+        let total: u64 = (first..=current_epoch)
+            .map(|epoch| Self::transfer_count(T::BlockNumber::sa(epoch)))
+            .sum();
+
+        total / (current_epoch - first + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use support::impl_outer_origin;
+    use primitives::{H256, Blake2Hasher};
+    use runtime_primitives::{
+        BuildStorage, traits::{BlakeTwo256, IdentityLookup},
+        testing::{Digest, DigestItem, Header}
+    };
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = u64;
+        type SigVerificationKey = u64;
+        type Lookup = IdentityLookup<u64>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl zk_system::Trait for Test {
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl Trait for Test { }
+
+    type ZkStats = Module<Test>;
+    type System = system::Module<Test>;
+
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let (mut t, mut c) = system::GenesisConfig::<Test>::default().build_storage().unwrap();
+        let _ = zk_system::GenesisConfig::<Test>{
+            last_epoch: 0,
+            epoch_length: 10,
+            max_anonymity_set_size: 12,
+            nonce_retention_epochs: 0,
+            confidential_vk: Default::default(),
+            anonymous_vk: Default::default(),
+            confidential_pk_hash: [0u8; 32],
+            anonymous_pk_hash: [0u8; 32],
+        }.assimilate_storage(&mut t, &mut c);
+
+        t.into()
+    }
+
+    #[test]
+    fn test_active_enc_keys_per_epoch() {
+        with_externalities(&mut new_test_ext(), || {
+            let alice = EncKey::from_slice(&[1u8; 32]);
+            let bob = EncKey::from_slice(&[2u8; 32]);
+            let charlie = EncKey::from_slice(&[3u8; 32]);
+
+            System::set_block_number(3);
+            ZkStats::note_confidential_transfer(&[alice, bob], &Ciphertext::zero());
+            ZkStats::note_confidential_transfer(&[alice, bob], &Ciphertext::zero());
+            ZkStats::note_anonymous_transfer();
+
+            assert_eq!(ZkStats::transfer_count(0), 3);
+            assert_eq!(ZkStats::active_enc_keys(0), 2);
+            assert_eq!(ZkStats::total_enc_keys(), 2);
+
+            System::set_block_number(12);
+            ZkStats::note_confidential_transfer(&[alice, charlie], &Ciphertext::zero());
+
+            assert_eq!(ZkStats::current_transfer_count(), 1);
+            assert_eq!(ZkStats::active_enc_keys(1), 2);
+            assert_eq!(ZkStats::total_enc_keys(), 3);
+            assert_eq!(ZkStats::average_transfer_count(2), 2);
+            assert_eq!(ZkStats::average_transfer_count(5), 2);
+            assert_eq!(ZkStats::total_fee(), Some(Ciphertext::zero()));
+        })
+    }
+}
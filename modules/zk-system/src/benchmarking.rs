@@ -0,0 +1,66 @@
+//! A harness measuring the costs of the verification behind the `weights`.
+//! The modules holding the proof fixtures run it from their ignored `bench_` tests:
+//!
+//!   cargo test -p encrypted-balances --release bench_ -- --ignored --nocapture
+//!   cargo test -p anonymous-balances --release bench_ -- --ignored --nocapture
+
+use std::time::{Duration, Instant};
+use crate::weights::Weight;
+
+/// The execution times of a repeated measurement.
+#[derive(Debug, Clone)]
+pub struct Measurement {
+    pub name: &'static str,
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Measurement {
+    /// The measured weight, i.e. the slowest execution in microseconds.
+    pub fn weight(&self) -> Weight {
+        let micros = self.max.as_secs() * 1_000_000 + u64::from(self.max.subsec_micros());
+        micros.min(Weight::max_value() as u64) as Weight
+    }
+
+    /// Print the measurement against the weight the dispatchables are annotated with.
+    pub fn report(&self, annotated: Weight) {
+        println!(
+            "{}: min {:?}, median {:?}, max {:?}; measured weight {}, annotated {}",
+            self.name, self.min, self.median, self.max, self.weight(), annotated
+        );
+    }
+}
+
+/// Execute `f` `iterations` times and measure each execution.
+pub fn measure<F: FnMut()>(name: &'static str, iterations: usize, mut f: F) -> Measurement {
+    assert!(iterations > 0, "should be measured at least once.");
+
+    let mut times: Vec<Duration> = (0..iterations).map(|_| {
+        let start = Instant::now();
+        f();
+        start.elapsed()
+    }).collect();
+    times.sort();
+
+    Measurement {
+        name,
+        min: times[0],
+        median: times[times.len() / 2],
+        max: times[times.len() - 1],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure() {
+        let mut count = 0;
+        let m = measure("count", 5, || count += 1);
+
+        assert_eq!(count, 5);
+        assert!(m.min <= m.median && m.median <= m.max);
+    }
+}
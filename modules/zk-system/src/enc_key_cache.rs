@@ -0,0 +1,54 @@
+//! A native-side cache of the affine coordinates of the encryption keys used within a block.
+//!
+//! Decoding an encryption key decompresses the point and checks that it's in the prime order subgroup.
+//! In native execution (block import and RPC verification), the coordinates of the keys decoded
+//! in the current block are kept in memory, so keys used repeatedly (e.g. popular recipients or an issuer)
+//! are decoded once a block. The coordinates only depend on the key, and the cache is cleared
+//! at the end of every block, which bounds its memory without writing the storage.
+//! The Wasm runtime has no state across calls and decodes every key as before.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use pairing::bls12_381::Fr;
+use zprimitives::{EncKey, IntoXY};
+
+/// The maximum number of encryption keys whose coordinates are cached within a block.
+const MAX_CACHED_ENC_KEYS: usize = 256;
+
+thread_local! {
+    static ENC_KEY_XYS: RefCell<BTreeMap<EncKey, (Fr, Fr)>> = RefCell::new(BTreeMap::new());
+}
+
+/// The affine coordinates of the encryption key, decoded unless they are cached in this block.
+pub fn get_or_decode(enc_key: &EncKey) -> Result<(Fr, Fr), pairing::io::Error> {
+    if let Some(xy) = ENC_KEY_XYS.with(|xys| xys.borrow().get(enc_key).cloned()) {
+        return Ok(xy);
+    }
+
+    let xy = enc_key.into_xy()?;
+    ENC_KEY_XYS.with(|xys| {
+        let mut xys = xys.borrow_mut();
+        if xys.len() < MAX_CACHED_ENC_KEYS {
+            xys.insert(*enc_key, xy);
+        }
+    });
+
+    Ok(xy)
+}
+
+/// Forget the keys of the block, at the end of it.
+pub fn clear() {
+    ENC_KEY_XYS.with(|xys| xys.borrow_mut().clear());
+}
+
+/// Whether the coordinates of the key are cached.
+#[cfg(test)]
+pub fn is_cached(enc_key: &EncKey) -> bool {
+    ENC_KEY_XYS.with(|xys| xys.borrow().contains_key(enc_key))
+}
+
+/// The number of cached keys.
+#[cfg(test)]
+pub fn len() -> usize {
+    ENC_KEY_XYS.with(|xys| xys.borrow().len())
+}
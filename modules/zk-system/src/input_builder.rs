@@ -1,9 +1,17 @@
 use jubjub::curve::JubjubEngine;
+use jubjub::pedersen_hash::{pedersen_hash, Personalization};
 use zprimitives::IntoXY;
-use pairing::io;
+use pairing::{io, Field, PrimeField, PrimeFieldRepr};
 use rstd::prelude::*;
 use rstd::result;
 
+/// The number of inputs absorbed by each Pedersen hash next to the previous hash.
+/// It must match `circuit::hashed_inputs` of the proofs crate.
+const INPUTS_PER_HASH: usize = 2;
+
+/// The personalization of the Pedersen hashes of the inputs.
+const HASH_PERSONALIZATION: Personalization = Personalization::MerkleTree(62);
+
 // TODO: make compatible with smallvec
 pub struct PublicInputBuilder<E: JubjubEngine>(Vec<E::Fr>);
 
@@ -26,6 +34,12 @@ impl<E: JubjubEngine> PublicInputBuilder<E> {
         Ok(())
     }
 
+    /// Push the coordinates of a point which are already decoded, e.g. cached ones.
+    pub fn push_xy(&mut self, x: E::Fr, y: E::Fr) {
+        self.0.push(x);
+        self.0.push(y);
+    }
+
     pub fn as_slice(&self) -> &[E::Fr] {
         &self.0[..]
     }
@@ -33,4 +47,32 @@ impl<E: JubjubEngine> PublicInputBuilder<E> {
     pub fn len(&self) -> usize {
         self.0.len()
     }
+
+    /// Commit to the inputs by the chain of Pedersen hashes the circuits wrapped by `HashedInputs` inputize:
+    /// h_0 = 0, h_{k+1} = PH(h_k || x_{2k} || x_{2k+1}), with each element in 255 bits of little endian.
+    pub fn hash(&self, params: &E::Params) -> E::Fr {
+        let mut hash = E::Fr::zero();
+        for chunk in self.0.chunks(INPUTS_PER_HASH) {
+            let bits = Some(&hash).into_iter()
+                .chain(chunk)
+                .flat_map(|e| bits_le::<E>(e))
+                .collect::<Vec<bool>>();
+
+            hash = pedersen_hash::<E, _>(HASH_PERSONALIZATION, bits, params).into_xy().0;
+        }
+
+        hash
+    }
+}
+
+/// A public input built from the schema of its circuit, e.g. to hash it.
+impl<E: JubjubEngine> From<Vec<E::Fr>> for PublicInputBuilder<E> {
+    fn from(inputs: Vec<E::Fr>) -> Self {
+        PublicInputBuilder(inputs)
+    }
+}
+
+fn bits_le<E: JubjubEngine>(e: &E::Fr) -> impl Iterator<Item=bool> {
+    let repr = e.into_repr();
+    (0..E::Fr::NUM_BITS as usize).map(move |i| (repr.as_ref()[i / 64] >> (i % 64)) & 1 == 1)
 }
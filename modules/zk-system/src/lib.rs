@@ -1,59 +1,509 @@
 //! A module for dealing with zk-system
+//!
+//! The `shielded` feature (enabled by default) adds the g_epoch, the nonce pool
+//! and the verification of confidential and anonymous transfer proofs, which pull in jubjub.
+//! Without it, the module provides epochs, verifying keys and `verify_groth16`
+//! for runtimes which only need on-chain Groth16 verification,
+//! and only takes the numbers of public inputs of the circuits from `zprimitives`.
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use support::{decl_module, decl_storage, StorageValue, ensure};
+use support::{decl_module, decl_storage, decl_event, StorageValue, ensure, dispatch::Result};
 use rstd::{
     prelude::*,
     result,
-    convert::TryFrom,
 };
-use bellman_verifier::{verify_proof, PreparedVerifyingKey};
-use pairing::bls12_381::Bls12;
-use runtime_primitives::traits::{As, Zero};
+use parity_codec::{Encode, Decode};
+use bellman_verifier::{verify_proof, verify_proofs_batch, PreparedVerifyingKey};
+use pairing::{
+    Engine,
+    bls12_381::{Bls12, Fr},
+};
+use runtime_primitives::traits::{Zero, One, Hash};
+use system::ensure_root;
+#[cfg(feature = "std")]
+use serde_derive::Serialize;
+use self::pool::Pool;
+#[cfg(feature = "shielded")]
+use support::StorageMap;
+#[cfg(feature = "shielded")]
+use rstd::convert::TryFrom;
+#[cfg(feature = "shielded")]
+use runtime_primitives::traits::{As, Saturating};
+#[cfg(feature = "shielded")]
 use zprimitives::{
-    Nonce, GEpoch, Proof, Ciphertext,
-    LeftCiphertext, RightCiphertext, EncKey,
+    Nonce, GEpoch, GEpochVersion, Proof, Ciphertext,
+    LeftCiphertext, RightCiphertext, EncKey, IntoXY,
+};
+#[cfg(feature = "shielded")]
+use zprimitives::public_input::{
+    ConfidentialInput, AnonymousInput, ANONYMITY_SET_SIZE,
+};
+// The numbers of public inputs of the circuits are derived from their schemas,
+// against which the verifying keys are checked with or without `shielded`.
+use zprimitives::public_input::{
+    CONFIDENTIAL_INPUT_SIZE, ANONYMOUS_INPUT_SIZE,
 };
+#[cfg(feature = "shielded")]
 use self::input_builder::PublicInputBuilder;
+#[cfg(feature = "shielded")]
 mod input_builder;
+pub mod weights;
+pub mod pool;
+#[cfg(feature = "std")]
+pub mod benchmarking;
+#[cfg(feature = "std")]
+mod vk_cache;
+#[cfg(all(feature = "std", feature = "shielded"))]
+mod enc_key_cache;
+
+pub trait Trait: system::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// Type for all log entries of this module.
+    type Log: From<Log<Self>> + Into<system::DigestItemOf<Self>>;
+}
+
+pub type Log<T> = RawLog<
+    <T as system::Trait>::Hash,
+>;
+
+/// Logs in this module.
+#[cfg_attr(feature = "std", derive(Serialize, Debug))]
+#[derive(Encode, Decode, PartialEq, Eq, Clone)]
+pub enum RawLog<Hash> {
+    /// The root of the child trie of the pool at the end of the block.
+    PoolRoot(Pool, Hash),
+}
+
+// Implementation for tests outside of this crate.
+#[cfg(any(feature = "std", test))]
+impl<Hash: Encode> From<RawLog<Hash>> for runtime_primitives::testing::DigestItem {
+    fn from(log: RawLog<Hash>) -> runtime_primitives::testing::DigestItem {
+        runtime_primitives::generic::DigestItem::Other(log.encode())
+    }
+}
+
+/// The maximum number of nonces of past epochs removed when a new epoch starts.
+#[cfg(feature = "shielded")]
+const NONCE_PRUNING_LIMIT: u64 = 256;
+
+/// The maximum number of nonces of past epochs removed each time a nonce is used,
+/// which keeps the queue from growing even if more nonces are used than pruned at epoch changes.
+#[cfg(feature = "shielded")]
+const NONCE_PRUNING_PER_USE: u64 = 2;
 
-pub trait Trait: system::Trait { }
+/// The result of changing the epoch length at a block height.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct EpochTransition<BlockNumber> {
+    /// The epoch which is closed by the change.
+    pub current_epoch: BlockNumber,
+    /// The first epoch under the new epoch length.
+    pub next_epoch: BlockNumber,
+    /// The block height at which `next_epoch` starts.
+    pub starts_at: BlockNumber,
+    /// The epoch length before the change.
+    pub old_length: BlockNumber,
+    /// The epoch length after the change.
+    pub new_length: BlockNumber,
+}
 
-const CONFIDENTIAL_INPUT_SIZE: usize = 22;
-const ANONIMOUS_INPUT_SIZE: usize = 104;
+/// Why a zk proof was not accepted.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum VerificationError {
+    /// The proof doesn't verify against the public input.
+    InvalidProof,
+    /// The proof isn't an encoding of a Groth16 proof.
+    MalformedProof,
+    /// The public input couldn't be built from the arguments, e.g. a point isn't on the curve.
+    MalformedInput(&'static str),
+    /// The verifying key isn't set or doesn't take the public input.
+    InvalidVerifyingKey(&'static str),
+    /// The nonce of the proof is already used in the current epoch.
+    NonceUsed,
+}
+
+impl VerificationError {
+    /// A code of the error which is stable across runtime upgrades, so front-ends can match on it.
+    pub fn code(&self) -> u8 {
+        match *self {
+            VerificationError::InvalidProof => 1,
+            VerificationError::MalformedProof => 2,
+            VerificationError::MalformedInput(_) => 3,
+            VerificationError::InvalidVerifyingKey(_) => 4,
+            VerificationError::NonceUsed => 5,
+        }
+    }
+}
+
+impl From<&'static str> for VerificationError {
+    fn from(e: &'static str) -> Self {
+        VerificationError::MalformedInput(e)
+    }
+}
+
+impl From<VerificationError> for &'static str {
+    fn from(e: VerificationError) -> Self {
+        match e {
+            VerificationError::InvalidProof => "Invalid zkproof",
+            VerificationError::MalformedProof => "Faild to read zkproof.",
+            VerificationError::MalformedInput(e) => e,
+            VerificationError::InvalidVerifyingKey(e) => e,
+            VerificationError::NonceUsed => "Provided nonce is already included in the nonce pool.",
+        }
+    }
+}
+
+/// A nonce reserved by `reserve_nonce` for a call which verifies a proof with it.
+/// Failing dispatches don't roll back storage changes, so the ticket has to be passed to
+/// `commit_nonce` once the call succeeds, or to `abort_nonce` if it fails after the reservation.
+/// Reservations which are neither committed nor aborted are released at the end of the block.
+#[cfg(feature = "shielded")]
+#[must_use = "a reserved nonce has to be committed or aborted"]
+#[derive(PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct NonceTicket {
+    nonce: Nonce,
+}
+
+#[cfg(feature = "shielded")]
+impl NonceTicket {
+    pub fn nonce(&self) -> &Nonce {
+        &self.nonce
+    }
+}
+
+/// The arguments of a confidential transfer which are bound by its zk proof.
+#[cfg(feature = "shielded")]
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ConfidentialProof<AccountId> {
+    pub zkproof: Proof,
+    pub address_sender: EncKey,
+    pub address_recipient: EncKey,
+    pub amount_sender: LeftCiphertext,
+    pub amount_recipient: LeftCiphertext,
+    pub balance_sender: Ciphertext,
+    pub rvk: AccountId,
+    pub fee_sender: LeftCiphertext,
+    pub randomness: RightCiphertext,
+    pub nonce: Nonce,
+}
 
 decl_module! {
-    pub struct Module<T: Trait> for enum Call where origin: T::Origin { }
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
+        fn on_initialize(n: T::BlockNumber) {
+            Self::apply_pending_epoch_transition(n);
+
+            // Start the new epoch before any extrinsic of the block,
+            // so that the first transfer of an epoch doesn't pay for deriving g_epoch and pruning nonces.
+            // The calls still initialize the epoch lazily as a fallback.
+            let epoch = Self::epoch_at(n);
+            if Self::last_epoch() < epoch {
+                Self::init_nonce_pool(epoch);
+            }
+        }
+
+        fn on_finalize(_n: T::BlockNumber) {
+            #[cfg(all(feature = "std", feature = "shielded"))]
+            enc_key_cache::clear();
+            #[cfg(feature = "shielded")]
+            Self::release_reserved_nonces();
+            Self::deposit_pool_roots();
+        }
+
+        /// Schedule a new epoch length. Only callable by root.
+        /// The change takes effect at the next epoch boundary, so that proofs bound to the g_epoch
+        /// of the current epoch stay valid until the epoch ends. A later call replaces the scheduled length.
+        /// The scheduled transition is the one `epoch_transition` returns as a dry-run.
+        pub fn set_epoch_length(origin, new_length: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+
+            <PendingEpochTransition<T>>::put(Self::epoch_transition(new_length)?);
+
+            Ok(())
+        }
+
+        /// Schedule the hash-to-curve g_epochs are derived with, as the byte of `GEpochVersion`.
+        /// Only callable by root. The version takes effect from the g_epoch of the next epoch,
+        /// so the g_epoch of the current epoch doesn't change.
+        pub fn set_g_epoch_version(origin, version: u8) -> Result {
+            ensure_root(origin)?;
+            #[cfg(feature = "shielded")]
+            ensure!(GEpochVersion::from_u8(version).is_some(), "Unknown g_epoch version.");
+
+            <PendingGEpochVersion<T>>::put(version);
+
+            Ok(())
+        }
+
+        /// Set the number of past epochs whose used nonces are kept. Only callable by root.
+        /// Nonces of epochs which fall out of the new window are pruned gradually, as when an epoch ends.
+        pub fn set_nonce_retention_epochs(origin, epochs: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+
+            <NonceRetentionEpochs<T>>::put(epochs);
+
+            Ok(())
+        }
+
+        /// Replace the verifying key of confidential transfer after a circuit upgrade. Only callable by root.
+        /// `pk_hash` is the hash of the matching proving key file, which wallets check before proving.
+        /// Like the other updates, `vk` can be the unprepared key, which is much smaller than the prepared one.
+        pub fn update_confidential_vk(origin, vk: Vec<u8>, pk_hash: [u8; 32]) -> Result {
+            ensure_root(origin)?;
+
+            let prepared_vk = Self::read_vk(&vk[..], CONFIDENTIAL_INPUT_SIZE)?;
+            <ConfidentialVk<T>>::put(prepared_vk);
+            <ConfidentialPkHash<T>>::put(pk_hash);
+
+            Self::deposit_event(RawEvent::ConfidentialVkUpdated(T::Hashing::hash(&vk[..])));
+
+            Ok(())
+        }
+
+        /// Replace the verifying key of anonymous transfer after a circuit upgrade. Only callable by root.
+        pub fn update_anonymous_vk(origin, vk: Vec<u8>, pk_hash: [u8; 32]) -> Result {
+            ensure_root(origin)?;
+
+            let prepared_vk = Self::read_vk(&vk[..], ANONYMOUS_INPUT_SIZE)?;
+            <AnonymousVk<T>>::put(prepared_vk);
+            <AnonymousPkHash<T>>::put(pk_hash);
+            <AnonymousInputsHashed<T>>::kill();
+
+            Self::deposit_event(RawEvent::AnonymousVkUpdated(T::Hashing::hash(&vk[..])));
+
+            Ok(())
+        }
+
+        /// Replace the verifying key of anonymous transfer by a key of the circuit which commits to
+        /// its public inputs by a single hash input. Only callable by root.
+        /// Proofs are then verified against the hash of the inputs, which only takes one input to prepare.
+        pub fn update_hashed_anonymous_vk(origin, vk: Vec<u8>, pk_hash: [u8; 32]) -> Result {
+            ensure_root(origin)?;
+
+            let prepared_vk = Self::read_vk(&vk[..], 1)?;
+            <AnonymousVk<T>>::put(prepared_vk);
+            <AnonymousPkHash<T>>::put(pk_hash);
+            <AnonymousInputsHashed<T>>::put(true);
+
+            Self::deposit_event(RawEvent::AnonymousVkUpdated(T::Hashing::hash(&vk[..])));
+
+            Ok(())
+        }
+    }
 }
 
-decl_storage! {
-    trait Store for Module<T: Trait> as ZkSystem {
-        /// Global epoch length for rollover.
-        /// The longer epoch length is, the longer rollover time is.
-        /// This parameter should be fixed based on trade-off between UX and security in terms of front-running attacks.
-        pub EpochLength get(epoch_length) config() : T::BlockNumber;
+decl_event! (
+    pub enum Event<T> where <T as system::Trait>::Hash, <T as system::Trait>::BlockNumber {
+        /// The verifying key of confidential transfer was replaced by the key with the given hash.
+        ConfidentialVkUpdated(Hash),
+        /// The verifying key of anonymous transfer was replaced by the key with the given hash.
+        AnonymousVkUpdated(Hash),
+        /// The epoch length changed to the new length from the given epoch, starting at the given block height.
+        /// (epoch, starts_at, new_length)
+        EpochLengthUpdated(BlockNumber, BlockNumber, BlockNumber),
+        /// The g_epochs are derived with the given version from the given epoch.
+        GEpochVersionUpdated(BlockNumber, u8),
+        /// A proof failed verification in the given epoch, with the code of the `VerificationError`.
+        ProofRejected(BlockNumber, u8),
+        /// The given number of used nonces of the epochs from the first to the second given one were pruned.
+        /// (first epoch, last epoch, count)
+        NoncesPruned(BlockNumber, BlockNumber, u64),
+    }
+);
+
+/// Declare the storage of the module, with the storage items of the `shielded` feature appended.
+macro_rules! decl_zk_storage {
+    ($($shielded:tt)*) => {
+        decl_storage! {
+            trait Store for Module<T: Trait> as ZkSystem {
+                /// Global epoch length for rollover.
+                /// The longer epoch length is, the longer rollover time is.
+                /// This parameter should be fixed based on trade-off between UX and security in terms of front-running attacks.
+                pub EpochLength get(epoch_length) config() : T::BlockNumber;
+
+                /// A global last epoch which will be updated in the roll_over function.
+                pub LastEpoch get(last_epoch) config() : T::BlockNumber;
+
+                /// The byte of the `GEpochVersion` the g_epoch of the last epoch is derived with.
+                pub CurrentGEpochVersion get(g_epoch_version) : u8;
+
+                /// The version scheduled by `set_g_epoch_version`, applied when the next epoch starts.
+                pub PendingGEpochVersion get(pending_g_epoch_version) : Option<u8>;
+
+                /// The epoch index at which the current epoch length took effect.
+                /// Epoch indices keep counting up from here, so that indices stored before
+                /// an epoch length change (e.g. `LastRollOver`) are still comparable.
+                pub EpochOffset get(epoch_offset) : T::BlockNumber;
+
+                /// The block height at which the current epoch length took effect.
+                pub EpochLengthChangedAt get(epoch_length_changed_at) : T::BlockNumber;
+
+                /// The epoch length change scheduled by `set_epoch_length`, applied at the block it starts at.
+                pub PendingEpochTransition get(pending_epoch_transition) : Option<EpochTransition<T::BlockNumber>>;
 
-        /// A global last epoch which will be updated in the roll_over function.
-        pub LastEpoch get(last_epoch) config() : T::BlockNumber;
+                /// The maximum number of accounts (including the sender and the recipient)
+                /// an anonymous transfer can hide among. Wallets use it as the default anonymity set size.
+                pub MaxAnonymitySetSize get(max_anonymity_set_size) config() : u32;
 
-        /// An epoch based generator point
-        pub LastGEpoch get(g_epoch) build(|_| GEpoch::try_new().expect("Should init.")) : GEpoch;
+                /// The number of past epochs whose used nonces are kept in `NonceEpoch`,
+                /// so that archive queries can tell whether a nonce was used in a recent epoch.
+                /// With 0, the nonces of an epoch are pruned once it is over.
+                pub NonceRetentionEpochs get(nonce_retention_epochs) config() : T::BlockNumber;
 
-        /// A nonce pool. All nonces are erasured at the time of starting each epochs.
-        // TODO: Change to BTreeSet once parity-codec is updated to parity-scale-codec
-        pub NoncePool get(nonce_pool) config() : Vec<Nonce>;
+                /// A verification key of zk proofs of confidential transfer, replaced by `update_confidential_vk`
+                pub ConfidentialVk get(confidential_vk) config(): PreparedVerifyingKey<Bls12>;
 
-        /// A verification key of zk proofs of confidential transfer(only readable)
-        pub ConfidentialVk get(confidential_vk) config(): PreparedVerifyingKey<Bls12>;
+                /// A verification key of zk proofs of anonymous transfer, replaced by `update_anonymous_vk`
+                pub AnonymousVk get(anonymous_vk) config(): PreparedVerifyingKey<Bls12>;
 
-        /// A verification key of zk proofs of anonymous transfer(only readable)
-        pub AnonymousVk get(anonymous_vk) config(): PreparedVerifyingKey<Bls12>;
+                /// blake2_256 of the proving key file of confidential transfer matching `ConfidentialVk`.
+                /// Wallets check their local proving key against it before proving.
+                pub ConfidentialPkHash get(confidential_pk_hash) config(): [u8; 32];
+
+                /// blake2_256 of the proving key file of anonymous transfer matching `AnonymousVk`.
+                pub AnonymousPkHash get(anonymous_pk_hash) config(): [u8; 32];
+
+                /// Whether `AnonymousVk` is a key of the circuit committing to its public inputs by their hash,
+                /// set by `update_hashed_anonymous_vk`.
+                pub AnonymousInputsHashed get(anonymous_inputs_hashed): bool;
+
+                $($shielded)*
+            }
+        }
     }
 }
 
+#[cfg(feature = "shielded")]
+decl_zk_storage! {
+    /// An epoch based generator point
+    pub LastGEpoch get(g_epoch) build(|_| GEpoch::try_new().expect("Should init.")) : GEpoch;
+
+    /// The epoch in which each nonce was used.
+    /// A nonce is spent only if it was used in the last epoch,
+    /// so the entries of past epochs don't have to be removed at once when a new epoch starts.
+    pub NonceEpoch get(nonce_epoch) : map Nonce => Option<T::BlockNumber>;
+
+    /// Used nonces with their epochs in the order of use,
+    /// pruned from the front once their epoch is older than `NonceRetentionEpochs`.
+    NonceQueue get(nonce_queue) : map u64 => Option<(T::BlockNumber, Nonce)>;
+
+    /// The index of the oldest entry of `NonceQueue`.
+    NonceQueueHead get(nonce_queue_head) : u64;
+
+    /// The index of the next entry of `NonceQueue`.
+    NonceQueueTail get(nonce_queue_tail) : u64;
+
+    /// The nonces reserved by `reserve_nonce` in the current block and not committed or aborted yet.
+    ReservedNonces get(reserved_nonces) : Vec<Nonce>;
+
+    /// The number of proofs which failed verification, so operators can tell a spam of malformed proofs.
+    pub InvalidProofCount get(invalid_proof_count) : u64;
+
+    /// The number of proofs which failed verification in an epoch, by the code of the `VerificationError`.
+    pub InvalidProofsInEpoch get(invalid_proofs_in_epoch) : map (T::BlockNumber, u8) => u64;
+}
+
+#[cfg(not(feature = "shielded"))]
+decl_zk_storage! { }
+
 impl<T: Trait> Module<T> {
+    /// Read a verifying key, checking that it takes `num_inputs` public inputs.
+    /// The key may be given unprepared, in which case it is prepared once here and stored prepared.
+    pub fn read_vk(vk: &[u8], num_inputs: usize) -> result::Result<PreparedVerifyingKey<Bls12>, &'static str> {
+        let prepared_vk = PreparedVerifyingKey::<Bls12>::from_bytes(vk)
+            .map_err(|_| "Faild to read verifying key.")?;
+        ensure!(prepared_vk.num_inputs() == num_inputs, "Mismatch the number of public inputs of verifying key.");
+
+        Ok(prepared_vk)
+    }
+
+    /// The verifying key stored in the storage value `S`, e.g. `ConfidentialVk<T>`.
+    /// Native builds reuse the key decoded by a previous call while its encoding is unchanged, see `vk_cache`.
+    #[cfg(feature = "std")]
+    pub fn prepared_vk<S>() -> PreparedVerifyingKey<Bls12>
+    where
+        S: support::storage::generator::StorageValue<PreparedVerifyingKey<Bls12>>
+    {
+        let key = runtime_io::twox_128(S::key());
+        support::storage::unhashed::get_raw(&key[..])
+            .map_or_else(Default::default, |encoded| vk_cache::get_or_decode(&key[..], &encoded[..]))
+    }
+
+    /// The verifying key stored in the storage value `S`, e.g. `ConfidentialVk<T>`.
+    #[cfg(not(feature = "std"))]
+    pub fn prepared_vk<S>() -> PreparedVerifyingKey<Bls12>
+    where
+        S: support::storage::generator::StorageValue<PreparedVerifyingKey<Bls12>>
+    {
+        <S as StorageValue<PreparedVerifyingKey<Bls12>>>::get()
+    }
+
+    /// Whether a key is set in the storage value `S`, e.g. `ConfidentialVk<T>`, without decoding it.
+    /// An `Uninitialized` key is encoded as an empty vector, i.e. in a single byte.
+    pub fn has_vk<S>() -> bool
+    where
+        S: support::storage::generator::StorageValue<PreparedVerifyingKey<Bls12>>
+    {
+        let key = runtime_io::twox_128(S::key());
+        support::storage::unhashed::get_raw(&key[..]).map_or(false, |encoded| encoded.len() > 1)
+    }
+
+    /// Verify a Groth16 proof against a prepared verifying key.
+    /// This is available without the `shielded` feature, e.g. for verifying proofs of other circuits.
+    /// The curve is chosen per circuit by the type of its key: the transfer circuits use `Bls12`,
+    /// and circuits which don't need its security level can use the cheaper `Bn254` of the `bn254` feature,
+    /// whose keys and proofs are also produced by the Ethereum tooling.
+    pub fn verify_groth16<E: Engine>(
+        vk: &PreparedVerifyingKey<E>,
+        proof: &bellman_verifier::Proof<E>,
+        public_input: &[E::Fr]
+    ) -> result::Result<(), VerificationError> {
+        Self::check_verification(verify_proof(vk, proof, public_input))
+    }
+
+    // Fold the result of the underlying verifier, which reports an invalid proof as `Ok(false)`.
+    fn check_verification<E>(res: result::Result<bool, E>) -> result::Result<(), VerificationError> {
+        match res {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(VerificationError::InvalidProof),
+            Err(_) => Err(VerificationError::InvalidVerifyingKey("Mismatch the public input and the verifying key.")),
+        }
+    }
+
+    /// Verify Groth16 proofs against the same prepared verifying key with a single final exponentiation
+    /// instead of one pairing check per proof.
+    /// The random scalars combining the verification equations are derived from the hash of
+    /// all the proofs and public inputs, so the provers can't choose them.
+    pub fn verify_groth16_batch<E: Engine>(
+        vk: &PreparedVerifyingKey<E>,
+        batch: &[(bellman_verifier::Proof<E>, Vec<E::Fr>)]
+    ) -> result::Result<(), VerificationError> {
+        let batch: Vec<_> = batch.iter().map(|(proof, input)| (proof, &input[..])).collect();
+        Self::check_verification(verify_proofs_batch(vk, &batch[..]))
+    }
+
+    // Count a proof which failed verification by the category of the error.
+    #[cfg(feature = "shielded")]
+    fn note_verification(res: result::Result<(), VerificationError>) -> result::Result<(), VerificationError> {
+        if let Err(e) = res {
+            let epoch = Self::get_current_epoch();
+            <InvalidProofCount<T>>::mutate(|count| *count = count.saturating_add(1));
+            <InvalidProofsInEpoch<T>>::mutate((epoch, e.code()), |count| *count = count.saturating_add(1));
+            Self::deposit_event(RawEvent::ProofRejected(epoch, e.code()));
+        }
+
+        res
+    }
+
     /// Verify zk proofs of confidential transfers
-	pub fn verify_confidential_proof (
+    #[cfg(feature = "shielded")]
+    pub fn verify_confidential_proof(
         zkproof: &Proof,
         address_sender: &EncKey,
         address_recipient: &EncKey,
@@ -64,123 +514,1051 @@ impl<T: Trait> Module<T> {
         fee_sender: &LeftCiphertext,
         randomness: &RightCiphertext,
         nonce: &Nonce
-    ) -> result::Result<bool, &'static str> {
-        // Construct public input for circuit
-        let mut public_input = PublicInputBuilder::<Bls12>::new(CONFIDENTIAL_INPUT_SIZE);
-        public_input.push(Some(address_sender))
-            .map_err(|_| "Faild to get address_sender into xy.")?;
-
-        public_input.push(Some(address_recipient))
-            .map_err(|_| "Faild to get address_recipient into xy.")?;
-
-        public_input.push(Some(amount_sender))
-            .map_err(|_| "Faild to get amount_sender into xy.")?;
-
-        public_input.push(Some(amount_recipient))
-            .map_err(|_| "Faild to get amount_recipient into xy.")?;
+    ) -> result::Result<(), VerificationError> {
+        Self::note_verification(Self::try_verify_confidential_proof(
+            zkproof,
+            address_sender,
+            address_recipient,
+            amount_sender,
+            amount_recipient,
+            balance_sender,
+            rvk,
+            fee_sender,
+            randomness,
+            nonce
+        ))
+    }
 
-        public_input.push(Some(randomness))
-            .map_err(|_| "Faild to get randomness into xy.")?;
+    #[cfg(feature = "shielded")]
+    fn try_verify_confidential_proof(
+        zkproof: &Proof,
+        address_sender: &EncKey,
+        address_recipient: &EncKey,
+        amount_sender: &LeftCiphertext,
+        amount_recipient: &LeftCiphertext,
+        balance_sender: &Ciphertext,
+        rvk: &T::AccountId,
+        fee_sender: &LeftCiphertext,
+        randomness: &RightCiphertext,
+        nonce: &Nonce
+    ) -> result::Result<(), VerificationError> {
+        let public_input = Self::confidential_public_input(
+            address_sender,
+            address_recipient,
+            amount_sender,
+            amount_recipient,
+            balance_sender,
+            rvk,
+            fee_sender,
+            randomness,
+            nonce
+        )?;
 
-        public_input.push(Some(fee_sender))
-            .map_err(|_| "Faild to get fee_sender into xy.")?;
+        let proof = bellman_verifier::Proof::<Bls12>::try_from(zkproof)
+            .map_err(|_| VerificationError::MalformedProof)?;
 
-        public_input.push(balance_sender.left().ok())
-            .map_err(|_| "Faild to get balance_sender's left into xy.")?;
+        // Verify the provided proof
+        Self::verify_groth16(&Self::prepared_vk::<ConfidentialVk<T>>(), &proof, &public_input[..])
+    }
 
-        public_input.push(balance_sender.right().ok())
-            .map_err(|_| "Faild to get balance_sender's right into xy.")?;
+    /// Verify zk proofs of multiple confidential transfers at once.
+    /// Returns `Ok(())` only if all the proofs are valid.
+    #[cfg(feature = "shielded")]
+    pub fn verify_confidential_proofs_batch(
+        transfers: &[ConfidentialProof<T::AccountId>]
+    ) -> result::Result<(), VerificationError> {
+        Self::note_verification(Self::try_verify_confidential_proofs_batch(transfers))
+    }
 
-        public_input.push(Some(rvk.clone()))
-            .map_err(|_| "Faild to get rvk into xy.")?;
+    #[cfg(feature = "shielded")]
+    fn try_verify_confidential_proofs_batch(
+        transfers: &[ConfidentialProof<T::AccountId>]
+    ) -> result::Result<(), VerificationError> {
+        let mut batch = Vec::with_capacity(transfers.len());
+        for transfer in transfers {
+            let public_input = Self::confidential_public_input(
+                &transfer.address_sender,
+                &transfer.address_recipient,
+                &transfer.amount_sender,
+                &transfer.amount_recipient,
+                &transfer.balance_sender,
+                &transfer.rvk,
+                &transfer.fee_sender,
+                &transfer.randomness,
+                &transfer.nonce
+            )?;
+            let proof = bellman_verifier::Proof::<Bls12>::try_from(&transfer.zkproof)
+                .map_err(|_| VerificationError::MalformedProof)?;
 
-        public_input.push(Some(Self::g_epoch()))
-            .map_err(|_| "Faild to get g_epoch into xy.")?;
+            batch.push((proof, public_input));
+        }
 
-        public_input.push(Some(nonce))
-            .map_err(|_| "Faild to get nonce into xy.")?;
+        Self::verify_groth16_batch(&Self::prepared_vk::<ConfidentialVk<T>>(), &batch[..])
+    }
 
-        ensure!(public_input.len() == CONFIDENTIAL_INPUT_SIZE, "Mismatch the length of public input.");
+    // Construct public input for the confidential transfer circuit.
+    #[cfg(feature = "shielded")]
+    fn confidential_public_input(
+        address_sender: &EncKey,
+        address_recipient: &EncKey,
+        amount_sender: &LeftCiphertext,
+        amount_recipient: &LeftCiphertext,
+        balance_sender: &Ciphertext,
+        rvk: &T::AccountId,
+        fee_sender: &LeftCiphertext,
+        randomness: &RightCiphertext,
+        nonce: &Nonce
+    ) -> result::Result<Vec<Fr>, &'static str> {
+        let g_epoch = Self::g_epoch();
+        let public_input = ConfidentialInput {
+            address_sender,
+            address_recipient,
+            amount_sender,
+            amount_recipient,
+            randomness,
+            fee_sender,
+            balance_sender,
+            rvk,
+            g_epoch: &g_epoch,
+            nonce,
+        }.to_vec_with(Self::enc_key_xy)?;
 
-        let proof = bellman_verifier::Proof::<Bls12>::try_from(zkproof)
-            .map_err(|_| "Faild to read zkproof.")?;
+        Ok(public_input)
+    }
 
-        // Verify the provided proof
-        verify_proof(
-            &Self::confidential_vk(),
-            &proof,
-            public_input.as_slice()
-        )
-        .map_err(|_| "Invalid proof.")
+    /// Verify zk proofs of anonymous transfers paying `fee`, the fee of the chain.
+    #[cfg(feature = "shielded")]
+    pub fn verify_anonymous_proof(
+        zkproof: &Proof,
+        enc_keys: &[EncKey],
+        left_ciphertexts: &[LeftCiphertext],
+        right_ciphertext: &RightCiphertext,
+        enc_balances: &[Ciphertext],
+        fee: u64,
+        rvk: &T::AccountId,
+        nonce: &Nonce
+    ) -> result::Result<(), VerificationError> {
+        Self::note_verification(Self::try_verify_anonymous_proof(
+            zkproof,
+            enc_keys,
+            left_ciphertexts,
+            right_ciphertext,
+            enc_balances,
+            fee,
+            rvk,
+            nonce
+        ))
     }
 
-    /// Verify zk proofs of anonymous transfers
-	pub fn verify_anonymous_proof (
+    #[cfg(feature = "shielded")]
+    fn try_verify_anonymous_proof(
         zkproof: &Proof,
         enc_keys: &[EncKey],
         left_ciphertexts: &[LeftCiphertext],
         right_ciphertext: &RightCiphertext,
         enc_balances: &[Ciphertext],
+        fee: u64,
         rvk: &T::AccountId,
         nonce: &Nonce
-    ) -> result::Result<bool, &'static str> {
+    ) -> result::Result<(), VerificationError> {
+        let mut enc_balances_left = Vec::with_capacity(enc_balances.len());
+        let mut enc_balances_right = Vec::with_capacity(enc_balances.len());
+        for enc_balance in enc_balances {
+            enc_balances_left.push(enc_balance.left().map_err(|_| "Faild to get left ciphertexts into xy.")?);
+            enc_balances_right.push(enc_balance.right().map_err(|_| "Faild to get right ciphertexts into xy.")?);
+        }
+
         // Construct public input for circuit
-        let mut public_input = PublicInputBuilder::<Bls12>::new(ANONIMOUS_INPUT_SIZE);
-        public_input.push(enc_keys)
-            .map_err(|_| "Faild to get enc keys into xy.")?;
+        let public_input = PublicInputBuilder::<Bls12>::from(AnonymousInput {
+            enc_keys: Self::anonymity_set(enc_keys)?,
+            left_ciphertexts: Self::anonymity_set(left_ciphertexts)?,
+            enc_balances_left: Self::anonymity_set(&enc_balances_left)?,
+            enc_balances_right: Self::anonymity_set(&enc_balances_right)?,
+            right_ciphertext,
+            fee: &fee,
+            rvk,
+            g_epoch: &g_epoch,
+            nonce,
+        }.to_vec_with(Self::enc_key_xy)?);
 
-        public_input.push(left_ciphertexts)
-            .map_err(|_| "Faild to get left ciphertexts into xy.")?;
+        let proof = bellman_verifier::Proof::<Bls12>::try_from(zkproof)
+            .map_err(|_| VerificationError::MalformedProof)?;
 
-        public_input.push(enc_balances.iter().map(|e| e.left().unwrap())) // TODO
-            .map_err(|_| "Faild to get left ciphertexts into xy.")?;
+        // Verify the provided proof
+        if Self::anonymous_inputs_hashed() {
+            Self::verify_groth16(&Self::prepared_vk::<AnonymousVk<T>>(), &proof, &[public_input.hash(&*zprimitives::PARAMS)])
+        } else {
+            Self::verify_groth16(&Self::prepared_vk::<AnonymousVk<T>>(), &proof, public_input.as_slice())
+        }
+    }
 
-        public_input.push(enc_balances.iter().map(|e| e.right().unwrap())) // TODO
-            .map_err(|_| "Faild to get right ciphertexts into xy.")?;
+    // The inputs of each account of the anonymity set of the anonymous transfer circuit.
+    #[cfg(feature = "shielded")]
+    fn anonymity_set<I>(inputs: &[I]) -> result::Result<&[I; ANONYMITY_SET_SIZE], VerificationError> {
+        TryFrom::try_from(inputs)
+            .map_err(|_| VerificationError::MalformedInput("Mismatch the length of public input."))
+    }
 
-        public_input.push(Some(right_ciphertext))
-            .map_err(|_| "Faild to get right ciphertexts into xy.")?;
+    /// Get current epoch based on current block height.
+    pub fn get_current_epoch() -> T::BlockNumber {
+        let current_height = <system::Module<T>>::block_number();
+        Self::epoch_at(current_height)
+    }
 
-        public_input.push(Some(rvk.clone()))
-            .map_err(|_| "Faild to get rvk into xy.")?;
+    /// Get the epoch of the given block height under the current epoch length.
+    /// Heights before the last epoch length change are mapped to `EpochOffset`.
+    pub fn epoch_at(height: T::BlockNumber) -> T::BlockNumber {
+        let changed_at = Self::epoch_length_changed_at();
+        if height < changed_at {
+            return Self::epoch_offset();
+        }
 
-        public_input.push(Some(Self::g_epoch()))
-            .map_err(|_| "Faild to get g_epoch into xy.")?;
+        Self::epoch_offset() + (height - changed_at) / Self::epoch_length()
+    }
 
-        public_input.push(Some(nonce))
-            .map_err(|_| "Faild to get nonce into xy.")?;
+    /// The first block after the current one which starts an epoch under the current epoch length.
+    pub fn next_epoch_boundary() -> T::BlockNumber {
+        let next_epoch = Self::get_current_epoch() + T::BlockNumber::one();
+        Self::epoch_length_changed_at() + (next_epoch - Self::epoch_offset()) * Self::epoch_length()
+    }
 
-        ensure!(public_input.len() == ANONIMOUS_INPUT_SIZE, "Mismatch the length of public input.");
+    /// Compute the epoch transition which `set_epoch_length` schedules at the current block height,
+    /// i.e. the one applied at the next epoch boundary, without any storage mutations.
+    /// This is exposed as a dry-run through the runtime API.
+    pub fn epoch_transition(new_length: T::BlockNumber) -> result::Result<EpochTransition<T::BlockNumber>, &'static str> {
+        ensure!(!new_length.is_zero(), "Epoch length must not be zero.");
 
-        let proof = bellman_verifier::Proof::<Bls12>::try_from(zkproof)
-            .map_err(|_| "Faild to read zkproof.")?;
+        let current_epoch = Self::get_current_epoch();
 
-        // Verify the provided proof
-        verify_proof(
-            &Self::anonymous_vk(),
-            &proof,
-            public_input.as_slice()
-        )
-        .map_err(|_| "Error occurred when valifying zkproof.")
+        Ok(EpochTransition {
+            current_epoch,
+            next_epoch: current_epoch + T::BlockNumber::one(),
+            starts_at: Self::next_epoch_boundary(),
+            old_length: Self::epoch_length(),
+            new_length,
+        })
     }
 
-    /// Get current epoch based on current block height.
-    pub fn get_current_epoch() -> T::BlockNumber {
-        let current_height = <system::Module<T>>::block_number();
-        current_height / Self::epoch_length()
+    /// Migrate to a new epoch length right away, closing the current epoch at the current block height.
+    /// Dividing the block height by the new length would re-interpret every stored epoch index,
+    /// so accounts could roll over twice in an epoch or skip rollovers, and g_epoch (and nonces) could be reused.
+    /// Instead, the current epoch is closed and the epochs under the new length
+    /// are counted up from the next epoch index, starting at the current block height.
+    /// This replaces the transition scheduled by `set_epoch_length`, if any.
+    pub fn migrate_epoch_length(new_length: T::BlockNumber) -> result::Result<EpochTransition<T::BlockNumber>, &'static str> {
+        let transition = EpochTransition {
+            starts_at: <system::Module<T>>::block_number(),
+            ..Self::epoch_transition(new_length)?
+        };
+
+        Self::migrate_to(&transition);
+
+        Ok(transition)
+    }
+
+    /// Apply the transition scheduled by `set_epoch_length` if it starts at the block `n`.
+    /// The block `n` is the first one of the next epoch under the old length, so no epoch index is skipped.
+    fn apply_pending_epoch_transition(n: T::BlockNumber) {
+        match Self::pending_epoch_transition() {
+            Some(ref transition) if transition.starts_at == n => Self::migrate_to(transition),
+            _ => {},
+        }
+    }
+
+    // Both the immediate and the scheduled changes of the epoch length are applied here.
+    fn migrate_to(transition: &EpochTransition<T::BlockNumber>) {
+        <EpochOffset<T>>::put(transition.next_epoch);
+        <EpochLengthChangedAt<T>>::put(transition.starts_at);
+        <EpochLength<T>>::put(transition.new_length);
+        <PendingEpochTransition<T>>::kill();
+
+        Self::init_nonce_pool(transition.next_epoch);
+
+        Self::deposit_event(RawEvent::EpochLengthUpdated(transition.next_epoch, transition.starts_at, transition.new_length));
     }
 
     /// Initialize global nonce-related storages
     /// 1. Set last g_epoch to current g_epoch
-    /// 2. Remove all nonces in the pool
-    /// 3. Set last epoch to current epoch
+    /// 2. Set last epoch to current epoch, which makes all the nonces used so far unspent
+    /// 3. Remove a bounded number of nonces of epochs out of the retention window
     pub fn init_nonce_pool(current_epoch: T::BlockNumber) {
         if Self::last_epoch() < current_epoch || current_epoch == T::BlockNumber::zero() {
-            let g_epoch = GEpoch::group_hash(current_epoch.as_() as u32).unwrap();
+            #[cfg(feature = "shielded")]
+            {
+                // The epoch 0 is initialized by every call in it, so a version is only applied at a new epoch.
+                if Self::last_epoch() < current_epoch {
+                    if let Some(version) = <PendingGEpochVersion<T>>::take() {
+                        <CurrentGEpochVersion<T>>::put(version);
+                        Self::deposit_event(RawEvent::GEpochVersionUpdated(current_epoch, version));
+                    }
+                }
+
+                let version = GEpochVersion::from_u8(Self::g_epoch_version()).unwrap_or_default();
+                let g_epoch = GEpoch::group_hash_with_version(current_epoch.as_() as u32, version).unwrap();
 
-            <LastGEpoch<T>>::put(g_epoch);
-            <NoncePool<T>>::kill();
+                <LastGEpoch<T>>::put(g_epoch);
+            }
             <LastEpoch<T>>::put(current_epoch);
+
+            #[cfg(feature = "shielded")]
+            Self::prune_nonces(NONCE_PRUNING_LIMIT);
+        }
+    }
+
+    /// The affine coordinates of an encryption key.
+    /// Native builds reuse the coordinates decoded earlier in the block, see `enc_key_cache`.
+    #[cfg(all(feature = "std", feature = "shielded"))]
+    pub fn enc_key_xy(enc_key: &EncKey) -> result::Result<(Fr, Fr), pairing::io::Error> {
+        enc_key_cache::get_or_decode(enc_key)
+    }
+
+    /// The affine coordinates of an encryption key.
+    #[cfg(all(not(feature = "std"), feature = "shielded"))]
+    pub fn enc_key_xy(enc_key: &EncKey) -> result::Result<(Fr, Fr), pairing::io::Error> {
+        enc_key.into_xy()
+    }
+
+    /// Check if the nonce has already been used in the current epoch.
+    #[cfg(feature = "shielded")]
+    pub fn is_nonce_used(nonce: &Nonce) -> bool {
+        Self::nonce_epoch(nonce) == Some(Self::last_epoch())
+    }
+
+    /// Check if the nonce is reserved by a call which hasn't committed or aborted it yet.
+    #[cfg(feature = "shielded")]
+    pub fn is_nonce_reserved(nonce: &Nonce) -> bool {
+        Self::reserved_nonces().contains(nonce)
+    }
+
+    /// Reserve the nonce for a call verifying a proof with it, before changing any other storage.
+    /// It fails if the nonce is used in the current epoch or already reserved, so two calls can't verify with the same nonce.
+    #[cfg(feature = "shielded")]
+    pub fn reserve_nonce(nonce: Nonce) -> result::Result<NonceTicket, VerificationError> {
+        let mut reserved = Self::reserved_nonces();
+        if Self::is_nonce_used(&nonce) || reserved.contains(&nonce) {
+            return Err(VerificationError::NonceUsed);
+        }
+
+        reserved.push(nonce);
+        <ReservedNonces<T>>::put(reserved);
+
+        Ok(NonceTicket { nonce })
+    }
+
+    /// Record the nonce of the ticket as used in the current epoch.
+    #[cfg(feature = "shielded")]
+    pub fn commit_nonce(ticket: NonceTicket) {
+        Self::release_nonce(&ticket.nonce);
+        Self::record_nonce(ticket.nonce);
+    }
+
+    /// Release the nonce of the ticket without recording it, so the nonce can be used again.
+    #[cfg(feature = "shielded")]
+    pub fn abort_nonce(ticket: NonceTicket) {
+        Self::release_nonce(&ticket.nonce);
+    }
+
+    /// Abort all the tickets, e.g. the ones reserved by a batch before one of its proofs failed.
+    #[cfg(feature = "shielded")]
+    pub fn abort_nonces<I: IntoIterator<Item = NonceTicket>>(tickets: I) {
+        for ticket in tickets {
+            Self::abort_nonce(ticket);
+        }
+    }
+
+    #[cfg(feature = "shielded")]
+    fn release_nonce(nonce: &Nonce) {
+        let mut reserved = Self::reserved_nonces();
+        reserved.retain(|n| n != nonce);
+        <ReservedNonces<T>>::put(reserved);
+    }
+
+    // Release the reservations whose tickets were dropped, at the end of the block.
+    #[cfg(feature = "shielded")]
+    fn release_reserved_nonces() {
+        <ReservedNonces<T>>::kill();
+    }
+
+    // Log the roots of the child tries of the pools at the end of the block,
+    // which light clients check the balances of the pools against.
+    fn deposit_pool_roots() {
+        for pool in Pool::ALL.iter() {
+            if let Some(root) = pool.root().and_then(|r| T::Hash::decode(&mut &r[..])) {
+                Self::deposit_log(RawLog::PoolRoot(*pool, root));
+            }
+        }
+    }
+
+    /// Deposit one of this module's logs.
+    fn deposit_log(log: Log<T>) {
+        <system::Module<T>>::deposit_log(<T as Trait>::Log::from(log).into());
+    }
+
+    /// Record the nonce as used in the current epoch, for calls which don't change any storage
+    /// between the check of the nonce and its use. Others should use `reserve_nonce`.
+    #[cfg(feature = "shielded")]
+    pub fn insert_nonce(nonce: Nonce) -> result::Result<(), VerificationError> {
+        if Self::is_nonce_used(&nonce) || Self::is_nonce_reserved(&nonce) {
+            return Err(VerificationError::NonceUsed);
         }
+
+        Self::record_nonce(nonce);
+
+        Ok(())
+    }
+
+    /// The indices of the oldest and the next entry of the queue of used nonces.
+    #[cfg(feature = "shielded")]
+    pub fn nonce_queue_bounds() -> (u64, u64) {
+        (Self::nonce_queue_head(), Self::nonce_queue_tail())
+    }
+
+    #[cfg(feature = "shielded")]
+    fn record_nonce(nonce: Nonce) {
+        Self::prune_nonces(NONCE_PRUNING_PER_USE);
+
+        let last_epoch = Self::last_epoch();
+        let tail = Self::nonce_queue_tail();
+        <NonceQueue<T>>::insert(tail, (last_epoch, nonce));
+        <NonceQueueTail<T>>::put(tail + 1);
+        <NonceEpoch<T>>::insert(nonce, last_epoch);
+    }
+
+    // Remove up to `limit` nonces used in epochs out of the retention window, the oldest first.
+    #[cfg(feature = "shielded")]
+    fn prune_nonces(limit: u64) {
+        let retained_from = Self::last_epoch().saturating_sub(Self::nonce_retention_epochs());
+        let tail = Self::nonce_queue_tail();
+        let mut head = Self::nonce_queue_head();
+        let mut pruned = 0;
+        let mut pruned_epochs = None;
+
+        while head < tail && pruned < limit {
+            if let Some((epoch, nonce)) = Self::nonce_queue(head) {
+                // The entries are in the order of use, so the rest are retained too.
+                if epoch >= retained_from {
+                    break;
+                }
+                // The nonce may have been used again in a later epoch.
+                if Self::nonce_epoch(nonce) == Some(epoch) {
+                    <NonceEpoch<T>>::remove(nonce);
+                }
+                pruned_epochs = Some((pruned_epochs.map_or(epoch, |(first, _)| first), epoch));
+            }
+            <NonceQueue<T>>::remove(head);
+            head += 1;
+            pruned += 1;
+        }
+
+        <NonceQueueHead<T>>::put(head);
+
+        if let Some((first, last)) = pruned_epochs {
+            Self::deposit_event(RawEvent::NoncesPruned(first, last, pruned));
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use support::{impl_outer_origin, assert_ok};
+    use primitives::{H256, Blake2Hasher};
+    use runtime_primitives::{
+        BuildStorage, traits::{BlakeTwo256, IdentityLookup, OnInitialize},
+        testing::{Digest, DigestItem, Header}
+    };
+    use pairing::{Field, CurveAffine, CurveProjective, bls12_381::{G1Affine, G2Affine}};
+    use bellman_verifier::VerifyingKey;
+    use std::{
+        path::Path,
+        fs::File,
+        io::{BufReader, Read},
+    };
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = u64;
+        type SigVerificationKey = u64;
+        type Lookup = IdentityLookup<u64>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl Trait for Test {
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    type ZkSystem = Module<Test>;
+    type System = system::Module<Test>;
+
+    fn get_vk(path: &str) -> PreparedVerifyingKey<Bls12> {
+        let vk_file = File::open(&Path::new(path)).unwrap();
+        let mut vk_reader = BufReader::new(vk_file);
+
+        let mut buf_vk = vec![];
+        vk_reader.read_to_end(&mut buf_vk).unwrap();
+
+        PreparedVerifyingKey::<Bls12>::read(&mut &buf_vk[..]).unwrap()
+    }
+
+    fn new_test_ext(epoch_length: u64) -> runtime_io::TestExternalities<Blake2Hasher> {
+        let (mut t, mut c) = system::GenesisConfig::<Test>::default().build_storage().unwrap();
+        let _ = GenesisConfig::<Test>{
+            last_epoch: 0,
+            epoch_length,
+            max_anonymity_set_size: 12,
+            nonce_retention_epochs: 0,
+            confidential_vk: get_vk("../../zface/params/test_conf_vk.dat"),
+            anonymous_vk: get_vk("../../zface/params/test_anony_vk.dat"),
+            confidential_pk_hash: [0u8; 32],
+            anonymous_pk_hash: [0u8; 32],
+        }.assimilate_storage(&mut t, &mut c);
+
+        t.into()
+    }
+
+    #[test]
+    fn test_update_vk() {
+        with_externalities(&mut new_test_ext(3), || {
+            let mut conf_vk = vec![];
+            File::open("../../zface/params/test_conf_vk.dat").unwrap().read_to_end(&mut conf_vk).unwrap();
+            let mut anony_vk = vec![];
+            File::open("../../zface/params/test_anony_vk.dat").unwrap().read_to_end(&mut anony_vk).unwrap();
+
+            assert!(ZkSystem::update_confidential_vk(Origin::signed(1), conf_vk.clone(), [1u8; 32]).is_err());
+            assert_eq!(
+                ZkSystem::update_confidential_vk(system::RawOrigin::Root.into(), anony_vk.clone(), [1u8; 32]),
+                Err("Mismatch the number of public inputs of verifying key.")
+            );
+            assert_eq!(
+                ZkSystem::update_anonymous_vk(system::RawOrigin::Root.into(), anony_vk[..100].to_vec(), [1u8; 32]),
+                Err("Faild to read verifying key.")
+            );
+            assert_eq!(ZkSystem::confidential_pk_hash(), [0u8; 32]);
+
+            // The key of the circuit with all the inputs isn't a key of the hashed inputs circuit.
+            assert_eq!(
+                ZkSystem::update_hashed_anonymous_vk(system::RawOrigin::Root.into(), anony_vk.clone(), [2u8; 32]),
+                Err("Mismatch the number of public inputs of verifying key.")
+            );
+            assert!(!ZkSystem::anonymous_inputs_hashed());
+
+            assert_eq!(ZkSystem::update_anonymous_vk(system::RawOrigin::Root.into(), anony_vk, [2u8; 32]), Ok(()));
+            assert!(ZkSystem::anonymous_vk() == get_vk("../../zface/params/test_anony_vk.dat"));
+            assert_eq!(ZkSystem::anonymous_pk_hash(), [2u8; 32]);
+            assert!(!ZkSystem::anonymous_inputs_hashed());
+        })
+    }
+
+    #[test]
+    fn test_update_vk_unprepared() {
+        with_externalities(&mut new_test_ext(3), || {
+            let g1 = |i: u64| G1Affine::one().mul(i).into_affine();
+            let g2 = |i: u64| G2Affine::one().mul(i).into_affine();
+            let vk = VerifyingKey::<Bls12> {
+                alpha_g1: g1(2),
+                beta_g1: g1(3),
+                beta_g2: g2(3),
+                gamma_g2: g2(5),
+                delta_g1: g1(7),
+                delta_g2: g2(7),
+                ic: (0..CONFIDENTIAL_INPUT_SIZE as u64 + 1).map(|i| g1(i + 11)).collect(),
+            };
+            let mut buf = vec![];
+            vk.write(&mut buf).unwrap();
+
+            assert_eq!(
+                ZkSystem::update_anonymous_vk(system::RawOrigin::Root.into(), buf.clone(), [1u8; 32]),
+                Err("Mismatch the number of public inputs of verifying key.")
+            );
+            assert_ok!(ZkSystem::update_confidential_vk(system::RawOrigin::Root.into(), buf, [1u8; 32]));
+            assert!(ZkSystem::confidential_vk() == vk.prepare());
+        })
+    }
+
+    #[test]
+    fn test_prepared_vk_is_decoded_again_on_rotation() {
+        with_externalities(&mut new_test_ext(3), || {
+            let storage_key = runtime_io::twox_128(<AnonymousVk<Test> as support::storage::generator::StorageValue<_>>::key());
+            let encoded = || support::storage::unhashed::get_raw(&storage_key[..]).unwrap();
+
+            assert!(ZkSystem::prepared_vk::<AnonymousVk<Test>>() == ZkSystem::anonymous_vk());
+            assert!(vk_cache::is_cached(&storage_key[..], &encoded()[..]));
+
+            // The rotated key has another encoding, whose decoding replaces the cached key.
+            let mut conf_vk = vec![];
+            File::open("../../zface/params/test_conf_vk.dat").unwrap().read_to_end(&mut conf_vk).unwrap();
+            <AnonymousVk<Test>>::put(ZkSystem::read_vk(&conf_vk[..], CONFIDENTIAL_INPUT_SIZE).unwrap());
+            assert!(!vk_cache::is_cached(&storage_key[..], &encoded()[..]));
+
+            assert!(ZkSystem::prepared_vk::<AnonymousVk<Test>>() == get_vk("../../zface/params/test_conf_vk.dat"));
+            assert!(vk_cache::is_cached(&storage_key[..], &encoded()[..]));
+
+        })
+    }
+
+    #[test]
+    fn test_epoch_without_length_change() {
+        with_externalities(&mut new_test_ext(3), || {
+            System::set_block_number(10);
+            assert_eq!(ZkSystem::get_current_epoch(), 3);
+            assert_eq!(ZkSystem::epoch_at(11), 3);
+            assert_eq!(ZkSystem::epoch_at(12), 4);
+        })
+    }
+
+    #[test]
+    fn test_dry_run_does_not_mutate_storage() {
+        with_externalities(&mut new_test_ext(3), || {
+            System::set_block_number(10);
+
+            let transition = ZkSystem::epoch_transition(5).unwrap();
+            assert_eq!(transition, EpochTransition {
+                current_epoch: 3,
+                next_epoch: 4,
+                starts_at: 12,
+                old_length: 3,
+                new_length: 5,
+            });
+
+            assert_eq!(ZkSystem::epoch_length(), 3);
+            assert_eq!(ZkSystem::epoch_offset(), 0);
+            assert_eq!(ZkSystem::epoch_length_changed_at(), 0);
+            assert_eq!(ZkSystem::get_current_epoch(), 3);
+
+            assert!(ZkSystem::epoch_transition(0).is_err());
+        })
+    }
+
+    #[test]
+    fn test_dry_run_matches_applied_transition() {
+        with_externalities(&mut new_test_ext(7), || {
+            // Epoch 5 started at block 28 after a migration at block 20.
+            System::set_block_number(20);
+            ZkSystem::migrate_epoch_length(4).unwrap();
+            System::set_block_number(30);
+            assert_eq!(ZkSystem::get_current_epoch(), 5);
+
+            let dry_run = ZkSystem::epoch_transition(2).unwrap();
+            assert_ok!(ZkSystem::set_epoch_length(system::RawOrigin::Root.into(), 2));
+            assert_eq!(ZkSystem::pending_epoch_transition(), Some(dry_run.clone()));
+            assert_eq!(dry_run.starts_at, 32);
+
+            // The epoch the dry-run closes lasts until the boundary...
+            for height in 31..dry_run.starts_at {
+                System::set_block_number(height);
+                ZkSystem::on_initialize(height);
+                assert_eq!(ZkSystem::get_current_epoch(), dry_run.current_epoch);
+                assert_eq!(ZkSystem::epoch_length(), dry_run.old_length);
+            }
+
+            // ...where the scheduled transition is applied as it was dry-run.
+            System::set_block_number(dry_run.starts_at);
+            ZkSystem::on_initialize(dry_run.starts_at);
+
+            assert_eq!(ZkSystem::pending_epoch_transition(), None);
+            assert_eq!(ZkSystem::epoch_offset(), dry_run.next_epoch);
+            assert_eq!(ZkSystem::epoch_length_changed_at(), dry_run.starts_at);
+            assert_eq!(ZkSystem::epoch_length(), dry_run.new_length);
+            assert_eq!(ZkSystem::get_current_epoch(), dry_run.next_epoch);
+            assert_eq!(ZkSystem::last_epoch(), dry_run.next_epoch);
+            assert_eq!(ZkSystem::epoch_at(dry_run.starts_at + 2), dry_run.next_epoch + 1);
+        })
+    }
+
+    #[test]
+    fn test_migrate_to_longer_epoch_length() {
+        with_externalities(&mut new_test_ext(3), || {
+            System::set_block_number(10);
+            let transition = ZkSystem::migrate_epoch_length(5).unwrap();
+            assert_eq!(transition.next_epoch, 4);
+
+            // A new epoch starts right at the transition...
+            assert_eq!(ZkSystem::get_current_epoch(), 4);
+            assert_eq!(ZkSystem::last_epoch(), 4);
+            // ...and lasts for the new epoch length.
+            assert_eq!(ZkSystem::epoch_at(14), 4);
+            assert_eq!(ZkSystem::epoch_at(15), 5);
+            assert_eq!(ZkSystem::epoch_at(20), 6);
+        })
+    }
+
+    #[test]
+    fn test_migrate_to_shorter_epoch_length() {
+        with_externalities(&mut new_test_ext(7), || {
+            System::set_block_number(30);
+            assert_eq!(ZkSystem::get_current_epoch(), 4);
+
+            // Recomputing from the genesis would map block 30 to epoch 15
+            // and make LastRollOver values up to 15 look like the current epoch.
+            ZkSystem::migrate_epoch_length(2).unwrap();
+
+            assert_eq!(ZkSystem::get_current_epoch(), 5);
+            assert_eq!(ZkSystem::epoch_at(31), 5);
+            assert_eq!(ZkSystem::epoch_at(32), 6);
+        })
+    }
+
+    #[test]
+    fn test_set_epoch_length_at_next_boundary() {
+        with_externalities(&mut new_test_ext(3), || {
+            System::set_block_number(10);
+            assert!(ZkSystem::set_epoch_length(system::RawOrigin::Root.into(), 0).is_err());
+            assert_ok!(ZkSystem::set_epoch_length(system::RawOrigin::Root.into(), 5));
+
+            // The current epoch keeps the old length until its end.
+            ZkSystem::on_initialize(10);
+            ZkSystem::on_initialize(11);
+            assert_eq!(ZkSystem::epoch_length(), 3);
+            assert_eq!(ZkSystem::pending_epoch_transition().map(|t| t.new_length), Some(5));
+
+            // Block 12 starts epoch 4, the first one under the new length.
+            System::set_block_number(12);
+            ZkSystem::on_initialize(12);
+            assert_eq!(ZkSystem::epoch_length(), 5);
+            assert_eq!(ZkSystem::pending_epoch_transition(), None);
+            assert_eq!(ZkSystem::get_current_epoch(), 4);
+            assert_eq!(ZkSystem::epoch_at(16), 4);
+            assert_eq!(ZkSystem::epoch_at(17), 5);
+        })
+    }
+
+    #[test]
+    fn test_verify_groth16_without_shielded_inputs() {
+        with_externalities(&mut new_test_ext(3), || {
+            let proof = bellman_verifier::Proof::<Bls12> {
+                a: G1Affine::one(),
+                b: G2Affine::one(),
+                c: G1Affine::one(),
+            };
+            let vk = ZkSystem::confidential_vk();
+
+            assert_eq!(
+                ZkSystem::verify_groth16(&vk, &proof, &[Fr::zero(); 3]),
+                Err(VerificationError::InvalidVerifyingKey("Mismatch the public input and the verifying key."))
+            );
+            assert_eq!(ZkSystem::verify_groth16(&vk, &proof, &[Fr::zero(); 22]), Err(VerificationError::InvalidProof));
+            // Modules report an invalid proof with the same message as before the typed errors.
+            assert_eq!(<&'static str>::from(VerificationError::InvalidProof), "Invalid zkproof");
+        })
+    }
+
+    #[test]
+    fn test_verify_groth16_batch_rejects_invalid_proofs() {
+        with_externalities(&mut new_test_ext(3), || {
+            let proof = bellman_verifier::Proof::<Bls12> {
+                a: G1Affine::one(),
+                b: G2Affine::one(),
+                c: G1Affine::one(),
+            };
+            let vk = ZkSystem::confidential_vk();
+
+            assert_eq!(ZkSystem::verify_groth16_batch(&vk, &[]), Ok(()));
+            assert_eq!(
+                ZkSystem::verify_groth16_batch(&vk, &[(proof.clone(), vec![Fr::zero(); 22])]),
+                Err(VerificationError::InvalidProof)
+            );
+            assert_eq!(ZkSystem::verify_groth16_batch(&vk, &[
+                (proof.clone(), vec![Fr::zero(); 22]),
+                (proof.clone(), vec![Fr::one(); 22]),
+            ]), Err(VerificationError::InvalidProof));
+            assert!(ZkSystem::verify_groth16_batch(&vk, &[
+                (proof.clone(), vec![Fr::zero(); 22]),
+                (proof, vec![Fr::zero(); 3]),
+            ]).is_err());
+        })
+    }
+
+    #[cfg(feature = "shielded")]
+    #[test]
+    fn test_nonces_are_spent_within_an_epoch() {
+        with_externalities(&mut new_test_ext(3), || {
+            let nonce = Nonce::repeat_byte(1);
+            ZkSystem::init_nonce_pool(0);
+
+            assert!(!ZkSystem::is_nonce_used(&nonce));
+            assert_eq!(ZkSystem::insert_nonce(nonce), Ok(()));
+            assert!(ZkSystem::is_nonce_used(&nonce));
+            assert_eq!(ZkSystem::insert_nonce(nonce), Err(VerificationError::NonceUsed));
+
+            // A new epoch makes the nonce unspent.
+            System::set_block_number(3);
+            ZkSystem::init_nonce_pool(ZkSystem::get_current_epoch());
+            assert!(!ZkSystem::is_nonce_used(&nonce));
+            assert_eq!(ZkSystem::insert_nonce(nonce), Ok(()));
+        })
+    }
+
+    #[cfg(feature = "shielded")]
+    #[test]
+    fn test_reserved_nonces() {
+        with_externalities(&mut new_test_ext(3), || {
+            let (nonce_a, nonce_b) = (Nonce::repeat_byte(1), Nonce::repeat_byte(2));
+            ZkSystem::init_nonce_pool(0);
+
+            // A reserved nonce can't be reserved or inserted by another call...
+            let ticket_a = ZkSystem::reserve_nonce(nonce_a).unwrap();
+            assert_eq!(ZkSystem::reserve_nonce(nonce_a), Err(VerificationError::NonceUsed));
+            assert_eq!(ZkSystem::insert_nonce(nonce_a), Err(VerificationError::NonceUsed));
+            assert!(!ZkSystem::is_nonce_used(&nonce_a));
+
+            // ...and is spent once committed.
+            ZkSystem::commit_nonce(ticket_a);
+            assert!(ZkSystem::is_nonce_used(&nonce_a));
+            assert!(!ZkSystem::is_nonce_reserved(&nonce_a));
+            assert_eq!(ZkSystem::reserve_nonce(nonce_a), Err(VerificationError::NonceUsed));
+
+            // An aborted nonce can be used again.
+            let ticket_b = ZkSystem::reserve_nonce(nonce_b).unwrap();
+            ZkSystem::abort_nonce(ticket_b);
+            assert!(!ZkSystem::is_nonce_used(&nonce_b));
+            let ticket_b = ZkSystem::reserve_nonce(nonce_b).unwrap();
+
+            // A dropped ticket is released at the end of the block.
+            drop(ticket_b);
+            assert!(ZkSystem::is_nonce_reserved(&nonce_b));
+            <ZkSystem as runtime_primitives::traits::OnFinalize<u64>>::on_finalize(0);
+            assert!(!ZkSystem::is_nonce_reserved(&nonce_b));
+            assert_eq!(ZkSystem::insert_nonce(nonce_b), Ok(()));
+        })
+    }
+
+    #[cfg(feature = "shielded")]
+    #[test]
+    fn test_rejected_proofs_are_counted() {
+        with_externalities(&mut new_test_ext(3), || {
+            System::set_block_number(4);
+            let verify = || ZkSystem::verify_confidential_proof(
+                &Proof::default(),
+                &EncKey::default(),
+                &EncKey::default(),
+                &LeftCiphertext::default(),
+                &LeftCiphertext::default(),
+                &Ciphertext::default(),
+                &0,
+                &LeftCiphertext::default(),
+                &RightCiphertext::default(),
+                &Nonce::default()
+            );
+
+            let code = verify().unwrap_err().code();
+            assert_eq!(verify().map_err(|e| e.code()), Err(code));
+            assert_eq!(ZkSystem::verify_confidential_proofs_batch(&[]), Ok(()));
+
+            assert_eq!(ZkSystem::invalid_proof_count(), 2);
+            assert_eq!(ZkSystem::invalid_proofs_in_epoch((1, code)), 2);
+            assert_eq!(ZkSystem::invalid_proofs_in_epoch((0, code)), 0);
+        })
+    }
+
+    #[cfg(feature = "shielded")]
+    #[test]
+    fn test_enc_key_xy_is_cached_within_a_block() {
+        with_externalities(&mut new_test_ext(3), || {
+            // Any point of the prime order subgroup is a valid encryption key.
+            let enc_key = EncKey::from_slice(ZkSystem::g_epoch().as_bytes());
+            enc_key_cache::clear();
+            assert!(!enc_key_cache::is_cached(&enc_key));
+
+            let xy = ZkSystem::enc_key_xy(&enc_key).unwrap();
+            assert_eq!(xy, enc_key.into_xy().unwrap());
+            assert!(enc_key_cache::is_cached(&enc_key));
+            assert_eq!(ZkSystem::enc_key_xy(&enc_key).unwrap(), xy);
+
+            // A non-canonical coordinate is rejected and not cached.
+            assert!(ZkSystem::enc_key_xy(&EncKey::from_slice(&[0xff; 32])).is_err());
+            assert_eq!(enc_key_cache::len(), 1);
+
+            // The cache is cleared at the end of the block.
+            <ZkSystem as runtime_primitives::traits::OnFinalize<u64>>::on_finalize(1);
+            assert!(!enc_key_cache::is_cached(&enc_key));
+        })
+    }
+
+    #[cfg(feature = "shielded")]
+    #[test]
+    fn test_g_epoch_version_changes_at_next_epoch() {
+        with_externalities(&mut new_test_ext(3), || {
+            ZkSystem::init_nonce_pool(0);
+            assert_eq!(ZkSystem::g_epoch(), GEpoch::group_hash(0).unwrap());
+
+            assert!(ZkSystem::set_g_epoch_version(Origin::signed(1), 1).is_err());
+            assert!(ZkSystem::set_g_epoch_version(system::RawOrigin::Root.into(), 2).is_err());
+            assert_ok!(ZkSystem::set_g_epoch_version(system::RawOrigin::Root.into(), 1));
+
+            // The g_epoch of the current epoch doesn't change...
+            ZkSystem::init_nonce_pool(0);
+            assert_eq!(ZkSystem::g_epoch(), GEpoch::group_hash(0).unwrap());
+            assert_eq!(ZkSystem::pending_g_epoch_version(), Some(1));
+
+            // ...and the next one is derived with the new version.
+            System::set_block_number(3);
+            ZkSystem::init_nonce_pool(ZkSystem::get_current_epoch());
+            assert_eq!(ZkSystem::g_epoch(), GEpoch::group_hash_with_version(1, GEpochVersion::V2).unwrap());
+            assert_eq!(ZkSystem::g_epoch_version(), 1);
+            assert_eq!(ZkSystem::pending_g_epoch_version(), None);
+        })
+    }
+
+    #[cfg(feature = "shielded")]
+    #[test]
+    fn test_epoch_starts_on_initialize() {
+        with_externalities(&mut new_test_ext(3), || {
+            let nonce = Nonce::repeat_byte(1);
+            ZkSystem::init_nonce_pool(0);
+            assert_eq!(ZkSystem::insert_nonce(nonce), Ok(()));
+
+            System::set_block_number(2);
+            ZkSystem::on_initialize(2);
+            assert_eq!(ZkSystem::last_epoch(), 0);
+            assert!(ZkSystem::is_nonce_used(&nonce));
+
+            // Block 3 starts epoch 1 without any call.
+            System::set_block_number(3);
+            ZkSystem::on_initialize(3);
+            assert_eq!(ZkSystem::last_epoch(), 1);
+            assert_eq!(ZkSystem::g_epoch(), GEpoch::group_hash(1).unwrap());
+            assert!(!ZkSystem::is_nonce_used(&nonce));
+        })
+    }
+
+    #[cfg(feature = "shielded")]
+    #[test]
+    fn test_nonces_of_past_epochs_are_pruned() {
+        with_externalities(&mut new_test_ext(3), || {
+            ZkSystem::init_nonce_pool(0);
+            let count = NONCE_PRUNING_LIMIT + 10;
+            for i in 0..count {
+                let mut nonce = Nonce::zero();
+                nonce.as_mut()[..8].copy_from_slice(&i.to_le_bytes());
+                assert_eq!(ZkSystem::insert_nonce(nonce), Ok(()));
+            }
+            assert_eq!(ZkSystem::nonce_queue_tail() - ZkSystem::nonce_queue_head(), count);
+
+            // The cleanup at the epoch change is bounded...
+            System::set_block_number(3);
+            ZkSystem::init_nonce_pool(ZkSystem::get_current_epoch());
+            assert_eq!(ZkSystem::nonce_queue_tail() - ZkSystem::nonce_queue_head(), 10);
+            assert_eq!(ZkSystem::nonce_epoch(Nonce::zero()), None);
+
+            // ...and the rest is pruned as new nonces are used.
+            for i in 0..5 {
+                assert_eq!(ZkSystem::insert_nonce(Nonce::repeat_byte(0xff - i)), Ok(()));
+            }
+            assert_eq!(ZkSystem::nonce_queue_tail() - ZkSystem::nonce_queue_head(), 5);
+        })
+    }
+
+    #[cfg(feature = "shielded")]
+    #[test]
+    fn test_nonces_are_retained_for_the_retention_window() {
+        with_externalities(&mut new_test_ext(3), || {
+            assert!(ZkSystem::set_nonce_retention_epochs(Origin::signed(1), 1).is_err());
+            assert_ok!(ZkSystem::set_nonce_retention_epochs(system::RawOrigin::Root.into(), 1));
+
+            let nonce = Nonce::repeat_byte(1);
+            ZkSystem::init_nonce_pool(0);
+            assert_eq!(ZkSystem::insert_nonce(nonce), Ok(()));
+
+            // The nonce of epoch 0 is kept in epoch 1, though it can be used again...
+            System::set_block_number(3);
+            ZkSystem::init_nonce_pool(ZkSystem::get_current_epoch());
+            assert_eq!(ZkSystem::nonce_epoch(nonce), Some(0));
+            assert!(!ZkSystem::is_nonce_used(&nonce));
+            assert_eq!(ZkSystem::nonce_queue_tail() - ZkSystem::nonce_queue_head(), 1);
+
+            // ...and pruned when epoch 2 starts.
+            System::set_block_number(6);
+            ZkSystem::init_nonce_pool(ZkSystem::get_current_epoch());
+            assert_eq!(ZkSystem::nonce_epoch(nonce), None);
+            assert_eq!(ZkSystem::nonce_queue_tail() - ZkSystem::nonce_queue_head(), 0);
+        })
+    }
+
+    #[cfg(feature = "shielded")]
+    #[test]
+    fn test_epochs_are_monotonic_across_migrations() {
+        with_externalities(&mut new_test_ext(4), || {
+            let mut last = ZkSystem::get_current_epoch();
+            let mut g_epochs = vec![ZkSystem::g_epoch()];
+
+            for height in 1..60 {
+                System::set_block_number(height);
+                match height {
+                    13 => { ZkSystem::migrate_epoch_length(1).unwrap(); },
+                    21 => { ZkSystem::migrate_epoch_length(9).unwrap(); },
+                    40 => { ZkSystem::migrate_epoch_length(3).unwrap(); },
+                    _ => {},
+                }
+
+                let current = ZkSystem::get_current_epoch();
+                // An epoch index never goes back, so comparing it with `LastRollOver`
+                // rolls each account over at most once per epoch and never misses one.
+                assert!(current >= last);
+
+                if current != last {
+                    ZkSystem::init_nonce_pool(current);
+                    assert!(!g_epochs.contains(&ZkSystem::g_epoch()));
+                    g_epochs.push(ZkSystem::g_epoch());
+                }
+                last = current;
+            }
+        })
+    }
+    struct TestBalance;
+
+    impl pool::PoolMap for TestBalance {
+        type Key = u64;
+        type Value = u32;
+        const POOL: Pool = Pool::EncryptedBalances;
+        const PREFIX: &'static [u8] = b"TestBalance";
+    }
+
+    #[test]
+    fn test_pool_roots_are_logged() {
+        use self::pool::PoolMap;
+
+        let (t, mut c) = system::GenesisConfig::<Test>::default().build_storage().unwrap();
+        TestBalance::build_genesis(&mut c, &[(1, 10)]);
+
+        with_externalities(&mut pool::test_externalities(t, c), || {
+            assert_eq!(TestBalance::get(1), Some(10));
+            assert!(runtime_io::storage(&TestBalance::key_for(1)).is_none());
+
+            TestBalance::mutate(2, |v| *v = Some(20));
+            assert!(TestBalance::exists(2));
+            assert_eq!(TestBalance::take(1), Some(10));
+            assert!(!TestBalance::exists(1));
+
+            ZkSystem::deposit_pool_roots();
+            let logs: Vec<RawLog<H256>> = System::digest().logs.iter()
+                .filter_map(|item| match item {
+                    DigestItem::Other(log) => RawLog::decode(&mut &log[..]),
+                    _ => None,
+                })
+                .collect();
+
+            // The other pools are empty, so only the root of the pool is logged.
+            assert_eq!(logs.len(), 1);
+            let RawLog::PoolRoot(pool, root) = logs[0].clone();
+            assert_eq!(pool, Pool::EncryptedBalances);
+            assert_eq!(Pool::EncryptedBalances.root(), Some(root.as_ref().to_vec()));
+        })
     }
 }
@@ -0,0 +1,134 @@
+//! The child tries of the shielded pools.
+//!
+//! The encrypted balances and pending transfers of encrypted-balances, encrypted-assets and
+//! anonymous-balances are stored in a child trie per pool instead of the main trie.
+//! The root of each pool is logged in the header digest by `zk_system` at the end of every block,
+//! so a light client can check a balance against the root of its pool with a proof
+//! which only covers the pool, and the heavy shielded state doesn't deepen the main trie.
+//!
+//! The items are declared as types implementing `PoolMap`, which mirrors `StorageMap`,
+//! and their keys are the item's prefix followed by the encoded key, without hashing,
+//! so that wallets can look them up with the child storage RPC.
+
+use rstd::{borrow::Borrow, prelude::*};
+use parity_codec::{Encode, Decode};
+use primitives::storage::well_known_keys::CHILD_STORAGE_KEY_PREFIX;
+#[cfg(feature = "std")]
+use serde_derive::Serialize;
+#[cfg(feature = "std")]
+use primitives::Blake2Hasher;
+#[cfg(feature = "std")]
+use runtime_primitives::{StorageOverlay, ChildrenStorageOverlay};
+
+/// A shielded pool which has a child trie of its own.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Debug))]
+pub enum Pool {
+    EncryptedBalances,
+    EncryptedAssets,
+    AnonymousBalances,
+}
+
+impl Pool {
+    /// The pools whose roots are logged in the header digest.
+    pub const ALL: [Pool; 3] = [Pool::EncryptedBalances, Pool::EncryptedAssets, Pool::AnonymousBalances];
+
+    /// The storage key of the child trie of the pool.
+    pub fn child_storage_key(&self) -> Vec<u8> {
+        let name: &[u8] = match *self {
+            Pool::EncryptedBalances => b"zerochain:EncryptedBalances",
+            Pool::EncryptedAssets => b"zerochain:EncryptedAssets",
+            Pool::AnonymousBalances => b"zerochain:AnonymousBalances",
+        };
+
+        let mut key = CHILD_STORAGE_KEY_PREFIX.to_vec();
+        key.extend_from_slice(name);
+        key
+    }
+
+    /// The root of the child trie of the pool, or `None` if nothing is stored in it.
+    pub fn root(&self) -> Option<Vec<u8>> {
+        runtime_io::child_storage_root(&self.child_storage_key())
+    }
+}
+
+/// A map stored in the child trie of a pool.
+pub trait PoolMap {
+    type Key: Encode;
+    type Value: Encode + Decode;
+
+    /// The pool whose child trie the map is stored in.
+    const POOL: Pool;
+
+    /// The prefix of the keys of the map, which tells it from the other maps of the pool.
+    const PREFIX: &'static [u8];
+
+    /// The key of an entry in the child trie.
+    fn key_for<K: Borrow<Self::Key>>(key: K) -> Vec<u8> {
+        let mut res = Self::PREFIX.to_vec();
+        key.borrow().encode_to(&mut res);
+        res
+    }
+
+    fn get<K: Borrow<Self::Key>>(key: K) -> Option<Self::Value> {
+        runtime_io::child_storage(&Self::POOL.child_storage_key(), &Self::key_for(key))
+            .and_then(|v| Decode::decode(&mut &v[..]))
+    }
+
+    fn exists<K: Borrow<Self::Key>>(key: K) -> bool {
+        runtime_io::exists_child_storage(&Self::POOL.child_storage_key(), &Self::key_for(key))
+    }
+
+    fn insert<K: Borrow<Self::Key>, V: Borrow<Self::Value>>(key: K, value: V) {
+        runtime_io::set_child_storage(&Self::POOL.child_storage_key(), &Self::key_for(key), &value.borrow().encode())
+    }
+
+    fn remove<K: Borrow<Self::Key>>(key: K) {
+        runtime_io::clear_child_storage(&Self::POOL.child_storage_key(), &Self::key_for(key))
+    }
+
+    fn take<K: Borrow<Self::Key>>(key: K) -> Option<Self::Value> {
+        let value = Self::get(key.borrow());
+        if value.is_some() {
+            Self::remove(key);
+        }
+        value
+    }
+
+    fn mutate<K: Borrow<Self::Key>, R, F: FnOnce(&mut Option<Self::Value>) -> R>(key: K, f: F) -> R {
+        let mut value = Self::get(key.borrow());
+        let res = f(&mut value);
+        match value {
+            Some(ref v) => Self::insert(key, v),
+            None => Self::remove(key),
+        }
+        res
+    }
+
+    /// Write the genesis entries of the map into the child trie of its pool.
+    #[cfg(feature = "std")]
+    fn build_genesis(children: &mut ChildrenStorageOverlay, entries: &[(Self::Key, Self::Value)]) {
+        let child = children.entry(Self::POOL.child_storage_key()).or_insert_with(Default::default);
+        for (key, value) in entries {
+            child.insert(Self::key_for(key), value.encode());
+        }
+    }
+}
+
+/// The test externalities of a genesis storage, with the child tries of the pools.
+/// `TestExternalities` is only built from the main trie, so the child tries are written into it.
+#[cfg(feature = "std")]
+pub fn test_externalities(
+    storage: StorageOverlay,
+    children: ChildrenStorageOverlay,
+) -> runtime_io::TestExternalities<Blake2Hasher> {
+    let mut ext: runtime_io::TestExternalities<Blake2Hasher> = storage.into();
+    runtime_io::with_externalities(&mut ext, || {
+        for (storage_key, child) in children {
+            for (key, value) in child {
+                runtime_io::set_child_storage(&storage_key, &key, &value);
+            }
+        }
+    });
+    ext
+}
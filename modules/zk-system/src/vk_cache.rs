@@ -0,0 +1,45 @@
+//! A native-side cache of the decoded verifying keys.
+//!
+//! Decoding a prepared verifying key reads and checks every point of it, which costs a good part of
+//! the verification of a proof. In native execution (block import and RPC verification),
+//! the decoded key of each storage item is kept together with the blake2_256 of its encoded value,
+//! so a call only reads and hashes the bytes while the key is unchanged.
+//! A key rotated by `update_*_vk`, or the key of another fork, has another hash and is decoded again.
+//! The Wasm runtime has no state across calls and decodes the key from storage as before.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use bellman_verifier::PreparedVerifyingKey;
+use pairing::bls12_381::Bls12;
+use parity_codec::Decode;
+
+thread_local! {
+    /// The hash of the encoded value and the decoded key, by the storage key of the item.
+    static PREPARED_VKS: RefCell<HashMap<Vec<u8>, ([u8; 32], PreparedVerifyingKey<Bls12>)>> =
+        RefCell::new(HashMap::new());
+}
+
+/// The key decoded from `encoded`, the value stored at `storage_key`.
+/// A malformed value decodes into `Uninitialized`, as the storage getter does.
+pub fn get_or_decode(storage_key: &[u8], encoded: &[u8]) -> PreparedVerifyingKey<Bls12> {
+    let hash = runtime_io::blake2_256(encoded);
+
+    PREPARED_VKS.with(|vks| {
+        let mut vks = vks.borrow_mut();
+        match vks.get(storage_key) {
+            Some((cached_hash, vk)) if *cached_hash == hash => vk.clone(),
+            _ => {
+                let vk = PreparedVerifyingKey::<Bls12>::decode(&mut &encoded[..]).unwrap_or_default();
+                vks.insert(storage_key.to_vec(), (hash, vk.clone()));
+                vk
+            }
+        }
+    })
+}
+
+/// Whether the key decoded from `encoded` is cached for `storage_key`.
+#[cfg(test)]
+pub fn is_cached(storage_key: &[u8], encoded: &[u8]) -> bool {
+    let hash = runtime_io::blake2_256(encoded);
+    PREPARED_VKS.with(|vks| vks.borrow().get(storage_key).map_or(false, |(h, _)| *h == hash))
+}
@@ -0,0 +1,37 @@
+//! The weights of dispatchables, i.e. upper bounds of their execution time in microseconds
+//! on the reference machine, dominated by the verification of proofs and signatures.
+//! The executive rejects extrinsics once the weights of a block exceed `MAX_BLOCK_WEIGHT`,
+//! so that blocks can't be stuffed with expensive proofs.
+//!
+//! Keep these above the costs measured by `benchmarking` whenever the circuits or the verifier change.
+
+pub type Weight = u32;
+
+/// The weight of a call without any verification, e.g. bookkeeping and root calls.
+pub const BASE: Weight = 1_000;
+
+/// Verifying a redjubjub signature or a few scalar multiplications on the jubjub curve.
+pub const SIGNATURE: Weight = 5_000;
+
+/// Verifying the proof of an audit ciphertext.
+pub const AUDIT: Weight = 15_000;
+
+/// Verifying the zk proof of a confidential transfer.
+pub const CONFIDENTIAL_PROOF: Weight = 60_000;
+
+/// Verifying the zk proof of an anonymous transfer.
+pub const ANONYMOUS_PROOF: Weight = 100_000;
+
+/// The sum of the weights of the extrinsics of a block.
+pub const MAX_BLOCK_WEIGHT: Weight = 2_000_000;
+
+/// The weight of a confidential transfer with `audits` audit ciphertexts.
+pub fn audited(proof: Weight, audits: usize) -> Weight {
+    proof.saturating_add(AUDIT.saturating_mul(audits as Weight))
+}
+
+/// The weight of a batch of `transfers` confidential transfers, `signatures` of which are signed apart from the batch.
+pub fn batched(proof: Weight, transfers: usize, signatures: usize) -> Weight {
+    proof.saturating_mul(transfers as Weight)
+        .saturating_add(SIGNATURE.saturating_mul(signatures as Weight))
+}
@@ -0,0 +1,278 @@
+//! A watchdog of the state of the zk subsystem for dev and test networks.
+//! At the end of each block, it checks a few invariants of `zk-system` which are cheap to recompute,
+//! and reports each one violated by an `InvariantViolated` event, so that operators learn of
+//! a corrupted state before it shows up as failing transfers.
+//!
+//! With `HaltOnViolation`, the first violation also halts the calls of the zk modules,
+//! which the runtime rejects from the transaction pool until root resumes them.
+//! The invariants are only checked if the module is built with the `testnet` feature.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use support::{decl_module, decl_storage, decl_event, StorageMap, StorageValue, dispatch::Result};
+use system::ensure_root;
+
+pub trait Trait: system::Trait + zk_system::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+/// An invariant of the zk subsystem checked by the watchdog.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Invariant {
+    /// The queue of used nonces only grows: its tail never decreases and its head never passes it.
+    NonceQueueMonotonic,
+    /// `LastEpoch` is not ahead of the epoch of the current block.
+    LastEpochNotAhead,
+    /// The verifying keys of confidential and anonymous transfers are set.
+    VerifyingKeysPresent,
+}
+
+impl Invariant {
+    /// A code of the invariant which is stable across runtime upgrades, so operators can match on it.
+    pub fn code(&self) -> u8 {
+        match *self {
+            Invariant::NonceQueueMonotonic => 1,
+            Invariant::LastEpochNotAhead => 2,
+            Invariant::VerifyingKeysPresent => 3,
+        }
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
+        fn on_finalize(_n: T::BlockNumber) {
+            if cfg!(feature = "testnet") {
+                Self::check_invariants();
+            }
+        }
+
+        /// Set whether a violated invariant halts the calls of the zk modules. Only callable by root.
+        pub fn set_halt_on_violation(origin, halt: bool) -> Result {
+            ensure_root(origin)?;
+
+            <HaltOnViolation<T>>::put(halt);
+
+            Ok(())
+        }
+
+        /// Resume the calls of the zk modules halted by a violated invariant. Only callable by root.
+        pub fn resume(origin) -> Result {
+            ensure_root(origin)?;
+
+            if Self::halted() {
+                <Halted<T>>::kill();
+                Self::deposit_event(RawEvent::CallsResumed());
+            }
+
+            Ok(())
+        }
+    }
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as ZkWatchdog {
+        /// Whether a violated invariant halts the calls of the zk modules.
+        pub HaltOnViolation get(halt_on_violation) config() : bool;
+
+        /// Whether the calls of the zk modules are halted, until root resumes them.
+        pub Halted get(halted) : bool;
+
+        /// The tail of the nonce queue at the end of the last block.
+        LastNonceQueueTail get(last_nonce_queue_tail) : u64;
+
+        /// The number of blocks in which each invariant was violated, by the code of the `Invariant`.
+        pub ViolationCount get(violation_count) : map u8 => u64;
+    }
+}
+
+decl_event! (
+    pub enum Event<T> where <T as system::Trait>::BlockNumber {
+        /// The invariant of the given code was violated at the end of the block.
+        InvariantViolated(BlockNumber, u8),
+        /// The calls of the zk modules were halted at the block.
+        CallsHalted(BlockNumber),
+        /// The halted calls of the zk modules were resumed.
+        CallsResumed(),
+    }
+);
+
+impl<T: Trait> Module<T> {
+    /// Check the invariants against the current state, reporting and counting each violated one.
+    /// Returns the violated invariants.
+    pub fn check_invariants() -> rstd::vec::Vec<Invariant> {
+        let violated = Self::violated_invariants();
+        let block_number = <system::Module<T>>::block_number();
+
+        for invariant in &violated {
+            <ViolationCount<T>>::mutate(invariant.code(), |count| *count += 1);
+            Self::deposit_event(RawEvent::InvariantViolated(block_number, invariant.code()));
+        }
+
+        if !violated.is_empty() && Self::halt_on_violation() && !Self::halted() {
+            <Halted<T>>::put(true);
+            Self::deposit_event(RawEvent::CallsHalted(block_number));
+        }
+
+        violated
+    }
+
+    fn violated_invariants() -> rstd::vec::Vec<Invariant> {
+        let mut violated = rstd::vec::Vec::new();
+
+        let (head, tail) = <zk_system::Module<T>>::nonce_queue_bounds();
+        if head > tail || tail < Self::last_nonce_queue_tail() {
+            violated.push(Invariant::NonceQueueMonotonic);
+        }
+        <LastNonceQueueTail<T>>::put(tail);
+
+        if <zk_system::Module<T>>::last_epoch() > <zk_system::Module<T>>::get_current_epoch() {
+            violated.push(Invariant::LastEpochNotAhead);
+        }
+
+        if !<zk_system::Module<T>>::has_vk::<zk_system::ConfidentialVk<T>>()
+            || !<zk_system::Module<T>>::has_vk::<zk_system::AnonymousVk<T>>()
+        {
+            violated.push(Invariant::VerifyingKeysPresent);
+        }
+
+        violated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_io::with_externalities;
+    use support::{impl_outer_origin, assert_ok};
+    use primitives::{H256, Blake2Hasher};
+    use runtime_primitives::{
+        BuildStorage, traits::{BlakeTwo256, IdentityLookup},
+        testing::{Digest, DigestItem, Header}
+    };
+    use bellman_verifier::{VerifyingKey, prepare_verifying_key};
+    use pairing::{CurveAffine, bls12_381::{Bls12, G1Affine, G2Affine}};
+    use zprimitives::Nonce;
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = u64;
+        type SigVerificationKey = u64;
+        type Lookup = IdentityLookup<u64>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl zk_system::Trait for Test {
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl Trait for Test {
+        type Event = ();
+    }
+
+    type ZkWatchdog = Module<Test>;
+    type ZkSystem = zk_system::Module<Test>;
+    type System = system::Module<Test>;
+
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let vk = prepare_verifying_key(&VerifyingKey::<Bls12> {
+            alpha_g1: G1Affine::one(),
+            beta_g1: G1Affine::one(),
+            beta_g2: G2Affine::one(),
+            gamma_g2: G2Affine::one(),
+            delta_g1: G1Affine::one(),
+            delta_g2: G2Affine::one(),
+            ic: vec![G1Affine::one(), G1Affine::one()],
+        });
+
+        let (mut t, mut c) = system::GenesisConfig::<Test>::default().build_storage().unwrap();
+        let _ = zk_system::GenesisConfig::<Test>{
+            last_epoch: 0,
+            epoch_length: 10,
+            max_anonymity_set_size: 12,
+            nonce_retention_epochs: 0,
+            confidential_vk: vk.clone(),
+            anonymous_vk: vk,
+            confidential_pk_hash: [0u8; 32],
+            anonymous_pk_hash: [0u8; 32],
+        }.assimilate_storage(&mut t, &mut c);
+        let _ = GenesisConfig::<Test>{
+            halt_on_violation: false,
+        }.assimilate_storage(&mut t, &mut c);
+
+        t.into()
+    }
+
+    #[test]
+    fn test_no_violation() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(3);
+            assert_eq!(ZkWatchdog::check_invariants(), vec![]);
+
+            assert_ok!(ZkSystem::insert_nonce(Nonce::from_slice(&[1u8; 32])));
+            assert_ok!(ZkSystem::insert_nonce(Nonce::from_slice(&[2u8; 32])));
+            assert_eq!(ZkWatchdog::check_invariants(), vec![]);
+            assert_eq!(ZkWatchdog::last_nonce_queue_tail(), 2);
+        })
+    }
+
+    #[test]
+    fn test_violations_are_counted() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(3);
+            <zk_system::LastEpoch<Test>>::put(1);
+            <zk_system::AnonymousVk<Test>>::kill();
+
+            assert_eq!(
+                ZkWatchdog::check_invariants(),
+                vec![Invariant::LastEpochNotAhead, Invariant::VerifyingKeysPresent]
+            );
+            assert_eq!(ZkWatchdog::check_invariants().len(), 2);
+            assert_eq!(ZkWatchdog::violation_count(Invariant::LastEpochNotAhead.code()), 2);
+            assert_eq!(ZkWatchdog::violation_count(Invariant::VerifyingKeysPresent.code()), 2);
+            assert_eq!(ZkWatchdog::violation_count(Invariant::NonceQueueMonotonic.code()), 0);
+            assert!(!ZkWatchdog::halted());
+
+            // The epoch of block 10 is 1, which `LastEpoch` is no longer ahead of.
+            System::set_block_number(10);
+            assert_eq!(ZkWatchdog::check_invariants(), vec![Invariant::VerifyingKeysPresent]);
+        })
+    }
+
+    #[test]
+    fn test_halt_on_violation() {
+        with_externalities(&mut new_test_ext(), || {
+            assert!(ZkWatchdog::set_halt_on_violation(Origin::signed(1), true).is_err());
+            assert_ok!(ZkWatchdog::set_halt_on_violation(system::RawOrigin::Root.into(), true));
+
+            System::set_block_number(3);
+            assert_eq!(ZkWatchdog::check_invariants(), vec![]);
+            assert!(!ZkWatchdog::halted());
+
+            <zk_system::ConfidentialVk<Test>>::put(bellman_verifier::PreparedVerifyingKey::Uninitialized);
+            assert_eq!(ZkWatchdog::check_invariants(), vec![Invariant::VerifyingKeysPresent]);
+            assert!(ZkWatchdog::halted());
+
+            assert!(ZkWatchdog::resume(Origin::signed(1)).is_err());
+            assert_ok!(ZkWatchdog::resume(system::RawOrigin::Root.into()));
+            assert!(!ZkWatchdog::halted());
+        })
+    }
+}
@@ -0,0 +1,44 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use blake2_rfc::blake2b::blake2b;
+
+// Embed short fingerprints of the verifying keys in `zface/params` the chain is configured with,
+// so that nodes, zface and explorers can detect mismatched keys from the runtime version.
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("set by cargo; qed");
+    let params_dir = Path::new(&manifest_dir).join("../zface/params");
+
+    let conf = fingerprint(&params_dir.join("conf_vk.dat"));
+    let anony = fingerprint(&params_dir.join("anony_vk.dat"));
+
+    let out_dir = env::var("OUT_DIR").expect("set by cargo; qed");
+    let mut file = File::create(Path::new(&out_dir).join("vk_fingerprints.rs"))
+        .expect("should create a file in OUT_DIR.");
+    write!(file, "\
+/// The first 8 bytes of blake2b-256 of `zface/params/conf_vk.dat` this runtime was built with.
+pub const CONFIDENTIAL_VK_FINGERPRINT: [u8; 8] = {:?};
+/// The first 8 bytes of blake2b-256 of `zface/params/anony_vk.dat` this runtime was built with.
+pub const ANONYMOUS_VK_FINGERPRINT: [u8; 8] = {:?};
+", conf, anony).expect("should write the fingerprints.");
+
+    println!("cargo:rustc-env=ZEROCHAIN_VK_FINGERPRINTS={}-{}", hex(&conf), hex(&anony));
+    println!("cargo:rerun-if-changed={}", params_dir.join("conf_vk.dat").display());
+    println!("cargo:rerun-if-changed={}", params_dir.join("anony_vk.dat").display());
+}
+
+// A missing key is fingerprinted as zeros rather than failing the build,
+// since the keys are only needed to build the genesis of a chain.
+fn fingerprint(path: &Path) -> [u8; 8] {
+    let mut res = [0u8; 8];
+    match fs::read(path) {
+        Ok(vk) => res.copy_from_slice(&blake2b(32, &[], &vk[..]).as_bytes()[..8]),
+        Err(_) => println!("cargo:warning={} is not found; its fingerprint is zeros.", path.display()),
+    }
+    res
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -18,7 +18,7 @@ use runtime_primitives::{
 };
 use client::{
 	block_builder::api::{CheckInherentsResult, InherentData, self as block_builder_api},
-	runtime_api, impl_runtime_apis
+	runtime_api, impl_runtime_apis, decl_runtime_apis
 };
 use version::RuntimeVersion;
 #[cfg(feature = "std")] 
@@ -26,7 +26,13 @@ use version::NativeVersion;
 use zprimitives::{
 	RedjubjubSignature,
 	SigVerificationKey,
+	EncKey,
+	Proof,
+	Nonce as ZkNonce,
+	LeftCiphertext,
+	RightCiphertext,
 };
+use zk_system::{EpochTransition, weights};
 
 // A few exports that help ease life for downstream crates.
 #[cfg(any(feature = "std", test))]
@@ -37,9 +43,14 @@ pub use balances::Call as BalancesCall;
 pub use encrypted_balances::Call as EncryptedBalancesCall;
 pub use encrypted_assets::Call as EncryptedAssetsCall;
 pub use anonymous_balances::Call as AnonymousBalancesCall;
+pub use zk_faucet::Call as ZkFaucetCall;
+pub use encrypted_standing_orders::Call as EncryptedStandingOrdersCall;
+pub use encrypted_standing_orders::Payment as StandingOrderPayment;
+pub use zk_shield::Call as ZkShieldCall;
 pub use runtime_primitives::{Permill, Perbill};
 pub use timestamp::BlockPeriod;
 pub use support::{StorageValue, construct_runtime};
+pub use zk_system::weights;
 
 /// The type that is used for identifying authorities.
 pub type AuthorityId = <AuthoritySignature as Verify>::Signer;
@@ -94,10 +105,17 @@ pub mod opaque {
 	pub type SessionKey = AuthorityId;
 }
 
+include!(concat!(env!("OUT_DIR"), "/vk_fingerprints.rs"));
+
+/// The fingerprints of the confidential and the anonymous verifying keys in hex, joined by `-`.
+pub const VK_FINGERPRINTS: &str = env!("ZEROCHAIN_VK_FINGERPRINTS");
+
 /// This runtime version.
+/// The implementation name carries the fingerprints of the verifying keys,
+/// so a mismatch shows up in `state_getRuntimeVersion` without touching the storage.
 pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("zerochain"),
-	impl_name: create_runtime_str!("zerochain"),
+	impl_name: create_runtime_str!(concat!("zerochain-vk-", env!("ZEROCHAIN_VK_FINGERPRINTS"))),
 	authoring_version: 3,
 	spec_version: 3,
 	impl_version: 0,
@@ -160,8 +178,8 @@ impl indices::Trait for Runtime {
 	type AccountIndex = u32;
 	/// Use the standard means of resolving an index hint from an id.
 	type ResolveHint = indices::SimpleResolveHint<Self::AccountId, Self::AccountIndex>;
-	/// Determine whether an account is dead.
-	type IsDeadAccount = EncryptedBalances;
+	/// Determine whether an account is dead. Account ids are rvks, whose only state is their transparent balance.
+	type IsDeadAccount = Balances;
 	/// The uniquitous event type.
 	type Event = Event;
 }
@@ -195,6 +213,7 @@ impl sudo::Trait for Runtime {
 
 impl encrypted_balances::Trait for Runtime {
 	type Event = Event;
+	type ConfidentialFee = encrypted_balances::FlatFee<Runtime>;
 }
 
 impl encrypted_assets::Trait for Runtime {
@@ -204,9 +223,48 @@ impl encrypted_assets::Trait for Runtime {
 
 impl anonymous_balances::Trait for Runtime {
 	type Event = Event;
+	type Currency = Balances;
+}
+
+impl zk_system::Trait for Runtime {
+	type Event = Event;
+	type Log = Log;
+}
+
+impl zk_stats::Trait for Runtime { }
+
+impl zk_auditor::Trait for Runtime {
+	type Event = Event;
+}
+
+impl state_attestation::Trait for Runtime {
+	type Event = Event;
+}
+
+impl zk_recovery::Trait for Runtime {
+	type Event = Event;
+}
+
+impl encrypted_airdrop::Trait for Runtime {
+	type Event = Event;
+}
+
+impl zk_faucet::Trait for Runtime {
+	type Event = Event;
+}
+
+impl encrypted_standing_orders::Trait for Runtime {
+	type Event = Event;
+}
+
+impl zk_shield::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
 }
 
-impl zk_system::Trait for Runtime { }
+impl zk_watchdog::Trait for Runtime {
+	type Event = Event;
+}
 
 construct_runtime!(
 	pub enum Runtime with Log(InternalLog: DigestItem<Hash, AuthorityId, AuthoritySignature>) where
@@ -217,7 +275,16 @@ construct_runtime!(
 		EncryptedBalances: encrypted_balances::{Module, Call, Storage, Event<T>, Config<T>},
 		EncryptedAssets: encrypted_assets::{Module, Call, Storage, Event<T>, Config<T>},
 		AnonymousBalances: anonymous_balances::{Module, Call, Storage, Event<T>, Config<T>},
-		ZkSystem: zk_system::{Module, Call, Storage, Config<T>},
+		ZkSystem: zk_system::{Module, Call, Storage, Event<T>, Config<T>, Log()},
+		ZkStats: zk_stats::{Module, Call, Storage},
+		ZkAuditor: zk_auditor::{Module, Call, Storage, Event<T>},
+		StateAttestation: state_attestation::{Module, Call, Storage, Event<T>, Config<T>},
+		ZkRecovery: zk_recovery::{Module, Call, Storage, Event<T>},
+		EncryptedAirdrop: encrypted_airdrop::{Module, Call, Storage, Event<T>},
+		ZkFaucet: zk_faucet::{Module, Call, Storage, Event<T>, Config<T>},
+		EncryptedStandingOrders: encrypted_standing_orders::{Module, Call, Storage, Event<T>},
+		ZkShield: zk_shield::{Module, Call, Storage, Event<T>},
+		ZkWatchdog: zk_watchdog::{Module, Call, Storage, Event<T>, Config<T>},
 		System: system::{default, Log(ChangesTrieRoot)},
 		Timestamp: timestamp::{Module, Call, Storage, Config<T>, Inherent},
 		Consensus: consensus::{Module, Call, Storage, Config<T>, Log(AuthoritiesChange), Inherent},
@@ -228,6 +295,94 @@ construct_runtime!(
 	}
 );
 
+/// The weights of the calls are dominated by the proofs and signatures they verify.
+impl executive::Weighable for Call {
+	const MAX_BLOCK_WEIGHT: u32 = weights::MAX_BLOCK_WEIGHT;
+
+	fn weight(&self) -> u32 {
+		match self {
+			Call::EncryptedBalances(call) => match call {
+				encrypted_balances::Call::confidential_transfer(..) |
+				encrypted_balances::Call::confidential_transfer_with_return_address(..) |
+				encrypted_balances::Call::reveal_and_burn(..) => weights::CONFIDENTIAL_PROOF,
+				encrypted_balances::Call::confidential_transfer_audited(_, _, _, _, _, _, _, _, _, audits) =>
+					weights::audited(weights::CONFIDENTIAL_PROOF, audits.len()),
+				encrypted_balances::Call::batch_confidential_transfer(transfers) => weights::batched(
+					weights::CONFIDENTIAL_PROOF,
+					transfers.len(),
+					transfers.iter().filter(|t| t.signature.is_some()).count()
+				),
+				_ => weights::BASE,
+			},
+			Call::EncryptedAssets(call) => match call {
+				encrypted_assets::Call::issue(..) |
+				encrypted_assets::Call::issue_with_hidden_issuer(..) |
+				encrypted_assets::Call::mint(..) |
+				encrypted_assets::Call::confidential_transfer(..) |
+				encrypted_assets::Call::burn(..) |
+				encrypted_assets::Call::destroy(..) => weights::CONFIDENTIAL_PROOF,
+				encrypted_assets::Call::set_metadata(..) => weights::SIGNATURE,
+				_ => weights::BASE,
+			},
+			Call::AnonymousBalances(call) => match call {
+				anonymous_balances::Call::anonymous_transfer(..) |
+				anonymous_balances::Call::anonymous_asset_transfer(..) => weights::ANONYMOUS_PROOF,
+				anonymous_balances::Call::anonymous_transfer_audited(_, _, _, _, _, audits) =>
+					weights::audited(weights::ANONYMOUS_PROOF, audits.len()),
+				anonymous_balances::Call::issue(..) => weights::CONFIDENTIAL_PROOF,
+				_ => weights::BASE,
+			},
+			Call::ZkAuditor(_) => weights::SIGNATURE,
+			Call::StateAttestation(state_attestation::Call::attest(..)) => weights::SIGNATURE,
+			Call::ZkRecovery(call) => match call {
+				zk_recovery::Call::register_guardians(..) |
+				zk_recovery::Call::cancel_recovery(..) => weights::SIGNATURE,
+				zk_recovery::Call::approve_recovery(..) |
+				zk_recovery::Call::complete_recovery(..) => weights::AUDIT,
+				_ => weights::BASE,
+			},
+			Call::EncryptedAirdrop(call) => match call {
+				encrypted_airdrop::Call::create(_, _, _, _, _, _, claims, _, _) =>
+					weights::batched(weights::CONFIDENTIAL_PROOF, 1 + claims.len(), 0),
+				encrypted_airdrop::Call::claim(..) => weights::CONFIDENTIAL_PROOF,
+				_ => weights::BASE,
+			},
+			Call::ZkFaucet(zk_faucet::Call::claim(..)) => weights::SIGNATURE,
+			Call::EncryptedStandingOrders(call) => match call {
+				encrypted_standing_orders::Call::schedule(_, _, _, _, _, _, _, _, _, payments, _) =>
+					weights::batched(weights::CONFIDENTIAL_PROOF, 1 + payments.len(), 0),
+				encrypted_standing_orders::Call::cancel(..) => weights::CONFIDENTIAL_PROOF,
+				_ => weights::BASE,
+			},
+			Call::ZkShield(call) => match call {
+				zk_shield::Call::shield(..) => weights::SIGNATURE,
+				zk_shield::Call::unshield(..) => weights::CONFIDENTIAL_PROOF,
+				_ => weights::BASE,
+			},
+			_ => weights::BASE,
+		}
+	}
+}
+
+/// The validity code of transactions rejected while the watchdog halts the calls of the zk modules.
+const ZK_CALLS_HALTED: i8 = -30;
+
+/// The calls halted by the watchdog on a violated invariant of the zk subsystem.
+/// The calls of `ZkSystem` are not, so that root can still replace the verifying keys.
+fn is_halted_by_watchdog(call: &Call) -> bool {
+	match call {
+		Call::EncryptedBalances(_) |
+		Call::EncryptedAssets(_) |
+		Call::AnonymousBalances(_) |
+		Call::ZkRecovery(_) |
+		Call::EncryptedAirdrop(_) |
+		Call::ZkFaucet(_) |
+		Call::EncryptedStandingOrders(_) |
+		Call::ZkShield(_) => ZkWatchdog::halted(),
+		_ => false,
+	}
+}
+
 /// The type used as a helper for interpreting the sender of transactions.
 type Context = system::ChainContext<Runtime>;
 /// The address format for describing accounts.
@@ -245,6 +400,47 @@ pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Nonce, Call>;
 /// Executive: handles dispatch to the various modules.
 pub type Executive = executive::Executive<Runtime, Block, Context, AllModules>;
 
+decl_runtime_apis! {
+	/// The API to query the zk-system module.
+	pub trait ZkSystemApi {
+		/// Dry-run `set_epoch_length` at the current block: the transition applied at the next epoch boundary,
+		/// including the block it starts at. Returns `None` if the new epoch length is invalid.
+		fn dry_run_epoch_length_change(new_length: NumberFor<Block>) -> Option<EpochTransition<NumberFor<Block>>>;
+		/// Dry-run the verification of a confidential transfer signed by `rvk` at the current block,
+		/// e.g. through the `state_call` RPC before submitting it.
+		/// Returns the code of the `VerificationError` if the transfer would be rejected.
+		fn check_confidential_proof(
+			rvk: AccountId,
+			zkproof: Proof,
+			address_sender: EncKey,
+			address_recipient: EncKey,
+			amount_sender: LeftCiphertext,
+			amount_recipient: LeftCiphertext,
+			fee_sender: LeftCiphertext,
+			randomness: RightCiphertext,
+			nonce: ZkNonce
+		) -> Result<(), u8>;
+		/// Dry-run the verification of an anonymous transfer signed by `rvk` at the current block.
+		/// Returns the code of the `VerificationError` if the transfer would be rejected.
+		fn check_anonymous_proof(
+			rvk: AccountId,
+			zkproof: Proof,
+			enc_keys: Vec<EncKey>,
+			left_ciphertexts: Vec<LeftCiphertext>,
+			right_ciphertext: RightCiphertext,
+			nonce: ZkNonce
+		) -> Result<(), u8>;
+	}
+
+	/// The API to query the anonymous-balances module.
+	pub trait AnonymousBalancesApi {
+		/// Accounts which rolled over within the last `k_epochs` epochs, to be sampled as decoys.
+		fn active_enc_keys(k_epochs: NumberFor<Block>) -> Vec<EncKey>;
+		/// The number of accounts of an anonymous transfer: the sender, the recipient and the decoys.
+		fn ring_size() -> u32;
+	}
+}
+
 // Implement our runtime API endpoints. This is just a bunch of proxying.
 impl_runtime_apis! {
 	impl runtime_api::Core<Block> for Runtime {
@@ -295,6 +491,9 @@ impl_runtime_apis! {
 
 	impl runtime_api::TaggedTransactionQueue<Block> for Runtime {
 		fn validate_transaction(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity {
+			if is_halted_by_watchdog(&tx.function) {
+				return TransactionValidity::Invalid(ZK_CALLS_HALTED);
+			}
 			Executive::validate_transaction(tx)
 		}
 	}
@@ -316,4 +515,63 @@ impl_runtime_apis! {
 			Consensus::authorities()
 		}
 	}
+
+	impl self::ZkSystemApi<Block> for Runtime {
+		fn dry_run_epoch_length_change(new_length: NumberFor<Block>) -> Option<EpochTransition<NumberFor<Block>>> {
+			ZkSystem::epoch_transition(new_length).ok()
+		}
+
+		fn check_confidential_proof(
+			rvk: AccountId,
+			zkproof: Proof,
+			address_sender: EncKey,
+			address_recipient: EncKey,
+			amount_sender: LeftCiphertext,
+			amount_recipient: LeftCiphertext,
+			fee_sender: LeftCiphertext,
+			randomness: RightCiphertext,
+			nonce: ZkNonce
+		) -> Result<(), u8> {
+			EncryptedBalances::check_confidential_proof(
+				&rvk,
+				&zkproof,
+				&address_sender,
+				&address_recipient,
+				&amount_sender,
+				&amount_recipient,
+				&fee_sender,
+				&randomness,
+				&nonce
+			).map_err(|e| e.code())
+		}
+
+		fn check_anonymous_proof(
+			rvk: AccountId,
+			zkproof: Proof,
+			enc_keys: Vec<EncKey>,
+			left_ciphertexts: Vec<LeftCiphertext>,
+			right_ciphertext: RightCiphertext,
+			nonce: ZkNonce
+		) -> Result<(), u8> {
+			AnonymousBalances::check_anonymous_proof(
+				&rvk,
+				&zkproof,
+				&enc_keys[..],
+				&left_ciphertexts[..],
+				&right_ciphertext,
+				&nonce,
+				None
+			).map_err(|e| e.code())
+		}
+	}
+
+	impl self::AnonymousBalancesApi<Block> for Runtime {
+		fn active_enc_keys(k_epochs: NumberFor<Block>) -> Vec<EncKey> {
+			AnonymousBalances::active_enc_keys(k_epochs)
+		}
+
+		fn ring_size() -> u32 {
+			anonymous_balances::RING_SIZE as u32
+		}
+	}
 }
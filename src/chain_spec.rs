@@ -1,17 +1,21 @@
-use primitives::{ed25519, sr25519, Pair, crypto::Ss58Codec};
+use primitives::{ed25519, sr25519, Pair, crypto::Ss58Codec, blake2_256, hexdisplay::HexDisplay};
 use zerochain_runtime::{
 	AccountId, GenesisConfig, ConsensusConfig, TimestampConfig, BalancesConfig, SudoConfig,
 	IndicesConfig, EncryptedBalancesConfig, EncryptedAssetsConfig, ZkSystemConfig,
-	AnonymousBalancesConfig,
+	AnonymousBalancesConfig, StateAttestationConfig, ZkFaucetConfig, ZkWatchdogConfig, VK_FINGERPRINTS,
+	weights,
 };
 use ed25519::Public as AuthorityId;
-use zprimitives::{EncKey, Ciphertext, SigVerificationKey};
+use zprimitives::{EncKey, Ciphertext, SigVerificationKey, AddressFormat};
 use bellman_verifier::PreparedVerifyingKey;
 use keys::EncryptionKey;
 use zjubjub::{curve::{FixedGenerators, fs}};
 use zpairing::{bls12_381::Bls12, Field};
 use zcrypto::elgamal;
 use zprimitives::PARAMS;
+use proofs::constants::ANONIMITY_SIZE;
+use substrate_service::Properties;
+use serde_json::json;
 use rand::{OsRng, Rng};
 use std::{
 	path::Path,
@@ -23,6 +27,27 @@ use std::{
 // Note this is the URL for the telemetry server
 //const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 
+const CONF_VK_PATH: &str = "./zface/params/conf_vk.dat";
+const ANONY_VK_PATH: &str = "./zface/params/anony_vk.dat";
+const CONF_PK_PATH: &str = "./zface/params/conf_pk.dat";
+const ANONY_PK_PATH: &str = "./zface/params/anony_pk.dat";
+const EPOCH_LENGTH: u64 = 7;
+const NONCE_RETENTION_EPOCHS: u64 = 100;
+const TRANSACTION_BASE_FEE: u64 = 1;
+const ANONYMOUS_FEE: u64 = 1;
+const ENC_KEY_DEPOSIT: u128 = 100;
+const ATTESTATION_PERIOD: u64 = 100;
+const FAUCET_AMOUNT: u64 = 1_000;
+const FAUCET_CLAIM_INTERVAL: u64 = 10;
+const TOKEN_SYMBOL: &str = "ZERO";
+const TOKEN_DECIMALS: u8 = 2;
+// The accounts whose encrypted balances are funded in the genesis, which `zface dev up` prints the keys of.
+const DEV_SEEDS: [&[u8; 32]; 3] = [
+	b"Alice                           ",
+	b"Bob                             ",
+	b"Charlie                         ",
+];
+
 /// Specialized `ChainSpec`. This is a specialization of the general Substrate ChainSpec type.
 pub type ChainSpec = substrate_service::ChainSpec<GenesisConfig>;
 
@@ -67,7 +92,7 @@ impl Alternative {
 				None,
 				None,
 				None,
-				None
+				Some(chain_properties())
 			),
 			Alternative::LocalTestnet => ChainSpec::from_genesis(
 				"Local Testnet",
@@ -90,7 +115,7 @@ impl Alternative {
 				None,
 				None,
 				None,
-				None
+				Some(chain_properties())
 			),
 		})
 	}
@@ -132,9 +157,10 @@ fn testnet_genesis(initial_authorities: Vec<AuthorityId>, endowed_accounts: Vec<
 			key: root_key,
 		}),
 		encrypted_balances: Some(EncryptedBalancesConfig {
-			encrypted_balance: vec![balance_init.clone()],
-			last_rollover: vec![epoch_init],
-			transaction_base_fee: 1,
+			encrypted_balance: dev_balances(),
+			last_rollover: dev_epoch_init(),
+			transaction_base_fee: TRANSACTION_BASE_FEE,
+			anonymous_fee: ANONYMOUS_FEE,
 			_genesis_phantom_data: Default::default(),
 		}),
 		encrypted_assets: Some(EncryptedAssetsConfig {
@@ -146,43 +172,98 @@ fn testnet_genesis(initial_authorities: Vec<AuthorityId>, endowed_accounts: Vec<
 			encrypted_balance: init_anonymous_balances(),
 			last_rollover: vec![epoch_init],
 			enc_key_set: init_anonymous_enc_keys(),
+			enc_key_deposit: ENC_KEY_DEPOSIT,
 			_genesis_phantom_data: Default::default(),
 		}),
 		zk_system: Some(ZkSystemConfig {
 			last_epoch: 0,
-			epoch_length: 7,
-			nonce_pool: vec![],
-			confidential_vk: get_conf_vk(),
-			anonymous_vk: get_anony_vk()
-		})
+			epoch_length: EPOCH_LENGTH,
+			max_anonymity_set_size: ANONIMITY_SIZE as u32,
+			nonce_retention_epochs: NONCE_RETENTION_EPOCHS,
+			confidential_vk: get_vk(CONF_VK_PATH),
+			anonymous_vk: get_vk(ANONY_VK_PATH),
+			confidential_pk_hash: pk_hash(CONF_PK_PATH),
+			anonymous_pk_hash: pk_hash(ANONY_PK_PATH),
+		}),
+		state_attestation: Some(StateAttestationConfig {
+			attesters: vec![],
+			attestation_period: ATTESTATION_PERIOD,
+		}),
+		zk_faucet: Some(ZkFaucetConfig {
+			faucet_amount: FAUCET_AMOUNT,
+			claim_interval: FAUCET_CLAIM_INTERVAL,
+		}),
+		zk_watchdog: Some(ZkWatchdogConfig {
+			halt_on_violation: false,
+		}),
 	}
 }
 
-fn get_conf_vk() -> PreparedVerifyingKey<Bls12> {
-	let vk_path = Path::new("./zface/params/conf_vk.dat");
-	let vk_file = File::open(&vk_path).unwrap();
-	let mut vk_reader = BufReader::new(vk_file);
+/// zk parameters embedded in the chain spec, so that operators exporting it with
+/// `build-spec` and wallets (`zface debug chain-info`) can check they agree.
+/// `ss58Format` is the address prefix of the network, which wallets select with `zface --network`.
+/// `tokenSymbol` and `tokenDecimals` are the denomination wallets show and parse amounts of the native coin in.
+/// `weights` are the weights of dispatchables which the runtime charges against `maxBlockWeight`.
+fn chain_properties() -> Properties {
+	let properties = json!({
+		"ss58Format": AddressFormat::Testnet.prefix(),
+		"tokenSymbol": TOKEN_SYMBOL,
+		"tokenDecimals": TOKEN_DECIMALS,
+		"epochLength": EPOCH_LENGTH,
+		"maxAnonymitySetSize": ANONIMITY_SIZE,
+		"transactionBaseFee": TRANSACTION_BASE_FEE,
+		"anonymousFee": ANONYMOUS_FEE,
+		"confidentialVkHash": vk_hash(CONF_VK_PATH),
+		"anonymousVkHash": vk_hash(ANONY_VK_PATH),
+		"vkFingerprints": VK_FINGERPRINTS,
+		"confidentialPkHash": format!("0x{}", HexDisplay::from(&pk_hash(CONF_PK_PATH))),
+		"anonymousPkHash": format!("0x{}", HexDisplay::from(&pk_hash(ANONY_PK_PATH))),
+		"weights": {
+			"base": weights::BASE,
+			"signature": weights::SIGNATURE,
+			"audit": weights::AUDIT,
+			"confidentialProof": weights::CONFIDENTIAL_PROOF,
+			"anonymousProof": weights::ANONYMOUS_PROOF,
+			"maxBlockWeight": weights::MAX_BLOCK_WEIGHT,
+		},
+	});
 
-	let mut buf_vk = vec![];
-    vk_reader.read_to_end(&mut buf_vk).unwrap();
+	match properties {
+		serde_json::Value::Object(properties) => properties,
+		_ => unreachable!("a json object literal; qed"),
+	}
+}
+
+fn read_file(path: &str) -> Vec<u8> {
+	let file = File::open(Path::new(path)).unwrap();
+	let mut reader = BufReader::new(file);
 
-	PreparedVerifyingKey::<Bls12>::read(&mut &buf_vk[..]).unwrap()
+	let mut buf = vec![];
+	reader.read_to_end(&mut buf).unwrap();
+	buf
 }
 
-fn get_anony_vk() -> PreparedVerifyingKey<Bls12> {
-	let vk_path = Path::new("./zface/params/anony_vk.dat");
-	let vk_file = File::open(&vk_path).unwrap();
-	let mut vk_reader = BufReader::new(vk_file);
+// The params can hold the unprepared key, which is then prepared once here at genesis.
+fn get_vk(path: &str) -> PreparedVerifyingKey<Bls12> {
+	PreparedVerifyingKey::<Bls12>::from_bytes(&read_file(path)[..]).unwrap()
+}
 
+// Hash the key as it is serialized in storage, which is what `zface debug chain-info` sees.
+fn vk_hash(path: &str) -> String {
 	let mut buf_vk = vec![];
-    vk_reader.read_to_end(&mut buf_vk).unwrap();
+	get_vk(path).write(&mut &mut buf_vk).unwrap();
+
+	format!("0x{}", HexDisplay::from(&blake2_256(&buf_vk[..])))
+}
 
-	PreparedVerifyingKey::<Bls12>::read(&mut &buf_vk[..]).unwrap()
+// Hash the proving key file as it is, which is what zface checks before proving.
+fn pk_hash(path: &str) -> [u8; 32] {
+	blake2_256(&read_file(path)[..])
 }
 
 fn balance_init() -> (EncKey, Ciphertext) {
 	let enc_key = get_alice_enc_key();
-	let alice_value = 10_000 as u32;
+	let alice_value = 10_000 as u64;
 	let p_g = FixedGenerators::Diversifier; // 1 same as NoteCommitmentRandomness;
 
 	// The default balance is not encrypted with randomness.
@@ -191,6 +272,21 @@ fn balance_init() -> (EncKey, Ciphertext) {
 	(EncKey::try_from(enc_key).unwrap(), Ciphertext::try_from(enc_alice_bal).unwrap())
 }
 
+fn dev_balances() -> Vec<(EncKey, Ciphertext)> {
+	let value = 10_000 as u64;
+	let p_g = FixedGenerators::Diversifier; // 1 same as NoteCommitmentRandomness;
+
+	DEV_SEEDS.iter().map(|seed| {
+		let enc_key = get_enc_key(&seed[..]);
+		let enc_bal = elgamal::Ciphertext::encrypt(value, &fs::Fs::one(), &enc_key, p_g, &PARAMS);
+		(EncKey::try_from(enc_key).unwrap(), Ciphertext::try_from(enc_bal).unwrap())
+	}).collect()
+}
+
+fn dev_epoch_init() -> Vec<(EncKey, u64)> {
+	DEV_SEEDS.iter().map(|seed| (EncKey::try_from(get_enc_key(&seed[..])).unwrap(), 0)).collect()
+}
+
 fn alice_epoch_init() -> (EncKey, u64) {
 	let enc_key = get_alice_enc_key();
 
@@ -202,10 +298,12 @@ fn get_alice_enc_key() -> EncryptionKey<Bls12> {
 	// let ss58_address = "5DC4kJ84b4KfVyddcFMYfy5skTJWVtxtWRETZo2i4nh8Ao1i";
 	// let enc_key_bytes = EncryptionKeyBytes::from_ss58check(ss58_address).unwrap();
 	// let enc_key = EncryptionKey::read(&mut &enc_key_bytes.0[..], &*PARAMS).unwrap();
-	let alice_seed = b"Alice                           ".to_vec();
-	let enc_key = EncryptionKey::<Bls12>::from_seed(&&alice_seed, &*PARAMS)
-		.expect("should be generated encryption key from seed.");
-	enc_key
+	get_enc_key(&DEV_SEEDS[0][..])
+}
+
+fn get_enc_key(seed: &[u8]) -> EncryptionKey<Bls12> {
+	EncryptionKey::<Bls12>::from_seed(seed, &*PARAMS)
+		.expect("should be generated encryption key from seed.")
 }
 
 
@@ -231,7 +329,7 @@ fn init_anonymous_enc_keys() -> Vec<EncKey> {
 
 fn init_anonymous_balances() -> Vec<(EncKey, Ciphertext)> {
 	let enc_keys = init_typed_enc_keys();
-	let alice_value = 10_000 as u32;
+	let alice_value = 10_000 as u64;
 	let p_g = FixedGenerators::Diversifier; // 1 same as NoteCommitmentRandomness;
 
 	let mut acc = vec![];
@@ -7,7 +7,8 @@ use substrate_cli::{informant, parse_and_execute, NoCustom};
 use substrate_service::{ServiceFactory, Roles as ServiceRoles};
 use crate::chain_spec;
 use std::ops::Deref;
-use log::info;
+use log::{info, warn};
+use crate::verify_history;
 
 /// Parse command line arguments into service configuration.
 pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()> where
@@ -15,6 +16,8 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
 	T: Into<std::ffi::OsString> + Clone,
 	E: IntoExit,
 {
+	let (args, verify_range) = verify_history::take_range(args)?;
+
 	parse_and_execute::<service::Factory, NoCustom, NoCustom, _, _, _, _, _>(
 		load_spec, &version, "substrate-node", args, exit,
 	 	|exit, _custom_args, config| {
@@ -24,6 +27,9 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
 			info!("Chain specification: {}", config.chain_spec.name());
 			info!("Node name: {}", config.name);
 			info!("Roles: {:?}", config.roles);
+			if let Some((from, to)) = verify_range {
+				return verify_historical_proofs(config, from, to);
+			}
 			let runtime = Runtime::new().map_err(|e| format!("{:?}", e))?;
 			let executor = runtime.executor();
 			match config.roles {
@@ -42,6 +48,28 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
 	).map_err(Into::into).map(|_| ())
 }
 
+// Re-verify the proofs of the blocks in the range instead of running the node.
+fn verify_historical_proofs(
+	config: substrate_service::FactoryFullConfiguration<service::Factory>,
+	from: u64,
+	to: u64,
+) -> error::Result<()> {
+	info!("Verifying the proofs of blocks #{}..#{}", from, to);
+	let client = substrate_service::new_client::<service::Factory>(&config)
+		.map_err(|e| format!("{:?}", e))?;
+
+	let discrepancies = verify_history::verify_historical_proofs(&client, from, to)?;
+	for d in &discrepancies {
+		warn!("Block #{} ({}) is not reproduced: {}", d.number, d.shielded_calls.join(", "), d.reason);
+	}
+	if !discrepancies.is_empty() {
+		return Err(format!("{} blocks are not reproduced by the current verifier.", discrepancies.len()).into());
+	}
+
+	info!("All the proofs of blocks #{}..#{} are verified.", from, to);
+	Ok(())
+}
+
 fn load_spec(id: &str) -> Result<Option<chain_spec::ChainSpec>, String> {
 	Ok(match chain_spec::Alternative::from(id) {
 		Some(spec) => Some(spec.load()?),
@@ -6,6 +6,7 @@
 mod chain_spec;
 mod service;
 mod cli;
+mod verify_history;
 
 pub use substrate_cli::{VersionInfo, IntoExit, error};
 
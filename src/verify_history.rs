@@ -0,0 +1,127 @@
+//! Re-verification of the zk proofs of historical blocks, for audits of the chain history after verifier upgrades.
+//!
+//! Each block with shielded extrinsics in the range is re-executed by this node's runtime on the state of its parent,
+//! so its proofs are verified again against the verifying keys stored at that height.
+//! Run it with the native execution strategy for the verifier of this build to be used.
+//! A block whose re-execution doesn't end in its recorded state is reported with its shielded calls.
+
+use std::ffi::OsString;
+use parity_codec::{Encode, Decode};
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::{Header as HeaderT, ProvideRuntimeApi};
+use substrate_client::runtime_api::Core;
+use substrate_service::FullClient;
+use zerochain_runtime::{Call, UncheckedExtrinsic, opaque};
+use crate::service::Factory;
+
+/// The flag of the verification mode, which takes a range of block numbers as `from..to`.
+pub const VERIFY_HISTORICAL_PROOFS_FLAG: &str = "--verify-historical-proofs";
+
+/// A block whose re-execution didn't reproduce its recorded state.
+#[derive(Debug)]
+pub struct Discrepancy {
+    pub number: u64,
+    pub reason: String,
+    /// The modules of the shielded extrinsics of the block, in the order of the extrinsics.
+    pub shielded_calls: Vec<&'static str>,
+}
+
+/// Parse a range of block numbers `from..to`, both ends inclusive.
+pub fn parse_range(range: &str) -> Result<(u64, u64), String> {
+    let mut ends = range.splitn(2, "..");
+    let from = ends.next().and_then(|n| n.parse::<u64>().ok());
+    let to = ends.next().and_then(|n| n.parse::<u64>().ok());
+
+    match (from, to) {
+        (Some(from), Some(to)) if from <= to => Ok((from, to)),
+        _ => Err(format!("Invalid block range {}, expected from..to.", range)),
+    }
+}
+
+/// Take the flag and its range out of the command line arguments, leaving the rest to the substrate CLI.
+pub fn take_range<I, T>(args: I) -> Result<(Vec<OsString>, Option<(u64, u64)>), String>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let mut rest = vec![];
+    let mut range = None;
+    let mut args = args.into_iter().map(Into::into);
+
+    while let Some(arg) = args.next() {
+        if arg.as_os_str() != VERIFY_HISTORICAL_PROOFS_FLAG {
+            rest.push(arg);
+            continue;
+        }
+
+        let value = args.next()
+            .ok_or_else(|| format!("{} takes a block range from..to.", VERIFY_HISTORICAL_PROOFS_FLAG))?;
+        range = Some(parse_range(&value.to_string_lossy())?);
+    }
+
+    Ok((rest, range))
+}
+
+/// Re-execute the blocks with shielded extrinsics in the range and collect those which don't reproduce their state.
+pub fn verify_historical_proofs(
+    client: &FullClient<Factory>,
+    from: u64,
+    to: u64,
+) -> Result<Vec<Discrepancy>, String> {
+    let mut discrepancies = vec![];
+
+    // The genesis block has no extrinsics to verify.
+    for number in from.max(1)..=to {
+        let block = client.block(&BlockId::Number(number))
+            .map_err(|e| format!("{:?}", e))?
+            .ok_or_else(|| format!("Block #{} is not found; an archive node is needed.", number))?
+            .block;
+
+        let shielded_calls = shielded_calls(&block.extrinsics);
+        if shielded_calls.is_empty() {
+            continue;
+        }
+
+        let parent = BlockId::Hash(*block.header.parent_hash());
+        if let Err(e) = client.runtime_api().execute_block(&parent, block) {
+            discrepancies.push(Discrepancy {
+                number,
+                reason: format!("{:?}", e),
+                shielded_calls,
+            });
+        }
+    }
+
+    Ok(discrepancies)
+}
+
+fn shielded_calls(extrinsics: &[opaque::UncheckedExtrinsic]) -> Vec<&'static str> {
+    extrinsics.iter()
+        .filter_map(|ext| UncheckedExtrinsic::decode(&mut &ext.encode()[..]))
+        .filter_map(|ext| match ext.function {
+            Call::EncryptedBalances(_) => Some("EncryptedBalances"),
+            Call::EncryptedAssets(_) => Some("EncryptedAssets"),
+            Call::AnonymousBalances(_) => Some("AnonymousBalances"),
+            Call::ZkRecovery(_) => Some("ZkRecovery"),
+            Call::EncryptedAirdrop(_) => Some("EncryptedAirdrop"),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_range() {
+        let (rest, range) = take_range(vec!["zerochain", "--dev", "--verify-historical-proofs", "10..20"]).unwrap();
+        assert_eq!(rest, vec![OsString::from("zerochain"), OsString::from("--dev")]);
+        assert_eq!(range, Some((10, 20)));
+
+        assert_eq!(take_range(vec!["zerochain", "--dev"]).unwrap().1, None);
+        assert!(take_range(vec!["zerochain", "--verify-historical-proofs"]).is_err());
+        assert!(parse_range("20..10").is_err());
+        assert!(parse_range("10").is_err());
+    }
+}
@@ -0,0 +1,22 @@
+use std::env;
+use std::process::Command;
+
+// Embed the build metadata checked by `zface verify-install`.
+fn main() {
+    let commit = Command::new("git")
+        .args(&["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok());
+
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=ZFACE_GIT_COMMIT={}", commit.trim());
+    }
+
+    if let Ok(profile) = env::var("PROFILE") {
+        println!("cargo:rustc-env=ZFACE_BUILD_PROFILE={}", profile);
+    }
+
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+}
@@ -2,16 +2,17 @@ use dirs;
 use clap::{Arg, ArgMatches};
 use super::term;
 use std::path::PathBuf;
+use zprimitives::AddressFormat;
 
 const APPLICATION_DIRECTORY_NAME: &'static str = "zface";
 const APPLICATION_ENVIRONMENT_ROOT_DIR: &'static str = "ZFACE_ROOT_DIR";
+const APPLICATION_ENVIRONMENT_NETWORK: &'static str = "ZFACE_NETWORK";
 
 pub const DEFAULT_AMOUNT: &str = "10";
 pub const DEFAULT_BALANCE: &str = "100";
 pub const ALICESEED: &str = "416c696365202020202020202020202020202020202020202020202020202020";
 pub const BOBSEED: &str = "426f622020202020202020202020202020202020202020202020202020202020";
 pub const BOBACCOUNTID: &str = "45e66da531088b55dcb3b273ca825454d79d2d1d5c4fa2ba4a12c1fa1ccd6389";
-pub const ALICEDECRYPTIONKEY: &str = "b0451b0bfab2830a75216779e010e0bfd2e6d0b4e4b1270dfcdfd0d538509e02";
 pub const DEFAULT_ENCRYPTED_BALANCE: &str = "6f4962da776a391c3b03f3e14e8156d2545f39a3ebbed675ea28859252cb006fac776c796563fcd44cc49cfaea8bb796952c266e47779d94574c10ad01754b11";
 
 /// root directory configuration
@@ -37,6 +38,25 @@ pub(crate) fn global_rootdir_match<'a>(default: &'a PathBuf, matches: &ArgMatche
     }
 }
 
+// network configuration
+
+pub(crate) fn global_network_definition<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("NETWORK")
+        .long("network")
+        .takes_value(true)
+        .default_value("testnet")
+        .possible_values(&["mainnet", "testnet"])
+        .global(true)
+        .env(APPLICATION_ENVIRONMENT_NETWORK)
+        .help("the network whose address format is used, addresses of other networks are refused")
+}
+
+pub(crate) fn global_network_option(matches: &ArgMatches) -> AddressFormat {
+    matches.value_of("NETWORK")
+        .and_then(AddressFormat::from_name)
+        .unwrap_or_default()
+}
+
 // quiet configuration
 
 pub(crate) fn global_quiet_difinition<'a, 'b>() -> Arg<'a, 'b> {
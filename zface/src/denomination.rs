@@ -0,0 +1,141 @@
+//! Human-readable amounts such as `1.5 ZERO`, converted from and to the integer amounts
+//! handled by the chain with the decimals and the symbol of the coin or the asset.
+use std::fmt;
+
+/// Errors on parsing an amount of a denomination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AmountError {
+    InvalidNumber,
+    TooManyDecimals(u8),
+    WrongSymbol(String),
+    Overflow,
+}
+
+impl fmt::Display for AmountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AmountError::InvalidNumber => write!(f, "The amount is not a number."),
+            AmountError::TooManyDecimals(decimals) => write!(f, "The amount has more than {} decimals.", decimals),
+            AmountError::WrongSymbol(ref symbol) => write!(f, "The amount is not denominated in {}.", symbol),
+            AmountError::Overflow => write!(f, "The amount is too large."),
+        }
+    }
+}
+
+/// The symbol and the decimals of the native coin or an asset,
+/// set in the chain properties and the asset metadata respectively.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Denomination {
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+impl Denomination {
+    pub fn new(symbol: String, decimals: u8) -> Self {
+        Denomination { symbol, decimals }
+    }
+
+    /// The integer amounts of the chain without a symbol,
+    /// for chains and assets which don't configure a denomination.
+    pub fn units() -> Self {
+        Denomination::new(String::new(), 0)
+    }
+
+    /// Parse an amount like `1.5` or `1.5 ZERO` into the integer amount, e.g. `150` with two decimals.
+    /// A symbol, if given, must be the one of the denomination.
+    pub fn parse_amount(&self, s: &str) -> Result<u64, AmountError> {
+        let mut words = s.split_whitespace();
+        let number = words.next().ok_or(AmountError::InvalidNumber)?;
+        match (words.next(), words.next()) {
+            (None, _) => {},
+            (Some(symbol), None) if !self.symbol.is_empty() && symbol.eq_ignore_ascii_case(&self.symbol) => {},
+            _ => return Err(AmountError::WrongSymbol(self.symbol.clone())),
+        }
+
+        let (integer, fraction) = match number.find('.') {
+            Some(i) => (&number[..i], &number[i + 1..]),
+            None => (number, ""),
+        };
+        let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if (integer.is_empty() && fraction.is_empty()) || !is_digits(integer) || !is_digits(fraction) {
+            return Err(AmountError::InvalidNumber);
+        }
+
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > self.decimals as usize {
+            return Err(AmountError::TooManyDecimals(self.decimals));
+        }
+
+        // Integer and fraction digits concatenated, with the fraction padded to the decimals.
+        let digits = format!("{}{:0<width$}", integer, fraction, width = self.decimals as usize);
+        digits.trim_start_matches('0').chars().try_fold(0u64, |acc, c| {
+            acc.checked_mul(10)
+                .and_then(|acc| acc.checked_add(c.to_digit(10).expect("checked to be a digit; qed") as u64))
+                .ok_or(AmountError::Overflow)
+        })
+    }
+
+    /// Format an integer amount with the decimals and the symbol, e.g. `12345` with two decimals as `123.45 ZUSD`.
+    pub fn format_amount(&self, amount: u64) -> String {
+        let decimals = self.decimals as usize;
+        let number = if decimals == 0 {
+            amount.to_string()
+        } else {
+            let digits = format!("{:0>width$}", amount, width = decimals + 1);
+            let (integer, fraction) = digits.split_at(digits.len() - decimals);
+            format!("{}.{}", integer, fraction)
+        };
+
+        if self.symbol.is_empty() {
+            number
+        } else {
+            format!("{} {}", number, self.symbol)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zero() -> Denomination {
+        Denomination::new("ZERO".to_string(), 2)
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!(zero().parse_amount("1.5 ZERO"), Ok(150));
+        assert_eq!(zero().parse_amount("1.5"), Ok(150));
+        assert_eq!(zero().parse_amount("0.01 zero"), Ok(1));
+        assert_eq!(zero().parse_amount(".5"), Ok(50));
+        assert_eq!(zero().parse_amount("3."), Ok(300));
+        assert_eq!(zero().parse_amount("1.500"), Ok(150));
+        assert_eq!(zero().parse_amount("184467440737095516.15"), Ok(u64::max_value()));
+        assert_eq!(Denomination::units().parse_amount("10"), Ok(10));
+    }
+
+    #[test]
+    fn test_parse_invalid_amount() {
+        assert_eq!(zero().parse_amount(""), Err(AmountError::InvalidNumber));
+        assert_eq!(zero().parse_amount("."), Err(AmountError::InvalidNumber));
+        assert_eq!(zero().parse_amount("-1"), Err(AmountError::InvalidNumber));
+        assert_eq!(zero().parse_amount("1.2.3"), Err(AmountError::InvalidNumber));
+        assert_eq!(zero().parse_amount("1.005"), Err(AmountError::TooManyDecimals(2)));
+        assert_eq!(zero().parse_amount("1 ZUSD"), Err(AmountError::WrongSymbol("ZERO".to_string())));
+        assert_eq!(zero().parse_amount("1 ZERO ZERO"), Err(AmountError::WrongSymbol("ZERO".to_string())));
+        assert_eq!(zero().parse_amount("184467440737095516.16"), Err(AmountError::Overflow));
+        assert_eq!(Denomination::units().parse_amount("1.5"), Err(AmountError::TooManyDecimals(0)));
+    }
+
+    #[test]
+    fn test_format_amount() {
+        assert_eq!(zero().format_amount(12345), "123.45 ZERO");
+        assert_eq!(zero().format_amount(5), "0.05 ZERO");
+        assert_eq!(zero().format_amount(0), "0.00 ZERO");
+        assert_eq!(Denomination::new("ZUSD".to_string(), 0).format_amount(7), "7 ZUSD");
+        assert_eq!(Denomination::units().format_amount(100), "100");
+
+        let amount = 123456;
+        assert_eq!(zero().parse_amount(&zero().format_amount(amount)), Ok(amount));
+    }
+}
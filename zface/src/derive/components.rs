@@ -46,30 +46,3 @@ impl EncKeyTag {
         EncKeyTag([0u8; 4])
     }
 }
-
-/// A child index for a derived key
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum ChildIndex {
-    NonHardened(u32),
-    Hardened(u32),
-}
-
-impl ChildIndex {
-    pub fn from_index(i: u32) -> Self {
-        match i {
-            n if n >= (1 << 31) => ChildIndex::Hardened(n - ( 1 << 31)),
-            n => ChildIndex::NonHardened(n),
-        }
-    }
-
-    pub(super) fn master() -> Self {
-        ChildIndex::from_index(0)
-    }
-
-    pub(super) fn to_index(&self) -> u32 {
-        match self {
-            &ChildIndex::Hardened(i) => i + ( 1 << 31 ),
-            &ChildIndex::NonHardened(i) => i,
-        }
-    }
-}
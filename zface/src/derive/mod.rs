@@ -15,6 +15,7 @@ mod constants;
 mod components;
 use constants::*;
 pub use components::*;
+pub use keys::derive::{ChildIndex, DerivationPath};
 
 pub trait Derivation: Sized {
     /// Master key generation:
@@ -29,6 +30,12 @@ pub trait Derivation: Sized {
     /// If an index `i` >= 2^31, the child is a hardended key. If not, the child is a non-hardened key.
     fn derive_child(&self, i: ChildIndex) -> io::Result<Self>;
 
+    /// Derive the descendant at the path, e.g. `m/32'/0'/1`, by deriving each child in turn.
+    fn derive_path(&self, path: &DerivationPath) -> io::Result<Self> where Self: Clone {
+        path.indices().iter()
+            .try_fold(self.clone(), |key, i| key.derive_child(*i))
+    }
+
     fn read<R: Read>(reader: R) -> io::Result<Self>;
 
     fn write<W: Write>(&self, writer: W) -> io::Result<()>;
@@ -253,6 +260,8 @@ impl TryFrom<ExtendedSpendingKey> for SerdeBytes {
 mod tests {
     use super::*;
     use rand::{SeedableRng, Rng, XorShiftRng};
+    use zpairing::bls12_381::Bls12 as zBls12;
+    use zprimitives::PARAMS as ZPARAMS;
 
     fn gen_master_key_pairs() -> (ExtendedSpendingKey, ExtendedProofGenerationKey) {
         let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
@@ -317,4 +326,21 @@ mod tests {
         let xsk_m_read = ExtendedProofGenerationKey::read(&bytes[..]).unwrap();
         assert_eq!(xsk_m_read, xpgk_m);
     }
+
+    #[test]
+    fn derive_path_as_keys_crate() {
+        let seed = [7u8; 32];
+        let path: DerivationPath = "m/32'/0'/1".parse().unwrap();
+
+        let xsk = ExtendedSpendingKey::master(&seed).derive_path(&path).unwrap();
+        let no_std_xsk = keys::derive::ExtendedSpendingKey::<zBls12>::master(&seed)
+            .derive_path(&path, &ZPARAMS)
+            .unwrap();
+
+        let mut bytes = vec![];
+        xsk.write(&mut bytes).unwrap();
+        let mut no_std_bytes = vec![];
+        no_std_xsk.write(&mut no_std_bytes).unwrap();
+        assert_eq!(bytes, no_std_bytes);
+    }
 }
@@ -1,6 +1,8 @@
 use parity_crypto as crypto;
 use serde_json;
 use std::{error::Error, fmt, io, convert};
+use crate::ss58::Ss58Error;
+use crate::denomination::AmountError;
 
 /// Defined keystore errors
 #[derive(Debug)]
@@ -16,6 +18,19 @@ pub enum KeystoreError {
     InfallibleError(convert::Infallible),
     SynthesisError(bellman::SynthesisError),
     RpcError(ws::Error),
+    UnknownRecipient,
+    RuntimeMismatch(String),
+    ProvingKeyMismatch(String),
+    NoActiveDecoys(u64),
+    InvalidUri(String),
+    InvalidInvoice(String),
+    InvalidAddress(Ss58Error),
+    InvalidAmount(AmountError),
+    InvalidReturnAddress,
+    InvalidMemo,
+    EpochPassed,
+    UndecryptableBalance,
+    InvalidDecryptionKey(&'static str),
 }
 
 impl From<io::Error> for KeystoreError {
@@ -54,6 +69,18 @@ impl From<ws::Error> for KeystoreError {
     }
 }
 
+impl From<Ss58Error> for KeystoreError {
+    fn from(e: Ss58Error) -> Self {
+        KeystoreError::InvalidAddress(e)
+    }
+}
+
+impl From<AmountError> for KeystoreError {
+    fn from(e: AmountError) -> Self {
+        KeystoreError::InvalidAmount(e)
+    }
+}
+
 impl From<zpairing::io::Error> for KeystoreError {
     fn from(e: zpairing::io::Error) -> Self {
         KeystoreError::NostdIoError(e)
@@ -74,6 +101,19 @@ impl fmt::Display for KeystoreError {
             KeystoreError::SynthesisError(ref err) => write!(f, "synthesis error: {}", err),
             KeystoreError::RpcError(ref err) => write!(f, "rpc api error: {}", err),
             KeystoreError::NostdIoError(ref err) => write!(f, "No std I/O error: {}", err),
+            KeystoreError::UnknownRecipient => write!(f, "The recipient address has never appeared on chain. Check the address, or pass --allow-new-account to send anyway."),
+            KeystoreError::RuntimeMismatch(ref msg) => write!(f, "Refused to sign, the runtime of the node doesn't match: {}", msg),
+            KeystoreError::ProvingKeyMismatch(ref msg) => write!(f, "Refused to prove, the proving key doesn't match the chain: {}", msg),
+            KeystoreError::NoActiveDecoys(k) => write!(f, "No account has rolled over within the last {} epochs to be used as a decoy.", k),
+            KeystoreError::InvalidUri(ref msg) => write!(f, "Invalid payment request URI: {}", msg),
+            KeystoreError::InvalidInvoice(ref msg) => write!(f, "Invalid invoice: {}", msg),
+            KeystoreError::InvalidAddress(ref err) => write!(f, "Invalid address: {}", err),
+            KeystoreError::InvalidAmount(ref err) => write!(f, "Invalid amount: {}", err),
+            KeystoreError::InvalidReturnAddress => write!(f, "The return address is not sealed to this account."),
+            KeystoreError::InvalidMemo => write!(f, "The memo is longer than {} bytes or malformed.", crate::transaction::memo::MAX_MEMO_PLAINTEXT_LENGTH),
+            KeystoreError::EpochPassed => write!(f, "The epoch passed every time while proving the transfer. Try again later."),
+            KeystoreError::UndecryptableBalance => write!(f, "The balance is not below 2^32 or not encrypted under the decryption key."),
+            KeystoreError::InvalidDecryptionKey(msg) => write!(f, "Invalid decryption key: {}", msg),
         }
     }
 }
@@ -92,6 +132,19 @@ impl Error for KeystoreError {
             KeystoreError::SynthesisError(ref err) => err.description(),
             KeystoreError::RpcError(ref err) => err.description(),
             KeystoreError::NostdIoError(ref err) => err.description(),
+            KeystoreError::UnknownRecipient => "Unknown recipient address",
+            KeystoreError::RuntimeMismatch(_) => "Runtime mismatch",
+            KeystoreError::ProvingKeyMismatch(_) => "Proving key mismatch",
+            KeystoreError::NoActiveDecoys(_) => "No active decoys",
+            KeystoreError::InvalidUri(_) => "Invalid payment request URI",
+            KeystoreError::InvalidInvoice(_) => "Invalid invoice",
+            KeystoreError::InvalidAddress(_) => "Invalid address",
+            KeystoreError::InvalidAmount(_) => "Invalid amount",
+            KeystoreError::InvalidReturnAddress => "Invalid return address",
+            KeystoreError::InvalidMemo => "Invalid memo",
+            KeystoreError::EpochPassed => "The epoch passed while proving",
+            KeystoreError::UndecryptableBalance => "Undecryptable balance",
+            KeystoreError::InvalidDecryptionKey(_) => "Invalid decryption key",
         }
     }
 }
@@ -0,0 +1,240 @@
+//! Invoices: payment requests signed by the recipient, so that a wallet can check whom it pays before paying.
+//!
+//! An invoice carries the recipient's address together with the key it is derived from (`rvk`),
+//! and is signed with the recipient's spending key. The amount is encrypted to the payer's address,
+//! so that an invoice shared as text or as a QR code reveals it to nobody else.
+//! Invoices are encoded as `zerochain-invoice:<base58 of the SCALE-encoded invoice>` URIs.
+
+use rand::{Rng, Rand};
+use base58::{ToBase58, FromBase58};
+use blake2_rfc::blake2b::Blake2b;
+use parity_codec::{Encode, Decode};
+use proofs::{SpendingKey, ProofGenerationKey, EncryptionKey, DecryptionKey, PARAMS, elgamal};
+use pairing::bls12_381::Bls12;
+use scrypto::{
+    jubjub::{fs::Fs, FixedGenerators},
+    redjubjub::{PrivateKey, PublicKey, Signature},
+};
+use crate::error::{KeystoreError, Result};
+use crate::transaction::memo::MAX_MEMO_PLAINTEXT_LENGTH;
+
+/// The scheme of invoice URIs.
+pub const INVOICE_URI_SCHEME: &str = "zerochain-invoice";
+
+const INVOICE_PERSONALIZATION: &[u8; 16] = b"zerochainInvoice";
+
+/// A payment request issued by the recipient to a given payer.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct Invoice {
+    /// The encryption key of the recipient.
+    pub recipient: [u8; 32],
+    /// The key verifying the signature, which the recipient's encryption key is derived from.
+    pub rvk: [u8; 32],
+    /// The encryption key of the payer.
+    pub payer: [u8; 32],
+    /// The amount encrypted to the payer.
+    pub amount_hint: [u8; 64],
+    pub memo: Vec<u8>,
+    /// The last epoch in which the invoice can be paid.
+    pub expiry: u64,
+    pub signature: [u8; 64],
+}
+
+/// The contents of an invoice which was verified by its payer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedInvoice {
+    pub recipient: [u8; 32],
+    pub amount: u64,
+    pub memo: Vec<u8>,
+    pub expiry: u64,
+}
+
+impl Invoice {
+    /// Create an invoice requesting `amount` from `payer`, signed with the recipient's spending key.
+    pub fn create<R: Rng>(
+        spending_key: &SpendingKey<Bls12>,
+        payer: &EncryptionKey<Bls12>,
+        amount: u64,
+        memo: &[u8],
+        expiry: u64,
+        rng: &mut R,
+    ) -> Result<Self> {
+        if memo.len() > MAX_MEMO_PLAINTEXT_LENGTH {
+            return Err(KeystoreError::InvalidMemo);
+        }
+
+        let p_g = FixedGenerators::NoteCommitmentRandomness;
+        let pgk = ProofGenerationKey::<Bls12>::from_spending_key(spending_key, &PARAMS);
+
+        let mut amount_hint = [0u8; 64];
+        elgamal::Ciphertext::encrypt(amount, &Fs::rand(rng), payer, p_g, &PARAMS)
+            .write(&mut amount_hint[..])?;
+
+        let mut invoice = Invoice {
+            recipient: pgk.into_encryption_key(&PARAMS)?.into_bytes()?,
+            rvk: pgk.into_bytes()?,
+            payer: payer.into_bytes()?,
+            amount_hint,
+            memo: memo.to_vec(),
+            expiry,
+            signature: [0u8; 64],
+        };
+
+        PrivateKey::<Bls12>(spending_key.0)
+            .sign(&invoice.message()[..], rng, p_g, &PARAMS)
+            .write(&mut invoice.signature[..])?;
+
+        Ok(invoice)
+    }
+
+    /// Verify the invoice on behalf of the payer with `dec_key`, before paying it in `current_epoch`.
+    /// It must be signed by the recipient, addressed to the payer and not expired.
+    pub fn verify(&self, dec_key: &DecryptionKey<Bls12>, current_epoch: u64) -> Result<VerifiedInvoice> {
+        let invalid = |msg: &str| KeystoreError::InvalidInvoice(msg.to_string());
+        let p_g = FixedGenerators::NoteCommitmentRandomness;
+
+        let pgk = ProofGenerationKey::<Bls12>::read(&mut &self.rvk[..], &PARAMS)
+            .map_err(|_| invalid("The signing key is not a valid point."))?;
+        if pgk.into_encryption_key(&PARAMS)?.into_bytes()? != self.recipient {
+            return Err(invalid("The recipient address is not derived from the signing key."));
+        }
+
+        let rvk = PublicKey::<Bls12>::read(&mut &self.rvk[..], &PARAMS)?;
+        let signature = Signature::read(&mut &self.signature[..])?;
+        if !rvk.verify(&self.message()[..], &signature, p_g, &PARAMS) {
+            return Err(invalid("The signature doesn't match."));
+        }
+
+        if EncryptionKey::from_decryption_key(dec_key, &PARAMS).into_bytes()? != self.payer {
+            return Err(invalid("The invoice is addressed to another payer."));
+        }
+        if self.memo.len() > MAX_MEMO_PLAINTEXT_LENGTH {
+            return Err(KeystoreError::InvalidMemo);
+        }
+        if self.expiry < current_epoch {
+            return Err(KeystoreError::InvalidInvoice(format!("The invoice expired in epoch {}.", self.expiry)));
+        }
+
+        let amount = elgamal::Ciphertext::<Bls12>::read(&mut &self.amount_hint[..], &PARAMS)
+            .ok()
+            .and_then(|c| c.decrypt(dec_key, p_g, &PARAMS))
+            .ok_or_else(|| invalid("The amount can't be decrypted."))?;
+
+        Ok(VerifiedInvoice {
+            recipient: self.recipient,
+            amount,
+            memo: self.memo.clone(),
+            expiry: self.expiry,
+        })
+    }
+
+    /// Parse a `zerochain-invoice:` URI.
+    pub fn parse(uri: &str) -> Result<Self> {
+        let rest = uri.trim()
+            .splitn(2, ':')
+            .collect::<Vec<_>>();
+        if rest.len() != 2 || rest[0] != INVOICE_URI_SCHEME {
+            return Err(KeystoreError::InvalidInvoice(format!("The scheme must be `{}:`.", INVOICE_URI_SCHEME)));
+        }
+
+        let payload = rest[1].from_base58()
+            .map_err(|_| KeystoreError::InvalidInvoice("The payload is not base58-encoded.".to_string()))?;
+        let mut input = &payload[..];
+        let invoice = Invoice::decode(&mut input)
+            .ok_or_else(|| KeystoreError::InvalidInvoice("The payload can't be decoded.".to_string()))?;
+        if !input.is_empty() {
+            return Err(KeystoreError::InvalidInvoice("The payload has trailing bytes.".to_string()));
+        }
+
+        Ok(invoice)
+    }
+
+    pub fn to_uri(&self) -> String {
+        format!("{}:{}", INVOICE_URI_SCHEME, self.encode().to_base58())
+    }
+
+    /// The hash of all fields but the signature, which is signed by the recipient.
+    fn message(&self) -> [u8; 32] {
+        let unsigned = (self.recipient, self.rvk, self.payer, self.amount_hint, self.memo.clone(), self.expiry);
+
+        let mut h = Blake2b::with_params(32, &[], &[], INVOICE_PERSONALIZATION);
+        h.update(&unsigned.encode()[..]);
+
+        let mut res = [0u8; 32];
+        res.copy_from_slice(h.finalize().as_bytes());
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, XorShiftRng};
+
+    fn keys(seed: &[u8; 32]) -> (SpendingKey<Bls12>, DecryptionKey<Bls12>, EncryptionKey<Bls12>) {
+        let spending_key = SpendingKey::<Bls12>::from_seed(seed);
+        let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
+            .into_decryption_key().unwrap();
+        let enc_key = EncryptionKey::from_decryption_key(&dec_key, &PARAMS);
+        (spending_key, dec_key, enc_key)
+    }
+
+    fn invoice(rng: &mut XorShiftRng) -> Invoice {
+        let (merchant, _, _) = keys(b"Alice                           ");
+        let (_, _, payer) = keys(b"Bob                             ");
+        Invoice::create(&merchant, &payer, 100, b"order #42", 10, rng).unwrap()
+    }
+
+    #[test]
+    fn test_create_and_verify() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let (_, _, merchant) = keys(b"Alice                           ");
+        let (_, payer_dec_key, _) = keys(b"Bob                             ");
+        let invoice = invoice(rng);
+
+        let verified = invoice.verify(&payer_dec_key, 10).unwrap();
+        assert_eq!(verified.recipient, merchant.into_bytes().unwrap());
+        assert_eq!(verified.amount, 100);
+        assert_eq!(verified.memo, b"order #42".to_vec());
+
+        assert_matches!(invoice.verify(&payer_dec_key, 11), Err(KeystoreError::InvalidInvoice(_)));
+
+        let (_, other_dec_key, _) = keys(b"Charlie                         ");
+        assert_matches!(invoice.verify(&other_dec_key, 10), Err(KeystoreError::InvalidInvoice(_)));
+    }
+
+    #[test]
+    fn test_tampered_invoice() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let (_, payer_dec_key, _) = keys(b"Bob                             ");
+        let invoice = invoice(rng);
+
+        let mut tampered = invoice.clone();
+        tampered.expiry = 100;
+        assert_matches!(tampered.verify(&payer_dec_key, 10), Err(KeystoreError::InvalidInvoice(_)));
+
+        // An invoice re-signed by someone else who claims the merchant's address.
+        let (_, _, payer) = keys(b"Bob                             ");
+        let (mallory, _, _) = keys(b"Mallory                         ");
+        let mut forged = Invoice::create(&mallory, &payer, 100, b"order #42", 10, rng).unwrap();
+        forged.recipient = invoice.recipient;
+        assert_matches!(forged.verify(&payer_dec_key, 10), Err(KeystoreError::InvalidInvoice(_)));
+    }
+
+    #[test]
+    fn test_invoice_uri_roundtrip() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let invoice = invoice(rng);
+        let uri = invoice.to_uri();
+
+        assert!(uri.starts_with("zerochain-invoice:"));
+        assert_eq!(Invoice::parse(&uri).unwrap(), invoice);
+
+        assert_matches!(Invoice::parse(&uri.replacen("zerochain-invoice", "zerochain", 1)), Err(KeystoreError::InvalidInvoice(_)));
+        assert_matches!(Invoice::parse("zerochain-invoice:0OIl"), Err(KeystoreError::InvalidInvoice(_)));
+
+        let mut trailing = invoice.encode();
+        trailing.push(0);
+        assert_matches!(Invoice::parse(&format!("zerochain-invoice:{}", trailing.to_base58())), Err(KeystoreError::InvalidInvoice(_)));
+    }
+}
@@ -5,6 +5,9 @@ pub mod term;
 pub mod utils;
 pub mod transaction;
 pub mod error;
+pub mod uri;
+pub mod invoice;
+pub mod denomination;
 
 #[macro_use]
 extern crate serde_derive;
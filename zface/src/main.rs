@@ -12,12 +12,12 @@ use std::path::PathBuf;
 use clap::{Arg, App, SubCommand, AppSettings, ArgMatches};
 use rand::{OsRng, Rng};
 use proofs::{
-    EncryptionKey, SpendingKey, DecryptionKey,
+    EncryptionKey, SpendingKey,
     elgamal, MultiEncKeys, anonymous_setup,
     confidential_setup, PARAMS, KeyContext, ProofBuilder,
-    Confidential,
+    Confidential, constants::ANONIMITY_SIZE,
     };
-use primitives::{hexdisplay::{HexDisplay, AsBytesRef}, crypto::Ss58Codec};
+use primitives::hexdisplay::{HexDisplay, AsBytesRef};
 use pairing::bls12_381::Bls12;
 use polkadot_rs::{Api, Url};
 use bip39::{Mnemonic, Language, MnemonicType};
@@ -30,7 +30,11 @@ pub mod derive;
 pub mod term;
 pub mod ss58;
 pub mod error;
+pub mod uri;
+pub mod invoice;
+pub mod denomination;
 use self::ss58::EncryptionKeyBytes;
+use self::denomination::Denomination;
 use self::utils::*;
 use self::config::*;
 use self::wallet::commands::*;
@@ -48,10 +52,15 @@ fn main() {
         .arg(global_quiet_difinition())
         .arg(global_color_definition())
         .arg(global_rootdir_definition(&default_root_dir))
+        .arg(global_network_definition())
         .subcommand(snark_commands_definition())
         .subcommand(wallet_commands_definition())
         .subcommand(tx_commands_definition())
+        .subcommand(invoice_commands_definition())
         .subcommand(debug_commands_definition())
+        .subcommand(verify_install_command_definition())
+        .subcommand(dev_commands_definition())
+        .subcommand(faucet_commands_definition())
         .get_matches();
 
     let mut term = term::Term::new(config_terminal(&matches));
@@ -62,7 +71,11 @@ fn main() {
         (SNARK_COMMAND, Some(matches)) => subcommand_snark(term, matches, rng),
         (WALLET_COMMAND, Some(matches)) => subcommand_wallet(term, root_dir, matches, rng),
         (TX_COMMAND, Some(matches)) => subcommand_tx(term, root_dir, matches, rng),
+        (INVOICE_COMMAND, Some(matches)) => subcommand_invoice(term, root_dir, matches, rng),
         (DEBUG_COMMAND, Some(matches)) => subcommand_debug(term, matches, rng),
+        (VERIFY_INSTALL_COMMAND, Some(matches)) => subcommand_verify_install(term, root_dir, matches),
+        (DEV_COMMAND, Some(matches)) => subcommand_dev(term, matches, rng),
+        (FAUCET_COMMAND, Some(matches)) => subcommand_faucet(term, root_dir, matches, rng),
         _ => {
             term.error(matches.usage()).unwrap();
             ::std::process::exit(1);
@@ -70,6 +83,167 @@ fn main() {
     }
 }
 
+//
+//  Verify Install Command
+//
+
+const VERIFY_INSTALL_COMMAND: &'static str = "verify-install";
+
+fn subcommand_verify_install(mut term: term::Term, root_dir: PathBuf, matches: &ArgMatches) {
+    let api = matches.value_of("url").map(|u| Api::init(Url::Custom(u.to_string())));
+    let report = install::verify_install(
+        api.as_ref(),
+        &[(CONF_PK_PATH, "ConfidentialPkHash"), (ANONY_PK_PATH, "AnonymousPkHash")],
+        &root_dir
+    ).unwrap_or_else(|e| term.fail_with(e));
+
+    for (name, status) in &report.checks {
+        match status {
+            install::CheckStatus::Ok(msg) => term.success(&format!("[OK] {}: {}\n", name, msg)).unwrap(),
+            install::CheckStatus::Warning(msg) => term.warn(&format!("[WARN] {}: {}\n", name, msg)).unwrap(),
+            install::CheckStatus::Failed(msg) => term.error(&format!("[FAIL] {}: {}\n", name, msg)).unwrap(),
+        }
+    }
+
+    if !report.is_ok() {
+        ::std::process::exit(1);
+    }
+}
+
+fn verify_install_command_definition<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(VERIFY_INSTALL_COMMAND)
+        .about("Check the build metadata, the local params against the chain and the wallet permissions")
+        .arg(Arg::with_name("url")
+            .short("u")
+            .long("url")
+            .help("Endpoint of a zerochain node to check the params against")
+            .takes_value(true)
+            .required(false)
+        )
+}
+
+//
+//  Dev Sub Commands
+//
+
+const DEV_COMMAND: &'static str = "dev";
+
+fn dev_arg_up_match<'a, R: Rng>(term: &mut term::Term, matches: &ArgMatches<'a>, rng: &mut R) {
+    let performed_setup = dev::ensure_params(rng).unwrap_or_else(|e| term.fail_with(e));
+
+    let mut node = matches.value_of("node").map(|node| {
+        let log_path = matches.value_of("log").unwrap();
+        println!("Launching {} --dev, logging to {}...", node, log_path);
+        // A chain started before the setup stores verifying keys which don't match the new params.
+        dev::launch_node(node.as_ref(), log_path.as_ref(), performed_setup)
+            .unwrap_or_else(|e| term.fail_with(e))
+    });
+
+    let api = Api::init(tx_arg_url_match(matches));
+    dev::wait_for_node(&api).unwrap_or_else(|e| term.fail_with(e));
+
+    let url = matches.value_of("url").unwrap_or(runtime::LOCAL_URL);
+    let accounts = dev::dev_accounts(&api, url).unwrap_or_else(|e| term.fail_with(e));
+    for account in accounts {
+        println!("{}:\n Seed: 0x{}\n Decryption key: {}\n Address (SS58): {}\n Balance: {}",
+            account.name,
+            hex::encode(&account.keys.seed[..]),
+            account.keys.decryption_key,
+            account.keys.ss58_encryption_key,
+            account.balance,
+        );
+    }
+    term.success("The devnet is ready.\n").unwrap();
+
+    if let Some(ref mut node) = node {
+        println!("Press Ctrl-C to stop the node.");
+        node.wait().unwrap_or_else(|e| term.fail_with(e));
+    }
+}
+
+fn subcommand_dev<R: Rng>(mut term: term::Term, matches: &ArgMatches, rng: &mut R) {
+    match matches.subcommand() {
+        ("up", Some(matches)) => {
+            dev_arg_up_match(&mut term, matches, rng);
+        },
+        _ => {
+            term.error(matches.usage()).unwrap();
+            ::std::process::exit(1)
+        }
+    };
+}
+
+fn dev_commands_definition<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(DEV_COMMAND)
+        .about("Local development network")
+        .subcommand(SubCommand::with_name("up")
+            .about("Set up the params if missing, launch or reach a dev node and print the keys of the funded accounts")
+            .arg(Arg::with_name("node")
+                .short("n")
+                .long("node")
+                .help("Path to a zerochain binary to launch with --dev; otherwise a running node is used")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("log")
+                .long("log")
+                .help("Path to the log file of the launched node")
+                .takes_value(true)
+                .required(false)
+                .default_value("zerochain-dev.log")
+            )
+            .arg(Arg::with_name("url")
+                .short("u")
+                .long("url")
+                .help("Endpoint to connect zerochain nodes")
+                .takes_value(true)
+                .required(false)
+            )
+        )
+}
+
+//
+//  Faucet Sub Commands
+//
+
+const FAUCET_COMMAND: &'static str = "faucet";
+
+fn subcommand_faucet<R: Rng>(mut term: term::Term, root_dir: PathBuf, matches: &ArgMatches, rng: &mut R) {
+    match matches.subcommand() {
+        ("claim", Some(sub_matches)) => {
+            // The claim is built with the call indices of the runtime zface was compiled with.
+            let url = sub_matches.value_of("url").unwrap_or(runtime::LOCAL_URL);
+            if let Err(e) = runtime::ensure_runtime_matches(&root_dir, url) {
+                term.fail_with(e)
+            }
+
+            faucet_claim_tx(&mut term, root_dir, tx_arg_url_match(&sub_matches), rng)
+                .unwrap_or_else(|e| term.fail_with(e));
+
+            println!("Claimed. The amount is added to the balance from the next epoch.");
+        },
+        _ => {
+            term.error(matches.usage()).unwrap();
+            ::std::process::exit(1);
+        }
+    }
+}
+
+fn faucet_commands_definition<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(FAUCET_COMMAND)
+        .about("Faucet of testnets")
+        .subcommand(SubCommand::with_name("claim")
+            .about("Claim the faucet for the account of the wallet, which can be done once per claim interval")
+            .arg(Arg::with_name("url")
+                .short("u")
+                .long("url")
+                .help("Endpoint to connect zerochain nodes")
+                .takes_value(true)
+                .required(false)
+            )
+        )
+}
+
 //
 //  Snark Sub Commands
 //
@@ -221,22 +395,51 @@ fn wallet_arg_id_match<'a>(matches: &ArgMatches<'a>) -> u32 {
     id
 }
 
+fn wallet_arg_metadata_entry(entry: &str) -> (String, String) {
+    let mut iter = entry.splitn(2, '=');
+    let key = iter.next().expect("splitn yields at least one item; qed");
+    let value = iter.next()
+        .expect("Metadata entry should be formatted as KEY=VALUE.");
+
+    (key.to_string(), value.to_string())
+}
+
 fn subcommand_wallet<R: Rng>(mut term: term::Term, root_dir: PathBuf, matches: &ArgMatches, rng: &mut R) {
+    let format = global_network_option(matches);
+
     match matches.subcommand() {
         ("init", Some(_)) => {
             // Create new wallet
-            new_wallet(&mut term, root_dir, rng)
+            new_wallet(&mut term, root_dir, format, rng)
                 .expect("Invalid operations of creating new wallet.");
         },
-        ("list", Some(_)) => {
+        ("list", Some(sub_matches)) => {
             // show accounts list
-            show_list(&mut term, root_dir)
+            show_list(&mut term, root_dir, format, sub_matches.is_present("verbose"))
                 .expect("Invalid operations of listing accounts.");
         },
         ("add-account", Some(_)) => {
-            new_keyfile(&mut term, root_dir, rng)
+            new_keyfile(&mut term, root_dir, format, rng)
                 .expect("Invalid operations of creating new account.");
         },
+        ("qr", Some(sub_matches)) => {
+            // The node is only asked for the denomination if an amount is requested.
+            let amount = match sub_matches.value_of("amount") {
+                Some(_) => {
+                    let denomination = tx_arg_native_denomination_match(&mut term, &sub_matches);
+                    Some(tx_arg_amount_match(&mut term, &sub_matches, &denomination))
+                },
+                None => None,
+            };
+            let memo = sub_matches.value_of("memo").map(|m| m.to_string());
+
+            show_qr(&mut term, root_dir, format, amount, memo)
+                .expect("Showing QR code failed.");
+        },
+        ("change-passphrase", Some(_)) => {
+            change_password(&mut term, root_dir, rng)
+                .unwrap_or_else(|e| term.fail_with(e));
+        },
         ("change-account", Some(sub_matches)) => {
             let account_name = sub_matches.value_of("account-name")
                 .expect("Account name is required; qed");
@@ -244,47 +447,118 @@ fn subcommand_wallet<R: Rng>(mut term: term::Term, root_dir: PathBuf, matches: &
             change_default_account(root_dir, account_name)
                 .expect("Change default account failed.");
         },
+        ("annotate", Some(sub_matches)) => {
+            let account_name = sub_matches.value_of("account-name")
+                .expect("Account name is required; qed");
+            let entries = sub_matches.values_of("set")
+                .map(|values| values.map(wallet_arg_metadata_entry).collect())
+                .unwrap_or_default();
+            let removed_keys = sub_matches.values_of("remove")
+                .map(|values| values.collect())
+                .unwrap_or_default();
+
+            annotate_account(root_dir, account_name, entries, removed_keys)
+                .expect("Annotating account failed.");
+        },
         ("recovery", Some(_)) => {
-            recover(&mut term, root_dir, rng)
+            recover(&mut term, root_dir, format, rng)
                 .expect("Invalid mnemonic to recover keystore.");
         },
         ("balance", Some(sub_matches)) => {
             println!("Getting encrypted balance...");
             let api = Api::init(tx_arg_url_match(&sub_matches));
+            let url = sub_matches.value_of("url").unwrap_or(runtime::LOCAL_URL);
 
             let dec_key = load_dec_key(&mut term, root_dir)
                 .expect("loading decrption key failed.");
 
-            let balance_query = getter::BalanceQuery::get_encrypted_balance(&dec_key, api)
+            let denomination = tx_arg_native_denomination_match(&mut term, &sub_matches);
+            let balance_query = getter::BalanceQuery::get_encrypted_balance(&dec_key, api, url)
                 .expect("Falid to get balance data.");
 
-            println!("Decrypted balance: {}", balance_query.decrypted_balance);
+            println!("Decrypted balance: {}", denomination.format_amount(balance_query.decrypted_balance));
             println!("Encrypted balance: {}", balance_query.encrypted_balance_str);
             println!("Encrypted pending transfer: {}", balance_query.pending_transfer_str);
         },
+        ("export-decryption-key", Some(_)) => {
+            let dec_key = load_dec_key(&mut term, root_dir)
+                .expect("loading decrption key failed.");
+
+            term.warn("Anyone with the decryption key can see the balances and the transfers of this account.\n").unwrap();
+            match getter::dec_key_to_string(&dec_key) {
+                Ok(dec_key) => println!("Decryption key: {}", dec_key),
+                Err(e) => term.fail_with(e),
+            }
+        },
+        ("counterparty", Some(sub_matches)) => {
+            let sealed = hex::decode(sub_matches.value_of("return-address")
+                .expect("Return address is required; qed")
+                .trim_start_matches("0x"))
+                .expect("should be decoded to hex.");
+
+            let dec_key = load_dec_key(&mut term, root_dir)
+                .expect("loading decrption key failed.");
+
+            match return_address::open(&dec_key, &sealed[..]).and_then(|sender| sender.into_bytes().map_err(Into::into)) {
+                Ok(sender) => println!("Counterparty: {}", EncryptionKeyBytes(sender).to_ss58check_with_format(format)),
+                Err(e) => term.fail_with(e),
+            }
+        },
+        ("memo", Some(sub_matches)) => {
+            let sealed = hex::decode(sub_matches.value_of("memo")
+                .expect("Memo is required; qed")
+                .trim_start_matches("0x"))
+                .expect("should be decoded to hex.");
+            if sealed.len() != zprimitives::memo::MEMO_SIZE {
+                term.fail_with(error::KeystoreError::InvalidMemo);
+            }
+
+            let dec_key = load_dec_key(&mut term, root_dir)
+                .expect("loading decrption key failed.");
+
+            match memo::open(&dec_key, &zprimitives::Memo::from_slice(&sealed[..])) {
+                Ok(plaintext) => println!("Memo: {}", String::from_utf8_lossy(&plaintext[..])),
+                Err(e) => term.fail_with(e),
+            }
+        },
         ("asset-balance", Some(sub_matches)) => {
             println!("Getting encrypted asset...");
             let api = Api::init(tx_arg_url_match(&sub_matches));
+            let url = sub_matches.value_of("url").unwrap_or(runtime::LOCAL_URL);
             let dec_key = load_dec_key(&mut term, root_dir)
                 .expect("loading decrption key failed.");
             let asset_id = wallet_arg_id_match(&sub_matches);
 
-            let balance_query = getter::BalanceQuery::get_encrypted_asset(asset_id, &dec_key, api)
+            let metadata = getter::asset_metadata(&api, asset_id)
+                .expect("Faild to get asset metadata.");
+            let balance_query = getter::BalanceQuery::get_encrypted_asset(asset_id, &dec_key, api, url)
                 .expect("Falid to get balance data.");
 
-            println!("Decrypted balance: {}", balance_query.decrypted_balance);
+            match metadata {
+                Some(metadata) => {
+                    println!("Asset: {} ({})", metadata.name, metadata.symbol);
+                    println!("Decrypted balance: {}", metadata.format_amount(balance_query.decrypted_balance));
+                },
+                None => println!("Decrypted balance: {}", balance_query.decrypted_balance),
+            }
             println!("Encrypted balance: {}", balance_query.encrypted_balance_str);
             println!("Encrypted pending transfer: {}", balance_query.pending_transfer_str);
         },
         ("anonymous-balance", Some(sub_matches)) => {
             println!("Getting anonymous balance...");
             let api = Api::init(tx_arg_url_match(&sub_matches));
+            let url = sub_matches.value_of("url").unwrap_or(runtime::LOCAL_URL);
             let dec_key = load_dec_key(&mut term, root_dir)
                 .expect("loading decrption key failed.");
-            let balance_query = getter::BalanceQuery::get_anonymous_balance(&dec_key, api)
-                .expect("Falid to get balance data.");
-
-            println!("Decrypted balance: {}", balance_query.decrypted_balance);
+            let decoys = sub_matches.value_of("decoys")
+                .map(|n| n.parse::<usize>().expect("should be parsed to usize number; qed"));
+            let balance_query = match decoys {
+                Some(n) => getter::BalanceQuery::get_anonymous_balance_with_decoys(&dec_key, api, url, n, rng),
+                None => getter::BalanceQuery::get_anonymous_balance(&dec_key, api, url),
+            }.expect("Falid to get balance data.");
+            let denomination = tx_arg_native_denomination_match(&mut term, &sub_matches);
+
+            println!("Decrypted balance: {}", denomination.format_amount(balance_query.decrypted_balance));
             println!("Encrypted balance: {}", balance_query.encrypted_balance_str);
             println!("Encrypted pending transfer: {}", balance_query.pending_transfer_str);
         },
@@ -306,9 +580,9 @@ fn subcommand_wallet<R: Rng>(mut term: term::Term, root_dir: PathBuf, matches: &
                 Bob: 0x{}
                 Charlie: 0x{}
                 \nDecryption Key
-                Alice: 0x{}
-                Bob: 0x{}
-                Charlie: 0x{}
+                Alice: {}
+                Bob: {}
+                Charlie: {}
                 \nEncryption Key
                 Alice: 0x{}
                 Bob: 0x{}
@@ -317,9 +591,9 @@ fn subcommand_wallet<R: Rng>(mut term: term::Term, root_dir: PathBuf, matches: &
                 hex::encode(&alice_seed[..]),
                 hex::encode(&print_keys_bob.seed[..]),
                 hex::encode(&print_keys_charlie.seed[..]),
-                hex::encode(&print_keys_alice.decryption_key[..]),
-                hex::encode(&print_keys_bob.decryption_key[..]),
-                hex::encode(&print_keys_charlie.decryption_key[..]),
+                print_keys_alice.decryption_key,
+                print_keys_bob.decryption_key,
+                print_keys_charlie.decryption_key,
                 hex::encode(&print_keys_alice.encryption_key[..]),
                 hex::encode(&print_keys_bob.encryption_key[..]),
                 hex::encode(&print_keys_charlie.encryption_key[..]),
@@ -343,6 +617,65 @@ fn wallet_commands_definition<'a, 'b>() -> App<'a, 'b> {
         )
         .subcommand(SubCommand::with_name("list")
             .about("Show accounts list.")
+            .arg(Arg::with_name("verbose")
+                .short("v")
+                .long("verbose")
+                .help("Show metadata of each account")
+            )
+        )
+        .subcommand(SubCommand::with_name("annotate")
+            .about("Set or remove metadata of an account, e.g. label, color, notes and creation-height.")
+            .arg(Arg::with_name("account-name")
+                .short("n")
+                .long("name")
+                .help("An account name that you have in your keystore.")
+                .takes_value(true)
+                .required(true)
+            )
+            .arg(Arg::with_name("set")
+                .short("s")
+                .long("set")
+                .help("Metadata entry to set, formatted as KEY=VALUE")
+                .value_name("KEY=VALUE")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .required(false)
+            )
+            .arg(Arg::with_name("remove")
+                .short("r")
+                .long("remove")
+                .help("Metadata key to remove")
+                .value_name("KEY")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .required(false)
+            )
+        )
+        .subcommand(SubCommand::with_name("qr")
+            .about("Show the address of the default account as a QR code, or a payment request if an amount or a memo is given.")
+            .arg(Arg::with_name("amount")
+                .short("a")
+                .long("amount")
+                .help("The requested coin amount, e.g. 1.5 or 1.5 ZERO")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("memo")
+                .short("m")
+                .long("memo")
+                .help("A memo attached to the payment request")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("url")
+                .short("u")
+                .long("url")
+                .help("Endpoint to connect zerochain nodes, which set the denomination of the amount")
+                .takes_value(true)
+                .required(false)
+            )
         )
         .subcommand(SubCommand::with_name("add-account")
             .about("Add a new account")
@@ -357,6 +690,9 @@ fn wallet_commands_definition<'a, 'b>() -> App<'a, 'b> {
                 .required(true)
             )
         )
+        .subcommand(SubCommand::with_name("change-passphrase")
+            .about("Re-encrypt all the keyfiles with a new wallet password")
+        )
         .subcommand(SubCommand::with_name("recovery")
             .about("Recover keystore from mnemonic.")
         )
@@ -370,6 +706,27 @@ fn wallet_commands_definition<'a, 'b>() -> App<'a, 'b> {
                 .required(false)
             )
         )
+        .subcommand(SubCommand::with_name("export-decryption-key")
+            .about("Export the decryption key of the default account, which watch-only wallets import to see its balances")
+        )
+        .subcommand(SubCommand::with_name("counterparty")
+            .about("Show who paid you from the return address attached to a transfer to you")
+            .arg(Arg::with_name("return-address")
+                .long("return-address")
+                .help("The hex-encoded return address in the ReturnAddress event of the transfer")
+                .takes_value(true)
+                .required(true)
+            )
+        )
+        .subcommand(SubCommand::with_name("memo")
+            .about("Show the memo attached to a transfer to you")
+            .arg(Arg::with_name("memo")
+                .long("memo")
+                .help("The hex-encoded memo in the Memo event of the transfer")
+                .takes_value(true)
+                .required(true)
+            )
+        )
         .subcommand(SubCommand::with_name("asset-balance")
             .about("Get current asset stored in encrypted asset module")
             .arg(Arg::with_name("asset-id")
@@ -389,6 +746,12 @@ fn wallet_commands_definition<'a, 'b>() -> App<'a, 'b> {
         )
         .subcommand(SubCommand::with_name("anonymous-balance")
             .about("Get current balance stored in encrypted balances module")
+            .arg(Arg::with_name("decoys")
+                .long("decoys")
+                .help("Also query the balances of this number of other accounts in a random order to hide which account is yours")
+                .takes_value(true)
+                .required(false)
+            )
             .arg(Arg::with_name("url")
                 .short("u")
                 .long("url")
@@ -405,24 +768,62 @@ fn wallet_commands_definition<'a, 'b>() -> App<'a, 'b> {
 
 const TX_COMMAND: &'static str = "tx";
 
-fn tx_arg_recipient_address_match<'a>(matches: &ArgMatches<'a>) -> [u8; 32] {
+/// The recipient's encryption key. Addresses of other networks than `--network` are refused.
+fn tx_arg_recipient_address_match<'a>(term: &mut term::Term, matches: &ArgMatches<'a>) -> [u8; 32] {
     let recipient_address = matches.value_of("recipient-address")
         .expect("Recipient's address is required; qed");
 
-    let recipient_enc_key = EncryptionKeyBytes::from_ss58check(recipient_address)
-        .expect("The string should be a properly encoded SS58Check address.");
+    let recipient_enc_key = EncryptionKeyBytes::from_ss58check_with_format(recipient_address, global_network_option(matches))
+        .unwrap_or_else(|e| term.fail_with(error::KeystoreError::from(e)));
 
     recipient_enc_key.0
 }
 
-fn tx_arg_amount_match<'a>(matches: &ArgMatches<'a>) -> u32 {
+/// `--amount` in the denomination, e.g. `1.5` or `1.5 ZERO`, as the integer amount of the chain.
+fn tx_arg_amount_match<'a>(term: &mut term::Term, matches: &ArgMatches<'a>, denomination: &Denomination) -> u64 {
     let amount_str = matches.value_of("amount")
         .expect("Amount parameter is required; qed");
 
-    let amount: u32 = amount_str.parse()
-        .expect("should be parsed to u32 number; qed");
+    denomination.parse_amount(amount_str)
+        .unwrap_or_else(|e| term.fail_with(error::KeystoreError::from(e)))
+}
+
+/// The denomination of the native coin in the chain properties of the node at `--url`.
+fn tx_arg_native_denomination_match<'a>(term: &mut term::Term, matches: &ArgMatches<'a>) -> Denomination {
+    let url = matches.value_of("url").unwrap_or(runtime::LOCAL_URL);
+
+    getter::native_denomination(url)
+        .unwrap_or_else(|e| term.fail_with(e))
+}
+
+/// The denomination of the asset in its metadata.
+fn tx_arg_asset_denomination_match<'a>(term: &mut term::Term, matches: &ArgMatches<'a>, asset_id: u32) -> Denomination {
+    let api = Api::init(tx_arg_url_match(matches));
+
+    getter::asset_denomination(&api, asset_id)
+        .unwrap_or_else(|e| term.fail_with(e))
+}
+
+/// The recipient and the amount of a payment request URI.
+/// `--amount` is only used if the request doesn't fix an amount.
+fn tx_arg_payment_request_match<'a>(
+    term: &mut term::Term,
+    uri: &str,
+    matches: &ArgMatches<'a>,
+    denomination: &Denomination
+) -> ([u8; 32], u64) {
+    let req = uri::PaymentRequest::parse(uri)
+        .unwrap_or_else(|e| term.fail_with(e));
+    let recipient_enc_key = req.enc_key(global_network_option(matches))
+        .unwrap_or_else(|e| term.fail_with(e));
+    let amount = req.amount.unwrap_or_else(|| tx_arg_amount_match(term, matches, denomination));
+
+    println!("Paying {} to {}", denomination.format_amount(amount), req.address);
+    if let Some(memo) = req.memo {
+        println!("Memo: {}", memo);
+    }
 
-    amount
+    (recipient_enc_key, amount)
 }
 
 fn tx_arg_url_match<'a>(matches: &ArgMatches<'a>) -> Url {
@@ -433,45 +834,122 @@ fn tx_arg_url_match<'a>(matches: &ArgMatches<'a>) -> Url {
 }
 
 fn subcommand_tx<R: Rng>(mut term: term::Term, root_dir: PathBuf, matches: &ArgMatches, rng: &mut R) {
+    // Calls are built with the call indices of the runtime zface was compiled with.
+    if let (_, Some(sub_matches)) = matches.subcommand() {
+        let url = sub_matches.value_of("url").unwrap_or(runtime::LOCAL_URL);
+        if let Err(e) = runtime::ensure_runtime_matches(&root_dir, url) {
+            term.fail_with(e)
+        }
+    }
+
+    // Stream the proving key from the file while proving, for machines which can't hold it in memory.
+    let low_memory = match matches.subcommand() {
+        (_, Some(sub_matches)) => sub_matches.is_present("low-memory"),
+        _ => false,
+    };
+
     let res = match matches.subcommand() {
         ("send", Some(sub_matches)) => {
-            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches);
-            let amount = tx_arg_amount_match(&sub_matches);
+            let denomination = tx_arg_native_denomination_match(&mut term, &sub_matches);
+            let (recipient_enc_key, amount) = match sub_matches.value_of("uri") {
+                Some(uri) => tx_arg_payment_request_match(&mut term, uri, &sub_matches, &denomination),
+                None => (
+                    tx_arg_recipient_address_match(&mut term, &sub_matches),
+                    tx_arg_amount_match(&mut term, &sub_matches, &denomination)
+                ),
+            };
             let url = tx_arg_url_match(&sub_matches);
 
-            confidential_transfer_tx(&mut term, root_dir, &recipient_enc_key[..], amount, url, rng)
+            let allow_new_account = sub_matches.is_present("allow-new-account");
+            let with_return_address = sub_matches.is_present("return-address");
+            let memo = sub_matches.value_of("memo").map(|memo| memo.as_bytes());
+
+            confidential_transfer_tx(&mut term, root_dir, &recipient_enc_key[..], amount, allow_new_account, with_return_address, memo, low_memory, url, rng)
+        },
+        ("schedule-recurring", Some(sub_matches)) => {
+            let recipient_enc_key = tx_arg_recipient_address_match(&mut term, &sub_matches);
+            let denomination = tx_arg_native_denomination_match(&mut term, &sub_matches);
+            let amount = tx_arg_amount_match(&mut term, &sub_matches, &denomination);
+            let url = tx_arg_url_match(&sub_matches);
+            let interval = sub_matches.value_of("interval")
+                .map(|i| i.parse::<u64>().expect("should be parsed to u64 number; qed"))
+                .expect("interval is required; qed");
+            let payments = sub_matches.value_of("payments")
+                .map(|p| p.parse::<u64>().expect("should be parsed to u64 number; qed"))
+                .expect("payments is required; qed");
+            let allow_new_account = sub_matches.is_present("allow-new-account");
+
+            schedule_recurring_tx(&mut term, root_dir, &recipient_enc_key[..], amount, interval, payments, allow_new_account, low_memory, url, rng)
         },
         ("asset-issue", Some(sub_matches)) => {
-            let amount = tx_arg_amount_match(&sub_matches);
+            // A new asset has no metadata yet, so the amount is an integer.
+            let amount = tx_arg_amount_match(&mut term, &sub_matches, &Denomination::units());
+            let url = tx_arg_url_match(&sub_matches);
+
+            asset_issue_tx(&mut term, root_dir, amount, low_memory, url, rng)
+        },
+        ("asset-mint", Some(sub_matches)) => {
             let url = tx_arg_url_match(&sub_matches);
+            let asset_id = wallet_arg_id_match(&sub_matches);
+            let denomination = tx_arg_asset_denomination_match(&mut term, &sub_matches, asset_id);
+            let amount = tx_arg_amount_match(&mut term, &sub_matches, &denomination);
 
-            asset_issue_tx(&mut term, root_dir, amount, url, rng)
+            asset_mint_tx(&mut term, root_dir, amount, asset_id, low_memory, url, rng)
         },
         ("asset-send", Some(sub_matches)) => {
-            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches);
-            let amount = tx_arg_amount_match(&sub_matches);
+            let recipient_enc_key = tx_arg_recipient_address_match(&mut term, &sub_matches);
             let url = tx_arg_url_match(&sub_matches);
             let asset_id = wallet_arg_id_match(&sub_matches);
+            let denomination = tx_arg_asset_denomination_match(&mut term, &sub_matches, asset_id);
+            let amount = tx_arg_amount_match(&mut term, &sub_matches, &denomination);
+            let allow_new_account = sub_matches.is_present("allow-new-account");
 
-            asset_transfer_tx(&mut term, root_dir, &recipient_enc_key[..], amount, asset_id, url, rng)
+            asset_transfer_tx(&mut term, root_dir, &recipient_enc_key[..], amount, asset_id, allow_new_account, low_memory, url, rng)
         },
         ("asset-burn", Some(sub_matches)) => {
             let url = tx_arg_url_match(&sub_matches);
             let asset_id = wallet_arg_id_match(&sub_matches);
-            asset_burn_tx(&mut term, root_dir, asset_id, url, rng)
+            let denomination = tx_arg_asset_denomination_match(&mut term, &sub_matches, asset_id);
+            let amount = tx_arg_amount_match(&mut term, &sub_matches, &denomination);
+
+            asset_burn_tx(&mut term, root_dir, amount, asset_id, low_memory, url, rng)
+        },
+        ("asset-destroy", Some(sub_matches)) => {
+            let url = tx_arg_url_match(&sub_matches);
+            let asset_id = wallet_arg_id_match(&sub_matches);
+            asset_destroy_tx(&mut term, root_dir, asset_id, low_memory, url, rng)
         },
         ("anonymous-send", Some(sub_matches)) => {
-            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches);
-            let amount = tx_arg_amount_match(&sub_matches);
+            let recipient_enc_key = tx_arg_recipient_address_match(&mut term, &sub_matches);
+            let denomination = tx_arg_native_denomination_match(&mut term, &sub_matches);
+            let amount = tx_arg_amount_match(&mut term, &sub_matches, &denomination);
             let url = tx_arg_url_match(&sub_matches);
 
-            anonymous_transfer_tx(&mut term, root_dir, &recipient_enc_key[..], amount, url, rng)
+            let allow_new_account = sub_matches.is_present("allow-new-account");
+            let decoy_max_age = sub_matches.value_of("decoy-max-age")
+                .map(|k| k.parse::<u64>().expect("should be parsed to u64 number; qed"));
+
+            anonymous_transfer_tx(&mut term, root_dir, &recipient_enc_key[..], amount, allow_new_account, decoy_max_age, low_memory, url, rng)
+        },
+        ("anonymous-asset-send", Some(sub_matches)) => {
+            let recipient_enc_key = tx_arg_recipient_address_match(&mut term, &sub_matches);
+            let url = tx_arg_url_match(&sub_matches);
+            let asset_id = wallet_arg_id_match(&sub_matches);
+            let denomination = tx_arg_asset_denomination_match(&mut term, &sub_matches, asset_id);
+            let amount = tx_arg_amount_match(&mut term, &sub_matches, &denomination);
+
+            let allow_new_account = sub_matches.is_present("allow-new-account");
+            let decoy_max_age = sub_matches.value_of("decoy-max-age")
+                .map(|k| k.parse::<u64>().expect("should be parsed to u64 number; qed"));
+
+            anonymous_asset_transfer_tx(&mut term, root_dir, &recipient_enc_key[..], amount, asset_id, allow_new_account, decoy_max_age, low_memory, url, rng)
         },
         ("anonymous-issue", Some(sub_matches)) => {
-            let amount = tx_arg_amount_match(&sub_matches);
+            let denomination = tx_arg_native_denomination_match(&mut term, &sub_matches);
+            let amount = tx_arg_amount_match(&mut term, &sub_matches, &denomination);
             let url = tx_arg_url_match(&sub_matches);
 
-            annonymous_issue_tx(&mut term, root_dir, amount, url, rng)
+            annonymous_issue_tx(&mut term, root_dir, amount, low_memory, url, rng)
         },
         _ => {
             term.error(matches.usage()).unwrap();
@@ -485,12 +963,63 @@ fn subcommand_tx<R: Rng>(mut term: term::Term, root_dir: PathBuf, matches: &ArgM
 fn tx_commands_definition<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name(TX_COMMAND)
         .about("transaction operations")
+        .arg(Arg::with_name("low-memory")
+            .long("low-memory")
+            .help("Read the proving key from the file while proving instead of loading it at once. Slower, but needs much less memory.")
+            .global(true)
+        )
         .subcommand(SubCommand::with_name("send")
             .about("Submit a transaction to zerochain nodes in order to call confidential_transfer function in encrypted-balances module.")
+            .arg(Arg::with_name("uri")
+                .long("uri")
+                .help("A payment request URI, e.g. zerochain:<address>?amount=<amount>. Replaces --recipient-address.")
+                .takes_value(true)
+                .required(false)
+                .conflicts_with("recipient-address")
+            )
+            .arg(Arg::with_name("amount")
+                .short("a")
+                .long("amount")
+                .help("The coin amount for the confidential transfer, e.g. 1.5 or 1.5 ZERO")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("recipient-address")
+                .short("to")
+                .long("recipient-address")
+                .help("Recipient's SS58-encoded address")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("allow-new-account")
+                .long("allow-new-account")
+                .help("Send even if the recipient address has never appeared on chain")
+            )
+            .arg(Arg::with_name("return-address")
+                .long("return-address")
+                .help("Attach your address encrypted to the recipient, so only they learn who paid them")
+            )
+            .arg(Arg::with_name("memo")
+                .long("memo")
+                .help("Attach a memo of up to 32 bytes encrypted to the recipient, e.g. a payment reference")
+                .takes_value(true)
+                .required(false)
+                .conflicts_with("return-address")
+            )
+            .arg(Arg::with_name("url")
+                .short("u")
+                .long("url")
+                .help("Endpoint to connect zerochain nodes")
+                .takes_value(true)
+                .required(false)
+            )
+        )
+        .subcommand(SubCommand::with_name("schedule-recurring")
+            .about("Submit a transaction in order to call schedule function in encrypted-standing-orders module. The first payment is made now.")
             .arg(Arg::with_name("amount")
                 .short("a")
                 .long("amount")
-                .help("The coin amount for the confidential transfer.")
+                .help("The coin amount of each payment, e.g. 1.5 or 1.5 ZERO")
                 .takes_value(true)
                 .required(false)
             )
@@ -501,6 +1030,22 @@ fn tx_commands_definition<'a, 'b>() -> App<'a, 'b> {
                 .takes_value(true)
                 .required(false)
             )
+            .arg(Arg::with_name("interval")
+                .long("interval")
+                .help("The number of epochs between two payments")
+                .takes_value(true)
+                .required(true)
+            )
+            .arg(Arg::with_name("payments")
+                .long("payments")
+                .help("The number of payments including the first one, from 2 to 17, all of which are deducted from the balance now")
+                .takes_value(true)
+                .required(true)
+            )
+            .arg(Arg::with_name("allow-new-account")
+                .long("allow-new-account")
+                .help("Schedule even if the recipient address has never appeared on chain")
+            )
             .arg(Arg::with_name("url")
                 .short("u")
                 .long("url")
@@ -514,7 +1059,7 @@ fn tx_commands_definition<'a, 'b>() -> App<'a, 'b> {
             .arg(Arg::with_name("amount")
                 .short("a")
                 .long("amount")
-                .help("The issued coin amount")
+                .help("The issued amount in the smallest unit, as the asset has no decimals yet")
                 .takes_value(true)
                 .required(false)
             )
@@ -526,12 +1071,36 @@ fn tx_commands_definition<'a, 'b>() -> App<'a, 'b> {
                 .required(false)
             )
         )
+        .subcommand(SubCommand::with_name("asset-mint")
+            .about("Submit a transaction to zerochain nodes in order to call mint function in encrypted-assets module.")
+            .arg(Arg::with_name("amount")
+                .short("a")
+                .long("amount")
+                .help("The minted amount in the decimals of the asset metadata")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("url")
+                .short("u")
+                .long("url")
+                .help("Endpoint to connect zerochain nodes")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("asset-id")
+                .short("i")
+                .long("id")
+                .help("Asset id")
+                .takes_value(true)
+                .required(false)
+            )
+        )
         .subcommand(SubCommand::with_name("asset-send")
             .about("Submit a transaction to zerochain nodes in order to call confidential_transfer function in encrypted-assets module.")
             .arg(Arg::with_name("amount")
                 .short("a")
                 .long("amount")
-                .help("The coin amount for the confidential transfer.")
+                .help("The asset amount for the confidential transfer in the decimals of the asset metadata")
                 .takes_value(true)
                 .required(false)
             )
@@ -542,6 +1111,10 @@ fn tx_commands_definition<'a, 'b>() -> App<'a, 'b> {
                 .takes_value(true)
                 .required(false)
             )
+            .arg(Arg::with_name("allow-new-account")
+                .long("allow-new-account")
+                .help("Send even if the recipient address has never appeared on chain")
+            )
             .arg(Arg::with_name("url")
                 .short("u")
                 .long("url")
@@ -558,6 +1131,30 @@ fn tx_commands_definition<'a, 'b>() -> App<'a, 'b> {
             )
         )
         .subcommand(SubCommand::with_name("asset-burn")
+            .about("Submit a transaction to zerochain in order to call burn function in encrypted-assets module.")
+            .arg(Arg::with_name("amount")
+                .short("a")
+                .long("amount")
+                .help("The burned amount in the decimals of the asset metadata")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("url")
+                .short("u")
+                .long("url")
+                .help("Endpoint to connect zerochain nodes")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("asset-id")
+                .short("i")
+                .long("id")
+                .help("Asset id")
+                .takes_value(true)
+                .required(false)
+            )
+        )
+        .subcommand(SubCommand::with_name("asset-destroy")
             .about("Submit a transaction to zerochain in order to call destroy function in encrypted-assets module.")
             .arg(Arg::with_name("url")
                 .short("u")
@@ -579,7 +1176,7 @@ fn tx_commands_definition<'a, 'b>() -> App<'a, 'b> {
             .arg(Arg::with_name("amount")
                 .short("a")
                 .long("amount")
-                .help("The coin amount for the anonymous transfer.")
+                .help("The coin amount for the anonymous transfer, e.g. 1.5 or 1.5 ZERO")
                 .takes_value(true)
                 .required(false)
             )
@@ -590,6 +1187,57 @@ fn tx_commands_definition<'a, 'b>() -> App<'a, 'b> {
                 .takes_value(true)
                 .required(false)
             )
+            .arg(Arg::with_name("allow-new-account")
+                .long("allow-new-account")
+                .help("Send even if the recipient address has never appeared on chain")
+            )
+            .arg(Arg::with_name("decoy-max-age")
+                .long("decoy-max-age")
+                .help("Only pick decoys among accounts which rolled over within this number of epochs")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("url")
+                .short("u")
+                .long("url")
+                .help("Endpoint to connect zerochain nodes")
+                .takes_value(true)
+                .required(false)
+            )
+        )
+        .subcommand(SubCommand::with_name("anonymous-asset-send")
+            .about("Submit a transaction to zerochain nodes in order to call anonymous_asset_transfer function in anonymous-balances module.")
+            .arg(Arg::with_name("amount")
+                .short("a")
+                .long("amount")
+                .help("The asset amount for the anonymous transfer in the decimals of the asset metadata")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("recipient-address")
+                .short("to")
+                .long("recipient-address")
+                .help("Recipient's SS58-encoded address")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("asset-id")
+                .short("i")
+                .long("id")
+                .help("Asset id")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("allow-new-account")
+                .long("allow-new-account")
+                .help("Send even if the recipient address has never appeared on chain")
+            )
+            .arg(Arg::with_name("decoy-max-age")
+                .long("decoy-max-age")
+                .help("Only pick decoys among accounts which rolled over within this number of epochs")
+                .takes_value(true)
+                .required(false)
+            )
             .arg(Arg::with_name("url")
                 .short("u")
                 .long("url")
@@ -603,7 +1251,7 @@ fn tx_commands_definition<'a, 'b>() -> App<'a, 'b> {
             .arg(Arg::with_name("amount")
                 .short("a")
                 .long("amount")
-                .help("The issued coin amount")
+                .help("The issued coin amount, e.g. 1.5 or 1.5 ZERO")
                 .takes_value(true)
                 .required(false)
             )
@@ -617,6 +1265,144 @@ fn tx_commands_definition<'a, 'b>() -> App<'a, 'b> {
         )
 }
 
+//
+// Invoice Sub Commands
+//
+
+const INVOICE_COMMAND: &'static str = "invoice";
+
+/// Print the contents of a verified invoice.
+fn invoice_print_verified(term: &mut term::Term, verified: &invoice::VerifiedInvoice, matches: &ArgMatches, denomination: &Denomination) {
+    let recipient = EncryptionKeyBytes(verified.recipient).to_ss58check_with_format(global_network_option(matches));
+
+    term.success(&format!("Recipient: {}\n", recipient)).unwrap();
+    term.success(&format!("Amount: {}\n", denomination.format_amount(verified.amount))).unwrap();
+    if !verified.memo.is_empty() {
+        term.success(&format!("Memo: {}\n", String::from_utf8_lossy(&verified.memo))).unwrap();
+    }
+    term.success(&format!("Payable until epoch: {}\n", verified.expiry)).unwrap();
+}
+
+fn subcommand_invoice<R: Rng>(mut term: term::Term, root_dir: PathBuf, matches: &ArgMatches, rng: &mut R) {
+    match matches.subcommand() {
+        ("create", Some(sub_matches)) => {
+            let payer_address = sub_matches.value_of("payer-address").expect("Payer's address is required; qed");
+            let payer_enc_key = EncryptionKeyBytes::from_ss58check_with_format(payer_address, global_network_option(&sub_matches))
+                .unwrap_or_else(|e| term.fail_with(error::KeystoreError::from(e)))
+                .0;
+            let denomination = tx_arg_native_denomination_match(&mut term, &sub_matches);
+            let amount = tx_arg_amount_match(&mut term, &sub_matches, &denomination);
+            let memo = sub_matches.value_of("memo").map(|memo| memo.as_bytes());
+            let payments = sub_matches.value_of("payments")
+                .map(|p| p.parse::<u64>().expect("should be parsed to u64 number; qed"))
+                .expect("payments is required; qed");
+
+            let uri = create_invoice(&mut term, root_dir, &payer_enc_key[..], amount, memo, expiry, rng)
+                .unwrap_or_else(|e| term.fail_with(e));
+            term.success(&format!("{}\n", uri)).unwrap();
+        },
+        ("verify", Some(sub_matches)) => {
+            let uri = sub_matches.value_of("uri").expect("Invoice URI is required; qed");
+            let denomination = tx_arg_native_denomination_match(&mut term, &sub_matches);
+            let api = Api::init(tx_arg_url_match(&sub_matches));
+
+            let verified = verify_invoice(&mut term, root_dir, uri, &api)
+                .unwrap_or_else(|e| term.fail_with(e));
+            invoice_print_verified(&mut term, &verified, &sub_matches, &denomination);
+        },
+        ("pay", Some(sub_matches)) => {
+            // The transfer is built with the call indices of the runtime zface was compiled with.
+            let url = sub_matches.value_of("url").unwrap_or(runtime::LOCAL_URL);
+            if let Err(e) = runtime::ensure_runtime_matches(&root_dir, url) {
+                term.fail_with(e)
+            }
+
+            let uri = sub_matches.value_of("uri").expect("Invoice URI is required; qed");
+            let invoice = invoice::Invoice::parse(uri)
+                .unwrap_or_else(|e| term.fail_with(e));
+            let denomination = tx_arg_native_denomination_match(&mut term, &sub_matches);
+            let current_epoch = getter::current_epoch(&Api::init(tx_arg_url_match(&sub_matches)))
+                .unwrap_or_else(|e| term.fail_with(e));
+            let allow_new_account = sub_matches.is_present("allow-new-account");
+            let low_memory = sub_matches.is_present("low-memory");
+
+            let verified = invoice_pay_tx(&mut term, root_dir, &invoice, current_epoch, allow_new_account, low_memory, tx_arg_url_match(&sub_matches), rng)
+                .unwrap_or_else(|e| term.fail_with(e));
+            invoice_print_verified(&mut term, &verified, &sub_matches, &denomination);
+        },
+        _ => {
+            term.error(matches.usage()).unwrap();
+            ::std::process::exit(1);
+        }
+    }
+}
+
+fn invoice_commands_definition<'a, 'b>() -> App<'a, 'b> {
+    let url_arg = Arg::with_name("url")
+        .short("u")
+        .long("url")
+        .help("Endpoint to connect zerochain nodes")
+        .takes_value(true)
+        .required(false);
+    let uri_arg = Arg::with_name("uri")
+        .help("An invoice URI, e.g. zerochain-invoice:<payload>")
+        .takes_value(true)
+        .required(true);
+
+    SubCommand::with_name(INVOICE_COMMAND)
+        .about("Invoices: payment requests signed by the recipient, which are verified before paying")
+        .subcommand(SubCommand::with_name("create")
+            .about("Create an invoice for a payer, signed by the default account")
+            .arg(Arg::with_name("payer-address")
+                .short("p")
+                .long("payer-address")
+                .help("Payer's SS58-encoded address, which the amount is encrypted to")
+                .takes_value(true)
+                .required(true)
+            )
+            .arg(Arg::with_name("amount")
+                .short("a")
+                .long("amount")
+                .help("The requested coin amount, e.g. 1.5 or 1.5 ZERO")
+                .takes_value(true)
+                .required(true)
+            )
+            .arg(Arg::with_name("memo")
+                .short("m")
+                .long("memo")
+                .help("A memo of up to 32 bytes, e.g. an order number, attached to the payment")
+                .takes_value(true)
+                .required(false)
+            )
+            .arg(Arg::with_name("expiry")
+                .short("e")
+                .long("expiry")
+                .help("The last epoch in which the invoice can be paid")
+                .takes_value(true)
+                .required(true)
+            )
+            .arg(url_arg.clone())
+        )
+        .subcommand(SubCommand::with_name("verify")
+            .about("Verify an invoice for the default account and show what it requests")
+            .arg(uri_arg.clone())
+            .arg(url_arg.clone())
+        )
+        .subcommand(SubCommand::with_name("pay")
+            .about("Verify an invoice for the default account and pay it with a confidential transfer")
+            .arg(uri_arg)
+            .arg(Arg::with_name("allow-new-account")
+                .long("allow-new-account")
+                .help("Pay even if the recipient address has never appeared on chain")
+            )
+            .arg(Arg::with_name("low-memory")
+                .long("low-memory")
+                .help("Read the proving key from the file while proving instead of loading it at once. Slower, but needs much less memory.")
+            )
+            .arg(url_arg)
+        )
+}
+
 //
 // Debug Sub Commands
 //
@@ -639,16 +1425,16 @@ fn subcommand_debug<R: Rng>(mut term: term::Term, matches: &ArgMatches, rng: &mu
         },
         ("send", Some(sub_matches)) => {
             let seed = debug_arg_seed_match(&sub_matches);
-            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches);
-            let amount = tx_arg_amount_match(&sub_matches);
+            let recipient_enc_key = tx_arg_recipient_address_match(&mut term, &sub_matches);
+            let amount = tx_arg_amount_match(&mut term, &sub_matches, &Denomination::units());
             let url = tx_arg_url_match(&sub_matches);
 
             transfer_tx_for_debug(&seed[..], &recipient_enc_key[..], amount, url, rng).unwrap();
         },
         ("anonymous-send", Some(sub_matches)) => {
             let seed = debug_arg_seed_match(&sub_matches);
-            let recipient_enc_key = tx_arg_recipient_address_match(&sub_matches);
-            let amount = tx_arg_amount_match(&sub_matches);
+            let recipient_enc_key = tx_arg_recipient_address_match(&mut term, &sub_matches);
+            let amount = tx_arg_amount_match(&mut term, &sub_matches, &Denomination::units());
             let url = tx_arg_url_match(&sub_matches);
 
             anonymous_transfer_tx_for_debug(&seed[..], &recipient_enc_key[..], amount, url, rng).unwrap();
@@ -676,11 +1462,11 @@ fn subcommand_debug<R: Rng>(mut term: term::Term, matches: &ArgMatches, rng: &mu
             let vk_path = sub_matches.value_of("verification-key-path").unwrap();
 
             let amount_str = sub_matches.value_of("amount").unwrap();
-            let amount: u32 = amount_str.parse().unwrap();
-            let fee = 1 as u32;
+            let amount: u64 = amount_str.parse().unwrap();
+            let fee = 1 as u64;
 
             let balance_str = sub_matches.value_of("balance").unwrap();
-            let balance: u32 = balance_str.parse().unwrap();
+            let balance: u64 = balance_str.parse().unwrap();
 
             println!("Transaction >>");
 
@@ -736,18 +1522,86 @@ fn subcommand_debug<R: Rng>(mut term: term::Term, matches: &ArgMatches, rng: &mu
                 HexDisplay::from(&tx.nonce as &dyn AsBytesRef)
             );
         },
+        ("chain-info", Some(sub_matches)) => {
+            let api = Api::init(tx_arg_url_match(&sub_matches));
+            let info = getter::ChainInfo::get(&api)
+                .expect("Falid to get chain info.");
+            let denomination = tx_arg_native_denomination_match(&mut term, &sub_matches);
+
+            println!("Epoch length: {}", info.epoch_length);
+            println!("Max anonymity set size: {}", info.max_anonymity_set_size);
+            println!("Transaction base fee: {}", denomination.format_amount(info.transaction_base_fee));
+            println!("Anonymous fee: {}", denomination.format_amount(info.anonymous_fee));
+            println!("Confidential VK hash: 0x{}", HexDisplay::from(&info.confidential_vk_hash));
+            println!("Anonymous VK hash: 0x{}", HexDisplay::from(&info.anonymous_vk_hash));
+
+            for (name, pinned, pk_path) in &[
+                ("Confidential", info.confidential_pk_hash, CONF_PK_PATH),
+                ("Anonymous", info.anonymous_pk_hash, ANONY_PK_PATH),
+            ] {
+                match pinned {
+                    Some(hash) => {
+                        println!("{} PK hash: 0x{}", name, HexDisplay::from(hash));
+                        match params::file_hash(pk_path) {
+                            Ok(ref local) if local == hash => {},
+                            Ok(local) => term.error(&format!(
+                                "Warning: {} hashes to 0x{}, which doesn't match the chain.\n",
+                                pk_path, HexDisplay::from(&local)
+                            )).unwrap(),
+                            Err(_) => term.error(&format!("Warning: {} is not found.\n", pk_path)).unwrap(),
+                        }
+                    },
+                    None => println!("{} PK hash: not pinned", name),
+                }
+            }
+
+            let url = sub_matches.value_of("url").unwrap_or(runtime::LOCAL_URL);
+            match getter::chain_weights(url).unwrap_or_else(|e| term.fail_with(e)) {
+                Some(weights) => {
+                    for (name, weight) in &weights {
+                        println!("Weight of {}: {}", name, weight);
+                    }
+                    if weights != getter::local_weights() {
+                        term.error("Warning: the weights of the chain differ from the ones this wallet was built with.\n").unwrap();
+                    }
+                },
+                None => println!("Weights: not exported"),
+            }
+
+            if info.max_anonymity_set_size as usize != ANONIMITY_SIZE {
+                term.error(&format!(
+                    "Warning: this wallet builds anonymous transfers with {} accounts, but the node allows up to {}.\n",
+                    ANONIMITY_SIZE,
+                    info.max_anonymity_set_size
+                )).unwrap();
+            }
+        },
+        ("typegen", Some(sub_matches)) => {
+            let types = serde_json::to_string_pretty(&typegen::types_json(&typegen::registry()))
+                .expect("Serializing JSON values never fails; qed");
+
+            match sub_matches.value_of("output") {
+                Some(path) => {
+                    ::std::fs::write(path, types + "\n").expect("Failed to write the types.");
+                    println!("Wrote the types to {}", path);
+                },
+                None => println!("{}", types),
+            }
+        },
         ("balance", Some(sub_matches)) => {
             println!("Getting encrypted balance...");
 
             let api = Api::init(tx_arg_url_match(&sub_matches));
-            let decr_key_vec = hex::decode(sub_matches.value_of("decryption-key")
-                .expect("Decryption key parameter is required; qed"))
-                .expect("should be decoded to hex.");
-
-            let dec_key = DecryptionKey::read(&mut &decr_key_vec[..])
-                .expect("Reading decryption key faild.");
-
-            let balance_query = getter::BalanceQuery::get_encrypted_balance(&dec_key, api)
+            let url = sub_matches.value_of("url").unwrap_or(runtime::LOCAL_URL);
+            let dec_key = match sub_matches.value_of("decryption-key") {
+                Some(dec_key) => getter::dec_key_from_str(dec_key)
+                    .unwrap_or_else(|e| term.fail_with(e)),
+                None => proofs::ProofGenerationKey::<Bls12>::from_spending_key(&SpendingKey::from_seed(&seed_to_array(ALICESEED)[..]), &PARAMS)
+                    .into_decryption_key()
+                    .expect("Alice's decryption key should be derived from her seed."),
+            };
+
+            let balance_query = getter::BalanceQuery::get_encrypted_balance(&dec_key, api, url)
                 .expect("Falid to get balance data.");
 
             println!("Decrypted balance: {}", balance_query.decrypted_balance);
@@ -835,6 +1689,16 @@ fn debug_commands_definition<'a, 'b>() -> App<'a, 'b> {
                 .required(false)
             )
         )
+        .subcommand(SubCommand::with_name("chain-info")
+            .about("Print the epoch length, anonymity set size, fee and verifying key hashes of a running node")
+            .arg(Arg::with_name("url")
+                .short("u")
+                .long("url")
+                .help("Endpoint to connect zerochain nodes")
+                .takes_value(true)
+                .required(false)
+            )
+        )
         .subcommand(SubCommand::with_name("print-tx")
             .about("Show transaction components for sending it from a browser")
             .arg(Arg::with_name("proving-key-path")
@@ -896,15 +1760,24 @@ fn debug_commands_definition<'a, 'b>() -> App<'a, 'b> {
                 .default_value(DEFAULT_ENCRYPTED_BALANCE)
             )
         )
+        .subcommand(SubCommand::with_name("typegen")
+            .about("Print the types.json of polkadot-js for the custom types of zerochain")
+            .arg(Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .help("The path to write the types to, instead of the standard output")
+                .takes_value(true)
+                .required(false)
+            )
+        )
         .subcommand(SubCommand::with_name("balance")
             .about("Get current balance stored in ConfTransfer module")
             .arg(Arg::with_name("decryption-key")
                 .short("d")
                 .long("decryption-key")
-                .help("Your decription key")
+                .help("Your decryption key, exported by `wallet export-decryption-key`. Alice's by default")
                 .takes_value(true)
-                .required(true)
-                .default_value(ALICEDECRYPTIONKEY)
+                .required(false)
             )
             .arg(Arg::with_name("url")
                 .short("u")
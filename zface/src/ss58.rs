@@ -1,16 +1,107 @@
 use parity_codec::{Encode, Decode};
 use primitives::crypto::{Ss58Codec, Derive, DeriveJunction};
 use proofs::{EncryptionKey, PARAMS};
+use zprimitives::AddressFormat;
+use base58::{ToBase58, FromBase58};
+use blake2_rfc::blake2b::Blake2b;
 use std::{
     convert::TryFrom,
-    io,
+    fmt, io,
 };
 use crate::derive::ExtendedSpendingKey;
 
+const SS58_PREFIX: &[u8] = b"SS58PRE";
+const CHECKSUM_LEN: usize = 2;
+
+/// Errors on decoding an SS58 address for a network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ss58Error {
+    BadBase58,
+    BadLength,
+    InvalidChecksum,
+    UnknownFormat(u8),
+    WrongNetwork {
+        expected: AddressFormat,
+        found: AddressFormat,
+    },
+}
+
+impl fmt::Display for Ss58Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Ss58Error::BadBase58 => write!(f, "The address is not base58 encoded."),
+            Ss58Error::BadLength => write!(f, "The address has a wrong length."),
+            Ss58Error::InvalidChecksum => write!(f, "The checksum of the address is invalid."),
+            Ss58Error::UnknownFormat(prefix) => write!(f, "Unknown address prefix: {}", prefix),
+            Ss58Error::WrongNetwork { expected, found } => write!(
+                f,
+                "The address is a {} address, but the wallet is on {}.",
+                found.as_str(),
+                expected.as_str()
+            ),
+        }
+    }
+}
+
+fn ss58hash(data: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut h = Blake2b::new(64);
+    h.update(SS58_PREFIX);
+    h.update(data);
+
+    let mut checksum = [0u8; CHECKSUM_LEN];
+    checksum.copy_from_slice(&h.finalize().as_bytes()[..CHECKSUM_LEN]);
+    checksum
+}
+
 /// Byte format of encryption key to implement SS58 trait.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode, Default)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode, Default, Debug)]
 pub struct EncryptionKeyBytes(pub [u8; 32]);
 
+impl EncryptionKeyBytes {
+    /// Encode with the prefix of the given network.
+    /// `to_ss58check` is the same as encoding for `AddressFormat::Testnet`.
+    pub fn to_ss58check_with_format(&self, format: AddressFormat) -> String {
+        let mut v = vec![format.prefix()];
+        v.extend_from_slice(&self.0[..]);
+        let checksum = ss58hash(&v[..]);
+        v.extend_from_slice(&checksum[..]);
+        v.to_base58()
+    }
+
+    /// Decode an address of any registered network, returning the network it is encoded for.
+    pub fn from_ss58check_any(s: &str) -> Result<(Self, AddressFormat), Ss58Error> {
+        let d = s.from_base58().map_err(|_| Ss58Error::BadBase58)?;
+        if d.len() != 1 + 32 + CHECKSUM_LEN {
+            return Err(Ss58Error::BadLength);
+        }
+        if d[33..] != ss58hash(&d[..33])[..] {
+            return Err(Ss58Error::InvalidChecksum);
+        }
+        let format = AddressFormat::from_prefix(d[0]).ok_or(Ss58Error::UnknownFormat(d[0]))?;
+
+        let mut res = EncryptionKeyBytes::default();
+        res.0.copy_from_slice(&d[1..33]);
+        Ok((res, format))
+    }
+
+    /// Decode an address, refusing the ones of other networks than `expected`.
+    pub fn from_ss58check_with_format(s: &str, expected: AddressFormat) -> Result<Self, Ss58Error> {
+        let (res, found) = Self::from_ss58check_any(s)?;
+        if found != expected {
+            return Err(Ss58Error::WrongNetwork { expected, found });
+        }
+
+        Ok(res)
+    }
+}
+
+/// Re-encode an address of any registered network for the given one,
+/// e.g. to show the address stored in a keyfile on the network the wallet is used on.
+pub fn reformat(address: &str, format: AddressFormat) -> Result<String, Ss58Error> {
+    let (enc_key, _) = EncryptionKeyBytes::from_ss58check_any(address)?;
+    Ok(enc_key.to_ss58check_with_format(format))
+}
+
 impl AsRef<EncryptionKeyBytes> for EncryptionKeyBytes {
     fn as_ref(&self) -> &EncryptionKeyBytes {
         &self
@@ -59,3 +150,44 @@ impl TryFrom<&ExtendedSpendingKey> for String {
         Ok(enc_key_bytes.to_ss58check())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_testnet_format_matches_ss58codec() {
+        let enc_key = EncryptionKeyBytes([3u8; 32]);
+        let address = enc_key.to_ss58check_with_format(AddressFormat::Testnet);
+
+        assert_eq!(address, enc_key.to_ss58check());
+        assert_eq!(EncryptionKeyBytes::from_ss58check(&address).unwrap(), enc_key);
+    }
+
+    #[test]
+    fn test_refuse_other_network() {
+        let enc_key = EncryptionKeyBytes([5u8; 32]);
+        let mainnet = enc_key.to_ss58check_with_format(AddressFormat::Mainnet);
+        let testnet = enc_key.to_ss58check_with_format(AddressFormat::Testnet);
+        assert_ne!(mainnet, testnet);
+
+        assert_eq!(EncryptionKeyBytes::from_ss58check_with_format(&mainnet, AddressFormat::Mainnet), Ok(enc_key.clone()));
+        assert_eq!(
+            EncryptionKeyBytes::from_ss58check_with_format(&mainnet, AddressFormat::Testnet),
+            Err(Ss58Error::WrongNetwork { expected: AddressFormat::Testnet, found: AddressFormat::Mainnet })
+        );
+        assert_eq!(reformat(&mainnet, AddressFormat::Testnet), Ok(testnet));
+    }
+
+    #[test]
+    fn test_invalid_address() {
+        let mut address = EncryptionKeyBytes([5u8; 32]).to_ss58check_with_format(AddressFormat::Mainnet);
+        assert_eq!(EncryptionKeyBytes::from_ss58check_any("0OIl"), Err(Ss58Error::BadBase58));
+        assert_eq!(EncryptionKeyBytes::from_ss58check_any(&[5u8; 32].to_base58()), Err(Ss58Error::BadLength));
+
+        let last = if address.ends_with('1') { '2' } else { '1' };
+        address.pop();
+        address.push(last);
+        assert_eq!(EncryptionKeyBytes::from_ss58check_any(&address), Err(Ss58Error::InvalidChecksum));
+    }
+}
@@ -3,28 +3,33 @@ use rand::{Rng, Rand};
 use proofs::{
     SpendingKey, ProofGenerationKey, EncryptionKey, PARAMS, elgamal,
     crypto_components::{MultiEncKeys, Confidential, Anonymous},
-    crypto_components::{ProofBuilder, KeyContext, Calls, Submitter},
+    crypto_components::{ProofBuilder, KeyContext, Witness, Calls, Submitter, PrivacyConfing, submit_call},
     constants::{ANONIMITY_SIZE, DECOY_SIZE},
 };
-use pairing::bls12_381::Bls12;
+use pairing::{bls12_381::Bls12, Field};
 use parity_codec::Decode;
 use polkadot_rs::{Api, Url, hexstr_to_vec};
 use scrypto::jubjub::{fs::Fs, FixedGenerators};
 use super::constants::*;
+use super::return_address;
+use super::memo;
 use crate::{
-    error::Result,
+    error::{Result, KeystoreError},
     term::Term,
     wallet::{
         DirOperations,
         commands::{wallet_keystore_dirs, get_default_keyfile_name}
     },
-    getter,
+    getter::{self, RecipientModule},
+    invoice::{Invoice, VerifiedInvoice},
+    utils::{params, runtime},
 };
 
 pub fn asset_issue_tx<R: Rng>(
     term: &mut Term,
     root_dir: PathBuf,
-    amount: u32,
+    amount: u64,
+    low_memory: bool,
     url: Url,
     rng: &mut R,
 ) -> Result<()> {
@@ -41,11 +46,12 @@ pub fn asset_issue_tx<R: Rng>(
     let enc_amount = vec![elgamal::Ciphertext::encrypt(amount, &Fs::rand(rng), &issuer_address, p_g, &PARAMS)];
     let multi_keys = MultiEncKeys::<Bls12, Confidential>::new(issuer_address.clone());
 
+    params::ensure_proving_key_matches(&api, CONF_PK_PATH, "ConfidentialPkHash")?;
     println!("Computing zk proof...");
     subscribe_event(api.clone(), amount);
 
     println!("Start submitting a transaction to Zerochain...");
-    KeyContext::read_from_path(CONF_PK_PATH, CONF_VK_PATH)?
+    read_key_context::<Confidential>(CONF_PK_PATH, CONF_VK_PATH, low_memory)?
         .gen_proof(
             amount,
             0,0,0,0,
@@ -65,12 +71,61 @@ pub fn asset_issue_tx<R: Rng>(
     Ok(())
 }
 
+pub fn asset_mint_tx<R: Rng>(
+    term: &mut Term,
+    root_dir: PathBuf,
+    amount: u64,
+    asset_id: u32,
+    low_memory: bool,
+    url: Url,
+    rng: &mut R,
+) -> Result<()> {
+    // user can enter password first.
+    let password = prompt_password(term)?;
+    println!("Preparing paramters...");
+
+    let api = Api::init(url);
+    let p_g = FixedGenerators::NoteCommitmentRandomness; // 1
+
+    let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
+    let issuer_address = EncryptionKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)?;
+
+    let enc_amount = vec![elgamal::Ciphertext::encrypt(amount, &Fs::rand(rng), &issuer_address, p_g, &PARAMS)];
+    let multi_keys = MultiEncKeys::<Bls12, Confidential>::new(issuer_address.clone());
+
+    params::ensure_proving_key_matches(&api, CONF_PK_PATH, "ConfidentialPkHash")?;
+    println!("Computing zk proof...");
+    subscribe_event(api.clone(), amount);
+
+    println!("Start submitting a transaction to Zerochain...");
+    read_key_context::<Confidential>(CONF_PK_PATH, CONF_VK_PATH, low_memory)?
+        .gen_proof(
+            amount,
+            0,0,0,0,
+            &spending_key,
+            multi_keys,
+            &enc_amount,
+            getter::g_epoch(&api)?,
+            rng,
+            &PARAMS
+        )?
+        .submit(
+            Calls::AssetMint(asset_id),
+            &api,
+            rng
+        );
+
+    Ok(())
+}
+
 pub fn asset_transfer_tx<R: Rng>(
     term: &mut Term,
     root_dir: PathBuf,
     recipient_enc_key: &[u8],
-    amount: u32,
+    amount: u64,
     asset_id: u32,
+    allow_new_account: bool,
+    low_memory: bool,
     url: Url,
     rng: &mut R,
 ) -> Result<()> {
@@ -79,20 +134,23 @@ pub fn asset_transfer_tx<R: Rng>(
 
     println!("Preparing paramters...");
 
+    let node_url = runtime::node_url(&url);
     let api = Api::init(url);
     let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
     let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
         .into_decryption_key()?;
     let fee = getter::fee(&api)?;
 
-    let balance_query = getter::BalanceQuery::get_encrypted_asset(asset_id, &dec_key, api.clone())?;
+    let balance_query = getter::BalanceQuery::get_encrypted_asset(asset_id, &dec_key, api.clone(), &node_url)?;
     let remaining_balance = balance_query.decrypted_balance - amount - fee;
     assert!(balance_query.decrypted_balance >= amount + fee, "Not enough balance you have");
 
     let recipient_account_id = EncryptionKey::<Bls12>::read(&mut &recipient_enc_key[..], &PARAMS)?;
+    ensure_recipient_exists(&api, &node_url, &recipient_account_id, RecipientModule::EncryptedAssets(asset_id), allow_new_account)?;
     let enc_balance = vec![elgamal::Ciphertext::read(&mut &balance_query.encrypted_balance[..], &*PARAMS)?];
     let multi_keys = MultiEncKeys::<Bls12, Confidential>::new(recipient_account_id.clone());
 
+    params::ensure_proving_key_matches(&api, CONF_PK_PATH, "ConfidentialPkHash")?;
     println!("Computing zk proof...");
     if recipient_account_id == EncryptionKey::from_decryption_key(&dec_key, &*PARAMS) {
         subscribe_event(api.clone(), remaining_balance + amount);
@@ -101,7 +159,7 @@ pub fn asset_transfer_tx<R: Rng>(
     }
 
     println!("Start submitting a transaction to Zerochain...");
-    KeyContext::read_from_path(CONF_PK_PATH, CONF_VK_PATH)?
+    read_key_context::<Confidential>(CONF_PK_PATH, CONF_VK_PATH, low_memory)?
         .gen_proof(
             amount,
             fee,
@@ -116,7 +174,7 @@ pub fn asset_transfer_tx<R: Rng>(
             &PARAMS
         )?
         .submit(
-            Calls::AssetTransfer(asset_id),
+            Calls::AssetTransfer(asset_id, None),
             &api,
             rng
         );
@@ -124,10 +182,70 @@ pub fn asset_transfer_tx<R: Rng>(
     Ok(())
 }
 
+/// Burn `amount` of an asset by proving a transfer to the issuer, which reduces the total supply.
 pub fn asset_burn_tx<R: Rng>(
+    term: &mut Term,
+    root_dir: PathBuf,
+    amount: u64,
+    asset_id: u32,
+    low_memory: bool,
+    url: Url,
+    rng: &mut R,
+) -> Result<()> {
+    // user can enter password first.
+    let password = prompt_password(term)?;
+
+    println!("Preparing paramters...");
+
+    let node_url = runtime::node_url(&url);
+    let api = Api::init(url);
+    let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
+    let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
+        .into_decryption_key()?;
+    let fee = getter::fee(&api)?;
+
+    let balance_query = getter::BalanceQuery::get_encrypted_asset(asset_id, &dec_key, api.clone(), &node_url)?;
+    assert!(balance_query.decrypted_balance >= amount + fee, "Not enough balance you have");
+    let remaining_balance = balance_query.decrypted_balance - amount - fee;
+
+    let issuer = getter::asset_issuer(&api, asset_id)?
+        .expect("The asset has no recorded issuer, so it can't be burned.");
+    let enc_balance = vec![elgamal::Ciphertext::read(&mut &balance_query.encrypted_balance[..], &*PARAMS)?];
+    let multi_keys = MultiEncKeys::<Bls12, Confidential>::new(issuer);
+
+    params::ensure_proving_key_matches(&api, CONF_PK_PATH, "ConfidentialPkHash")?;
+    println!("Computing zk proof...");
+    subscribe_event(api.clone(), remaining_balance);
+
+    println!("Start submitting a transaction to Zerochain...");
+    read_key_context::<Confidential>(CONF_PK_PATH, CONF_VK_PATH, low_memory)?
+        .gen_proof(
+            amount,
+            fee,
+            remaining_balance,
+            0,
+            0,
+            &spending_key,
+            multi_keys,
+            &enc_balance,
+            getter::g_epoch(&api)?,
+            rng,
+            &PARAMS
+        )?
+        .submit(
+            Calls::AssetBurn(asset_id),
+            &api,
+            rng
+        );
+
+    Ok(())
+}
+
+pub fn asset_destroy_tx<R: Rng>(
     term: &mut Term,
     root_dir: PathBuf,
     asset_id: u32,
+    low_memory: bool,
     url: Url,
     rng: &mut R,
 ) -> Result<()> {
@@ -135,6 +253,7 @@ pub fn asset_burn_tx<R: Rng>(
     let password = prompt_password(term).expect("Invalid password");
     println!("Preparing paramters...");
 
+    let node_url = runtime::node_url(&url);
     let api = Api::init(url);
     let p_g = FixedGenerators::NoteCommitmentRandomness; // 1
 
@@ -142,7 +261,7 @@ pub fn asset_burn_tx<R: Rng>(
     let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
     let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
         .into_decryption_key()?;
-    let balance_query = getter::BalanceQuery::get_encrypted_asset(asset_id, &dec_key, api.clone())?;
+    let balance_query = getter::BalanceQuery::get_encrypted_asset(asset_id, &dec_key, api.clone(), &node_url)?;
     assert!(balance_query.decrypted_balance != 0, "You don't have the asset. Asset id may be incorrect.");
 
     let amount = 0;
@@ -150,11 +269,12 @@ pub fn asset_burn_tx<R: Rng>(
     let enc_amount =  vec![elgamal::Ciphertext::encrypt(amount, &Fs::rand(rng), &issuer_address, p_g, &PARAMS)];
     let multi_keys = MultiEncKeys::<Bls12, Confidential>::new(issuer_address);
 
+    params::ensure_proving_key_matches(&api, CONF_PK_PATH, "ConfidentialPkHash")?;
     println!("Computing zk proof...");
     subscribe_event(api.clone(), amount);
 
     println!("Start submitting a transaction to Zerochain...");
-    KeyContext::read_from_path(CONF_PK_PATH, CONF_VK_PATH)?
+    read_key_context::<Confidential>(CONF_PK_PATH, CONF_VK_PATH, low_memory)?
         .gen_proof(
             amount,
             0, 0, 0, 0,
@@ -166,7 +286,7 @@ pub fn asset_burn_tx<R: Rng>(
             &PARAMS
         )?
         .submit(
-            Calls::AssetBurn(asset_id),
+            Calls::AssetDestroy(asset_id),
             &api,
             rng
         );
@@ -178,7 +298,11 @@ pub fn confidential_transfer_tx<R: Rng>(
     term: &mut Term,
     root_dir: PathBuf,
     recipient_enc_key: &[u8],
-    amount: u32,
+    amount: u64,
+    allow_new_account: bool,
+    with_return_address: bool,
+    memo: Option<&[u8]>,
+    low_memory: bool,
     url: Url,
     rng: &mut R,
 ) -> Result<()> {
@@ -186,7 +310,106 @@ pub fn confidential_transfer_tx<R: Rng>(
     let password = prompt_password(term)?;
     let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
 
-    inner_confidential_transfer_tx(spending_key, recipient_enc_key, amount, url, rng)?;
+    inner_confidential_transfer_tx(spending_key, recipient_enc_key, amount, allow_new_account, with_return_address, memo, low_memory, url, rng)?;
+
+    Ok(())
+}
+
+/// Pay an invoice from the default account after verifying it as of `current_epoch`.
+/// The memo of the invoice is attached to the transfer.
+pub fn invoice_pay_tx<R: Rng>(
+    term: &mut Term,
+    root_dir: PathBuf,
+    invoice: &Invoice,
+    current_epoch: u64,
+    allow_new_account: bool,
+    low_memory: bool,
+    url: Url,
+    rng: &mut R,
+) -> Result<VerifiedInvoice> {
+    let password = prompt_password(term)?;
+    let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
+    let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
+        .into_decryption_key()?;
+
+    let verified = invoice.verify(&dec_key, current_epoch)?;
+    let memo = if verified.memo.is_empty() { None } else { Some(&verified.memo[..]) };
+
+    inner_confidential_transfer_tx(spending_key, &verified.recipient[..], verified.amount, allow_new_account, false, memo, low_memory, url, rng)?;
+
+    Ok(verified)
+}
+
+/// Schedule a standing order paying `amount` now and `payments - 1` more times every `interval` epochs.
+/// The proofs of the later payments are made against the balance left by the payments before them,
+/// and the payments are moved out of the balance into the escrow of the order.
+pub fn schedule_recurring_tx<R: Rng>(
+    term: &mut Term,
+    root_dir: PathBuf,
+    recipient_enc_key: &[u8],
+    amount: u64,
+    interval: u64,
+    payments: u64,
+    allow_new_account: bool,
+    low_memory: bool,
+    url: Url,
+    rng: &mut R,
+) -> Result<()> {
+    assert!(payments >= 2, "A standing order has at least two payments");
+
+    let password = prompt_password(term)?;
+    let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
+    println!("Preparing paramters...");
+
+    let node_url = runtime::node_url(&url);
+    let api = Api::init(url);
+    let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
+        .into_decryption_key()?;
+    let fee = getter::fee(&api)?;
+
+    let balance_query = getter::BalanceQuery::get_encrypted_balance(&dec_key, api.clone(), &node_url)?;
+    let total = amount.checked_mul(payments).and_then(|t| t.checked_add(fee));
+    assert!(total.map_or(false, |t| balance_query.decrypted_balance >= t), "Not enough balance you have");
+    let remaining_balance = balance_query.decrypted_balance - amount * payments - fee;
+
+    let recipient_account_id = EncryptionKey::<Bls12>::read(&mut &recipient_enc_key[..], &PARAMS)?;
+    ensure_recipient_exists(&api, &node_url, &recipient_account_id, RecipientModule::EncryptedBalances, allow_new_account)?;
+    let mut enc_balance = elgamal::Ciphertext::read(&mut &balance_query.encrypted_balance[..], &*PARAMS)?;
+
+    params::ensure_proving_key_matches(&api, CONF_PK_PATH, "ConfidentialPkHash")?;
+    println!("Computing zk proofs...");
+    subscribe_event(api.clone(), remaining_balance);
+
+    // Only the first payment pays the fee, and each payment is proven against the balance left by the ones before it.
+    let key_context = read_key_context::<Confidential>(CONF_PK_PATH, CONF_VK_PATH, low_memory)?;
+    let g_epoch = getter::g_epoch(&api)?;
+    let mut witnesses = Vec::with_capacity(payments as usize);
+    for i in 0..payments {
+        let witness = key_context.build_witness(
+            amount,
+            if i == 0 { fee } else { 0 },
+            balance_query.decrypted_balance - amount * (i + 1) - fee,
+            0,
+            0,
+            &spending_key,
+            MultiEncKeys::<Bls12, Confidential>::new(recipient_account_id.clone()),
+            &[enc_balance.clone()],
+            g_epoch.clone(),
+            rng,
+            &PARAMS
+        )?;
+        enc_balance = witness.remaining_enc_balance(&PARAMS);
+        witnesses.push(witness);
+    }
+
+    println!("Start submitting a transaction to Zerochain...");
+    let mut txs = prove_all_in_current_epoch(&key_context, witnesses, &api, rng)?;
+    let payments = txs.split_off(1).iter().map(|tx| tx.standing_order_payment()).collect();
+    txs[0].submit(
+        Calls::StandingOrderSchedule(payments, interval),
+        &api,
+        rng
+    );
 
     Ok(())
 }
@@ -194,7 +417,8 @@ pub fn confidential_transfer_tx<R: Rng>(
 pub fn annonymous_issue_tx<R: Rng>(
     term: &mut Term,
     root_dir: PathBuf,
-    amount: u32,
+    amount: u64,
+    low_memory: bool,
     url: Url,
     rng: &mut R,
 ) -> Result<()> {
@@ -211,11 +435,12 @@ pub fn annonymous_issue_tx<R: Rng>(
     let enc_amount = vec![elgamal::Ciphertext::encrypt(amount, &Fs::rand(rng), &issuer_address, p_g, &PARAMS)];
     let multi_keys = MultiEncKeys::<Bls12, Confidential>::new(issuer_address.clone());
 
+    params::ensure_proving_key_matches(&api, CONF_PK_PATH, "ConfidentialPkHash")?;
     println!("Computing zk proof...");
     subscribe_event(api.clone(), amount);
 
     println!("Start submitting a transaction to Zerochain...");
-    KeyContext::read_from_path(CONF_PK_PATH, CONF_VK_PATH)?
+    read_key_context::<Confidential>(CONF_PK_PATH, CONF_VK_PATH, low_memory)?
         .gen_proof(
             amount,
             0,0,0,0,
@@ -239,7 +464,32 @@ pub fn anonymous_transfer_tx<R: Rng>(
     term: &mut Term,
     root_dir: PathBuf,
     recipient_enc_key: &[u8],
-    amount: u32,
+    amount: u64,
+    allow_new_account: bool,
+    decoy_max_age: Option<u64>,
+    low_memory: bool,
+    url: Url,
+    rng: &mut R,
+) -> Result<()> {
+    // user can enter password first.
+    let password = prompt_password(term)?;
+    let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
+
+    inner_anonymous_transfer_tx(spending_key, recipient_enc_key, amount, None, allow_new_account, decoy_max_age, low_memory, url, rng)?;
+
+    Ok(())
+}
+
+/// Transfer an asset of the encrypted assets module anonymously, with decoys from the anonymity set.
+pub fn anonymous_asset_transfer_tx<R: Rng>(
+    term: &mut Term,
+    root_dir: PathBuf,
+    recipient_enc_key: &[u8],
+    amount: u64,
+    asset_id: u32,
+    allow_new_account: bool,
+    decoy_max_age: Option<u64>,
+    low_memory: bool,
     url: Url,
     rng: &mut R,
 ) -> Result<()> {
@@ -247,7 +497,35 @@ pub fn anonymous_transfer_tx<R: Rng>(
     let password = prompt_password(term)?;
     let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
 
-    inner_anonymous_transfer_tx(spending_key, recipient_enc_key, amount, url, rng)?;
+    inner_anonymous_transfer_tx(spending_key, recipient_enc_key, amount, Some(asset_id), allow_new_account, decoy_max_age, low_memory, url, rng)?;
+
+    Ok(())
+}
+
+/// Claim the faucet of a testnet for the wallet's account.
+/// The claim reveals the recipient anyway, so it is signed by the spending key without re-randomizing it,
+/// which lets the faucet rate-limit the signer along with the recipient.
+pub fn faucet_claim_tx<R: Rng>(
+    term: &mut Term,
+    root_dir: PathBuf,
+    url: Url,
+    rng: &mut R,
+) -> Result<()> {
+    let password = prompt_password(term)?;
+    let spending_key = spending_key_from_keystore(root_dir, &password[..])?;
+
+    let enc_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
+        .into_encryption_key(&PARAMS)?;
+    let recipient = zprimitives::EncKey::from_slice(&enc_key.into_bytes()?[..]);
+
+    let api = Api::init(url);
+    submit_call(
+        &spending_key,
+        Fs::zero(),
+        zerochain_runtime::Call::ZkFaucet(zerochain_runtime::ZkFaucetCall::claim(recipient)),
+        &api,
+        rng
+    )?;
 
     Ok(())
 }
@@ -255,12 +533,12 @@ pub fn anonymous_transfer_tx<R: Rng>(
 pub fn transfer_tx_for_debug<R: Rng>(
     seed: &[u8],
     recipient_enc_key: &[u8],
-    amount: u32,
+    amount: u64,
     url: Url,
     rng: &mut R,
 ) -> Result<()> {
     let spending_key = SpendingKey::from_seed(seed);
-    inner_confidential_transfer_tx(spending_key, recipient_enc_key, amount, url, rng)?;
+    inner_confidential_transfer_tx(spending_key, recipient_enc_key, amount, true, false, None, false, url, rng)?;
 
     Ok(())
 }
@@ -268,38 +546,47 @@ pub fn transfer_tx_for_debug<R: Rng>(
 pub fn anonymous_transfer_tx_for_debug<R: Rng>(
     seed: &[u8],
     recipient_enc_key: &[u8],
-    amount: u32,
+    amount: u64,
     url: Url,
     rng: &mut R,
 ) -> Result<()> {
     let spending_key = SpendingKey::from_seed(seed);
-    inner_anonymous_transfer_tx(spending_key, recipient_enc_key, amount, url, rng)?;
+    inner_anonymous_transfer_tx(spending_key, recipient_enc_key, amount, None, true, None, false, url, rng)?;
 
     Ok(())
 }
 
+/// If `with_return_address` is set, the sender's address is sealed to the recipient and attached to the transfer.
+/// If `memo` is set, it is sealed to the recipient and attached to the transfer.
 fn inner_confidential_transfer_tx<R: Rng>(
     spending_key: SpendingKey::<Bls12>,
     recipient_enc_key: &[u8],
-    amount: u32,
+    amount: u64,
+    allow_new_account: bool,
+    with_return_address: bool,
+    memo: Option<&[u8]>,
+    low_memory: bool,
     url: Url,
     rng: &mut R
 ) -> Result<()> {
     println!("Preparing paramters...");
 
+    let node_url = runtime::node_url(&url);
     let api = Api::init(url);
     let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
         .into_decryption_key()?;
     let fee = getter::fee(&api)?;
 
-    let balance_query = getter::BalanceQuery::get_encrypted_balance(&dec_key, api.clone())?;
+    let balance_query = getter::BalanceQuery::get_encrypted_balance(&dec_key, api.clone(), &node_url)?;
     let remaining_balance = balance_query.decrypted_balance - amount - fee;
     assert!(balance_query.decrypted_balance >= amount + fee, "Not enough balance you have");
 
     let recipient_account_id = EncryptionKey::<Bls12>::read(&mut &recipient_enc_key[..], &PARAMS)?;
+    ensure_recipient_exists(&api, &node_url, &recipient_account_id, RecipientModule::EncryptedBalances, allow_new_account)?;
     let multi_keys = MultiEncKeys::<Bls12, Confidential>::new(recipient_account_id.clone());
     let enc_balance = vec![elgamal::Ciphertext::read(&mut &balance_query.encrypted_balance[..], &*PARAMS)?];
 
+    params::ensure_proving_key_matches(&api, CONF_PK_PATH, "ConfidentialPkHash")?;
     println!("Computing zk proof...");
     if recipient_account_id == EncryptionKey::from_decryption_key(&dec_key, &*PARAMS) {
         subscribe_event(api.clone(), remaining_balance + amount);
@@ -307,23 +594,36 @@ fn inner_confidential_transfer_tx<R: Rng>(
         subscribe_event(api.clone(), remaining_balance);
     }
 
+    let calls = if with_return_address {
+        let sender = EncryptionKey::from_decryption_key(&dec_key, &*PARAMS);
+        Calls::BalanceTransferWithReturnAddress(return_address::seal(&sender, &recipient_account_id, rng)?)
+    } else {
+        let memo = match memo {
+            Some(plaintext) => Some(memo::seal(plaintext, &recipient_account_id, rng)?),
+            None => None,
+        };
+        Calls::BalanceTransfer(memo)
+    };
+
+    let key_context = read_key_context::<Confidential>(CONF_PK_PATH, CONF_VK_PATH, low_memory)?;
+    let witness = key_context.build_witness(
+        amount,
+        fee,
+        remaining_balance,
+        0,
+        0,
+        &spending_key,
+        multi_keys,
+        &enc_balance,
+        getter::g_epoch(&api)?,
+        rng,
+        &PARAMS
+    )?;
+
     println!("Start submitting a transaction to Zerochain...");
-    KeyContext::read_from_path(CONF_PK_PATH, CONF_VK_PATH)?
-        .gen_proof(
-            amount,
-            fee,
-            remaining_balance,
-            0,
-            0,
-            &spending_key,
-            multi_keys,
-            &enc_balance,
-            getter::g_epoch(&api)?,
-            rng,
-            &PARAMS
-        )?
+    prove_in_current_epoch(&key_context, witness, &api, rng)?
         .submit(
-            Calls::BalanceTransfer,
+            calls,
             &api,
             rng
         );
@@ -331,23 +631,36 @@ fn inner_confidential_transfer_tx<R: Rng>(
     Ok(())
 }
 
+/// If `decoy_max_age` is set, decoys are only picked among accounts which rolled over
+/// within that many epochs, so that they look as active as the sender and the recipient.
+/// If `asset_id` is set, the balances of the asset in the encrypted assets module are transferred.
 fn inner_anonymous_transfer_tx<R: Rng>(
     spending_key: SpendingKey::<Bls12>,
     recipient_enc_key: &[u8],
-    amount: u32,
+    amount: u64,
+    asset_id: Option<u32>,
+    allow_new_account: bool,
+    decoy_max_age: Option<u64>,
+    low_memory: bool,
     url: Url,
     rng: &mut R
 ) -> Result<()> {
     println!("Preparing paramters...");
 
+    let node_url = runtime::node_url(&url);
     let api = Api::init(url);
     let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&spending_key, &PARAMS)
         .into_decryption_key()?;
     let enc_key_sender = EncryptionKey::<Bls12>::from_decryption_key(&dec_key, &PARAMS);
 
-    let balance_query = getter::BalanceQuery::get_anonymous_balance(&dec_key, api.clone())?;
-    let remaining_balance = balance_query.decrypted_balance - amount;
-    assert!(balance_query.decrypted_balance >= amount, "Not enough balance you have");
+    let balance_query = match asset_id {
+        Some(id) => getter::BalanceQuery::get_encrypted_asset(id, &dec_key, api.clone(), &node_url)?,
+        None => getter::BalanceQuery::get_anonymous_balance(&dec_key, api.clone(), &node_url)?,
+    };
+    // The fee is the same for every anonymous transfer, as the runtime inputs it to the verification.
+    let fee = getter::anonymous_fee(&api)?;
+    assert!(balance_query.decrypted_balance >= amount + fee, "Not enough balance you have");
+    let remaining_balance = balance_query.decrypted_balance - amount - fee;
 
     let s_index: usize = rng.gen_range(0, DECOY_SIZE-1);
     let mut t_index: usize;
@@ -359,7 +672,15 @@ fn inner_anonymous_transfer_tx<R: Rng>(
     }
 
     let recipient_account_id = EncryptionKey::<Bls12>::read(&mut &recipient_enc_key[..], &PARAMS)?;
-    let decoys = getter::get_enc_keys(&api, rng)?;
+    let recipient_module = match asset_id {
+        Some(id) => RecipientModule::EncryptedAssets(id),
+        None => RecipientModule::AnonymousBalances,
+    };
+    ensure_recipient_exists(&api, &node_url, &recipient_account_id, recipient_module, allow_new_account)?;
+    let decoys = match decoy_max_age {
+        Some(k_epochs) => getter::get_active_enc_keys(&api, k_epochs, rng)?,
+        None => getter::get_enc_keys(&api, rng)?,
+    };
     assert_eq!(decoys.len(), DECOY_SIZE);
     let multi_keys = MultiEncKeys::<Bls12, Anonymous>::new(recipient_account_id.clone(), decoys.clone());
 
@@ -375,8 +696,12 @@ fn inner_anonymous_transfer_tx<R: Rng>(
             j += 1;
         }
     }
-    let enc_balances = getter::get_enc_balances(&api, &enc_keys[..])?;
+    let enc_balances = match asset_id {
+        Some(id) => getter::get_enc_asset_balances(&api, &node_url, id, &enc_keys[..])?,
+        None => getter::get_enc_balances(&api, &node_url, &enc_keys[..])?,
+    };
 
+    params::ensure_proving_key_matches(&api, ANONY_PK_PATH, "AnonymousPkHash")?;
     println!("Computing zk proof...");
     if recipient_account_id == EncryptionKey::from_decryption_key(&dec_key, &*PARAMS) {
         subscribe_event(api.clone(), remaining_balance + amount);
@@ -384,23 +709,25 @@ fn inner_anonymous_transfer_tx<R: Rng>(
         subscribe_event(api.clone(), remaining_balance);
     }
 
+    let key_context = read_key_context::<Anonymous>(ANONY_PK_PATH, ANONY_VK_PATH, low_memory)?;
+    let witness = key_context.build_witness(
+        amount,
+        fee,
+        remaining_balance,
+        s_index,
+        t_index,
+        &spending_key,
+        multi_keys,
+        &enc_balances[..],
+        getter::g_epoch(&api)?,
+        rng,
+        &PARAMS
+    )?;
+
     println!("Start submitting a transaction to Zerochain...");
-    KeyContext::read_from_path(ANONY_PK_PATH, ANONY_VK_PATH)?
-        .gen_proof(
-            amount,
-            0,
-            remaining_balance,
-            s_index,
-            t_index,
-            &spending_key,
-            multi_keys,
-            &enc_balances[..],
-            getter::g_epoch(&api)?,
-            rng,
-            &PARAMS
-        )?
+    prove_in_current_epoch(&key_context, witness, &api, rng)?
         .submit(
-            Calls::AnonymousTransfer,
+            asset_id.map_or(Calls::AnonymousTransfer, Calls::AnonymousAssetTransfer),
             &api,
             rng
         );
@@ -408,6 +735,85 @@ fn inner_anonymous_transfer_tx<R: Rng>(
     Ok(())
 }
 
+/// Prove the witness, and prove it again in the new epoch if the epoch passed while proving,
+/// since the chain rejects a proof against the `g_epoch` of a past epoch.
+/// Only `g_epoch` and the nonce are rebuilt; if the balance changed by a rollover as well,
+/// the proof fails and the transfer has to be made again.
+fn prove_in_current_epoch<PC: PrivacyConfing, R: Rng>(
+    key_context: &KeyContext<Bls12, PC>,
+    witness: Witness<Bls12, PC>,
+    api: &Api,
+    rng: &mut R,
+) -> Result<<KeyContext<Bls12, PC> as ProofBuilder<Bls12, PC>>::Submitter>
+where
+    KeyContext<Bls12, PC>: ProofBuilder<Bls12, PC>,
+{
+    let mut txs = prove_all_in_current_epoch(key_context, vec![witness], api, rng)?;
+    Ok(txs.remove(0))
+}
+
+/// Prove the witnesses, which share the nonce of the epoch, proving all of them again if the epoch passed meanwhile.
+fn prove_all_in_current_epoch<PC: PrivacyConfing, R: Rng>(
+    key_context: &KeyContext<Bls12, PC>,
+    mut witnesses: Vec<Witness<Bls12, PC>>,
+    api: &Api,
+    rng: &mut R,
+) -> Result<Vec<<KeyContext<Bls12, PC> as ProofBuilder<Bls12, PC>>::Submitter>>
+where
+    KeyContext<Bls12, PC>: ProofBuilder<Bls12, PC>,
+{
+    for _ in 0..MAX_PROOF_ATTEMPTS {
+        let mut txs = Vec::with_capacity(witnesses.len());
+        for witness in &witnesses {
+            txs.push(key_context.prove(witness, rng, &PARAMS)?);
+        }
+
+        let g_epoch = getter::g_epoch(api)?;
+        if witnesses.iter().all(|w| g_epoch == *w.g_epoch()) {
+            return Ok(txs);
+        }
+        println!("The epoch passed while proving. Proving again in the new epoch...");
+        for witness in &mut witnesses {
+            witness.set_epoch(g_epoch.clone(), &PARAMS);
+        }
+    }
+
+    Err(KeystoreError::EpochPassed)
+}
+
+/// Read the proving and verifying keys.
+/// In low memory mode, the proving key is read from the file while proving instead of being loaded at once.
+fn read_key_context<PC: PrivacyConfing>(
+    pk_path: &str,
+    vk_path: &str,
+    low_memory: bool
+) -> Result<KeyContext<Bls12, PC>>
+where
+    KeyContext<Bls12, PC>: ProofBuilder<Bls12, PC>,
+{
+    if low_memory {
+        Ok(KeyContext::read_streamed(pk_path, vk_path)?)
+    } else {
+        Ok(KeyContext::read_from_path(pk_path, vk_path)?)
+    }
+}
+
+/// Refuse to send to an address which has never appeared on chain unless explicitly allowed,
+/// because funds sent to a mistyped address are locked forever.
+fn ensure_recipient_exists(
+    api: &Api,
+    url: &str,
+    recipient: &EncryptionKey<Bls12>,
+    module: RecipientModule,
+    allow_new_account: bool,
+) -> Result<()> {
+    if allow_new_account || getter::enc_key_exists(api, url, recipient, module)? {
+        return Ok(());
+    }
+
+    Err(KeystoreError::UnknownRecipient)
+}
+
 pub fn spending_key_from_keystore(
     root_dir: PathBuf,
     password: &[u8],
@@ -430,7 +836,7 @@ pub fn prompt_password(term: &mut Term) -> Result<Vec<u8>> {
     Ok(password)
 }
 
-pub fn subscribe_event(api: Api, remaining_balance: u32) {
+pub fn subscribe_event(api: Api, remaining_balance: u64) {
     use std::sync::mpsc::channel;
     use std::thread;
     use zerochain_runtime::Event;
@@ -460,11 +866,13 @@ pub fn subscribe_event(api: Api, remaining_balance: u32) {
                                             _zkproof,
                                             _enc_key_sender, _enc_key_recipient,
                                             _amount_sender, _amount_recipient,
-                                            _fee_sender,  _randomness, _enc_balances, _sig_vk
+                                            _fee_sender,  _randomness, _enc_balances, _sig_vk,
+                                            _epoch
                                         ) => println!("Submitting transaction is completed successfully. \n Remaining balance is {}", remaining_balance),
                                         encrypted_balances::RawEvent::InvalidZkProof() => {
                                             println!("Invalid zk proof.");
                                         }
+                                        _ => {},
                                     }
                                 },
                                 Event::encrypted_assets(enc_assets) => {
@@ -472,6 +880,9 @@ pub fn subscribe_event(api: Api, remaining_balance: u32) {
                                         encrypted_assets::RawEvent::Issued(
                                             asset_id, _address, _total
                                         ) => println!("Submitting transaction is completed successfully. \nThe total issued coin is {}. \nThe asset id is {}.", remaining_balance, asset_id),
+                                        encrypted_assets::RawEvent::IssuedWithHiddenIssuer(
+                                            asset_id, _issuer_commitment, _issuer_hint, _total
+                                        ) => println!("Submitting transaction is completed successfully. \nThe total issued coin is {}. \nThe asset id is {}.", remaining_balance, asset_id),
                                         encrypted_assets::RawEvent::ConfidentialAssetTransferred(
                                             asset_id, _zkproof,
                                             _enc_key_sender, _enc_key_recipient,
@@ -481,6 +892,7 @@ pub fn subscribe_event(api: Api, remaining_balance: u32) {
                                         encrypted_assets::RawEvent::Destroyed(asset_id, _address, _balance, _pending_transfer)
                                             => println!("destroyed coins. \nThe asset id is {}.", asset_id),
                                         encrypted_assets::RawEvent::InvalidZkProof() => println!("Invalid zk proof."),
+                                        _ => {},
                                     }
                                 },
                                 Event::anonymous_balances(annoy_be) => {
@@ -491,7 +903,11 @@ pub fn subscribe_event(api: Api, remaining_balance: u32) {
                                         anonymous_balances::RawEvent::AnonymousTransfer(
                                             _proof, _enc_keys, _left_ciphertexts, _right_ciphertext, _sig_vk,
                                         ) => println!("Submitting transaction is completed successfully. \n Remaining balance is {}", remaining_balance),
+                                        anonymous_balances::RawEvent::AnonymousAssetTransfer(
+                                            _asset_id, _proof, _enc_keys, _left_ciphertexts, _right_ciphertext, _sig_vk,
+                                        ) => println!("Submitting transaction is completed successfully. \n Remaining balance is {}", remaining_balance),
                                         anonymous_balances::RawEvent::InvalidZkProof() => println!("Invalid zk proof."),
+                                        _ => {},
                                     }
                                 }
                                 _ => {},
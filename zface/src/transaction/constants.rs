@@ -2,3 +2,6 @@ pub const CONF_PK_PATH: &'static str = "zface/params/conf_pk.dat";
 pub const CONF_VK_PATH: &'static str = "zface/params/conf_vk.dat";
 pub const ANONY_PK_PATH: &'static str = "zface/params/anony_pk.dat";
 pub const ANONY_VK_PATH: &'static str = "zface/params/anony_vk.dat";
+
+/// The number of times a transfer is proven before giving up, if the epoch passes while proving.
+pub const MAX_PROOF_ATTEMPTS: usize = 3;
@@ -0,0 +1,112 @@
+//! Memos: a short plaintext, e.g. a payment reference, sealed to the recipient of a transfer.
+//!
+//! The plaintext is zero-padded and encrypted with AES-128-CTR under a key agreed by Diffie-Hellman
+//! between an ephemeral key and the recipient's encryption key, as return addresses are.
+//! No tag is appended, as the memo is an argument of the call signed by the sender.
+
+use rand::{Rng, Rand};
+use parity_crypto as crypto;
+use blake2_rfc::blake2b::Blake2b;
+use proofs::{EncryptionKey, DecryptionKey, PARAMS};
+use pairing::bls12_381::Bls12;
+use scrypto::jubjub::{fs::Fs, edwards, FixedGenerators, Unknown, PrimeOrder};
+use zprimitives::{Memo, memo::MEMO_SIZE};
+use crate::error::{KeystoreError, Result};
+
+/// The maximum length of the plaintext of a memo: the memo less the ephemeral key.
+pub const MAX_MEMO_PLAINTEXT_LENGTH: usize = MEMO_SIZE - 32;
+
+const MEMO_PERSONALIZATION: &[u8; 16] = b"zerochainMemoKey";
+
+/// Seal `plaintext` to `recipient`.
+pub fn seal<R: Rng>(
+    plaintext: &[u8],
+    recipient: &EncryptionKey<Bls12>,
+    rng: &mut R,
+) -> Result<Memo> {
+    if plaintext.len() > MAX_MEMO_PLAINTEXT_LENGTH {
+        return Err(KeystoreError::InvalidMemo);
+    }
+
+    let esk = Fs::rand(rng);
+    let epk = PARAMS.generator(FixedGenerators::NoteCommitmentRandomness).mul(esk, &*PARAMS);
+    let shared = recipient.0.mul(esk, &*PARAMS);
+
+    let mut epk_bytes = [0u8; 32];
+    epk.write(&mut &mut epk_bytes[..])?;
+    let enc_key = derive_key(&shared, &epk_bytes)?;
+
+    let mut padded = [0u8; MAX_MEMO_PLAINTEXT_LENGTH];
+    padded[..plaintext.len()].copy_from_slice(plaintext);
+    let mut ciphertext = [0u8; MAX_MEMO_PLAINTEXT_LENGTH];
+    crypto::aes::encrypt_128_ctr(&enc_key, &[0u8; 16], &padded[..], &mut ciphertext)
+        .map_err(crypto::Error::from)?;
+
+    let mut sealed = [0u8; MEMO_SIZE];
+    sealed[..32].copy_from_slice(&epk_bytes[..]);
+    sealed[32..].copy_from_slice(&ciphertext[..]);
+
+    Ok(Memo::from_slice(&sealed[..]))
+}
+
+/// Open a memo with the recipient's decryption key. The padding is stripped from the plaintext.
+/// A memo sealed to another key opens to garbage, so memos should only be opened for transfers to this key.
+pub fn open(dec_key: &DecryptionKey<Bls12>, memo: &Memo) -> Result<Vec<u8>> {
+    let (epk_bytes, ciphertext) = memo.as_bytes().split_at(32);
+
+    let epk = edwards::Point::<Bls12, Unknown>::read(&mut &epk_bytes[..], &*PARAMS)?
+        .as_prime_order(&*PARAMS)
+        .ok_or(KeystoreError::InvalidMemo)?;
+    let shared = epk.mul(dec_key.0, &*PARAMS);
+    let enc_key = derive_key(&shared, epk_bytes)?;
+
+    let mut plain = vec![0u8; MAX_MEMO_PLAINTEXT_LENGTH];
+    crypto::aes::decrypt_128_ctr(&enc_key, &[0u8; 16], ciphertext, &mut plain[..])
+        .map_err(crypto::Error::from)?;
+
+    let len = plain.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+    plain.truncate(len);
+
+    Ok(plain)
+}
+
+fn derive_key(
+    shared: &edwards::Point<Bls12, PrimeOrder>,
+    epk_bytes: &[u8],
+) -> Result<[u8; 16]> {
+    let mut shared_bytes = [0u8; 32];
+    shared.write(&mut &mut shared_bytes[..])?;
+
+    let mut h = Blake2b::with_params(16, &[], &[], MEMO_PERSONALIZATION);
+    h.update(&shared_bytes[..]);
+    h.update(epk_bytes);
+
+    let mut enc_key = [0u8; 16];
+    enc_key.copy_from_slice(h.finalize().as_bytes());
+
+    Ok(enc_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, XorShiftRng};
+    use proofs::{SpendingKey, ProofGenerationKey};
+
+    #[test]
+    fn test_seal_and_open() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let recipient_dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&SpendingKey::from_seed(b"Bob                             "), &PARAMS)
+            .into_decryption_key().unwrap();
+        let recipient = EncryptionKey::from_decryption_key(&recipient_dec_key, &PARAMS);
+
+        let memo = seal(b"invoice-2019-0042", &recipient, rng).unwrap();
+        assert_eq!(open(&recipient_dec_key, &memo).unwrap(), b"invoice-2019-0042".to_vec());
+
+        let other_dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&SpendingKey::from_seed(b"Charlie                         "), &PARAMS)
+            .into_decryption_key().unwrap();
+        assert!(open(&other_dec_key, &memo).unwrap() != b"invoice-2019-0042".to_vec());
+
+        assert!(seal(&[1u8; MAX_MEMO_PLAINTEXT_LENGTH + 1], &recipient, rng).is_err());
+    }
+}
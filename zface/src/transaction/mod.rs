@@ -1,4 +1,6 @@
 pub mod commands;
 pub mod constants;
+pub mod return_address;
+pub mod memo;
 pub use self::commands::*;
 pub use self::constants::*;
@@ -0,0 +1,128 @@
+//! Return addresses: the sender's encryption key sealed to the recipient,
+//! so that only the recipient learns who paid them and can reply.
+//!
+//! The key is encrypted with AES-128-CTR under a key agreed by Diffie-Hellman
+//! between an ephemeral key and the recipient's encryption key, and authenticated with a keyed Blake2b.
+
+use rand::{Rng, Rand};
+use parity_crypto as crypto;
+use blake2_rfc::blake2b::{Blake2b, blake2b};
+use proofs::{EncryptionKey, DecryptionKey, PARAMS};
+use pairing::bls12_381::Bls12;
+use scrypto::jubjub::{fs::Fs, edwards, FixedGenerators, Unknown, PrimeOrder};
+use crate::error::{KeystoreError, Result};
+
+/// The length of a sealed return address: the ephemeral key, the encrypted key and the tag.
+pub const RETURN_ADDRESS_LENGTH: usize = 96;
+
+const RETURN_ADDRESS_PERSONALIZATION: &[u8; 16] = b"zerochainRetAddr";
+
+/// Seal `sender` to `recipient`.
+pub fn seal<R: Rng>(
+    sender: &EncryptionKey<Bls12>,
+    recipient: &EncryptionKey<Bls12>,
+    rng: &mut R,
+) -> Result<Vec<u8>> {
+    let esk = Fs::rand(rng);
+    let epk = PARAMS.generator(FixedGenerators::NoteCommitmentRandomness).mul(esk, &*PARAMS);
+    let shared = recipient.0.mul(esk, &*PARAMS);
+
+    let mut epk_bytes = [0u8; 32];
+    epk.write(&mut &mut epk_bytes[..])?;
+    let (enc_key, mac_key) = derive_keys(&shared, &epk_bytes)?;
+
+    let mut ciphertext = [0u8; 32];
+    crypto::aes::encrypt_128_ctr(&enc_key, &[0u8; 16], &sender.into_bytes()?[..], &mut ciphertext)
+        .map_err(crypto::Error::from)?;
+
+    let mut sealed = Vec::with_capacity(RETURN_ADDRESS_LENGTH);
+    sealed.extend_from_slice(&epk_bytes[..]);
+    sealed.extend_from_slice(&ciphertext[..]);
+    sealed.extend_from_slice(&tag(&mac_key, &epk_bytes, &ciphertext)[..]);
+
+    Ok(sealed)
+}
+
+/// Open a sealed return address with the recipient's decryption key.
+/// Fails if it was not sealed to this key or has been tampered with.
+pub fn open(dec_key: &DecryptionKey<Bls12>, sealed: &[u8]) -> Result<EncryptionKey<Bls12>> {
+    if sealed.len() != RETURN_ADDRESS_LENGTH {
+        return Err(KeystoreError::InvalidReturnAddress);
+    }
+    let (epk_bytes, rest) = sealed.split_at(32);
+    let (ciphertext, received_tag) = rest.split_at(32);
+
+    let epk = edwards::Point::<Bls12, Unknown>::read(&mut &epk_bytes[..], &*PARAMS)?
+        .as_prime_order(&*PARAMS)
+        .ok_or(KeystoreError::InvalidReturnAddress)?;
+    let shared = epk.mul(dec_key.0, &*PARAMS);
+    let (enc_key, mac_key) = derive_keys(&shared, epk_bytes)?;
+
+    if !crypto::is_equal(&tag(&mac_key, epk_bytes, ciphertext)[..], received_tag) {
+        return Err(KeystoreError::InvalidReturnAddress);
+    }
+
+    let mut plain = [0u8; 32];
+    crypto::aes::decrypt_128_ctr(&enc_key, &[0u8; 16], ciphertext, &mut plain)
+        .map_err(crypto::Error::from)?;
+
+    EncryptionKey::read(&mut &plain[..], &*PARAMS)
+        .map_err(|_| KeystoreError::InvalidReturnAddress)
+}
+
+fn derive_keys(
+    shared: &edwards::Point<Bls12, PrimeOrder>,
+    epk_bytes: &[u8],
+) -> Result<([u8; 16], [u8; 32])> {
+    let mut shared_bytes = [0u8; 32];
+    shared.write(&mut &mut shared_bytes[..])?;
+
+    let mut h = Blake2b::with_params(48, &[], &[], RETURN_ADDRESS_PERSONALIZATION);
+    h.update(&shared_bytes[..]);
+    h.update(epk_bytes);
+    let okm = h.finalize();
+
+    let mut enc_key = [0u8; 16];
+    let mut mac_key = [0u8; 32];
+    enc_key.copy_from_slice(&okm.as_bytes()[..16]);
+    mac_key.copy_from_slice(&okm.as_bytes()[16..]);
+
+    Ok((enc_key, mac_key))
+}
+
+fn tag(mac_key: &[u8], epk_bytes: &[u8], ciphertext: &[u8]) -> [u8; 32] {
+    let mut msg = epk_bytes.to_vec();
+    msg.extend_from_slice(ciphertext);
+
+    let mut res = [0u8; 32];
+    res.copy_from_slice(blake2b(32, mac_key, &msg[..]).as_bytes());
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, XorShiftRng};
+    use proofs::{SpendingKey, ProofGenerationKey};
+
+    #[test]
+    fn test_seal_and_open() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let sender = EncryptionKey::from_seed(b"Alice                           ", &PARAMS).unwrap();
+        let recipient_dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&SpendingKey::from_seed(b"Bob                             "), &PARAMS)
+            .into_decryption_key().unwrap();
+        let recipient = EncryptionKey::from_decryption_key(&recipient_dec_key, &PARAMS);
+
+        let sealed = seal(&sender, &recipient, rng).unwrap();
+        assert_eq!(sealed.len(), RETURN_ADDRESS_LENGTH);
+        assert_eq!(open(&recipient_dec_key, &sealed).unwrap(), sender);
+
+        let other_dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&SpendingKey::from_seed(b"Charlie                         "), &PARAMS)
+            .into_decryption_key().unwrap();
+        assert!(open(&other_dec_key, &sealed).is_err());
+
+        let mut tampered = sealed.clone();
+        tampered[40] ^= 1;
+        assert!(open(&recipient_dec_key, &tampered).is_err());
+    }
+}
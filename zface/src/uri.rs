@@ -0,0 +1,158 @@
+//! Payment requests encoded as `zerochain:<ss58 address>?amount=<amount>&memo=<memo>` URIs,
+//! which can be passed around as text or as a QR code.
+
+use zprimitives::AddressFormat;
+use crate::error::{Result, KeystoreError};
+use crate::ss58::EncryptionKeyBytes;
+
+/// The scheme of payment request URIs.
+pub const URI_SCHEME: &str = "zerochain";
+
+/// A request to pay to an address, optionally with a fixed amount and a memo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentRequest {
+    /// SS58-encoded encryption key of the recipient.
+    pub address: String,
+    pub amount: Option<u64>,
+    pub memo: Option<String>,
+}
+
+impl PaymentRequest {
+    pub fn new(address: String, amount: Option<u64>, memo: Option<String>) -> Self {
+        PaymentRequest { address, amount, memo }
+    }
+
+    /// Parse a `zerochain:` URI. Unknown query parameters are rejected
+    /// so that a request isn't paid while ignoring part of it.
+    pub fn parse(uri: &str) -> Result<Self> {
+        let rest = uri.trim()
+            .splitn(2, ':')
+            .collect::<Vec<_>>();
+        if rest.len() != 2 || rest[0] != URI_SCHEME {
+            return Err(KeystoreError::InvalidUri(format!("The scheme must be `{}:`.", URI_SCHEME)));
+        }
+
+        let mut iter = rest[1].splitn(2, '?');
+        let address = iter.next().unwrap_or_default().to_string();
+        EncryptionKeyBytes::from_ss58check_any(&address)
+            .map_err(|_| KeystoreError::InvalidUri("The address is not properly encoded with SS58Check.".to_string()))?;
+
+        let mut req = PaymentRequest::new(address, None, None);
+        let query = match iter.next() {
+            Some(q) => q,
+            None => return Ok(req),
+        };
+
+        for param in query.split('&').filter(|p| !p.is_empty()) {
+            let mut kv = param.splitn(2, '=');
+            let key = kv.next().unwrap_or_default();
+            let value = percent_decode(kv.next().unwrap_or_default())?;
+
+            match key {
+                "amount" if req.amount.is_none() => {
+                    let amount = value.parse::<u64>()
+                        .map_err(|_| KeystoreError::InvalidUri(format!("Invalid amount: {}", value)))?;
+                    req.amount = Some(amount);
+                },
+                "memo" if req.memo.is_none() => req.memo = Some(value),
+                _ => return Err(KeystoreError::InvalidUri(format!("Unknown or duplicated parameter: {}", key))),
+            }
+        }
+
+        Ok(req)
+    }
+
+    /// The recipient's encryption key. Requests for an address of another network than `format` are refused.
+    pub fn enc_key(&self, format: AddressFormat) -> Result<[u8; 32]> {
+        let enc_key = EncryptionKeyBytes::from_ss58check_with_format(&self.address, format)?;
+
+        Ok(enc_key.0)
+    }
+
+    pub fn to_uri(&self) -> String {
+        let mut params = vec![];
+        if let Some(amount) = self.amount {
+            params.push(format!("amount={}", amount));
+        }
+        if let Some(ref memo) = self.memo {
+            params.push(format!("memo={}", percent_encode(memo)));
+        }
+
+        if params.is_empty() {
+            format!("{}:{}", URI_SCHEME, self.address)
+        } else {
+            format!("{}:{}?{}", URI_SCHEME, self.address, params.join("&"))
+        }
+    }
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> Result<String> {
+    let invalid = || KeystoreError::InvalidUri(format!("Invalid percent-encoding: {}", s));
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = s.get(i + 1..i + 3).ok_or_else(invalid)?;
+                decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+                i += 3;
+            },
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            },
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use primitives::crypto::Ss58Codec;
+
+    fn address() -> String {
+        EncryptionKeyBytes([7u8; 32]).to_ss58check()
+    }
+
+    #[test]
+    fn test_payment_request_roundtrip() {
+        let req = PaymentRequest::new(address(), Some(100), Some("coffee & cake".to_string()));
+        let uri = req.to_uri();
+
+        assert_eq!(uri, format!("zerochain:{}?amount=100&memo=coffee%20%26%20cake", address()));
+        assert_eq!(PaymentRequest::parse(&uri).unwrap(), req);
+        assert_eq!(req.enc_key(AddressFormat::Testnet).unwrap(), [7u8; 32]);
+        assert_matches!(req.enc_key(AddressFormat::Mainnet), Err(KeystoreError::InvalidAddress(_)));
+
+        let plain = PaymentRequest::new(address(), None, None);
+        assert_eq!(plain.to_uri(), format!("zerochain:{}", address()));
+        assert_eq!(PaymentRequest::parse(&plain.to_uri()).unwrap(), plain);
+    }
+
+    #[test]
+    fn test_payment_request_rejects_invalid_uris() {
+        assert_matches!(PaymentRequest::parse(&format!("bitcoin:{}", address())), Err(KeystoreError::InvalidUri(_)));
+        assert_matches!(PaymentRequest::parse("zerochain:notanaddress"), Err(KeystoreError::InvalidUri(_)));
+        assert_matches!(PaymentRequest::parse(&format!("zerochain:{}?amount=-1", address())), Err(KeystoreError::InvalidUri(_)));
+        assert_matches!(PaymentRequest::parse(&format!("zerochain:{}?amount=1&amount=2", address())), Err(KeystoreError::InvalidUri(_)));
+        assert_matches!(PaymentRequest::parse(&format!("zerochain:{}?fee=1", address())), Err(KeystoreError::InvalidUri(_)));
+        assert_matches!(PaymentRequest::parse(&format!("zerochain:{}?memo=%zz", address())), Err(KeystoreError::InvalidUri(_)));
+    }
+}
@@ -0,0 +1,128 @@
+//! A one-shot local devnet: the zk params, a dev node and the funded dev accounts.
+//!
+//! The dev chain spec funds the encrypted balances of Alice, Bob and Charlie in its genesis,
+//! so bringing a devnet up only takes making sure the params exist, starting or reaching a node
+//! and printing the keys of those accounts.
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use rand::Rng;
+use polkadot_rs::Api;
+use pairing::bls12_381::Bls12;
+use proofs::{confidential_setup, anonymous_setup, ProofBuilder, SpendingKey, ProofGenerationKey, PARAMS};
+use crate::error::Result;
+use crate::transaction::{CONF_PK_PATH, CONF_VK_PATH, ANONY_PK_PATH, ANONY_VK_PATH};
+use super::{getter, PrintKeys};
+
+/// The accounts funded in the genesis of the dev chain, with the seeds of their keys.
+pub const DEV_ACCOUNTS: [(&str, &[u8; 32]); 3] = [
+    ("Alice", b"Alice                           "),
+    ("Bob", b"Bob                             "),
+    ("Charlie", b"Charlie                         "),
+];
+
+/// How long to wait for a dev node to serve RPC.
+const NODE_STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub struct DevAccount {
+    pub name: &'static str,
+    pub keys: PrintKeys,
+    pub balance: u64,
+}
+
+/// Perform the setups whose params are missing and return whether any was performed.
+/// These params are only fit for a devnet, since nobody attests their toxic waste was destroyed.
+pub fn ensure_params<R: Rng>(rng: &mut R) -> Result<bool> {
+    let mut performed = false;
+
+    if !Path::new(CONF_PK_PATH).exists() || !Path::new(CONF_VK_PATH).exists() {
+        println!("Performing setup for confidential transfer...");
+        confidential_setup(rng).write_to_file(CONF_PK_PATH, CONF_VK_PATH)?;
+        performed = true;
+    }
+
+    if !Path::new(ANONY_PK_PATH).exists() || !Path::new(ANONY_VK_PATH).exists() {
+        println!("Performing setup for anonymous transfer...");
+        anonymous_setup(rng).write_to_file(ANONY_PK_PATH, ANONY_VK_PATH)?;
+        performed = true;
+    }
+
+    Ok(performed)
+}
+
+/// Start `node --dev`, logging to `log_path`.
+/// With `purge`, the dev chain is purged first so that its genesis stores the current verifying keys.
+pub fn launch_node(node: &Path, log_path: &Path, purge: bool) -> Result<Child> {
+    if purge {
+        let status = Command::new(node).args(&["purge-chain", "--dev", "-y"]).status()?;
+        if !status.success() {
+            return Err(io::Error::new(io::ErrorKind::Other, "Failed to purge the dev chain.").into());
+        }
+    }
+
+    let log = File::create(log_path)?;
+    let child = Command::new(node)
+        .arg("--dev")
+        .stdout(Stdio::from(log.try_clone()?))
+        .stderr(Stdio::from(log))
+        .spawn()?;
+
+    Ok(child)
+}
+
+/// Wait until the node serves the latest block height.
+pub fn wait_for_node(api: &Api) -> Result<()> {
+    let start = Instant::now();
+    loop {
+        match api.get_latest_height() {
+            Ok(_) => return Ok(()),
+            Err(e) => if start.elapsed() > NODE_STARTUP_TIMEOUT {
+                return Err(e.into());
+            },
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+/// The keys of the dev accounts and their balances on the node.
+pub fn dev_accounts(api: &Api, url: &str) -> Result<Vec<DevAccount>> {
+    DEV_ACCOUNTS.iter().map(|(name, seed)| {
+        let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&SpendingKey::from_seed(&seed[..]), &PARAMS)
+            .into_decryption_key()?;
+        let balance = getter::BalanceQuery::get_encrypted_balance(&dec_key, api.clone(), url)?.decrypted_balance;
+
+        Ok(DevAccount {
+            name,
+            keys: PrintKeys::generate_from_seed(**seed),
+            balance,
+        })
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ALICESEED, BOBSEED};
+
+    #[test]
+    fn test_dev_accounts_match_config() {
+        assert_eq!(hex::encode(&DEV_ACCOUNTS[0].1[..]), ALICESEED);
+        assert_eq!(hex::encode(&DEV_ACCOUNTS[1].1[..]), BOBSEED);
+    }
+
+    #[test]
+    fn test_printed_decryption_key_is_importable() {
+        let alice = PrintKeys::generate_from_seed(*DEV_ACCOUNTS[0].1);
+        let dec_key = ProofGenerationKey::<Bls12>::from_spending_key(&SpendingKey::from_seed(&DEV_ACCOUNTS[0].1[..]), &PARAMS)
+            .into_decryption_key()
+            .unwrap();
+
+        let imported = getter::dec_key_from_str(&alice.decryption_key).unwrap();
+        assert!(imported.0 == dec_key.0);
+        assert_eq!(getter::dec_key_to_string(&dec_key).unwrap(), alice.decryption_key);
+    }
+}
@@ -3,70 +3,124 @@
 use keys::EncryptionKey as zEncryptionKey;
 use rand::Rng;
 use pairing::bls12_381::Bls12;
-use zprimitives::{EncKey, GEpoch};
+use zprimitives::{EncKey, GEpoch, GEpochVersion, Ciphertext};
 use zcrypto::elgamal as zelgamal;
 use polkadot_rs::{Api, hexstr_to_vec, hexstr_to_u64};
-use parity_codec::Encode;
+use parity_codec::{Encode, Decode};
 use proofs::{PARAMS, elgamal};
 use zprimitives::PARAMS as ZPARAMS;
 use zjubjub::curve::FixedGenerators as zFixedGenerators;
 use proofs::{EncryptionKey, DecryptionKey, constants::DECOY_SIZE};
 use zpairing::bls12_381::Bls12 as zBls12;
 use scrypto::jubjub::{edwards, PrimeOrder};
-use crate::error::Result;
-use std::convert::TryFrom;
-
-pub fn get_enc_balances(api: &Api, enc_keys: &[EncryptionKey<Bls12>]) -> Result<Vec<elgamal::Ciphertext<Bls12>>> {
+use crate::error::{Result, KeystoreError};
+use crate::denomination::Denomination;
+use super::runtime;
+use zerochain_runtime::Runtime;
+use zk_system::pool::PoolMap;
+use serde_json::json;
+use primitives::blake2_256;
+use std::{io, convert::TryFrom, fs::File, io::{BufReader, BufWriter}, collections::BTreeMap};
+
+/// The cache of the decryption table of balances, built on the first balance query.
+const DECRYPTION_TABLE_PATH: &str = "zface/params/decryption_table.dat";
+
+/// The number of baby steps of the decryption table, with which balances below 2^32 are decrypted.
+const DECRYPTION_TABLE_SIZE: u32 = 1 << 16;
+
+pub fn get_enc_balances(api: &Api, url: &str, enc_keys: &[EncryptionKey<Bls12>]) -> Result<Vec<elgamal::Ciphertext<Bls12>>> {
     let mut acc = vec![];
     for e in enc_keys {
-        let mut encrypted_balance_str = api.get_storage(
-            "AnonymousBalances",
-            "EncryptedBalance",
-            Some(EncKey::try_from(no_std_e(e)?)?.encode())
-        )?;
+        let enc_key = EncKey::try_from(no_std_e(e)?)?;
+        let balance = get_pool_ciphertext::<anonymous_balances::EncryptedBalance<Runtime>>(url, &enc_key)?;
+        let pending_transfer = get_pending_transfer(api, "AnonymousBalances", enc_key.encode(), |epoch| {
+            get_pool_ciphertext::<anonymous_balances::PendingTransfer<Runtime>>(url, &(enc_key, epoch))
+        })?;
+        acc.push(total_balance(balance, pending_transfer)?);
+    }
 
-        let mut pending_transfer_str = api.get_storage(
-            "AnonymousBalances",
-            "PendingTransfer",
-            Some(EncKey::try_from(no_std_e(e)?)?.encode())
-        )?;
+    Ok(acc)
+}
 
-        let mut ciphertext = None;
-        let mut p_ciphertext = None;
+/// Get the balances of an asset of the encrypted assets module, for an anonymous transfer of the asset.
+pub fn get_enc_asset_balances(api: &Api, url: &str, asset_id: u32, enc_keys: &[EncryptionKey<Bls12>]) -> Result<Vec<elgamal::Ciphertext<Bls12>>> {
+    let mut acc = vec![];
+    for e in enc_keys {
+        let enc_key = EncKey::try_from(no_std_e(e)?)?;
+        let balance = get_pool_ciphertext::<encrypted_assets::EncryptedBalance<Runtime>>(url, &(asset_id, enc_key))?;
+        let pending_transfer = get_pending_transfer(api, "EncryptedAssets", (asset_id, enc_key).encode(), |epoch| {
+            get_pool_ciphertext::<encrypted_assets::PendingTransfer<Runtime>>(url, &(asset_id, enc_key, epoch))
+        })?;
+        acc.push(total_balance(balance, pending_transfer)?);
+    }
 
-        if encrypted_balance_str.as_str() != "0x00" {
-            // TODO: remove unnecessary prefix. If it returns `0x00`, it will be panic.
-            for _ in 0..4 {
-                encrypted_balance_str.remove(2);
-            }
+    Ok(acc)
+}
 
-            let encrypted_balance = hexstr_to_vec(encrypted_balance_str.clone());
-            ciphertext = Some(zelgamal::Ciphertext::<zBls12>::read(&mut &encrypted_balance[..], &ZPARAMS)?);
-        }
+/// The sum of a balance and the pending transfers of an account.
+fn total_balance(
+    balance: Option<zelgamal::Ciphertext<zBls12>>,
+    pending_transfer: Option<zelgamal::Ciphertext<zBls12>>,
+) -> Result<elgamal::Ciphertext<Bls12>> {
+    let zero = zelgamal::Ciphertext::<zBls12>::zero();
+    let enc_total = balance.unwrap_or(zero.clone()).add(&pending_transfer.unwrap_or(zero), &*ZPARAMS);
+    let mut buf = vec![0u8; 64];
+    enc_total.write(&mut buf[..])?;
+
+    Ok(elgamal::Ciphertext::<Bls12>::read(&mut &buf[..], &*PARAMS)?)
+}
 
-        if pending_transfer_str.as_str() != "0x00" {
-            // TODO: remove unnecessary prefix. If it returns `0x00`, it will be panic.
-            for _ in 0..4 {
-                pending_transfer_str.remove(2);
-            }
+/// Get an entry of a map in the child trie of a shielded pool, where the balances are stored.
+fn get_pool_storage<M: PoolMap>(url: &str, key: &M::Key) -> Result<Option<M::Value>> {
+    let params = json!([
+        format!("0x{}", hex::encode(M::POOL.child_storage_key())),
+        format!("0x{}", hex::encode(M::key_for(key))),
+    ]);
+
+    match runtime::rpc_request_with_params(url, "state_getChildStorage", params)?.as_str() {
+        Some(value_str) => {
+            let value = M::Value::decode(&mut &hexstr_to_vec(value_str.to_string())[..])
+                .ok_or(io::Error::from(io::ErrorKind::InvalidData))?;
+            Ok(Some(value))
+        },
+        None => Ok(None),
+    }
+}
 
-            let pending_transfer = hexstr_to_vec(pending_transfer_str.clone());
-            p_ciphertext = Some(zelgamal::Ciphertext::<zBls12>::read(&mut &pending_transfer[..], &ZPARAMS)?);
-        }
+/// Get a balance or a pending transfer bucket in the child trie of a shielded pool.
+fn get_pool_ciphertext<M: PoolMap<Value=Ciphertext>>(url: &str, key: &M::Key) -> Result<Option<zelgamal::Ciphertext<zBls12>>> {
+    match get_pool_storage::<M>(url, key)? {
+        Some(ciphertext) => Ok(Some(zelgamal::Ciphertext::<zBls12>::try_from(ciphertext)?)),
+        None => Ok(None),
+    }
+}
 
-        let zero = zelgamal::Ciphertext::<zBls12>::zero();
-        let enc_total = ciphertext.unwrap_or(zero.clone()).add(&p_ciphertext.unwrap_or(zero), &*ZPARAMS);
-        let mut buf = vec![0u8; 64];
-        enc_total.write(&mut buf[..])?;
-        let tmp = elgamal::Ciphertext::<Bls12>::read(&mut &buf[..], &*PARAMS)?;
-        acc.push(tmp);
+/// Get the sum of the pending transfers of an account stored under `storage_key` in `PendingEpochs`,
+/// which are bucketed by the epoch in which they were received. `get_bucket` gets the bucket of an epoch.
+fn get_pending_transfer<F>(api: &Api, module: &str, storage_key: Vec<u8>, get_bucket: F) -> Result<Option<zelgamal::Ciphertext<zBls12>>>
+    where F: Fn(u64) -> Result<Option<zelgamal::Ciphertext<zBls12>>>
+{
+    let epochs_str = api.get_storage(module, "PendingEpochs", Some(storage_key))?;
+    let epochs = <Vec<u64>>::decode(&mut &hexstr_to_vec(epochs_str)[..])
+        .unwrap_or_default();
+
+    let mut acc: Option<zelgamal::Ciphertext<zBls12>> = None;
+    for epoch in epochs {
+        let p = match get_bucket(epoch)? {
+            Some(p) => p,
+            None => continue,
+        };
+        acc = Some(match acc {
+            Some(a) => a.add(&p, &*ZPARAMS),
+            None => p,
+        });
     }
 
     Ok(acc)
 }
 
 pub struct BalanceQuery {
-    pub decrypted_balance: u32,
+    pub decrypted_balance: u64,
     pub encrypted_balance: Vec<u8>, // total of encrypted balance and pending transfer
     pub encrypted_balance_str: String,
     pub pending_transfer_str: String,
@@ -75,94 +129,90 @@ pub struct BalanceQuery {
 // Temporary code.
 impl BalanceQuery {
     /// Get encrypted and decrypted balance for the decryption key
-    pub fn get_encrypted_balance(dec_key: &DecryptionKey<Bls12>, api: Api) -> Result<Self> {
+    pub fn get_encrypted_balance(dec_key: &DecryptionKey<Bls12>, api: Api, url: &str) -> Result<Self> {
         let encryption_key = zEncryptionKey::from_decryption_key(&no_std(&dec_key)?, &*ZPARAMS);
         let account_id = EncKey::try_from(encryption_key)?;
 
-        let encrypted_balance_str = api.get_storage(
-            "EncryptedBalances",
-            "EncryptedBalance",
-            Some(account_id.encode())
-        )?;
+        let encrypted_balance = get_pool_ciphertext::<encrypted_balances::EncryptedBalance<Runtime>>(url, &account_id)?;
+        let pending_transfer = get_pending_transfer(&api, "EncryptedBalances", account_id.encode(), |epoch| {
+            get_pool_ciphertext::<encrypted_balances::PendingTransfer<Runtime>>(url, &(account_id, epoch))
+        })?;
 
-        let pending_transfer_str = api.get_storage(
-            "EncryptedBalances",
-            "PendingTransfer",
-            Some(account_id.encode())
-        )?;
-
-        Self::get_balance_from_decryption_key(encrypted_balance_str, pending_transfer_str, dec_key)
+        Self::get_balance_from_decryption_key(encrypted_balance, pending_transfer, dec_key)
     }
 
-    pub fn get_encrypted_asset(asset_id: u32, dec_key: &DecryptionKey<Bls12>, api: Api) -> Result<Self> {
+    pub fn get_encrypted_asset(asset_id: u32, dec_key: &DecryptionKey<Bls12>, api: Api, url: &str) -> Result<Self> {
         let encryption_key = zEncryptionKey::from_decryption_key(&no_std(&dec_key)?, &*ZPARAMS);
         let account_id = EncKey::try_from(encryption_key)?;
 
-        let encrypted_asset_str = api.get_storage(
-            "EncryptedAssets",
-            "EncryptedBalance",
-            Some((asset_id, account_id).encode())
-        )?;
+        let encrypted_asset = get_pool_ciphertext::<encrypted_assets::EncryptedBalance<Runtime>>(url, &(asset_id, account_id))?;
+        let pending_transfer = get_pending_transfer(&api, "EncryptedAssets", (asset_id, account_id).encode(), |epoch| {
+            get_pool_ciphertext::<encrypted_assets::PendingTransfer<Runtime>>(url, &(asset_id, account_id, epoch))
+        })?;
 
-        let pending_transfer_str = api.get_storage(
-            "EncryptedAssets",
-            "PendingTransfer",
-            Some((asset_id, account_id).encode())
-        )?;
-
-        Self::get_balance_from_decryption_key(encrypted_asset_str, pending_transfer_str, dec_key)
+        Self::get_balance_from_decryption_key(encrypted_asset, pending_transfer, dec_key)
     }
 
-    pub fn get_anonymous_balance(dec_key: &DecryptionKey<Bls12>, api: Api) -> Result<Self> {
+    pub fn get_anonymous_balance(dec_key: &DecryptionKey<Bls12>, api: Api, url: &str) -> Result<Self> {
         let encryption_key = zEncryptionKey::from_decryption_key(&no_std(&dec_key)?, &*ZPARAMS);
         let account_id = EncKey::try_from(encryption_key)?;
 
-        let encrypted_balance_str = api.get_storage(
-            "AnonymousBalances",
-            "EncryptedBalance",
-            Some(account_id.encode())
-        )?;
+        let (encrypted_balance, pending_transfer) = get_anonymous_account(&api, url, &account_id)?;
 
-        let pending_transfer_str = api.get_storage(
-            "AnonymousBalances",
-            "PendingTransfer",
-            Some(account_id.encode())
-        )?;
-
-        Self::get_balance_from_decryption_key(encrypted_balance_str, pending_transfer_str, dec_key)
+        Self::get_balance_from_decryption_key(encrypted_balance, pending_transfer, dec_key)
     }
 
-    fn get_balance_from_decryption_key(
-        mut encrypted_balance_str: String,
-        mut pending_transfer_str: String,
-        dec_key: &DecryptionKey<Bls12>
+    /// Get the anonymous balance while also querying the accounts of `decoys` other members of
+    /// the `EncKeySet`. All the accounts are fetched in a random order with the same requests,
+    /// so the node serving them can't tell which one belongs to the wallet owner.
+    pub fn get_anonymous_balance_with_decoys<R: Rng>(
+        dec_key: &DecryptionKey<Bls12>,
+        api: Api,
+        url: &str,
+        decoys: usize,
+        rng: &mut R
     ) -> Result<Self> {
-        let p_g = zFixedGenerators::Diversifier; // 1
-        let mut ciphertext = None;
-        let mut p_ciphertext = None;
-
-        if encrypted_balance_str.as_str() != "0x00" {
-            // TODO: remove unnecessary prefix. If it returns `0x00`, it will be panic.
-            for _ in 0..4 {
-                encrypted_balance_str.remove(2);
-            }
+        let encryption_key = zEncryptionKey::from_decryption_key(&no_std(&dec_key)?, &*ZPARAMS);
+        let account_id = EncKey::try_from(encryption_key)?;
 
-            let encrypted_balance = hexstr_to_vec(encrypted_balance_str.clone());
-            ciphertext = Some(zelgamal::Ciphertext::<zBls12>::read(&mut &encrypted_balance[..], &ZPARAMS)?);
-        }
-        if pending_transfer_str.as_str() != "0x00" {
-            // TODO: remove unnecessary prefix. If it returns `0x00`, it will be panic.
-            for _ in 0..4 {
-                pending_transfer_str.remove(2);
+        let mut candidates = vec![];
+        for e in enc_key_set(&api)? {
+            let enc_key = EncKey::try_from(no_std_e(&e)?)?;
+            if enc_key != account_id && !candidates.contains(&enc_key) {
+                candidates.push(enc_key);
             }
+        }
+        rng.shuffle(&mut candidates[..]);
+        candidates.truncate(decoys);
+        candidates.push(account_id);
+        rng.shuffle(&mut candidates[..]);
+
+        let mut own = None;
+        for enc_key in candidates {
+            let account = get_anonymous_account(&api, url, &enc_key)?;
 
-            let pending_transfer = hexstr_to_vec(pending_transfer_str.clone());
-            p_ciphertext = Some(zelgamal::Ciphertext::<zBls12>::read(&mut &pending_transfer[..], &ZPARAMS)?);
+            if enc_key == account_id {
+                own = Some(account);
+            }
         }
 
+        let (encrypted_balance, pending_transfer) = own
+            .expect("the own account is always queried; qed");
+        Self::get_balance_from_decryption_key(encrypted_balance, pending_transfer, dec_key)
+    }
+
+    fn get_balance_from_decryption_key(
+        ciphertext: Option<zelgamal::Ciphertext<zBls12>>,
+        p_ciphertext: Option<zelgamal::Ciphertext<zBls12>>,
+        dec_key: &DecryptionKey<Bls12>
+    ) -> Result<Self> {
+        let encrypted_balance_str = ciphertext_str(&ciphertext)?;
+        let pending_transfer_str = ciphertext_str(&p_ciphertext)?;
+
         let zero = zelgamal::Ciphertext::<zBls12>::zero();
         let enc_total = ciphertext.unwrap_or(zero.clone()).add(&p_ciphertext.unwrap_or(zero), &*ZPARAMS);
-        let dec_balance = enc_total.decrypt(&no_std(&dec_key)?, p_g, &ZPARAMS).unwrap();
+        let dec_balance = enc_total.decrypt_with_table(&no_std(&dec_key)?, &decryption_table(), &ZPARAMS)
+            .ok_or(KeystoreError::UndecryptableBalance)?;
         let mut buf = vec![0u8; 64];
         enc_total.write(&mut buf[..])?;
 
@@ -175,6 +225,52 @@ impl BalanceQuery {
     }
 }
 
+/// Get the balance and the sum of the pending transfers of an account of the anonymous balances module.
+fn get_anonymous_account(
+    api: &Api,
+    url: &str,
+    enc_key: &EncKey
+) -> Result<(Option<zelgamal::Ciphertext<zBls12>>, Option<zelgamal::Ciphertext<zBls12>>)> {
+    let encrypted_balance = get_pool_ciphertext::<anonymous_balances::EncryptedBalance<Runtime>>(url, enc_key)?;
+    let pending_transfer = get_pending_transfer(api, "AnonymousBalances", enc_key.encode(), |epoch| {
+        get_pool_ciphertext::<anonymous_balances::PendingTransfer<Runtime>>(url, &(*enc_key, epoch))
+    })?;
+
+    Ok((encrypted_balance, pending_transfer))
+}
+
+/// The hex string of a ciphertext, or `0x00` if there is none.
+fn ciphertext_str(ciphertext: &Option<zelgamal::Ciphertext<zBls12>>) -> Result<String> {
+    match ciphertext {
+        Some(c) => {
+            let mut buf = vec![0u8; 64];
+            c.write(&mut buf[..])?;
+            Ok(format!("0x{}", hex::encode(&buf)))
+        },
+        None => Ok("0x00".to_string()),
+    }
+}
+
+/// The decryption table of balances, read from its cache or built and cached.
+/// The cache is only an optimization, so a broken or missing cache is built again.
+fn decryption_table() -> zelgamal::DecryptionTable<zBls12> {
+    let p_g = zFixedGenerators::Diversifier; // 1
+
+    let cached = File::open(DECRYPTION_TABLE_PATH)
+        .ok()
+        .and_then(|file| zelgamal::DecryptionTable::<zBls12>::read(&mut BufReader::new(file), p_g, &ZPARAMS).ok())
+        .filter(|table| table.size() == DECRYPTION_TABLE_SIZE);
+    if let Some(table) = cached {
+        return table;
+    }
+
+    let table = zelgamal::DecryptionTable::<zBls12>::new(DECRYPTION_TABLE_SIZE, p_g, &ZPARAMS);
+    if let Ok(file) = File::create(DECRYPTION_TABLE_PATH) {
+        let _ = table.write(BufWriter::new(file));
+    }
+    table
+}
+
 pub fn address(seed: &[u8]) -> Result<Vec<u8>> {
     let address = EncryptionKey::<Bls12>::from_seed(seed, &PARAMS)?;
 
@@ -184,11 +280,38 @@ pub fn address(seed: &[u8]) -> Result<Vec<u8>> {
     Ok(address_bytes)
 }
 
+/// Get the epoch of the latest block.
+/// Epochs are counted from `EpochOffset` since the last epoch length change, as `ZkSystem::epoch_at` does.
+pub fn current_epoch(api: &Api) -> Result<u64> {
+    let current_height = hexstr_to_u64(api.get_latest_height()?);
+    let epoch_length = hexstr_to_u64(api.get_storage("ZkSystem", "EpochLength", None)?);
+    let epoch_offset = hexstr_to_u64(api.get_storage("ZkSystem", "EpochOffset", None)?);
+    let changed_at = hexstr_to_u64(api.get_storage("ZkSystem", "EpochLengthChangedAt", None)?);
+
+    if current_height < changed_at {
+        return Ok(epoch_offset);
+    }
+    Ok(epoch_offset + (current_height - changed_at) / epoch_length)
+}
+
+/// Get the g_epoch of the current epoch, derived with the version the chain derives it with.
 pub fn g_epoch(api: &Api) -> Result<edwards::Point<Bls12, PrimeOrder>> {
-    let current_height_str = api.get_latest_height()?;
-    let epoch_length_str = api.get_storage("ZkSystem", "EpochLength", None)?;
-    let current_epoch = hexstr_to_u64(current_height_str) / hexstr_to_u64(epoch_length_str);
-    let g_epoch = GEpoch::group_hash(current_epoch as u32)?; // TODO
+    let current_epoch = current_epoch(api)?;
+    let last_epoch = hexstr_to_u64(api.get_storage("ZkSystem", "LastEpoch", None)?);
+    let mut version = hexstr_to_u64(api.get_storage("ZkSystem", "CurrentGEpochVersion", None)?);
+
+    // A scheduled version is applied when the chain initializes the current epoch.
+    // An absent value reads as 0, so scheduling back to the first version is only seen once it's applied.
+    if last_epoch < current_epoch {
+        let pending = hexstr_to_u64(api.get_storage("ZkSystem", "PendingGEpochVersion", None)?);
+        if pending != 0 {
+            version = pending;
+        }
+    }
+
+    let version = GEpochVersion::from_u8(version as u8)
+        .ok_or(io::Error::from(io::ErrorKind::InvalidData))?;
+    let g_epoch = GEpoch::group_hash_with_version(current_epoch as u32, version)?;
 
     let point = edwards::Point::<Bls12, _>::read(&mut g_epoch.as_ref(), &PARAMS)?
             .as_prime_order(&PARAMS)
@@ -198,9 +321,229 @@ pub fn g_epoch(api: &Api) -> Result<edwards::Point<Bls12, PrimeOrder>> {
 }
 
 // Get set fee amount as `TransactionBaseFee` in encrypyed-balances module.
-pub fn fee(api: &Api) -> Result<u32> {
+pub fn fee(api: &Api) -> Result<u64> {
     let fee_str = api.get_storage("EncryptedBalances", "TransactionBaseFee", None)?;
-    Ok(hexstr_to_u64(fee_str) as u32)
+    Ok(hexstr_to_u64(fee_str))
+}
+
+/// Get the fee of every anonymous transfer as `AnonymousFee` in encrypted-balances module.
+pub fn anonymous_fee(api: &Api) -> Result<u64> {
+    let fee_str = api.get_storage("EncryptedBalances", "AnonymousFee", None)?;
+    Ok(hexstr_to_u64(fee_str))
+}
+
+/// zk-related chain parameters a wallet must agree with the node on.
+pub struct ChainInfo {
+    pub epoch_length: u64,
+    pub max_anonymity_set_size: u32,
+    pub transaction_base_fee: u64,
+    pub anonymous_fee: u64,
+    pub confidential_vk_hash: [u8; 32],
+    pub anonymous_vk_hash: [u8; 32],
+    pub confidential_pk_hash: Option<[u8; 32]>,
+    pub anonymous_pk_hash: Option<[u8; 32]>,
+}
+
+impl ChainInfo {
+    /// Get the chain parameters from the node's storage.
+    /// The verifying key hashes are blake2_256 of the serialized keys,
+    /// so they can be compared with the ones embedded in the chain spec.
+    pub fn get(api: &Api) -> Result<Self> {
+        let epoch_length_str = api.get_storage("ZkSystem", "EpochLength", None)?;
+        let max_anonymity_set_size_str = api.get_storage("ZkSystem", "MaxAnonymitySetSize", None)?;
+
+        Ok(ChainInfo {
+            epoch_length: hexstr_to_u64(epoch_length_str),
+            max_anonymity_set_size: hexstr_to_u64(max_anonymity_set_size_str) as u32,
+            transaction_base_fee: fee(api)?,
+            anonymous_fee: anonymous_fee(api)?,
+            confidential_vk_hash: vk_hash(api, "ConfidentialVk")?,
+            anonymous_vk_hash: vk_hash(api, "AnonymousVk")?,
+            confidential_pk_hash: pk_hash(api, "ConfidentialPkHash")?,
+            anonymous_pk_hash: pk_hash(api, "AnonymousPkHash")?,
+        })
+    }
+}
+
+fn vk_hash(api: &Api, storage_name: &str) -> Result<[u8; 32]> {
+    let vk_str = api.get_storage("ZkSystem", storage_name, None)?;
+    let vk_encoded = hexstr_to_vec(vk_str);
+    let vk = <Vec<u8>>::decode(&mut &vk_encoded[..])
+        .ok_or(io::Error::from(io::ErrorKind::InvalidData))?;
+
+    Ok(blake2_256(&vk[..]))
+}
+
+/// Get the issuer of an encrypted asset, who can mint it and under whose key its total supply is encrypted.
+/// Returns `None` if the asset was issued without recording the issuer.
+pub fn asset_issuer(api: &Api, asset_id: u32) -> Result<Option<EncryptionKey<Bls12>>> {
+    let issuer_str = api.get_storage("EncryptedAssets", "Issuer", Some(asset_id.encode()))?;
+    if issuer_str.as_str() == "0x00" {
+        return Ok(None);
+    }
+
+    let issuer = EncKey::decode(&mut &hexstr_to_vec(issuer_str)[..])
+        .ok_or(io::Error::from(io::ErrorKind::InvalidData))?;
+
+    Ok(Some(EncryptionKey::<Bls12>::read(&mut issuer.as_bytes(), &PARAMS)?))
+}
+
+/// The name, the symbol and the decimals of an asset set by its issuer.
+pub struct AssetMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+impl AssetMetadata {
+    pub fn denomination(&self) -> Denomination {
+        Denomination::new(self.symbol.clone(), self.decimals)
+    }
+
+    /// Format an amount with the decimals and the symbol of the asset, e.g. `12345` with two decimals as `123.45 ZUSD`.
+    pub fn format_amount(&self, amount: u64) -> String {
+        self.denomination().format_amount(amount)
+    }
+}
+
+/// Get the denomination of an asset from its metadata.
+/// Amounts are integers if the issuer hasn't set the metadata.
+pub fn asset_denomination(api: &Api, asset_id: u32) -> Result<Denomination> {
+    Ok(asset_metadata(api, asset_id)?
+        .map(|metadata| metadata.denomination())
+        .unwrap_or_else(Denomination::units))
+}
+
+/// Get the denomination of the native coin from `tokenSymbol` and `tokenDecimals` in the chain properties.
+/// Amounts are integers if the chain spec doesn't set them.
+pub fn native_denomination(url: &str) -> Result<Denomination> {
+    let properties = runtime::rpc_request(url, "system_properties")?;
+    let symbol = properties["tokenSymbol"].as_str().unwrap_or_default();
+    let decimals = match properties["tokenDecimals"].as_u64() {
+        Some(decimals) if decimals <= u8::max_value() as u64 => decimals as u8,
+        Some(_) => return Err(io::Error::from(io::ErrorKind::InvalidData).into()),
+        None => 0,
+    };
+
+    Ok(Denomination::new(symbol.to_string(), decimals))
+}
+
+/// Get the weights of dispatchables from `weights` in the chain properties.
+/// Returns `None` if the chain spec doesn't export them.
+pub fn chain_weights(url: &str) -> Result<Option<BTreeMap<String, u64>>> {
+    let properties = runtime::rpc_request(url, "system_properties")?;
+    let weights = match properties["weights"].as_object() {
+        Some(weights) => weights,
+        None => return Ok(None),
+    };
+
+    let mut res = BTreeMap::new();
+    for (name, weight) in weights {
+        let weight = weight.as_u64()
+            .ok_or(io::Error::from(io::ErrorKind::InvalidData))?;
+        res.insert(name.clone(), weight);
+    }
+
+    Ok(Some(res))
+}
+
+/// The weights of dispatchables in the runtime zface was built against, named as in the chain properties.
+pub fn local_weights() -> BTreeMap<String, u64> {
+    use zerochain_runtime::weights;
+
+    vec![
+        ("base", weights::BASE),
+        ("signature", weights::SIGNATURE),
+        ("audit", weights::AUDIT),
+        ("confidentialProof", weights::CONFIDENTIAL_PROOF),
+        ("anonymousProof", weights::ANONYMOUS_PROOF),
+        ("maxBlockWeight", weights::MAX_BLOCK_WEIGHT),
+    ].into_iter()
+        .map(|(name, weight)| (name.to_string(), weight as u64))
+        .collect()
+}
+
+/// Get the metadata of an asset. Returns `None` if the issuer hasn't set it.
+pub fn asset_metadata(api: &Api, asset_id: u32) -> Result<Option<AssetMetadata>> {
+    let metadata_str = api.get_storage("EncryptedAssets", "AssetMetadata", Some(asset_id.encode()))?;
+    if metadata_str.as_str() == "0x00" {
+        return Ok(None);
+    }
+
+    // The same encoding as `encrypted_assets::Metadata`
+    let (name, symbol, decimals, _issuer) = <(Vec<u8>, Vec<u8>, u8, EncKey)>::decode(&mut &hexstr_to_vec(metadata_str)[..])
+        .ok_or(io::Error::from(io::ErrorKind::InvalidData))?;
+
+    Ok(Some(AssetMetadata {
+        name: String::from_utf8_lossy(&name[..]).into_owned(),
+        symbol: String::from_utf8_lossy(&symbol[..]).into_owned(),
+        decimals,
+    }))
+}
+
+/// Get the blake2_256 hash of the proving key pinned in `ZkSystem`.
+/// Returns `None` if the chain doesn't pin one.
+pub fn pk_hash(api: &Api, storage_name: &str) -> Result<Option<[u8; 32]>> {
+    let pk_hash_str = api.get_storage("ZkSystem", storage_name, None)?;
+    if pk_hash_str.as_str() == "0x00" {
+        return Ok(None);
+    }
+
+    let pk_hash = <[u8; 32]>::decode(&mut &hexstr_to_vec(pk_hash_str)[..])
+        .ok_or(io::Error::from(io::ErrorKind::InvalidData))?;
+    if pk_hash == [0u8; 32] {
+        return Ok(None);
+    }
+
+    Ok(Some(pk_hash))
+}
+
+/// Storage of the module whose accounts a transfer is sent to.
+pub enum RecipientModule {
+    EncryptedBalances,
+    EncryptedAssets(u32),
+    AnonymousBalances,
+}
+
+/// Check whether the encryption key has ever appeared on chain, that is,
+/// it has a balance or a pending transfer, or it is in the anonymous `EncKeySet`.
+pub fn enc_key_exists(api: &Api, url: &str, enc_key: &EncryptionKey<Bls12>, module: RecipientModule) -> Result<bool> {
+    let enc_key = EncKey::try_from(no_std_e(enc_key)?)?;
+
+    let (module_name, storage_key, has_balance) = match module {
+        RecipientModule::EncryptedBalances => (
+            "EncryptedBalances",
+            enc_key.encode(),
+            get_pool_storage::<encrypted_balances::EncryptedBalance<Runtime>>(url, &enc_key)?.is_some(),
+        ),
+        RecipientModule::EncryptedAssets(asset_id) => (
+            "EncryptedAssets",
+            (asset_id, enc_key.clone()).encode(),
+            get_pool_storage::<encrypted_assets::EncryptedBalance<Runtime>>(url, &(asset_id, enc_key))?.is_some(),
+        ),
+        RecipientModule::AnonymousBalances => (
+            "AnonymousBalances",
+            enc_key.encode(),
+            get_pool_storage::<anonymous_balances::EncryptedBalance<Runtime>>(url, &enc_key)?.is_some(),
+        ),
+    };
+    if has_balance {
+        return Ok(true);
+    }
+
+    let pending_epochs_str = api.get_storage(module_name, "PendingEpochs", Some(storage_key))?;
+    if pending_epochs_str.as_str() != "0x00" {
+        return Ok(true);
+    }
+
+    if let RecipientModule::AnonymousBalances = module {
+        let enc_keys_str = api.get_storage("AnonymousBalances", "EncKeySet", None)?;
+        let enc_keys = <Vec<EncKey>>::decode(&mut &hexstr_to_vec(enc_keys_str)[..])
+            .unwrap_or_default();
+        return Ok(enc_keys.contains(&enc_key));
+    }
+
+    Ok(false)
 }
 
 fn no_std(dec_key: &DecryptionKey<Bls12>) -> Result<keys::DecryptionKey<zBls12>> {
@@ -211,6 +554,23 @@ fn no_std(dec_key: &DecryptionKey<Bls12>) -> Result<keys::DecryptionKey<zBls12>>
     Ok(key)
 }
 
+/// Encode the decryption key in the exported format of `keys`, which the other wallets import.
+pub fn dec_key_to_string(dec_key: &DecryptionKey<Bls12>) -> Result<String> {
+    Ok(no_std(dec_key)?.to_string())
+}
+
+/// Decode a decryption key exported by `dec_key_to_string` or another wallet.
+pub fn dec_key_from_str(s: &str) -> Result<DecryptionKey<Bls12>> {
+    let key: keys::DecryptionKey<zBls12> = s.trim().parse()
+        .map_err(KeystoreError::InvalidDecryptionKey)?;
+
+    let mut dec_key_vec = vec![];
+    key.write(&mut dec_key_vec)?;
+    let dec_key = DecryptionKey::read(&mut &dec_key_vec[..])?;
+
+    Ok(dec_key)
+}
+
 fn no_std_e(enc_key: &EncryptionKey<Bls12>) -> Result<keys::EncryptionKey<zBls12>> {
     let mut enc_key_vec = vec![];
     enc_key.write(&mut enc_key_vec)?;
@@ -219,6 +579,37 @@ fn no_std_e(enc_key: &EncryptionKey<Bls12>) -> Result<keys::EncryptionKey<zBls12
 }
 
 pub fn get_enc_keys<R: Rng>(api: &Api, rng: &mut R) -> Result<Vec<EncryptionKey<Bls12>>> {
+    sample_decoys(&enc_key_set(api)?[..], rng)
+}
+
+/// Get decoys only among the accounts which rolled over within the last `k_epochs` epochs.
+/// Accounts which have been inactive for longer are unlikely to take part in a transfer,
+/// so picking them as decoys would let an observer shrink the anonymity set.
+/// This mirrors `active_enc_keys` of the anonymous-balances module.
+pub fn get_active_enc_keys<R: Rng>(api: &Api, k_epochs: u64, rng: &mut R) -> Result<Vec<EncryptionKey<Bls12>>> {
+    let oldest = current_epoch(api)?.saturating_sub(k_epochs);
+
+    let mut active = vec![];
+    for e in enc_key_set(api)? {
+        let last_rollover_str = api.get_storage(
+            "AnonymousBalances",
+            "LastRollOver",
+            Some(EncKey::try_from(no_std_e(&e)?)?.encode())
+        )?;
+
+        if last_rollover_str.as_str() != "0x00" && hexstr_to_u64(last_rollover_str) >= oldest {
+            active.push(e);
+        }
+    }
+
+    if active.is_empty() {
+        return Err(KeystoreError::NoActiveDecoys(k_epochs));
+    }
+
+    sample_decoys(&active[..], rng)
+}
+
+fn enc_key_set(api: &Api) -> Result<Vec<EncryptionKey<Bls12>>> {
     let mut enc_keys_str = api.get_storage("AnonymousBalances", "EncKeySet", None)?;
     // TODO: remove unnecessary prefix. If it returns `0x00`, it will be panic.
     for _ in 0..4 {
@@ -232,10 +623,15 @@ pub fn get_enc_keys<R: Rng>(api: &Api, rng: &mut R) -> Result<Vec<EncryptionKey<
         let tmp = enc_keys_vec.drain(..32).collect::<Vec<u8>>();
         tmp_acc.push(EncryptionKey::<Bls12>::read(&mut &tmp[..], &PARAMS)?)
     }
+
+    Ok(tmp_acc)
+}
+
+fn sample_decoys<R: Rng>(enc_keys: &[EncryptionKey<Bls12>], rng: &mut R) -> Result<Vec<EncryptionKey<Bls12>>> {
     let mut acc = vec![];
     for _ in 0..DECOY_SIZE {
-        let random_i = rng.gen_range(0, tmp_acc.len());
-        acc.push(tmp_acc[random_i].clone());
+        let random_i = rng.gen_range(0, enc_keys.len());
+        acc.push(enc_keys[random_i].clone());
     }
 
     Ok(acc)
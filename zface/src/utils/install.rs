@@ -0,0 +1,201 @@
+//! Self-check of a zface installation: the build metadata embedded in the binary,
+//! the local verifying keys against the fingerprints in the runtime,
+//! the local proving keys against the hashes pinned on chain,
+//! and the permissions of the wallet directory holding the keyfiles.
+
+use std::fs;
+use std::path::Path;
+use polkadot_rs::Api;
+use primitives::hexdisplay::HexDisplay;
+use crate::error::Result;
+use crate::transaction::{CONF_VK_PATH, ANONY_VK_PATH};
+use super::params;
+
+/// The version of zface.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The git commit zface was built from, embedded by the build script.
+pub const GIT_COMMIT: Option<&str> = option_env!("ZFACE_GIT_COMMIT");
+
+/// The cargo profile zface was built with, embedded by the build script.
+pub const BUILD_PROFILE: Option<&str> = option_env!("ZFACE_BUILD_PROFILE");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok(String),
+    Warning(String),
+    Failed(String),
+}
+
+/// The results of all the checks, in the order they were run.
+#[derive(Debug, Default)]
+pub struct InstallReport {
+    pub checks: Vec<(String, CheckStatus)>,
+}
+
+impl InstallReport {
+    pub fn push(&mut self, name: &str, status: CheckStatus) {
+        self.checks.push((name.to_string(), status));
+    }
+
+    /// No check failed. Warnings don't make an installation unsafe by themselves.
+    pub fn is_ok(&self) -> bool {
+        self.checks.iter().all(|(_, status)| match status {
+            CheckStatus::Failed(_) => false,
+            _ => true,
+        })
+    }
+}
+
+/// Run all the checks. The proving keys are only checked against the chain if `api` is given.
+pub fn verify_install<P: AsRef<Path>>(
+    api: Option<&Api>,
+    proving_keys: &[(&str, &str)],
+    wallet_dir: P,
+) -> Result<InstallReport> {
+    let mut report = InstallReport::default();
+
+    check_build(&mut report);
+    check_vk_fingerprints(&mut report);
+
+    for (pk_path, storage_name) in proving_keys {
+        let name = format!("params {}", pk_path);
+        let status = match api {
+            Some(api) => check_proving_key(api, pk_path, storage_name)?,
+            None => CheckStatus::Warning("Not checked against the chain; pass --url to check it.".to_string()),
+        };
+        report.push(&name, status);
+    }
+
+    check_permissions(&mut report, wallet_dir.as_ref())?;
+
+    Ok(report)
+}
+
+fn check_build(report: &mut InstallReport) {
+    let commit = match GIT_COMMIT {
+        Some(commit) => CheckStatus::Ok(format!("zface {} ({})", VERSION, commit)),
+        None => CheckStatus::Warning(format!("zface {} was built without the git commit.", VERSION)),
+    };
+    report.push("build commit", commit);
+
+    let profile = match BUILD_PROFILE {
+        Some("release") => CheckStatus::Ok("release".to_string()),
+        Some(profile) => CheckStatus::Warning(format!("Built with the {} profile; proving is slow.", profile)),
+        None => CheckStatus::Warning("Unknown build profile.".to_string()),
+    };
+    report.push("build profile", profile);
+}
+
+// The verifying keys aren't pinned on chain by a hash, but the runtime zface was built with
+// carries their fingerprints, which the node's runtime version is checked against before signing.
+fn check_vk_fingerprints(report: &mut InstallReport) {
+    let vks = [
+        (CONF_VK_PATH, zerochain_runtime::CONFIDENTIAL_VK_FINGERPRINT),
+        (ANONY_VK_PATH, zerochain_runtime::ANONYMOUS_VK_FINGERPRINT),
+    ];
+
+    for (vk_path, expected) in vks.iter() {
+        let status = match params::vk_fingerprint(vk_path) {
+            Ok(ref local) if local == expected => CheckStatus::Ok(format!("0x{}", HexDisplay::from(local))),
+            Ok(local) => CheckStatus::Failed(format!(
+                "fingerprint 0x{}, but the runtime was built with 0x{}.",
+                HexDisplay::from(&local), HexDisplay::from(expected)
+            )),
+            Err(_) => CheckStatus::Warning("not found; run `zface snark setup` or fetch the params.".to_string()),
+        };
+        report.push(&format!("params {}", vk_path), status);
+    }
+}
+
+fn check_proving_key(api: &Api, pk_path: &str, storage_name: &str) -> Result<CheckStatus> {
+    let pinned = match super::getter::pk_hash(api, storage_name)? {
+        Some(hash) => hash,
+        None => return Ok(CheckStatus::Warning(format!("{} is not pinned on chain.", storage_name))),
+    };
+
+    let status = match params::file_hash(pk_path) {
+        Ok(ref local) if *local == pinned => CheckStatus::Ok(format!("0x{}", HexDisplay::from(local))),
+        Ok(local) => CheckStatus::Failed(format!(
+            "hashes to 0x{}, but the chain pins 0x{}.",
+            HexDisplay::from(&local), HexDisplay::from(&pinned)
+        )),
+        Err(_) => CheckStatus::Failed("not found.".to_string()),
+    };
+
+    Ok(status)
+}
+
+// Keyfiles are created with owner and group permissions only,
+// so anything accessible by others has been changed after zface created it.
+#[cfg(unix)]
+fn check_permissions(report: &mut InstallReport, wallet_dir: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if !wallet_dir.exists() {
+        report.push("wallet directory", CheckStatus::Ok("No wallet is created yet.".to_string()));
+        return Ok(());
+    }
+
+    let mut insecure = vec![];
+    let mut paths = vec![wallet_dir.to_path_buf()];
+    while let Some(path) = paths.pop() {
+        let metadata = fs::symlink_metadata(&path)?;
+        if metadata.is_dir() {
+            for entry in fs::read_dir(&path)? {
+                paths.push(entry?.path());
+            }
+        }
+
+        let mode = metadata.permissions().mode();
+        if mode & 0o007 != 0 {
+            insecure.push(format!("{} ({:o})", path.display(), mode & 0o777));
+        }
+    }
+
+    let status = if insecure.is_empty() {
+        CheckStatus::Ok(format!("{} is not accessible by others.", wallet_dir.display()))
+    } else {
+        insecure.sort();
+        CheckStatus::Failed(format!("accessible by others: {}", insecure.join(", ")))
+    };
+    report.push("wallet permissions", status);
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(report: &mut InstallReport, _wallet_dir: &Path) -> Result<()> {
+    report.push("wallet permissions", CheckStatus::Warning("Not checked on this platform.".to_string()));
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_wallet_permissions() {
+        let mut dir = env::temp_dir();
+        dir.push("zface_verify_install_permissions");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("keystore")).unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700)).unwrap();
+        fs::set_permissions(dir.join("keystore"), fs::Permissions::from_mode(0o700)).unwrap();
+
+        let keyfile = dir.join("keystore").join("keyfile.json");
+        fs::write(&keyfile, b"{}").unwrap();
+        fs::set_permissions(&keyfile, fs::Permissions::from_mode(0o660)).unwrap();
+
+        let report = verify_install(None, &[], &dir).unwrap();
+        assert!(report.is_ok());
+
+        fs::set_permissions(&keyfile, fs::Permissions::from_mode(0o664)).unwrap();
+        let report = verify_install(None, &[], &dir).unwrap();
+        assert!(!report.is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,5 +1,10 @@
 pub mod print_keys;
 pub mod mnemonics;
 pub mod getter;
+pub mod runtime;
+pub mod params;
+pub mod install;
+pub mod dev;
+pub mod typegen;
 
 pub use self::print_keys::*;
\ No newline at end of file
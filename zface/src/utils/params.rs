@@ -0,0 +1,77 @@
+//! Check the local proving keys against the hashes pinned on chain.
+//! A proving key which doesn't correspond to the on-chain verifying key still produces a proof,
+//! and the transaction only fails once it's verified in the block.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use blake2_rfc::blake2b::Blake2b;
+use polkadot_rs::Api;
+use primitives::hexdisplay::HexDisplay;
+use crate::error::{Result, KeystoreError};
+use super::getter;
+
+/// blake2_256 of a file, without reading it into memory at once.
+pub fn file_hash<P: AsRef<Path>>(path: P) -> Result<[u8; 32]> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = Blake2b::new(32);
+    let mut buf = [0u8; 8192];
+
+    loop {
+        let len = reader.read(&mut buf)?;
+        if len == 0 {
+            break;
+        }
+        hasher.update(&buf[..len]);
+    }
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(hasher.finalize().as_bytes());
+    Ok(hash)
+}
+
+/// The first 8 bytes of `file_hash`, as embedded in the runtime version for verifying keys.
+pub fn vk_fingerprint<P: AsRef<Path>>(path: P) -> Result<[u8; 8]> {
+    let mut res = [0u8; 8];
+    res.copy_from_slice(&file_hash(path)?[..8]);
+    Ok(res)
+}
+
+/// Refuse to prove unless the local proving key is the one pinned in `storage_name` of `ZkSystem`.
+/// Chains which don't pin a proving key are not checked.
+pub fn ensure_proving_key_matches<P: AsRef<Path>>(api: &Api, pk_path: P, storage_name: &str) -> Result<()> {
+    let expected = match getter::pk_hash(api, storage_name)? {
+        Some(hash) => hash,
+        None => return Ok(()),
+    };
+
+    let local = file_hash(&pk_path)?;
+    if local != expected {
+        return Err(KeystoreError::ProvingKeyMismatch(format!(
+            "{} hashes to 0x{}, but the chain expects 0x{}",
+            pk_path.as_ref().display(), HexDisplay::from(&local), HexDisplay::from(&expected)
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use primitives::blake2_256;
+    use std::{env, fs, io::Write};
+
+    #[test]
+    fn test_file_hash_matches_blake2_256() {
+        let mut path = env::temp_dir();
+        path.push("file_hash_matches_blake2_256.dat");
+
+        let data: Vec<u8> = (0..20000u32).map(|i| i as u8).collect();
+        fs::File::create(&path).unwrap().write_all(&data).unwrap();
+
+        assert_eq!(file_hash(&path).unwrap(), blake2_256(&data));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
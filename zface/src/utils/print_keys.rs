@@ -2,7 +2,7 @@
 use zprimitives::PARAMS as ZPARAMS;
 use crate::ss58::EncryptionKeyBytes;
 use primitives::crypto::Ss58Codec;
-use zpairing::{bls12_381::Bls12 as zBls12, PrimeField as zPrimeField, PrimeFieldRepr as zPrimeFieldRepr, io};
+use zpairing::{bls12_381::Bls12 as zBls12, io};
 use rand::{OsRng, Rng};
 use bip39::{Mnemonic, Language};
 use substrate_bip39::mini_secret_from_entropy;
@@ -10,7 +10,8 @@ use substrate_bip39::mini_secret_from_entropy;
 pub struct PrintKeys {
     pub phrase: Option<String>,
     pub seed: [u8; 32],
-    pub decryption_key: [u8; 32],
+    /// The decryption key in the exported format of `keys`.
+    pub decryption_key: String,
     pub encryption_key: [u8; 32],
     pub ss58_encryption_key: String,
 }
@@ -30,10 +31,10 @@ impl PrintKeys {
         let seed = phrase_to_seed(phrase, password, lang);
         let print_keys = gen_from_seed(seed, Some(phrase)).unwrap();
 
-        println!("Phrase `{}` is account:\n Seed: 0x{}\n Decryption key: 0x{}\n Encryption key (hex): 0x{}\n Address (SS58): {}",
+        println!("Phrase `{}` is account:\n Seed: 0x{}\n Decryption key: {}\n Encryption key (hex): 0x{}\n Address (SS58): {}",
             phrase,
             hex::encode(&print_keys.seed[..]),
-            hex::encode(&print_keys.decryption_key[..]),
+            print_keys.decryption_key,
             hex::encode(&print_keys.encryption_key[..]),
             print_keys.ss58_encryption_key,
         );
@@ -56,9 +57,6 @@ fn gen_from_seed(seed: [u8; 32], phrase: Option<&str>) -> io::Result<PrintKeys>
     let pgk = keys::ProofGenerationKey::<zBls12>::from_seed(&seed[..], &ZPARAMS);
     let decryption_key = pgk.into_decryption_key()?;
 
-    let mut dk_buf = [0u8; 32];
-    decryption_key.0.into_repr().write_le(&mut &mut dk_buf[..])?;
-
     let encryption_key = pgk.into_encryption_key(&ZPARAMS)?;
 
     let mut ek_buf = [0u8; 32];
@@ -69,7 +67,7 @@ fn gen_from_seed(seed: [u8; 32], phrase: Option<&str>) -> io::Result<PrintKeys>
     Ok(PrintKeys {
         phrase: phrase.map(|e| e.to_string()),
         seed: seed,
-        decryption_key: dk_buf,
+        decryption_key: decryption_key.to_string(),
         encryption_key: ek_buf,
         ss58_encryption_key: ek_ss58,
     })
@@ -0,0 +1,229 @@
+//! Check that the runtime of the connected node is the one zface builds calls for.
+//! Call indices of `Call::EncryptedBalances(...)` etc. are compiled in from `zerochain-runtime`,
+//! so signing against an upgraded runtime silently produces wrong extrinsics.
+
+use std::path::Path;
+use std::fs;
+use std::collections::BTreeMap;
+use std::io::{Write, BufReader};
+use std::sync::mpsc::channel;
+use parity_codec::Encode;
+use serde_json::json;
+use primitives::blake2_256;
+use polkadot_rs::{Url, hexstr_to_vec};
+use zerochain_runtime::{Runtime, VERSION};
+use crate::error::{Result, KeystoreError};
+use crate::wallet::{create_new_file, replace_file};
+
+pub const LOCAL_URL: &'static str = "ws://127.0.0.1:9944";
+pub const RUNTIME_CACHE_FILE: &'static str = "runtime.json";
+
+/// The address of the node at `url`, for the requests polkadot-rs doesn't make,
+/// e.g. reading the child tries of the shielded pools.
+pub fn node_url(url: &Url) -> String {
+    match url {
+        Url::Custom(u) => u.clone(),
+        Url::Local => LOCAL_URL.to_string(),
+    }
+}
+
+/// The runtime which zface was built against, cached after it was verified with a node.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimePin {
+    pub spec_name: String,
+    pub spec_version: u32,
+    pub metadata_hash: String,
+}
+
+impl RuntimePin {
+    /// The runtime compiled into zface.
+    pub fn local() -> Self {
+        RuntimePin {
+            spec_name: VERSION.spec_name.to_string(),
+            spec_version: VERSION.spec_version,
+            metadata_hash: hex::encode(blake2_256(&Runtime::metadata().encode()[..])),
+        }
+    }
+}
+
+/// The runtimes verified with nodes, keyed by the genesis hash of their chain and their spec version,
+/// so that switching to another node, chain or runtime fetches and verifies the metadata again.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize, Clone)]
+pub struct RuntimeCache {
+    pins: BTreeMap<String, RuntimePin>,
+}
+
+impl RuntimeCache {
+    fn key(genesis_hash: &str, spec_version: u32) -> String {
+        format!("{}/{}", genesis_hash, spec_version)
+    }
+
+    pub fn get(&self, genesis_hash: &str, spec_version: u32) -> Option<&RuntimePin> {
+        self.pins.get(&Self::key(genesis_hash, spec_version))
+    }
+
+    pub fn insert(&mut self, genesis_hash: &str, pin: RuntimePin) {
+        self.pins.insert(Self::key(genesis_hash, pin.spec_version), pin);
+    }
+
+    /// A missing or unreadable cache, e.g. one of an older zface, is empty.
+    fn load(path: &Path) -> Self {
+        fs::File::open(path).ok()
+            .and_then(|file| serde_json::from_reader(BufReader::new(file)).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        let mut file = if path.exists() {
+            replace_file(path)?
+        } else {
+            create_new_file(path)?
+        };
+        serde_json::to_writer(&mut file, self)?;
+
+        file.flush()?;
+        file.sync_all()?;
+
+        Ok(())
+    }
+}
+
+/// Refuse to sign unless the node runs the runtime zface was built against.
+/// The metadata is only fetched when no pin matching the local runtime is cached
+/// for the genesis hash of the node's chain and its spec version.
+pub fn ensure_runtime_matches<P: AsRef<Path>>(root_dir: P, url: &str) -> Result<()> {
+    let local = RuntimePin::local();
+    let (spec_name, spec_version, impl_name) = remote_version(url)?;
+
+    if spec_name != local.spec_name || spec_version != local.spec_version {
+        return Err(KeystoreError::RuntimeMismatch(format!(
+            "node runs {} v{}, but zface was built for {} v{}",
+            spec_name, spec_version, local.spec_name, local.spec_version
+        )));
+    }
+
+    // The implementation name carries the fingerprints of the verifying keys the runtime was built with.
+    if impl_name != VERSION.impl_name.to_string() {
+        return Err(KeystoreError::RuntimeMismatch(format!(
+            "node runs {}, but zface was built for {}; the verifying keys differ",
+            impl_name, VERSION.impl_name
+        )));
+    }
+
+    let genesis_hash = remote_genesis_hash(url)?;
+    let cache_path = root_dir.as_ref().join(RUNTIME_CACHE_FILE);
+    let mut cache = RuntimeCache::load(&cache_path);
+    if cache.get(&genesis_hash, spec_version) == Some(&local) {
+        return Ok(());
+    }
+
+    let metadata_hash = remote_metadata_hash(url)?;
+    if metadata_hash != local.metadata_hash {
+        return Err(KeystoreError::RuntimeMismatch(format!(
+            "metadata of {} v{} differs from the one zface was built with",
+            spec_name, spec_version
+        )));
+    }
+
+    fs::create_dir_all(root_dir.as_ref())?;
+    cache.insert(&genesis_hash, local);
+    cache.save(&cache_path)
+}
+
+fn remote_version(url: &str) -> Result<(String, u32, String)> {
+    let version = rpc_request(url, "state_getRuntimeVersion")?;
+    let spec_name = version["specName"].as_str()
+        .ok_or(KeystoreError::RuntimeMismatch("invalid runtime version response".to_string()))?;
+    let spec_version = version["specVersion"].as_u64()
+        .ok_or(KeystoreError::RuntimeMismatch("invalid runtime version response".to_string()))?;
+    let impl_name = version["implName"].as_str()
+        .ok_or(KeystoreError::RuntimeMismatch("invalid runtime version response".to_string()))?;
+
+    Ok((spec_name.to_string(), spec_version as u32, impl_name.to_string()))
+}
+
+fn remote_genesis_hash(url: &str) -> Result<String> {
+    let hash = rpc_request_with_params(url, "chain_getBlockHash", json!([0]))?;
+    hash.as_str()
+        .map(|h| h.trim_start_matches("0x").to_string())
+        .ok_or(KeystoreError::RuntimeMismatch("invalid genesis hash response".to_string()))
+}
+
+fn remote_metadata_hash(url: &str) -> Result<String> {
+    let metadata = rpc_request(url, "state_getMetadata")?;
+    let metadata_str = metadata.as_str()
+        .ok_or(KeystoreError::RuntimeMismatch("invalid metadata response".to_string()))?;
+
+    Ok(hex::encode(blake2_256(&hexstr_to_vec(metadata_str.to_string())[..])))
+}
+
+pub(crate) fn rpc_request(url: &str, method: &str) -> Result<serde_json::Value> {
+    rpc_request_with_params(url, method, json!([]))
+}
+
+pub(crate) fn rpc_request_with_params(url: &str, method: &str, params: serde_json::Value) -> Result<serde_json::Value> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    }).to_string();
+
+    let (tx, rx) = channel();
+    ws::connect(url, |out| {
+        out.send(request.as_str()).expect("should send a request to the node.");
+        let tx = tx.clone();
+
+        move |msg: ws::Message| {
+            tx.send(msg.into_text()?).expect("receiver should be alive.");
+            out.close(ws::CloseCode::Normal)
+        }
+    })?;
+
+    let response: serde_json::Value = serde_json::from_str(&rx.recv().expect("should receive a response")[..])?;
+    Ok(response["result"].clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_runtime_pin_roundtrip() {
+        let mut path = env::temp_dir();
+        path.push("runtime_pin_roundtrip.json");
+        let _ = fs::remove_file(&path);
+
+        let pin = RuntimePin::local();
+        assert_eq!(pin.spec_name, "zerochain");
+        assert_eq!(pin.metadata_hash.len(), 64);
+
+        let mut cache = RuntimeCache::load(&path);
+        assert_eq!(cache, RuntimeCache::default());
+        cache.insert("aa", pin.clone());
+        cache.save(&path).unwrap();
+        cache.save(&path).unwrap();
+        assert_eq!(RuntimeCache::load(&path), cache);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_runtime_cache_is_keyed_by_chain_and_spec_version() {
+        let pin = RuntimePin::local();
+        let mut cache = RuntimeCache::default();
+        cache.insert("aa", pin.clone());
+
+        assert_eq!(cache.get("aa", pin.spec_version), Some(&pin));
+        // Another chain, or another runtime of the same chain, isn't verified yet.
+        assert_eq!(cache.get("bb", pin.spec_version), None);
+        assert_eq!(cache.get("aa", pin.spec_version + 1), None);
+
+        let upgraded = RuntimePin { spec_version: pin.spec_version + 1, ..pin.clone() };
+        cache.insert("aa", upgraded.clone());
+        assert_eq!(cache.get("aa", pin.spec_version), Some(&pin));
+        assert_eq!(cache.get("aa", pin.spec_version + 1), Some(&upgraded));
+    }
+}
@@ -0,0 +1,50 @@
+//! The `types.json` of polkadot-js for the custom types of zerochain, generated from the
+//! type registry of the runtime modules instead of being maintained by hand.
+
+use serde_json::{Map, Value};
+use zprimitives::type_info::{Registry, TypeDef};
+
+/// The registry of the types of the events of the runtime.
+pub fn registry() -> Registry {
+    let mut registry = Registry::new();
+    encrypted_balances::register_types(&mut registry);
+    // The `AssetId` of `encrypted_assets` in the runtime.
+    registry.register_alias("AssetId", TypeDef::Alias("u32"));
+    registry
+}
+
+/// The definitions in the format of polkadot-js: a type name for an alias
+/// and an object of the types of the fields for a struct.
+pub fn types_json(registry: &Registry) -> Value {
+    let mut types = Map::new();
+    for (name, def) in registry.types() {
+        let def = match def {
+            TypeDef::Alias(ty) => Value::String(normalize(ty)),
+            TypeDef::Struct(fields) => Value::Object(fields.iter()
+                .map(|(field, ty)| (field.to_string(), Value::String(normalize(ty))))
+                .collect()),
+        };
+        types.insert(name.to_string(), def);
+    }
+    Value::Object(types)
+}
+
+/// Type names as written by `stringify!`, e.g. `Vec < u8 >`, without the spaces.
+fn normalize(ty: &str) -> String {
+    ty.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_types_json() {
+        let types = types_json(&registry());
+
+        assert_eq!(types["EncKey"], "H256");
+        assert_eq!(types["Proof"], "Vec<u8>");
+        assert_eq!(types["AuditCiphertext"]["account"], "EncKey");
+        assert_eq!(types["AuditCiphertext"]["proof"], "Vec<u8>");
+    }
+}
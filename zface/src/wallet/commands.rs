@@ -4,18 +4,26 @@ use crate::term::Term;
 use crate::derive::ChildIndex;
 use crate::utils::mnemonics::*;
 use crate::error::{Result, KeystoreError};
-use super::{WalletDirectory, KeystoreDirectory, DirOperations};
+use crate::uri::PaymentRequest;
+use crate::invoice::{Invoice, VerifiedInvoice};
+use crate::getter;
+use super::{WalletDirectory, KeystoreDirectory, DirOperations, reencrypt_keyfiles};
 use super::keyfile::{KeyFile, IndexFile};
 use super::config::*;
 use bip39::{Mnemonic, Language, MnemonicType, Seed};
 use rand::Rng;
-use proofs::DecryptionKey;
+use zprimitives::AddressFormat;
+use proofs::{DecryptionKey, EncryptionKey, PARAMS};
 use pairing::bls12_381::Bls12;
+use qrcode::{QrCode, render::unicode};
+use polkadot_rs::Api;
+use std::io;
 
 /// Create a new wallet
 pub fn new_wallet<R: Rng>(
     term: &mut Term,
     root_dir: PathBuf,
+    format: AddressFormat,
     rng: &mut R,
 ) -> Result<()> {
     // 1. configure wallet directory
@@ -54,7 +62,7 @@ pub fn new_wallet<R: Rng>(
         "wallet and a new account successfully created.\n
         {}: {}\n\n",
         keyfile.account_name,
-        keyfile.ss58_address
+        keyfile.address(format)?
     ))?;
 
     Ok(())
@@ -63,6 +71,8 @@ pub fn new_wallet<R: Rng>(
 pub fn show_list(
     term: &mut Term,
     root_dir: PathBuf,
+    format: AddressFormat,
+    verbose: bool,
 ) -> Result<()> {
     let (wallet_dir, keystore_dir) = wallet_keystore_dirs(&root_dir)?;
 
@@ -75,12 +85,18 @@ pub fn show_list(
     let default_index = get_default_index(&wallet_dir)? as usize;
 
     for (i, keyfile) in keyfiles.iter().enumerate() {
-        let (name, address) = (&*keyfile.account_name, &*keyfile.ss58_address);
+        let (name, address) = (&*keyfile.account_name, keyfile.address(format)?);
         if i == default_index {
             term.success(&format!("* {}: {}\n", name, address))?;
         } else {
             term.success(&format!("{}: {}\n", name, address))?;
         }
+
+        if verbose {
+            for (key, value) in keyfile.metadata.iter() {
+                term.info(&format!("    {}: {}\n", key, value))?;
+            }
+        }
     }
 
     Ok(())
@@ -89,6 +105,7 @@ pub fn show_list(
 pub fn new_keyfile<R: Rng>(
     term: &mut Term,
     root_dir: PathBuf,
+    format: AddressFormat,
     rng: &mut R,
 ) -> Result<()> {
     let (wallet_dir, keystore_dir) = wallet_keystore_dirs(&root_dir)?;
@@ -112,7 +129,7 @@ pub fn new_keyfile<R: Rng>(
         "a new account successfully created.\n
         {}: {}\n\n",
         keyfile.account_name,
-        keyfile.ss58_address
+        keyfile.address(format)?
     ))?;
 
     Ok(())
@@ -121,6 +138,7 @@ pub fn new_keyfile<R: Rng>(
 pub fn recover<R: Rng>(
     term: &mut Term,
     root_dir: PathBuf,
+    format: AddressFormat,
     rng: &mut R,
 ) -> Result<()> {
     // 1. configure wallet directory
@@ -145,7 +163,10 @@ pub fn recover<R: Rng>(
     let child_index = ChildIndex::from_index(0);
     let mut keyfile = get_new_keyfile(term, rng, &password[..], &wallet_dir, child_index)?;
 
-    // 7. store a genesis keyfile
+    // 7. carry over metadata of a restored keyfile for the same account, then store a genesis keyfile
+    if let Some(restored) = keystore_dir.load_all()?.into_iter().find(|k| k.ss58_address == keyfile.ss58_address) {
+        keyfile.annotate(restored.metadata);
+    }
     keystore_dir.insert(&mut keyfile, rng)?;
 
     // 8. store new indexfile
@@ -156,7 +177,7 @@ pub fn recover<R: Rng>(
         "Re-generated your wallet from the provided mnemonic successfully.\n
         {}: {}\n\n",
         keyfile.account_name,
-        keyfile.ss58_address
+        keyfile.address(format)?
     ))?;
 
     Ok(())
@@ -179,6 +200,78 @@ pub fn load_dec_key(
     Ok(dec_key)
 }
 
+/// Show the address of the default account as a QR code.
+/// If an amount or a memo is given, a payment request URI is encoded instead of the bare address.
+pub fn show_qr(
+    term: &mut Term,
+    root_dir: PathBuf,
+    format: AddressFormat,
+    amount: Option<u64>,
+    memo: Option<String>,
+) -> Result<()> {
+    let (wallet_dir, keystore_dir) = wallet_keystore_dirs(&root_dir)?;
+    let default_keyfile_name = get_default_keyfile_name(&wallet_dir)?;
+    let keyfile = keystore_dir.load(default_keyfile_name.as_str())?;
+
+    let address = keyfile.address(format)?;
+    let data = if amount.is_none() && memo.is_none() {
+        address
+    } else {
+        PaymentRequest::new(address, amount, memo).to_uri()
+    };
+
+    let code = QrCode::new(data.as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+    // Dark modules are drawn light, so it can be scanned on dark terminals.
+    let image = code.render::<unicode::Dense1x2>()
+        .dark_color(unicode::Dense1x2::Light)
+        .light_color(unicode::Dense1x2::Dark)
+        .build();
+
+    term.info(&format!("{}\n", image))?;
+    term.success(&format!("{}\n", data))?;
+
+    Ok(())
+}
+
+/// Create an invoice requesting `amount` from `payer_enc_key`, signed by the default account.
+/// It can be paid until the epoch `expiry`.
+pub fn create_invoice<R: Rng>(
+    term: &mut Term,
+    root_dir: PathBuf,
+    payer_enc_key: &[u8],
+    amount: u64,
+    memo: Option<&[u8]>,
+    expiry: u64,
+    rng: &mut R,
+) -> Result<String> {
+    let (wallet_dir, keystore_dir) = wallet_keystore_dirs(&root_dir)?;
+    let default_keyfile_name = get_default_keyfile_name(&wallet_dir)?;
+    let keyfile = keystore_dir.load(default_keyfile_name.as_str())?;
+
+    term.info("Enter the wallet password.\n")?;
+    let password = term.passowrd("wallet password")?;
+    let spending_key = keyfile.get_current_spending_key(&password[..])?;
+
+    let payer = EncryptionKey::<Bls12>::read(&mut &payer_enc_key[..], &PARAMS)?;
+    let invoice = Invoice::create(&spending_key, &payer, amount, memo.unwrap_or_default(), expiry, rng)?;
+
+    Ok(invoice.to_uri())
+}
+
+/// Verify an invoice URI for the default account as of the current epoch of the chain.
+pub fn verify_invoice(
+    term: &mut Term,
+    root_dir: PathBuf,
+    uri: &str,
+    api: &Api,
+) -> Result<VerifiedInvoice> {
+    let invoice = Invoice::parse(uri)?;
+    let dec_key = load_dec_key(term, root_dir)?;
+
+    invoice.verify(&dec_key, getter::current_epoch(api)?)
+}
+
 pub fn change_default_account(
     root_dir: PathBuf,
     account_name: &str,
@@ -198,6 +291,49 @@ pub fn change_default_account(
     Ok(())
 }
 
+/// Re-encrypt the master keyfile and all the keyfiles with a new password.
+pub fn change_password<R: Rng>(
+    term: &mut Term,
+    root_dir: PathBuf,
+    rng: &mut R,
+) -> Result<()> {
+    let (wallet_dir, _) = wallet_keystore_dirs(&root_dir)?;
+
+    term.info("Enter the current wallet password.\n")?;
+    let old_password = term.passowrd("current wallet password")?;
+    term.info("Set a new wallet password.\n")?;
+    let new_password = term.new_password("new wallet password", "confirm new wallet password", "password mismatch")?;
+
+    let count = reencrypt_keyfiles(&wallet_dir, &old_password[..], &new_password[..], rng)?;
+
+    term.success(&format!("re-encrypted the master keyfile and {} account keyfiles with the new password.\n", count))?;
+
+    Ok(())
+}
+
+/// Set and remove metadata entries of an account.
+pub fn annotate_account(
+    root_dir: PathBuf,
+    account_name: &str,
+    entries: Vec<(String, String)>,
+    removed_keys: Vec<&str>,
+) -> Result<()> {
+    let (wallet_dir, keystore_dir) = wallet_keystore_dirs(&root_dir)?;
+
+    let index_file = wallet_dir.load_indexfile()?;
+    let (keyfile_name, _) = index_file
+        .map_account_keyfile
+        .get(account_name)
+        .ok_or(KeystoreError::InvalidKeyfile)?;
+
+    let mut keyfile = keystore_dir.load(keyfile_name.as_str())?;
+    keyfile.annotate(entries);
+    keyfile.remove_annotations(removed_keys);
+    keystore_dir.update(&keyfile)?;
+
+    Ok(())
+}
+
 fn get_new_keyfile<R: Rng>(
     term: &mut Term,
     rng: &mut R,
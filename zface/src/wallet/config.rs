@@ -4,3 +4,7 @@ pub const MASTER_ACCOUNTNAME: &'static str = "master";
 pub const INDEXFILE: &'static str = "index.json";
 pub const VERSION: u32 = 1;
 pub const ITERS: u32 = 1024;
+/// The directory the re-encrypted keyfiles are written to while changing the password.
+pub const REENCRYPT_TMP_DIR: &'static str = ".reencrypt";
+/// The directory the old keyfiles are moved to while the re-encrypted ones are swapped in.
+pub const REENCRYPT_BACKUP_DIR: &'static str = ".reencrypt-backup";
@@ -86,6 +86,22 @@ impl DirOperations for KeystoreDirectory{
         save_keyfile(filename, &keyfile_path, keyfile)
     }
 
+    fn update(&self, keyfile: &KeyFile) -> Result<()> {
+        let filename = keyfile.file_name.as_ref().ok_or(KeystoreError::InvalidKeyfile)?;
+        let keyfile_path = self.0.join(filename.as_str());
+        if !keyfile_path.exists() {
+            return Err(KeystoreError::InvalidKeyfile);
+        }
+
+        let mut file = replace_file(&keyfile_path)?;
+        serde_json::to_writer(&mut file, keyfile)?;
+
+        file.flush()?;
+        file.sync_all()?;
+
+        Ok(())
+    }
+
     fn load_all(&self) -> Result<Vec<KeyFile>> {
         Ok(self.get_all_keyfiles()?
             .into_iter()
@@ -155,6 +171,92 @@ impl KeystoreDirectory {
     }
 }
 
+/// Re-encrypt the master keyfile and all the keyfiles in the keystore with a new password,
+/// and return the number of the re-encrypted keyfiles in the keystore.
+/// Every keyfile is decrypted before any file is written, so a wrong password changes nothing.
+/// The re-encrypted keyfiles are written to a temporary directory and swapped in by renaming;
+/// if a rename fails, the ones done so far are rolled back.
+pub fn reencrypt_keyfiles<R: Rng>(
+    wallet_dir: &WalletDirectory,
+    old_password: &[u8],
+    new_password: &[u8],
+    rng: &mut R,
+) -> Result<usize> {
+    let keystore_dir = KeystoreDirectory::create(wallet_dir.get_default_keystore_dir())?;
+    let backup_dir = wallet_dir.0.join(REENCRYPT_BACKUP_DIR);
+    let tmp_dir = wallet_dir.0.join(REENCRYPT_TMP_DIR);
+
+    // The old keyfiles of an interrupted swap must be restored by hand.
+    if backup_dir.exists() {
+        return Err(KeystoreError::InvalidPath);
+    }
+
+    let master = wallet_dir.load_master()?.reencrypt(old_password, new_password, rng)?;
+    let mut keyfiles = vec![];
+    for (path, keyfile) in keystore_dir.get_all_keyfiles()? {
+        let file_name = path.file_name().ok_or(KeystoreError::InvalidKeyfile)?.to_os_string();
+        keyfiles.push((file_name, keyfile.reencrypt(old_password, new_password, rng)?));
+    }
+
+    if tmp_dir.exists() {
+        fs::remove_dir_all(&tmp_dir)?;
+    }
+    fs::create_dir_all(tmp_dir.join(KEYSTORE_DIR))?;
+    write_keyfile(&tmp_dir.join(MASTER_KEYFILE), &master)?;
+    for (file_name, keyfile) in keyfiles.iter() {
+        write_keyfile(&tmp_dir.join(KEYSTORE_DIR).join(file_name), keyfile)?;
+    }
+
+    fs::create_dir_all(&backup_dir)?;
+    let mut swapped: Vec<(PathBuf, PathBuf)> = vec![];
+    for name in [KEYSTORE_DIR, MASTER_KEYFILE].iter() {
+        let current = wallet_dir.0.join(name);
+        let backup = backup_dir.join(name);
+
+        let res = fs::rename(&current, &backup)
+            .and_then(|_| fs::rename(tmp_dir.join(name), &current).or_else(|e| {
+                fs::rename(&backup, &current)?;
+                Err(e)
+            }));
+
+        if let Err(e) = res {
+            rollback_swaps(&swapped)?;
+            fs::remove_dir_all(&backup_dir)?;
+            return Err(e.into());
+        }
+        swapped.push((current, backup));
+    }
+
+    fs::remove_dir_all(&backup_dir)?;
+    fs::remove_dir_all(&tmp_dir)?;
+
+    Ok(keyfiles.len())
+}
+
+// Put the old keyfiles back in the reverse order they were swapped out.
+fn rollback_swaps(swapped: &[(PathBuf, PathBuf)]) -> Result<()> {
+    for (current, backup) in swapped.iter().rev() {
+        if current.is_dir() {
+            fs::remove_dir_all(current)?;
+        } else {
+            fs::remove_file(current)?;
+        }
+        fs::rename(backup, current)?;
+    }
+
+    Ok(())
+}
+
+fn write_keyfile(path: &Path, keyfile: &KeyFile) -> Result<()> {
+    let mut file = create_new_file(path)?;
+    serde_json::to_writer(&mut file, keyfile)?;
+
+    file.flush()?;
+    file.sync_all()?;
+
+    Ok(())
+}
+
 fn save_keyfile(filename: String, keyfile_path: &PathBuf, keyfile: &mut KeyFile) -> Result<()> {
     keyfile.file_name = Some(filename);
 
@@ -259,7 +361,7 @@ mod tests {
     use super::*;
     use std::env;
     use rand::{XorShiftRng, SeedableRng};
-    use crate::derive::{ExtendedSpendingKey, Derivation};
+    use crate::derive::{ExtendedSpendingKey, Derivation, ChildIndex};
 
     #[test]
     fn test_manage_keyfile() {
@@ -294,4 +396,90 @@ mod tests {
         dir.push(keyfile.file_name.unwrap());
         assert!(!dir.exists(), "Should be removed keyfile.")
     }
+
+    #[test]
+    fn test_update_keyfile_metadata() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let mut dir = env::temp_dir();
+        dir.push("update_keyfile_metadata");
+
+        let seed: [u8; 32] = rng.gen();
+        let xsk_master = ExtendedSpendingKey::master(&seed);
+
+        let directory = KeystoreDirectory::create(dir.clone()).unwrap();
+        let mut keyfile = KeyFile::new("Test", 1, b"abcd", 1024, &xsk_master, rng).unwrap();
+        directory.insert(&mut keyfile, rng).unwrap();
+
+        keyfile.annotate(vec![
+            ("label".to_string(), "savings".to_string()),
+            ("color".to_string(), "green".to_string()),
+        ]);
+        keyfile.remove_annotations(vec!["color"]);
+        directory.update(&keyfile).unwrap();
+
+        let file_name = keyfile.file_name.clone().unwrap();
+        let keyfile_loaded = directory.load(&file_name).unwrap();
+        assert_eq!(keyfile_loaded, keyfile);
+        assert_eq!(keyfile_loaded.metadata.get("label").map(|s| s.as_str()), Some("savings"));
+        assert!(keyfile_loaded.metadata.get("color").is_none());
+
+        directory.remove(&mut keyfile).unwrap();
+    }
+
+    #[test]
+    fn test_reencrypt_keyfiles() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let mut dir = env::temp_dir();
+        dir.push("reencrypt_keyfiles");
+        let _ = fs::remove_dir_all(&dir);
+
+        let seed: [u8; 32] = rng.gen();
+        let wallet_dir = WalletDirectory::create(&dir).unwrap();
+        let keystore_dir = KeystoreDirectory::create(wallet_dir.get_default_keystore_dir()).unwrap();
+        let mut master = KeyFile::create_master("master", 1, b"abcd", 1024, rng, &seed).unwrap();
+        wallet_dir.insert_master(&mut master).unwrap();
+        for i in 0..2 {
+            let xsk = ExtendedSpendingKey::master(&seed).derive_child(ChildIndex::from_index(i)).unwrap();
+            let mut keyfile = KeyFile::new("Test", 1, b"abcd", 1024, &xsk, rng).unwrap();
+            keystore_dir.insert(&mut keyfile, rng).unwrap();
+        }
+        let before = keystore_dir.load_all().unwrap();
+
+        // A wrong password changes nothing.
+        assert!(reencrypt_keyfiles(&wallet_dir, b"wrong", b"efgh", rng).is_err());
+        assert_eq!(keystore_dir.load_all().unwrap(), before);
+
+        assert_eq!(reencrypt_keyfiles(&wallet_dir, b"abcd", b"efgh", rng).unwrap(), 2);
+
+        let after = keystore_dir.load_all().unwrap();
+        assert_eq!(after.len(), 2);
+        for (old, new) in before.iter().zip(after.iter()) {
+            assert_eq!(old.file_name, new.file_name);
+            assert!(new.get_current_spending_key(b"abcd").is_err());
+            assert_eq!(
+                new.encrypted_key.decrypt(b"efgh").unwrap(),
+                old.encrypted_key.decrypt(b"abcd").unwrap()
+            );
+        }
+        assert!(wallet_dir.load_master().unwrap().get_child_xsk(b"efgh", ChildIndex::from_index(0)).is_ok());
+        assert!(!dir.join(REENCRYPT_TMP_DIR).exists());
+        assert!(!dir.join(REENCRYPT_BACKUP_DIR).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_keyfile_without_metadata() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let seed: [u8; 32] = rng.gen();
+        let xsk_master = ExtendedSpendingKey::master(&seed);
+        let keyfile = KeyFile::new("Test", 1, b"abcd", 1024, &xsk_master, rng).unwrap();
+
+        let mut json = serde_json::to_value(&keyfile).unwrap();
+        json.as_object_mut().unwrap().remove("metadata");
+        let keyfile_loaded: KeyFile = serde_json::from_value(json).unwrap();
+
+        assert!(keyfile_loaded.metadata.is_empty());
+        assert_eq!(keyfile_loaded, keyfile);
+    }
 }
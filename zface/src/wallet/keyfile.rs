@@ -7,10 +7,12 @@ use smallvec::SmallVec;
 use proofs::{SpendingKey, ProofGenerationKey, DecryptionKey, PARAMS};
 use pairing::bls12_381::Bls12;
 use std::convert::TryInto;
-use std::collections::HashMap;
+use std::collections::{HashMap, BTreeMap};
 use super::SerdeBytes;
 use crate::error::{KeystoreError, Result};
 use crate::derive::{ExtendedSpendingKey, Derivation, ChildIndex};
+use crate::ss58;
+use zprimitives::AddressFormat;
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -30,6 +32,11 @@ pub struct KeyFile {
 
     /// Encrypted private key
     pub encrypted_key: KeyCiphertext,
+
+    /// User defined key-value metadata such as label, color, notes and creation height.
+    /// Keyfiles written before metadata was introduced are loaded with empty metadata.
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
 }
 
 impl KeyFile {
@@ -50,6 +57,7 @@ impl KeyFile {
             ss58_address,
             version,
             encrypted_key,
+            metadata: BTreeMap::new(),
         })
     }
 
@@ -72,6 +80,36 @@ impl KeyFile {
             ss58_address: ss58_master_addr,
             version,
             encrypted_key,
+            metadata: BTreeMap::new(),
+        })
+    }
+
+    /// The address of the account encoded for the network the wallet is used on.
+    /// `ss58_address` is stored in the testnet format regardless of the network.
+    pub fn address(&self, format: AddressFormat) -> Result<String> {
+        Ok(ss58::reformat(&self.ss58_address, format)?)
+    }
+
+    /// Set metadata entries, overwriting existing values of the same keys.
+    pub fn annotate<I: IntoIterator<Item = (String, String)>>(&mut self, entries: I) {
+        self.metadata.extend(entries);
+    }
+
+    /// Remove metadata entries. Keys which are not set are ignored.
+    pub fn remove_annotations<'a, I: IntoIterator<Item = &'a str>>(&mut self, keys: I) {
+        for key in keys {
+            self.metadata.remove(key);
+        }
+    }
+
+    /// A copy of this keyfile with the key encrypted by a new password, with the same iterations.
+    pub fn reencrypt<R: Rng>(&self, old_password: &[u8], new_password: &[u8], rng: &mut R) -> Result<Self> {
+        let xsk = self.encrypted_key.decrypt(old_password)?;
+        let encrypted_key = KeyCiphertext::encrypt(&xsk, new_password, self.encrypted_key.iters, rng)?;
+
+        Ok(KeyFile {
+            encrypted_key,
+            ..self.clone()
         })
     }
 
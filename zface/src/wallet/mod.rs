@@ -6,7 +6,7 @@ mod config;
 mod keyfile;
 mod disk;
 pub use self::keyfile::KeyFile;
-pub use self::disk::{KeystoreDirectory, WalletDirectory};
+pub use self::disk::{KeystoreDirectory, WalletDirectory, create_new_file, replace_file, reencrypt_keyfiles};
 use crate::error::Result;
 
 /// Operations in a keystore directory
@@ -14,6 +14,9 @@ pub trait DirOperations {
     /// Insert a new keyfile to this keystore directory.
     fn insert<R: Rng>(&self, keyfile: &mut KeyFile, rng: &mut R) -> Result<()>;
 
+    /// Overwrite a stored keyfile with the updated one.
+    fn update(&self, keyfile: &KeyFile) -> Result<()>;
+
     /// Load a keyfile
     fn load(&self, keyfile_name: &str) -> Result<KeyFile>;
 